@@ -0,0 +1,370 @@
+// Private Contact Discovery - Computation Cost Benchmark
+//
+// Runs the circuits a session pays for against an Arcium localnet and
+// reports, per circuit and capacity, the latency from sending the queue
+// transaction to seeing its callback's event and what it cost:
+//
+//   init_session            session creation, session and handle rent included
+//   submit_contacts_alice   Alice's full list (32 contacts), and its
+//                           capacity 16 and 8 tiers
+//   submit_and_match        Bob's full list matched against Alice's (32),
+//                           and its capacity 16 and 8 tiers
+//   match_set_chunk         a committed set of 64 or 128 contacts,
+//                           matched one 32-contact chunk at a time
+//
+// One-shot sessions are measured at every capacity tier. A list holds
+// MAX_CONTACTS (32) contacts, so larger capacities are measured as
+// committed sets: the row is the sum over the set's chunks.
+// Latency includes the event poll interval (500 ms).
+//
+//     arcium localnet            # in another terminal
+//     cargo run -p pcd-bench -- --runs 5 --out bench-report
+//     cargo run -p pcd-bench -- --baseline bench-report/bench.csv --max-regression 20
+//
+// The report is written as `bench.md` and `bench.csv`. With
+// `--baseline`, a row whose median latency, payer spend, credit spend,
+// or compute units exceed the baseline's by more than
+// `--max-regression` percent fails the run.
+
+mod report;
+
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use anchor_lang::{AnchorDeserialize, Event};
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use pcd_client::{
+    accounts::{arcium_accounts, circuits},
+    crypto::{random_computation_offset, random_nonce, random_session_id},
+    hash_contact_list, hash_contact_set, instructions,
+    rpc::{self, RequeuePolicy},
+    EncryptedContacts, HashScheme, Namespace, PartyCipher, CAPACITY_TIERS, MAX_CONTACTS,
+};
+use private_contact_discovery::{
+    AliceSubmitted, MatchComplete, SessionInitialized, SessionMode, SetChunkMatched, NO_INVITE, NO_MEMO,
+};
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signature},
+    signer::Signer,
+};
+
+use report::{Report, Sample};
+
+/// Committed set sizes measured, in chunks of `MAX_CONTACTS`.
+const SET_CHUNKS: [usize; 2] = [2, 4];
+
+#[derive(Parser)]
+#[command(name = "pcd-bench", about = "Benchmark circuit latency and fees on a localnet")]
+struct Args {
+    /// RPC endpoint
+    #[arg(long, default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Payer keypair, the program's upgrade authority
+    #[arg(long, default_value = "~/.config/solana/id.json")]
+    keypair: String,
+    /// MXE cluster offset
+    #[arg(long, default_value_t = 0)]
+    cluster_offset: u32,
+    /// Sessions run per circuit and capacity
+    #[arg(long, default_value_t = 3)]
+    runs: usize,
+    /// Seconds to wait for each callback
+    #[arg(long, default_value_t = 120)]
+    timeout: u64,
+    /// Directory the report is written to
+    #[arg(long, default_value = "bench-report")]
+    out: PathBuf,
+    /// Earlier `bench.csv` to compare against
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+    /// Percent a metric may exceed its baseline before the run fails
+    #[arg(long, default_value_t = 10.0)]
+    max_regression: f64,
+}
+
+struct Bench {
+    rpc: RpcClient,
+    payer: Keypair,
+    cluster: u32,
+    mxe_key: [u8; 32],
+    app_id: [u8; 32],
+    timeout: Duration,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let keypair = shellexpand::tilde(&args.keypair).into_owned();
+    let payer = read_keypair_file(&keypair).map_err(|e| anyhow::anyhow!("reading keypair {keypair}: {e}"))?;
+    let rpc = RpcClient::new_with_commitment(args.url, CommitmentConfig::confirmed());
+    let bench = Bench {
+        mxe_key: rpc::fetch_mxe_public_key(&rpc).context("MXE key: is `arcium localnet` running?")?,
+        rpc,
+        payer,
+        cluster: args.cluster_offset,
+        app_id: random_session_id(),
+        timeout: Duration::from_secs(args.timeout),
+    };
+    bench.setup(args.runs)?;
+
+    let mut report = Report::default();
+    for run in 0..args.runs {
+        println!("run {}/{}", run + 1, args.runs);
+        for capacity in CAPACITY_TIERS {
+            bench.one_shot(capacity, &mut report)?;
+        }
+        for chunks in SET_CHUNKS {
+            bench.committed_set(chunks, &mut report)?;
+        }
+    }
+
+    std::fs::create_dir_all(&args.out)?;
+    std::fs::write(args.out.join("bench.md"), report.markdown())?;
+    std::fs::write(args.out.join("bench.csv"), report.csv())?;
+    print!("{}", report.markdown());
+
+    if let Some(path) = args.baseline {
+        let baseline = std::fs::read_to_string(&path).with_context(|| format!("reading {path:?}"))?;
+        let regressions = report.regressions(&report::parse_csv(&baseline)?, args.max_regression);
+        for regression in &regressions {
+            println!("REGRESSION {regression}");
+        }
+        if !regressions.is_empty() {
+            bail!(
+                "{} metric(s) regressed more than {}%",
+                regressions.len(),
+                args.max_regression
+            );
+        }
+    }
+    Ok(())
+}
+
+impl Bench {
+    /// Initialize what an earlier run has not (comp defs, global stats,
+    /// config), and fund a fresh app's credit for `runs` runs.
+    fn setup(&self, runs: usize) -> Result<()> {
+        let mxe = rpc::fetch_mxe(&self.rpc)?;
+        for circuit in circuits::ALL {
+            if !rpc::is_comp_def_initialized(&self.rpc, circuit)? {
+                self.send(
+                    &[],
+                    instructions::init_comp_def(self.payer.pubkey(), circuit, mxe.lut_offset_slot),
+                )?;
+            }
+        }
+        if rpc::fetch_global_stats(&self.rpc).is_err() {
+            self.send(&[], instructions::init_global_stats(self.payer.pubkey()))?;
+        }
+        if rpc::fetch_config(&self.rpc).is_err() {
+            self.send(&[], instructions::init_config(self.payer.pubkey(), 1_000))?;
+        }
+
+        let price = rpc::fetch_config(&self.rpc)?.computation_price;
+        let per_run = 3 * CAPACITY_TIERS.len() + SET_CHUNKS.iter().map(|chunks| 2 + chunks).sum::<usize>();
+        let payer = self.payer.pubkey();
+        self.send(&[], instructions::open_credit_account(payer, payer, self.app_id))?;
+        self.send(
+            &[],
+            instructions::deposit_credit(payer, &self.app_id, (runs * per_run) as u64 * price),
+        )?;
+        Ok(())
+    }
+
+    fn send(&self, signers: &[&Keypair], ix: Instruction) -> Result<Signature> {
+        Ok(rpc::send_with_requeue(
+            &self.rpc,
+            &self.payer,
+            signers,
+            &[ix],
+            RequeuePolicy::default(),
+        )?)
+    }
+
+    /// Send a queue instruction for `circuit` at `offset`, wait for its
+    /// callback's event, and measure it.
+    fn measure<E: Event + AnchorDeserialize>(
+        &self,
+        signers: &[&Keypair],
+        circuit: &str,
+        offset: u64,
+        ix: Instruction,
+    ) -> Result<Sample> {
+        let lamports = self.rpc.get_balance(&self.payer.pubkey())?;
+        let credit = rpc::fetch_credit_account(&self.rpc, &self.app_id)?.balance;
+        let start = Instant::now();
+
+        let signature = self.send(signers, ix)?;
+        let computation = arcium_accounts(self.cluster, circuit, offset).computation_account;
+        let _: E = rpc::await_event(&self.rpc, &computation, self.timeout)?;
+        let latency = start.elapsed();
+
+        let tx = self.rpc.get_transaction_with_config(
+            &signature,
+            RpcTransactionConfig {
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+                ..Default::default()
+            },
+        )?;
+        let compute_units = tx
+            .transaction
+            .meta
+            .and_then(|meta| Option::<u64>::from(meta.compute_units_consumed))
+            .unwrap_or(0);
+        Ok(Sample {
+            latency,
+            payer_lamports: lamports.saturating_sub(self.rpc.get_balance(&self.payer.pubkey())?),
+            credit_lamports: credit - rpc::fetch_credit_account(&self.rpc, &self.app_id)?.balance,
+            compute_units,
+        })
+    }
+
+    /// A session of `mode` and `capacity` with Alice's full list
+    /// submitted; returns the session id, Alice, and the init and submit
+    /// samples.
+    fn session_with_alice(&self, mode: SessionMode, capacity: u8) -> Result<([u8; 32], Keypair, Sample, Sample)> {
+        let session_id = random_session_id();
+        let alice = Keypair::new();
+        let cipher = PartyCipher::new(self.mxe_key);
+        let payer = self.payer.pubkey();
+
+        let offset = random_computation_offset();
+        let init = self.measure::<SessionInitialized>(
+            &[&alice],
+            circuits::INIT_SESSION,
+            offset,
+            instructions::create_session(
+                payer,
+                alice.pubkey(),
+                self.cluster,
+                offset,
+                session_id,
+                self.app_id,
+                cipher.public_key(),
+                u128::from_le_bytes(random_nonce()),
+                mode as u8,
+                Namespace::Phone as u8,
+                HashScheme::Sha256 as u8,
+                false,
+                false,
+                0,
+                0,
+                capacity,
+                NO_MEMO,
+                Pubkey::default(),
+                NO_INVITE,
+            ),
+        )?;
+
+        let contacts = cipher.encrypt_contacts(&hash_contact_list(
+            HashScheme::Sha256,
+            Namespace::Phone,
+            &phone_numbers(0, capacity as usize),
+        )?);
+        let offset = random_computation_offset();
+        let submit = self.measure::<AliceSubmitted>(
+            &[&alice],
+            instructions::alice_circuit(capacity),
+            offset,
+            instructions::submit_contacts_alice(
+                payer,
+                alice.pubkey(),
+                self.cluster,
+                offset,
+                &session_id,
+                &self.app_id,
+                &contacts,
+                capacity,
+            ),
+        )?;
+        Ok((session_id, alice, init, submit))
+    }
+
+    /// One one-shot session of `capacity`: init, submit, and match.
+    fn one_shot(&self, capacity: u8, report: &mut Report) -> Result<()> {
+        let (session_id, alice, init, submit) = self.session_with_alice(SessionMode::OneShot, capacity)?;
+        let size = capacity as usize;
+        report.record(circuits::INIT_SESSION, size, init);
+        report.record(instructions::alice_circuit(capacity), size, submit);
+
+        // Half of Bob's list overlaps Alice's
+        let bob = Keypair::new();
+        let contacts = PartyCipher::new(self.mxe_key).encrypt_contacts(&hash_contact_list(
+            HashScheme::Sha256,
+            Namespace::Phone,
+            &phone_numbers(size / 2, size),
+        )?);
+        let circuit = instructions::match_circuit(capacity);
+        let offset = random_computation_offset();
+        let matched = self.measure::<MatchComplete>(
+            &[&bob],
+            circuit,
+            offset,
+            instructions::submit_and_match(
+                self.payer.pubkey(),
+                bob.pubkey(),
+                alice.pubkey(),
+                self.cluster,
+                offset,
+                &session_id,
+                &self.app_id,
+                &contacts,
+                capacity,
+                false,
+            ),
+        )?;
+        report.record(circuit, size, matched);
+        Ok(())
+    }
+
+    /// One committed-set session whose set fills `chunks` chunks, matched
+    /// chunk by chunk.
+    fn committed_set(&self, chunks: usize, report: &mut Report) -> Result<()> {
+        let (session_id, alice, _, _) = self.session_with_alice(SessionMode::CommittedSet, MAX_CONTACTS as u8)?;
+        let bob = Keypair::new();
+        let cipher = PartyCipher::new(self.mxe_key);
+        let set: Vec<EncryptedContacts> = hash_contact_set(
+            HashScheme::Sha256,
+            Namespace::Phone,
+            &phone_numbers(MAX_CONTACTS / 2, chunks * MAX_CONTACTS),
+        )?
+        .iter()
+        .map(|chunk| cipher.encrypt_contacts(chunk))
+        .collect();
+        self.send(&[&bob], instructions::commit_set(bob.pubkey(), alice.pubkey(), &session_id, &set))?;
+
+        let mut total = Sample::default();
+        for index in 0..set.len() {
+            let offset = random_computation_offset();
+            total += self.measure::<SetChunkMatched>(
+                &[&bob],
+                circuits::MATCH_SET_CHUNK,
+                offset,
+                instructions::submit_set_chunk(
+                    self.payer.pubkey(),
+                    bob.pubkey(),
+                    self.cluster,
+                    offset,
+                    &session_id,
+                    &self.app_id,
+                    &set,
+                    index,
+                ),
+            )?;
+        }
+        report.record(circuits::MATCH_SET_CHUNK, chunks * MAX_CONTACTS, total);
+        Ok(())
+    }
+}
+
+/// `count` distinct phone numbers, starting at the `first`-th.
+fn phone_numbers(first: usize, count: usize) -> Vec<String> {
+    (first..first + count).map(|i| format!("+1 555 {i:04}")).collect()
+}
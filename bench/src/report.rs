@@ -0,0 +1,180 @@
+// Benchmark samples, their per-row summary, and the markdown and CSV
+// renderings of it. A row is one circuit at one capacity; the CSV is
+// also the baseline format a later run is compared against.
+
+use std::{collections::BTreeMap, fmt, ops::AddAssign, time::Duration};
+
+use anyhow::{anyhow, Result};
+
+/// What one measured computation (or the chunks of one set) took.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sample {
+    /// From sending the queue transaction to seeing the callback's event
+    pub latency: Duration,
+    /// Lamports the payer spent: transaction fees, rent, Arcium fees
+    pub payer_lamports: u64,
+    /// Lamports debited from the app's credit
+    pub credit_lamports: u64,
+    /// Compute units of the queue transaction
+    pub compute_units: u64,
+}
+
+impl AddAssign for Sample {
+    fn add_assign(&mut self, other: Sample) {
+        self.latency += other.latency;
+        self.payer_lamports += other.payer_lamports;
+        self.credit_lamports += other.credit_lamports;
+        self.compute_units += other.compute_units;
+    }
+}
+
+/// A row's samples summarized: median and worst latency, mean spend.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Summary {
+    pub runs: usize,
+    pub latency_p50_ms: u64,
+    pub latency_max_ms: u64,
+    pub payer_lamports: u64,
+    pub credit_lamports: u64,
+    pub compute_units: u64,
+}
+
+impl Summary {
+    fn of(samples: &[Sample]) -> Self {
+        let mut latencies: Vec<u64> = samples.iter().map(|s| s.latency.as_millis() as u64).collect();
+        latencies.sort_unstable();
+        let mean = |metric: fn(&Sample) -> u64| samples.iter().map(metric).sum::<u64>() / samples.len() as u64;
+        Summary {
+            runs: samples.len(),
+            latency_p50_ms: latencies[latencies.len() / 2],
+            latency_max_ms: latencies[latencies.len() - 1],
+            payer_lamports: mean(|s| s.payer_lamports),
+            credit_lamports: mean(|s| s.credit_lamports),
+            compute_units: mean(|s| s.compute_units),
+        }
+    }
+
+    /// The compared metrics, by CSV column name.
+    fn metrics(&self) -> [(&'static str, u64); 4] {
+        [
+            ("latency_p50_ms", self.latency_p50_ms),
+            ("payer_lamports", self.payer_lamports),
+            ("credit_lamports", self.credit_lamports),
+            ("compute_units", self.compute_units),
+        ]
+    }
+}
+
+/// Row key: circuit and capacity in contacts.
+type Row = (String, usize);
+
+/// Samples by row, in circuit then capacity order.
+#[derive(Default)]
+pub struct Report {
+    rows: BTreeMap<Row, Vec<Sample>>,
+}
+
+const CSV_HEADER: &str =
+    "circuit,capacity,runs,latency_p50_ms,latency_max_ms,payer_lamports,credit_lamports,compute_units";
+
+impl Report {
+    pub fn record(&mut self, circuit: &str, capacity: usize, sample: Sample) {
+        self.rows
+            .entry((circuit.to_string(), capacity))
+            .or_default()
+            .push(sample);
+    }
+
+    fn summaries(&self) -> impl Iterator<Item = (&Row, Summary)> {
+        self.rows.iter().map(|(row, samples)| (row, Summary::of(samples)))
+    }
+
+    pub fn markdown(&self) -> String {
+        let mut out = String::from(
+            "| circuit | capacity | runs | p50 latency (ms) | max latency (ms) | payer (lamports) | credit (lamports) | CU |\n\
+             |---|---:|---:|---:|---:|---:|---:|---:|\n",
+        );
+        for ((circuit, capacity), s) in self.summaries() {
+            out += &format!(
+                "| {circuit} | {capacity} | {} | {} | {} | {} | {} | {} |\n",
+                s.runs, s.latency_p50_ms, s.latency_max_ms, s.payer_lamports, s.credit_lamports, s.compute_units
+            );
+        }
+        out
+    }
+
+    pub fn csv(&self) -> String {
+        let mut out = format!("{CSV_HEADER}\n");
+        for ((circuit, capacity), s) in self.summaries() {
+            out += &format!(
+                "{circuit},{capacity},{},{},{},{},{},{}\n",
+                s.runs, s.latency_p50_ms, s.latency_max_ms, s.payer_lamports, s.credit_lamports, s.compute_units
+            );
+        }
+        out
+    }
+
+    /// Metrics that exceed `baseline`'s for the same row by more than
+    /// `max_percent`. Rows missing from either side are not compared.
+    pub fn regressions(&self, baseline: &BTreeMap<Row, Summary>, max_percent: f64) -> Vec<Regression> {
+        let mut regressions = Vec::new();
+        for (row, summary) in self.summaries() {
+            let Some(old) = baseline.get(row) else {
+                continue;
+            };
+            for ((metric, new), (_, old)) in summary.metrics().into_iter().zip(old.metrics()) {
+                if new as f64 > old as f64 * (1.0 + max_percent / 100.0) {
+                    regressions.push(Regression {
+                        row: row.clone(),
+                        metric,
+                        old,
+                        new,
+                    });
+                }
+            }
+        }
+        regressions
+    }
+}
+
+/// Parse a `Report::csv` back into summaries by row.
+pub fn parse_csv(csv: &str) -> Result<BTreeMap<Row, Summary>> {
+    let mut lines = csv.lines();
+    if lines.next() != Some(CSV_HEADER) {
+        return Err(anyhow!("not a pcd-bench CSV report"));
+    }
+    let mut rows = BTreeMap::new();
+    for line in lines.filter(|line| !line.is_empty()) {
+        let cells: Vec<&str> = line.split(',').collect();
+        let &[circuit, capacity, runs, p50, max, payer, credit, cu] = cells.as_slice() else {
+            return Err(anyhow!("malformed report line {line:?}"));
+        };
+        rows.insert(
+            (circuit.to_string(), capacity.parse()?),
+            Summary {
+                runs: runs.parse()?,
+                latency_p50_ms: p50.parse()?,
+                latency_max_ms: max.parse()?,
+                payer_lamports: payer.parse()?,
+                credit_lamports: credit.parse()?,
+                compute_units: cu.parse()?,
+            },
+        );
+    }
+    Ok(rows)
+}
+
+/// One metric of one row above its baseline.
+pub struct Regression {
+    row: Row,
+    metric: &'static str,
+    old: u64,
+    new: u64,
+}
+
+impl fmt::Display for Regression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (circuit, capacity) = &self.row;
+        write!(f, "{circuit}@{capacity} {}: {} -> {}", self.metric, self.old, self.new)
+    }
+}
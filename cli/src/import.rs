@@ -0,0 +1,227 @@
+// Contact import from address-book exports.
+//
+// Supported formats, picked by file extension:
+//   .vcf / .vcard  vCard 2.1/3.0/4.0 (TEL and EMAIL properties)
+//   .csv           CSV with phone/email columns (Google, Outlook, iCloud exports)
+//   anything else  one identifier per line
+//
+// Output is normalized (same rules as the hashing step) and deduplicated,
+// preserving first-seen order.
+
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use pcd_client::normalize_contact;
+
+/// Read, parse, normalize, and deduplicate a contact file.
+pub fn read_contacts(path: &Path) -> Result<Vec<String>> {
+    let text = fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+
+    let raw = match extension.as_str() {
+        "vcf" | "vcard" => parse_vcard(&text),
+        "csv" => parse_csv(&text),
+        _ => text.lines().map(str::to_string).collect(),
+    };
+
+    Ok(dedupe(raw))
+}
+
+/// Normalize and drop duplicates and empties, keeping first-seen order.
+pub fn dedupe(raw: Vec<String>) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::with_capacity(raw.len());
+    for entry in raw {
+        let normalized = normalize_contact(strip_uri_scheme(&entry));
+        if !normalized.is_empty() && !unique.contains(&normalized) {
+            unique.push(normalized);
+        }
+    }
+    unique
+}
+
+/// Drop `tel:` / `mailto:` prefixes used by vCard 4.0 URIs.
+fn strip_uri_scheme(value: &str) -> &str {
+    let trimmed = value.trim();
+    for scheme in ["tel:", "mailto:"] {
+        if trimmed.len() >= scheme.len() && trimmed[..scheme.len()].eq_ignore_ascii_case(scheme) {
+            return &trimmed[scheme.len()..];
+        }
+    }
+    trimmed
+}
+
+/// Extract TEL and EMAIL values from vCard text.
+fn parse_vcard(text: &str) -> Vec<String> {
+    // Unfold continuation lines (RFC 6350 §3.2)
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.starts_with(' ') || line.starts_with('\t'), lines.last_mut()) {
+            (true, Some(last)) => last.push_str(&line[1..]),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    lines
+        .iter()
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            // Property name is before any ';' parameters, possibly group-prefixed ("item1.TEL")
+            let property = name.split(';').next()?.rsplit('.').next()?;
+            if property.eq_ignore_ascii_case("TEL") || property.eq_ignore_ascii_case("EMAIL") {
+                Some(value.to_string())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Extract phone and email cells from a CSV export.
+/// Value columns are chosen by header name ("E-mail 1 - Value",
+/// "E-mail Address", "Mobile Phone", ...), skipping the Type, Label and
+/// Display Name columns beside them; without a recognizable header,
+/// every cell that looks like a phone number or email is taken.
+fn parse_csv(text: &str) -> Vec<String> {
+    let mut rows = csv_records(text).into_iter();
+    let Some(header) = rows.next() else {
+        return Vec::new();
+    };
+
+    let columns: Vec<usize> = header
+        .iter()
+        .enumerate()
+        .filter(|(_, h)| is_value_column(h))
+        .map(|(i, _)| i)
+        .collect();
+
+    if columns.is_empty() {
+        return std::iter::once(header)
+            .chain(rows)
+            .flatten()
+            .filter(|cell| looks_like_contact(cell))
+            .collect();
+    }
+
+    rows.flat_map(|row| {
+        columns
+            .iter()
+            .filter_map(|&i| row.get(i).cloned())
+            // Google exports pack several values into one cell: "a ::: b"
+            .flat_map(|cell| cell.split(":::").map(|value| value.trim().to_string()).collect::<Vec<_>>())
+            .filter(|value| !value.is_empty())
+            .collect::<Vec<_>>()
+    })
+    .collect()
+}
+
+/// Whether a CSV header names a column of phone numbers or emails, as
+/// opposed to the type, label or display name describing one.
+fn is_value_column(header: &str) -> bool {
+    const METADATA: [&str; 3] = ["type", "label", "display"];
+    let header = header.trim().to_ascii_lowercase();
+    (header.contains("phone") || header.contains("mobile") || header.contains("mail"))
+        && !header
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|word| METADATA.contains(&word))
+}
+
+fn looks_like_contact(cell: &str) -> bool {
+    let cell = cell.trim();
+    let digits = cell.chars().filter(char::is_ascii_digit).count();
+    cell.contains('@')
+        || (digits >= 7 && cell.chars().all(|c| c.is_ascii_digit() || " -+().".contains(c)))
+}
+
+/// Split CSV text into records (RFC 4180): fields are separated by
+/// commas and records by line breaks, except inside double quotes, so
+/// a quoted field may hold commas, `""` escapes and line breaks (Google
+/// and Outlook export multi-line notes and addresses that way). Blank
+/// lines and a leading byte order mark are skipped.
+fn csv_records(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut cell = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => record.push(std::mem::take(&mut cell)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                record.push(std::mem::take(&mut cell));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => cell.push(c),
+        }
+    }
+    if !cell.is_empty() || !record.is_empty() {
+        record.push(cell);
+        records.push(record);
+    }
+    records.retain(|record| record.iter().any(|cell| !cell.trim().is_empty()));
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A Google Contacts export: every email and phone comes as a
+    /// Label column and a Value column.
+    const GOOGLE: &str = "\
+First Name,Middle Name,Last Name,Nickname,Notes,Labels,E-mail 1 - Label,E-mail 1 - Value,E-mail 2 - Label,E-mail 2 - Value,Phone 1 - Label,Phone 1 - Value,Address 1 - Label,Address 1 - Formatted
+Ada,,Lovelace,,\"Met at the conference,
+call after 5\",* myContacts,* Home,ada@example.com,Work,ada@work.example,Mobile,+1 415 555 0100 ::: +1 415 555 0101,Home,\"12 Main St
+Springfield\"
+Grace,,Hopper,,,* myContacts,* Work,grace@example.com,,,Work,+1 415 555 0102,,
+";
+
+    /// An Outlook export: values under "E-mail Address" and the phone
+    /// columns, next to "E-mail Type" and "E-mail Display Name".
+    const OUTLOOK: &str = "\u{feff}\
+First Name,Middle Name,Last Name,E-mail Address,E-mail Type,E-mail Display Name,E-mail 2 Address,E-mail 2 Type,E-mail 2 Display Name,Home Phone,Business Phone,Mobile Phone,Business Fax,Assistant's Phone,Notes\r
+Ada,,Lovelace,ada@example.com,SMTP,Ada Lovelace (ada@example.com),,,,,+1 415 555 0103,+1 415 555 0100,+1 415 555 0199,,\"Prefers email\r
+Not on weekends\"\r
+";
+
+    #[test]
+    fn google_export_takes_value_columns_only() {
+        assert_eq!(
+            parse_csv(GOOGLE),
+            [
+                "ada@example.com",
+                "ada@work.example",
+                "+1 415 555 0100",
+                "+1 415 555 0101",
+                "grace@example.com",
+                "+1 415 555 0102",
+            ]
+        );
+    }
+
+    #[test]
+    fn outlook_export_skips_type_and_display_name_columns() {
+        assert_eq!(parse_csv(OUTLOOK), ["ada@example.com", "+1 415 555 0103", "+1 415 555 0100"]);
+    }
+
+    #[test]
+    fn quoted_fields_may_span_lines() {
+        let records = csv_records("a,b\n\"one\ntwo\",\"say \"\"hi\"\"\"\n\n3,4");
+        assert_eq!(records, [vec!["a", "b"], vec!["one\ntwo", "say \"hi\""], vec!["3", "4"]]);
+    }
+
+    #[test]
+    fn headerless_csv_takes_cells_that_look_like_contacts() {
+        assert_eq!(parse_csv("Ada,ada@example.com,+1 415 555 0100,n/a\n"), ["ada@example.com", "+1 415 555 0100"]);
+    }
+}
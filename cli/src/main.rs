@@ -0,0 +1,2673 @@
+// Private Contact Discovery - Command-line client
+//
+// Drives the full PSI protocol against a deployed program:
+//
+//     pcd-cli init-comp-defs
+//     pcd-cli init-config --price <lamports>            (upgrade authority)
+//     pcd-cli set-price <lamports>
+//     pcd-cli set-fee-share <bps>                       (share topping up the Arcium fee pool)
+//     pcd-cli set-refund-share <bps>                    (share of a failed computation refunded)
+//     pcd-cli --app <name> open-credit
+//     pcd-cli --app <name> deposit  <lamports>
+//     pcd-cli --app <name> withdraw <lamports>
+//     pcd-cli --app <name> create-lut                   (lookup table for v0 transactions)
+//     pcd-cli create-session [--namespace email] [--hash-scheme blake3] [--public-count]
+//     pcd-cli create-session --stateless               (state built by Alice's first submit)
+//     pcd-cli commit  <session_id> --from alice.vcf --save alice.commit
+//     pcd-cli submit  <session_id> --from alice.vcf   (or --committed alice.commit)
+//     pcd-cli permit  <session_id> --from alice.vcf --save alice.permit
+//     pcd-cli relay   <session_id> --permit alice.permit (as the relayer)
+//     pcd-cli match   <session_id> --from bob.csv [--public-count]
+//     pcd-cli --invite <code> match <session_id> --from bob.csv  (claims, then redeems with the join)
+//     pcd-cli reveal  <session_id> --from alice.vcf [--page N]   (every page unless --page)
+//     pcd-cli delta   <session_id> --add new.vcf --remove gone.txt
+//     pcd-cli score   <session_id> --from bob.csv      (score sessions)
+//     pcd-cli reveal-score <session_id>
+//     pcd-cli fof-join    <session_id> --from bob.csv  (friends-of-friends)
+//     pcd-cli fof-connect <session_id> --from carol.vcf
+//     pcd-cli fof-reveal  <session_id>
+//     pcd-cli create-session --follow                  (mutual-follow)
+//     pcd-cli follow-submit <session_id> --from alice-follows.txt
+//     pcd-cli follow-check  <session_id> --from bob-follows.txt
+//     pcd-cli follow-reveal <session_id>
+//     pcd-cli create-session --committed-set           (committed-set)
+//     pcd-cli set-match <session_id> --from big.csv    (up to 2048 contacts, 32 per chunk)
+//     pcd-cli status  <session_id>
+//     pcd-cli view    <session_id>                     (simulated view_session report)
+//     pcd-cli stats
+//     pcd-cli realloc <session_id>                     (older session layouts)
+//     pcd-cli purge   <session_id>                     (matched, or after --join-within expires)
+//     pcd-cli close   <session_id>                     (once purged, or if it never stored state)
+//     pcd-cli notify  <session_id> --target <url-or-app-id>
+//     pcd-cli hook    <session_id> --program <id> [--account <key>]... [--writable <key>]...
+//     pcd-cli connect <session_id>                     (consent; attests once both parties have)
+//     pcd-cli activity [--open | --close]              (the signer's recent session activity)
+//     pcd-cli refund  <session_id>                     (failed sessions)
+//     pcd-cli create-pool --from attendees.csv
+//     pcd-cli match-pool  <pool_id> --from bob.csv
+//     pcd-cli create-database --from users.csv         (up to 256 contacts, 32 per chunk)
+//     pcd-cli refresh-db <database_id> --from users.csv  (next epoch, swapped in when loaded)
+//     pcd-cli query-db <database_id> --from bob.csv
+//     pcd-cli check-db <database_id> --identifier +15550142  (is it registered? yes or no)
+//     pcd-cli check-db <database_id> --from invites.csv      (16 per computation, billed per identifier)
+//     pcd-cli create-blocklist --from spam.txt
+//     pcd-cli check-blocklist  <blocklist_id> --from bob.csv
+//     pcd-cli create-card-registry [--namespace email]
+//     pcd-cli publish-card <registry_id> --identifier +15550142 --key <hex x25519 key>
+//     pcd-cli match-cards  <registry_id> --from bob.csv
+//
+// Contact files may be vCard, CSV, or one identifier per line
+// (see `import`); follow files hold one base58 pubkey per line.
+// `--app <name>` meters new sessions against that app's on-chain usage
+// counters. `--fee-payer sponsor.json` on any command has a sponsor fund rent and
+// MPC fees while `--keypair` only signs as the party. A queue refused
+// because the Arcium mempool is full is resent unchanged up to
+// `--requeues` times at an escalating priority fee.
+// Hashing and encryption happen locally; only ciphertexts are sent.
+
+mod import;
+
+use std::{
+    path::Path,
+    path::PathBuf,
+    str::FromStr,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Result};
+use clap::{Parser, Subcommand};
+use pcd_client::{
+    accounts::{
+        activity_log_pda, app_lookup_table, app_stats_pda, arcium_accounts, circuits, comp_def_account, config_pda,
+        connection_pda, credit_pda, global_stats_pda, sign_pda, treasury_pda,
+    },
+    crypto::{random_computation_offset, random_invite_code, random_nonce, random_session_id},
+    hash::resolve_matches,
+    fold_follow_list, hash_contact, hash_contact_list, hash_contact_set, hash_delta, instructions, pad_with_decoys,
+    parse_session_id, preflight,
+    rpc::{self, RequeuePolicy},
+    DecryptedMatches, DecryptedScore, EncryptedContacts, HashScheme, HashedContacts, Namespace, PartyCipher,
+    CAPACITY_TIERS, DATABASE_CHUNKS, MAX_CONTACTS, MAX_DELTA, MEMBERSHIP_BATCH_SLOTS,
+};
+use private_contact_discovery::{
+    ActivityKind, AliceRevealed, AliceScoreRevealed, AliceSubmitted, BobRevealed, BobSubmitted, DeltaMatched,
+    MatchComplete,
+    BlocklistChecked, BlocklistOpened, CardRegistryOpened, CardsMatched, ContactCardPublished, DatabaseChunkLoaded,
+    DatabaseQueried, FollowChecked, HookAccount, MembershipBatchChecked, MembershipChecked, PoolMatched, PoolOpened, ScoreComplete, SecondDegreeComputed, SetChunkMatched,
+    PublicCount, SecondDegreeRevealed, SessionInitialized, SessionMode, StatePurged, UsageCounters, NO_APP_ID,
+    StateMigrated, CONSENT_ALICE, CONSENT_BOB, MAX_HOOK_ACCOUNTS, MAX_SWEEP, NO_MEMO, RESULT_PAGES, SESSION_VERSION,
+};
+use private_contact_discovery::{gatekeeper::Gatekeeper, invite_hash, NO_INVITE};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    hash::hash,
+    signature::{read_keypair_file, Keypair, Signature},
+    signer::Signer,
+};
+
+#[derive(Parser)]
+#[command(name = "pcd-cli", about = "Private Contact Discovery on Arcium")]
+struct Cli {
+    /// RPC endpoint
+    #[arg(long, global = true, default_value = "https://api.devnet.solana.com")]
+    url: String,
+    /// Signer keypair
+    #[arg(long, global = true, default_value = "~/.config/solana/id.json")]
+    keypair: String,
+    /// Keypair that funds rent and MPC fees instead of the signer
+    #[arg(long, global = true)]
+    fee_payer: Option<String>,
+    /// MXE cluster offset
+    #[arg(long, global = true, default_value_t = 456)]
+    cluster_offset: u32,
+    /// Seconds to wait for each MPC callback
+    #[arg(long, global = true, default_value_t = 120)]
+    timeout: u64,
+    /// Integrating application to meter new sessions against (hashed
+    /// into the on-chain app id); `stats` also prints its usage
+    #[arg(long, global = true)]
+    app: Option<String>,
+    /// Business reference (ticket, case id) to bind new sessions to;
+    /// only its hash goes on-chain, and every session event carries it
+    #[arg(long, global = true)]
+    memo: Option<String>,
+    /// Times to resend a queue the Arcium mempool refused as full,
+    /// at an escalating priority fee
+    #[arg(long, global = true, default_value_t = 4)]
+    requeues: u32,
+    /// Invite code of the session to join as Bob: claimed first, then
+    /// redeemed in the same transaction as the join
+    #[arg(long, global = true)]
+    invite: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Initialize all computation definitions (once per deployment) and
+    /// sync the deployment registry
+    InitCompDefs,
+    /// Create a new session as Alice
+    CreateSession {
+        /// Take every option below from this `--app` template instead
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "subscription", "score", "fof", "follow", "committed_set", "namespace", "hash_scheme",
+                "public_count", "hide_counts", "join_within", "reveal_after_slot", "capacity", "stateless", "bob",
+                "invite",
+            ]
+        )]
+        template: Option<String>,
+        /// Skip `init_session`: Alice's first submission creates the
+        /// encrypted state, one computation and fee fewer
+        #[arg(long)]
+        stateless: bool,
+        /// Allow delta rounds after the initial match
+        #[arg(long, conflicts_with_all = ["score", "fof", "follow", "committed_set"])]
+        subscription: bool,
+        /// Reveal only overlap ratios, never matched identities
+        #[arg(long, conflicts_with_all = ["fof", "follow", "committed_set"])]
+        score: bool,
+        /// Count connections shared through a third party (the connector)
+        #[arg(long, conflicts_with_all = ["follow", "committed_set"])]
+        fof: bool,
+        /// Check only whether both parties follow each other (wallet namespace)
+        #[arg(long, conflicts_with_all = ["namespace", "committed_set"])]
+        follow: bool,
+        /// Let Bob match a large set, committed by Merkle root, in chunks
+        #[arg(long)]
+        committed_set: bool,
+        /// Identifier type: phone, email, username, wallet, or solname
+        #[arg(long, default_value = "phone", value_parser = parse_namespace)]
+        namespace: Namespace,
+        /// Identifier hash: sha256 or blake3
+        #[arg(long, default_value = "sha256", value_parser = parse_hash_scheme)]
+        hash_scheme: HashScheme,
+        /// Opt in to publishing the match count (Bob must opt in too)
+        #[arg(long, conflicts_with_all = ["score", "fof", "follow", "committed_set"])]
+        public_count: bool,
+        /// Keep each party's list size from the other (scores use full lists)
+        #[arg(long, conflicts_with = "committed_set")]
+        hide_counts: bool,
+        /// Seconds Bob has to join; afterwards the session can be purged and closed
+        #[arg(long)]
+        join_within: Option<u64>,
+        /// Slot before which neither party can reveal the result
+        #[arg(long, default_value_t = 0, conflicts_with_all = ["score", "fof", "follow", "committed_set", "public_count"])]
+        reveal_after_slot: u64,
+        /// Most contacts either list may hold: 8, 16, or 32 (one-shot
+        /// sessions only); smaller lists match with fewer comparisons
+        #[arg(
+            long,
+            default_value_t = MAX_CONTACTS as u8,
+            value_parser = parse_capacity,
+            conflicts_with_all = ["subscription", "score", "fof", "follow", "committed_set"],
+        )]
+        capacity: u8,
+        /// Only this wallet may join, and only if its consent record
+        /// allows the signer and `--app`
+        #[arg(long, conflicts_with = "stateless")]
+        bob: Option<Pubkey>,
+        /// Leave the session to whoever redeems a printed invite code
+        #[arg(long, conflicts_with_all = ["stateless", "bob"])]
+        invite: bool,
+    },
+    /// Submit Alice's contacts to a session
+    Submit {
+        session_id: String,
+        /// Contact file (.vcf, .csv, or one per line)
+        #[arg(long = "from", alias = "contacts", required_unless_present = "committed")]
+        from: Option<PathBuf>,
+        /// Spread contacts beyond session capacity over new sessions
+        #[arg(long, conflicts_with = "committed")]
+        split: bool,
+        /// Submission saved by `commit`
+        #[arg(long, conflicts_with = "from")]
+        committed: Option<PathBuf>,
+    },
+    /// Commit to Alice's contacts before sharing the session id
+    Commit {
+        session_id: String,
+        /// Contact file (.vcf, .csv, or one per line)
+        #[arg(long = "from", alias = "contacts")]
+        from: PathBuf,
+        /// Where to keep the encrypted submission until `submit --committed`
+        #[arg(long)]
+        save: PathBuf,
+    },
+    /// Sign, as Alice, a permit letting a relayer submit her contacts later
+    Permit {
+        session_id: String,
+        /// Contact file (.vcf, .csv, or one per line)
+        #[arg(long = "from", alias = "contacts")]
+        from: PathBuf,
+        /// Seconds the permit stays valid
+        #[arg(long, default_value_t = 3600)]
+        expires_in: u64,
+        /// Where to write the signed permit and ciphertexts
+        #[arg(long)]
+        save: PathBuf,
+    },
+    /// Submit Alice's contacts from a permit she signed, as the relayer
+    Relay {
+        session_id: String,
+        /// File written by `permit`
+        #[arg(long)]
+        permit: PathBuf,
+    },
+    /// Submit Bob's contacts and run the intersection
+    Match {
+        session_id: String,
+        /// Contact file (.vcf, .csv, or one per line)
+        #[arg(long = "from", alias = "contacts")]
+        from: PathBuf,
+        /// Opt in to publishing the match count (if Alice did too)
+        #[arg(long)]
+        public_count: bool,
+    },
+    /// Commit Bob's large contact set to a committed-set session and match it chunk by chunk
+    SetMatch {
+        session_id: String,
+        /// Contact file (.vcf, .csv, or one per line)
+        #[arg(long = "from", alias = "contacts")]
+        from: PathBuf,
+    },
+    /// Reveal your side of the intersection (Alice's, or Bob's when run by Bob)
+    Reveal {
+        session_id: String,
+        /// Your contact file, to print matched contacts by name
+        #[arg(long = "from", alias = "contacts")]
+        from: Option<PathBuf>,
+        /// Result page (32 matches per page); every page in turn if omitted
+        #[arg(long)]
+        page: Option<u8>,
+    },
+    /// Reveal both sides in one computation, signed by Alice (`--keypair`)
+    /// and Bob together
+    RevealBoth {
+        session_id: String,
+        /// Bob's keypair, co-signing the reveal
+        #[arg(long)]
+        bob_keypair: String,
+        /// Result page (32 matches per page)
+        #[arg(long, default_value_t = 0)]
+        page: u8,
+    },
+    /// Submit Bob's contacts to a score session and print the overlap
+    Score {
+        session_id: String,
+        /// Contact file (.vcf, .csv, or one per line)
+        #[arg(long = "from", alias = "contacts")]
+        from: PathBuf,
+    },
+    /// Reveal the overlap of a score session to Alice
+    RevealScore { session_id: String },
+    /// Submit Bob's contacts to a friends-of-friends session
+    FofJoin {
+        session_id: String,
+        /// Contact file (.vcf, .csv, or one per line)
+        #[arg(long = "from", alias = "contacts")]
+        from: PathBuf,
+    },
+    /// Submit the connector's contacts and count shared connections
+    FofConnect {
+        session_id: String,
+        /// Contact file (.vcf, .csv, or one per line)
+        #[arg(long = "from", alias = "contacts")]
+        from: PathBuf,
+    },
+    /// Reveal the friends-of-friends count to Alice or Bob
+    FofReveal { session_id: String },
+    /// Submit Alice's follow list to a mutual-follow session
+    FollowSubmit {
+        session_id: String,
+        /// Follow targets, one base58 pubkey per line
+        #[arg(long = "from", alias = "follows")]
+        from: PathBuf,
+    },
+    /// Submit Bob's follow list and check for a mutual follow
+    FollowCheck {
+        session_id: String,
+        /// Follow targets, one base58 pubkey per line
+        #[arg(long = "from", alias = "follows")]
+        from: PathBuf,
+    },
+    /// Reveal the mutual-follow result to Alice or Bob
+    FollowReveal { session_id: String },
+    /// Push added/removed contacts to a subscription session
+    Delta {
+        session_id: String,
+        /// Contacts to add
+        #[arg(long)]
+        add: Option<PathBuf>,
+        /// Contacts to remove
+        #[arg(long)]
+        remove: Option<PathBuf>,
+    },
+    /// Show a session's on-chain state
+    Status { session_id: String },
+    /// Print the program's own report on a session, by simulating `view_session`
+    View { session_id: String },
+    /// Re-emit a session's status for indexers (at most once a minute per session)
+    Ping { session_id: String },
+    /// Print the program-wide session and computation counters
+    Stats,
+    /// Create the deployment config and treasury (as the upgrade authority)
+    InitConfig {
+        /// Lamports each queued session computation debits from app credit
+        #[arg(long, default_value_t = 0)]
+        price: u64,
+    },
+    /// Change the price of a queued computation (as the config admin)
+    SetPrice { lamports: u64 },
+    /// Set the basis points of each fee forwarded to the Arcium fee pool (as the config admin)
+    SetFeeShare { bps: u16 },
+    /// Set the basis points of a failed computation's fee that are refunded (as the config admin)
+    SetRefundShare { bps: u16 },
+    /// Refuse sessions from clients built for an older program version (as the config admin)
+    SetMinClientVersion { version: u16 },
+    /// Cap the sessions the same two wallets may join per day, 0 for no cap (as the config admin)
+    SetMaxPairSessions { sessions: u16 },
+    /// Cap the contact slots a wallet may submit against one other until it renews, 0 for no cap (as the config admin)
+    SetQueryBudget { slots: u32 },
+    /// Require an attestation from both parties: none, or civic with
+    /// --network (as the config admin)
+    SetGatekeeper {
+        #[arg(value_parser = parse_gatekeeper)]
+        gatekeeper: Gatekeeper,
+        /// Gatekeeper network whose passes are accepted
+        #[arg(long, required_if_eq("gatekeeper", "civic"))]
+        network: Option<Pubkey>,
+    },
+    /// Stop a wallet from creating or joining sessions (as the config admin)
+    BlockWallet { wallet: Pubkey },
+    /// Lift a wallet's block (as the config admin)
+    UnblockWallet { wallet: Pubkey },
+    /// Let a wallet that used up its query budget against the signer query it again
+    RenewBudget { querier: Pubkey },
+    /// Name the wallets, and apps, whose sessions may be addressed to the signer
+    Consent {
+        /// Wallet allowed to address sessions to the signer (repeatable)
+        #[arg(long = "wallet")]
+        wallets: Vec<Pubkey>,
+        /// App whose sessions may be addressed to the signer by any wallet (repeatable)
+        #[arg(long = "allow-app")]
+        apps: Vec<String>,
+    },
+    /// Close the signer's consent record and reclaim its rent
+    CloseConsent,
+    /// Open the `--app` credit account, controlled by the signer
+    OpenCredit,
+    /// Store a session template for `--app` (as its credit authority)
+    CreateTemplate {
+        /// Template name; only its hash goes on-chain
+        name: String,
+        /// one_shot, subscription, score, friends_of_friends, mutual_follow, or committed_set
+        #[arg(long, default_value = "one_shot", value_parser = parse_session_mode)]
+        mode: u8,
+        /// Identifier type: phone, email, username, wallet, or solname
+        #[arg(long, default_value = "phone", value_parser = parse_namespace)]
+        namespace: Namespace,
+        /// Identifier hash: sha256 or blake3
+        #[arg(long, default_value = "sha256", value_parser = parse_hash_scheme)]
+        hash_scheme: HashScheme,
+        /// Opt Alice in to publishing the match count
+        #[arg(long)]
+        public_count: bool,
+        /// Keep each party's list size from the other
+        #[arg(long)]
+        hide_counts: bool,
+        /// Seconds Bob has to join each session
+        #[arg(long, default_value_t = 0)]
+        join_within: u32,
+        /// Slots each session's result stays escrowed after creation
+        #[arg(long, default_value_t = 0)]
+        reveal_delay: u64,
+        /// Only payer allowed to fund sessions from the template
+        #[arg(long)]
+        sponsor: Option<Pubkey>,
+    },
+    /// Close one of `--app`'s session templates (as its authority)
+    CloseTemplate { name: String },
+    /// Prepay lamports into the `--app` credit account
+    Deposit { lamports: u64 },
+    /// Withdraw unspent `--app` credit (as its authority)
+    Withdraw { lamports: u64 },
+    /// Create the `--app` lookup table and add the program's accounts to it (as its credit authority)
+    CreateLut,
+    /// Grow a session created under an older layout to the current one
+    Realloc { session_id: String },
+    /// Re-encrypt a session's state under the current circuit layout
+    MigrateState { session_id: String },
+    /// Zero a matched or expired session's encrypted state
+    Purge { session_id: String },
+    /// Close a purged session and reclaim its rent
+    Close { session_id: String },
+    /// Close abandoned sessions of anyone's, for a share of their rent
+    Sweep {
+        #[arg(required = true)]
+        session_ids: Vec<String>,
+    },
+    /// Refund a failed session's computation fee to its app's credit
+    Refund { session_id: String },
+    /// Register where the session's completion should be routed
+    Notify {
+        session_id: String,
+        /// Webhook URL or application id; only its hash goes on-chain
+        #[arg(long)]
+        target: String,
+    },
+    /// Consent to attesting a matched session's connection, and attest it
+    /// once both parties have consented
+    Connect { session_id: String },
+    /// Show the signer's activity log, or open or close it
+    Activity {
+        #[arg(long, conflicts_with = "close")]
+        open: bool,
+        #[arg(long)]
+        close: bool,
+    },
+    /// Show the deployment registry and whether this client is
+    /// compatible with it, or sync it first
+    Deployment {
+        #[arg(long)]
+        sync: bool,
+    },
+    /// Register a program for the match callback to notify
+    Hook {
+        session_id: String,
+        /// Program the callback calls
+        #[arg(long)]
+        program: Pubkey,
+        /// Read-only account passed to the program (repeatable)
+        #[arg(long = "account")]
+        accounts: Vec<Pubkey>,
+        /// Writable account passed to the program, after the read-only ones (repeatable)
+        #[arg(long)]
+        writable: Vec<Pubkey>,
+    },
+    /// Publish a contact list as a matchmaking pool (e.g. event attendees)
+    CreatePool {
+        /// Contact file (.vcf, .csv, or one per line)
+        #[arg(long = "from", alias = "contacts")]
+        from: PathBuf,
+        /// Identifier type: phone, email, username, wallet, or solname
+        #[arg(long, default_value = "phone", value_parser = parse_namespace)]
+        namespace: Namespace,
+        /// Identifier hash: sha256 or blake3
+        #[arg(long, default_value = "sha256", value_parser = parse_hash_scheme)]
+        hash_scheme: HashScheme,
+    },
+    /// Find which of your contacts are in a pool
+    MatchPool {
+        pool_id: String,
+        /// Contact file (.vcf, .csv, or one per line)
+        #[arg(long = "from", alias = "contacts")]
+        from: PathBuf,
+    },
+    /// Load a large contact list as a server database for unbalanced PSI
+    CreateDatabase {
+        /// Contact file (.vcf, .csv, or one per line)
+        #[arg(long = "from", alias = "contacts")]
+        from: PathBuf,
+        /// Identifier type: phone, email, username, wallet, or solname
+        #[arg(long, default_value = "phone", value_parser = parse_namespace)]
+        namespace: Namespace,
+        /// Identifier hash: sha256 or blake3
+        #[arg(long, default_value = "sha256", value_parser = parse_hash_scheme)]
+        hash_scheme: HashScheme,
+    },
+    /// Load a new version of a server database and swap it in
+    RefreshDb {
+        database_id: String,
+        /// Contact file (.vcf, .csv, or one per line)
+        #[arg(long = "from", alias = "contacts")]
+        from: PathBuf,
+    },
+    /// Find which of your contacts are in a server database
+    QueryDb {
+        database_id: String,
+        /// Contact file (.vcf, .csv, or one per line)
+        #[arg(long = "from", alias = "contacts")]
+        from: PathBuf,
+    },
+    /// Check whether identifiers are in a server database, billed per
+    /// identifier to --app
+    CheckDb {
+        database_id: String,
+        /// Phone number, email, username, or wallet to look up
+        #[arg(long, required_unless_present = "from", conflicts_with = "from")]
+        identifier: Option<String>,
+        /// Identifiers to look up in batches (.vcf, .csv, or one per line)
+        #[arg(long)]
+        from: Option<PathBuf>,
+    },
+    /// Publish a moderator blocklist (e.g. known spam numbers)
+    CreateBlocklist {
+        /// Contact file (.vcf, .csv, or one per line)
+        #[arg(long = "from", alias = "contacts")]
+        from: PathBuf,
+        /// Identifier type: phone, email, username, wallet, or solname
+        #[arg(long, default_value = "phone", value_parser = parse_namespace)]
+        namespace: Namespace,
+        /// Identifier hash: sha256 or blake3
+        #[arg(long, default_value = "sha256", value_parser = parse_hash_scheme)]
+        hash_scheme: HashScheme,
+    },
+    /// Check whether any of your contacts are on a blocklist
+    CheckBlocklist {
+        blocklist_id: String,
+        /// Contact file (.vcf, .csv, or one per line)
+        #[arg(long = "from", alias = "contacts")]
+        from: PathBuf,
+    },
+    /// Open a registry of contact cards (messaging keys)
+    CreateCardRegistry {
+        /// Identifier type: phone, email, username, wallet, or solname
+        #[arg(long, default_value = "phone", value_parser = parse_namespace)]
+        namespace: Namespace,
+        /// Identifier hash: sha256 or blake3
+        #[arg(long, default_value = "sha256", value_parser = parse_hash_scheme)]
+        hash_scheme: HashScheme,
+    },
+    /// Publish your messaging key under your own identifier
+    PublishCard {
+        registry_id: String,
+        /// Your own phone number, email, username, or wallet
+        #[arg(long)]
+        identifier: String,
+        /// X25519 messaging public key, hex
+        #[arg(long)]
+        key: String,
+    },
+    /// Fetch the messaging keys of your contacts in a registry
+    MatchCards {
+        registry_id: String,
+        /// Contact file (.vcf, .csv, or one per line)
+        #[arg(long = "from", alias = "contacts")]
+        from: PathBuf,
+    },
+}
+
+struct Ctx {
+    rpc: RpcClient,
+    signer: Keypair,
+    fee_payer: Option<Keypair>,
+    cluster_offset: u32,
+    timeout: Duration,
+    requeue: RequeuePolicy,
+    app_id: [u8; 32],
+    memo: [u8; 32],
+    /// `--invite`, redeemed with whichever join the command sends
+    invite: Option<String>,
+}
+
+impl Ctx {
+    fn send(&self, ix: Instruction) -> Result<Signature> {
+        self.send_all(&[ix])
+    }
+
+    /// Send `ixs` in one transaction, requeuing while the mempool is full.
+    fn send_all(&self, ixs: &[Instruction]) -> Result<Signature> {
+        let payer = self.fee_payer.as_ref().unwrap_or(&self.signer);
+        Ok(rpc::send_with_requeue(&self.rpc, payer, &[&self.signer], ixs, self.requeue)?)
+    }
+
+    /// Send `ix`, which records the signer as the session's Bob. While
+    /// the config caps sessions per pair or sets a query budget, the
+    /// pair's quota goes with it, opened first if it never was; while
+    /// it sets a gatekeeper, both parties' attestations do. With
+    /// `--invite`, the code is claimed first and redeemed ahead of `ix`.
+    fn send_as_bob(&self, session_id: &[u8; 32], ix: Instruction) -> Result<Signature> {
+        let mut ixs = match &self.invite {
+            Some(code) => vec![self.claim_invite(session_id, code)?],
+            None => Vec::new(),
+        };
+        let Ok(config) = rpc::fetch_config(&self.rpc) else {
+            ixs.push(ix);
+            return self.send_all(&ixs);
+        };
+        let capped = config.max_pair_sessions != 0 || config.query_budget != 0;
+        if !capped && config.gatekeeper == Gatekeeper::None as u8 {
+            ixs.push(ix);
+            return self.send_all(&ixs);
+        }
+        let alice = rpc::fetch_session(&self.rpc, session_id)?.alice;
+        let bob = self.signer.pubkey();
+        let mut ix = instructions::with_attestations(ix, &config, &[alice, bob]);
+        if capped {
+            if rpc::fetch_pair_quota(&self.rpc, &alice, &bob).is_err() {
+                ixs.push(instructions::open_pair_quota(self.payer(), alice, bob));
+            }
+            ix = instructions::with_pair_quota(ix, alice, bob);
+        }
+        ixs.push(ix);
+        self.send_all(&ixs)
+    }
+
+    /// Claim the session's invite `code` for the signer and wait out
+    /// the claim's slot, returning the redemption to send with the join.
+    fn claim_invite(&self, session_id: &[u8; 32], code: &str) -> Result<Instruction> {
+        let bob = self.signer.pubkey();
+        let sig = self.send(instructions::claim_invite(bob, session_id, code))?;
+        println!("invite claimed ({sig})");
+        let claimed = self.rpc.get_slot()?;
+        while self.rpc.get_slot()? <= claimed {
+            thread::sleep(Duration::from_millis(400));
+        }
+        Ok(instructions::redeem_invite(bob, session_id, code))
+    }
+
+    /// The app a session is billed to.
+    fn app_of(&self, session_id: &[u8; 32]) -> Result<[u8; 32]> {
+        Ok(rpc::fetch_session(&self.rpc, session_id)?.app_id)
+    }
+
+    /// Account funding rent and fees: `--fee-payer` if given, else the signer.
+    fn payer(&self) -> Pubkey {
+        self.fee_payer.as_ref().unwrap_or(&self.signer).pubkey()
+    }
+
+    fn cipher(&self) -> Result<PartyCipher> {
+        Ok(PartyCipher::new(rpc::fetch_mxe_public_key(&self.rpc)?))
+    }
+
+    /// `contacts` hashed for a session, filled with decoys when the
+    /// session hides list sizes so every slot its capacity allows is used.
+    fn session_list(
+        &self,
+        session_id: &[u8; 32],
+        scheme: HashScheme,
+        namespace: Namespace,
+        contacts: &[String],
+    ) -> Result<HashedContacts> {
+        let hashed = hash_contact_list(scheme, namespace, contacts)?;
+        let session = rpc::fetch_session(&self.rpc, session_id)?;
+        if session.hide_counts == 0 {
+            return Ok(hashed);
+        }
+        Ok(pad_with_decoys(
+            &hashed,
+            session_id,
+            &self.signer.pubkey().to_bytes(),
+            session.capacity(),
+        ))
+    }
+
+    /// Scheme and namespace a session's contacts must be hashed with.
+    fn hash_domain(&self, session_id: &[u8; 32]) -> Result<(HashScheme, Namespace)> {
+        let session = rpc::fetch_session(&self.rpc, session_id)?;
+        Ok((
+            HashScheme::try_from(session.hash_scheme)?,
+            Namespace::try_from(session.namespace)?,
+        ))
+    }
+}
+
+fn parse_namespace(value: &str) -> Result<Namespace, String> {
+    match value {
+        "phone" => Ok(Namespace::Phone),
+        "email" => Ok(Namespace::Email),
+        "username" => Ok(Namespace::Username),
+        "wallet" => Ok(Namespace::Wallet),
+        "solname" => Ok(Namespace::SolName),
+        other => Err(format!("unknown namespace {other:?}")),
+    }
+}
+
+fn parse_gatekeeper(value: &str) -> Result<Gatekeeper, String> {
+    match value {
+        "none" => Ok(Gatekeeper::None),
+        "civic" => Ok(Gatekeeper::CivicPass),
+        other => Err(format!("unknown gatekeeper {other:?}")),
+    }
+}
+
+fn parse_session_mode(value: &str) -> Result<u8, String> {
+    (0..=SessionMode::CommittedSet as u8)
+        .find(|mode| SessionMode::name(*mode) == value)
+        .ok_or_else(|| format!("unknown session mode {value:?}"))
+}
+
+fn parse_capacity(value: &str) -> Result<u8, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|capacity| CAPACITY_TIERS.contains(capacity))
+        .ok_or_else(|| format!("capacity must be one of {CAPACITY_TIERS:?}"))
+}
+
+fn parse_hash_scheme(value: &str) -> Result<HashScheme, String> {
+    match value {
+        "sha256" => Ok(HashScheme::Sha256),
+        "blake3" => Ok(HashScheme::Blake3),
+        other => Err(format!("unknown hash scheme {other:?}")),
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let read_keypair = |path: &str| {
+        let path = shellexpand::tilde(path).into_owned();
+        read_keypair_file(&path).map_err(|e| anyhow::anyhow!("reading keypair {path}: {e}"))
+    };
+    let ctx = Ctx {
+        rpc: RpcClient::new_with_commitment(cli.url, CommitmentConfig::confirmed()),
+        signer: read_keypair(&cli.keypair)?,
+        fee_payer: cli.fee_payer.as_deref().map(read_keypair).transpose()?,
+        cluster_offset: cli.cluster_offset,
+        timeout: Duration::from_secs(cli.timeout),
+        requeue: RequeuePolicy {
+            max_requeues: cli.requeues,
+            ..RequeuePolicy::default()
+        },
+        app_id: cli.app.map_or(NO_APP_ID, |app| hash(app.as_bytes()).to_bytes()),
+        memo: cli.memo.map_or(NO_MEMO, |memo| hash(memo.as_bytes()).to_bytes()),
+        invite: cli.invite,
+    };
+
+    match cli.command {
+        Command::InitCompDefs => init_comp_defs(&ctx),
+        Command::CreateSession { template: Some(name), .. } => create_session_from_template(&ctx, &name),
+        Command::CreateSession {
+            template: None,
+            subscription,
+            score,
+            fof,
+            follow,
+            committed_set,
+            namespace,
+            hash_scheme,
+            public_count,
+            hide_counts,
+            join_within,
+            reveal_after_slot,
+            capacity,
+            stateless,
+            bob,
+            invite,
+        } => {
+            let intended_bob = bob.unwrap_or_default();
+            let invite_code = invite.then(random_invite_code);
+            let join_deadline = match join_within {
+                Some(secs) => unix_now()? + i64::try_from(secs)?,
+                None => 0,
+            };
+            if follow {
+                return create_session(
+                    &ctx,
+                    SessionMode::MutualFollow,
+                    hash_scheme,
+                    Namespace::Wallet,
+                    false,
+                    hide_counts,
+                    join_deadline,
+                    reveal_after_slot,
+                    capacity,
+                    stateless,
+                    intended_bob,
+                    invite_code.as_deref(),
+                );
+            }
+            let mode = match (subscription, score, fof, committed_set) {
+                (true, _, _, _) => SessionMode::Subscription,
+                (_, true, _, _) => SessionMode::Score,
+                (_, _, true, _) => SessionMode::FriendsOfFriends,
+                (_, _, _, true) => SessionMode::CommittedSet,
+                _ => SessionMode::OneShot,
+            };
+            create_session(
+                &ctx,
+                mode,
+                hash_scheme,
+                namespace,
+                public_count,
+                hide_counts,
+                join_deadline,
+                reveal_after_slot,
+                capacity,
+                stateless,
+                intended_bob,
+                invite_code.as_deref(),
+            )
+        }
+        Command::FofJoin { session_id, from } => fof_join(&ctx, &session_id, &from),
+        Command::FofConnect { session_id, from } => fof_connect(&ctx, &session_id, &from),
+        Command::FofReveal { session_id } => fof_reveal(&ctx, &session_id),
+        Command::FollowSubmit { session_id, from } => follow_submit(&ctx, &session_id, &from),
+        Command::FollowCheck { session_id, from } => follow_check(&ctx, &session_id, &from),
+        Command::FollowReveal { session_id } => follow_reveal(&ctx, &session_id),
+        Command::Score { session_id, from } => run_score(&ctx, &session_id, &from),
+        Command::RevealScore { session_id } => reveal_score(&ctx, &session_id),
+        Command::Submit { session_id, from, split, committed } => match (from, committed) {
+            (_, Some(saved)) => submit_committed(&ctx, &session_id, &saved),
+            (Some(from), None) => submit(&ctx, &session_id, &from, split),
+            (None, None) => bail!("pass --from or --committed"),
+        },
+        Command::Commit { session_id, from, save } => commit(&ctx, &session_id, &from, &save),
+        Command::Permit { session_id, from, expires_in, save } => {
+            permit(&ctx, &session_id, &from, expires_in, &save)
+        }
+        Command::Relay { session_id, permit } => relay(&ctx, &session_id, &permit),
+        Command::Match { session_id, from, public_count } => run_match(&ctx, &session_id, &from, public_count),
+        Command::SetMatch { session_id, from } => set_match(&ctx, &session_id, &from),
+        Command::Reveal { session_id, from, page } => reveal(&ctx, &session_id, from.as_deref(), page),
+        Command::RevealBoth { session_id, bob_keypair, page } => {
+            reveal_both(&ctx, &session_id, &read_keypair(&bob_keypair)?, page)
+        }
+        Command::Delta { session_id, add, remove } => {
+            delta(&ctx, &session_id, add.as_deref(), remove.as_deref())
+        }
+        Command::Status { session_id } => status(&ctx, &session_id),
+        Command::View { session_id } => view(&ctx, &session_id),
+        Command::Ping { session_id } => ping(&ctx, &session_id),
+        Command::Stats => stats(&ctx),
+        Command::InitConfig { price } => {
+            let sig = ctx.send(instructions::init_config(ctx.signer.pubkey(), price))?;
+            println!("config initialized at {price} lamports per computation ({sig})");
+            Ok(())
+        }
+        Command::SetPrice { lamports } => {
+            let sig = ctx.send(instructions::set_computation_price(ctx.signer.pubkey(), lamports))?;
+            println!("computation price set to {lamports} lamports ({sig})");
+            Ok(())
+        }
+        Command::SetFeeShare { bps } => {
+            let sig = ctx.send(instructions::set_fee_pool_share(ctx.signer.pubkey(), bps))?;
+            println!("fee pool share set to {bps} bps ({sig})");
+            Ok(())
+        }
+        Command::SetRefundShare { bps } => {
+            let sig = ctx.send(instructions::set_refund_share(ctx.signer.pubkey(), bps))?;
+            println!("refund share set to {bps} bps ({sig})");
+            Ok(())
+        }
+        Command::SetMinClientVersion { version } => {
+            let sig = ctx.send(instructions::set_min_client_version(ctx.signer.pubkey(), version))?;
+            println!("minimum client version set to {version} ({sig})");
+            Ok(())
+        }
+        Command::SetMaxPairSessions { sessions } => {
+            let sig = ctx.send(instructions::set_max_pair_sessions(ctx.signer.pubkey(), sessions))?;
+            println!("sessions per pair per day capped at {sessions} ({sig})");
+            Ok(())
+        }
+        Command::SetQueryBudget { slots } => {
+            let sig = ctx.send(instructions::set_query_budget(ctx.signer.pubkey(), slots))?;
+            println!("query budget set to {slots} slots ({sig})");
+            Ok(())
+        }
+        Command::SetGatekeeper { gatekeeper, network } => {
+            let sig = ctx.send(instructions::set_gatekeeper(
+                ctx.signer.pubkey(),
+                gatekeeper as u8,
+                network.unwrap_or_default(),
+            ))?;
+            println!("gatekeeper set to {gatekeeper:?} ({sig})");
+            Ok(())
+        }
+        Command::BlockWallet { wallet } => {
+            let sig = ctx.send(instructions::block_wallet(ctx.signer.pubkey(), wallet))?;
+            println!("blocked {wallet} ({sig})");
+            Ok(())
+        }
+        Command::UnblockWallet { wallet } => {
+            let sig = ctx.send(instructions::unblock_wallet(ctx.signer.pubkey(), wallet))?;
+            println!("unblocked {wallet} ({sig})");
+            Ok(())
+        }
+        Command::RenewBudget { querier } => {
+            let sig = ctx.send(instructions::renew_query_budget(ctx.signer.pubkey(), querier))?;
+            println!("query budget of {querier} renewed ({sig})");
+            Ok(())
+        }
+        Command::Consent { wallets, apps } => {
+            let apps: Vec<[u8; 32]> = apps.iter().map(|app| hash(app.as_bytes()).to_bytes()).collect();
+            let sig = ctx.send(instructions::set_consent_record(ctx.signer.pubkey(), wallets, apps))?;
+            println!("consent record updated ({sig})");
+            Ok(())
+        }
+        Command::CloseConsent => {
+            let sig = ctx.send(instructions::close_consent_record(ctx.signer.pubkey()))?;
+            println!("consent record closed ({sig})");
+            Ok(())
+        }
+        Command::OpenCredit => {
+            if ctx.app_id == NO_APP_ID {
+                bail!("open-credit needs --app");
+            }
+            let sig = ctx.send(instructions::open_credit_account(ctx.payer(), ctx.signer.pubkey(), ctx.app_id))?;
+            println!("credit account opened for app {} ({sig})", hex::encode(ctx.app_id));
+            Ok(())
+        }
+        Command::CreateTemplate {
+            name,
+            mode,
+            namespace,
+            hash_scheme,
+            public_count,
+            hide_counts,
+            join_within,
+            reveal_delay,
+            sponsor,
+        } => {
+            if ctx.app_id == NO_APP_ID {
+                bail!("create-template needs --app");
+            }
+            let sig = ctx.send(instructions::create_session_template(
+                ctx.payer(),
+                ctx.signer.pubkey(),
+                ctx.app_id,
+                hash(name.as_bytes()).to_bytes(),
+                mode,
+                namespace as u8,
+                hash_scheme as u8,
+                public_count,
+                hide_counts,
+                join_within,
+                reveal_delay,
+                sponsor.unwrap_or_default(),
+            ))?;
+            println!("template {name:?} created ({sig})");
+            Ok(())
+        }
+        Command::CloseTemplate { name } => {
+            let template_id = hash(name.as_bytes()).to_bytes();
+            let sig = ctx.send(instructions::close_session_template(ctx.signer.pubkey(), &ctx.app_id, &template_id))?;
+            println!("template {name:?} closed ({sig})");
+            Ok(())
+        }
+        Command::Deposit { lamports } => {
+            let sig = ctx.send(instructions::deposit_credit(ctx.payer(), &ctx.app_id, lamports))?;
+            println!("deposited {lamports} lamports ({sig})");
+            Ok(())
+        }
+        Command::Withdraw { lamports } => {
+            let sig = ctx.send(instructions::withdraw_credit(ctx.signer.pubkey(), &ctx.app_id, lamports))?;
+            println!("withdrew {lamports} lamports ({sig})");
+            Ok(())
+        }
+        Command::CreateLut => create_lut(&ctx),
+        Command::Realloc { session_id } => realloc(&ctx, &session_id),
+        Command::MigrateState { session_id } => migrate_state(&ctx, &session_id),
+        Command::Purge { session_id } => purge(&ctx, &session_id),
+        Command::Close { session_id } => close(&ctx, &session_id),
+        Command::Sweep { session_ids } => sweep(&ctx, &session_ids),
+        Command::Notify { session_id, target } => notify(&ctx, &session_id, &target),
+        Command::Connect { session_id } => connect(&ctx, &session_id),
+        Command::Activity { open, close } => activity(&ctx, open, close),
+        Command::Deployment { sync } => deployment(&ctx, sync),
+        Command::Hook { session_id, program, accounts, writable } => {
+            hook(&ctx, &session_id, program, &accounts, &writable)
+        }
+        Command::Refund { session_id } => refund(&ctx, &session_id),
+        Command::CreatePool { from, namespace, hash_scheme } => {
+            create_pool(&ctx, &from, hash_scheme, namespace)
+        }
+        Command::MatchPool { pool_id, from } => match_pool(&ctx, &pool_id, &from),
+        Command::CreateDatabase { from, namespace, hash_scheme } => {
+            create_database(&ctx, &from, hash_scheme, namespace)
+        }
+        Command::RefreshDb { database_id, from } => refresh_db(&ctx, &database_id, &from),
+        Command::QueryDb { database_id, from } => query_db(&ctx, &database_id, &from),
+        Command::CheckDb { database_id, identifier, from } => match (identifier, from) {
+            (Some(identifier), _) => check_db(&ctx, &database_id, &identifier),
+            (None, Some(from)) => check_db_batch(&ctx, &database_id, &from),
+            (None, None) => bail!("pass --identifier or --from"),
+        },
+        Command::CreateBlocklist { from, namespace, hash_scheme } => {
+            create_blocklist(&ctx, &from, hash_scheme, namespace)
+        }
+        Command::CheckBlocklist { blocklist_id, from } => check_blocklist(&ctx, &blocklist_id, &from),
+        Command::CreateCardRegistry { namespace, hash_scheme } => {
+            create_card_registry(&ctx, hash_scheme, namespace)
+        }
+        Command::PublishCard { registry_id, identifier, key } => {
+            publish_card(&ctx, &registry_id, &identifier, &key)
+        }
+        Command::MatchCards { registry_id, from } => match_cards(&ctx, &registry_id, &from),
+    }
+}
+
+/// Read a contact file, warning and truncating when it exceeds capacity.
+fn read_contacts(path: &Path) -> Result<Vec<String>> {
+    let mut contacts = import::read_contacts(path)?;
+    if contacts.len() > MAX_CONTACTS {
+        eprintln!(
+            "warning: {} unique contacts in {}, only the first {MAX_CONTACTS} fit in a session",
+            contacts.len(),
+            path.display(),
+        );
+        contacts.truncate(MAX_CONTACTS);
+    }
+    Ok(contacts)
+}
+
+/// Results name slots of the list the caller submitted; given that list
+/// and the contacts it was hashed from, print the matches by name.
+fn print_matches(
+    result: &DecryptedMatches,
+    (scheme, namespace): (HashScheme, Namespace),
+    submitted: Option<(&HashedContacts, &[String])>,
+) {
+    println!("{} mutual contact(s)", result.match_count);
+    match submitted {
+        Some((list, contacts)) => {
+            for contact in resolve_matches(scheme, namespace, contacts, &result.hashes(&list.hashes)) {
+                println!("  {contact}");
+            }
+        }
+        None => {
+            for slot in &result.slots {
+                println!("  slot {slot}");
+            }
+        }
+    }
+}
+
+fn print_score(score: &DecryptedScore) {
+    println!(
+        "overlap: {:.2}% of the smaller list, Jaccard {:.2}%",
+        score.overlap_bp as f64 / 100.0,
+        score.jaccard_bp as f64 / 100.0,
+    );
+}
+
+fn init_comp_defs(ctx: &Ctx) -> Result<()> {
+    let mxe = rpc::fetch_mxe(&ctx.rpc)?;
+    for circuit in circuits::ALL {
+        if rpc::is_comp_def_initialized(&ctx.rpc, circuit)? {
+            println!("{circuit}: already initialized");
+            continue;
+        }
+        let sig = ctx.send(instructions::init_comp_def(
+            ctx.payer(),
+            circuit,
+            mxe.lut_offset_slot,
+        ))?;
+        println!("{circuit}: initialized ({sig})");
+    }
+    if rpc::fetch_global_stats(&ctx.rpc).is_ok() {
+        println!("global stats: already initialized");
+    } else {
+        let sig = ctx.send(instructions::init_global_stats(ctx.payer()))?;
+        println!("global stats: initialized ({sig})");
+    }
+    let sig = ctx.send(instructions::sync_deployment(ctx.payer()))?;
+    println!("deployment registry: synced ({sig})");
+    Ok(())
+}
+
+fn ping(ctx: &Ctx, session_id: &str) -> Result<()> {
+    let id = parse_session_id(session_id)?;
+    let sig = ctx.send(instructions::ping_session(&id))?;
+    println!("heartbeat emitted ({sig})");
+    Ok(())
+}
+
+fn view(ctx: &Ctx, session_id: &str) -> Result<()> {
+    let id = parse_session_id(session_id)?;
+    let report = rpc::view_session(&ctx.rpc, &ctx.payer(), &id)?;
+    println!("session:  {}", hex::encode(report.session_id));
+    println!("status:   {} (layout v{})", report.status, report.version);
+    println!("mode:     {}", report.mode);
+    println!("capacity: {} contacts", report.capacity);
+    println!("alice:    {}", report.alice);
+    println!("bob:      {}", report.bob);
+    if report.app_id != NO_APP_ID {
+        println!("app:      {}", hex::encode(report.app_id));
+    }
+    if report.memo != NO_MEMO {
+        println!("memo:     {}", hex::encode(report.memo));
+    }
+    if report.join_deadline != 0 {
+        let state = if report.join_expired { "passed" } else { "open" };
+        println!("deadline: {} ({state})", report.join_deadline);
+    }
+    if report.reveal_after_slot != 0 {
+        let state = if report.reveal_locked { "locked" } else { "open" };
+        println!("reveal:   after slot {} ({state})", report.reveal_after_slot);
+    }
+    if let (Some(stage), Some(offset)) = (&report.pending_stage, report.pending_offset) {
+        println!("pending:  {stage} computation {offset}");
+    }
+    if report.refundable_fee != 0 {
+        println!("refund:   {} lamports", report.refundable_fee);
+    }
+    let missing: Vec<_> = circuits::ALL
+        .into_iter()
+        .filter(|circuit| !report.initialized_comp_defs.iter().any(|ready| ready == circuit))
+        .collect();
+    if missing.is_empty() {
+        println!("comp defs: all {} ready", circuits::ALL.len());
+    } else {
+        println!("comp defs: not ready: {}", missing.join(", "));
+    }
+    Ok(())
+}
+
+fn stats(ctx: &Ctx) -> Result<()> {
+    print_usage("all apps", &rpc::fetch_global_stats(&ctx.rpc)?.usage);
+    if ctx.app_id != NO_APP_ID {
+        print_usage(&hex::encode(ctx.app_id), &rpc::fetch_app_stats(&ctx.rpc, &ctx.app_id)?.usage);
+    }
+    if let Ok(config) = rpc::fetch_config(&ctx.rpc) {
+        println!("price:          {} lamports per computation", config.computation_price);
+        println!("fee pool share: {} bps", config.fee_pool_share_bps);
+        println!("refund share:   {} bps", config.refund_bps);
+        let treasury = rpc::fetch_treasury(&ctx.rpc)?;
+        println!(
+            "treasury:       {} lamports kept, {} topped up, {} refunded",
+            treasury.collected, treasury.fee_pool_topped_up, treasury.refunded
+        );
+        if let Ok(credit) = rpc::fetch_credit_account(&ctx.rpc, &ctx.app_id) {
+            println!("credit:         {} lamports", credit.balance);
+        }
+    }
+    Ok(())
+}
+
+fn print_usage(label: &str, usage: &UsageCounters) {
+    println!("{label}:");
+    println!("  sessions:     {}", usage.sessions_created);
+    println!("  matches:      {}", usage.matches_completed);
+    println!("  rejected:     {}", usage.submissions_rejected);
+    println!("  computations: {}", usage.computations);
+}
+
+fn create_session(
+    ctx: &Ctx,
+    mode: SessionMode,
+    scheme: HashScheme,
+    namespace: Namespace,
+    public_count: bool,
+    hide_counts: bool,
+    join_deadline: i64,
+    reveal_after_slot: u64,
+    capacity: u8,
+    stateless: bool,
+    intended_bob: Pubkey,
+    invite_code: Option<&str>,
+) -> Result<()> {
+    if stateless {
+        let session_id = random_session_id();
+        let sig = ctx.send(instructions::create_stateless_session(
+            ctx.payer(),
+            ctx.signer.pubkey(),
+            session_id,
+            ctx.app_id,
+            mode as u8,
+            namespace as u8,
+            scheme as u8,
+            public_count,
+            hide_counts,
+            join_deadline,
+            reveal_after_slot,
+            capacity,
+            ctx.memo,
+        ))?;
+        println!("created stateless session ({sig})");
+        println!("session id: {}", hex::encode(session_id));
+        return Ok(());
+    }
+    let session_id = new_session(
+        ctx,
+        mode,
+        scheme,
+        namespace,
+        public_count,
+        hide_counts,
+        join_deadline,
+        reveal_after_slot,
+        capacity,
+        intended_bob,
+        invite_code,
+    )?;
+    println!("session id: {}", hex::encode(session_id));
+    if let Some(code) = invite_code {
+        println!("invite code: {code}");
+    }
+    Ok(())
+}
+
+fn create_session_from_template(ctx: &Ctx, name: &str) -> Result<()> {
+    let cipher = ctx.cipher()?;
+    let session_id = random_session_id();
+    let offset = random_computation_offset();
+
+    let sig = ctx.send(instructions::create_session_from_template(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        ctx.cluster_offset,
+        offset,
+        session_id,
+        &ctx.app_id,
+        &hash(name.as_bytes()).to_bytes(),
+        cipher.public_key(),
+        u128::from_le_bytes(random_nonce()),
+        ctx.memo,
+    ))?;
+    println!("queued init_session from template {name:?} ({sig})");
+
+    let computation = arcium_accounts(ctx.cluster_offset, circuits::INIT_SESSION, offset).computation_account;
+    rpc::await_event::<SessionInitialized>(&ctx.rpc, &computation, ctx.timeout)?;
+    println!("session id: {}", hex::encode(session_id));
+    Ok(())
+}
+
+/// Create a session and wait for its encrypted state to be initialized.
+fn new_session(
+    ctx: &Ctx,
+    mode: SessionMode,
+    scheme: HashScheme,
+    namespace: Namespace,
+    public_count: bool,
+    hide_counts: bool,
+    join_deadline: i64,
+    reveal_after_slot: u64,
+    capacity: u8,
+    intended_bob: Pubkey,
+    invite_code: Option<&str>,
+) -> Result<[u8; 32]> {
+    let cipher = ctx.cipher()?;
+    let session_id = random_session_id();
+    let invite = invite_code.map_or(NO_INVITE, |code| invite_hash(&session_id, code.as_bytes()));
+    let offset = random_computation_offset();
+
+    let sig = ctx.send(instructions::create_session(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        ctx.cluster_offset,
+        offset,
+        session_id,
+        ctx.app_id,
+        cipher.public_key(),
+        u128::from_le_bytes(random_nonce()),
+        mode as u8,
+        namespace as u8,
+        scheme as u8,
+        public_count,
+        hide_counts,
+        join_deadline,
+        reveal_after_slot,
+        capacity,
+        ctx.memo,
+        intended_bob,
+        invite,
+    ))?;
+    println!("queued init_session ({sig})");
+
+    let computation = arcium_accounts(ctx.cluster_offset, circuits::INIT_SESSION, offset).computation_account;
+    rpc::await_event::<SessionInitialized>(&ctx.rpc, &computation, ctx.timeout)?;
+    Ok(session_id)
+}
+
+fn submit(ctx: &Ctx, session_id: &str, path: &Path, split: bool) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let (scheme, namespace) = ctx.hash_domain(&session_id)?;
+    let contacts = import::read_contacts(path)?;
+    let session = rpc::fetch_session(&ctx.rpc, &session_id)?;
+    let capacity = session.capacity();
+    let slots = capacity as usize;
+
+    if contacts.len() <= slots {
+        return submit_chunk(ctx, &session_id, scheme, namespace, &contacts);
+    }
+    if !split {
+        eprintln!(
+            "warning: {} unique contacts in {}, only the first {slots} are submitted; \
+             pass --split to spread the rest over new sessions",
+            contacts.len(),
+            path.display(),
+        );
+        return submit_chunk(ctx, &session_id, scheme, namespace, &contacts[..slots]);
+    }
+    if session.invite_hash != NO_INVITE {
+        bail!("--split needs the session's invite redeemed first, so the overflow sessions can go to the same Bob");
+    }
+
+    let mut chunks = contacts.chunks(slots);
+    if let Some(first) = chunks.next() {
+        submit_chunk(ctx, &session_id, scheme, namespace, first)?;
+    }
+    for chunk in chunks {
+        let extra = new_session(
+            ctx,
+            SessionMode::OneShot,
+            scheme,
+            namespace,
+            false,
+            false,
+            0,
+            0,
+            capacity,
+            session.intended_bob,
+            None,
+        )?;
+        println!("overflow session id: {}", hex::encode(extra));
+        submit_chunk(ctx, &extra, scheme, namespace, chunk)?;
+    }
+    Ok(())
+}
+
+fn submit_chunk(
+    ctx: &Ctx,
+    session_id: &[u8; 32],
+    scheme: HashScheme,
+    namespace: Namespace,
+    contacts: &[String],
+) -> Result<()> {
+    let hashed = ctx.session_list(session_id, scheme, namespace, contacts)?;
+    let encrypted = ctx.cipher()?.encrypt_contacts(&hashed);
+    send_alice_submission(ctx, session_id, &encrypted)
+}
+
+/// Queue Alice's submission and wait for it to be stored. A stateless
+/// session without state yet gets `init_and_submit_alice`.
+fn send_alice_submission(ctx: &Ctx, session_id: &[u8; 32], encrypted: &EncryptedContacts) -> Result<()> {
+    let offset = random_computation_offset();
+    let session = rpc::fetch_session(&ctx.rpc, session_id)?;
+    let stateless = rpc::fetch_state_handle(&ctx.rpc, session_id)?.generation == 0;
+    let (ix, circuit) = if stateless {
+        (
+            instructions::init_and_submit_alice(
+                ctx.payer(),
+                ctx.signer.pubkey(),
+                ctx.cluster_offset,
+                offset,
+                session_id,
+                &session.app_id,
+                encrypted,
+            ),
+            circuits::INIT_AND_SUBMIT_ALICE,
+        )
+    } else {
+        (
+            instructions::submit_contacts_alice(
+                ctx.payer(),
+                ctx.signer.pubkey(),
+                ctx.cluster_offset,
+                offset,
+                session_id,
+                &session.app_id,
+                encrypted,
+                session.capacity(),
+            ),
+            instructions::alice_circuit(session.capacity()),
+        )
+    };
+    let sig = ctx.send(ix)?;
+    println!("submitted contacts ({sig})");
+
+    let computation = arcium_accounts(ctx.cluster_offset, circuit, offset).computation_account;
+    rpc::await_event::<AliceSubmitted>(&ctx.rpc, &computation, ctx.timeout)?;
+    println!("submission stored; waiting for a partner to match");
+    Ok(())
+}
+
+fn commit(ctx: &Ctx, session_id: &str, path: &Path, save: &Path) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let (scheme, namespace) = ctx.hash_domain(&session_id)?;
+    let contacts = read_contacts(path)?;
+    let hashed = ctx.session_list(&session_id, scheme, namespace, &contacts)?;
+    let encrypted = ctx.cipher()?.encrypt_contacts(&hashed);
+
+    // Save first: the commitment is useless without the exact ciphertexts
+    std::fs::write(save, encrypted.to_bytes())?;
+    let sig = ctx.send(instructions::commit_contacts(ctx.signer.pubkey(), &session_id, &encrypted))?;
+    println!("committed to {} contact(s) ({sig})", hashed.count);
+    println!("submit later with: submit {} --committed {}", hex::encode(session_id), save.display());
+    Ok(())
+}
+
+/// Permit file: Alice's pubkey, expiry (i64 LE), her signature, then the
+/// encrypted submission it covers.
+const PERMIT_HEADER_LEN: usize = 32 + 8 + 64;
+
+fn permit(ctx: &Ctx, session_id: &str, path: &Path, expires_in: u64, save: &Path) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let (scheme, namespace) = ctx.hash_domain(&session_id)?;
+    let contacts = read_contacts(path)?;
+    let hashed = ctx.session_list(&session_id, scheme, namespace, &contacts)?;
+    let encrypted = ctx.cipher()?.encrypt_contacts(&hashed);
+    let expiry = unix_now()? + i64::try_from(expires_in)?;
+
+    let message = instructions::alice_permit_message(&session_id, &encrypted, expiry);
+    let signature = ctx.signer.sign_message(&message);
+
+    let mut bytes = Vec::with_capacity(PERMIT_HEADER_LEN + EncryptedContacts::ENCODED_LEN);
+    bytes.extend_from_slice(ctx.signer.pubkey().as_ref());
+    bytes.extend_from_slice(&expiry.to_le_bytes());
+    bytes.extend_from_slice(signature.as_ref());
+    bytes.extend_from_slice(&encrypted.to_bytes());
+    std::fs::write(save, bytes)?;
+    println!("signed a permit for {} contact(s), valid until {expiry}", hashed.count);
+    println!("relay with: relay {} --permit {}", hex::encode(session_id), save.display());
+    Ok(())
+}
+
+fn relay(ctx: &Ctx, session_id: &str, path: &Path) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let bytes = std::fs::read(path)?;
+    if bytes.len() != PERMIT_HEADER_LEN + EncryptedContacts::ENCODED_LEN {
+        bail!("{}: not a permit file", path.display());
+    }
+    let (header, contacts) = bytes.split_at(PERMIT_HEADER_LEN);
+    let alice = Pubkey::try_from(&header[..32])?;
+    let expiry = i64::from_le_bytes(header[32..40].try_into()?);
+    let signature: [u8; 64] = header[40..].try_into()?;
+    let encrypted = EncryptedContacts::from_bytes(contacts)?;
+
+    let offset = random_computation_offset();
+    let sig = ctx.send_all(&instructions::submit_contacts_with_permit(
+        ctx.payer(),
+        alice,
+        &signature,
+        ctx.cluster_offset,
+        offset,
+        &session_id,
+        &ctx.app_of(&session_id)?,
+        &encrypted,
+        expiry,
+    ))?;
+    println!("relayed {alice}'s contacts ({sig})");
+
+    let computation = arcium_accounts(ctx.cluster_offset, circuits::SUBMIT_ALICE, offset).computation_account;
+    rpc::await_event::<AliceSubmitted>(&ctx.rpc, &computation, ctx.timeout)?;
+    println!("submission stored; waiting for a partner to match");
+    Ok(())
+}
+
+fn realloc(ctx: &Ctx, session_id: &str) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let sig = ctx.send(instructions::realloc_session(ctx.payer(), ctx.signer.pubkey(), &session_id))?;
+    println!("session is on layout v{SESSION_VERSION} ({sig})");
+    Ok(())
+}
+
+fn migrate_state(ctx: &Ctx, session_id: &str) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let offset = random_computation_offset();
+    let sig = ctx.send(instructions::migrate_session_state(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        ctx.cluster_offset,
+        offset,
+        &session_id,
+    ))?;
+    println!("queued migrate_session_state ({sig})");
+
+    let computation = arcium_accounts(ctx.cluster_offset, circuits::MIGRATE_STATE, offset).computation_account;
+    let migrated: StateMigrated = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+    println!(
+        "session state migrated from layout v{} to v{}",
+        migrated.from_version, migrated.state_version
+    );
+    Ok(())
+}
+
+fn purge(ctx: &Ctx, session_id: &str) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let offset = random_computation_offset();
+    let sig = ctx.send(instructions::purge_session_state(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        ctx.cluster_offset,
+        offset,
+        &session_id,
+    ))?;
+    println!("queued purge_session_state ({sig})");
+
+    let computation = arcium_accounts(ctx.cluster_offset, circuits::PURGE_STATE, offset).computation_account;
+    let _: StatePurged = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+    println!("session state purged; `close` reclaims the rent");
+    Ok(())
+}
+
+/// Addresses `extend_app_lut` takes per transaction, within the legacy size limit.
+const LUT_EXTEND_CHUNK: usize = 20;
+
+fn create_lut(ctx: &Ctx) -> Result<()> {
+    if ctx.app_id == NO_APP_ID {
+        bail!("create-lut needs --app");
+    }
+    let recent_slot = ctx.rpc.get_slot()?;
+    let table = app_lookup_table(&ctx.app_id, recent_slot);
+    let sig = ctx.send(instructions::create_app_lut(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        ctx.cluster_offset,
+        ctx.app_id,
+        recent_slot,
+    ))?;
+    println!("lookup table {table} created ({sig})");
+
+    let addresses: Vec<Pubkey> = circuits::ALL
+        .into_iter()
+        .map(comp_def_account)
+        .chain([
+            sign_pda(),
+            global_stats_pda(),
+            app_stats_pda(&ctx.app_id),
+            credit_pda(&ctx.app_id),
+            config_pda(),
+            treasury_pda(),
+        ])
+        .collect();
+    for chunk in addresses.chunks(LUT_EXTEND_CHUNK) {
+        let sig = ctx.send(instructions::extend_app_lut(
+            ctx.payer(),
+            ctx.signer.pubkey(),
+            &ctx.app_id,
+            table,
+            chunk.to_vec(),
+        ))?;
+        println!("added {} addresses ({sig})", chunk.len());
+    }
+    Ok(())
+}
+
+fn refund(ctx: &Ctx, session_id: &str) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let session = rpc::fetch_session(&ctx.rpc, &session_id)?;
+    if session.refundable_fee == 0 {
+        bail!("session has no failed computation fee to refund");
+    }
+    let refund = rpc::fetch_config(&ctx.rpc)?.refund_share(session.refundable_fee);
+    let sig = ctx.send(instructions::claim_refund(&session_id, &session.app_id))?;
+    println!("refunded {refund} of {} lamports to the app's credit ({sig})", session.refundable_fee);
+    Ok(())
+}
+
+fn notify(ctx: &Ctx, session_id: &str, target: &str) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let notification_target = hash(target.as_bytes()).to_bytes();
+    let sig = ctx.send(instructions::register_notification(
+        ctx.signer.pubkey(),
+        &session_id,
+        notification_target,
+    ))?;
+    println!("notification target {} registered ({sig})", hex::encode(notification_target));
+    Ok(())
+}
+
+fn connect(ctx: &Ctx, session_id: &str) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let sig = ctx.send(instructions::consent_connection(ctx.signer.pubkey(), &session_id))?;
+    println!("consented ({sig})");
+
+    let session = rpc::fetch_session(&ctx.rpc, &session_id)?;
+    if session.connection_consent != CONSENT_ALICE | CONSENT_BOB {
+        println!("waiting for the other party's consent");
+        return Ok(());
+    }
+    let sig = ctx.send(instructions::attest_connection(ctx.payer(), &session_id, session.alice, session.bob))?;
+    println!("connection attested at {} ({sig})", connection_pda(&session.alice, &session.bob));
+    Ok(())
+}
+
+fn activity(ctx: &Ctx, open: bool, close: bool) -> Result<()> {
+    let wallet = ctx.signer.pubkey();
+    if open {
+        let sig = ctx.send(instructions::open_activity_log(ctx.payer(), wallet))?;
+        println!("activity log opened at {} ({sig})", activity_log_pda(&wallet));
+        return Ok(());
+    }
+    if close {
+        let sig = ctx.send(instructions::close_activity_log(wallet))?;
+        println!("activity log closed ({sig})");
+        return Ok(());
+    }
+
+    let log = rpc::fetch_activity_log(&ctx.rpc, &wallet)?;
+    if log.len == 0 {
+        println!("no activity recorded");
+    }
+    for (session_id, kind, slot) in log.entries() {
+        let kind = match kind {
+            k if k == ActivityKind::Created as u8 => "created",
+            k if k == ActivityKind::Submitted as u8 => "submitted",
+            k if k == ActivityKind::Matched as u8 => "matched",
+            k if k == ActivityKind::Rejected as u8 => "rejected",
+            k if k == ActivityKind::Failed as u8 => "failed",
+            k if k == ActivityKind::Closed as u8 => "closed",
+            _ => "unknown",
+        };
+        println!("slot {slot:>12}  {kind:<9}  {}", hex::encode(session_id));
+    }
+    Ok(())
+}
+
+fn deployment(ctx: &Ctx, sync: bool) -> Result<()> {
+    if sync {
+        let sig = ctx.send(instructions::sync_deployment(ctx.payer()))?;
+        println!("deployment registry synced ({sig})");
+    }
+    let registry = rpc::fetch_deployment(&ctx.rpc)?;
+    println!("program version:  {}", registry.program_version);
+    println!("session version:  {}", registry.session_version);
+    println!("mxe:              {}", registry.mxe);
+    println!("cluster:          {}", registry.cluster_offset);
+    println!("synced at slot:   {}", registry.updated_slot);
+    for (i, circuit) in circuits::ALL.iter().enumerate() {
+        let ready = if registry.comp_def_ready(i) { "ready" } else { "not initialized" };
+        println!("  {circuit:<26} v{:<3} {ready}", registry.circuit_versions[i]);
+    }
+
+    let mxe_key = rpc::fetch_mxe_public_key(&ctx.rpc)?;
+    match preflight::check_deployment(&ctx.rpc, ctx.cluster_offset, &mxe_key, &[]) {
+        Ok(_) => println!("compatible with this client"),
+        Err(e) => println!("incompatible: {e}"),
+    }
+    Ok(())
+}
+
+fn hook(ctx: &Ctx, session_id: &str, program: Pubkey, accounts: &[Pubkey], writable: &[Pubkey]) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let accounts: Vec<HookAccount> = accounts
+        .iter()
+        .map(|&pubkey| HookAccount { pubkey, is_writable: false })
+        .chain(writable.iter().map(|&pubkey| HookAccount { pubkey, is_writable: true }))
+        .collect();
+    if accounts.len() > MAX_HOOK_ACCOUNTS {
+        bail!("a hook takes at most {MAX_HOOK_ACCOUNTS} accounts, {} given", accounts.len());
+    }
+    let sig = ctx.send(instructions::register_app_hook(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        &session_id,
+        program,
+        accounts,
+    ))?;
+    println!("app hook {program} registered ({sig})");
+    Ok(())
+}
+
+fn close(ctx: &Ctx, session_id: &str) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let rent_payer = rpc::fetch_session(&ctx.rpc, &session_id)?.rent_recipient();
+    let sig = ctx.send(instructions::close_session(ctx.signer.pubkey(), rent_payer, &session_id))?;
+    println!("session closed, rent returned to {rent_payer} ({sig})");
+    Ok(())
+}
+
+fn sweep(ctx: &Ctx, session_ids: &[String]) -> Result<()> {
+    let sessions = session_ids
+        .iter()
+        .map(|id| {
+            let id = parse_session_id(id)?;
+            Ok((id, rpc::fetch_session(&ctx.rpc, &id)?.rent_recipient()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    for chunk in sessions.chunks(MAX_SWEEP) {
+        let sig = ctx.send(instructions::sweep_sessions(ctx.signer.pubkey(), chunk))?;
+        println!("swept {} sessions ({sig})", chunk.len());
+    }
+    Ok(())
+}
+
+fn unix_now() -> Result<i64> {
+    Ok(i64::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())?)
+}
+
+fn submit_committed(ctx: &Ctx, session_id: &str, saved: &Path) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let encrypted = EncryptedContacts::from_bytes(&std::fs::read(saved)?)?;
+    send_alice_submission(ctx, &session_id, &encrypted)
+}
+
+fn run_match(ctx: &Ctx, session_id: &str, path: &Path, public_count: bool) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let domain = ctx.hash_domain(&session_id)?;
+    let contacts = read_contacts(path)?;
+    let hashed = ctx.session_list(&session_id, domain.0, domain.1, &contacts)?;
+    let cipher = ctx.cipher()?;
+    let encrypted = cipher.encrypt_contacts(&hashed);
+    let offset = random_computation_offset();
+    let session = rpc::fetch_session(&ctx.rpc, &session_id)?;
+    let circuit = instructions::match_circuit(session.capacity());
+
+    let sig = ctx.send_as_bob(
+        &session_id,
+        instructions::submit_and_match(
+            ctx.payer(),
+            ctx.signer.pubkey(),
+            session.alice,
+            ctx.cluster_offset,
+            offset,
+            &session_id,
+            &session.app_id,
+            &encrypted,
+            session.capacity(),
+            public_count,
+        ),
+    )?;
+    println!("queued {circuit} ({sig})");
+
+    let computation = arcium_accounts(ctx.cluster_offset, circuit, offset).computation_account;
+    let event: MatchComplete = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+    let result = cipher.decrypt_matches(&event.encrypted_matches, &event.encrypted_count, &event.nonce);
+    print_matches(&result, domain, Some((&hashed, &contacts)));
+    Ok(())
+}
+
+/// Encrypt every chunk up front and commit their root, so no chunk can
+/// be swapped after earlier ones have matched, then match them in order.
+fn set_match(ctx: &Ctx, session_id: &str, path: &Path) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let domain = ctx.hash_domain(&session_id)?;
+    let contacts = import::read_contacts(path)?;
+    let chunks = hash_contact_set(domain.0, domain.1, &contacts)?;
+    let cipher = ctx.cipher()?;
+    let encrypted: Vec<EncryptedContacts> = chunks.iter().map(|chunk| cipher.encrypt_contacts(chunk)).collect();
+
+    let session = rpc::fetch_session(&ctx.rpc, &session_id)?;
+    let commit = instructions::commit_set(ctx.signer.pubkey(), session.alice, &session_id, &encrypted);
+    let sig = ctx.send_as_bob(&session_id, commit)?;
+    println!("committed {} contact(s) in {} chunk(s) ({sig})", contacts.len(), encrypted.len());
+
+    let app_id = session.app_id;
+    let mut matched = Vec::new();
+    for index in 0..encrypted.len() {
+        let offset = random_computation_offset();
+        let sig = ctx.send(instructions::submit_set_chunk(
+            ctx.payer(),
+            ctx.signer.pubkey(),
+            ctx.cluster_offset,
+            offset,
+            &session_id,
+            &app_id,
+            &encrypted,
+            index,
+        ))?;
+        println!("queued chunk {index} ({sig})");
+
+        let computation =
+            arcium_accounts(ctx.cluster_offset, circuits::MATCH_SET_CHUNK, offset).computation_account;
+        let event: SetChunkMatched = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+        let result = cipher.decrypt_matches(&event.encrypted_matches, &event.encrypted_count, &event.nonce);
+        matched.extend(result.hashes(&chunks[index].hashes));
+    }
+
+    println!("{} mutual contact(s)", matched.len());
+    for contact in resolve_matches(domain.0, domain.1, &contacts, &matched) {
+        println!("  {contact}");
+    }
+    Ok(())
+}
+
+/// Reveal `page` of the signer's result, or stream every window in turn
+/// until the callback reports the result complete.
+fn reveal(ctx: &Ctx, session_id: &str, path: Option<&Path>, page: Option<u8>) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let domain = ctx.hash_domain(&session_id)?;
+    let contacts = path.map(import::read_contacts).transpose()?;
+    let is_bob = rpc::fetch_session(&ctx.rpc, &session_id)?.bob == ctx.signer.pubkey();
+
+    let pages = match page {
+        Some(page) => page..page + 1,
+        None => 0..RESULT_PAGES,
+    };
+    let mut result = DecryptedMatches {
+        slots: Vec::new(),
+        match_count: 0,
+    };
+    for page in pages {
+        let (window, complete) = reveal_window(ctx, &session_id, is_bob, page)?;
+        result.slots.extend(window.slots);
+        result.match_count = window.match_count;
+        if complete {
+            break;
+        }
+    }
+    // Rebuilt exactly as submitted: decoys are derived, not random
+    let submitted = contacts
+        .as_deref()
+        .map(|contacts| ctx.session_list(&session_id, domain.0, domain.1, contacts))
+        .transpose()?;
+    print_matches(&result, domain, submitted.as_ref().zip(contacts.as_deref()));
+    Ok(())
+}
+
+/// Queue the reveal of one result window and decrypt it; also returns
+/// whether that window completed the signer's result.
+fn reveal_window(ctx: &Ctx, session_id: &[u8; 32], is_bob: bool, page: u8) -> Result<(DecryptedMatches, bool)> {
+    let cipher = ctx.cipher()?;
+    let offset = random_computation_offset();
+    let nonce = u128::from_le_bytes(random_nonce());
+
+    let result = if is_bob {
+        let sig = ctx.send(instructions::reveal_bob_matches(
+            ctx.payer(),
+            ctx.signer.pubkey(),
+            ctx.cluster_offset,
+            offset,
+            session_id,
+            &ctx.app_of(session_id)?,
+            cipher.public_key(),
+            nonce,
+            page,
+        ))?;
+        println!("queued reveal_bob_matches ({sig})");
+
+        let computation = arcium_accounts(ctx.cluster_offset, circuits::REVEAL_BOB, offset).computation_account;
+        let event: BobRevealed = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+        let matches = cipher.decrypt_matches(&event.encrypted_matches, &event.encrypted_count, &event.nonce);
+        (matches, event.complete)
+    } else {
+        let sig = ctx.send(instructions::reveal_alice_matches(
+            ctx.payer(),
+            ctx.signer.pubkey(),
+            ctx.cluster_offset,
+            offset,
+            session_id,
+            &ctx.app_of(session_id)?,
+            cipher.public_key(),
+            nonce,
+            page,
+        ))?;
+        println!("queued reveal_alice_matches ({sig})");
+
+        let computation = arcium_accounts(ctx.cluster_offset, circuits::REVEAL_ALICE, offset).computation_account;
+        let event: AliceRevealed = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+        let matches = cipher.decrypt_matches(&event.encrypted_matches, &event.encrypted_count, &event.nonce);
+        (matches, event.complete)
+    };
+    println!("page {page} revealed");
+    Ok(result)
+}
+
+fn reveal_both(ctx: &Ctx, session_id: &str, bob: &Keypair, page: u8) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let domain = ctx.hash_domain(&session_id)?;
+    let alice_cipher = ctx.cipher()?;
+    let bob_cipher = ctx.cipher()?;
+    let offset = random_computation_offset();
+
+    let ix = instructions::reveal_both(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        bob.pubkey(),
+        ctx.cluster_offset,
+        offset,
+        &session_id,
+        &ctx.app_of(&session_id)?,
+        alice_cipher.public_key(),
+        u128::from_le_bytes(random_nonce()),
+        bob_cipher.public_key(),
+        u128::from_le_bytes(random_nonce()),
+        page,
+    );
+    let payer = ctx.fee_payer.as_ref().unwrap_or(&ctx.signer);
+    let sig = rpc::send_with_requeue(&ctx.rpc, payer, &[&ctx.signer, bob], &[ix], ctx.requeue)?;
+    println!("queued reveal_both ({sig})");
+
+    // Both events come from the same callback, so the first one landing
+    // means the second is already on chain.
+    let computation = arcium_accounts(ctx.cluster_offset, circuits::REVEAL_BOTH, offset).computation_account;
+    let alice_event: AliceRevealed = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+    let bob_event: BobRevealed = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+    println!("alice:");
+    print_matches(
+        &alice_cipher.decrypt_matches(&alice_event.encrypted_matches, &alice_event.encrypted_count, &alice_event.nonce),
+        domain,
+        None,
+    );
+    println!("bob:");
+    print_matches(
+        &bob_cipher.decrypt_matches(&bob_event.encrypted_matches, &bob_event.encrypted_count, &bob_event.nonce),
+        domain,
+        None,
+    );
+    Ok(())
+}
+
+fn run_score(ctx: &Ctx, session_id: &str, path: &Path) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let contacts = read_contacts(path)?;
+    let (scheme, namespace) = ctx.hash_domain(&session_id)?;
+    let hashed = ctx.session_list(&session_id, scheme, namespace, &contacts)?;
+    let cipher = ctx.cipher()?;
+    let encrypted = cipher.encrypt_contacts(&hashed);
+    let offset = random_computation_offset();
+    let session = rpc::fetch_session(&ctx.rpc, &session_id)?;
+
+    let sig = ctx.send_as_bob(
+        &session_id,
+        instructions::submit_and_score(
+            ctx.payer(),
+            ctx.signer.pubkey(),
+            session.alice,
+            ctx.cluster_offset,
+            offset,
+            &session_id,
+            &session.app_id,
+            &encrypted,
+        ),
+    )?;
+    println!("queued submit_and_score ({sig})");
+
+    let computation =
+        arcium_accounts(ctx.cluster_offset, circuits::SUBMIT_AND_SCORE, offset).computation_account;
+    let event: ScoreComplete = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+    print_score(&cipher.decrypt_score(&event.encrypted_score, &event.nonce));
+    Ok(())
+}
+
+fn reveal_score(ctx: &Ctx, session_id: &str) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let cipher = ctx.cipher()?;
+    let offset = random_computation_offset();
+
+    let sig = ctx.send(instructions::reveal_alice_score(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        ctx.cluster_offset,
+        offset,
+        &session_id,
+        &ctx.app_of(&session_id)?,
+        cipher.public_key(),
+        u128::from_le_bytes(random_nonce()),
+    ))?;
+    println!("queued reveal_alice_score ({sig})");
+
+    let computation =
+        arcium_accounts(ctx.cluster_offset, circuits::REVEAL_ALICE_SCORE, offset).computation_account;
+    let event: AliceScoreRevealed = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+    print_score(&cipher.decrypt_score(&event.encrypted_score, &event.nonce));
+    Ok(())
+}
+
+fn fof_join(ctx: &Ctx, session_id: &str, path: &Path) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let contacts = read_contacts(path)?;
+    let (scheme, namespace) = ctx.hash_domain(&session_id)?;
+    let hashed = ctx.session_list(&session_id, scheme, namespace, &contacts)?;
+    let encrypted = ctx.cipher()?.encrypt_contacts(&hashed);
+    let offset = random_computation_offset();
+    let session = rpc::fetch_session(&ctx.rpc, &session_id)?;
+
+    let sig = ctx.send_as_bob(
+        &session_id,
+        instructions::submit_contacts_bob(
+            ctx.payer(),
+            ctx.signer.pubkey(),
+            session.alice,
+            ctx.cluster_offset,
+            offset,
+            &session_id,
+            &session.app_id,
+            &encrypted,
+        ),
+    )?;
+    println!("submitted {} contact(s) ({sig})", hashed.count);
+
+    let computation = arcium_accounts(ctx.cluster_offset, circuits::SUBMIT_BOB, offset).computation_account;
+    rpc::await_event::<BobSubmitted>(&ctx.rpc, &computation, ctx.timeout)?;
+    println!("submission stored; waiting for the connector");
+    Ok(())
+}
+
+fn fof_connect(ctx: &Ctx, session_id: &str, path: &Path) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let contacts = read_contacts(path)?;
+    let (scheme, namespace) = ctx.hash_domain(&session_id)?;
+    let hashed = ctx.session_list(&session_id, scheme, namespace, &contacts)?;
+    let encrypted = ctx.cipher()?.encrypt_contacts(&hashed);
+    let offset = random_computation_offset();
+
+    let sig = ctx.send(instructions::submit_connector(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        ctx.cluster_offset,
+        offset,
+        &session_id,
+        &ctx.app_of(&session_id)?,
+        &encrypted,
+    ))?;
+    println!("queued submit_connector ({sig})");
+
+    let computation =
+        arcium_accounts(ctx.cluster_offset, circuits::SUBMIT_CONNECTOR, offset).computation_account;
+    rpc::await_event::<SecondDegreeComputed>(&ctx.rpc, &computation, ctx.timeout)?;
+    println!("count computed; Alice and Bob can now run fof-reveal");
+    Ok(())
+}
+
+fn fof_reveal(ctx: &Ctx, session_id: &str) -> Result<()> {
+    let count = reveal_second_degree(ctx, session_id)?;
+    println!("{count} connection(s) shared through the connector");
+    Ok(())
+}
+
+/// Reveal the count a friends-of-friends or mutual-follow session stores.
+fn reveal_second_degree(ctx: &Ctx, session_id: &str) -> Result<u32> {
+    let session_id = parse_session_id(session_id)?;
+    let cipher = ctx.cipher()?;
+    let offset = random_computation_offset();
+
+    let sig = ctx.send(instructions::reveal_second_degree(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        ctx.cluster_offset,
+        offset,
+        &session_id,
+        &ctx.app_of(&session_id)?,
+        cipher.public_key(),
+        u128::from_le_bytes(random_nonce()),
+    ))?;
+    println!("queued reveal_second_degree ({sig})");
+
+    let computation =
+        arcium_accounts(ctx.cluster_offset, circuits::REVEAL_SECOND_DEGREE, offset).computation_account;
+    let event: SecondDegreeRevealed = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+    Ok(cipher.decrypt_count(&event.encrypted_count, &event.nonce))
+}
+
+/// Read a follow file: one base58 pubkey per line, blank lines and
+/// `#` comments skipped.
+fn read_follows(path: &Path) -> Result<Vec<[u8; 32]>> {
+    let mut follows = Vec::new();
+    for line in std::fs::read_to_string(path)?.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let target = Pubkey::from_str(line).map_err(|e| anyhow::anyhow!("{}: {line}: {e}", path.display()))?;
+        if !follows.contains(&target.to_bytes()) {
+            follows.push(target.to_bytes());
+        }
+    }
+    if follows.len() > MAX_CONTACTS {
+        eprintln!(
+            "warning: {} follows in {}, only the first {MAX_CONTACTS} fit in a session",
+            follows.len(),
+            path.display(),
+        );
+        follows.truncate(MAX_CONTACTS);
+    }
+    Ok(follows)
+}
+
+fn follow_submit(ctx: &Ctx, session_id: &str, path: &Path) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let (scheme, _) = ctx.hash_domain(&session_id)?;
+    let folded = fold_follow_list(scheme, &read_follows(path)?)?;
+    let encrypted = ctx.cipher()?.encrypt_contacts(&folded);
+    send_alice_submission(ctx, &session_id, &encrypted)
+}
+
+fn follow_check(ctx: &Ctx, session_id: &str, path: &Path) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let (scheme, _) = ctx.hash_domain(&session_id)?;
+    let folded = fold_follow_list(scheme, &read_follows(path)?)?;
+    let cipher = ctx.cipher()?;
+    let encrypted = cipher.encrypt_contacts(&folded);
+    let offset = random_computation_offset();
+    let session = rpc::fetch_session(&ctx.rpc, &session_id)?;
+
+    let sig = ctx.send_as_bob(
+        &session_id,
+        instructions::submit_and_check_follow(
+            ctx.payer(),
+            ctx.signer.pubkey(),
+            session.alice,
+            ctx.cluster_offset,
+            offset,
+            &session_id,
+            &session.app_id,
+            &encrypted,
+        ),
+    )?;
+    println!("queued submit_and_check_follow ({sig})");
+
+    let computation = arcium_accounts(ctx.cluster_offset, circuits::CHECK_FOLLOW, offset).computation_account;
+    let event: FollowChecked = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+    print_follow(cipher.decrypt_mutual(&event.encrypted_mutual, &event.nonce));
+    Ok(())
+}
+
+fn follow_reveal(ctx: &Ctx, session_id: &str) -> Result<()> {
+    print_follow(reveal_second_degree(ctx, session_id)? != 0);
+    Ok(())
+}
+
+fn print_follow(mutual: bool) {
+    if mutual {
+        println!("mutual follow: you follow each other");
+    } else {
+        println!("no mutual follow");
+    }
+}
+
+fn delta(ctx: &Ctx, session_id: &str, add: Option<&Path>, remove: Option<&Path>) -> Result<()> {
+    let session_id = parse_session_id(session_id)?;
+    let added = add.map(import::read_contacts).transpose()?.unwrap_or_default();
+    let removed = remove.map(import::read_contacts).transpose()?.unwrap_or_default();
+    if added.len() > MAX_DELTA || removed.len() > MAX_DELTA {
+        bail!("a delta adds and removes at most {MAX_DELTA} contacts each");
+    }
+
+    let (scheme, namespace) = ctx.hash_domain(&session_id)?;
+    let hashed = hash_delta(scheme, namespace, &added, &removed)?;
+    let cipher = ctx.cipher()?;
+    let encrypted = cipher.encrypt_delta(&hashed);
+    let offset = random_computation_offset();
+
+    let sig = ctx.send(instructions::push_delta(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        ctx.cluster_offset,
+        offset,
+        &session_id,
+        &ctx.app_of(&session_id)?,
+        &encrypted,
+    ))?;
+    println!("queued push_delta ({sig})");
+
+    let computation = arcium_accounts(ctx.cluster_offset, circuits::PUSH_DELTA, offset).computation_account;
+    let event: DeltaMatched = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+    let result = cipher.decrypt_matches(&event.encrypted_matches, &event.encrypted_count, &event.nonce);
+    println!("round {}: {} new mutual contact(s)", event.round, result.match_count);
+    // Slots name the stored list as the delta left it, which only the
+    // MXE holds, so they are printed as is
+    for slot in &result.slots {
+        println!("  slot {slot}");
+    }
+    Ok(())
+}
+
+fn status(ctx: &Ctx, session_id: &str) -> Result<()> {
+    let id = parse_session_id(session_id)?;
+    let session = rpc::fetch_session(&ctx.rpc, &id)?;
+    let status = match session.status {
+        0 => "awaiting_alice",
+        1 => "awaiting_bob",
+        2 => "computing",
+        3 => "matched",
+        4 => "awaiting_connector",
+        5 if session.purged_slot == 0 => "purging",
+        5 => "purged",
+        6 => "failed",
+        other => bail!("unknown status {other}"),
+    };
+    println!("session: {}", hex::encode(session.session_id));
+    println!("status:  {status}");
+    println!("alice:   {}", session.alice);
+    println!("bob:     {}", session.bob);
+    println!(
+        "ids:     {} ({:?})",
+        Namespace::try_from(session.namespace)?.tag(),
+        HashScheme::try_from(session.hash_scheme)?,
+    );
+    if session.commitment != [0u8; 32] {
+        println!("commit:  {}", hex::encode(session.commitment));
+    }
+    if session.rejected_party != 0 {
+        println!("rejected: party {}'s last list was malformed", session.rejected_party);
+    }
+    if session.app_id != NO_APP_ID {
+        println!("app:     {}", hex::encode(session.app_id));
+    }
+    if session.notification_target != [0u8; 32] {
+        println!("notify:  {}", hex::encode(session.notification_target));
+    }
+    if session.memo != NO_MEMO {
+        println!("memo:    {}", hex::encode(session.memo));
+    }
+    if session.refundable_fee != 0 {
+        println!("refund:  {} lamports failed computation fee (`refund` claims it)", session.refundable_fee);
+    }
+    if session.join_deadline != 0 {
+        let state = if session.join_expired(unix_now()?) { "passed" } else { "open" };
+        println!("deadline: {} ({state})", session.join_deadline);
+    }
+    for (stage, name) in ["init", "alice", "match", "reveal"].into_iter().enumerate() {
+        match (session.stage_offsets[stage], session.finalized_slots[stage]) {
+            (0, _) => {}
+            (offset, 0) => println!("stage:   {name} computation {offset} pending"),
+            (offset, slot) => println!("stage:   {name} computation {offset} finalized at slot {slot}"),
+        }
+    }
+    if session.public_count == PublicCount::BothOptedIn as u8 && session.status == 3 {
+        println!("public:  {} mutual contact(s)", session.match_count);
+    } else if session.public_count == PublicCount::AliceOptedIn as u8 {
+        println!("public:  Alice opted in to publishing the match count");
+    }
+    if session.hide_counts != 0 {
+        println!("sizes:   hidden from the counterparty");
+    }
+    if session.reveal_after_slot != 0 {
+        let state = if session.reveal_locked(ctx.rpc.get_slot()?) { "locked" } else { "open" };
+        println!("reveal:  after slot {} ({state})", session.reveal_after_slot);
+    }
+    if session.mode == SessionMode::Subscription as u8 {
+        println!("mode:    subscription (round {})", session.round);
+    } else if session.mode == SessionMode::Score as u8 {
+        println!("mode:    score");
+    } else if session.mode == SessionMode::FriendsOfFriends as u8 {
+        println!("mode:    friends-of-friends");
+    } else if session.mode == SessionMode::MutualFollow as u8 {
+        println!("mode:    mutual-follow");
+    } else if session.mode == SessionMode::CommittedSet as u8 && session.set_chunk_count != 0 {
+        println!(
+            "mode:    committed-set ({} of {} chunk(s) matched)",
+            session.set_chunks_done.count_ones(),
+            session.set_chunk_count
+        );
+    } else if session.mode == SessionMode::CommittedSet as u8 {
+        println!("mode:    committed-set (no set committed yet)");
+    }
+    Ok(())
+}
+
+fn create_pool(ctx: &Ctx, path: &Path, scheme: HashScheme, namespace: Namespace) -> Result<()> {
+    let contacts = read_contacts(path)?;
+    let hashed = hash_contact_list(scheme, namespace, &contacts)?;
+    let cipher = ctx.cipher()?;
+    let encrypted = cipher.encrypt_contacts(&hashed);
+    let pool_id = random_session_id();
+    let offset = random_computation_offset();
+
+    let sig = ctx.send(instructions::create_pool(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        ctx.cluster_offset,
+        offset,
+        pool_id,
+        namespace as u8,
+        &encrypted,
+    ))?;
+    println!("queued create_pool with {} contact(s) ({sig})", hashed.count);
+
+    let computation = arcium_accounts(ctx.cluster_offset, circuits::CREATE_POOL, offset).computation_account;
+    rpc::await_event::<PoolOpened>(&ctx.rpc, &computation, ctx.timeout)?;
+    println!("pool id: {}", hex::encode(pool_id));
+    Ok(())
+}
+
+fn match_pool(ctx: &Ctx, pool_id: &str, path: &Path) -> Result<()> {
+    // Pool ids share the session id format
+    let pool_id = parse_session_id(pool_id)?;
+    let pool = rpc::fetch_pool(&ctx.rpc, &pool_id)?;
+    let domain = (HashScheme::try_from(pool.hash_scheme)?, Namespace::try_from(pool.namespace)?);
+    let contacts = read_contacts(path)?;
+    let hashed = hash_contact_list(domain.0, domain.1, &contacts)?;
+    let cipher = ctx.cipher()?;
+    let encrypted = cipher.encrypt_contacts(&hashed);
+    let offset = random_computation_offset();
+
+    let sig = ctx.send(instructions::match_against_pool(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        ctx.cluster_offset,
+        offset,
+        &pool_id,
+        &encrypted,
+    ))?;
+    println!("queued match_against_pool ({sig})");
+
+    let computation = arcium_accounts(ctx.cluster_offset, circuits::MATCH_POOL, offset).computation_account;
+    let event: PoolMatched = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+    let result = cipher.decrypt_matches(&event.encrypted_matches, &event.encrypted_count, &event.nonce);
+    print_matches(&result, domain, Some((&hashed, &contacts)));
+    Ok(())
+}
+
+/// Hash the contacts in `path` into database chunks.
+fn database_chunks(path: &Path, scheme: HashScheme, namespace: Namespace) -> Result<Vec<HashedContacts>> {
+    let contacts = read_contacts(path)?;
+    let chunks = hash_contact_set(scheme, namespace, &contacts)?;
+    if chunks.len() > DATABASE_CHUNKS {
+        bail!(
+            "{} contacts need {} chunks, a database holds at most {DATABASE_CHUNKS}",
+            contacts.len(),
+            chunks.len()
+        );
+    }
+    Ok(chunks)
+}
+
+/// Load `chunks` into epoch `epoch` of a database and seal it.
+fn load_and_seal(ctx: &Ctx, database_id: &[u8; 32], epoch: u32, chunks: &[HashedContacts]) -> Result<()> {
+    let cipher = ctx.cipher()?;
+    // One load is in flight at a time, so each waits for the last
+    for (index, chunk) in chunks.iter().enumerate() {
+        let offset = random_computation_offset();
+        let sig = ctx.send(instructions::load_database_chunk(
+            ctx.payer(),
+            ctx.signer.pubkey(),
+            ctx.cluster_offset,
+            offset,
+            database_id,
+            epoch,
+            &cipher.encrypt_contacts(chunk),
+        ))?;
+        println!("queued chunk {index} with {} contact(s) ({sig})", chunk.count);
+
+        let computation =
+            arcium_accounts(ctx.cluster_offset, circuits::LOAD_DATABASE_CHUNK, offset).computation_account;
+        let event: DatabaseChunkLoaded = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+        if !event.accepted {
+            bail!("chunk {index} was rejected");
+        }
+    }
+
+    let sig = ctx.send(instructions::seal_database(ctx.signer.pubkey(), database_id, epoch))?;
+    println!("sealed epoch {epoch} with {} chunk(s) ({sig})", chunks.len());
+    Ok(())
+}
+
+fn create_database(ctx: &Ctx, path: &Path, scheme: HashScheme, namespace: Namespace) -> Result<()> {
+    let chunks = database_chunks(path, scheme, namespace)?;
+    let database_id = random_session_id();
+
+    let sig = ctx.send(instructions::create_database(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        database_id,
+        namespace as u8,
+        scheme as u8,
+    ))?;
+    println!("database id: {} ({sig})", hex::encode(database_id));
+    load_and_seal(ctx, &database_id, 0, &chunks)
+}
+
+fn refresh_db(ctx: &Ctx, database_id: &str, path: &Path) -> Result<()> {
+    let database_id = parse_session_id(database_id)?;
+    let live = rpc::fetch_live_database(&ctx.rpc, &database_id)?;
+    let chunks = database_chunks(
+        path,
+        HashScheme::try_from(live.hash_scheme)?,
+        Namespace::try_from(live.namespace)?,
+    )?;
+    let epoch = live.epoch + 1;
+
+    let sig = ctx.send(instructions::stage_database_epoch(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        database_id,
+        epoch,
+    ))?;
+    println!("staged epoch {epoch} ({sig})");
+    load_and_seal(ctx, &database_id, epoch, &chunks)?;
+
+    let sig = ctx.send(instructions::rotate_database_epoch(ctx.signer.pubkey(), &database_id, epoch))?;
+    println!("epoch {epoch} is live, epoch {} retired ({sig})", live.epoch);
+    Ok(())
+}
+
+fn query_db(ctx: &Ctx, database_id: &str, path: &Path) -> Result<()> {
+    // Database ids share the session id format
+    let database_id = parse_session_id(database_id)?;
+    let database = rpc::fetch_live_database(&ctx.rpc, &database_id)?;
+    let domain = (
+        HashScheme::try_from(database.hash_scheme)?,
+        Namespace::try_from(database.namespace)?,
+    );
+    let contacts = read_contacts(path)?;
+    let hashed = hash_contact_list(domain.0, domain.1, &contacts)?;
+    let cipher = ctx.cipher()?;
+    let encrypted = cipher.encrypt_contacts(&hashed);
+    let offset = random_computation_offset();
+
+    let sig = ctx.send(instructions::query_psi(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        ctx.cluster_offset,
+        offset,
+        &database_id,
+        database.epoch,
+        &encrypted,
+    ))?;
+    println!("queued query_psi against epoch {} ({sig})", database.epoch);
+
+    let computation = arcium_accounts(ctx.cluster_offset, circuits::QUERY_PSI, offset).computation_account;
+    let event: DatabaseQueried = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+    let result = cipher.decrypt_matches(&event.encrypted_matches, &event.encrypted_count, &event.nonce);
+    print_matches(&result, domain, Some((&hashed, &contacts)));
+    Ok(())
+}
+
+fn check_db(ctx: &Ctx, database_id: &str, identifier: &str) -> Result<()> {
+    // Database ids share the session id format
+    let database_id = parse_session_id(database_id)?;
+    let database = rpc::fetch_live_database(&ctx.rpc, &database_id)?;
+    let scheme = HashScheme::try_from(database.hash_scheme)?;
+    let namespace = Namespace::try_from(database.namespace)?;
+    let hash = hash_contact(scheme, namespace, identifier);
+    let cipher = ctx.cipher()?;
+    let query = cipher.encrypt_membership_query(scheme, namespace, hash);
+    let offset = random_computation_offset();
+
+    let sig = ctx.send(instructions::check_membership(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        ctx.cluster_offset,
+        offset,
+        &database_id,
+        database.epoch,
+        &ctx.app_id,
+        &query,
+    ))?;
+    println!("queued check_membership against epoch {} ({sig})", database.epoch);
+
+    let computation =
+        arcium_accounts(ctx.cluster_offset, circuits::CHECK_MEMBERSHIP, offset).computation_account;
+    let event: MembershipChecked = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+    if cipher.decrypt_member(&event.encrypted_member, &event.nonce) {
+        println!("{identifier} is in the database");
+    } else {
+        println!("{identifier} is not in the database");
+    }
+    Ok(())
+}
+
+fn check_db_batch(ctx: &Ctx, database_id: &str, path: &Path) -> Result<()> {
+    // Database ids share the session id format
+    let database_id = parse_session_id(database_id)?;
+    let database = rpc::fetch_live_database(&ctx.rpc, &database_id)?;
+    let scheme = HashScheme::try_from(database.hash_scheme)?;
+    let namespace = Namespace::try_from(database.namespace)?;
+    let identifiers = read_contacts(path)?;
+    let cipher = ctx.cipher()?;
+
+    let mut found = 0;
+    for batch in identifiers.chunks(MEMBERSHIP_BATCH_SLOTS) {
+        let hashes: Vec<u128> = batch.iter().map(|id| hash_contact(scheme, namespace, id)).collect();
+        let encrypted = cipher.encrypt_membership_batch(scheme, namespace, &hashes)?;
+        let offset = random_computation_offset();
+
+        let sig = ctx.send(instructions::check_membership_batch(
+            ctx.payer(),
+            ctx.signer.pubkey(),
+            ctx.cluster_offset,
+            offset,
+            &database_id,
+            database.epoch,
+            &ctx.app_id,
+            &encrypted,
+        ))?;
+        println!("queued check_membership_batch of {} ({sig})", batch.len());
+
+        let computation =
+            arcium_accounts(ctx.cluster_offset, circuits::CHECK_MEMBERSHIP_BATCH, offset).computation_account;
+        let event: MembershipBatchChecked = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+        let mask = cipher.decrypt_membership_mask(&event.encrypted_mask, &event.nonce);
+        for (j, identifier) in batch.iter().enumerate() {
+            if mask & (1 << j) != 0 {
+                println!("  {identifier}");
+                found += 1;
+            }
+        }
+    }
+    println!("{found} of {} identifier(s) are in the database", identifiers.len());
+    Ok(())
+}
+
+fn create_blocklist(ctx: &Ctx, path: &Path, scheme: HashScheme, namespace: Namespace) -> Result<()> {
+    let contacts = read_contacts(path)?;
+    let hashed = hash_contact_list(scheme, namespace, &contacts)?;
+    let cipher = ctx.cipher()?;
+    let encrypted = cipher.encrypt_contacts(&hashed);
+    let blocklist_id = random_session_id();
+    let offset = random_computation_offset();
+
+    let sig = ctx.send(instructions::create_blocklist(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        ctx.cluster_offset,
+        offset,
+        blocklist_id,
+        namespace as u8,
+        &encrypted,
+    ))?;
+    println!("queued create_blocklist with {} hash(es) ({sig})", hashed.count);
+
+    let computation =
+        arcium_accounts(ctx.cluster_offset, circuits::CREATE_BLOCKLIST, offset).computation_account;
+    rpc::await_event::<BlocklistOpened>(&ctx.rpc, &computation, ctx.timeout)?;
+    println!("blocklist id: {}", hex::encode(blocklist_id));
+    Ok(())
+}
+
+fn check_blocklist(ctx: &Ctx, blocklist_id: &str, path: &Path) -> Result<()> {
+    // Blocklist ids share the session id format
+    let blocklist_id = parse_session_id(blocklist_id)?;
+    let blocklist = rpc::fetch_blocklist(&ctx.rpc, &blocklist_id)?;
+    let contacts = read_contacts(path)?;
+    let hashed = hash_contact_list(
+        HashScheme::try_from(blocklist.hash_scheme)?,
+        Namespace::try_from(blocklist.namespace)?,
+        &contacts,
+    )?;
+    let cipher = ctx.cipher()?;
+    let encrypted = cipher.encrypt_contacts(&hashed);
+    let offset = random_computation_offset();
+
+    let sig = ctx.send(instructions::check_against_blocklist(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        ctx.cluster_offset,
+        offset,
+        &blocklist_id,
+        &encrypted,
+    ))?;
+    println!("queued check_against_blocklist ({sig})");
+
+    let computation =
+        arcium_accounts(ctx.cluster_offset, circuits::CHECK_BLOCKLIST, offset).computation_account;
+    let event: BlocklistChecked = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+    let result = cipher.decrypt_blocklist_result(&event.encrypted_result, &event.nonce);
+    if result.blocked {
+        println!("{} contact(s) are on the blocklist", result.blocked_count);
+    } else {
+        println!("no contacts are on the blocklist");
+    }
+    Ok(())
+}
+
+fn create_card_registry(ctx: &Ctx, scheme: HashScheme, namespace: Namespace) -> Result<()> {
+    let cipher = ctx.cipher()?;
+    let registry_id = random_session_id();
+    let offset = random_computation_offset();
+
+    let sig = ctx.send(instructions::create_card_registry(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        ctx.cluster_offset,
+        offset,
+        registry_id,
+        namespace as u8,
+        scheme as u8,
+        cipher.public_key(),
+        u128::from_le_bytes(random_nonce()),
+    ))?;
+    println!("queued create_card_registry ({sig})");
+
+    let computation =
+        arcium_accounts(ctx.cluster_offset, circuits::CREATE_CARD_REGISTRY, offset).computation_account;
+    rpc::await_event::<CardRegistryOpened>(&ctx.rpc, &computation, ctx.timeout)?;
+    println!("card registry id: {}", hex::encode(registry_id));
+    Ok(())
+}
+
+fn publish_card(ctx: &Ctx, registry_id: &str, identifier: &str, key: &str) -> Result<()> {
+    // Registry ids share the session id format
+    let registry_id = parse_session_id(registry_id)?;
+    let registry = rpc::fetch_card_registry(&ctx.rpc, &registry_id)?;
+    let namespace = Namespace::try_from(registry.namespace)?;
+    let hash = hash_contact(HashScheme::try_from(registry.hash_scheme)?, namespace, identifier);
+    let Ok(messaging_key) = <[u8; 32]>::try_from(hex::decode(key.trim())?) else {
+        bail!("--key must be 32 bytes of hex");
+    };
+    let cipher = ctx.cipher()?;
+    let card = cipher.encrypt_card(hash, namespace, &messaging_key);
+    let offset = random_computation_offset();
+
+    let sig = ctx.send(instructions::publish_contact_card(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        ctx.cluster_offset,
+        offset,
+        &registry_id,
+        &card,
+    ))?;
+    println!("queued publish_contact_card ({sig})");
+
+    let computation = arcium_accounts(ctx.cluster_offset, circuits::PUBLISH_CARD, offset).computation_account;
+    rpc::await_event::<ContactCardPublished>(&ctx.rpc, &computation, ctx.timeout)?;
+    // The MPC drops a card claimed by another wallet without saying so
+    println!("card processed; look yourself up with match-cards to confirm it is yours");
+    Ok(())
+}
+
+fn match_cards(ctx: &Ctx, registry_id: &str, path: &Path) -> Result<()> {
+    let registry_id = parse_session_id(registry_id)?;
+    let registry = rpc::fetch_card_registry(&ctx.rpc, &registry_id)?;
+    let (scheme, namespace) = (
+        HashScheme::try_from(registry.hash_scheme)?,
+        Namespace::try_from(registry.namespace)?,
+    );
+    let contacts = read_contacts(path)?;
+    let hashed = hash_contact_list(scheme, namespace, &contacts)?;
+    let cipher = ctx.cipher()?;
+    let encrypted = cipher.encrypt_contacts(&hashed);
+    let offset = random_computation_offset();
+
+    let sig = ctx.send(instructions::match_contact_cards(
+        ctx.payer(),
+        ctx.signer.pubkey(),
+        ctx.cluster_offset,
+        offset,
+        &registry_id,
+        &encrypted,
+    ))?;
+    println!("queued match_contact_cards ({sig})");
+
+    let computation = arcium_accounts(ctx.cluster_offset, circuits::MATCH_CARDS, offset).computation_account;
+    let event: CardsMatched = rpc::await_event(&ctx.rpc, &computation, ctx.timeout)?;
+    let result = cipher.decrypt_cards(
+        &event.encrypted_hashes,
+        &event.encrypted_keys_lo,
+        &event.encrypted_keys_hi,
+        &event.encrypted_count,
+        &event.nonce,
+    );
+    println!("{} contact card(s)", result.card_count);
+    for card in &result.cards {
+        let contact = contacts
+            .iter()
+            .find(|c| hash_contact(scheme, namespace, c) == card.hash)
+            .cloned()
+            .unwrap_or_else(|| format!("{:032x}", card.hash));
+        println!("  {contact}: {}", hex::encode(card.messaging_key));
+    }
+    Ok(())
+}
@@ -0,0 +1,265 @@
+// Account derivation for the program and the Arcium accounts its
+// queue_computation contexts expect. Mirror of getArciumAccounts()
+// in frontend/src/utils/arcium.ts.
+
+use arcium_anchor::prelude::{comp_def_offset, SIGN_PDA_SEED};
+use arcium_client::pda;
+use solana_sdk::{address_lookup_table::instruction::derive_lookup_table_address, pubkey::Pubkey};
+
+use private_contact_discovery::ID as PROGRAM_ID;
+
+/// Circuit names, as registered with `comp_def_offset`.
+pub mod circuits {
+    pub const INIT_SESSION: &str = "init_session";
+    pub const SUBMIT_ALICE: &str = "submit_contacts_alice";
+    pub const SUBMIT_AND_MATCH: &str = "submit_and_match";
+    pub const REVEAL_ALICE: &str = "reveal_alice_matches";
+    pub const PUSH_DELTA: &str = "push_delta";
+    pub const CREATE_POOL: &str = "create_pool";
+    pub const MATCH_POOL: &str = "match_against_pool";
+    pub const SUBMIT_AND_SCORE: &str = "submit_and_score";
+    pub const REVEAL_ALICE_SCORE: &str = "reveal_alice_score";
+    pub const SUBMIT_BOB: &str = "submit_contacts_bob";
+    pub const SUBMIT_CONNECTOR: &str = "submit_connector";
+    pub const REVEAL_SECOND_DEGREE: &str = "reveal_second_degree";
+    pub const CREATE_BLOCKLIST: &str = "create_blocklist";
+    pub const CHECK_BLOCKLIST: &str = "check_against_blocklist";
+    pub const CHECK_FOLLOW: &str = "submit_and_check_follow";
+    pub const REVEAL_BOB: &str = "reveal_bob_matches";
+    pub const PURGE_STATE: &str = "purge_session_state";
+    pub const CREATE_CARD_REGISTRY: &str = "create_card_registry";
+    pub const PUBLISH_CARD: &str = "publish_contact_card";
+    pub const MATCH_CARDS: &str = "match_contact_cards";
+    pub const MATCH_SET_CHUNK: &str = "match_set_chunk";
+    pub const LOAD_DATABASE_CHUNK: &str = "load_database_chunk";
+    pub const QUERY_PSI: &str = "query_psi";
+    pub const CHECK_MEMBERSHIP: &str = "check_membership";
+    pub const CHECK_MEMBERSHIP_BATCH: &str = "check_membership_batch";
+    pub const REVEAL_BOTH: &str = "reveal_both";
+    pub const SUBMIT_AND_MATCH_16: &str = "submit_and_match_16";
+    pub const SUBMIT_AND_MATCH_8: &str = "submit_and_match_8";
+    pub const SUBMIT_ALICE_16: &str = "submit_contacts_alice_16";
+    pub const SUBMIT_ALICE_8: &str = "submit_contacts_alice_8";
+    pub const INSTANT_MATCH: &str = "instant_match";
+    pub const INIT_AND_SUBMIT_ALICE: &str = "init_and_submit_alice";
+    pub const MIGRATE_STATE: &str = "migrate_session_state";
+
+    pub const ALL: [&str; 33] = [
+        INIT_SESSION,
+        SUBMIT_ALICE,
+        SUBMIT_AND_MATCH,
+        REVEAL_ALICE,
+        PUSH_DELTA,
+        CREATE_POOL,
+        MATCH_POOL,
+        SUBMIT_AND_SCORE,
+        REVEAL_ALICE_SCORE,
+        SUBMIT_BOB,
+        SUBMIT_CONNECTOR,
+        REVEAL_SECOND_DEGREE,
+        CREATE_BLOCKLIST,
+        CHECK_BLOCKLIST,
+        CHECK_FOLLOW,
+        REVEAL_BOB,
+        PURGE_STATE,
+        CREATE_CARD_REGISTRY,
+        PUBLISH_CARD,
+        MATCH_CARDS,
+        MATCH_SET_CHUNK,
+        LOAD_DATABASE_CHUNK,
+        QUERY_PSI,
+        CHECK_MEMBERSHIP,
+        CHECK_MEMBERSHIP_BATCH,
+        REVEAL_BOTH,
+        SUBMIT_AND_MATCH_16,
+        SUBMIT_AND_MATCH_8,
+        SUBMIT_ALICE_16,
+        SUBMIT_ALICE_8,
+        INSTANT_MATCH,
+        INIT_AND_SUBMIT_ALICE,
+        MIGRATE_STATE,
+    ];
+}
+
+/// The Arcium accounts shared by every queue_computation context.
+#[derive(Clone, Copy, Debug)]
+pub struct ArciumAccounts {
+    pub mxe_account: Pubkey,
+    pub mempool_account: Pubkey,
+    pub executing_pool: Pubkey,
+    pub computation_account: Pubkey,
+    pub comp_def_account: Pubkey,
+    pub cluster_account: Pubkey,
+    pub pool_account: Pubkey,
+    pub clock_account: Pubkey,
+}
+
+/// Derive every Arcium account for queueing `circuit` at `computation_offset`.
+pub fn arcium_accounts(cluster_offset: u32, circuit: &str, computation_offset: u64) -> ArciumAccounts {
+    ArciumAccounts {
+        mxe_account: pda::mxe_acc(&PROGRAM_ID),
+        mempool_account: pda::mempool_acc(cluster_offset),
+        executing_pool: pda::execpool_acc(cluster_offset),
+        computation_account: pda::computation_acc(cluster_offset, computation_offset),
+        comp_def_account: comp_def_account(circuit),
+        cluster_account: pda::cluster_acc(cluster_offset),
+        pool_account: pda::fee_pool_acc(),
+        clock_account: pda::clock_acc(),
+    }
+}
+
+/// Computation definition account for a circuit.
+pub fn comp_def_account(circuit: &str) -> Pubkey {
+    pda::comp_def_acc(&PROGRAM_ID, comp_def_offset(circuit))
+}
+
+/// Session PDA. Seeds: ["session", session_id]
+pub fn session_pda(session_id: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[b"session", session_id], &PROGRAM_ID).0
+}
+
+/// Pool PDA. Seeds: ["pool", pool_id]
+pub fn pool_pda(pool_id: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[b"pool", pool_id], &PROGRAM_ID).0
+}
+
+/// Blocklist PDA. Seeds: ["blocklist", blocklist_id]
+pub fn blocklist_pda(blocklist_id: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[b"blocklist", blocklist_id], &PROGRAM_ID).0
+}
+
+/// Staged list PDA for `owner`'s list to be matched with `peer`'s.
+/// Seeds: ["staged_list", owner, peer]
+pub fn staged_list_pda(owner: &Pubkey, peer: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"staged_list", owner.as_ref(), peer.as_ref()], &PROGRAM_ID).0
+}
+
+/// Card registry PDA. Seeds: ["card_registry", registry_id]
+pub fn card_registry_pda(registry_id: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[b"card_registry", registry_id], &PROGRAM_ID).0
+}
+
+/// Server database PDA for one epoch. Seeds: ["database", database_id, epoch (LE)]
+pub fn database_pda(database_id: &[u8; 32], epoch: u32) -> Pubkey {
+    Pubkey::find_program_address(&[b"database", database_id, &epoch.to_le_bytes()], &PROGRAM_ID).0
+}
+
+/// Program-wide counters PDA. Seeds: ["global_stats"]
+pub fn global_stats_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"global_stats"], &PROGRAM_ID).0
+}
+
+/// Per-application counters PDA. Seeds: ["app_stats", app_id]
+pub fn app_stats_pda(app_id: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[b"app_stats", app_id], &PROGRAM_ID).0
+}
+
+/// Deployment config PDA. Seeds: ["config"]
+pub fn config_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &PROGRAM_ID).0
+}
+
+/// Fee treasury PDA. Seeds: ["treasury"]
+pub fn treasury_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"treasury"], &PROGRAM_ID).0
+}
+
+/// Per-application prepaid credit PDA. Seeds: ["credit", app_id]
+pub fn credit_pda(app_id: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[b"credit", app_id], &PROGRAM_ID).0
+}
+
+/// An app's lookup table record PDA, also the table's authority.
+/// Seeds: ["app_lut", app_id]
+pub fn app_lut_pda(app_id: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[b"app_lut", app_id], &PROGRAM_ID).0
+}
+
+/// Address of the lookup table `create_app_lut` creates at `recent_slot`.
+pub fn app_lookup_table(app_id: &[u8; 32], recent_slot: u64) -> Pubkey {
+    derive_lookup_table_address(&app_lut_pda(app_id), recent_slot).0
+}
+
+/// A session's MXE state handle PDA. Seeds: ["state_handle", session]
+pub fn state_handle_pda(session: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"state_handle", session.as_ref()], &PROGRAM_ID).0
+}
+
+/// A session's app hook PDA. Seeds: ["app_hook", session]
+pub fn app_hook_pda(session: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"app_hook", session.as_ref()], &PROGRAM_ID).0
+}
+
+/// PDA that signs `program`'s app hook calls for `session`, for the
+/// hook program to check the caller against.
+/// Seeds: ["hook_authority", session, program]
+pub fn hook_authority_pda(session: &Pubkey, program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"hook_authority", session.as_ref(), program.as_ref()], &PROGRAM_ID).0
+}
+
+/// The two wallets of a connection in the order its attestation's
+/// seeds take them: ascending.
+pub fn connection_parties(a: Pubkey, b: Pubkey) -> (Pubkey, Pubkey) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Two wallets' `ConnectionAttestation` PDA, in either order.
+/// Seeds: ["connection", lower, higher]
+pub fn connection_pda(a: &Pubkey, b: &Pubkey) -> Pubkey {
+    let (party_a, party_b) = connection_parties(*a, *b);
+    Pubkey::find_program_address(&[b"connection", party_a.as_ref(), party_b.as_ref()], &PROGRAM_ID).0
+}
+
+/// Mint of two wallets' soulbound connection tokens, in either order.
+/// Seeds: ["connection_mint", attestation]
+pub fn connection_mint_pda(a: &Pubkey, b: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"connection_mint", connection_pda(a, b).as_ref()], &PROGRAM_ID).0
+}
+
+/// Two wallets' `PairQuota` PDA, in either order.
+/// Seeds: ["pair", lower, higher]
+pub fn pair_quota_pda(a: &Pubkey, b: &Pubkey) -> Pubkey {
+    let (party_a, party_b) = connection_parties(*a, *b);
+    Pubkey::find_program_address(&[b"pair", party_a.as_ref(), party_b.as_ref()], &PROGRAM_ID).0
+}
+
+/// A wallet's `ConsentRecord` PDA. Seeds: ["consent", wallet]
+pub fn consent_record_pda(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"consent", wallet.as_ref()], &PROGRAM_ID).0
+}
+
+/// A wallet's `BlockedWallet` PDA, present while the config admin
+/// blocks it. Seeds: ["blocked", wallet]
+pub fn blocked_wallet_pda(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"blocked", wallet.as_ref()], &PROGRAM_ID).0
+}
+
+/// `bob`'s `InviteClaim` PDA of a session, from `claim_invite` until
+/// he redeems it. Seeds: ["invite_claim", session, bob]
+pub fn invite_claim_pda(session: &Pubkey, bob: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"invite_claim", session.as_ref(), bob.as_ref()], &PROGRAM_ID).0
+}
+
+/// A wallet's `ActivityLog` PDA. Seeds: ["activity", wallet]
+pub fn activity_log_pda(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"activity", wallet.as_ref()], &PROGRAM_ID).0
+}
+
+/// The `DeploymentRegistry` PDA. Seeds: ["deployment"]
+pub fn deployment_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"deployment"], &PROGRAM_ID).0
+}
+
+/// Per-application session template PDA. Seeds: ["template", app_id, template_id]
+pub fn session_template_pda(app_id: &[u8; 32], template_id: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[b"template", app_id, template_id], &PROGRAM_ID).0
+}
+
+/// Signer PDA used by the Arcium macros.
+pub fn sign_pda() -> Pubkey {
+    Pubkey::find_program_address(&[&SIGN_PDA_SEED], &PROGRAM_ID).0
+}
@@ -0,0 +1,613 @@
+// Client-side encryption for Arcium MPC inputs and outputs.
+// Mirror of frontend/src/utils/arcium.ts: an x25519 exchange with the MXE
+// public key yields a shared secret, which keys a Rescue cipher (CTR mode).
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use arcium_client::encryption::RescueCipher;
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::{
+    error::ClientError,
+    fixtures::Fixtures,
+    hash::{
+        HashScheme, HashedContacts, HashedDelta, Namespace, CARD_SLOTS, MATCH_WORDS, MAX_CONTACTS, MAX_DELTA,
+        MEMBERSHIP_BATCH_SLOTS,
+    },
+};
+
+/// A ContactList encrypted for `submit_contacts_alice` / `submit_and_match`.
+#[derive(Clone, Debug)]
+pub struct EncryptedContacts {
+    pub encrypted_hashes: [[u8; 32]; MAX_CONTACTS],
+    pub encrypted_count: [u8; 32],
+    pub encrypted_namespace: [u8; 32],
+    /// `HashScheme` the hashes were derived with, declared in plaintext
+    pub hash_scheme: u8,
+    pub pubkey: [u8; 32],
+    pub nonce: u128,
+}
+
+impl EncryptedContacts {
+    /// Length of `to_bytes`: 34 ciphertexts, pubkey, nonce, hash scheme.
+    pub const ENCODED_LEN: usize = (MAX_CONTACTS + 3) * 32 + 16 + 1;
+
+    /// Commitment for `commit_contacts`. Must match `contacts_commitment`
+    /// in the program.
+    pub fn commitment(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        for hash in &self.encrypted_hashes {
+            hasher.update(hash);
+        }
+        hasher.update(self.encrypted_count);
+        hasher.update(self.encrypted_namespace);
+        hasher.update(self.pubkey);
+        hasher.update(self.nonce.to_le_bytes());
+        hasher.finalize().into()
+    }
+
+    /// Fixed-size encoding, so a committed submission can be kept until
+    /// it is sent.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::ENCODED_LEN);
+        for hash in &self.encrypted_hashes {
+            bytes.extend_from_slice(hash);
+        }
+        bytes.extend_from_slice(&self.encrypted_count);
+        bytes.extend_from_slice(&self.encrypted_namespace);
+        bytes.extend_from_slice(&self.pubkey);
+        bytes.extend_from_slice(&self.nonce.to_le_bytes());
+        bytes.push(self.hash_scheme);
+        bytes
+    }
+
+    /// Inverse of `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ClientError> {
+        if bytes.len() != Self::ENCODED_LEN {
+            return Err(ClientError::Decode(format!(
+                "encrypted contacts are {} bytes, expected {}",
+                bytes.len(),
+                Self::ENCODED_LEN
+            )));
+        }
+        let mut chunks = bytes.chunks_exact(32);
+        let mut next = || -> [u8; 32] { chunks.next().unwrap().try_into().unwrap() };
+
+        let mut encrypted_hashes = [[0u8; 32]; MAX_CONTACTS];
+        for hash in &mut encrypted_hashes {
+            *hash = next();
+        }
+        let encrypted_count = next();
+        let encrypted_namespace = next();
+        let pubkey = next();
+
+        let tail = &bytes[(MAX_CONTACTS + 3) * 32..];
+        Ok(Self {
+            encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+            hash_scheme: tail[16],
+            pubkey,
+            nonce: u128::from_le_bytes(tail[..16].try_into().unwrap()),
+        })
+    }
+}
+
+/// A ContactDelta encrypted for `push_delta`.
+#[derive(Clone, Debug)]
+pub struct EncryptedDelta {
+    pub encrypted_added: [[u8; 32]; MAX_DELTA],
+    pub encrypted_removed: [[u8; 32]; MAX_DELTA],
+    pub encrypted_namespace: [u8; 32],
+    /// `HashScheme` the hashes were derived with, declared in plaintext
+    pub hash_scheme: u8,
+    pub pubkey: [u8; 32],
+    pub nonce: u128,
+}
+
+/// A ContactCard encrypted for `publish_contact_card`.
+#[derive(Clone, Debug)]
+pub struct EncryptedCard {
+    /// Hash, both key halves, and namespace
+    pub encrypted_card: [[u8; 32]; 4],
+    pub pubkey: [u8; 32],
+    pub nonce: u128,
+}
+
+/// A MembershipQuery encrypted for `check_membership`.
+#[derive(Clone, Debug)]
+pub struct EncryptedMembershipQuery {
+    /// Hash and namespace
+    pub encrypted_query: [[u8; 32]; 2],
+    /// `HashScheme` the hash was derived with, declared in plaintext
+    pub hash_scheme: u8,
+    pub pubkey: [u8; 32],
+    pub nonce: u128,
+}
+
+/// A MembershipBatch encrypted for `check_membership_batch`.
+#[derive(Clone, Debug)]
+pub struct EncryptedMembershipBatch {
+    pub encrypted_hashes: [[u8; 32]; MEMBERSHIP_BATCH_SLOTS],
+    pub encrypted_namespace: [u8; 32],
+    /// Number of identifiers asked about, each billed as one query
+    pub queries: u8,
+    /// `HashScheme` the hashes were derived with, declared in plaintext
+    pub hash_scheme: u8,
+    pub pubkey: [u8; 32],
+    pub nonce: u128,
+}
+
+/// A contact's card released by `match_contact_cards`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContactCard {
+    /// The contact's hashed identifier
+    pub hash: u128,
+    /// The X25519 messaging key they published
+    pub messaging_key: [u8; 32],
+}
+
+/// A decrypted CardMatches.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DecryptedCards {
+    /// Released cards, empty slots removed
+    pub cards: Vec<ContactCard>,
+    pub card_count: u32,
+}
+
+/// A decrypted MatchResult. The circuits return which slots of the
+/// recipient's own list matched rather than the hashes themselves;
+/// `hashes` reads them back from the list that was submitted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DecryptedMatches {
+    /// Matched slots of the recipient's list, ascending (one page, for reveals)
+    pub slots: Vec<usize>,
+    /// Total matches across every page
+    pub match_count: u32,
+}
+
+impl DecryptedMatches {
+    /// The matched hashes, given the list the slots refer to: the
+    /// submitted list, decoys included, or for `push_delta` the list as
+    /// it stands after the delta.
+    pub fn hashes(&self, list: &[u128; MAX_CONTACTS]) -> Vec<u128> {
+        self.slots.iter().filter_map(|&slot| list.get(slot).copied()).collect()
+    }
+}
+
+/// A match result as a relayer or webhook delivers it: the fields of the
+/// `MatchComplete`, `AliceRevealed` or `BobRevealed` event it came from.
+/// Check it against the session's receipt with `verify` before
+/// decrypting; `rpc::decrypt_relayed_result` does both.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RelayedResult {
+    pub session_id: [u8; 32],
+    /// Recipient: 1 for Alice, 2 for Bob
+    pub party: u8,
+    /// Result window; 0 for `MatchComplete`
+    pub window: u8,
+    pub encrypted_matches: [[u8; 32]; MATCH_WORDS],
+    pub encrypted_count: [u8; 32],
+    pub nonce: [u8; 16],
+}
+
+impl RelayedResult {
+    /// Length of `to_bytes`: session id, party, window, 3 ciphertexts, nonce.
+    pub const ENCODED_LEN: usize = 32 + 2 + (MATCH_WORDS + 1) * 32 + 16;
+
+    /// Receipt the program stores for this result. Must match
+    /// `result_receipt` in the program.
+    pub fn receipt(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.session_id);
+        hasher.update([self.party, self.window]);
+        for word in &self.encrypted_matches {
+            hasher.update(word);
+        }
+        hasher.update(self.encrypted_count);
+        hasher.update(self.nonce);
+        hasher.finalize().into()
+    }
+
+    /// Fail unless the result is the one `receipt`, the party's receipt
+    /// on the session, was recorded for. An all-zero receipt means
+    /// nothing was delivered yet, so no result verifies against it.
+    pub fn verify(&self, receipt: &[u8; 32]) -> Result<(), ClientError> {
+        if *receipt == [0u8; 32] || self.receipt() != *receipt {
+            return Err(ClientError::ReceiptMismatch(self.party));
+        }
+        Ok(())
+    }
+
+    /// Fixed-size encoding, for relaying the result.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::ENCODED_LEN);
+        bytes.extend_from_slice(&self.session_id);
+        bytes.push(self.party);
+        bytes.push(self.window);
+        for word in &self.encrypted_matches {
+            bytes.extend_from_slice(word);
+        }
+        bytes.extend_from_slice(&self.encrypted_count);
+        bytes.extend_from_slice(&self.nonce);
+        bytes
+    }
+
+    /// Inverse of `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ClientError> {
+        if bytes.len() != Self::ENCODED_LEN {
+            return Err(ClientError::Decode(format!(
+                "relayed result is {} bytes, expected {}",
+                bytes.len(),
+                Self::ENCODED_LEN
+            )));
+        }
+        let word = |i: usize| -> [u8; 32] { bytes[34 + i * 32..66 + i * 32].try_into().unwrap() };
+        let mut encrypted_matches = [[0u8; 32]; MATCH_WORDS];
+        for (i, slot) in encrypted_matches.iter_mut().enumerate() {
+            *slot = word(i);
+        }
+        Ok(Self {
+            session_id: bytes[..32].try_into().unwrap(),
+            party: bytes[32],
+            window: bytes[33],
+            encrypted_matches,
+            encrypted_count: word(MATCH_WORDS),
+            nonce: bytes[Self::ENCODED_LEN - 16..].try_into().unwrap(),
+        })
+    }
+}
+
+/// A decrypted OverlapScore, in basis points (10000 = 100%).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DecryptedScore {
+    /// Matches divided by the smaller list size
+    pub overlap_bp: u32,
+    /// Matches divided by the union size
+    pub jaccard_bp: u32,
+}
+
+/// A decrypted BlocklistResult.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DecryptedBlocklistResult {
+    /// Whether any of the user's contacts are on the blocklist
+    pub blocked: bool,
+    /// How many of them are
+    pub blocked_count: u32,
+}
+
+/// One party's x25519 keypair and the Rescue cipher it shares with the MXE.
+pub struct PartyCipher {
+    public_key: [u8; 32],
+    cipher: RescueCipher,
+    /// Set for fixture ciphers; encryption nonces are random otherwise
+    seeded_nonces: Option<SeededNonces>,
+}
+
+/// The nonce sequence of a fixture cipher.
+struct SeededNonces {
+    fixtures: Fixtures,
+    label: String,
+    next: AtomicU64,
+}
+
+impl PartyCipher {
+    /// Generate a fresh keypair and derive the shared cipher.
+    pub fn new(mxe_public_key: [u8; 32]) -> Self {
+        Self::from_secret(StaticSecret::random_from_rng(OsRng), mxe_public_key)
+    }
+
+    /// Derive the shared cipher from an existing x25519 secret.
+    pub fn from_secret(secret: StaticSecret, mxe_public_key: [u8; 32]) -> Self {
+        let public_key = PublicKey::from(&secret).to_bytes();
+        let shared = secret.diffie_hellman(&PublicKey::from(mxe_public_key));
+        Self {
+            public_key,
+            cipher: RescueCipher::new(shared.as_bytes()),
+            seeded_nonces: None,
+        }
+    }
+
+    /// Encrypt under `fixtures`' nonces for `label`, in call order,
+    /// instead of random ones.
+    pub(crate) fn with_seeded_nonces(mut self, fixtures: Fixtures, label: &str) -> Self {
+        self.seeded_nonces = Some(SeededNonces {
+            fixtures,
+            label: label.to_string(),
+            next: AtomicU64::new(0),
+        });
+        self
+    }
+
+    fn nonce(&self) -> [u8; 16] {
+        match &self.seeded_nonces {
+            Some(seeded) => {
+                let index = seeded.next.fetch_add(1, Ordering::Relaxed);
+                seeded.fixtures.nonce(&seeded.label, index)
+            }
+            None => random_nonce(),
+        }
+    }
+
+    /// The x25519 public key passed as `pubkey` to queue instructions.
+    pub fn public_key(&self) -> [u8; 32] {
+        self.public_key
+    }
+
+    /// Encrypt a ContactList (32 hashes + count + namespace) under a fresh nonce.
+    pub fn encrypt_contacts(&self, contacts: &HashedContacts) -> EncryptedContacts {
+        let nonce = self.nonce();
+
+        let mut plaintext = contacts.hashes.to_vec();
+        plaintext.push(contacts.count as u128);
+        plaintext.push(contacts.namespace as u128);
+        let ciphertexts = self.cipher.encrypt(&plaintext, &nonce);
+
+        let mut encrypted_hashes = [[0u8; 32]; MAX_CONTACTS];
+        encrypted_hashes.copy_from_slice(&ciphertexts[..MAX_CONTACTS]);
+
+        EncryptedContacts {
+            encrypted_hashes,
+            encrypted_count: ciphertexts[MAX_CONTACTS],
+            encrypted_namespace: ciphertexts[MAX_CONTACTS + 1],
+            hash_scheme: contacts.scheme as u8,
+            pubkey: self.public_key,
+            nonce: u128::from_le_bytes(nonce),
+        }
+    }
+
+    /// Encrypt a ContactDelta (16 added + 16 removed hashes + namespace)
+    /// under a fresh nonce.
+    pub fn encrypt_delta(&self, delta: &HashedDelta) -> EncryptedDelta {
+        let nonce = self.nonce();
+
+        let mut plaintext = delta.added.to_vec();
+        plaintext.extend_from_slice(&delta.removed);
+        plaintext.push(delta.namespace as u128);
+        let ciphertexts = self.cipher.encrypt(&plaintext, &nonce);
+
+        let mut encrypted_added = [[0u8; 32]; MAX_DELTA];
+        let mut encrypted_removed = [[0u8; 32]; MAX_DELTA];
+        encrypted_added.copy_from_slice(&ciphertexts[..MAX_DELTA]);
+        encrypted_removed.copy_from_slice(&ciphertexts[MAX_DELTA..2 * MAX_DELTA]);
+
+        EncryptedDelta {
+            encrypted_added,
+            encrypted_removed,
+            encrypted_namespace: ciphertexts[2 * MAX_DELTA],
+            hash_scheme: delta.scheme as u8,
+            pubkey: self.public_key,
+            nonce: u128::from_le_bytes(nonce),
+        }
+    }
+
+    /// Encrypt a ContactCard publishing `messaging_key` under the
+    /// publisher's own identifier `hash`, under a fresh nonce.
+    pub fn encrypt_card(&self, hash: u128, namespace: Namespace, messaging_key: &[u8; 32]) -> EncryptedCard {
+        let nonce = self.nonce();
+
+        let key_lo = u128::from_le_bytes(messaging_key[..16].try_into().unwrap());
+        let key_hi = u128::from_le_bytes(messaging_key[16..].try_into().unwrap());
+        let ciphertexts = self.cipher.encrypt(&[hash, key_lo, key_hi, namespace as u128], &nonce);
+
+        let mut encrypted_card = [[0u8; 32]; 4];
+        encrypted_card.copy_from_slice(&ciphertexts);
+
+        EncryptedCard {
+            encrypted_card,
+            pubkey: self.public_key,
+            nonce: u128::from_le_bytes(nonce),
+        }
+    }
+
+    /// Encrypt a MembershipQuery asking about identifier `hash`, under a
+    /// fresh nonce.
+    pub fn encrypt_membership_query(
+        &self,
+        scheme: HashScheme,
+        namespace: Namespace,
+        hash: u128,
+    ) -> EncryptedMembershipQuery {
+        let nonce = self.nonce();
+        let ciphertexts = self.cipher.encrypt(&[hash, namespace as u128], &nonce);
+
+        let mut encrypted_query = [[0u8; 32]; 2];
+        encrypted_query.copy_from_slice(&ciphertexts);
+
+        EncryptedMembershipQuery {
+            encrypted_query,
+            hash_scheme: scheme as u8,
+            pubkey: self.public_key,
+            nonce: u128::from_le_bytes(nonce),
+        }
+    }
+
+    /// Encrypt a MembershipBatch asking about `hashes` (at most
+    /// `MEMBERSHIP_BATCH_SLOTS`), zero-padded, under a fresh nonce.
+    pub fn encrypt_membership_batch(
+        &self,
+        scheme: HashScheme,
+        namespace: Namespace,
+        hashes: &[u128],
+    ) -> Result<EncryptedMembershipBatch, ClientError> {
+        if hashes.is_empty() || hashes.len() > MEMBERSHIP_BATCH_SLOTS {
+            return Err(ClientError::TooManyContacts {
+                count: hashes.len(),
+                max: MEMBERSHIP_BATCH_SLOTS,
+            });
+        }
+        let nonce = self.nonce();
+        let mut plaintext = [0u128; MEMBERSHIP_BATCH_SLOTS + 1];
+        plaintext[..hashes.len()].copy_from_slice(hashes);
+        plaintext[MEMBERSHIP_BATCH_SLOTS] = namespace as u128;
+        let ciphertexts = self.cipher.encrypt(&plaintext, &nonce);
+
+        let mut encrypted_hashes = [[0u8; 32]; MEMBERSHIP_BATCH_SLOTS];
+        encrypted_hashes.copy_from_slice(&ciphertexts[..MEMBERSHIP_BATCH_SLOTS]);
+
+        Ok(EncryptedMembershipBatch {
+            encrypted_hashes,
+            encrypted_namespace: ciphertexts[MEMBERSHIP_BATCH_SLOTS],
+            queries: hashes.len() as u8,
+            hash_scheme: scheme as u8,
+            pubkey: self.public_key,
+            nonce: u128::from_le_bytes(nonce),
+        })
+    }
+
+    /// Decrypt a MatchResult emitted by `MatchComplete` / `AliceRevealed` /
+    /// `DeltaMatched`.
+    pub fn decrypt_matches(
+        &self,
+        encrypted_matches: &[[u8; 32]; MATCH_WORDS],
+        encrypted_count: &[u8; 32],
+        nonce: &[u8; 16],
+    ) -> DecryptedMatches {
+        let mut ciphertexts = encrypted_matches.to_vec();
+        ciphertexts.push(*encrypted_count);
+        let plaintext = self.cipher.decrypt(&ciphertexts, nonce);
+
+        // Byte j of the packed words holds the slot plus one, 0 if unmatched
+        let mut slots: Vec<usize> = plaintext[..MATCH_WORDS]
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .filter(|byte| *byte != 0)
+            .map(|byte| byte as usize - 1)
+            .collect();
+        slots.sort_unstable();
+
+        DecryptedMatches {
+            slots,
+            match_count: plaintext[MATCH_WORDS] as u32,
+        }
+    }
+
+    /// Decrypt a relayed match result, after checking it against
+    /// `receipt`, the recipient's receipt read from the session.
+    pub fn decrypt_relayed(&self, result: &RelayedResult, receipt: &[u8; 32]) -> Result<DecryptedMatches, ClientError> {
+        result.verify(receipt)?;
+        Ok(self.decrypt_matches(&result.encrypted_matches, &result.encrypted_count, &result.nonce))
+    }
+
+    /// Decrypt an OverlapScore emitted by `ScoreComplete` / `AliceScoreRevealed`.
+    pub fn decrypt_score(&self, encrypted_score: &[[u8; 32]; 2], nonce: &[u8; 16]) -> DecryptedScore {
+        let plaintext = self.cipher.decrypt(encrypted_score, nonce);
+        DecryptedScore {
+            overlap_bp: plaintext[0] as u32,
+            jaccard_bp: plaintext[1] as u32,
+        }
+    }
+
+    /// Decrypt a BlocklistResult emitted by `BlocklistChecked`.
+    pub fn decrypt_blocklist_result(
+        &self,
+        encrypted_result: &[[u8; 32]; 2],
+        nonce: &[u8; 16],
+    ) -> DecryptedBlocklistResult {
+        let plaintext = self.cipher.decrypt(encrypted_result, nonce);
+        DecryptedBlocklistResult {
+            blocked: plaintext[0] != 0,
+            blocked_count: plaintext[1] as u32,
+        }
+    }
+
+    /// Decrypt the CardMatches emitted by `CardsMatched`.
+    pub fn decrypt_cards(
+        &self,
+        encrypted_hashes: &[[u8; 32]; CARD_SLOTS],
+        encrypted_keys_lo: &[[u8; 32]; CARD_SLOTS],
+        encrypted_keys_hi: &[[u8; 32]; CARD_SLOTS],
+        encrypted_count: &[u8; 32],
+        nonce: &[u8; 16],
+    ) -> DecryptedCards {
+        let mut ciphertexts = encrypted_hashes.to_vec();
+        ciphertexts.extend_from_slice(encrypted_keys_lo);
+        ciphertexts.extend_from_slice(encrypted_keys_hi);
+        ciphertexts.push(*encrypted_count);
+        let plaintext = self.cipher.decrypt(&ciphertexts, nonce);
+
+        let cards = (0..CARD_SLOTS)
+            .filter(|&i| plaintext[i] != 0)
+            .map(|i| {
+                let mut messaging_key = [0u8; 32];
+                messaging_key[..16].copy_from_slice(&plaintext[CARD_SLOTS + i].to_le_bytes());
+                messaging_key[16..].copy_from_slice(&plaintext[2 * CARD_SLOTS + i].to_le_bytes());
+                ContactCard {
+                    hash: plaintext[i],
+                    messaging_key,
+                }
+            })
+            .collect();
+
+        DecryptedCards {
+            cards,
+            card_count: plaintext[3 * CARD_SLOTS] as u32,
+        }
+    }
+
+    /// Decrypt the friends-of-friends count emitted by `SecondDegreeRevealed`.
+    pub fn decrypt_count(&self, encrypted_count: &[u8; 32], nonce: &[u8; 16]) -> u32 {
+        self.cipher.decrypt(&[*encrypted_count], nonce)[0] as u32
+    }
+
+    /// Decrypt the membership flag emitted by `MembershipChecked`.
+    pub fn decrypt_member(&self, encrypted_member: &[u8; 32], nonce: &[u8; 16]) -> bool {
+        self.cipher.decrypt(&[*encrypted_member], nonce)[0] != 0
+    }
+
+    /// Decrypt the membership mask emitted by `MembershipBatchChecked`:
+    /// bit `j` set iff the batch's identifier `j` is in the database.
+    pub fn decrypt_membership_mask(&self, encrypted_mask: &[u8; 32], nonce: &[u8; 16]) -> u16 {
+        self.cipher.decrypt(&[*encrypted_mask], nonce)[0] as u16
+    }
+
+    /// Decrypt the mutual-follow flag emitted by `FollowChecked`.
+    pub fn decrypt_mutual(&self, encrypted_mutual: &[u8; 32], nonce: &[u8; 16]) -> bool {
+        self.cipher.decrypt(&[*encrypted_mutual], nonce)[0] != 0
+    }
+}
+
+/// Random 16-byte nonce for Rescue CTR mode.
+pub fn random_nonce() -> [u8; 16] {
+    let mut nonce = [0u8; 16];
+    OsRng.fill_bytes(&mut nonce);
+    nonce
+}
+
+/// Random computation offset.
+pub fn random_computation_offset() -> u64 {
+    OsRng.next_u64()
+}
+
+/// Random 32-byte session id.
+pub fn random_session_id() -> [u8; 32] {
+    let mut id = [0u8; 32];
+    OsRng.fill_bytes(&mut id);
+    id
+}
+
+/// Random invite code: 16 bytes, 128 bits, hex-encoded to the 32
+/// characters `redeem_invite` requires at least.
+pub fn random_invite_code() -> String {
+    let mut code = [0u8; 16];
+    OsRng.fill_bytes(&mut code);
+    hex::encode(code)
+}
+
+/// Session id both parties can compute without exchanging it:
+/// SHA-256 over a domain tag, Alice's and Bob's wallet keys, the app id,
+/// and `round` (little-endian), which tells apart repeated sessions
+/// between the same pair. The roles are ordered, so swapping Alice and
+/// Bob gives another id. Pass the next round if `create_session` finds
+/// the id taken (`SessionIdTaken`).
+pub fn derive_session_id(alice: &[u8; 32], bob: &[u8; 32], app_id: &[u8; 32], round: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"pcd-session-id");
+    hasher.update(alice);
+    hasher.update(bob);
+    hasher.update(app_id);
+    hasher.update(round.to_le_bytes());
+    hasher.finalize().into()
+}
@@ -0,0 +1,49 @@
+use thiserror::Error;
+
+/// Errors surfaced by the client SDK.
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("{count} contacts given, a session holds at most {max}")]
+    TooManyContacts { count: usize, max: usize },
+    #[error("MXE x25519 public key is not available; is the MXE initialized?")]
+    MxeKeyUnavailable,
+    #[error("session {0} not found")]
+    SessionNotFound(String),
+    #[error("pool {0} not found")]
+    PoolNotFound(String),
+    #[error("blocklist {0} not found")]
+    BlocklistNotFound(String),
+    #[error("card registry {0} not found")]
+    CardRegistryNotFound(String),
+    #[error("database {0} not found")]
+    DatabaseNotFound(String),
+    #[error("invalid session id: {0}")]
+    InvalidSessionId(String),
+    #[error("unknown identifier namespace {0}")]
+    InvalidNamespace(u8),
+    #[error("unknown hash scheme {0}")]
+    InvalidHashScheme(u8),
+    #[error("account data could not be decoded: {0}")]
+    Decode(String),
+    #[error("event subscription failed: {0}")]
+    Subscription(String),
+    #[error("no callback transaction found for computation {0}")]
+    CallbackNotFound(String),
+    #[error("computation output failed verification: {0}")]
+    Unverified(String),
+    #[error("the MPC rejected party {0}'s list as malformed")]
+    SubmissionRejected(u8),
+    #[error("timed out waiting for {0}")]
+    Timeout(&'static str),
+    #[error("the Arcium mempool was full on all {0} attempt(s) to queue the computation")]
+    MempoolFull(u32),
+    #[error("versioned transaction could not be built: {0}")]
+    VersionedTransaction(String),
+    #[error("relayed result does not match party {0}'s receipt on the session")]
+    ReceiptMismatch(u8),
+    #[error("{0} is not a .sol name")]
+    UnsupportedName(String),
+    #[cfg(feature = "rpc")]
+    #[error(transparent)]
+    Rpc(#[from] solana_client::client_error::ClientError),
+}
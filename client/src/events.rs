@@ -0,0 +1,323 @@
+// Typed program events and a resumable websocket event stream.
+//
+// `SessionEventStream` replays program transactions from a given slot
+// (via getSignaturesForAddress) and then follows live logs
+// (via logsSubscribe), yielding every decoded event in order.
+
+use std::{
+    collections::HashSet,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::{Stream, StreamExt};
+use solana_client::{
+    nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::{RpcTransactionConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+};
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use tokio::sync::mpsc;
+
+use private_contact_discovery::{
+    AliceRevealed, AliceRevealing, AliceScoreRevealed, AliceSubmitted, BobJoined, BobRevealed, BobRevealing,
+    BobSubmitted, ComputationFailedEvent, ContactsCommitted, ContactsSubmitted, DeltaMatched, DeltaSubmitted,
+    FollowChecked, MatchComplete, MatchComputing, MatchCountPublished, NotificationRegistered, RefundClaimed,
+    ScoreComplete, SessionClosed, SessionFailed, SessionHeartbeat, SessionReallocated, SecondDegreeComputed,
+    SecondDegreeRevealed, SecondDegreeRevealing, SessionCreated, SessionInitialized, SetChunkComputing, SetChunkMatched,
+    SetCommitted, StateMigrated, StateMigrating, StatePurged, StatePurging, SubmissionRejected, ID as PROGRAM_ID,
+};
+
+use crate::error::ClientError;
+
+/// Every event the program emits.
+#[derive(Clone, Debug)]
+pub enum SessionEvent {
+    SessionCreated(SessionCreated),
+    SessionInitialized(SessionInitialized),
+    ContactsSubmitted(ContactsSubmitted),
+    AliceSubmitted(AliceSubmitted),
+    BobJoined(BobJoined),
+    MatchComputing(MatchComputing),
+    MatchComplete(MatchComplete),
+    AliceRevealing(AliceRevealing),
+    AliceRevealed(AliceRevealed),
+    DeltaSubmitted(DeltaSubmitted),
+    DeltaMatched(DeltaMatched),
+    ScoreComplete(ScoreComplete),
+    AliceScoreRevealed(AliceScoreRevealed),
+    BobSubmitted(BobSubmitted),
+    SecondDegreeComputed(SecondDegreeComputed),
+    SecondDegreeRevealing(SecondDegreeRevealing),
+    SecondDegreeRevealed(SecondDegreeRevealed),
+    ContactsCommitted(ContactsCommitted),
+    SubmissionRejected(SubmissionRejected),
+    FollowChecked(FollowChecked),
+    BobRevealing(BobRevealing),
+    BobRevealed(BobRevealed),
+    MatchCountPublished(MatchCountPublished),
+    SessionReallocated(SessionReallocated),
+    SessionClosed(SessionClosed),
+    StatePurging(StatePurging),
+    StatePurged(StatePurged),
+    StateMigrating(StateMigrating),
+    StateMigrated(StateMigrated),
+    NotificationRegistered(NotificationRegistered),
+    SessionFailed(SessionFailed),
+    SessionHeartbeat(SessionHeartbeat),
+    /// Also emitted outside sessions, with a pool's or similar id as `session_id`
+    ComputationFailed(ComputationFailedEvent),
+    RefundClaimed(RefundClaimed),
+    SetCommitted(SetCommitted),
+    SetChunkComputing(SetChunkComputing),
+    SetChunkMatched(SetChunkMatched),
+}
+
+/// A decoded event with the transaction it came from.
+#[derive(Clone, Debug)]
+pub struct EventNotification {
+    pub signature: String,
+    pub slot: u64,
+    pub event: SessionEvent,
+}
+
+fn try_decode<E: Discriminator + AnchorDeserialize>(bytes: &[u8]) -> Option<E> {
+    let data = bytes.strip_prefix(E::DISCRIMINATOR)?;
+    E::deserialize(&mut &data[..]).ok()
+}
+
+impl SessionEvent {
+    /// Decode one `Program data:` payload.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        None.or_else(|| try_decode(bytes).map(Self::SessionCreated))
+            .or_else(|| try_decode(bytes).map(Self::SessionInitialized))
+            .or_else(|| try_decode(bytes).map(Self::ContactsSubmitted))
+            .or_else(|| try_decode(bytes).map(Self::AliceSubmitted))
+            .or_else(|| try_decode(bytes).map(Self::BobJoined))
+            .or_else(|| try_decode(bytes).map(Self::MatchComputing))
+            .or_else(|| try_decode(bytes).map(Self::MatchComplete))
+            .or_else(|| try_decode(bytes).map(Self::AliceRevealing))
+            .or_else(|| try_decode(bytes).map(Self::AliceRevealed))
+            .or_else(|| try_decode(bytes).map(Self::DeltaSubmitted))
+            .or_else(|| try_decode(bytes).map(Self::DeltaMatched))
+            .or_else(|| try_decode(bytes).map(Self::ScoreComplete))
+            .or_else(|| try_decode(bytes).map(Self::AliceScoreRevealed))
+            .or_else(|| try_decode(bytes).map(Self::BobSubmitted))
+            .or_else(|| try_decode(bytes).map(Self::SecondDegreeComputed))
+            .or_else(|| try_decode(bytes).map(Self::SecondDegreeRevealing))
+            .or_else(|| try_decode(bytes).map(Self::SecondDegreeRevealed))
+            .or_else(|| try_decode(bytes).map(Self::ContactsCommitted))
+            .or_else(|| try_decode(bytes).map(Self::SubmissionRejected))
+            .or_else(|| try_decode(bytes).map(Self::FollowChecked))
+            .or_else(|| try_decode(bytes).map(Self::BobRevealing))
+            .or_else(|| try_decode(bytes).map(Self::BobRevealed))
+            .or_else(|| try_decode(bytes).map(Self::MatchCountPublished))
+            .or_else(|| try_decode(bytes).map(Self::SessionReallocated))
+            .or_else(|| try_decode(bytes).map(Self::SessionClosed))
+            .or_else(|| try_decode(bytes).map(Self::StatePurging))
+            .or_else(|| try_decode(bytes).map(Self::StatePurged))
+            .or_else(|| try_decode(bytes).map(Self::StateMigrating))
+            .or_else(|| try_decode(bytes).map(Self::StateMigrated))
+            .or_else(|| try_decode(bytes).map(Self::NotificationRegistered))
+            .or_else(|| try_decode(bytes).map(Self::SessionFailed))
+            .or_else(|| try_decode(bytes).map(Self::SessionHeartbeat))
+            .or_else(|| try_decode(bytes).map(Self::ComputationFailed))
+            .or_else(|| try_decode(bytes).map(Self::RefundClaimed))
+            .or_else(|| try_decode(bytes).map(Self::SetCommitted))
+            .or_else(|| try_decode(bytes).map(Self::SetChunkComputing))
+            .or_else(|| try_decode(bytes).map(Self::SetChunkMatched))
+    }
+
+    /// The session this event belongs to.
+    pub fn session_id(&self) -> [u8; 32] {
+        match self {
+            Self::SessionCreated(e) => e.session_id,
+            Self::SessionInitialized(e) => e.session_id,
+            Self::ContactsSubmitted(e) => e.session_id,
+            Self::AliceSubmitted(e) => e.session_id,
+            Self::BobJoined(e) => e.session_id,
+            Self::MatchComputing(e) => e.session_id,
+            Self::MatchComplete(e) => e.session_id,
+            Self::AliceRevealing(e) => e.session_id,
+            Self::AliceRevealed(e) => e.session_id,
+            Self::DeltaSubmitted(e) => e.session_id,
+            Self::DeltaMatched(e) => e.session_id,
+            Self::ScoreComplete(e) => e.session_id,
+            Self::AliceScoreRevealed(e) => e.session_id,
+            Self::BobSubmitted(e) => e.session_id,
+            Self::SecondDegreeComputed(e) => e.session_id,
+            Self::SecondDegreeRevealing(e) => e.session_id,
+            Self::SecondDegreeRevealed(e) => e.session_id,
+            Self::ContactsCommitted(e) => e.session_id,
+            Self::SubmissionRejected(e) => e.session_id,
+            Self::FollowChecked(e) => e.session_id,
+            Self::BobRevealing(e) => e.session_id,
+            Self::BobRevealed(e) => e.session_id,
+            Self::MatchCountPublished(e) => e.session_id,
+            Self::SessionReallocated(e) => e.session_id,
+            Self::SessionClosed(e) => e.session_id,
+            Self::StatePurging(e) => e.session_id,
+            Self::StatePurged(e) => e.session_id,
+            Self::StateMigrating(e) => e.session_id,
+            Self::StateMigrated(e) => e.session_id,
+            Self::NotificationRegistered(e) => e.session_id,
+            Self::SessionFailed(e) => e.session_id,
+            Self::SessionHeartbeat(e) => e.session_id,
+            Self::ComputationFailed(e) => e.session_id,
+            Self::RefundClaimed(e) => e.session_id,
+            Self::SetCommitted(e) => e.session_id,
+            Self::SetChunkComputing(e) => e.session_id,
+            Self::SetChunkMatched(e) => e.session_id,
+        }
+    }
+}
+
+/// Decode every program event in a transaction's logs, in order.
+pub fn decode_logs(logs: &[String]) -> Vec<SessionEvent> {
+    logs.iter()
+        .filter_map(|line| line.strip_prefix("Program data: "))
+        .filter_map(|data| STANDARD.decode(data).ok())
+        .filter_map(|bytes| SessionEvent::decode(&bytes))
+        .collect()
+}
+
+/// Stream of program events, optionally resuming from a slot.
+pub struct SessionEventStream {
+    receiver: mpsc::Receiver<Result<EventNotification, ClientError>>,
+}
+
+impl SessionEventStream {
+    /// Connect and start streaming. When `resume_from` is set, every
+    /// successful program transaction after that slot is replayed first.
+    pub async fn connect(
+        rpc_url: &str,
+        ws_url: &str,
+        resume_from: Option<u64>,
+    ) -> Result<Self, ClientError> {
+        let rpc = RpcClient::new_with_commitment(rpc_url.to_string(), CommitmentConfig::confirmed());
+        let pubsub = PubsubClient::new(ws_url)
+            .await
+            .map_err(|e| ClientError::Subscription(e.to_string()))?;
+        let (sender, receiver) = mpsc::channel(256);
+
+        tokio::spawn(async move {
+            if let Err(e) = run(rpc, pubsub, resume_from, &sender).await {
+                let _ = sender.send(Err(e)).await;
+            }
+        });
+
+        Ok(Self { receiver })
+    }
+}
+
+impl Stream for SessionEventStream {
+    type Item = Result<EventNotification, ClientError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+type Sender = mpsc::Sender<Result<EventNotification, ClientError>>;
+
+async fn run(
+    rpc: RpcClient,
+    pubsub: PubsubClient,
+    resume_from: Option<u64>,
+    sender: &Sender,
+) -> Result<(), ClientError> {
+    // Subscribe before replaying so nothing lands in the gap
+    let (mut live, _unsubscribe) = pubsub
+        .logs_subscribe(
+            RpcTransactionLogsFilter::Mentions(vec![PROGRAM_ID.to_string()]),
+            RpcTransactionLogsConfig {
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )
+        .await
+        .map_err(|e| ClientError::Subscription(e.to_string()))?;
+
+    let mut replayed = HashSet::new();
+    if let Some(slot) = resume_from {
+        replayed = replay(&rpc, slot, sender).await?;
+    }
+
+    while let Some(notification) = live.next().await {
+        let logs = notification.value;
+        if logs.err.is_some() || replayed.remove(&logs.signature) {
+            continue;
+        }
+        if !emit(sender, &logs.signature, notification.context.slot, &logs.logs).await {
+            return Ok(());
+        }
+    }
+
+    Err(ClientError::Subscription("log subscription closed".into()))
+}
+
+/// Replay program transactions after `slot`, oldest first.
+/// Returns the replayed signatures so the live stream can skip them.
+async fn replay(rpc: &RpcClient, slot: u64, sender: &Sender) -> Result<HashSet<String>, ClientError> {
+    let mut pending = Vec::new();
+    let mut before = None;
+
+    'pages: loop {
+        let page = rpc
+            .get_signatures_for_address_with_config(
+                &PROGRAM_ID,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        let Some(last) = page.last() else { break };
+        before = last.signature.parse().ok();
+        for status in page {
+            if status.slot <= slot {
+                break 'pages;
+            }
+            if status.err.is_none() {
+                pending.push((status.signature, status.slot));
+            }
+        }
+    }
+
+    let config = RpcTransactionConfig {
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+        ..Default::default()
+    };
+    let mut seen = HashSet::new();
+    for (signature, slot) in pending.into_iter().rev() {
+        let Ok(parsed) = signature.parse::<Signature>() else {
+            continue;
+        };
+        let tx = rpc.get_transaction_with_config(&parsed, config).await?;
+        let logs: Option<Vec<String>> = tx.transaction.meta.and_then(|m| m.log_messages.into());
+        if let Some(logs) = logs {
+            if !emit(sender, &signature, slot, &logs).await {
+                break;
+            }
+        }
+        seen.insert(signature);
+    }
+    Ok(seen)
+}
+
+/// Send every event in `logs`; false once the receiver is gone.
+async fn emit(sender: &Sender, signature: &str, slot: u64, logs: &[String]) -> bool {
+    for event in decode_logs(logs) {
+        let notification = EventNotification {
+            signature: signature.to_string(),
+            slot,
+            event,
+        };
+        if sender.send(Ok(notification)).await.is_err() {
+            return false;
+        }
+    }
+    true
+}
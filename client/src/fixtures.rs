@@ -0,0 +1,78 @@
+// Deterministic encrypted fixtures.
+//
+// Encryption normally draws a fresh x25519 key per party and a fresh
+// nonce per payload, so no two runs produce the same ciphertexts. When
+// an integration test or a bug report needs to name an exact encrypted
+// payload, `Fixtures` derives both from a seed instead:
+//
+//     let fixtures = Fixtures::new(b"issue-412");
+//     let alice = fixtures.cipher("alice", mxe_public_key);
+//     let encrypted = alice.encrypt_contacts(&contacts);
+//
+// The same seed, label, MXE key, and call order give the same bytes on
+// every machine and in the wasm build. With no cluster involved,
+// `fixtures.secret("mxe")` can stand in for the MXE's key, so a fixture
+// also decrypts offline.
+//
+// Anyone who knows the seed knows the keys. Fixtures are for test data
+// only, never for real contacts.
+
+use sha2::{Digest, Sha256};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::crypto::PartyCipher;
+
+/// Domain tags keeping keys and nonces from the same seed apart.
+const KEY_DOMAIN: &[u8] = b"pcd-fixture-key";
+const NONCE_DOMAIN: &[u8] = b"pcd-fixture-nonce";
+
+/// Reproducible keys, nonces, and ciphers derived from one seed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fixtures {
+    seed: [u8; 32],
+}
+
+impl Fixtures {
+    /// Any bytes serve as a seed: a test name, an issue number.
+    pub fn new(seed: &[u8]) -> Self {
+        Fixtures {
+            seed: Sha256::digest(seed).into(),
+        }
+    }
+
+    /// The x25519 secret of `label`: a party, or `"mxe"` for an offline
+    /// stand-in for the MXE.
+    pub fn secret(&self, label: &str) -> StaticSecret {
+        StaticSecret::from(self.derive(KEY_DOMAIN, label, 0))
+    }
+
+    /// The x25519 public key of `label`.
+    pub fn public_key(&self, label: &str) -> [u8; 32] {
+        PublicKey::from(&self.secret(label)).to_bytes()
+    }
+
+    /// The `index`th nonce of `label`.
+    pub fn nonce(&self, label: &str, index: u64) -> [u8; 16] {
+        let mut nonce = [0u8; 16];
+        nonce.copy_from_slice(&self.derive(NONCE_DOMAIN, label, index)[..16]);
+        nonce
+    }
+
+    /// `label`'s cipher with the MXE. It encrypts under `secret(label)`
+    /// and takes its nonces in order: `nonce(label, 0)` for its first
+    /// payload, `nonce(label, 1)` for the next, and so on.
+    pub fn cipher(&self, label: &str, mxe_public_key: [u8; 32]) -> PartyCipher {
+        PartyCipher::from_secret(self.secret(label), mxe_public_key).with_seeded_nonces(self.clone(), label)
+    }
+
+    fn derive(&self, domain: &[u8], label: &str, index: u64) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(domain);
+        hasher.update(self.seed);
+        // Length-prefixed, so no label is a prefix of another's input
+        hasher.update((label.len() as u64).to_le_bytes());
+        hasher.update(label.as_bytes());
+        hasher.update(index.to_le_bytes());
+        hasher.finalize().into()
+    }
+}
@@ -0,0 +1,424 @@
+// Client-side contact hashing.
+// Mirror of frontend/src/utils/hash.ts: contacts are normalized, then
+// hashed to u128 (upper 128 bits of SHA-256, or of BLAKE3 for sessions
+// on the newer scheme) in a per-namespace domain, so a phone number and
+// an email never hash alike. Plaintext contacts never leave this module.
+
+use sha2::{Digest, Sha256};
+
+use crate::error::ClientError;
+
+/// Maximum contacts per session. Must match `MAX_CONTACTS` in the circuits.
+pub const MAX_CONTACTS: usize = 32;
+
+/// List capacities a session can be created with. Must match
+/// `CAPACITY_TIERS` in the schema.
+pub const CAPACITY_TIERS: [u8; 3] = [8, 16, 32];
+
+/// Maximum chunks of a committed set. Must match `MAX_SET_CHUNKS` in
+/// the program.
+pub const MAX_SET_CHUNKS: usize = 64;
+
+/// Chunks a server database holds. Must match `DATABASE_CHUNKS` in the
+/// schema.
+pub const DATABASE_CHUNKS: usize = 8;
+
+/// Identifiers per batched membership check. Must match
+/// `MEMBERSHIP_BATCH_SLOTS` in the schema.
+pub const MEMBERSHIP_BATCH_SLOTS: usize = 16;
+
+/// Cards a registry holds. Must match `CARD_SLOTS` in the circuits.
+pub const CARD_SLOTS: usize = 16;
+
+/// Words a MatchResult packs its matched slots into, one byte per slot.
+/// Must match `MATCH_WORDS` in the circuits.
+pub const MATCH_WORDS: usize = 2;
+
+/// Hashes at or above this are decoys, which the circuits never match
+/// or count. Must match `DECOY_FLOOR` in the schema.
+pub const DECOY_FLOOR: u128 = 0xFFFF_FFFF_FFFF_FFFF_0000_0000_0000_0000;
+
+/// Domain of a decoy hash, so decoys never collide with a contact's
+/// preimage.
+const DECOY_DOMAIN: &[u8] = b"pcd-decoy-v1";
+
+/// Maximum additions (and removals) per subscription delta.
+/// Must match `MAX_DELTA` in the circuits.
+pub const MAX_DELTA: usize = 16;
+
+/// Identifier type a session's contacts are hashed as.
+/// Must match `Namespace` in the program.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Namespace {
+    #[default]
+    Phone = 0,
+    Email = 1,
+    Username = 2,
+    Wallet = 3,
+    /// Solana name-service names (`.sol`, and other AllDomains TLDs)
+    SolName = 4,
+}
+
+impl Namespace {
+    /// Domain tag mixed into every hash in this namespace.
+    pub fn tag(self) -> &'static str {
+        match self {
+            Namespace::Phone => "phone",
+            Namespace::Email => "email",
+            Namespace::Username => "username",
+            Namespace::Wallet => "wallet",
+            Namespace::SolName => "solname",
+        }
+    }
+}
+
+impl TryFrom<u8> for Namespace {
+    type Error = ClientError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Namespace::Phone),
+            1 => Ok(Namespace::Email),
+            2 => Ok(Namespace::Username),
+            3 => Ok(Namespace::Wallet),
+            4 => Ok(Namespace::SolName),
+            other => Err(ClientError::InvalidNamespace(other)),
+        }
+    }
+}
+
+/// How identifiers are hashed. Must match `HashScheme` in the program;
+/// both parties of a session have to use the same one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum HashScheme {
+    #[default]
+    Sha256 = 0,
+    Blake3 = 1,
+}
+
+impl TryFrom<u8> for HashScheme {
+    type Error = ClientError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(HashScheme::Sha256),
+            1 => Ok(HashScheme::Blake3),
+            other => Err(ClientError::InvalidHashScheme(other)),
+        }
+    }
+}
+
+/// A hashed, sorted, deduplicated, zero-padded contact list ready for
+/// encryption. The circuits reject lists not in this form.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HashedContacts {
+    /// Hashes in ascending order, zero-padded to `MAX_CONTACTS`
+    pub hashes: [u128; MAX_CONTACTS],
+    /// Number of used slots
+    pub count: u32,
+    /// Namespace the hashes were derived in
+    pub namespace: Namespace,
+    /// Scheme the hashes were derived with
+    pub scheme: HashScheme,
+}
+
+/// Normalize a contact identifier for consistent hashing:
+/// - Trim whitespace
+/// - Lowercase
+/// - Phone: strip non-digits, add country code if missing
+pub fn normalize_contact(contact: &str) -> String {
+    let mut normalized = contact.trim().to_lowercase();
+
+    // Phone number detection: only digits, whitespace, and + - ( )
+    let phone_like = !normalized.is_empty()
+        && normalized
+            .chars()
+            .all(|c| c.is_ascii_digit() || c.is_whitespace() || "-+()".contains(c));
+    if phone_like {
+        normalized.retain(|c| c.is_ascii_digit() || c == '+');
+        if !normalized.starts_with('+') {
+            normalized.insert_str(0, "+1"); // Default country code
+        }
+    }
+
+    normalized
+}
+
+/// Normalize a Solana name: trim, lowercase, drop a leading `@`, and
+/// default to the `.sol` TLD, so `@Bonfida` and `bonfida.sol` hash alike.
+/// A name with any other TLD (`miester.abc`) keeps it.
+pub fn normalize_name(name: &str) -> String {
+    let mut normalized = name.trim().trim_start_matches('@').trim().to_lowercase();
+    if !normalized.is_empty() && !normalized.contains('.') {
+        normalized.push_str(".sol");
+    }
+    normalized
+}
+
+/// Normalize `contact` the way `namespace` hashes it: names by
+/// `normalize_name`, everything else by `normalize_contact`.
+pub fn normalize_identifier(namespace: Namespace, contact: &str) -> String {
+    match namespace {
+        Namespace::SolName => normalize_name(contact),
+        _ => normalize_contact(contact),
+    }
+}
+
+/// Hash a single contact to u128: upper 128 bits (big-endian) of
+/// H(namespace_tag || ":" || normalize(contact)), where H is the
+/// scheme's hash function and normalize is `normalize_identifier`.
+pub fn hash_contact(scheme: HashScheme, namespace: Namespace, contact: &str) -> u128 {
+    let preimage = format!("{}:{}", namespace.tag(), normalize_identifier(namespace, contact));
+    let digest: [u8; 32] = match scheme {
+        HashScheme::Sha256 => Sha256::digest(preimage.as_bytes()).into(),
+        HashScheme::Blake3 => blake3::hash(preimage.as_bytes()).into(),
+    };
+    let mut upper = [0u8; 16];
+    upper.copy_from_slice(&digest[..16]);
+    u128::from_be_bytes(upper)
+}
+
+/// Hash a list of contacts, deduplicate after normalization, sort the
+/// hashes ascending, and pad to `MAX_CONTACTS` with zeros.
+pub fn hash_contact_list<S: AsRef<str>>(
+    scheme: HashScheme,
+    namespace: Namespace,
+    contacts: &[S],
+) -> Result<HashedContacts, ClientError> {
+    let mut unique: Vec<String> = Vec::new();
+    for contact in contacts {
+        let normalized = normalize_identifier(namespace, contact.as_ref());
+        if !normalized.is_empty() && !unique.contains(&normalized) {
+            unique.push(normalized);
+        }
+    }
+
+    if unique.len() > MAX_CONTACTS {
+        return Err(ClientError::TooManyContacts {
+            count: unique.len(),
+            max: MAX_CONTACTS,
+        });
+    }
+
+    let mut sorted: Vec<u128> = unique
+        .iter()
+        .map(|contact| hash_contact(scheme, namespace, contact))
+        .collect();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    let mut hashes = [0u128; MAX_CONTACTS];
+    hashes[..sorted.len()].copy_from_slice(&sorted);
+
+    Ok(HashedContacts {
+        hashes,
+        count: sorted.len() as u32,
+        namespace,
+        scheme,
+    })
+}
+
+/// The `index`th decoy `owner` (the submitting wallet) pads its lists
+/// with in session `session_id`: `DECOY_FLOOR` plus the top 64 bits of
+/// SHA-256(domain || session_id || owner || index). The session id
+/// salts them, so they differ per session and cannot be linked across
+/// sessions.
+pub fn decoy_hash(session_id: &[u8; 32], owner: &[u8; 32], index: u32) -> u128 {
+    let digest = Sha256::new()
+        .chain_update(DECOY_DOMAIN)
+        .chain_update(session_id)
+        .chain_update(owner)
+        .chain_update(index.to_le_bytes())
+        .finalize();
+    let mut upper = [0u8; 8];
+    upper.copy_from_slice(&digest[..8]);
+    DECOY_FLOOR | u128::from(u64::from_be_bytes(upper))
+}
+
+/// `contacts` with every free slot up to the session's `capacity` filled
+/// by a decoy and re-sorted, so the submission uses all the slots the
+/// session allows however short the real list is. The circuits drop
+/// decoys from matches and stored counts; `count` here covers them, as
+/// the circuits require of used slots. A list already past `capacity`
+/// is returned unpadded, for the circuit to reject.
+pub fn pad_with_decoys(
+    contacts: &HashedContacts,
+    session_id: &[u8; 32],
+    owner: &[u8; 32],
+    capacity: u8,
+) -> HashedContacts {
+    let mut hashes: Vec<u128> = contacts.hashes[..contacts.count as usize].to_vec();
+    let mut index = 0;
+    while hashes.len() < (capacity as usize).min(MAX_CONTACTS) {
+        let decoy = decoy_hash(session_id, owner, index);
+        if !hashes.contains(&decoy) {
+            hashes.push(decoy);
+        }
+        index += 1;
+    }
+    hashes.sort_unstable();
+
+    let mut padded = [0u128; MAX_CONTACTS];
+    padded[..hashes.len()].copy_from_slice(&hashes);
+    HashedContacts {
+        hashes: padded,
+        count: hashes.len() as u32,
+        namespace: contacts.namespace,
+        scheme: contacts.scheme,
+    }
+}
+
+/// Hash a set too large for one list into the sorted chunks of a
+/// committed set: hashes are deduplicated and sorted across the whole
+/// set, then cut into lists of `MAX_CONTACTS`, the last zero-padded. An
+/// empty set is one empty chunk.
+pub fn hash_contact_set<S: AsRef<str>>(
+    scheme: HashScheme,
+    namespace: Namespace,
+    contacts: &[S],
+) -> Result<Vec<HashedContacts>, ClientError> {
+    let mut sorted: Vec<u128> = contacts
+        .iter()
+        .map(|contact| normalize_identifier(namespace, contact.as_ref()))
+        .filter(|normalized| !normalized.is_empty())
+        .map(|normalized| hash_contact(scheme, namespace, &normalized))
+        .collect();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    let max = MAX_CONTACTS * MAX_SET_CHUNKS;
+    if sorted.len() > max {
+        return Err(ClientError::TooManyContacts {
+            count: sorted.len(),
+            max,
+        });
+    }
+
+    if sorted.is_empty() {
+        return Ok(vec![hash_contact_list::<&str>(scheme, namespace, &[])?]);
+    }
+    Ok(sorted
+        .chunks(MAX_CONTACTS)
+        .map(|chunk| {
+            let mut hashes = [0u128; MAX_CONTACTS];
+            hashes[..chunk.len()].copy_from_slice(chunk);
+            HashedContacts {
+                hashes,
+                count: chunk.len() as u32,
+                namespace,
+                scheme,
+            }
+        })
+        .collect())
+}
+
+/// Fold a 32-byte pubkey to u128: the XOR of its two 16-byte halves,
+/// each read big-endian. Must match `fold_pubkey` in the program, which
+/// folds the parties' own keys in mutual-follow sessions.
+pub fn fold_pubkey(pubkey: &[u8; 32]) -> u128 {
+    let mut high = [0u8; 16];
+    let mut low = [0u8; 16];
+    high.copy_from_slice(&pubkey[..16]);
+    low.copy_from_slice(&pubkey[16..]);
+    u128::from_be_bytes(high) ^ u128::from_be_bytes(low)
+}
+
+/// Fold a list of follow-target pubkeys for a mutual-follow session:
+/// fold each, deduplicate, sort ascending, and pad to `MAX_CONTACTS`
+/// with zeros. Follow lists are always in the wallet namespace; `scheme`
+/// is only carried along to match the session's declared scheme.
+pub fn fold_follow_list(scheme: HashScheme, targets: &[[u8; 32]]) -> Result<HashedContacts, ClientError> {
+    let mut sorted: Vec<u128> = targets.iter().map(fold_pubkey).filter(|h| *h != 0).collect();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    if sorted.len() > MAX_CONTACTS {
+        return Err(ClientError::TooManyContacts {
+            count: sorted.len(),
+            max: MAX_CONTACTS,
+        });
+    }
+
+    let mut hashes = [0u128; MAX_CONTACTS];
+    hashes[..sorted.len()].copy_from_slice(&sorted);
+
+    Ok(HashedContacts {
+        hashes,
+        count: sorted.len() as u32,
+        namespace: Namespace::Wallet,
+        scheme,
+    })
+}
+
+/// Hashed additions and removals for a subscription delta round.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HashedDelta {
+    /// Hashes to add, zero-padded to `MAX_DELTA`
+    pub added: [u128; MAX_DELTA],
+    /// Hashes to remove, zero-padded to `MAX_DELTA`
+    pub removed: [u128; MAX_DELTA],
+    /// Namespace the hashes were derived in
+    pub namespace: Namespace,
+    /// Scheme the hashes were derived with
+    pub scheme: HashScheme,
+}
+
+/// Hash a delta's added and removed contacts, deduplicating each side.
+pub fn hash_delta<S: AsRef<str>>(
+    scheme: HashScheme,
+    namespace: Namespace,
+    added: &[S],
+    removed: &[S],
+) -> Result<HashedDelta, ClientError> {
+    Ok(HashedDelta {
+        added: hash_padded(scheme, namespace, added)?,
+        removed: hash_padded(scheme, namespace, removed)?,
+        namespace,
+        scheme,
+    })
+}
+
+fn hash_padded<S: AsRef<str>>(
+    scheme: HashScheme,
+    namespace: Namespace,
+    contacts: &[S],
+) -> Result<[u128; MAX_DELTA], ClientError> {
+    let mut hashes: Vec<u128> = Vec::new();
+    for contact in contacts {
+        let hash = hash_contact(scheme, namespace, contact.as_ref());
+        if !normalize_identifier(namespace, contact.as_ref()).is_empty() && !hashes.contains(&hash) {
+            hashes.push(hash);
+        }
+    }
+    if hashes.len() > MAX_DELTA {
+        return Err(ClientError::TooManyContacts {
+            count: hashes.len(),
+            max: MAX_DELTA,
+        });
+    }
+
+    let mut padded = [0u128; MAX_DELTA];
+    padded[..hashes.len()].copy_from_slice(&hashes);
+    Ok(padded)
+}
+
+/// Given the original contact list and a set of matched hashes,
+/// return the human-readable contacts that matched.
+pub fn resolve_matches<S: AsRef<str>>(
+    scheme: HashScheme,
+    namespace: Namespace,
+    contacts: &[S],
+    matched: &[u128],
+) -> Vec<String> {
+    contacts
+        .iter()
+        .map(AsRef::as_ref)
+        .filter(|c| {
+            let h = hash_contact(scheme, namespace, c);
+            h != 0 && matched.contains(&h)
+        })
+        .map(str::to_string)
+        .collect()
+}
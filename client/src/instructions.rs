@@ -0,0 +1,2459 @@
+// Instruction builders for every program instruction.
+// Each returns a ready-to-sign `Instruction` with the full account list.
+// Builders for paying instructions take a `payer` ahead of the party:
+// pass the party's own key, or a sponsor's to fund rent and MPC fees on
+// the party's behalf (both must sign). Builders for session computations
+// take the session's `app_id`, whose credit account they are billed to.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use arcium_anchor::prelude::{ARCIUM_PROG_ID, LUT_PROGRAM_ID};
+use arcium_client::pda;
+use solana_sdk::{
+    bpf_loader_upgradeable,
+    ed25519_program,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+use private_contact_discovery::{
+    accounts, ed25519_instruction_data, gatekeeper, instruction, invite_claim, permit_message, set_leaf, set_proof,
+    set_root, soulbound, BatchItem, HookAccount, ProtocolConfig, ID as PROGRAM_ID, NO_APP_ID, PROGRAM_VERSION,
+};
+
+use crate::accounts::{
+    activity_log_pda, app_hook_pda, app_lookup_table, app_lut_pda, app_stats_pda, arcium_accounts, blocked_wallet_pda,
+    blocklist_pda, card_registry_pda, circuits, comp_def_account, config_pda, connection_mint_pda, connection_parties,
+    connection_pda, consent_record_pda, credit_pda, database_pda, deployment_pda, global_stats_pda, invite_claim_pda,
+    pair_quota_pda, pool_pda, session_pda, session_template_pda, sign_pda, staged_list_pda, state_handle_pda,
+    treasury_pda,
+};
+use crate::crypto::{
+    EncryptedCard, EncryptedContacts, EncryptedDelta, EncryptedMembershipBatch, EncryptedMembershipQuery,
+};
+use crate::hash::{MAX_CONTACTS, MAX_DELTA};
+
+/// Append `wallet`'s `BlockedWallet` address, which every instruction
+/// creating a session checks for Alice and every one recording Bob for
+/// both Bob and Alice.
+fn with_block_check(mut ix: Instruction, wallet: &Pubkey) -> Instruction {
+    ix.accounts.push(AccountMeta::new_readonly(blocked_wallet_pda(wallet), false));
+    ix
+}
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Billing accounts of a computation on a session of `app_id`.
+fn billing(app_id: &[u8; 32]) -> accounts::Billing {
+    accounts::Billing {
+        config: config_pda(),
+        credit: credit_pda(app_id),
+        treasury: treasury_pda(),
+    }
+}
+
+/// Initialize the computation definition for `circuit`.
+/// `lut_offset_slot` comes from the MXE account.
+pub fn init_comp_def(payer: Pubkey, circuit: &str, lut_offset_slot: u64) -> Instruction {
+    let mxe_account = pda::mxe_acc(&PROGRAM_ID);
+    // All init contexts share the same account layout
+    let metas = accounts::InitSessionCompDef {
+        payer,
+        mxe_account,
+        comp_def_account: comp_def_account(circuit),
+        address_lookup_table: pda::mxe_lut_acc(&mxe_account, lut_offset_slot),
+        lut_program: LUT_PROGRAM_ID,
+        arcium_program: ARCIUM_PROG_ID,
+        system_program: system_program::ID,
+    };
+
+    match circuit {
+        circuits::INIT_SESSION => build(metas, instruction::InitSessionCompDef {}),
+        circuits::SUBMIT_ALICE => build(metas, instruction::InitSubmitAliceCompDef {}),
+        circuits::SUBMIT_AND_MATCH => build(metas, instruction::InitSubmitAndMatchCompDef {}),
+        circuits::REVEAL_ALICE => build(metas, instruction::InitRevealAliceCompDef {}),
+        circuits::PUSH_DELTA => build(metas, instruction::InitPushDeltaCompDef {}),
+        circuits::CREATE_POOL => build(metas, instruction::InitCreatePoolCompDef {}),
+        circuits::MATCH_POOL => build(metas, instruction::InitMatchPoolCompDef {}),
+        circuits::SUBMIT_AND_SCORE => build(metas, instruction::InitSubmitAndScoreCompDef {}),
+        circuits::REVEAL_ALICE_SCORE => build(metas, instruction::InitRevealAliceScoreCompDef {}),
+        circuits::SUBMIT_BOB => build(metas, instruction::InitSubmitBobCompDef {}),
+        circuits::SUBMIT_CONNECTOR => build(metas, instruction::InitSubmitConnectorCompDef {}),
+        circuits::REVEAL_SECOND_DEGREE => build(metas, instruction::InitRevealSecondDegreeCompDef {}),
+        circuits::CREATE_BLOCKLIST => build(metas, instruction::InitCreateBlocklistCompDef {}),
+        circuits::CHECK_BLOCKLIST => build(metas, instruction::InitCheckBlocklistCompDef {}),
+        circuits::CHECK_FOLLOW => build(metas, instruction::InitSubmitAndCheckFollowCompDef {}),
+        circuits::REVEAL_BOB => build(metas, instruction::InitRevealBobCompDef {}),
+        circuits::PURGE_STATE => build(metas, instruction::InitPurgeSessionStateCompDef {}),
+        circuits::CREATE_CARD_REGISTRY => build(metas, instruction::InitCreateCardRegistryCompDef {}),
+        circuits::PUBLISH_CARD => build(metas, instruction::InitPublishContactCardCompDef {}),
+        circuits::MATCH_CARDS => build(metas, instruction::InitMatchContactCardsCompDef {}),
+        circuits::MATCH_SET_CHUNK => build(metas, instruction::InitMatchSetChunkCompDef {}),
+        circuits::LOAD_DATABASE_CHUNK => build(metas, instruction::InitLoadDatabaseChunkCompDef {}),
+        circuits::QUERY_PSI => build(metas, instruction::InitQueryPsiCompDef {}),
+        circuits::CHECK_MEMBERSHIP => build(metas, instruction::InitCheckMembershipCompDef {}),
+        circuits::CHECK_MEMBERSHIP_BATCH => build(metas, instruction::InitCheckMembershipBatchCompDef {}),
+        circuits::REVEAL_BOTH => build(metas, instruction::InitRevealBothCompDef {}),
+        circuits::SUBMIT_AND_MATCH_16 => build(metas, instruction::InitSubmitAndMatch16CompDef {}),
+        circuits::SUBMIT_AND_MATCH_8 => build(metas, instruction::InitSubmitAndMatch8CompDef {}),
+        circuits::SUBMIT_ALICE_16 => build(metas, instruction::InitSubmitAlice16CompDef {}),
+        circuits::SUBMIT_ALICE_8 => build(metas, instruction::InitSubmitAlice8CompDef {}),
+        circuits::INSTANT_MATCH => build(metas, instruction::InitInstantMatchCompDef {}),
+        circuits::INIT_AND_SUBMIT_ALICE => build(metas, instruction::InitAndSubmitAliceCompDef {}),
+        circuits::MIGRATE_STATE => build(metas, instruction::InitMigrateSessionStateCompDef {}),
+        other => panic!("unknown circuit {other}"),
+    }
+}
+
+/// Create the program-wide `GlobalStats` counters and the app-less
+/// `AppStats` bucket (once per deployment).
+pub fn init_global_stats(payer: Pubkey) -> Instruction {
+    build(
+        accounts::InitGlobalStats {
+            payer,
+            global_stats: global_stats_pda(),
+            unattributed_app_stats: app_stats_pda(&NO_APP_ID),
+            system_program: system_program::ID,
+        },
+        instruction::InitGlobalStats {},
+    )
+}
+
+/// Create the deployment's config, treasury, and app-less credit
+/// account. `admin` must be the program's upgrade authority.
+pub fn init_config(admin: Pubkey, computation_price: u64) -> Instruction {
+    build(
+        accounts::InitConfig {
+            admin,
+            config: config_pda(),
+            treasury: treasury_pda(),
+            unattributed_credit: credit_pda(&NO_APP_ID),
+            program: PROGRAM_ID,
+            program_data: Pubkey::find_program_address(&[PROGRAM_ID.as_ref()], &bpf_loader_upgradeable::ID).0,
+            system_program: system_program::ID,
+        },
+        instruction::InitConfig { computation_price },
+    )
+}
+
+/// Change the lamports each queued session computation costs.
+pub fn set_computation_price(admin: Pubkey, computation_price: u64) -> Instruction {
+    build(
+        accounts::UpdateConfig {
+            admin,
+            config: config_pda(),
+        },
+        instruction::SetComputationPrice { computation_price },
+    )
+}
+
+/// Set the basis points of each computation fee that top up the Arcium
+/// fee pool.
+pub fn set_fee_pool_share(admin: Pubkey, fee_pool_share_bps: u16) -> Instruction {
+    build(
+        accounts::UpdateConfig {
+            admin,
+            config: config_pda(),
+        },
+        instruction::SetFeePoolShare { fee_pool_share_bps },
+    )
+}
+
+/// Set the basis points of a failed computation's fee that are refunded.
+pub fn set_refund_share(admin: Pubkey, refund_bps: u16) -> Instruction {
+    build(
+        accounts::UpdateConfig {
+            admin,
+            config: config_pda(),
+        },
+        instruction::SetRefundShare { refund_bps },
+    )
+}
+
+/// Set the oldest client version `create_session` accepts.
+pub fn set_min_client_version(admin: Pubkey, min_client_version: u16) -> Instruction {
+    build(
+        accounts::UpdateConfig {
+            admin,
+            config: config_pda(),
+        },
+        instruction::SetMinClientVersion { min_client_version },
+    )
+}
+
+/// Cap the sessions two wallets may join per day, 0 for no cap.
+pub fn set_max_pair_sessions(admin: Pubkey, max_pair_sessions: u16) -> Instruction {
+    build(
+        accounts::UpdateConfig {
+            admin,
+            config: config_pda(),
+        },
+        instruction::SetMaxPairSessions { max_pair_sessions },
+    )
+}
+
+/// Cap the contact slots a wallet may submit against one other before it
+/// renews, 0 for no cap.
+pub fn set_query_budget(admin: Pubkey, query_budget: u32) -> Instruction {
+    build(
+        accounts::UpdateConfig {
+            admin,
+            config: config_pda(),
+        },
+        instruction::SetQueryBudget { query_budget },
+    )
+}
+
+/// Require both parties to hold an attestation from `gatekeeper` (a
+/// `gatekeeper::Gatekeeper`) within `network`, or `Gatekeeper::None`
+/// for none (as the config admin).
+pub fn set_gatekeeper(admin: Pubkey, gatekeeper: u8, network: Pubkey) -> Instruction {
+    build(
+        accounts::UpdateConfig {
+            admin,
+            config: config_pda(),
+        },
+        instruction::SetGatekeeper { gatekeeper, network },
+    )
+}
+
+/// Block `wallet` from creating and joining sessions (as the config
+/// admin, who funds the block).
+pub fn block_wallet(admin: Pubkey, wallet: Pubkey) -> Instruction {
+    build(
+        accounts::BlockWallet {
+            admin,
+            config: config_pda(),
+            blocked_wallet: blocked_wallet_pda(&wallet),
+            system_program: system_program::ID,
+        },
+        instruction::BlockWallet { wallet },
+    )
+}
+
+/// Lift the block on `wallet` (as the config admin, who gets the rent
+/// back).
+pub fn unblock_wallet(admin: Pubkey, wallet: Pubkey) -> Instruction {
+    build(
+        accounts::UnblockWallet {
+            admin,
+            config: config_pda(),
+            blocked_wallet: blocked_wallet_pda(&wallet),
+        },
+        instruction::UnblockWallet {},
+    )
+}
+
+/// Refund a failed session's computation fee to the credit of `app_id`,
+/// the session's app. Anyone may send it.
+pub fn claim_refund(session_id: &[u8; 32], app_id: &[u8; 32]) -> Instruction {
+    build(
+        accounts::ClaimRefund {
+            session: session_pda(session_id),
+            config: config_pda(),
+            credit: credit_pda(app_id),
+            treasury: treasury_pda(),
+        },
+        instruction::ClaimRefund {},
+    )
+}
+
+/// Open `app_id`'s credit account with `authority` controlling it.
+pub fn open_credit_account(payer: Pubkey, authority: Pubkey, app_id: [u8; 32]) -> Instruction {
+    build(
+        accounts::OpenCreditAccount {
+            authority,
+            payer,
+            credit: credit_pda(&app_id),
+            system_program: system_program::ID,
+        },
+        instruction::OpenCreditAccount { app_id },
+    )
+}
+
+/// Create `app_id`'s lookup table at `recent_slot` and fill it with the
+/// static Arcium accounts of `cluster_offset`. `authority` is the app's
+/// credit authority.
+pub fn create_app_lut(
+    payer: Pubkey,
+    authority: Pubkey,
+    cluster_offset: u32,
+    app_id: [u8; 32],
+    recent_slot: u64,
+) -> Instruction {
+    build(
+        accounts::CreateAppLut {
+            authority,
+            payer,
+            credit: credit_pda(&app_id),
+            app_lut: app_lut_pda(&app_id),
+            lookup_table: app_lookup_table(&app_id, recent_slot),
+            mxe_account: pda::mxe_acc(&PROGRAM_ID),
+            mempool_account: pda::mempool_acc(cluster_offset),
+            executing_pool: pda::execpool_acc(cluster_offset),
+            cluster_account: pda::cluster_acc(cluster_offset),
+            pool_account: pda::fee_pool_acc(),
+            clock_account: pda::clock_acc(),
+            lut_program: LUT_PROGRAM_ID,
+            system_program: system_program::ID,
+        },
+        instruction::CreateAppLut { app_id, recent_slot },
+    )
+}
+
+/// Add `addresses` to `app_id`'s lookup table `table`.
+pub fn extend_app_lut(
+    payer: Pubkey,
+    authority: Pubkey,
+    app_id: &[u8; 32],
+    table: Pubkey,
+    addresses: Vec<Pubkey>,
+) -> Instruction {
+    build(
+        accounts::ExtendAppLut {
+            authority,
+            payer,
+            credit: credit_pda(app_id),
+            app_lut: app_lut_pda(app_id),
+            lookup_table: table,
+            lut_program: LUT_PROGRAM_ID,
+            system_program: system_program::ID,
+        },
+        instruction::ExtendAppLut { addresses },
+    )
+}
+
+/// Add `amount` lamports to `app_id`'s credit.
+pub fn deposit_credit(depositor: Pubkey, app_id: &[u8; 32], amount: u64) -> Instruction {
+    build(
+        accounts::DepositCredit {
+            depositor,
+            credit: credit_pda(app_id),
+            system_program: system_program::ID,
+        },
+        instruction::DepositCredit { amount },
+    )
+}
+
+/// Withdraw `amount` lamports of unspent credit to its authority.
+pub fn withdraw_credit(authority: Pubkey, app_id: &[u8; 32], amount: u64) -> Instruction {
+    build(
+        accounts::WithdrawCredit {
+            authority,
+            credit: credit_pda(app_id),
+        },
+        instruction::WithdrawCredit { amount },
+    )
+}
+
+/// `mode` is a `SessionMode` value (0 = one-shot, 1 = subscription,
+/// 2 = score, 3 = friends-of-friends, 4 = mutual-follow); `namespace` is
+/// a `Namespace` and `hash_scheme` a `HashScheme` value. `public_count`
+/// opts Alice in to publishing the match count. `hide_counts` keeps
+/// either party's list size from the other. `join_deadline` is a
+/// unix timestamp after which Bob can no longer join, 0 for none.
+/// `reveal_after_slot` escrows the result until that slot, 0 for none.
+/// `app_id` picks the `AppStats` the session is metered against and
+/// the credit it is billed to (`NO_APP_ID` for none); the app's credit
+/// authority must be `alice` or `payer`, or be added with
+/// `with_app_authority`.
+/// `payer` funds the session and is refunded when it closes. The
+/// session is created as this SDK's `PROGRAM_VERSION`.
+/// `intended_bob` is the only wallet that may join, or the default key
+/// for anyone; naming one passes his `ConsentRecord`, which must allow
+/// `alice` or `app_id`. `invite_hash` (`invite_hash` over the session id
+/// and an invite code, or `NO_INVITE`) instead leaves the session to
+/// whoever redeems the code.
+pub fn create_session(
+    payer: Pubkey,
+    alice: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: [u8; 32],
+    app_id: [u8; 32],
+    pubkey: [u8; 32],
+    nonce: u128,
+    mode: u8,
+    namespace: u8,
+    hash_scheme: u8,
+    public_count: bool,
+    hide_counts: bool,
+    join_deadline: i64,
+    reveal_after_slot: u64,
+    capacity: u8,
+    memo: [u8; 32],
+    intended_bob: Pubkey,
+    invite_hash: [u8; 32],
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::INIT_SESSION, computation_offset);
+    let mut ix = build(
+        accounts::CreateSession {
+            alice,
+            payer,
+            session: session_pda(&session_id),
+            state_handle: state_handle_pda(&session_pda(&session_id)),
+            billing: billing(&app_id),
+            global_stats: global_stats_pda(),
+            app_stats: app_stats_pda(&app_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::CreateSession {
+            client_version: PROGRAM_VERSION,
+            computation_offset,
+            session_id,
+            app_id,
+            pubkey,
+            nonce,
+            mode,
+            namespace,
+            hash_scheme,
+            public_count,
+            hide_counts,
+            join_deadline,
+            reveal_after_slot,
+            capacity,
+            memo,
+            intended_bob,
+            invite_hash,
+        },
+    );
+    if intended_bob != Pubkey::default() {
+        ix.accounts
+            .push(AccountMeta::new_readonly(consent_record_pda(&intended_bob), false));
+    }
+    with_block_check(ix, &alice)
+}
+
+/// A session without `init_session`: takes `create_session`'s options,
+/// queues nothing, and leaves the MXE state to Alice's first submission,
+/// `init_and_submit_alice`.
+pub fn create_stateless_session(
+    payer: Pubkey,
+    alice: Pubkey,
+    session_id: [u8; 32],
+    app_id: [u8; 32],
+    mode: u8,
+    namespace: u8,
+    hash_scheme: u8,
+    public_count: bool,
+    hide_counts: bool,
+    join_deadline: i64,
+    reveal_after_slot: u64,
+    capacity: u8,
+    memo: [u8; 32],
+) -> Instruction {
+    let ix = build(
+        accounts::CreateStatelessSession {
+            alice,
+            payer,
+            session: session_pda(&session_id),
+            state_handle: state_handle_pda(&session_pda(&session_id)),
+            credit: credit_pda(&app_id),
+            config: config_pda(),
+            global_stats: global_stats_pda(),
+            app_stats: app_stats_pda(&app_id),
+            system_program: system_program::ID,
+        },
+        instruction::CreateStatelessSession {
+            client_version: PROGRAM_VERSION,
+            session_id,
+            app_id,
+            mode,
+            namespace,
+            hash_scheme,
+            public_count,
+            hide_counts,
+            join_deadline,
+            reveal_after_slot,
+            capacity,
+            memo,
+        },
+    );
+    with_block_check(ix, &alice)
+}
+
+/// Store a session template for `app_id`; `authority` must control the
+/// app's credit account. `join_ttl` is in seconds and `reveal_delay` in
+/// slots, 0 for none; `sponsor` is the only allowed payer, or the
+/// default key for any.
+pub fn create_session_template(
+    payer: Pubkey,
+    authority: Pubkey,
+    app_id: [u8; 32],
+    template_id: [u8; 32],
+    mode: u8,
+    namespace: u8,
+    hash_scheme: u8,
+    public_count: bool,
+    hide_counts: bool,
+    join_ttl: u32,
+    reveal_delay: u64,
+    sponsor: Pubkey,
+) -> Instruction {
+    build(
+        accounts::CreateSessionTemplate {
+            authority,
+            payer,
+            credit: credit_pda(&app_id),
+            template: session_template_pda(&app_id, &template_id),
+            system_program: system_program::ID,
+        },
+        instruction::CreateSessionTemplate {
+            app_id,
+            template_id,
+            mode,
+            namespace,
+            hash_scheme,
+            public_count,
+            hide_counts,
+            join_ttl,
+            reveal_delay,
+            sponsor,
+        },
+    )
+}
+
+/// Close a session template, refunding its rent to `authority`.
+pub fn close_session_template(authority: Pubkey, app_id: &[u8; 32], template_id: &[u8; 32]) -> Instruction {
+    build(
+        accounts::CloseSessionTemplate {
+            authority,
+            template: session_template_pda(app_id, template_id),
+        },
+        instruction::CloseSessionTemplate {},
+    )
+}
+
+/// Create a session configured by `app_id`'s template `template_id`.
+/// `payer` must be the template's sponsor if it names one.
+pub fn create_session_from_template(
+    payer: Pubkey,
+    alice: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: [u8; 32],
+    app_id: &[u8; 32],
+    template_id: &[u8; 32],
+    pubkey: [u8; 32],
+    nonce: u128,
+    memo: [u8; 32],
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::INIT_SESSION, computation_offset);
+    let ix = build(
+        accounts::CreateSessionFromTemplate {
+            alice,
+            payer,
+            template: session_template_pda(app_id, template_id),
+            session: session_pda(&session_id),
+            state_handle: state_handle_pda(&session_pda(&session_id)),
+            billing: billing(app_id),
+            global_stats: global_stats_pda(),
+            app_stats: app_stats_pda(app_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::CreateSessionFromTemplate {
+            client_version: PROGRAM_VERSION,
+            computation_offset,
+            session_id,
+            pubkey,
+            nonce,
+            memo,
+        },
+    );
+    with_block_check(ix, &alice)
+}
+
+/// Commit to `contacts` ahead of `submit_contacts_alice`.
+pub fn commit_contacts(alice: Pubkey, session_id: &[u8; 32], contacts: &EncryptedContacts) -> Instruction {
+    build(
+        accounts::CommitContacts {
+            alice,
+            session: session_pda(session_id),
+        },
+        instruction::CommitContacts {
+            commitment: contacts.commitment(),
+        },
+    )
+}
+
+/// Zero a session's stored MXE state, as either party of a matched
+/// session or as Alice once an unjoined session's deadline has passed.
+pub fn purge_session_state(
+    payer: Pubkey,
+    party: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: &[u8; 32],
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::PURGE_STATE, computation_offset);
+    build(
+        accounts::PurgeSessionState {
+            party,
+            payer,
+            session: session_pda(session_id),
+            state_handle: state_handle_pda(&session_pda(session_id)),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::PurgeSessionState { computation_offset },
+    )
+}
+
+/// Re-encode a session's stored MXE state into the current layout after
+/// an upgrade that changed it, as either party.
+pub fn migrate_session_state(
+    payer: Pubkey,
+    party: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: &[u8; 32],
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::MIGRATE_STATE, computation_offset);
+    build(
+        accounts::MigrateSessionState {
+            party,
+            payer,
+            session: session_pda(session_id),
+            state_handle: state_handle_pda(&session_pda(session_id)),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::MigrateSessionState { computation_offset },
+    )
+}
+
+/// Close a session once its state purge has landed. Only the session's
+/// Alice may send it; the rent goes back to `rent_payer`, which must be
+/// the session's `rent_recipient()`.
+pub fn close_session(alice: Pubkey, rent_payer: Pubkey, session_id: &[u8; 32]) -> Instruction {
+    build(
+        accounts::CloseSession {
+            alice,
+            rent_payer,
+            session: session_pda(session_id),
+            state_handle: state_handle_pda(&session_pda(session_id)),
+        },
+        instruction::CloseSession {},
+    )
+}
+
+/// Close abandoned sessions, paying `sweeper` `SWEEP_REWARD_BPS` of each
+/// one's rent. `sessions` pairs each session id with its rent recipient
+/// (`DiscoverySession::rent_recipient`); at most `MAX_SWEEP`. Anyone may
+/// send it.
+pub fn sweep_sessions(sweeper: Pubkey, sessions: &[([u8; 32], Pubkey)]) -> Instruction {
+    let mut ix = build(accounts::SweepSessions { sweeper }, instruction::SweepSessions {});
+    for (session_id, rent_payer) in sessions {
+        let session = session_pda(session_id);
+        ix.accounts.extend([
+            AccountMeta::new(*rent_payer, false),
+            AccountMeta::new(session, false),
+            AccountMeta::new(state_handle_pda(&session), false),
+        ]);
+    }
+    ix
+}
+
+/// Register the opaque notification target echoed in `MatchComplete`.
+/// Only the session's Alice may send it.
+pub fn register_notification(alice: Pubkey, session_id: &[u8; 32], notification_target: [u8; 32]) -> Instruction {
+    build(
+        accounts::RegisterNotification {
+            alice,
+            session: session_pda(session_id),
+        },
+        instruction::RegisterNotification { notification_target },
+    )
+}
+
+/// Register the program the session's match callback notifies, with
+/// the accounts passed to it after the `hook_authority`. Only the
+/// session's Alice may send it, before the match is queued; `payer`
+/// funds the `AppHook` account.
+pub fn register_app_hook(
+    payer: Pubkey,
+    alice: Pubkey,
+    session_id: &[u8; 32],
+    program: Pubkey,
+    accounts: Vec<HookAccount>,
+) -> Instruction {
+    build(
+        accounts::RegisterAppHook {
+            alice,
+            payer,
+            session: session_pda(session_id),
+            app_hook: app_hook_pda(&session_pda(session_id)),
+            system_program: system_program::ID,
+        },
+        instruction::RegisterAppHook { program, accounts },
+    )
+}
+
+/// Alice's or Bob's consent to attesting their connection; `party`
+/// signs.
+pub fn consent_connection(party: Pubkey, session_id: &[u8; 32]) -> Instruction {
+    build(
+        accounts::ConsentConnection {
+            party,
+            session: session_pda(session_id),
+        },
+        instruction::ConsentConnection {},
+    )
+}
+
+/// Attest the connection of a session's `alice` and `bob` once both
+/// consented, minting each a soulbound token. Anyone may send it;
+/// `payer` funds the attestation, the mint and both token accounts.
+pub fn attest_connection(payer: Pubkey, session_id: &[u8; 32], alice: Pubkey, bob: Pubkey) -> Instruction {
+    let (party_a, party_b) = connection_parties(alice, bob);
+    let mint = connection_mint_pda(&party_a, &party_b);
+    build(
+        accounts::AttestConnection {
+            payer,
+            session: session_pda(session_id),
+            attestation: connection_pda(&party_a, &party_b),
+            mint,
+            party_a_wallet: party_a,
+            party_b_wallet: party_b,
+            party_a_token: soulbound::associated_token_address(&party_a, &mint),
+            party_b_token: soulbound::associated_token_address(&party_b, &mint),
+            token_program: soulbound::TOKEN_2022_PROGRAM_ID,
+            associated_token_program: soulbound::ASSOCIATED_TOKEN_PROGRAM_ID,
+            system_program: system_program::ID,
+        },
+        instruction::AttestConnection { party_a, party_b },
+    )
+}
+
+/// Open the session quota of wallets `a` and `b`, in either order;
+/// `payer` funds it.
+pub fn open_pair_quota(payer: Pubkey, a: Pubkey, b: Pubkey) -> Instruction {
+    let (party_a, party_b) = connection_parties(a, b);
+    build(
+        accounts::OpenPairQuota {
+            payer,
+            pair_quota: pair_quota_pda(&party_a, &party_b),
+            system_program: system_program::ID,
+        },
+        instruction::OpenPairQuota { party_a, party_b },
+    )
+}
+
+/// Append `authority`, the app's credit authority, to `ix` as a
+/// read-only signer, for `create_session`, `create_stateless_session`,
+/// `check_membership` and `check_membership_batch` when it is neither
+/// the party nor the payer. The app is billed only with its signature.
+pub fn with_app_authority(mut ix: Instruction, authority: Pubkey) -> Instruction {
+    ix.accounts.push(AccountMeta::new_readonly(authority, true));
+    ix
+}
+
+/// Append the `PairQuota` of `alice` and `bob` to `ix`, writable, so the
+/// instruction recording Bob counts the session in it. Needed by
+/// `submit_and_match` and its tiers, `commit_set`, `submit_and_score`,
+/// `submit_contacts_bob` and `submit_and_check_follow` while the config
+/// caps sessions per pair or sets a query budget.
+pub fn with_pair_quota(mut ix: Instruction, alice: Pubkey, bob: Pubkey) -> Instruction {
+    ix.accounts.push(AccountMeta::new(pair_quota_pda(&alice, &bob), false));
+    ix
+}
+
+/// Append each of `wallets`' attestation under `config`'s gatekeeper
+/// to `ix`, read-only. The instructions `with_pair_quota` lists need
+/// both parties' while the config sets a gatekeeper; with none set,
+/// `ix` is returned as is.
+pub fn with_attestations(mut ix: Instruction, config: &ProtocolConfig, wallets: &[Pubkey]) -> Instruction {
+    for wallet in wallets {
+        if let Some(address) = gatekeeper::attestation_address(config, wallet) {
+            ix.accounts.push(AccountMeta::new_readonly(address, false));
+        }
+    }
+    ix
+}
+
+/// Let `counterparties`, as Alice, and sessions of `apps` create
+/// sessions naming `wallet` as their Bob, replacing any earlier lists.
+pub fn set_consent_record(wallet: Pubkey, counterparties: Vec<Pubkey>, apps: Vec<[u8; 32]>) -> Instruction {
+    build(
+        accounts::SetConsentRecord {
+            wallet,
+            consent_record: consent_record_pda(&wallet),
+            system_program: system_program::ID,
+        },
+        instruction::SetConsentRecord { counterparties, apps },
+    )
+}
+
+/// Close `wallet`'s consent record, refunding the rent to it.
+pub fn close_consent_record(wallet: Pubkey) -> Instruction {
+    build(
+        accounts::CloseConsentRecord {
+            wallet,
+            consent_record: consent_record_pda(&wallet),
+        },
+        instruction::CloseConsentRecord {},
+    )
+}
+
+/// `target` lets `querier` query it again, resetting the slots `querier`
+/// has submitted to 0.
+pub fn renew_query_budget(target: Pubkey, querier: Pubkey) -> Instruction {
+    build(
+        accounts::RenewQueryBudget {
+            target,
+            pair_quota: pair_quota_pda(&target, &querier),
+        },
+        instruction::RenewQueryBudget {},
+    )
+}
+
+/// Open `wallet`'s activity log; `payer` funds it.
+pub fn open_activity_log(payer: Pubkey, wallet: Pubkey) -> Instruction {
+    build(
+        accounts::OpenActivityLog {
+            wallet,
+            payer,
+            activity_log: activity_log_pda(&wallet),
+            system_program: system_program::ID,
+        },
+        instruction::OpenActivityLog {},
+    )
+}
+
+/// Close `wallet`'s activity log, refunding the rent to it.
+pub fn close_activity_log(wallet: Pubkey) -> Instruction {
+    build(
+        accounts::CloseActivityLog {
+            wallet,
+            activity_log: activity_log_pda(&wallet),
+        },
+        instruction::CloseActivityLog {},
+    )
+}
+
+/// Append the activity logs of `wallets` to `ix`, writable, so the
+/// session instruction records itself in them. For `create_session`
+/// and its variants, Alice's submissions, `submit_and_match` (whose
+/// callback also records the outcome; pass both parties), and
+/// `close_session`. Logs that were never opened are skipped.
+pub fn with_activity_logs(mut ix: Instruction, wallets: &[Pubkey]) -> Instruction {
+    ix.accounts.extend(
+        wallets
+            .iter()
+            .map(|wallet| AccountMeta::new(activity_log_pda(wallet), false)),
+    );
+    ix
+}
+
+/// Record the deployment's versions, comp def readiness and MXE in the
+/// `DeploymentRegistry`, creating it on first use; `payer` funds it.
+/// Every circuit's comp def is passed, as the program requires.
+pub fn sync_deployment(payer: Pubkey) -> Instruction {
+    let mut ix = build(
+        accounts::SyncDeployment {
+            payer,
+            registry: deployment_pda(),
+            mxe_account: pda::mxe_acc(&PROGRAM_ID),
+            system_program: system_program::ID,
+        },
+        instruction::SyncDeployment {},
+    );
+    ix.accounts.extend(
+        circuits::ALL
+            .iter()
+            .map(|circuit| AccountMeta::new_readonly(comp_def_account(circuit), false)),
+    );
+    ix
+}
+
+/// Commit `bob` to a session's invite `code` without revealing it,
+/// ahead of `redeem_invite` in a later slot.
+pub fn claim_invite(bob: Pubkey, session_id: &[u8; 32], code: &str) -> Instruction {
+    let session = session_pda(session_id);
+    build(
+        accounts::ClaimInvite {
+            bob,
+            session,
+            invite_claim: invite_claim_pda(&session, &bob),
+            system_program: system_program::ID,
+        },
+        instruction::ClaimInvite {
+            commitment: invite_claim(session_id, code.as_bytes(), &bob),
+        },
+    )
+}
+
+/// Redeem the invite `code` `bob` claimed with `claim_invite`, making
+/// him the session's only joiner. Send it in the same transaction as
+/// his join.
+pub fn redeem_invite(bob: Pubkey, session_id: &[u8; 32], code: &str) -> Instruction {
+    let session = session_pda(session_id);
+    build(
+        accounts::RedeemInvite {
+            bob,
+            session,
+            invite_claim: invite_claim_pda(&session, &bob),
+        },
+        instruction::RedeemInvite { code: code.to_string() },
+    )
+}
+
+/// Re-emit a session's status as `SessionHeartbeat`. Anyone may send it,
+/// once per `PING_INTERVAL_SLOTS` per session.
+pub fn ping_session(session_id: &[u8; 32]) -> Instruction {
+    build(
+        accounts::PingSession {
+            session: session_pda(session_id),
+        },
+        instruction::PingSession {},
+    )
+}
+
+/// Report a session's status, parties, pending computation, and which
+/// computation definitions are ready. Meant for simulation only; see
+/// `rpc::view_session`. Every circuit's comp def is passed so all of
+/// them are reported.
+pub fn view_session(session_id: &[u8; 32]) -> Instruction {
+    let mut ix = build(
+        accounts::ViewSession {
+            session: session_pda(session_id),
+        },
+        instruction::ViewSession {},
+    );
+    ix.accounts.extend(
+        circuits::ALL
+            .iter()
+            .map(|circuit| AccountMeta::new_readonly(comp_def_account(circuit), false)),
+    );
+    ix
+}
+
+/// Queue up to `MAX_BATCH` computations in one transaction. Each item
+/// pairs its `BatchItem` with the instruction that would queue it alone,
+/// built with the same computation offset; that instruction's accounts
+/// are passed on, in order, and its signers must sign the batch.
+pub fn queue_batch(relayer: Pubkey, items: &[(BatchItem, Instruction)]) -> Instruction {
+    let mut ix = build(
+        accounts::QueueBatch { relayer },
+        instruction::QueueBatch {
+            items: items.iter().map(|(item, _)| *item).collect(),
+        },
+    );
+    for (_, single) in items {
+        ix.accounts.extend(single.accounts.iter().cloned());
+    }
+    ix
+}
+
+/// Grow a session created under an older layout to the current one.
+/// Only the session's Alice may send it; `payer` funds the extra rent.
+pub fn realloc_session(payer: Pubkey, alice: Pubkey, session_id: &[u8; 32]) -> Instruction {
+    build(
+        accounts::ReallocSession {
+            alice,
+            payer,
+            session: session_pda(session_id),
+            state_handle: state_handle_pda(&session_pda(session_id)),
+            system_program: system_program::ID,
+        },
+        instruction::ReallocSession {},
+    )
+}
+
+/// Alice's submission circuit for a session created with `capacity`.
+pub fn alice_circuit(capacity: u8) -> &'static str {
+    match capacity {
+        8 => circuits::SUBMIT_ALICE_8,
+        16 => circuits::SUBMIT_ALICE_16,
+        _ => circuits::SUBMIT_ALICE,
+    }
+}
+
+/// `capacity` is the session's (`DiscoverySession::capacity()`) and
+/// picks the instruction of its tier.
+pub fn submit_contacts_alice(
+    payer: Pubkey,
+    alice: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: &[u8; 32],
+    app_id: &[u8; 32],
+    contacts: &EncryptedContacts,
+    capacity: u8,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, alice_circuit(capacity), computation_offset);
+    // All tiers' contexts share the same account layout
+    let metas = accounts::SubmitContactsAlice {
+        alice,
+        payer,
+        session: session_pda(session_id),
+        state_handle: state_handle_pda(&session_pda(session_id)),
+        billing: billing(app_id),
+        sign_pda_account: sign_pda(),
+        mxe_account: a.mxe_account,
+        mempool_account: a.mempool_account,
+        executing_pool: a.executing_pool,
+        computation_account: a.computation_account,
+        comp_def_account: a.comp_def_account,
+        cluster_account: a.cluster_account,
+        pool_account: a.pool_account,
+        clock_account: a.clock_account,
+        system_program: system_program::ID,
+        arcium_program: ARCIUM_PROG_ID,
+    };
+
+    match capacity {
+        8 => build(
+            metas,
+            instruction::SubmitContactsAlice8 {
+                computation_offset,
+                hash_scheme: contacts.hash_scheme,
+                encrypted_hashes: contacts.encrypted_hashes,
+                encrypted_count: contacts.encrypted_count,
+                encrypted_namespace: contacts.encrypted_namespace,
+                pubkey: contacts.pubkey,
+                nonce: contacts.nonce,
+            },
+        ),
+        16 => build(
+            metas,
+            instruction::SubmitContactsAlice16 {
+                computation_offset,
+                hash_scheme: contacts.hash_scheme,
+                encrypted_hashes: contacts.encrypted_hashes,
+                encrypted_count: contacts.encrypted_count,
+                encrypted_namespace: contacts.encrypted_namespace,
+                pubkey: contacts.pubkey,
+                nonce: contacts.nonce,
+            },
+        ),
+        _ => build(
+            metas,
+            instruction::SubmitContactsAlice {
+                computation_offset,
+                hash_scheme: contacts.hash_scheme,
+                encrypted_hashes: contacts.encrypted_hashes,
+                encrypted_count: contacts.encrypted_count,
+                encrypted_namespace: contacts.encrypted_namespace,
+                pubkey: contacts.pubkey,
+                nonce: contacts.nonce,
+            },
+        ),
+    }
+}
+
+/// Alice's first submission to a stateless session, which also creates
+/// its MXE state. Later submissions use `submit_contacts_alice`.
+pub fn init_and_submit_alice(
+    payer: Pubkey,
+    alice: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: &[u8; 32],
+    app_id: &[u8; 32],
+    contacts: &EncryptedContacts,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::INIT_AND_SUBMIT_ALICE, computation_offset);
+    build(
+        accounts::InitAndSubmitAlice {
+            alice,
+            payer,
+            session: session_pda(session_id),
+            state_handle: state_handle_pda(&session_pda(session_id)),
+            billing: billing(app_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::InitAndSubmitAlice {
+            computation_offset,
+            hash_scheme: contacts.hash_scheme,
+            encrypted_hashes: contacts.encrypted_hashes,
+            encrypted_count: contacts.encrypted_count,
+            encrypted_namespace: contacts.encrypted_namespace,
+            pubkey: contacts.pubkey,
+            nonce: contacts.nonce,
+        },
+    )
+}
+
+/// The message Alice signs off-chain to let a relayer submit `contacts`
+/// to `session_id` for her until unix time `expiry`.
+pub fn alice_permit_message(session_id: &[u8; 32], contacts: &EncryptedContacts, expiry: i64) -> Vec<u8> {
+    permit_message(session_id, &contacts.commitment(), expiry)
+}
+
+/// Submit Alice's contacts as a relayer (`payer`) holding her signature
+/// over `alice_permit_message`. Returns the Ed25519 verification
+/// instruction followed by the program instruction; send both, in that
+/// order, in one transaction. Only `payer` signs it.
+pub fn submit_contacts_with_permit(
+    payer: Pubkey,
+    alice: Pubkey,
+    signature: &[u8; 64],
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: &[u8; 32],
+    app_id: &[u8; 32],
+    contacts: &EncryptedContacts,
+    expiry: i64,
+) -> [Instruction; 2] {
+    let verify = Instruction {
+        program_id: ed25519_program::ID,
+        accounts: Vec::new(),
+        data: ed25519_instruction_data(&alice, signature, &alice_permit_message(session_id, contacts, expiry)),
+    };
+    let a = arcium_accounts(cluster_offset, circuits::SUBMIT_ALICE, computation_offset);
+    let submit = build(
+        accounts::SubmitContactsWithPermit {
+            alice,
+            payer,
+            session: session_pda(session_id),
+            state_handle: state_handle_pda(&session_pda(session_id)),
+            billing: billing(app_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            instructions_sysvar: solana_sdk::sysvar::instructions::ID,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::SubmitContactsWithPermit {
+            computation_offset,
+            hash_scheme: contacts.hash_scheme,
+            encrypted_hashes: contacts.encrypted_hashes,
+            encrypted_count: contacts.encrypted_count,
+            encrypted_namespace: contacts.encrypted_namespace,
+            pubkey: contacts.pubkey,
+            nonce: contacts.nonce,
+            expiry,
+        },
+    );
+    [verify, submit]
+}
+
+/// The match circuit of a session created with `capacity`.
+pub fn match_circuit(capacity: u8) -> &'static str {
+    match capacity {
+        8 => circuits::SUBMIT_AND_MATCH_8,
+        16 => circuits::SUBMIT_AND_MATCH_16,
+        _ => circuits::SUBMIT_AND_MATCH,
+    }
+}
+
+/// `public_count` opts Bob in to publishing the match count. `alice` is
+/// the session's Alice, whose block is checked along with Bob's.
+/// `capacity` is the session's (`DiscoverySession::capacity()`) and
+/// picks the instruction of its tier. The session's `AppHook` PDA
+/// follows as a remaining account, which the program reads only if
+/// Alice registered a hook.
+pub fn submit_and_match(
+    payer: Pubkey,
+    bob: Pubkey,
+    alice: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: &[u8; 32],
+    app_id: &[u8; 32],
+    contacts: &EncryptedContacts,
+    capacity: u8,
+    public_count: bool,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, match_circuit(capacity), computation_offset);
+    // All tiers' contexts share the same account layout
+    let metas = accounts::SubmitAndMatch {
+        bob,
+        payer,
+        session: session_pda(session_id),
+        state_handle: state_handle_pda(&session_pda(session_id)),
+        billing: billing(app_id),
+        sign_pda_account: sign_pda(),
+        mxe_account: a.mxe_account,
+        mempool_account: a.mempool_account,
+        executing_pool: a.executing_pool,
+        computation_account: a.computation_account,
+        comp_def_account: a.comp_def_account,
+        cluster_account: a.cluster_account,
+        pool_account: a.pool_account,
+        clock_account: a.clock_account,
+        system_program: system_program::ID,
+        arcium_program: ARCIUM_PROG_ID,
+    };
+
+    let mut ix = match capacity {
+        8 => build(
+            metas,
+            instruction::SubmitAndMatch8 {
+                computation_offset,
+                hash_scheme: contacts.hash_scheme,
+                encrypted_hashes: contacts.encrypted_hashes,
+                encrypted_count: contacts.encrypted_count,
+                encrypted_namespace: contacts.encrypted_namespace,
+                pubkey: contacts.pubkey,
+                nonce: contacts.nonce,
+                public_count,
+            },
+        ),
+        16 => build(
+            metas,
+            instruction::SubmitAndMatch16 {
+                computation_offset,
+                hash_scheme: contacts.hash_scheme,
+                encrypted_hashes: contacts.encrypted_hashes,
+                encrypted_count: contacts.encrypted_count,
+                encrypted_namespace: contacts.encrypted_namespace,
+                pubkey: contacts.pubkey,
+                nonce: contacts.nonce,
+                public_count,
+            },
+        ),
+        _ => build(
+            metas,
+            instruction::SubmitAndMatch {
+                computation_offset,
+                hash_scheme: contacts.hash_scheme,
+                encrypted_hashes: contacts.encrypted_hashes,
+                encrypted_count: contacts.encrypted_count,
+                encrypted_namespace: contacts.encrypted_namespace,
+                pubkey: contacts.pubkey,
+                nonce: contacts.nonce,
+                public_count,
+            },
+        ),
+    };
+    ix.accounts.push(AccountMeta::new_readonly(app_hook_pda(&session_pda(session_id)), false));
+    with_block_check(with_block_check(ix, &bob), &alice)
+}
+
+/// Merkle leaves of a committed set's encrypted `chunks`, in order.
+pub fn set_leaves(chunks: &[EncryptedContacts]) -> Vec<[u8; 32]> {
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| set_leaf(i as u8, &chunk.commitment()))
+        .collect()
+}
+
+/// Commit a `CommittedSet` session to Bob's encrypted `chunks`. `alice`
+/// is the session's Alice, whose block is checked along with Bob's.
+pub fn commit_set(bob: Pubkey, alice: Pubkey, session_id: &[u8; 32], chunks: &[EncryptedContacts]) -> Instruction {
+    let ix = build(
+        accounts::CommitSet {
+            bob,
+            session: session_pda(session_id),
+            config: config_pda(),
+        },
+        instruction::CommitSet {
+            set_root: set_root(&set_leaves(chunks)),
+            chunk_count: chunks.len() as u8,
+        },
+    );
+    with_block_check(with_block_check(ix, &bob), &alice)
+}
+
+/// Match chunk `index` of the set `chunks` that `commit_set` committed
+/// to, with its Merkle witness.
+pub fn submit_set_chunk(
+    payer: Pubkey,
+    bob: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: &[u8; 32],
+    app_id: &[u8; 32],
+    chunks: &[EncryptedContacts],
+    index: usize,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::MATCH_SET_CHUNK, computation_offset);
+    let chunk = &chunks[index];
+    build(
+        accounts::SubmitSetChunk {
+            bob,
+            payer,
+            session: session_pda(session_id),
+            state_handle: state_handle_pda(&session_pda(session_id)),
+            billing: billing(app_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::SubmitSetChunk {
+            computation_offset,
+            chunk_index: index as u8,
+            proof: set_proof(&set_leaves(chunks), index),
+            hash_scheme: chunk.hash_scheme,
+            encrypted_hashes: chunk.encrypted_hashes,
+            encrypted_count: chunk.encrypted_count,
+            encrypted_namespace: chunk.encrypted_namespace,
+            pubkey: chunk.pubkey,
+            nonce: chunk.nonce,
+        },
+    )
+}
+
+/// Reveal page `page` of Alice's matches; page 0 holds the first 32.
+pub fn reveal_alice_matches(
+    payer: Pubkey,
+    alice: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: &[u8; 32],
+    app_id: &[u8; 32],
+    pubkey: [u8; 32],
+    nonce: u128,
+    page: u8,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::REVEAL_ALICE, computation_offset);
+    build(
+        accounts::RevealAliceMatches {
+            alice,
+            payer,
+            session: session_pda(session_id),
+            state_handle: state_handle_pda(&session_pda(session_id)),
+            billing: billing(app_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::RevealAliceMatches {
+            computation_offset,
+            pubkey,
+            nonce,
+            page,
+        },
+    )
+}
+
+/// Reveal page `page` of Bob's matches; page 0 is the one
+/// `submit_and_match` already returned.
+pub fn reveal_bob_matches(
+    payer: Pubkey,
+    bob: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: &[u8; 32],
+    app_id: &[u8; 32],
+    pubkey: [u8; 32],
+    nonce: u128,
+    page: u8,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::REVEAL_BOB, computation_offset);
+    build(
+        accounts::RevealBobMatches {
+            bob,
+            payer,
+            session: session_pda(session_id),
+            state_handle: state_handle_pda(&session_pda(session_id)),
+            billing: billing(app_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::RevealBobMatches {
+            computation_offset,
+            pubkey,
+            nonce,
+            page,
+        },
+    )
+}
+
+/// Reveal page `page` of both parties' matches in one computation. Alice
+/// and Bob must both sign; each side is encrypted to that party's key.
+pub fn reveal_both(
+    payer: Pubkey,
+    alice: Pubkey,
+    bob: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: &[u8; 32],
+    app_id: &[u8; 32],
+    alice_pubkey: [u8; 32],
+    alice_nonce: u128,
+    bob_pubkey: [u8; 32],
+    bob_nonce: u128,
+    page: u8,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::REVEAL_BOTH, computation_offset);
+    build(
+        accounts::RevealBoth {
+            alice,
+            bob,
+            payer,
+            session: session_pda(session_id),
+            state_handle: state_handle_pda(&session_pda(session_id)),
+            billing: billing(app_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::RevealBoth {
+            computation_offset,
+            alice_pubkey,
+            alice_nonce,
+            bob_pubkey,
+            bob_nonce,
+            page,
+        },
+    )
+}
+
+/// Push a delta round to a matched subscription session, as either party.
+pub fn push_delta(
+    payer: Pubkey,
+    party: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: &[u8; 32],
+    app_id: &[u8; 32],
+    delta: &EncryptedDelta,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::PUSH_DELTA, computation_offset);
+    build(
+        accounts::PushDelta {
+            party,
+            payer,
+            session: session_pda(session_id),
+            state_handle: state_handle_pda(&session_pda(session_id)),
+            billing: billing(app_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::PushDelta {
+            computation_offset,
+            hash_scheme: delta.hash_scheme,
+            encrypted_added: delta.encrypted_added,
+            encrypted_removed: delta.encrypted_removed,
+            encrypted_namespace: delta.encrypted_namespace,
+            pubkey: delta.pubkey,
+            nonce: delta.nonce,
+        },
+    )
+}
+
+/// Publish `contacts` as matchmaking pool `pool_id`, hashed in `namespace`.
+pub fn create_pool(
+    payer: Pubkey,
+    host: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    pool_id: [u8; 32],
+    namespace: u8,
+    contacts: &EncryptedContacts,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::CREATE_POOL, computation_offset);
+    build(
+        accounts::CreatePool {
+            host,
+            payer,
+            pool: pool_pda(&pool_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::CreatePool {
+            computation_offset,
+            pool_id,
+            namespace,
+            hash_scheme: contacts.hash_scheme,
+            encrypted_hashes: contacts.encrypted_hashes,
+            encrypted_count: contacts.encrypted_count,
+            encrypted_namespace: contacts.encrypted_namespace,
+            pubkey: contacts.pubkey,
+            nonce: contacts.nonce,
+        },
+    )
+}
+
+pub fn match_against_pool(
+    payer: Pubkey,
+    user: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    pool_id: &[u8; 32],
+    contacts: &EncryptedContacts,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::MATCH_POOL, computation_offset);
+    build(
+        accounts::MatchAgainstPool {
+            user,
+            payer,
+            pool: pool_pda(pool_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::MatchAgainstPool {
+            computation_offset,
+            hash_scheme: contacts.hash_scheme,
+            encrypted_hashes: contacts.encrypted_hashes,
+            encrypted_count: contacts.encrypted_count,
+            encrypted_namespace: contacts.encrypted_namespace,
+            pubkey: contacts.pubkey,
+            nonce: contacts.nonce,
+        },
+    )
+}
+
+/// Stage `owner`'s `contacts`, encrypted to the owner's key, for an
+/// instant match with `peer`.
+pub fn stage_list(payer: Pubkey, owner: Pubkey, peer: Pubkey, contacts: &EncryptedContacts) -> Instruction {
+    build(
+        accounts::StageList {
+            owner,
+            payer,
+            staged_list: staged_list_pda(&owner, &peer),
+            system_program: system_program::ID,
+        },
+        instruction::StageList {
+            peer,
+            hash_scheme: contacts.hash_scheme,
+            encrypted_hashes: contacts.encrypted_hashes,
+            encrypted_count: contacts.encrypted_count,
+            encrypted_namespace: contacts.encrypted_namespace,
+            pubkey: contacts.pubkey,
+            nonce: contacts.nonce,
+        },
+    )
+}
+
+/// Match the lists `alice` and `bob` staged for each other. Anyone may
+/// send it; `payer` covers the MPC fee.
+pub fn instant_match(
+    payer: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    alice: Pubkey,
+    bob: Pubkey,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::INSTANT_MATCH, computation_offset);
+    build(
+        accounts::InstantMatch {
+            payer,
+            alice_list: staged_list_pda(&alice, &bob),
+            bob_list: staged_list_pda(&bob, &alice),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::InstantMatch { computation_offset },
+    )
+}
+
+/// Withdraw `owner`'s list staged for `peer`, refunding its rent to
+/// `rent_payer`, who paid for it.
+pub fn close_staged_list(owner: Pubkey, peer: Pubkey, rent_payer: Pubkey) -> Instruction {
+    build(
+        accounts::CloseStagedList {
+            owner,
+            rent_payer,
+            staged_list: staged_list_pda(&owner, &peer),
+        },
+        instruction::CloseStagedList {},
+    )
+}
+
+/// Open an empty server database `database_id` (epoch 0) for identifiers
+/// hashed in `namespace` with `hash_scheme`. `provider` loads and seals it.
+pub fn create_database(
+    payer: Pubkey,
+    provider: Pubkey,
+    database_id: [u8; 32],
+    namespace: u8,
+    hash_scheme: u8,
+) -> Instruction {
+    build(
+        accounts::CreateDatabase {
+            provider,
+            payer,
+            database: database_pda(&database_id, 0),
+            system_program: system_program::ID,
+        },
+        instruction::CreateDatabase {
+            database_id,
+            namespace,
+            hash_scheme,
+        },
+    )
+}
+
+/// Start epoch `epoch` of database `database_id`, whose live epoch is
+/// `epoch - 1`.
+pub fn stage_database_epoch(payer: Pubkey, provider: Pubkey, database_id: [u8; 32], epoch: u32) -> Instruction {
+    build(
+        accounts::StageDatabaseEpoch {
+            provider,
+            payer,
+            live_database: database_pda(&database_id, epoch - 1),
+            database: database_pda(&database_id, epoch),
+            system_program: system_program::ID,
+        },
+        instruction::StageDatabaseEpoch { database_id, epoch },
+    )
+}
+
+/// Swap sealed epoch `epoch` of database `database_id` in for epoch
+/// `epoch - 1`.
+pub fn rotate_database_epoch(provider: Pubkey, database_id: &[u8; 32], epoch: u32) -> Instruction {
+    build(
+        accounts::RotateDatabaseEpoch {
+            provider,
+            live_database: database_pda(database_id, epoch - 1),
+            next_database: database_pda(database_id, epoch),
+        },
+        instruction::RotateDatabaseEpoch {},
+    )
+}
+
+/// Load `chunk` (one `hash_contact_set` chunk) into the next free slot
+/// of epoch `epoch` of database `database_id`.
+pub fn load_database_chunk(
+    payer: Pubkey,
+    provider: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    database_id: &[u8; 32],
+    epoch: u32,
+    chunk: &EncryptedContacts,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::LOAD_DATABASE_CHUNK, computation_offset);
+    build(
+        accounts::LoadDatabaseChunk {
+            provider,
+            payer,
+            database: database_pda(database_id, epoch),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::LoadDatabaseChunk {
+            computation_offset,
+            hash_scheme: chunk.hash_scheme,
+            encrypted_hashes: chunk.encrypted_hashes,
+            encrypted_count: chunk.encrypted_count,
+            encrypted_namespace: chunk.encrypted_namespace,
+            pubkey: chunk.pubkey,
+            nonce: chunk.nonce,
+        },
+    )
+}
+
+/// Seal epoch `epoch` of database `database_id`: epoch 0 takes queries
+/// at once, later epochs once rotated in.
+pub fn seal_database(provider: Pubkey, database_id: &[u8; 32], epoch: u32) -> Instruction {
+    build(
+        accounts::SealDatabase {
+            provider,
+            database: database_pda(database_id, epoch),
+        },
+        instruction::SealDatabase {},
+    )
+}
+
+/// Query `contacts` against live epoch `epoch` of database `database_id`.
+pub fn query_psi(
+    payer: Pubkey,
+    user: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    database_id: &[u8; 32],
+    epoch: u32,
+    contacts: &EncryptedContacts,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::QUERY_PSI, computation_offset);
+    build(
+        accounts::QueryPsi {
+            user,
+            payer,
+            database: database_pda(database_id, epoch),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::QueryPsi {
+            computation_offset,
+            hash_scheme: contacts.hash_scheme,
+            encrypted_hashes: contacts.encrypted_hashes,
+            encrypted_count: contacts.encrypted_count,
+            encrypted_namespace: contacts.encrypted_namespace,
+            pubkey: contacts.pubkey,
+            nonce: contacts.nonce,
+        },
+    )
+}
+
+/// Ask live epoch `epoch` of database `database_id` whether it holds the
+/// identifier in `query`, billed as one query to `app_id`'s credit.
+pub fn check_membership(
+    payer: Pubkey,
+    user: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    database_id: &[u8; 32],
+    epoch: u32,
+    app_id: &[u8; 32],
+    query: &EncryptedMembershipQuery,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::CHECK_MEMBERSHIP, computation_offset);
+    build(
+        accounts::CheckMembership {
+            user,
+            payer,
+            database: database_pda(database_id, epoch),
+            billing: billing(app_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::CheckMembership {
+            computation_offset,
+            hash_scheme: query.hash_scheme,
+            encrypted_query: query.encrypted_query,
+            pubkey: query.pubkey,
+            nonce: query.nonce,
+        },
+    )
+}
+
+/// Ask live epoch `epoch` of database `database_id` about every
+/// identifier in `batch`, billed as `batch.queries` queries to `app_id`'s
+/// credit.
+pub fn check_membership_batch(
+    payer: Pubkey,
+    user: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    database_id: &[u8; 32],
+    epoch: u32,
+    app_id: &[u8; 32],
+    batch: &EncryptedMembershipBatch,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::CHECK_MEMBERSHIP_BATCH, computation_offset);
+    build(
+        accounts::CheckMembershipBatch {
+            user,
+            payer,
+            database: database_pda(database_id, epoch),
+            billing: billing(app_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::CheckMembershipBatch {
+            computation_offset,
+            hash_scheme: batch.hash_scheme,
+            queries: batch.queries,
+            encrypted_hashes: batch.encrypted_hashes,
+            encrypted_namespace: batch.encrypted_namespace,
+            pubkey: batch.pubkey,
+            nonce: batch.nonce,
+        },
+    )
+}
+
+/// Publish `contacts` as moderator blocklist `blocklist_id`, hashed in `namespace`.
+pub fn create_blocklist(
+    payer: Pubkey,
+    moderator: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    blocklist_id: [u8; 32],
+    namespace: u8,
+    contacts: &EncryptedContacts,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::CREATE_BLOCKLIST, computation_offset);
+    build(
+        accounts::CreateBlocklist {
+            moderator,
+            payer,
+            blocklist: blocklist_pda(&blocklist_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::CreateBlocklist {
+            computation_offset,
+            blocklist_id,
+            namespace,
+            hash_scheme: contacts.hash_scheme,
+            encrypted_hashes: contacts.encrypted_hashes,
+            encrypted_count: contacts.encrypted_count,
+            encrypted_namespace: contacts.encrypted_namespace,
+            pubkey: contacts.pubkey,
+            nonce: contacts.nonce,
+        },
+    )
+}
+
+pub fn check_against_blocklist(
+    payer: Pubkey,
+    user: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    blocklist_id: &[u8; 32],
+    contacts: &EncryptedContacts,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::CHECK_BLOCKLIST, computation_offset);
+    build(
+        accounts::CheckAgainstBlocklist {
+            user,
+            payer,
+            blocklist: blocklist_pda(blocklist_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::CheckAgainstBlocklist {
+            computation_offset,
+            hash_scheme: contacts.hash_scheme,
+            encrypted_hashes: contacts.encrypted_hashes,
+            encrypted_count: contacts.encrypted_count,
+            encrypted_namespace: contacts.encrypted_namespace,
+            pubkey: contacts.pubkey,
+            nonce: contacts.nonce,
+        },
+    )
+}
+
+/// Open contact card registry `registry_id` for identifiers hashed in
+/// `namespace` with `hash_scheme`. `pubkey` and `nonce` only carry the
+/// authority's key to the MPC.
+pub fn create_card_registry(
+    payer: Pubkey,
+    authority: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    registry_id: [u8; 32],
+    namespace: u8,
+    hash_scheme: u8,
+    pubkey: [u8; 32],
+    nonce: u128,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::CREATE_CARD_REGISTRY, computation_offset);
+    build(
+        accounts::CreateCardRegistry {
+            authority,
+            payer,
+            registry: card_registry_pda(&registry_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::CreateCardRegistry {
+            computation_offset,
+            registry_id,
+            namespace,
+            hash_scheme,
+            pubkey,
+            nonce,
+        },
+    )
+}
+
+/// Publish `publisher`'s contact card to registry `registry_id`.
+pub fn publish_contact_card(
+    payer: Pubkey,
+    publisher: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    registry_id: &[u8; 32],
+    card: &EncryptedCard,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::PUBLISH_CARD, computation_offset);
+    build(
+        accounts::PublishContactCard {
+            publisher,
+            payer,
+            registry: card_registry_pda(registry_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::PublishContactCard {
+            computation_offset,
+            encrypted_card: card.encrypted_card,
+            pubkey: card.pubkey,
+            nonce: card.nonce,
+        },
+    )
+}
+
+/// Look `contacts` up in registry `registry_id`.
+pub fn match_contact_cards(
+    payer: Pubkey,
+    user: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    registry_id: &[u8; 32],
+    contacts: &EncryptedContacts,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::MATCH_CARDS, computation_offset);
+    build(
+        accounts::MatchContactCards {
+            user,
+            payer,
+            registry: card_registry_pda(registry_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::MatchContactCards {
+            computation_offset,
+            hash_scheme: contacts.hash_scheme,
+            encrypted_hashes: contacts.encrypted_hashes,
+            encrypted_count: contacts.encrypted_count,
+            encrypted_namespace: contacts.encrypted_namespace,
+            pubkey: contacts.pubkey,
+            nonce: contacts.nonce,
+        },
+    )
+}
+
+/// Bob's submission to a score session. `alice` is the session's
+/// Alice, whose block is checked along with Bob's.
+pub fn submit_and_score(
+    payer: Pubkey,
+    bob: Pubkey,
+    alice: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: &[u8; 32],
+    app_id: &[u8; 32],
+    contacts: &EncryptedContacts,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::SUBMIT_AND_SCORE, computation_offset);
+    let ix = build(
+        accounts::SubmitAndScore {
+            bob,
+            payer,
+            session: session_pda(session_id),
+            state_handle: state_handle_pda(&session_pda(session_id)),
+            billing: billing(app_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::SubmitAndScore {
+            computation_offset,
+            hash_scheme: contacts.hash_scheme,
+            encrypted_hashes: contacts.encrypted_hashes,
+            encrypted_count: contacts.encrypted_count,
+            encrypted_namespace: contacts.encrypted_namespace,
+            pubkey: contacts.pubkey,
+            nonce: contacts.nonce,
+        },
+    );
+    with_block_check(with_block_check(ix, &bob), &alice)
+}
+
+pub fn reveal_alice_score(
+    payer: Pubkey,
+    alice: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: &[u8; 32],
+    app_id: &[u8; 32],
+    pubkey: [u8; 32],
+    nonce: u128,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::REVEAL_ALICE_SCORE, computation_offset);
+    build(
+        accounts::RevealAliceScore {
+            alice,
+            payer,
+            session: session_pda(session_id),
+            state_handle: state_handle_pda(&session_pda(session_id)),
+            billing: billing(app_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::RevealAliceScore {
+            computation_offset,
+            pubkey,
+            nonce,
+        },
+    )
+}
+
+/// Bob's submission to a friends-of-friends session. `alice` is the
+/// session's Alice, whose block is checked along with Bob's.
+pub fn submit_contacts_bob(
+    payer: Pubkey,
+    bob: Pubkey,
+    alice: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: &[u8; 32],
+    app_id: &[u8; 32],
+    contacts: &EncryptedContacts,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::SUBMIT_BOB, computation_offset);
+    let ix = build(
+        accounts::SubmitContactsBob {
+            bob,
+            payer,
+            session: session_pda(session_id),
+            state_handle: state_handle_pda(&session_pda(session_id)),
+            billing: billing(app_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::SubmitContactsBob {
+            computation_offset,
+            hash_scheme: contacts.hash_scheme,
+            encrypted_hashes: contacts.encrypted_hashes,
+            encrypted_count: contacts.encrypted_count,
+            encrypted_namespace: contacts.encrypted_namespace,
+            pubkey: contacts.pubkey,
+            nonce: contacts.nonce,
+        },
+    );
+    with_block_check(with_block_check(ix, &bob), &alice)
+}
+
+/// The connector's submission to a friends-of-friends session.
+pub fn submit_connector(
+    payer: Pubkey,
+    connector: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: &[u8; 32],
+    app_id: &[u8; 32],
+    contacts: &EncryptedContacts,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::SUBMIT_CONNECTOR, computation_offset);
+    build(
+        accounts::SubmitConnector {
+            connector,
+            payer,
+            session: session_pda(session_id),
+            state_handle: state_handle_pda(&session_pda(session_id)),
+            billing: billing(app_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::SubmitConnector {
+            computation_offset,
+            hash_scheme: contacts.hash_scheme,
+            encrypted_hashes: contacts.encrypted_hashes,
+            encrypted_count: contacts.encrypted_count,
+            encrypted_namespace: contacts.encrypted_namespace,
+            pubkey: contacts.pubkey,
+            nonce: contacts.nonce,
+        },
+    )
+}
+
+/// `party` is the session's Alice or Bob.
+pub fn reveal_second_degree(
+    payer: Pubkey,
+    party: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: &[u8; 32],
+    app_id: &[u8; 32],
+    pubkey: [u8; 32],
+    nonce: u128,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::REVEAL_SECOND_DEGREE, computation_offset);
+    build(
+        accounts::RevealSecondDegree {
+            party,
+            payer,
+            session: session_pda(session_id),
+            state_handle: state_handle_pda(&session_pda(session_id)),
+            billing: billing(app_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::RevealSecondDegree {
+            computation_offset,
+            pubkey,
+            nonce,
+        },
+    )
+}
+
+/// Bob's follow list for a mutual-follow session. `contacts` must come
+/// from `hash::fold_follow_list`. `alice` is the session's Alice, whose
+/// block is checked along with Bob's.
+pub fn submit_and_check_follow(
+    payer: Pubkey,
+    bob: Pubkey,
+    alice: Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: &[u8; 32],
+    app_id: &[u8; 32],
+    contacts: &EncryptedContacts,
+) -> Instruction {
+    let a = arcium_accounts(cluster_offset, circuits::CHECK_FOLLOW, computation_offset);
+    let ix = build(
+        accounts::SubmitAndCheckFollow {
+            bob,
+            payer,
+            session: session_pda(session_id),
+            state_handle: state_handle_pda(&session_pda(session_id)),
+            billing: billing(app_id),
+            sign_pda_account: sign_pda(),
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::SubmitAndCheckFollow {
+            computation_offset,
+            hash_scheme: contacts.hash_scheme,
+            encrypted_hashes: contacts.encrypted_hashes,
+            encrypted_count: contacts.encrypted_count,
+            encrypted_namespace: contacts.encrypted_namespace,
+            pubkey: contacts.pubkey,
+            nonce: contacts.nonce,
+        },
+    );
+    with_block_check(with_block_check(ix, &bob), &alice)
+}
+
+/// A session computation, for `resolve_accounts`. Tiered stages take
+/// the session's capacity, which picks the computation definition.
+#[derive(Clone, Copy, Debug)]
+pub enum Stage {
+    SubmitAlice { capacity: u8 },
+    InitAndSubmitAlice,
+    /// `party` is Bob; the joining stages also take the session's Alice
+    SubmitAndMatch { capacity: u8, alice: Pubkey },
+    RevealAlice,
+    RevealBob,
+    /// `party` is Alice; both must sign
+    RevealBoth { bob: Pubkey },
+    PushDelta,
+    SubmitAndScore { alice: Pubkey },
+    RevealAliceScore,
+    SubmitContactsBob { alice: Pubkey },
+    SubmitConnector,
+    RevealSecondDegree,
+    SubmitAndCheckFollow { alice: Pubkey },
+    PurgeState,
+    MigrateState,
+}
+
+/// Who sends a stage, and the cluster and app it is queued on and billed to.
+#[derive(Clone, Copy, Debug)]
+pub struct Sender {
+    pub payer: Pubkey,
+    /// The party the stage authorizes: Alice, Bob, or the connector
+    pub party: Pubkey,
+    pub cluster_offset: u32,
+    pub app_id: [u8; 32],
+}
+
+/// Every account of `stage`'s instruction on `session_id`, in the order
+/// the program expects, with the Arcium PDAs for `computation_offset`
+/// derived. For integrators that build instruction data themselves.
+pub fn resolve_accounts(
+    stage: Stage,
+    sender: &Sender,
+    session_id: &[u8; 32],
+    computation_offset: u64,
+) -> Vec<AccountMeta> {
+    let Sender {
+        payer,
+        party,
+        cluster_offset: cluster,
+        app_id,
+    } = *sender;
+    let app = &app_id;
+    let offset = computation_offset;
+    // Accounts never depend on the ciphertexts, so the builders are
+    // given zeroed ones
+    let contacts = &EncryptedContacts {
+        encrypted_hashes: [[0; 32]; MAX_CONTACTS],
+        encrypted_count: [0; 32],
+        encrypted_namespace: [0; 32],
+        hash_scheme: 0,
+        pubkey: [0; 32],
+        nonce: 0,
+    };
+    let delta = &EncryptedDelta {
+        encrypted_added: [[0; 32]; MAX_DELTA],
+        encrypted_removed: [[0; 32]; MAX_DELTA],
+        encrypted_namespace: [0; 32],
+        hash_scheme: 0,
+        pubkey: [0; 32],
+        nonce: 0,
+    };
+    let ix = match stage {
+        Stage::SubmitAlice { capacity } => {
+            submit_contacts_alice(payer, party, cluster, offset, session_id, app, contacts, capacity)
+        }
+        Stage::InitAndSubmitAlice => init_and_submit_alice(payer, party, cluster, offset, session_id, app, contacts),
+        Stage::SubmitAndMatch { capacity, alice } => submit_and_match(
+            payer, party, alice, cluster, offset, session_id, app, contacts, capacity, false,
+        ),
+        Stage::RevealAlice => reveal_alice_matches(payer, party, cluster, offset, session_id, app, [0; 32], 0, 0),
+        Stage::RevealBob => reveal_bob_matches(payer, party, cluster, offset, session_id, app, [0; 32], 0, 0),
+        Stage::RevealBoth { bob } => reveal_both(
+            payer, party, bob, cluster, offset, session_id, app, [0; 32], 0, [0; 32], 0, 0,
+        ),
+        Stage::PushDelta => push_delta(payer, party, cluster, offset, session_id, app, delta),
+        Stage::SubmitAndScore { alice } => {
+            submit_and_score(payer, party, alice, cluster, offset, session_id, app, contacts)
+        }
+        Stage::RevealAliceScore => reveal_alice_score(payer, party, cluster, offset, session_id, app, [0; 32], 0),
+        Stage::SubmitContactsBob { alice } => {
+            submit_contacts_bob(payer, party, alice, cluster, offset, session_id, app, contacts)
+        }
+        Stage::SubmitConnector => submit_connector(payer, party, cluster, offset, session_id, app, contacts),
+        Stage::RevealSecondDegree => reveal_second_degree(payer, party, cluster, offset, session_id, app, [0; 32], 0),
+        Stage::SubmitAndCheckFollow { alice } => {
+            submit_and_check_follow(payer, party, alice, cluster, offset, session_id, app, contacts)
+        }
+        Stage::PurgeState => purge_session_state(payer, party, cluster, offset, session_id),
+        Stage::MigrateState => migrate_session_state(payer, party, cluster, offset, session_id),
+    };
+    ix.accounts
+}
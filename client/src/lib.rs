@@ -0,0 +1,74 @@
+// Private Contact Discovery - Client SDK
+//
+// Everything a client needs to take part in a PSI session:
+// contact normalization and hashing, Rescue encryption against
+// the MXE key, Arcium account derivation, instruction builders,
+// RPC helpers for reading sessions and callback events, off-chain
+// verification of signed outputs, a typed, resumable event stream,
+// provisional match estimates from Bloom filters for same-app backends,
+// `.sol` name resolution for the Solana-name namespace, and seeded keys
+// and nonces for reproducible encrypted test fixtures.
+//
+// Features:
+//   rpc  (default)  accounts, instructions, RPC, preflight checks, and
+//                   event streaming; pulls in solana-client and the
+//                   program crate
+//   wasm            wasm-bindgen exports of hashing and encryption
+//   e2e             localnet tests under tests/ (needs `arcium localnet`)
+//
+// Hashing, normalization, and encryption compile to
+// wasm32-unknown-unknown with `--no-default-features --features wasm`,
+// so browsers produce ciphertexts with the same code as native clients.
+
+pub mod crypto;
+pub mod error;
+pub mod fixtures;
+pub mod hash;
+pub mod provisional;
+
+#[cfg(feature = "rpc")]
+pub mod accounts;
+#[cfg(feature = "rpc")]
+pub mod events;
+#[cfg(feature = "rpc")]
+pub mod instructions;
+#[cfg(feature = "rpc")]
+pub mod preflight;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+#[cfg(feature = "rpc")]
+pub mod shard;
+#[cfg(feature = "rpc")]
+pub mod sns;
+#[cfg(feature = "rpc")]
+pub mod verify;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use crypto::{
+    ContactCard, DecryptedBlocklistResult, DecryptedCards, DecryptedMatches, DecryptedScore, EncryptedCard,
+    EncryptedContacts, EncryptedDelta, EncryptedMembershipBatch, EncryptedMembershipQuery, PartyCipher, RelayedResult,
+};
+pub use error::ClientError;
+pub use fixtures::Fixtures;
+pub use hash::{
+    decoy_hash, fold_follow_list, fold_pubkey, hash_contact, hash_contact_list, hash_contact_set, hash_delta,
+    normalize_contact, normalize_identifier, normalize_name, pad_with_decoys, HashScheme, HashedContacts, HashedDelta,
+    Namespace, CAPACITY_TIERS, CARD_SLOTS, MATCH_WORDS, MAX_CONTACTS, MAX_DELTA, DATABASE_CHUNKS, DECOY_FLOOR,
+    MAX_SET_CHUNKS, MEMBERSHIP_BATCH_SLOTS,
+};
+pub use provisional::{BloomFilter, MatchState};
+
+#[cfg(feature = "rpc")]
+pub use events::{EventNotification, SessionEvent, SessionEventStream};
+#[cfg(feature = "rpc")]
+pub use private_contact_discovery::ID as PROGRAM_ID;
+
+/// Parse a 64-character hex session id.
+pub fn parse_session_id(hex_id: &str) -> Result<[u8; 32], ClientError> {
+    let bytes = hex::decode(hex_id.trim()).map_err(|_| ClientError::InvalidSessionId(hex_id.into()))?;
+    bytes
+        .try_into()
+        .map_err(|_| ClientError::InvalidSessionId(hex_id.into()))
+}
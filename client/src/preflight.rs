@@ -0,0 +1,401 @@
+// Preflight checks before paying for an MPC computation.
+//
+// Fetches the session, computation definition, cluster, and fee pool
+// accounts, checks them against the stage about to be queued, then
+// simulates the transaction to get an exact lamport cost (network fee,
+// rent, and Arcium computation fee) from the payer's balance delta.
+//
+// `check_deployment` runs once per connection rather than per stage: it
+// compares the `DeploymentRegistry` against the versions this client
+// was built with, before anything is encrypted for the deployment.
+
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig},
+};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, transaction::Transaction};
+use thiserror::Error;
+
+use private_contact_discovery::{
+    DeploymentRegistry, SessionMode, Status, CIRCUIT_VERSIONS, PROGRAM_VERSION, SESSION_VERSION,
+};
+
+use crate::{
+    accounts::{arcium_accounts, circuits, deployment_pda, session_pda},
+    error::ClientError,
+    instructions::{alice_circuit, match_circuit},
+    rpc::{fetch_deployment, fetch_session, fetch_state_handle},
+};
+
+/// The queue instruction being checked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    CreateSession,
+    SubmitAlice,
+    /// Alice's first submission to a stateless session
+    InitAndSubmitAlice,
+    SubmitAndMatch,
+    RevealAlice,
+    PushDelta,
+    SubmitAndScore,
+    RevealAliceScore,
+    SubmitBob,
+    SubmitConnector,
+    RevealSecondDegree,
+    CheckFollow,
+    RevealBob,
+    RevealBoth,
+    SubmitSetChunk,
+}
+
+impl Stage {
+    pub fn circuit(self) -> &'static str {
+        match self {
+            Stage::CreateSession => circuits::INIT_SESSION,
+            Stage::SubmitAlice => circuits::SUBMIT_ALICE,
+            Stage::InitAndSubmitAlice => circuits::INIT_AND_SUBMIT_ALICE,
+            Stage::SubmitAndMatch => circuits::SUBMIT_AND_MATCH,
+            Stage::RevealAlice => circuits::REVEAL_ALICE,
+            Stage::PushDelta => circuits::PUSH_DELTA,
+            Stage::SubmitAndScore => circuits::SUBMIT_AND_SCORE,
+            Stage::RevealAliceScore => circuits::REVEAL_ALICE_SCORE,
+            Stage::SubmitBob => circuits::SUBMIT_BOB,
+            Stage::SubmitConnector => circuits::SUBMIT_CONNECTOR,
+            Stage::RevealSecondDegree => circuits::REVEAL_SECOND_DEGREE,
+            Stage::CheckFollow => circuits::CHECK_FOLLOW,
+            Stage::RevealBob => circuits::REVEAL_BOB,
+            Stage::RevealBoth => circuits::REVEAL_BOTH,
+            Stage::SubmitSetChunk => circuits::MATCH_SET_CHUNK,
+        }
+    }
+
+    /// Status the session must be in, `None` if it must not exist yet.
+    fn required_status(self) -> Option<Status> {
+        match self {
+            Stage::CreateSession => None,
+            Stage::SubmitAlice | Stage::InitAndSubmitAlice => Some(Status::AwaitingAlice),
+            Stage::SubmitAndMatch
+            | Stage::SubmitAndScore
+            | Stage::SubmitBob
+            | Stage::CheckFollow
+            | Stage::SubmitSetChunk => Some(Status::AwaitingBob),
+            Stage::SubmitConnector => Some(Status::AwaitingConnector),
+            Stage::RevealAlice
+            | Stage::RevealBob
+            | Stage::RevealBoth
+            | Stage::PushDelta
+            | Stage::RevealAliceScore
+            | Stage::RevealSecondDegree => Some(Status::Matched),
+        }
+    }
+
+    /// Whether only the session's Alice may queue this stage.
+    fn alice_only(self) -> bool {
+        matches!(
+            self,
+            Stage::SubmitAlice | Stage::InitAndSubmitAlice | Stage::RevealAlice | Stage::RevealAliceScore
+        )
+    }
+
+    /// Whether only the session's Alice or Bob may queue this stage.
+    fn alice_or_bob(self) -> bool {
+        matches!(self, Stage::PushDelta | Stage::RevealSecondDegree | Stage::RevealBoth)
+    }
+
+    /// Whether this stage waits for the session's reveal slot.
+    fn time_locked(self) -> bool {
+        matches!(self, Stage::RevealAlice | Stage::RevealBob | Stage::RevealBoth | Stage::PushDelta)
+    }
+
+    /// Session modes this stage is available in, `None` for any.
+    fn modes(self) -> Option<&'static [u8]> {
+        match self {
+            Stage::SubmitAndMatch | Stage::RevealBob | Stage::RevealBoth => {
+                Some(&[SessionMode::OneShot as u8, SessionMode::Subscription as u8])
+            }
+            Stage::RevealAlice => Some(&[
+                SessionMode::OneShot as u8,
+                SessionMode::Subscription as u8,
+                SessionMode::CommittedSet as u8,
+            ]),
+            Stage::SubmitSetChunk => Some(&[SessionMode::CommittedSet as u8]),
+            Stage::SubmitAndScore | Stage::RevealAliceScore => Some(&[SessionMode::Score as u8]),
+            Stage::SubmitBob | Stage::SubmitConnector => Some(&[SessionMode::FriendsOfFriends as u8]),
+            Stage::RevealSecondDegree => {
+                Some(&[SessionMode::FriendsOfFriends as u8, SessionMode::MutualFollow as u8])
+            }
+            Stage::CheckFollow => Some(&[SessionMode::MutualFollow as u8]),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PreflightError {
+    #[error("session already exists")]
+    SessionExists,
+    #[error("session is in status {actual}, {stage:?} requires {expected}")]
+    WrongStatus { stage: Stage, expected: u8, actual: u8 },
+    #[error("signer is not a party to this stage of the session")]
+    Unauthorized,
+    #[error("session is not a subscription session")]
+    NotSubscription,
+    #[error("{0:?} is not available in this session's mode")]
+    ModeMismatch(Stage),
+    #[error("session results are escrowed until slot {0}")]
+    RevealLocked(u64),
+    #[error("session's MXE state is not initialized yet")]
+    StateNotInitialized,
+    #[error("session's MXE state already exists")]
+    StateAlreadyInitialized,
+    #[error("computation definition for {0} is not initialized")]
+    CompDefNotInitialized(&'static str),
+    #[error("deployment registry not found; run sync_deployment")]
+    DeploymentNotSynced,
+    #[error("deployment runs program version {deployed}, this client was built for {expected}")]
+    ProgramVersionMismatch { expected: u16, deployed: u16 },
+    #[error("deployment uses session layout v{deployed}, this client was built for v{expected}")]
+    SessionVersionMismatch { expected: u8, deployed: u8 },
+    #[error("deployment runs a different version of circuit {0}")]
+    CircuitVersionMismatch(&'static str),
+    #[error("deployment computes on cluster {deployed}, not {expected}")]
+    ClusterMismatch { expected: u32, deployed: u32 },
+    #[error("deployment's MXE key differs from the one being encrypted to")]
+    MxeKeyMismatch,
+    #[error("MXE cluster account {0} not found")]
+    ClusterMissing(Pubkey),
+    #[error("Arcium fee pool account {0} not found")]
+    FeePoolMissing(Pubkey),
+    #[error("payer holds {available} lamports, needs about {needed}")]
+    InsufficientFunds { needed: u64, available: u64 },
+    #[error("simulation failed: {error}")]
+    SimulationFailed { error: String, logs: Vec<String> },
+    #[error(transparent)]
+    Client(#[from] ClientError),
+}
+
+impl From<solana_client::client_error::ClientError> for PreflightError {
+    fn from(e: solana_client::client_error::ClientError) -> Self {
+        Self::Client(e.into())
+    }
+}
+
+/// Outcome of a successful preflight.
+#[derive(Clone, Debug)]
+pub struct PreflightReport {
+    /// Lamports the payer is expected to spend, network fee included
+    pub estimated_cost: u64,
+    /// Compute units consumed in simulation
+    pub compute_units: Option<u64>,
+    pub logs: Vec<String>,
+}
+
+/// Validate on-chain state for `stage` and simulate `ix`.
+/// `computation_offset` must be the one encoded in `ix`. `party` is the
+/// signing Alice, Bob, or connector; `payer` funds the transaction and is
+/// the same key unless a sponsor pays.
+pub fn preflight(
+    rpc: &RpcClient,
+    stage: Stage,
+    party: &Pubkey,
+    payer: &Pubkey,
+    cluster_offset: u32,
+    computation_offset: u64,
+    session_id: &[u8; 32],
+    ix: Instruction,
+) -> Result<PreflightReport, PreflightError> {
+    check_session(rpc, stage, party, session_id)?;
+
+    // Alice's submission and the match queue the circuit of the
+    // session's capacity tier
+    let circuit = match stage {
+        Stage::SubmitAlice => alice_circuit(fetch_session(rpc, session_id)?.capacity()),
+        Stage::SubmitAndMatch => match_circuit(fetch_session(rpc, session_id)?.capacity()),
+        _ => stage.circuit(),
+    };
+    let accounts = arcium_accounts(cluster_offset, circuit, computation_offset);
+    let exists = |key: &Pubkey| -> Result<bool, PreflightError> {
+        Ok(rpc.get_account_with_commitment(key, rpc.commitment())?.value.is_some())
+    };
+    if !exists(&accounts.comp_def_account)? {
+        return Err(PreflightError::CompDefNotInitialized(circuit));
+    }
+    if !exists(&accounts.cluster_account)? {
+        return Err(PreflightError::ClusterMissing(accounts.cluster_account));
+    }
+    if !exists(&accounts.pool_account)? {
+        return Err(PreflightError::FeePoolMissing(accounts.pool_account));
+    }
+
+    simulate(rpc, payer, ix)
+}
+
+/// Check the deployment is one this client can use before encrypting
+/// anything for it: the program, session layout and circuit versions it
+/// was built with, the comp defs of `needed` circuits ready, and the
+/// cluster and MXE key the client is about to use. Returns the registry.
+pub fn check_deployment(
+    rpc: &RpcClient,
+    cluster_offset: u32,
+    mxe_public_key: &[u8; 32],
+    needed: &[&'static str],
+) -> Result<DeploymentRegistry, PreflightError> {
+    let exists = rpc
+        .get_account_with_commitment(&deployment_pda(), rpc.commitment())?
+        .value
+        .is_some();
+    if !exists {
+        return Err(PreflightError::DeploymentNotSynced);
+    }
+    let registry = fetch_deployment(rpc)?;
+
+    if registry.program_version != PROGRAM_VERSION {
+        return Err(PreflightError::ProgramVersionMismatch {
+            expected: PROGRAM_VERSION,
+            deployed: registry.program_version,
+        });
+    }
+    if registry.session_version != SESSION_VERSION {
+        return Err(PreflightError::SessionVersionMismatch {
+            expected: SESSION_VERSION,
+            deployed: registry.session_version,
+        });
+    }
+    let stale = (0..circuits::ALL.len()).find(|&i| registry.circuit_versions[i] != CIRCUIT_VERSIONS[i]);
+    if let Some(i) = stale {
+        return Err(PreflightError::CircuitVersionMismatch(circuits::ALL[i]));
+    }
+    for circuit in needed {
+        let index = circuits::ALL.iter().position(|c| c == circuit);
+        if !index.is_some_and(|i| registry.comp_def_ready(i)) {
+            return Err(PreflightError::CompDefNotInitialized(circuit));
+        }
+    }
+    if registry.cluster_offset != cluster_offset {
+        return Err(PreflightError::ClusterMismatch {
+            expected: cluster_offset,
+            deployed: registry.cluster_offset,
+        });
+    }
+    if registry.mxe_x25519_pubkey != *mxe_public_key {
+        return Err(PreflightError::MxeKeyMismatch);
+    }
+    Ok(registry)
+}
+
+fn check_session(
+    rpc: &RpcClient,
+    stage: Stage,
+    party: &Pubkey,
+    session_id: &[u8; 32],
+) -> Result<(), PreflightError> {
+    let Some(expected) = stage.required_status() else {
+        let existing = rpc
+            .get_account_with_commitment(&session_pda(session_id), rpc.commitment())?
+            .value;
+        return match existing {
+            Some(_) => Err(PreflightError::SessionExists),
+            None => Ok(()),
+        };
+    };
+
+    let session = fetch_session(rpc, session_id)?;
+    if session.status != expected as u8 {
+        return Err(PreflightError::WrongStatus {
+            stage,
+            expected: expected as u8,
+            actual: session.status,
+        });
+    }
+    if stage.alice_only() && session.alice != *party {
+        return Err(PreflightError::Unauthorized);
+    }
+    if stage.alice_or_bob() && session.alice != *party && session.bob != *party {
+        return Err(PreflightError::Unauthorized);
+    }
+    if matches!(stage, Stage::RevealBob | Stage::SubmitSetChunk) && session.bob != *party {
+        return Err(PreflightError::Unauthorized);
+    }
+    if stage == Stage::CheckFollow && session.alice == *party {
+        return Err(PreflightError::Unauthorized);
+    }
+    if stage == Stage::SubmitConnector && (session.alice == *party || session.bob == *party) {
+        return Err(PreflightError::Unauthorized);
+    }
+    if let Some(modes) = stage.modes() {
+        if !modes.contains(&session.mode) {
+            return Err(PreflightError::ModeMismatch(stage));
+        }
+    }
+    if stage == Stage::PushDelta && session.mode != SessionMode::Subscription as u8 {
+        return Err(PreflightError::NotSubscription);
+    }
+    if stage.time_locked() && session.reveal_locked(rpc.get_slot()?) {
+        return Err(PreflightError::RevealLocked(session.reveal_after_slot));
+    }
+    // Only a stateless session's first submission runs without state
+    match (stage, fetch_state_handle(rpc, session_id)) {
+        (Stage::InitAndSubmitAlice, Ok(handle)) if handle.generation == 0 => Ok(()),
+        (Stage::InitAndSubmitAlice, Ok(_)) => Err(PreflightError::StateAlreadyInitialized),
+        (_, Ok(handle)) if handle.generation != 0 => Ok(()),
+        _ => Err(PreflightError::StateNotInitialized),
+    }
+}
+
+fn simulate(
+    rpc: &RpcClient,
+    payer: &Pubkey,
+    ix: Instruction,
+) -> Result<PreflightReport, PreflightError> {
+    let mut tx = Transaction::new_with_payer(&[ix], Some(payer));
+    tx.message.recent_blockhash = rpc.get_latest_blockhash()?;
+
+    let balance = rpc.get_balance(payer)?;
+    let network_fee = rpc.get_fee_for_message(&tx.message)?;
+
+    let result = rpc
+        .simulate_transaction_with_config(
+            &tx,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(rpc.commitment()),
+                accounts: Some(RpcSimulateTransactionAccountsConfig {
+                    encoding: None,
+                    addresses: vec![payer.to_string()],
+                }),
+                ..Default::default()
+            },
+        )?
+        .value;
+
+    let logs = result.logs.unwrap_or_default();
+    if let Some(err) = result.err {
+        return Err(PreflightError::SimulationFailed {
+            error: err.to_string(),
+            logs,
+        });
+    }
+
+    // Simulation does not charge the network fee, so the payer's
+    // balance delta covers rent and the Arcium computation fee.
+    let post_balance = result
+        .accounts
+        .and_then(|a| a.into_iter().next().flatten())
+        .map(|a| a.lamports)
+        .unwrap_or(balance);
+    let estimated_cost = balance.saturating_sub(post_balance) + network_fee;
+
+    if balance < estimated_cost {
+        return Err(PreflightError::InsufficientFunds {
+            needed: estimated_cost,
+            available: balance,
+        });
+    }
+
+    Ok(PreflightReport {
+        estimated_cost,
+        compute_units: result.units_consumed,
+        logs,
+    })
+}
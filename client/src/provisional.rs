@@ -0,0 +1,251 @@
+// Optimistic local matching.
+//
+// When both parties use the same app backend, it can show an instant
+// estimate of how many contacts they share before the MPC answers: each
+// client sends the backend a Bloom filter of its contact hashes, never
+// the hashes themselves, and the backend estimates the intersection
+// size from the two filters' bit counts. The estimate is only ever
+// shown as provisional; the MPC result confirms or corrects it once its
+// callback lands.
+//
+// A filter still answers membership queries for any identifier the
+// backend can guess, so it must only go to a backend already trusted
+// with the app's contact graph, never on-chain or to the other party.
+
+use crate::crypto::DecryptedMatches;
+use crate::hash::{HashedContacts, DECOY_FLOOR};
+
+/// Bits in a filter. 1024 bits and `BLOOM_HASHES` probes keep the
+/// false-positive rate near 0.1% for a full 32-contact list.
+pub const BLOOM_BITS: usize = 1024;
+
+/// Bit positions each hash sets.
+pub const BLOOM_HASHES: u32 = 7;
+
+/// A Bloom filter over one list's contact hashes. Decoys and empty slots
+/// are left out, so the filter counts real contacts only.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BloomFilter {
+    pub bits: [u64; BLOOM_BITS / 64],
+}
+
+impl BloomFilter {
+    /// Filter over `contacts`' real hashes.
+    pub fn from_contacts(contacts: &HashedContacts) -> Self {
+        Self::from_hashes(&contacts.hashes)
+    }
+
+    /// Filter over `hashes`, skipping zero padding and decoys.
+    pub fn from_hashes(hashes: &[u128]) -> Self {
+        let mut filter = BloomFilter {
+            bits: [0; BLOOM_BITS / 64],
+        };
+        for &hash in hashes.iter().filter(|&&h| h != 0 && h < DECOY_FLOOR) {
+            filter.insert(hash);
+        }
+        filter
+    }
+
+    /// Contact hashes are uniform, so their two halves serve as the
+    /// independent hashes of double hashing.
+    fn insert(&mut self, hash: u128) {
+        let h1 = hash as u64;
+        let h2 = ((hash >> 64) as u64) | 1;
+        for i in 0..BLOOM_HASHES as u64 {
+            let bit = (h1.wrapping_add(i.wrapping_mul(h2)) % BLOOM_BITS as u64) as usize;
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    /// Set bits.
+    pub fn ones(&self) -> u32 {
+        self.bits.iter().map(|word| word.count_ones()).sum()
+    }
+
+    /// Estimated number of contacts in the filter (Swamidass and Baldi).
+    pub fn estimate_len(&self) -> f64 {
+        estimate(self.ones())
+    }
+
+    /// Estimated number of contacts in both filters: the two sizes less
+    /// the size of their union, estimated from the filters OR-ed.
+    pub fn estimate_intersection(&self, other: &BloomFilter) -> u32 {
+        let union_ones = self
+            .bits
+            .iter()
+            .zip(&other.bits)
+            .map(|(a, b)| (a | b).count_ones())
+            .sum();
+        let shared = self.estimate_len() + other.estimate_len() - estimate(union_ones);
+        shared.round().max(0.0) as u32
+    }
+
+    /// The filter as bytes, little-endian words, for sending to the backend.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.bits.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    /// Inverse of `to_bytes`; `None` unless `bytes` is exactly one filter.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != BLOOM_BITS / 8 {
+            return None;
+        }
+        let mut bits = [0u64; BLOOM_BITS / 64];
+        for (word, chunk) in bits.iter_mut().zip(bytes.chunks_exact(8)) {
+            *word = u64::from_le_bytes(chunk.try_into().ok()?);
+        }
+        Some(BloomFilter { bits })
+    }
+}
+
+/// Items in a filter with `ones` bits set: -m/k * ln(1 - X/m).
+fn estimate(ones: u32) -> f64 {
+    let m = BLOOM_BITS as f64;
+    // A saturated filter would estimate infinity
+    let x = (ones as f64).min(m - 1.0);
+    -(m / BLOOM_HASHES as f64) * (1.0 - x / m).ln()
+}
+
+/// A session's match result as an app shows it: an estimate until the
+/// MPC result arrives, then that result.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MatchState {
+    /// Estimated from the parties' filters; not authoritative
+    Provisional { estimated_count: u32 },
+    /// Decrypted from the MPC callback's event
+    Confirmed {
+        matches: DecryptedMatches,
+        /// What was shown before, if anything, so the app can tell the
+        /// user when the estimate was off
+        estimated_count: Option<u32>,
+    },
+}
+
+impl MatchState {
+    /// The provisional state for two parties' filters.
+    pub fn provisional(alice: &BloomFilter, bob: &BloomFilter) -> Self {
+        MatchState::Provisional {
+            estimated_count: alice.estimate_intersection(bob),
+        }
+    }
+
+    /// Reconcile with the MPC result. A confirmed state is replaced by
+    /// the newer result, keeping the original estimate.
+    pub fn confirm(self, matches: DecryptedMatches) -> Self {
+        let estimated_count = match self {
+            MatchState::Provisional { estimated_count } => Some(estimated_count),
+            MatchState::Confirmed { estimated_count, .. } => estimated_count,
+        };
+        MatchState::Confirmed {
+            matches,
+            estimated_count,
+        }
+    }
+
+    pub fn is_provisional(&self) -> bool {
+        matches!(self, MatchState::Provisional { .. })
+    }
+
+    pub fn is_confirmed(&self) -> bool {
+        matches!(self, MatchState::Confirmed { .. })
+    }
+
+    /// The count to display: the estimate, or the confirmed count.
+    pub fn match_count(&self) -> u32 {
+        match self {
+            MatchState::Provisional { estimated_count } => *estimated_count,
+            MatchState::Confirmed { matches, .. } => matches.match_count,
+        }
+    }
+
+    /// Whether the confirmed count differs from the estimate shown
+    /// before it; false while provisional or if nothing was shown.
+    pub fn estimate_was_off(&self) -> bool {
+        match self {
+            MatchState::Confirmed {
+                matches,
+                estimated_count: Some(estimate),
+            } => *estimate != matches.match_count,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    /// Uniform contact hashes for `ids`, below `DECOY_FLOOR`.
+    fn hashes(ids: std::ops::Range<u64>) -> Vec<u128> {
+        ids.map(|i| u128::from_le_bytes(Sha256::digest(i.to_le_bytes())[..16].try_into().unwrap()) >> 1)
+            .collect()
+    }
+
+    fn confirmed(match_count: u32) -> DecryptedMatches {
+        DecryptedMatches {
+            slots: (0..match_count as usize).collect(),
+            match_count,
+        }
+    }
+
+    #[test]
+    fn padding_and_decoys_are_left_out() {
+        let empty = BloomFilter::from_hashes(&[]);
+        assert_eq!(empty.ones(), 0);
+        assert_eq!(empty.estimate_len(), 0.0);
+        assert_eq!(BloomFilter::from_hashes(&[0, DECOY_FLOOR, DECOY_FLOOR + 5, u128::MAX]), empty);
+    }
+
+    #[test]
+    fn estimates_are_close_for_a_full_list() {
+        let (alice, bob) = (hashes(0..32), hashes(22..54));
+        let (alice, bob) = (BloomFilter::from_hashes(&alice), BloomFilter::from_hashes(&bob));
+        assert!((alice.estimate_len() - 32.0).abs() <= 2.0);
+        assert!(alice.estimate_intersection(&bob).abs_diff(10) <= 2);
+        assert!(alice.estimate_intersection(&alice).abs_diff(32) <= 2);
+    }
+
+    #[test]
+    fn disjoint_lists_never_estimate_below_zero() {
+        let alice = BloomFilter::from_hashes(&hashes(0..32));
+        let bob = BloomFilter::from_hashes(&hashes(100..132));
+        assert!(alice.estimate_intersection(&bob) <= 2);
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let filter = BloomFilter::from_hashes(&hashes(0..20));
+        let bytes = filter.to_bytes();
+        assert_eq!(bytes.len(), BLOOM_BITS / 8);
+        assert_eq!(BloomFilter::from_bytes(&bytes), Some(filter));
+        assert_eq!(BloomFilter::from_bytes(&bytes[1..]), None);
+    }
+
+    #[test]
+    fn confirming_keeps_the_first_estimate() {
+        let filter = BloomFilter::from_hashes(&hashes(0..32));
+        let state = MatchState::provisional(&filter, &filter);
+        assert!(state.is_provisional());
+        let estimate = state.match_count();
+
+        let state = state.confirm(confirmed(estimate));
+        assert!(state.is_confirmed());
+        assert!(!state.estimate_was_off());
+
+        let state = state.confirm(confirmed(estimate + 1));
+        assert_eq!(state.match_count(), estimate + 1);
+        assert!(state.estimate_was_off());
+        assert!(matches!(state, MatchState::Confirmed { estimated_count: Some(e), .. } if e == estimate));
+    }
+
+    #[test]
+    fn a_result_with_no_estimate_shown_is_never_off() {
+        let state = MatchState::Confirmed {
+            matches: confirmed(3),
+            estimated_count: None,
+        };
+        assert!(!state.estimate_was_off());
+        assert!(!MatchState::Provisional { estimated_count: 3 }.estimate_was_off());
+    }
+}
@@ -0,0 +1,444 @@
+// RPC helpers: sending (with requeue on a full mempool), account
+// fetching, and waiting for callback events.
+
+use std::{thread, time::Duration, time::Instant};
+
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator, Event};
+use arcium_anchor::prelude::MXEAccount;
+use arcium_client::pda;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_client::{
+    client_error::{ClientError as RpcClientError, ClientErrorKind},
+    rpc_client::RpcClient,
+    rpc_config::{RpcSimulateTransactionConfig, RpcTransactionConfig},
+    rpc_request::{RpcError, RpcResponseErrorData},
+};
+use solana_sdk::{
+    address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount},
+    commitment_config::CommitmentConfig,
+    compute_budget::ComputeBudgetInstruction,
+    instruction::Instruction,
+    message::{v0, VersionedMessage},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::{Transaction, VersionedTransaction},
+};
+
+use private_contact_discovery::{
+    ActivityLog, AppLookupTable, AppStats, ConnectionAttestation, ConsentRecord, CreditAccount, DatabaseStatus,
+    DeploymentRegistry,
+    DiscoveryBlocklist, DiscoveryCardRegistry, DiscoveryDatabase, DiscoveryPool, DiscoverySession, GlobalStats,
+    PairQuota, ProtocolConfig, SessionReport, SessionStateHandle, SessionTemplate, SubmissionRejected, Treasury,
+    ID as PROGRAM_ID,
+};
+
+use crate::accounts::{
+    activity_log_pda, app_lut_pda, app_stats_pda, blocklist_pda, card_registry_pda, comp_def_account, config_pda,
+    connection_pda, consent_record_pda, credit_pda, database_pda, deployment_pda, global_stats_pda, pair_quota_pda,
+    pool_pda, session_pda, session_template_pda, state_handle_pda, treasury_pda,
+};
+use crate::crypto::{DecryptedMatches, PartyCipher, RelayedResult};
+use crate::error::ClientError;
+use crate::instructions;
+
+/// Error name Arcium logs when `queue_computation` finds the mempool full.
+const ARCIUM_MEMPOOL_FULL: &str = "MempoolFull";
+
+/// How `send_with_requeue` retries a queue the Arcium mempool refused.
+#[derive(Clone, Copy, Debug)]
+pub struct RequeuePolicy {
+    /// Attempts after the first
+    pub max_requeues: u32,
+    /// Compute unit price of the first requeue, in micro-lamports;
+    /// doubled on each later one
+    pub initial_cu_price: u64,
+    /// Wait before each requeue, for the mempool to drain
+    pub backoff: Duration,
+}
+
+impl RequeuePolicy {
+    /// Send once; a full mempool fails with `MempoolFull` straight away.
+    pub const NONE: RequeuePolicy = RequeuePolicy {
+        max_requeues: 0,
+        initial_cu_price: 0,
+        backoff: Duration::ZERO,
+    };
+}
+
+impl Default for RequeuePolicy {
+    fn default() -> Self {
+        RequeuePolicy {
+            max_requeues: 4,
+            initial_cu_price: 10_000,
+            backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Sign `ix` with `signer` (also the fee payer) and send it.
+pub fn send(rpc: &RpcClient, signer: &Keypair, ix: Instruction) -> Result<Signature, ClientError> {
+    send_with_requeue(rpc, signer, &[], &[ix], RequeuePolicy::NONE)
+}
+
+/// Send `ixs` in one transaction paid by `payer` and also signed by
+/// `signers`, requeuing while the Arcium mempool is full.
+///
+/// A queue that finds the mempool full reverts its whole transaction, so
+/// the session is untouched and the same instructions (ciphertexts and
+/// computation offset included) are valid again. Each requeue resends
+/// them unchanged after `backoff`, behind an escalating compute unit
+/// price. Any other failure returns at once.
+pub fn send_with_requeue(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    signers: &[&Keypair],
+    ixs: &[Instruction],
+    policy: RequeuePolicy,
+) -> Result<Signature, ClientError> {
+    let all_signers = [&[payer], signers].concat();
+    requeue(ixs, policy, |priced| {
+        let blockhash = rpc.get_latest_blockhash()?;
+        let tx = Transaction::new_signed_with_payer(priced, Some(&payer.pubkey()), &all_signers, blockhash);
+        Ok(rpc.send_and_confirm_transaction(&tx)?)
+    })
+}
+
+/// `send_with_requeue` as a v0 transaction whose account keys resolve
+/// through `lookup_tables`, e.g. the app's table from `fetch_app_lut`.
+/// Queue instructions pass most of their accounts through the table
+/// and so fit several to a transaction.
+pub fn send_versioned_with_requeue(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    signers: &[&Keypair],
+    ixs: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    policy: RequeuePolicy,
+) -> Result<Signature, ClientError> {
+    let all_signers = [&[payer], signers].concat();
+    requeue(ixs, policy, |priced| {
+        let blockhash = rpc.get_latest_blockhash()?;
+        let message = v0::Message::try_compile(&payer.pubkey(), priced, lookup_tables, blockhash)
+            .map_err(|e| ClientError::VersionedTransaction(e.to_string()))?;
+        let tx = VersionedTransaction::try_new(VersionedMessage::V0(message), &all_signers)
+            .map_err(|e| ClientError::VersionedTransaction(e.to_string()))?;
+        Ok(rpc.send_and_confirm_transaction(&tx)?)
+    })
+}
+
+/// Run `send_once` on `ixs`, and again behind an escalating compute
+/// unit price for each requeue `policy` allows while the mempool is full.
+fn requeue(
+    ixs: &[Instruction],
+    policy: RequeuePolicy,
+    mut send_once: impl FnMut(&[Instruction]) -> Result<Signature, ClientError>,
+) -> Result<Signature, ClientError> {
+    let mut cu_price = 0;
+    for attempt in 0..=policy.max_requeues {
+        if attempt > 0 {
+            thread::sleep(policy.backoff);
+            cu_price = if cu_price == 0 { policy.initial_cu_price } else { cu_price.saturating_mul(2) };
+        }
+        let priced: Vec<Instruction> = (cu_price > 0)
+            .then(|| ComputeBudgetInstruction::set_compute_unit_price(cu_price))
+            .into_iter()
+            .chain(ixs.iter().cloned())
+            .collect();
+        match send_once(&priced) {
+            Ok(signature) => return Ok(signature),
+            Err(ClientError::Rpc(err)) if mempool_full(&err) => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Err(ClientError::MempoolFull(policy.max_requeues + 1))
+}
+
+/// Whether preflight failed because Arcium found the mempool full.
+fn mempool_full(err: &RpcClientError) -> bool {
+    match err.kind() {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            data: RpcResponseErrorData::SendTransactionPreflightFailure(result),
+            ..
+        }) => result.logs.iter().flatten().any(|log| log.contains(ARCIUM_MEMPOOL_FULL)),
+        _ => false,
+    }
+}
+
+/// Fetch and decode the MXE account.
+pub fn fetch_mxe(rpc: &RpcClient) -> Result<MXEAccount, ClientError> {
+    let data = rpc.get_account_data(&pda::mxe_acc(&PROGRAM_ID))?;
+    MXEAccount::try_deserialize(&mut data.as_slice()).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// The MXE x25519 public key used to derive each party's shared secret.
+pub fn fetch_mxe_public_key(rpc: &RpcClient) -> Result<[u8; 32], ClientError> {
+    fetch_mxe(rpc)?
+        .utility_pubkeys
+        .x25519_pubkey()
+        .ok_or(ClientError::MxeKeyUnavailable)
+}
+
+/// Whether the computation definition for `circuit` has been created.
+pub fn is_comp_def_initialized(rpc: &RpcClient, circuit: &str) -> Result<bool, ClientError> {
+    let account = rpc
+        .get_account_with_commitment(&comp_def_account(circuit), rpc.commitment())?
+        .value;
+    Ok(account.is_some())
+}
+
+/// Fetch and decode a session by id.
+pub fn fetch_session(rpc: &RpcClient, session_id: &[u8; 32]) -> Result<DiscoverySession, ClientError> {
+    let address = session_pda(session_id);
+    let account = rpc
+        .get_account_with_commitment(&address, rpc.commitment())?
+        .value
+        .ok_or_else(|| ClientError::SessionNotFound(hex::encode(session_id)))?;
+    if account.data.len() < DiscoverySession::SIZE {
+        return Err(ClientError::Decode(format!(
+            "session {} uses an older layout; realloc_session upgrades it",
+            hex::encode(session_id)
+        )));
+    }
+    DiscoverySession::try_from_account_data(&account.data)
+        .map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Decrypt a match result delivered off-chain (by a relayer or webhook)
+/// once it checks out against the recipient's receipt on the session,
+/// so a forged, altered or replayed result is refused before decryption.
+/// Only the recipient's latest result verifies.
+pub fn decrypt_relayed_result(
+    rpc: &RpcClient,
+    cipher: &PartyCipher,
+    result: &RelayedResult,
+) -> Result<DecryptedMatches, ClientError> {
+    let session = fetch_session(rpc, &result.session_id)?;
+    let receipt = match result.party {
+        1 => session.alice_receipt,
+        2 => session.bob_receipt,
+        _ => return Err(ClientError::ReceiptMismatch(result.party)),
+    };
+    cipher.decrypt_relayed(result, &receipt)
+}
+
+/// Simulate `view_session` and decode the `SessionReport` it returns.
+/// Nothing is sent; `payer` only has to be an existing account.
+pub fn view_session(rpc: &RpcClient, payer: &Pubkey, session_id: &[u8; 32]) -> Result<SessionReport, ClientError> {
+    let tx = Transaction::new_with_payer(&[instructions::view_session(session_id)], Some(payer));
+    let result = rpc
+        .simulate_transaction_with_config(
+            &tx,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(rpc.commitment()),
+                ..Default::default()
+            },
+        )?
+        .value;
+    if let Some(err) = result.err {
+        // A missing session fails the account check before the handler runs
+        return Err(match rpc.get_account_with_commitment(&session_pda(session_id), rpc.commitment())?.value {
+            None => ClientError::SessionNotFound(hex::encode(session_id)),
+            Some(_) => ClientError::Decode(format!("view_session failed: {err}")),
+        });
+    }
+    let return_data = result
+        .return_data
+        .filter(|data| data.program_id == PROGRAM_ID.to_string())
+        .ok_or_else(|| ClientError::Decode("view_session returned no data".into()))?;
+    let bytes = STANDARD
+        .decode(&return_data.data.0)
+        .map_err(|e| ClientError::Decode(e.to_string()))?;
+    SessionReport::try_from_slice(&bytes).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Fetch and decode the program-wide `GlobalStats` counters.
+pub fn fetch_global_stats(rpc: &RpcClient) -> Result<GlobalStats, ClientError> {
+    let data = rpc.get_account_data(&global_stats_pda())?;
+    GlobalStats::try_deserialize(&mut data.as_slice()).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Fetch and decode one application's `AppStats` counters.
+pub fn fetch_app_stats(rpc: &RpcClient, app_id: &[u8; 32]) -> Result<AppStats, ClientError> {
+    let data = rpc.get_account_data(&app_stats_pda(app_id))?;
+    AppStats::try_deserialize(&mut data.as_slice()).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Fetch and decode the deployment's `ProtocolConfig`.
+pub fn fetch_config(rpc: &RpcClient) -> Result<ProtocolConfig, ClientError> {
+    let data = rpc.get_account_data(&config_pda())?;
+    ProtocolConfig::try_deserialize(&mut data.as_slice()).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Fetch and decode the deployment's `Treasury`.
+pub fn fetch_treasury(rpc: &RpcClient) -> Result<Treasury, ClientError> {
+    let data = rpc.get_account_data(&treasury_pda())?;
+    Treasury::try_deserialize(&mut data.as_slice()).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Fetch and decode one application's prepaid `CreditAccount`.
+pub fn fetch_credit_account(rpc: &RpcClient, app_id: &[u8; 32]) -> Result<CreditAccount, ClientError> {
+    let data = rpc.get_account_data(&credit_pda(app_id))?;
+    CreditAccount::try_deserialize(&mut data.as_slice()).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Fetch the attestation of two wallets' connection, in either order.
+pub fn fetch_connection(rpc: &RpcClient, a: &Pubkey, b: &Pubkey) -> Result<ConnectionAttestation, ClientError> {
+    let data = rpc.get_account_data(&connection_pda(a, b))?;
+    ConnectionAttestation::try_deserialize(&mut data.as_slice()).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Fetch who may create sessions naming `wallet` as their Bob.
+pub fn fetch_consent_record(rpc: &RpcClient, wallet: &Pubkey) -> Result<ConsentRecord, ClientError> {
+    let data = rpc.get_account_data(&consent_record_pda(wallet))?;
+    ConsentRecord::try_deserialize(&mut data.as_slice()).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Fetch the session quota of two wallets, in either order.
+pub fn fetch_pair_quota(rpc: &RpcClient, a: &Pubkey, b: &Pubkey) -> Result<PairQuota, ClientError> {
+    let data = rpc.get_account_data(&pair_quota_pda(a, b))?;
+    PairQuota::try_deserialize(&mut data.as_slice()).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Fetch `wallet`'s activity log; `ActivityLog::entries` lists it newest
+/// first.
+pub fn fetch_activity_log(rpc: &RpcClient, wallet: &Pubkey) -> Result<ActivityLog, ClientError> {
+    let data = rpc.get_account_data(&activity_log_pda(wallet))?;
+    ActivityLog::try_deserialize(&mut data.as_slice()).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Fetch and decode the `DeploymentRegistry`.
+pub fn fetch_deployment(rpc: &RpcClient) -> Result<DeploymentRegistry, ClientError> {
+    let data = rpc.get_account_data(&deployment_pda())?;
+    DeploymentRegistry::try_deserialize(&mut data.as_slice()).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Fetch `app_id`'s lookup table, ready for `send_versioned_with_requeue`.
+pub fn fetch_app_lut(rpc: &RpcClient, app_id: &[u8; 32]) -> Result<AddressLookupTableAccount, ClientError> {
+    let data = rpc.get_account_data(&app_lut_pda(app_id))?;
+    let app_lut =
+        AppLookupTable::try_deserialize(&mut data.as_slice()).map_err(|e| ClientError::Decode(e.to_string()))?;
+    let data = rpc.get_account_data(&app_lut.table)?;
+    let table = AddressLookupTable::deserialize(&data).map_err(|e| ClientError::Decode(e.to_string()))?;
+    Ok(AddressLookupTableAccount {
+        key: app_lut.table,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+/// Fetch and decode the `SessionStateHandle` of a session.
+pub fn fetch_state_handle(rpc: &RpcClient, session_id: &[u8; 32]) -> Result<SessionStateHandle, ClientError> {
+    let data = rpc.get_account_data(&state_handle_pda(&session_pda(session_id)))?;
+    SessionStateHandle::try_deserialize(&mut data.as_slice()).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Fetch and decode one of an application's `SessionTemplate`s.
+pub fn fetch_session_template(
+    rpc: &RpcClient,
+    app_id: &[u8; 32],
+    template_id: &[u8; 32],
+) -> Result<SessionTemplate, ClientError> {
+    let data = rpc.get_account_data(&session_template_pda(app_id, template_id))?;
+    SessionTemplate::try_deserialize(&mut data.as_slice()).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Fetch and decode a matchmaking pool by id.
+pub fn fetch_pool(rpc: &RpcClient, pool_id: &[u8; 32]) -> Result<DiscoveryPool, ClientError> {
+    let account = rpc
+        .get_account_with_commitment(&pool_pda(pool_id), rpc.commitment())?
+        .value
+        .ok_or_else(|| ClientError::PoolNotFound(hex::encode(pool_id)))?;
+    DiscoveryPool::try_deserialize(&mut account.data.as_slice())
+        .map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Fetch and decode a moderator blocklist by id.
+pub fn fetch_blocklist(rpc: &RpcClient, blocklist_id: &[u8; 32]) -> Result<DiscoveryBlocklist, ClientError> {
+    let account = rpc
+        .get_account_with_commitment(&blocklist_pda(blocklist_id), rpc.commitment())?
+        .value
+        .ok_or_else(|| ClientError::BlocklistNotFound(hex::encode(blocklist_id)))?;
+    DiscoveryBlocklist::try_deserialize(&mut account.data.as_slice())
+        .map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Fetch and decode a contact card registry by id.
+pub fn fetch_card_registry(
+    rpc: &RpcClient,
+    registry_id: &[u8; 32],
+) -> Result<DiscoveryCardRegistry, ClientError> {
+    let account = rpc
+        .get_account_with_commitment(&card_registry_pda(registry_id), rpc.commitment())?
+        .value
+        .ok_or_else(|| ClientError::CardRegistryNotFound(hex::encode(registry_id)))?;
+    DiscoveryCardRegistry::try_deserialize(&mut account.data.as_slice())
+        .map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Fetch and decode one epoch of a server database.
+pub fn fetch_database(rpc: &RpcClient, database_id: &[u8; 32], epoch: u32) -> Result<DiscoveryDatabase, ClientError> {
+    let account = rpc
+        .get_account_with_commitment(&database_pda(database_id, epoch), rpc.commitment())?
+        .value
+        .ok_or_else(|| ClientError::DatabaseNotFound(hex::encode(database_id)))?;
+    DiscoveryDatabase::try_from_account_data(&account.data)
+        .map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Fetch the newest epoch of a server database that is not retired:
+/// the live one, unless epoch 0 is still loading. Walks the epochs from
+/// 0, one fetch each.
+pub fn fetch_live_database(rpc: &RpcClient, database_id: &[u8; 32]) -> Result<DiscoveryDatabase, ClientError> {
+    let mut database = fetch_database(rpc, database_id, 0)?;
+    while database.status == DatabaseStatus::Retired as u8 {
+        database = fetch_database(rpc, database_id, database.epoch + 1)?;
+    }
+    Ok(database)
+}
+
+/// Decode the first event of type `E` from a transaction's log messages.
+pub fn decode_event<E: Event + AnchorDeserialize>(logs: &[String]) -> Option<E> {
+    logs.iter()
+        .filter_map(|line| line.strip_prefix("Program data: "))
+        .filter_map(|data| STANDARD.decode(data).ok())
+        .find(|bytes| bytes.starts_with(E::DISCRIMINATOR))
+        .and_then(|bytes| E::deserialize(&mut &bytes[E::DISCRIMINATOR.len()..]).ok())
+}
+
+/// Poll the transactions touching `computation_account` until one emits
+/// event `E`. Callbacks land in a separate transaction once the MPC finalizes.
+pub fn await_event<E: Event + AnchorDeserialize>(
+    rpc: &RpcClient,
+    computation_account: &Pubkey,
+    timeout: Duration,
+) -> Result<E, ClientError> {
+    let deadline = Instant::now() + timeout;
+    let config = RpcTransactionConfig {
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+        ..Default::default()
+    };
+
+    while Instant::now() < deadline {
+        for status in rpc.get_signatures_for_address(computation_account)? {
+            let Ok(signature) = status.signature.parse::<Signature>() else {
+                continue;
+            };
+            let Ok(tx) = rpc.get_transaction_with_config(&signature, config) else {
+                continue;
+            };
+            let logs: Option<Vec<String>> = tx.transaction.meta.and_then(|m| m.log_messages.into());
+            if let Some(event) = logs.as_deref().and_then(decode_event::<E>) {
+                return Ok(event);
+            }
+            if let Some(rejected) = logs.as_deref().and_then(decode_event::<SubmissionRejected>) {
+                return Err(ClientError::SubmissionRejected(rejected.party));
+            }
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+
+    Err(ClientError::Timeout(std::any::type_name::<E>()))
+}
@@ -0,0 +1,346 @@
+// Multi-session sharding for address books larger than one session.
+//
+// A session compares at most 32 contacts per side, so N contacts on
+// Alice's side and M on Bob's need a grid of ⌈N/32⌉ × ⌈M/32⌉ sessions
+// for full coverage: session (i, j) holds Alice's chunk i and Bob's
+// chunk j. Both parties derive the same session ids from one shared
+// base id, so only the base id and the shard counts are exchanged
+// out of band.
+//
+// Sessions are driven concurrently (one thread per session) and the
+// decrypted results merged into one deduplicated match set.
+
+use std::{collections::BTreeSet, sync::Mutex, thread, time::Duration};
+
+use private_contact_discovery::{
+    AliceRevealed, AliceSubmitted, MatchComplete, SessionInitialized, SessionMode, NO_APP_ID,
+    NO_INVITE, NO_MEMO,
+};
+use sha2::{Digest, Sha256};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+use crate::{
+    accounts::{arcium_accounts, circuits},
+    crypto::{random_computation_offset, random_nonce},
+    error::ClientError,
+    hash::{hash_contact_list, HashScheme, Namespace, MAX_CONTACTS},
+    instructions, rpc, PartyCipher,
+};
+
+/// Which side of every session in the grid this client plays.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Alice,
+    Bob,
+}
+
+/// One session in the grid, from this client's point of view.
+#[derive(Clone, Debug)]
+pub struct ShardAssignment {
+    pub alice_shard: usize,
+    pub bob_shard: usize,
+    pub session_id: [u8; 32],
+    /// This client's contacts for the session (at most 32)
+    pub contacts: Vec<String>,
+}
+
+/// Progress reported while a plan runs.
+#[derive(Clone, Debug)]
+pub enum ShardProgress {
+    Queued { session_id: [u8; 32] },
+    Completed { session_id: [u8; 32], done: usize, total: usize },
+    Failed { session_id: [u8; 32], error: String },
+}
+
+/// Deduplicated matches merged across every session in a plan.
+#[derive(Clone, Debug, Default)]
+pub struct MergedMatches {
+    pub matches: BTreeSet<u128>,
+    pub sessions_completed: usize,
+    pub sessions_failed: usize,
+}
+
+/// Number of sessions needed for `contact_count` contacts on one side.
+pub fn shard_count(contact_count: usize) -> usize {
+    contact_count.div_ceil(MAX_CONTACTS).max(1)
+}
+
+/// Session id for cell (alice_shard, bob_shard) of the grid.
+/// Seeds: sha256("pcd-shard" || base || alice_shard || bob_shard)
+pub fn shard_session_id(base: &[u8; 32], alice_shard: usize, bob_shard: usize) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"pcd-shard");
+    hasher.update(base);
+    hasher.update((alice_shard as u32).to_le_bytes());
+    hasher.update((bob_shard as u32).to_le_bytes());
+    hasher.finalize().into()
+}
+
+/// A full sharding plan for one party.
+#[derive(Clone, Debug)]
+pub struct ShardPlan {
+    pub role: Role,
+    pub assignments: Vec<ShardAssignment>,
+}
+
+impl ShardPlan {
+    /// Split `contacts` (already normalized and deduplicated) into chunks
+    /// and lay out the session grid against a peer with `peer_shards` chunks.
+    pub fn new(role: Role, base_session_id: [u8; 32], contacts: &[String], peer_shards: usize) -> Self {
+        let chunks: Vec<&[String]> = if contacts.is_empty() {
+            vec![&[]]
+        } else {
+            contacts.chunks(MAX_CONTACTS).collect()
+        };
+
+        let mut assignments = Vec::with_capacity(chunks.len() * peer_shards);
+        for (mine, chunk) in chunks.iter().enumerate() {
+            for peer in 0..peer_shards.max(1) {
+                let (alice_shard, bob_shard) = match role {
+                    Role::Alice => (mine, peer),
+                    Role::Bob => (peer, mine),
+                };
+                assignments.push(ShardAssignment {
+                    alice_shard,
+                    bob_shard,
+                    session_id: shard_session_id(&base_session_id, alice_shard, bob_shard),
+                    contacts: chunk.to_vec(),
+                });
+            }
+        }
+
+        Self { role, assignments }
+    }
+}
+
+/// Drives a `ShardPlan` against the chain.
+pub struct ShardRunner<'a> {
+    pub rpc_url: String,
+    pub signer: &'a Keypair,
+    pub cluster_offset: u32,
+    pub timeout: Duration,
+    /// Identifier type of every contact in the plan
+    pub namespace: Namespace,
+    /// Hash scheme both parties agreed on
+    pub scheme: HashScheme,
+}
+
+impl ShardRunner<'_> {
+    /// Alice: create every session in the plan and submit her chunk to it.
+    pub fn create_and_submit(
+        &self,
+        plan: &ShardPlan,
+        progress: &(dyn Fn(ShardProgress) + Sync),
+    ) -> MergedMatches {
+        self.run(plan, progress, |rpc, cipher, a| {
+            let offset = random_computation_offset();
+            rpc::send(rpc, self.signer, instructions::create_session(
+                self.signer.pubkey(),
+                self.signer.pubkey(),
+                self.cluster_offset,
+                offset,
+                a.session_id,
+                NO_APP_ID,
+                cipher.public_key(),
+                u128::from_le_bytes(random_nonce()),
+                SessionMode::OneShot as u8,
+                self.namespace as u8,
+                self.scheme as u8,
+                false,
+                false,
+                0,
+                0,
+                MAX_CONTACTS as u8,
+                NO_MEMO,
+                Pubkey::default(),
+                NO_INVITE,
+            ))?;
+            let computation = arcium_accounts(self.cluster_offset, circuits::INIT_SESSION, offset).computation_account;
+            rpc::await_event::<SessionInitialized>(rpc, &computation, self.timeout)?;
+
+            let encrypted = cipher.encrypt_contacts(&hash_contact_list(self.scheme, self.namespace, &a.contacts)?);
+            let offset = random_computation_offset();
+            rpc::send(rpc, self.signer, instructions::submit_contacts_alice(
+                self.signer.pubkey(),
+                self.signer.pubkey(),
+                self.cluster_offset,
+                offset,
+                &a.session_id,
+                &NO_APP_ID,
+                &encrypted,
+                MAX_CONTACTS as u8,
+            ))?;
+            let computation = arcium_accounts(self.cluster_offset, circuits::SUBMIT_ALICE, offset).computation_account;
+            rpc::await_event::<AliceSubmitted>(rpc, &computation, self.timeout)?;
+            Ok(Vec::new())
+        })
+    }
+
+    /// Bob: submit each chunk to its sessions and merge his matches.
+    pub fn submit_and_match(
+        &self,
+        plan: &ShardPlan,
+        progress: &(dyn Fn(ShardProgress) + Sync),
+    ) -> MergedMatches {
+        self.run(plan, progress, |rpc, cipher, a| {
+            let hashed = hash_contact_list(self.scheme, self.namespace, &a.contacts)?;
+            let encrypted = cipher.encrypt_contacts(&hashed);
+            let offset = random_computation_offset();
+            let alice = rpc::fetch_session(rpc, &a.session_id)?.alice;
+            rpc::send(rpc, self.signer, instructions::submit_and_match(
+                self.signer.pubkey(),
+                self.signer.pubkey(),
+                alice,
+                self.cluster_offset,
+                offset,
+                &a.session_id,
+                &NO_APP_ID,
+                &encrypted,
+                MAX_CONTACTS as u8,
+                false,
+            ))?;
+            let computation =
+                arcium_accounts(self.cluster_offset, circuits::SUBMIT_AND_MATCH, offset).computation_account;
+            let event: MatchComplete = rpc::await_event(rpc, &computation, self.timeout)?;
+            Ok(cipher
+                .decrypt_matches(&event.encrypted_matches, &event.encrypted_count, &event.nonce)
+                .hashes(&hashed.hashes))
+        })
+    }
+
+    /// Alice: reveal every session in the plan and merge her matches.
+    pub fn reveal(&self, plan: &ShardPlan, progress: &(dyn Fn(ShardProgress) + Sync)) -> MergedMatches {
+        self.run(plan, progress, |rpc, cipher, a| {
+            let offset = random_computation_offset();
+            rpc::send(rpc, self.signer, instructions::reveal_alice_matches(
+                self.signer.pubkey(),
+                self.signer.pubkey(),
+                self.cluster_offset,
+                offset,
+                &a.session_id,
+                &NO_APP_ID,
+                cipher.public_key(),
+                u128::from_le_bytes(random_nonce()),
+                0,
+            ))?;
+            let computation = arcium_accounts(self.cluster_offset, circuits::REVEAL_ALICE, offset).computation_account;
+            let event: AliceRevealed = rpc::await_event(rpc, &computation, self.timeout)?;
+            // Slots name Alice's shard list, rebuilt as she submitted it
+            let hashed = hash_contact_list(self.scheme, self.namespace, &a.contacts)?;
+            Ok(cipher
+                .decrypt_matches(&event.encrypted_matches, &event.encrypted_count, &event.nonce)
+                .hashes(&hashed.hashes))
+        })
+    }
+
+    /// Run `step` for every assignment concurrently and merge the results.
+    fn run<F>(&self, plan: &ShardPlan, progress: &(dyn Fn(ShardProgress) + Sync), step: F) -> MergedMatches
+    where
+        F: Fn(&RpcClient, &PartyCipher, &ShardAssignment) -> Result<Vec<u128>, ClientError> + Sync,
+    {
+        let merged = Mutex::new(MergedMatches::default());
+        let total = plan.assignments.len();
+
+        thread::scope(|scope| {
+            for assignment in &plan.assignments {
+                let (merged, step) = (&merged, &step);
+                scope.spawn(move || {
+                    let rpc = RpcClient::new_with_commitment(self.rpc_url.clone(), CommitmentConfig::confirmed());
+                    progress(ShardProgress::Queued { session_id: assignment.session_id });
+
+                    let outcome = rpc::fetch_mxe_public_key(&rpc)
+                        .map(PartyCipher::new)
+                        .and_then(|cipher| step(&rpc, &cipher, assignment));
+
+                    let mut merged = merged.lock().unwrap();
+                    match outcome {
+                        Ok(matches) => {
+                            merged.matches.extend(matches);
+                            merged.sessions_completed += 1;
+                            progress(ShardProgress::Completed {
+                                session_id: assignment.session_id,
+                                done: merged.sessions_completed + merged.sessions_failed,
+                                total,
+                            });
+                        }
+                        Err(e) => {
+                            merged.sessions_failed += 1;
+                            progress(ShardProgress::Failed {
+                                session_id: assignment.session_id,
+                                error: e.to_string(),
+                            });
+                        }
+                    }
+                });
+            }
+        });
+
+        merged.into_inner().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: [u8; 32] = [7; 32];
+
+    fn contacts(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("+1555{i:07}")).collect()
+    }
+
+    fn cells(plan: &ShardPlan) -> BTreeSet<(usize, usize, [u8; 32])> {
+        plan.assignments.iter().map(|a| (a.alice_shard, a.bob_shard, a.session_id)).collect()
+    }
+
+    #[test]
+    fn shard_count_rounds_up_to_whole_sessions() {
+        assert_eq!(shard_count(0), 1);
+        assert_eq!(shard_count(1), 1);
+        assert_eq!(shard_count(MAX_CONTACTS), 1);
+        assert_eq!(shard_count(MAX_CONTACTS + 1), 2);
+        assert_eq!(shard_count(3 * MAX_CONTACTS), 3);
+    }
+
+    #[test]
+    fn shard_session_ids_are_deterministic_and_distinct_per_cell() {
+        assert_eq!(shard_session_id(&BASE, 1, 2), shard_session_id(&BASE, 1, 2));
+        assert_ne!(shard_session_id(&BASE, 1, 2), shard_session_id(&BASE, 2, 1));
+        assert_ne!(shard_session_id(&BASE, 0, 0), shard_session_id(&[8; 32], 0, 0));
+
+        let ids: BTreeSet<_> = (0..4).flat_map(|i| (0..4).map(move |j| shard_session_id(&BASE, i, j))).collect();
+        assert_eq!(ids.len(), 16);
+    }
+
+    #[test]
+    fn alice_and_bob_lay_out_the_same_grid() {
+        let (alice, bob) = (contacts(40), contacts(70));
+        let alice_plan = ShardPlan::new(Role::Alice, BASE, &alice, shard_count(bob.len()));
+        let bob_plan = ShardPlan::new(Role::Bob, BASE, &bob, shard_count(alice.len()));
+
+        assert_eq!(alice_plan.assignments.len(), 2 * 3);
+        assert_eq!(cells(&alice_plan), cells(&bob_plan));
+    }
+
+    #[test]
+    fn each_assignment_holds_its_own_chunk() {
+        let alice = contacts(40);
+        let plan = ShardPlan::new(Role::Alice, BASE, &alice, 2);
+        for a in &plan.assignments {
+            let start = a.alice_shard * MAX_CONTACTS;
+            assert_eq!(a.contacts, alice[start..alice.len().min(start + MAX_CONTACTS)]);
+        }
+    }
+
+    #[test]
+    fn an_empty_list_still_gets_one_shard() {
+        let plan = ShardPlan::new(Role::Bob, BASE, &[], 2);
+        assert_eq!(plan.assignments.len(), 2);
+        assert!(plan.assignments.iter().all(|a| a.bob_shard == 0 && a.contacts.is_empty()));
+
+        let plan = ShardPlan::new(Role::Alice, BASE, &[], 0);
+        assert_eq!(plan.assignments.len(), 1);
+        assert_eq!(plan.assignments[0].session_id, shard_session_id(&BASE, 0, 0));
+    }
+}
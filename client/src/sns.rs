@@ -0,0 +1,108 @@
+// Solana name resolution for the `SolName` namespace.
+//
+// A session in the `SolName` namespace matches lists of name-service
+// handles (`bonfida.sol`, `@toly`) like any other identifier: each side
+// hashes its handles locally and the MPC reveals only the shared ones.
+// Once a session has matched, these helpers resolve the matched handles
+// to the wallets that own them, so a user learns which of their known
+// handles are live wallets without publishing the handles they hold.
+//
+// Resolution reads name accounts over RPC, so the node sees every name
+// looked up. Resolve matched handles only, never the whole input list.
+//
+// Only `.sol` names (and their subdomains) resolve here, by deriving
+// the SPL Name Service account directly. Other AllDomains TLDs hash and
+// match the same way but need that registry's own resolver.
+
+use sha2::{Digest, Sha256};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{pubkey, pubkey::Pubkey};
+
+use crate::error::ClientError;
+use crate::hash::normalize_name;
+
+/// The SPL Name Service program.
+pub const NAME_SERVICE_PROGRAM_ID: Pubkey = pubkey!("namesLPneVptA9Z5rqUDD9tMTWEJwofgaYwp8cawRkX");
+
+/// Parent of every `.sol` domain.
+pub const SOL_TLD_AUTHORITY: Pubkey = pubkey!("58PwtjSDuFHuUkYjH9BYnnQKHfwo9reZhC2zMJv9JPkx");
+
+/// Prefix hashed with each label to derive its name account.
+const HASH_PREFIX: &str = "SPL Name Service";
+
+/// Accounts per `getMultipleAccounts` call.
+const MAX_ACCOUNTS_PER_CALL: usize = 100;
+
+/// The owner sits after the parent in a name account's header.
+const OWNER_OFFSET: usize = 32;
+
+/// The name account of `name` (normalized first), or `UnsupportedName`
+/// for a TLD other than `.sol`. `sub.bonfida.sol` derives under the
+/// account of `bonfida.sol`, as the name service stores subdomains.
+pub fn sol_name_account(name: &str) -> Result<Pubkey, ClientError> {
+    let normalized = normalize_name(name);
+    let labels: Vec<&str> = normalized
+        .strip_suffix(".sol")
+        .ok_or_else(|| ClientError::UnsupportedName(normalized.clone()))?
+        .split('.')
+        .collect();
+    if labels.iter().any(|label| label.is_empty()) {
+        return Err(ClientError::UnsupportedName(normalized));
+    }
+
+    let (domain, subdomains) = labels.split_last().expect("split yields at least one label");
+    let mut parent = name_account(domain, &SOL_TLD_AUTHORITY);
+    for label in subdomains.iter().rev() {
+        // Subdomain labels are stored with a leading NUL
+        parent = name_account(&format!("\0{label}"), &parent);
+    }
+    Ok(parent)
+}
+
+fn name_account(label: &str, parent: &Pubkey) -> Pubkey {
+    let hashed = Sha256::digest(format!("{HASH_PREFIX}{label}").as_bytes());
+    let (key, _) = Pubkey::find_program_address(
+        &[hashed.as_slice(), Pubkey::default().as_ref(), parent.as_ref()],
+        &NAME_SERVICE_PROGRAM_ID,
+    );
+    key
+}
+
+/// The wallet owning `name`, or `None` if it is not registered.
+pub fn resolve_sol_name(rpc: &RpcClient, name: &str) -> Result<Option<Pubkey>, ClientError> {
+    let key = sol_name_account(name)?;
+    let account = rpc.get_multiple_accounts(&[key])?.pop().flatten();
+    account.map(|account| owner_of(&account.data)).transpose()
+}
+
+/// Resolve `names` in as few RPC calls as possible, pairing each with
+/// its owner (`None` if unregistered). Fails on the first name that is
+/// not a `.sol` name, before any lookup.
+pub fn resolve_names<S: AsRef<str>>(
+    rpc: &RpcClient,
+    names: &[S],
+) -> Result<Vec<(String, Option<Pubkey>)>, ClientError> {
+    let keys = names
+        .iter()
+        .map(|name| sol_name_account(name.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut owners = Vec::with_capacity(keys.len());
+    for chunk in keys.chunks(MAX_ACCOUNTS_PER_CALL) {
+        for account in rpc.get_multiple_accounts(chunk)? {
+            owners.push(account.map(|account| owner_of(&account.data)).transpose()?);
+        }
+    }
+    Ok(names
+        .iter()
+        .map(|name| normalize_name(name.as_ref()))
+        .zip(owners)
+        .collect())
+}
+
+fn owner_of(data: &[u8]) -> Result<Pubkey, ClientError> {
+    data.get(OWNER_OFFSET..OWNER_OFFSET + 32)
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .map(Pubkey::new_from_array)
+        .ok_or_else(|| ClientError::Decode("name account header is truncated".into()))
+}
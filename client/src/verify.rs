@@ -0,0 +1,159 @@
+// Independent verification of signed computation outputs.
+//
+// Every callback carries `SignedComputationOutputs<T>`, which the
+// program checks against the cluster and computation accounts before
+// emitting anything. These helpers run the same `verify_output` check
+// off-chain, from the callback transaction itself, so results read from
+// events or an indexer can be confirmed without trusting the source.
+
+use anchor_lang::{prelude::*, Discriminator};
+use arcium_anchor::prelude::{Cluster, SignedComputationOutputs};
+use arcium_client::pda;
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_sdk::{
+    account::Account as SolanaAccount, bs58, commitment_config::CommitmentConfig, instruction::CompiledInstruction,
+    signature::Signature,
+};
+use solana_transaction_status::{UiInnerInstructions, UiInstruction, UiLoadedAddresses, UiTransactionEncoding};
+
+use private_contact_discovery::ID as PROGRAM_ID;
+
+use crate::error::ClientError;
+
+/// Check `output` against the cluster and computation accounts, exactly as
+/// the program's callback does, returning the verified output on success.
+pub fn verify_signed_output<T>(
+    output: SignedComputationOutputs<T>,
+    cluster: (Pubkey, SolanaAccount),
+    computation: (Pubkey, SolanaAccount),
+) -> std::result::Result<T, ClientError> {
+    let (cluster_key, mut cluster) = cluster;
+    let (computation_key, mut computation) = computation;
+
+    let cluster_info = account_info(&cluster_key, &mut cluster);
+    let computation_info = account_info(&computation_key, &mut computation);
+    let cluster_account = Account::<Cluster>::try_from(&cluster_info)
+        .map_err(|e| ClientError::Decode(e.to_string()))?;
+    let computation_account = UncheckedAccount::try_from(&computation_info);
+
+    output
+        .verify_output(&cluster_account, &computation_account)
+        .map_err(|e| ClientError::Unverified(e.to_string()))
+}
+
+/// Find the callback for `computation_account` (instruction `C`, e.g.
+/// `instruction::SubmitAndMatchCallback`), decode its signed output, and
+/// verify it against the current cluster and computation accounts.
+pub fn verify_callback<C, T>(
+    rpc: &RpcClient,
+    cluster_offset: u32,
+    computation_account: &Pubkey,
+) -> std::result::Result<T, ClientError>
+where
+    C: Discriminator,
+    T: AnchorDeserialize,
+{
+    let output = find_callback_output::<C, T>(rpc, computation_account)?;
+    let cluster_key = pda::cluster_acc(cluster_offset);
+    let cluster = rpc.get_account(&cluster_key)?;
+    let computation = rpc.get_account(computation_account)?;
+    verify_signed_output(output, (cluster_key, cluster), (*computation_account, computation))
+}
+
+/// Decode the signed output from the callback transaction that touched
+/// `computation_account`.
+fn find_callback_output<C, T>(
+    rpc: &RpcClient,
+    computation_account: &Pubkey,
+) -> std::result::Result<SignedComputationOutputs<T>, ClientError>
+where
+    C: Discriminator,
+    T: AnchorDeserialize,
+{
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+
+    for status in rpc.get_signatures_for_address(computation_account)? {
+        if status.err.is_some() {
+            continue;
+        }
+        let Ok(signature) = status.signature.parse::<Signature>() else {
+            continue;
+        };
+        let confirmed = rpc.get_transaction_with_config(&signature, config)?.transaction;
+        let Some(tx) = confirmed.transaction.decode() else {
+            continue;
+        };
+        let (loaded, inner) = match confirmed.meta {
+            Some(meta) => (
+                Option::<UiLoadedAddresses>::from(meta.loaded_addresses),
+                Option::<Vec<UiInnerInstructions>>::from(meta.inner_instructions),
+            ),
+            None => (None, None),
+        };
+
+        // Inner instructions index into the static keys followed by
+        // those loaded from lookup tables, writable first
+        let mut keys = tx.message.static_account_keys().to_vec();
+        if let Some(loaded) = loaded {
+            let Ok(loaded) = loaded
+                .writable
+                .iter()
+                .chain(&loaded.readonly)
+                .map(|key| key.parse())
+                .collect::<std::result::Result<Vec<Pubkey>, _>>()
+            else {
+                continue;
+            };
+            keys.extend(loaded);
+        }
+
+        // The callback's only argument is the signed output
+        if let Some(args) = callback_args::<C>(&keys, tx.message.instructions(), &inner.unwrap_or_default()) {
+            return SignedComputationOutputs::<T>::deserialize(&mut &args[..])
+                .map_err(|e| ClientError::Decode(e.to_string()));
+        }
+    }
+
+    Err(ClientError::CallbackNotFound(computation_account.to_string()))
+}
+
+/// Arguments of the first instruction `C` of this program in a
+/// transaction, after its discriminator: among the top-level
+/// `instructions`, or among the `inner_instructions` when the callback
+/// was invoked by CPI. `account_keys` are those the instructions index
+/// into.
+pub fn callback_args<C: Discriminator>(
+    account_keys: &[Pubkey],
+    instructions: &[CompiledInstruction],
+    inner_instructions: &[UiInnerInstructions],
+) -> Option<Vec<u8>> {
+    let top_level = instructions.iter().map(|ix| (ix.program_id_index, ix.data.clone()));
+    let nested = inner_instructions
+        .iter()
+        .flat_map(|inner| &inner.instructions)
+        .filter_map(|ix| match ix {
+            UiInstruction::Compiled(ix) => Some((ix.program_id_index, bs58::decode(&ix.data).into_vec().ok()?)),
+            UiInstruction::Parsed(_) => None,
+        });
+    top_level
+        .chain(nested)
+        .filter(|(program, _)| account_keys.get(*program as usize) == Some(&PROGRAM_ID))
+        .find_map(|(_, data)| data.strip_prefix(C::DISCRIMINATOR).map(<[u8]>::to_vec))
+}
+
+fn account_info<'a>(key: &'a Pubkey, account: &'a mut SolanaAccount) -> AccountInfo<'a> {
+    AccountInfo::new(
+        key,
+        false,
+        false,
+        &mut account.lamports,
+        &mut account.data,
+        &account.owner,
+        account.executable,
+        account.rent_epoch,
+    )
+}
@@ -0,0 +1,195 @@
+// wasm-bindgen exports for in-browser clients.
+//
+// u128 hashes cross the JS boundary as 32-character hex strings and
+// ciphertexts as 32-byte arrays, so callers need no BigInt plumbing.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+use x25519_dalek::StaticSecret;
+
+use crate::{crypto::PartyCipher, hash, HashScheme, HashedContacts, Namespace, MATCH_WORDS, MAX_CONTACTS};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsHashedContacts {
+    hashes: Vec<String>,
+    count: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsEncryptedContacts {
+    encrypted_hashes: Vec<[u8; 32]>,
+    encrypted_count: [u8; 32],
+    encrypted_namespace: [u8; 32],
+    pubkey: [u8; 32],
+    /// u128 nonce as little-endian bytes
+    nonce: [u8; 16],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsMatches {
+    /// Matched slots of the caller's own submitted list
+    slots: Vec<u32>,
+    match_count: u32,
+}
+
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    serde_wasm_bindgen::to_value(value).map_err(|e| JsError::new(&e.to_string()))
+}
+
+fn key32(bytes: &[u8], what: &str) -> Result<[u8; 32], JsError> {
+    bytes
+        .try_into()
+        .map_err(|_| JsError::new(&format!("{what} must be 32 bytes")))
+}
+
+fn parse_hash(hex_hash: &str) -> Result<u128, JsError> {
+    u128::from_str_radix(hex_hash, 16).map_err(|_| JsError::new("invalid hash"))
+}
+
+fn parse_namespace(namespace: u8) -> Result<Namespace, JsError> {
+    Namespace::try_from(namespace).map_err(|e| JsError::new(&e.to_string()))
+}
+
+fn parse_scheme(scheme: u8) -> Result<HashScheme, JsError> {
+    HashScheme::try_from(scheme).map_err(|e| JsError::new(&e.to_string()))
+}
+
+#[wasm_bindgen(js_name = normalizeContact)]
+pub fn normalize_contact(contact: &str) -> String {
+    hash::normalize_contact(contact)
+}
+
+#[wasm_bindgen(js_name = normalizeName)]
+pub fn normalize_name(name: &str) -> String {
+    hash::normalize_name(name)
+}
+
+/// Hash one contact; `namespace` is 0=phone, 1=email, 2=username, 3=wallet,
+/// 4=Solana name and `scheme` is 0=SHA-256, 1=BLAKE3.
+#[wasm_bindgen(js_name = hashContact)]
+pub fn hash_contact(contact: &str, namespace: u8, scheme: u8) -> Result<String, JsError> {
+    let hash = hash::hash_contact(parse_scheme(scheme)?, parse_namespace(namespace)?, contact);
+    Ok(format!("{hash:032x}"))
+}
+
+/// Hash, deduplicate, and pad a contact list: `{ hashes, count }`.
+#[wasm_bindgen(js_name = hashContactList)]
+pub fn hash_contact_list(contacts: Vec<String>, namespace: u8, scheme: u8) -> Result<JsValue, JsError> {
+    let hashed = hash::hash_contact_list(parse_scheme(scheme)?, parse_namespace(namespace)?, &contacts)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    to_js(&JsHashedContacts {
+        hashes: hashed.hashes.iter().map(|h| format!("{h:032x}")).collect(),
+        count: hashed.count,
+    })
+}
+
+/// Fill a hashed list's free slots, up to the session's `capacity`, with
+/// session-salted decoys: `{ hashes, count }`. `owner` is the submitting
+/// wallet's 32 bytes.
+#[wasm_bindgen(js_name = padWithDecoys)]
+pub fn pad_with_decoys(
+    hashes: Vec<String>,
+    count: u32,
+    session_id: &[u8],
+    owner: &[u8],
+    capacity: u8,
+) -> Result<JsValue, JsError> {
+    if hashes.len() > MAX_CONTACTS || count as usize > hashes.len() {
+        return Err(JsError::new("too many hashes"));
+    }
+    let mut slots = [0u128; MAX_CONTACTS];
+    for (slot, h) in slots.iter_mut().zip(&hashes) {
+        *slot = parse_hash(h)?;
+    }
+    let padded = hash::pad_with_decoys(
+        &HashedContacts {
+            hashes: slots,
+            count,
+            namespace: Namespace::default(),
+            scheme: HashScheme::default(),
+        },
+        &key32(session_id, "session id")?,
+        &key32(owner, "owner")?,
+        capacity,
+    );
+    to_js(&JsHashedContacts {
+        hashes: padded.hashes.iter().map(|h| format!("{h:032x}")).collect(),
+        count: padded.count,
+    })
+}
+
+/// Encrypt a hashed list for submission.
+/// `secret_key` is the caller's x25519 secret; `hashes` are hex strings.
+#[wasm_bindgen(js_name = encryptContacts)]
+pub fn encrypt_contacts(
+    secret_key: &[u8],
+    mxe_public_key: &[u8],
+    hashes: Vec<String>,
+    count: u32,
+    namespace: u8,
+    scheme: u8,
+) -> Result<JsValue, JsError> {
+    if hashes.len() > MAX_CONTACTS {
+        return Err(JsError::new("too many hashes"));
+    }
+    let mut padded = [0u128; MAX_CONTACTS];
+    for (slot, h) in padded.iter_mut().zip(&hashes) {
+        *slot = parse_hash(h)?;
+    }
+
+    let cipher = PartyCipher::from_secret(
+        StaticSecret::from(key32(secret_key, "secret key")?),
+        key32(mxe_public_key, "MXE public key")?,
+    );
+    let encrypted = cipher.encrypt_contacts(&HashedContacts {
+        hashes: padded,
+        count,
+        namespace: parse_namespace(namespace)?,
+        scheme: parse_scheme(scheme)?,
+    });
+
+    to_js(&JsEncryptedContacts {
+        encrypted_hashes: encrypted.encrypted_hashes.to_vec(),
+        encrypted_count: encrypted.encrypted_count,
+        encrypted_namespace: encrypted.encrypted_namespace,
+        pubkey: encrypted.pubkey,
+        nonce: encrypted.nonce.to_le_bytes(),
+    })
+}
+
+/// Decrypt a MatchResult from a `MatchComplete` / `AliceRevealed` event.
+/// Returns the matched slots; the caller reads the hashes back from the
+/// list it submitted.
+#[wasm_bindgen(js_name = decryptMatches)]
+pub fn decrypt_matches(
+    secret_key: &[u8],
+    mxe_public_key: &[u8],
+    encrypted_matches: Vec<u8>,
+    encrypted_count: &[u8],
+    nonce: &[u8],
+) -> Result<JsValue, JsError> {
+    if encrypted_matches.len() != MATCH_WORDS * 32 {
+        return Err(JsError::new("encrypted matches must be 2 x 32 bytes"));
+    }
+    let mut matches = [[0u8; 32]; MATCH_WORDS];
+    for (slot, chunk) in matches.iter_mut().zip(encrypted_matches.chunks_exact(32)) {
+        slot.copy_from_slice(chunk);
+    }
+    let nonce: [u8; 16] = nonce
+        .try_into()
+        .map_err(|_| JsError::new("nonce must be 16 bytes"))?;
+
+    let cipher = PartyCipher::from_secret(
+        StaticSecret::from(key32(secret_key, "secret key")?),
+        key32(mxe_public_key, "MXE public key")?,
+    );
+    let result = cipher.decrypt_matches(&matches, &key32(encrypted_count, "encrypted count")?, &nonce);
+
+    to_js(&JsMatches {
+        slots: result.slots.iter().map(|&slot| slot as u32).collect(),
+        match_count: result.match_count,
+    })
+}
@@ -0,0 +1,2056 @@
+// Localnet authorization matrix.
+//
+// Table-driven: one row per instruction a client sends, each built
+// with the SDK exactly as its authorized party would send it. Every
+// row expands into the attacks that apply to it, and each attack must
+// fail with the expected error:
+//
+//   missing signature  the party's account is passed without signing
+//   wrong signer       an intruder signs in place of the one party
+//                      allowed to (rows whose role is open to anyone,
+//                      such as Bob joining, have no wrong signer)
+//   wrong status       the session (or database) is in a status the
+//                      instruction does not accept
+//   comp-def mismatch  another circuit's computation definition
+//   foreign account    the target account replaced by one another
+//                      program owns
+//   closed account     the target replaced by a closed session, or by a
+//                      pool, database, template, staged list, credit
+//                      account, activity log, pair quota, consent
+//                      record or wallet block that was never created
+//   blocked wallet     a wallet the admin blocked creates a session or
+//                      joins one as Bob, with and without its
+//                      `BlockedWallet` address; a join without Alice's
+//
+// Cases are simulated with signature verification off, so any key can
+// stand in as a signer and nothing lands on chain; only the fixtures are
+// sent. Callbacks are not in the table: the transaction guard's unit
+// tests and the `instruction_data` fuzz target cover them. Nor are the
+// one-time initializers (comp defs, global stats, config), which the
+// setup below runs.
+//
+// Requires a running Arcium localnet with the program deployed (see
+// programs/private-contact-discovery/tests/e2e.rs):
+//
+//     arcium localnet            # in another terminal
+//     cargo test --features e2e --test authorization -- --nocapture
+//
+// Environment:
+//     ARCIUM_LOCALNET_URL     RPC endpoint (default http://127.0.0.1:8899)
+//     ARCIUM_CLUSTER_OFFSET   MXE cluster offset (default 0)
+//     ANCHOR_WALLET           payer keypair, the program's upgrade
+//                             authority (default ~/.config/solana/id.json)
+
+#![cfg(feature = "e2e")]
+
+use std::{path::PathBuf, thread, time::Duration};
+
+use anchor_lang::{error::ErrorCode as AnchorError, AnchorDeserialize, Event};
+use pcd_client::{
+    accounts::{
+        activity_log_pda, arcium_accounts, blocked_wallet_pda, blocklist_pda, card_registry_pda, circuits,
+        comp_def_account, consent_record_pda, credit_pda, database_pda, pair_quota_pda, pool_pda, session_pda,
+        session_template_pda, staged_list_pda, state_handle_pda,
+    },
+    crypto::{random_computation_offset, random_nonce, random_session_id},
+    fold_follow_list, hash_contact_list, hash_contact_set, hash_delta, instructions,
+    rpc::{self, RequeuePolicy},
+    EncryptedContacts, HashScheme, Namespace, PartyCipher, PROGRAM_ID,
+};
+use private_contact_discovery::{
+    AliceSubmitted, BatchItem, BatchStage, BlocklistOpened, BobSubmitted, CardRegistryOpened, DatabaseChunkLoaded,
+    invite_hash, ErrorCode, FollowChecked, MatchComplete, PoolOpened, SessionInitialized, SessionMode, StatePurged,
+    NO_INVITE, NO_MEMO,
+};
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    message::Message,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair},
+    signer::Signer,
+    transaction::{Transaction, TransactionError},
+};
+
+const CALLBACK_TIMEOUT: Duration = Duration::from_secs(120);
+/// Computations the fixtures bill to the test app, with headroom
+const FIXTURE_COMPUTATIONS: u64 = 48;
+/// Seconds until the purge fixtures' join deadline passes
+const PURGE_DEADLINE: i64 = 10;
+
+const CONTACTS: [&str; 3] = ["+1 555 0100", "+1 555 0142", "+1 555 0199"];
+/// Code of the invite fixture, claimed by the operator
+const INVITE_CODE: &str = "0123456789abcdef0123456789abcdef";
+
+// ============================================================
+// THE MATRIX
+// ============================================================
+
+#[derive(Clone, Copy, Debug)]
+enum Attack {
+    MissingSignature,
+    WrongSigner,
+    WrongStatus,
+    CompDefMismatch,
+    ForeignAccount,
+    ClosedAccount,
+    BlockedWallet,
+    WrongCode,
+}
+
+struct Case {
+    attack: Attack,
+    ixs: Vec<Instruction>,
+    /// Any of these custom error codes counts as the rejection
+    expected: Vec<u32>,
+}
+
+/// One instruction: how its authorized party sends it, and what about it
+/// is checked.
+struct Row {
+    name: &'static str,
+    /// The valid transaction (the permit row's Ed25519 instruction first)
+    ixs: Vec<Instruction>,
+    /// Accounts that must sign
+    signers: Vec<Pubkey>,
+    /// Signers no one else may stand in for
+    parties: Vec<Pubkey>,
+    circuit: Option<&'static str>,
+    /// Accounts acted on, each with a closed or never-created counterpart
+    targets: Vec<(Pubkey, Pubkey)>,
+    extra: Vec<Case>,
+}
+
+impl Row {
+    fn new(name: &'static str, ix: Instruction) -> Self {
+        Self::with_ixs(name, vec![ix])
+    }
+
+    fn with_ixs(name: &'static str, ixs: Vec<Instruction>) -> Self {
+        Row {
+            name,
+            ixs,
+            signers: Vec::new(),
+            parties: Vec::new(),
+            circuit: None,
+            targets: Vec::new(),
+            extra: Vec::new(),
+        }
+    }
+
+    /// `key` must sign, but anyone may take the role.
+    fn signer(mut self, key: Pubkey) -> Self {
+        self.signers.push(key);
+        self
+    }
+
+    /// `key` must sign, and only `key` may.
+    fn party(mut self, key: Pubkey) -> Self {
+        self.signers.push(key);
+        self.parties.push(key);
+        self
+    }
+
+    fn circuit(mut self, circuit: &'static str) -> Self {
+        self.circuit = Some(circuit);
+        self
+    }
+
+    fn target(mut self, live: Pubkey, closed: Pubkey) -> Self {
+        self.targets.push((live, closed));
+        self
+    }
+
+    /// The session and its state handle, against the closed fixture's.
+    fn session(self, live: &Fixture, closed: &Fixture) -> Self {
+        self.target(live.session(), closed.session())
+            .target(live.handle(), closed.handle())
+    }
+
+    /// `ix` is this instruction against a fixture whose status it refuses.
+    fn wrong_status(self, ix: Instruction, error: ErrorCode) -> Self {
+        self.case(Attack::WrongStatus, vec![ix], vec![program_error(error)])
+    }
+
+    /// `ixs` is this instruction sent by the blocked wallet, whose
+    /// `BlockedWallet` address is `block`; leaving the address out must
+    /// fail too.
+    fn blocked(self, ixs: Vec<Instruction>, block: Pubkey) -> Self {
+        let unchecked = remove(&ixs, block);
+        self.case(Attack::BlockedWallet, ixs, vec![program_error(ErrorCode::WalletBlocked)])
+            .case(Attack::BlockedWallet, unchecked, vec![program_error(ErrorCode::BlockCheckMissing)])
+    }
+
+    fn case(mut self, attack: Attack, ixs: Vec<Instruction>, expected: Vec<u32>) -> Self {
+        self.extra.push(Case { attack, ixs, expected });
+        self
+    }
+
+    fn cases(self, world: &World) -> Vec<Case> {
+        let mut cases = Vec::new();
+        for signer in &self.signers {
+            cases.push(Case {
+                attack: Attack::MissingSignature,
+                ixs: remap(&self.ixs, |meta| {
+                    if meta.pubkey == *signer {
+                        meta.is_signer = false;
+                    }
+                }),
+                expected: vec![AnchorError::AccountNotSigner as u32],
+            });
+        }
+        for party in &self.parties {
+            cases.push(Case {
+                attack: Attack::WrongSigner,
+                ixs: replace(&self.ixs, *party, world.intruder.pubkey()),
+                expected: vec![program_error(ErrorCode::Unauthorized)],
+            });
+        }
+        if let Some(circuit) = self.circuit {
+            let decoy = if circuit == circuits::INIT_SESSION {
+                circuits::SUBMIT_ALICE
+            } else {
+                circuits::INIT_SESSION
+            };
+            cases.push(Case {
+                attack: Attack::CompDefMismatch,
+                ixs: replace(&self.ixs, comp_def_account(circuit), comp_def_account(decoy)),
+                expected: vec![AnchorError::ConstraintAddress as u32],
+            });
+        }
+        if !self.targets.is_empty() {
+            let foreign = world.foreign_account();
+            cases.push(Case {
+                attack: Attack::ForeignAccount,
+                ixs: self
+                    .targets
+                    .iter()
+                    .fold(self.ixs.clone(), |ixs, (live, _)| replace(&ixs, *live, foreign)),
+                expected: vec![AnchorError::AccountOwnedByWrongProgram as u32],
+            });
+            cases.push(Case {
+                attack: Attack::ClosedAccount,
+                ixs: self
+                    .targets
+                    .iter()
+                    .fold(self.ixs.clone(), |ixs, (live, closed)| replace(&ixs, *live, *closed)),
+                // `Account` reports an empty system account as uninitialized,
+                // `AccountLoader` as owned by the wrong program
+                expected: vec![
+                    AnchorError::AccountNotInitialized as u32,
+                    AnchorError::AccountOwnedByWrongProgram as u32,
+                ],
+            });
+        }
+        cases.extend(self.extra);
+        cases
+    }
+}
+
+fn program_error(error: ErrorCode) -> u32 {
+    anchor_lang::error::ERROR_CODE_OFFSET + error as u32
+}
+
+/// Apply `f` to every account of this program's instructions in `ixs`.
+fn remap(ixs: &[Instruction], f: impl Fn(&mut AccountMeta)) -> Vec<Instruction> {
+    let mut ixs = ixs.to_vec();
+    for ix in ixs.iter_mut().filter(|ix| ix.program_id == PROGRAM_ID) {
+        ix.accounts.iter_mut().for_each(&f);
+    }
+    ixs
+}
+
+/// `ixs` without the account `key`.
+fn remove(ixs: &[Instruction], key: Pubkey) -> Vec<Instruction> {
+    let mut ixs = ixs.to_vec();
+    for ix in ixs.iter_mut().filter(|ix| ix.program_id == PROGRAM_ID) {
+        ix.accounts.retain(|meta| meta.pubkey != key);
+    }
+    ixs
+}
+
+fn replace(ixs: &[Instruction], from: Pubkey, to: Pubkey) -> Vec<Instruction> {
+    remap(ixs, |meta| {
+        if meta.pubkey == from {
+            meta.pubkey = to;
+        }
+    })
+}
+
+#[test]
+fn every_instruction_rejects_unauthorized_calls() {
+    let world = World::connect();
+    let fixtures = Fixtures::build(&world);
+
+    let mut failures = Vec::new();
+    let mut count = 0;
+    for row in rows(&world, &fixtures) {
+        let name = row.name;
+        for case in row.cases(&world) {
+            count += 1;
+            let err = world.simulate(&case.ixs);
+            let code = match &err {
+                Some(TransactionError::InstructionError(_, InstructionError::Custom(code))) => Some(*code),
+                _ => None,
+            };
+            if !code.is_some_and(|code| case.expected.contains(&code)) {
+                failures.push(format!(
+                    "{name} / {:?}: expected one of {:?}, got {err:?}",
+                    case.attack, case.expected
+                ));
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} of {count} cases were not rejected as expected:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
+
+fn rows(w: &World, f: &Fixtures) -> Vec<Row> {
+    let payer = w.payer.pubkey();
+    let op = w.operator.pubkey();
+    let intruder = w.intruder.pubkey();
+    let alice = f.a.alice.pubkey();
+    let blocked = w.blocked;
+    let block = blocked_wallet_pda(&blocked);
+    let cluster = w.cluster;
+    let app = &w.app_id;
+    let contacts = &w.contacts(Namespace::Phone);
+    let nonce = u128::from_le_bytes(random_nonce());
+    let key = PartyCipher::new(w.mxe_key).public_key();
+    let off = random_computation_offset;
+    let closed = &f.closed;
+    let unopened = [0xee; 32];
+    let never = random_session_id();
+
+    let permit = |fixture: &Fixture, alice: &Keypair| {
+        let expiry = w.now() + 600;
+        let message = instructions::alice_permit_message(&fixture.id, contacts, expiry);
+        let signature: [u8; 64] = alice.sign_message(&message).as_ref().try_into().unwrap();
+        instructions::submit_contacts_with_permit(
+            payer,
+            alice.pubkey(),
+            &signature,
+            cluster,
+            off(),
+            &fixture.id,
+            app,
+            contacts,
+            expiry,
+        )
+        .to_vec()
+    };
+    // Alice's reveal, queued as the one item of a batch
+    let batched_reveal = |fixture: &Fixture| {
+        let offset = off();
+        let item = BatchItem {
+            computation_offset: offset,
+            stage: BatchStage::RevealAlice {
+                pubkey: key,
+                nonce,
+                page: 0,
+            },
+        };
+        let alice = fixture.alice.pubkey();
+        let reveal = instructions::reveal_alice_matches(payer, alice, cluster, offset, &fixture.id, app, key, nonce, 0);
+        instructions::queue_batch(payer, &[(item, reveal)])
+    };
+    let delta = w
+        .cipher()
+        .encrypt_delta(&hash_delta(HashScheme::Sha256, Namespace::Phone, &["+1 555 0123"], &[] as &[&str]).unwrap());
+    let card = w.cipher().encrypt_card(1, Namespace::Phone, &[7; 32]);
+    let query = w
+        .cipher()
+        .encrypt_membership_query(HashScheme::Sha256, Namespace::Phone, 1);
+    let batch = w
+        .cipher()
+        .encrypt_membership_batch(HashScheme::Sha256, Namespace::Phone, &[1, 2])
+        .unwrap();
+
+    // Sessions billed to the test app, whose credit authority `op` must
+    // co-sign
+    let new_session = |alice: Pubkey, intended_bob: Pubkey| {
+        instructions::create_session(
+            payer,
+            alice,
+            cluster,
+            off(),
+            random_session_id(),
+            *app,
+            key,
+            nonce,
+            SessionMode::OneShot as u8,
+            Namespace::Phone as u8,
+            HashScheme::Sha256 as u8,
+            false,
+            false,
+            0,
+            0,
+            32,
+            NO_MEMO,
+            intended_bob,
+            NO_INVITE,
+        )
+    };
+    let new_stateless_session = |alice: Pubkey| {
+        instructions::create_stateless_session(
+            payer,
+            alice,
+            random_session_id(),
+            *app,
+            SessionMode::OneShot as u8,
+            Namespace::Phone as u8,
+            HashScheme::Sha256 as u8,
+            false,
+            false,
+            0,
+            0,
+            32,
+            NO_MEMO,
+        )
+    };
+    let unauthorized = || vec![program_error(ErrorCode::Unauthorized)];
+
+    let config = rpc::fetch_config(&w.rpc).expect("config");
+    let admin = config.admin;
+    let recent_slot = w.rpc.get_slot().expect("slot");
+
+    vec![
+        // ---- configuration and credit ----
+        Row::new(
+            "set_computation_price",
+            instructions::set_computation_price(admin, config.computation_price),
+        )
+        .party(admin),
+        Row::new(
+            "set_fee_pool_share",
+            instructions::set_fee_pool_share(admin, config.fee_pool_share_bps),
+        )
+        .party(admin),
+        Row::new(
+            "set_refund_share",
+            instructions::set_refund_share(admin, config.refund_bps),
+        )
+        .party(admin),
+        Row::new(
+            "set_min_client_version",
+            instructions::set_min_client_version(admin, config.min_client_version),
+        )
+        .party(admin),
+        Row::new(
+            "set_max_pair_sessions",
+            instructions::set_max_pair_sessions(admin, config.max_pair_sessions),
+        )
+        .party(admin),
+        Row::new(
+            "set_query_budget",
+            instructions::set_query_budget(admin, config.query_budget),
+        )
+        .party(admin),
+        Row::new(
+            "set_gatekeeper",
+            instructions::set_gatekeeper(admin, config.gatekeeper, config.gatekeeper_network),
+        )
+        .party(admin),
+        Row::new(
+            "block_wallet",
+            instructions::block_wallet(admin, Pubkey::new_from_array(never)),
+        )
+        .party(admin),
+        Row::new("unblock_wallet", instructions::unblock_wallet(admin, w.blocked))
+            .party(admin)
+            .target(blocked_wallet_pda(&w.blocked), blocked_wallet_pda(&Pubkey::new_from_array(never))),
+        Row::new(
+            "open_credit_account",
+            instructions::open_credit_account(payer, op, never),
+        )
+        .signer(op),
+        Row::new("deposit_credit", instructions::deposit_credit(op, app, 1))
+            .signer(op)
+            .target(credit_pda(app), credit_pda(&unopened)),
+        Row::new("withdraw_credit", instructions::withdraw_credit(op, app, 1))
+            .party(op)
+            .target(credit_pda(app), credit_pda(&unopened)),
+        Row::new(
+            "create_app_lut",
+            instructions::create_app_lut(payer, op, cluster, *app, recent_slot),
+        )
+        .party(op)
+        .target(credit_pda(app), credit_pda(&unopened)),
+        Row::new("claim_refund", instructions::claim_refund(&f.m.id, app)).session(&f.m, closed),
+        // ---- templates ----
+        Row::new(
+            "create_session_template",
+            instructions::create_session_template(
+                payer,
+                op,
+                *app,
+                never,
+                SessionMode::OneShot as u8,
+                Namespace::Phone as u8,
+                HashScheme::Sha256 as u8,
+                false,
+                false,
+                0,
+                0,
+                Pubkey::default(),
+            ),
+        )
+        .party(op)
+        .target(credit_pda(app), credit_pda(&unopened)),
+        Row::new(
+            "close_session_template",
+            instructions::close_session_template(op, app, &f.template_id),
+        )
+        .party(op)
+        .target(
+            session_template_pda(app, &f.template_id),
+            session_template_pda(app, &never),
+        ),
+        Row::new(
+            "create_session_from_template",
+            instructions::create_session_from_template(
+                payer,
+                f.a.alice.pubkey(),
+                cluster,
+                off(),
+                random_session_id(),
+                app,
+                &f.template_id,
+                key,
+                nonce,
+                NO_MEMO,
+            ),
+        )
+        .signer(f.a.alice.pubkey())
+        .circuit(circuits::INIT_SESSION)
+        .target(
+            session_template_pda(app, &f.template_id),
+            session_template_pda(app, &never),
+        )
+        .blocked(
+            vec![instructions::create_session_from_template(
+                payer,
+                blocked,
+                cluster,
+                off(),
+                random_session_id(),
+                app,
+                &f.template_id,
+                key,
+                nonce,
+                NO_MEMO,
+            )],
+            block,
+        ),
+        // ---- session lifecycle ----
+        Row::new(
+            "create_session",
+            instructions::with_app_authority(new_session(alice, Pubkey::default()), op),
+        )
+        .signer(f.a.alice.pubkey())
+        .circuit(circuits::INIT_SESSION)
+        .case(Attack::MissingSignature, vec![new_session(alice, Pubkey::default())], unauthorized())
+        .case(
+            Attack::WrongSigner,
+            vec![instructions::with_app_authority(new_session(alice, Pubkey::default()), intruder)],
+            unauthorized(),
+        )
+        // `op` consents to sessions of the app, which Alice cannot claim
+        // without the app's signature
+        .case(Attack::MissingSignature, vec![new_session(alice, op)], unauthorized())
+        .blocked(
+            vec![instructions::with_app_authority(new_session(blocked, Pubkey::default()), op)],
+            block,
+        ),
+        Row::new(
+            "create_stateless_session",
+            instructions::with_app_authority(new_stateless_session(alice), op),
+        )
+        .signer(f.a.alice.pubkey())
+        .case(Attack::MissingSignature, vec![new_stateless_session(alice)], unauthorized())
+        .case(
+            Attack::WrongSigner,
+            vec![instructions::with_app_authority(new_stateless_session(alice), intruder)],
+            unauthorized(),
+        )
+        .blocked(
+            vec![instructions::with_app_authority(new_stateless_session(blocked), op)],
+            block,
+        ),
+        Row::new(
+            "commit_contacts",
+            instructions::commit_contacts(f.a.alice.pubkey(), &f.a.id, contacts),
+        )
+        .party(f.a.alice.pubkey())
+        .session(&f.a, closed)
+        .wrong_status(
+            instructions::commit_contacts(f.m.alice.pubkey(), &f.m.id, contacts),
+            ErrorCode::InvalidSessionState,
+        ),
+        // As with the match, the tiers share a context and check the
+        // session's status and Alice before its tier
+        Row::new(
+            "submit_contacts_alice",
+            instructions::submit_contacts_alice(payer, f.a.alice.pubkey(), cluster, off(), &f.a.id, app, contacts, 32),
+        )
+        .party(f.a.alice.pubkey())
+        .circuit(circuits::SUBMIT_ALICE)
+        .session(&f.a, closed)
+        .wrong_status(
+            instructions::submit_contacts_alice(payer, f.m.alice.pubkey(), cluster, off(), &f.m.id, app, contacts, 32),
+            ErrorCode::InvalidSessionState,
+        ),
+        Row::new(
+            "submit_contacts_alice_16",
+            instructions::submit_contacts_alice(payer, f.a.alice.pubkey(), cluster, off(), &f.a.id, app, contacts, 16),
+        )
+        .party(f.a.alice.pubkey())
+        .circuit(circuits::SUBMIT_ALICE_16)
+        .session(&f.a, closed)
+        .wrong_status(
+            instructions::submit_contacts_alice(payer, f.m.alice.pubkey(), cluster, off(), &f.m.id, app, contacts, 16),
+            ErrorCode::InvalidSessionState,
+        ),
+        Row::new(
+            "submit_contacts_alice_8",
+            instructions::submit_contacts_alice(payer, f.a.alice.pubkey(), cluster, off(), &f.a.id, app, contacts, 8),
+        )
+        .party(f.a.alice.pubkey())
+        .circuit(circuits::SUBMIT_ALICE_8)
+        .session(&f.a, closed)
+        .wrong_status(
+            instructions::submit_contacts_alice(payer, f.m.alice.pubkey(), cluster, off(), &f.m.id, app, contacts, 8),
+            ErrorCode::InvalidSessionState,
+        ),
+        // A session whose state exists takes the ordinary submission
+        Row::new(
+            "init_and_submit_alice",
+            instructions::init_and_submit_alice(
+                payer,
+                f.stateless.alice.pubkey(),
+                cluster,
+                off(),
+                &f.stateless.id,
+                app,
+                contacts,
+            ),
+        )
+        .party(f.stateless.alice.pubkey())
+        .circuit(circuits::INIT_AND_SUBMIT_ALICE)
+        .session(&f.stateless, closed)
+        .wrong_status(
+            instructions::init_and_submit_alice(payer, f.a.alice.pubkey(), cluster, off(), &f.a.id, app, contacts),
+            ErrorCode::StateAlreadyInitialized,
+        ),
+        // Alice authorizes by permit rather than by signing: a valid
+        // permit from anyone else is the wrong signer
+        Row::with_ixs("submit_contacts_with_permit", permit(&f.a, &f.a.alice))
+            .circuit(circuits::SUBMIT_ALICE)
+            .session(&f.a, closed)
+            .case(
+                Attack::WrongSigner,
+                permit(&f.a, &w.intruder),
+                vec![program_error(ErrorCode::Unauthorized)],
+            )
+            .case(
+                Attack::WrongStatus,
+                permit(&f.m, &f.m.alice),
+                vec![program_error(ErrorCode::InvalidSessionState)],
+            ),
+        // The capacity tiers share a context; the session's status is
+        // checked before its tier, so the 32-slot fixtures cover them
+        Row::new(
+            "submit_and_match",
+            instructions::submit_and_match(
+                payer,
+                f.a.bob.pubkey(),
+                f.a.alice.pubkey(),
+                cluster,
+                off(),
+                &f.a.id,
+                app,
+                contacts,
+                32,
+                false,
+            ),
+        )
+        .signer(f.a.bob.pubkey())
+        .circuit(circuits::SUBMIT_AND_MATCH)
+        .session(&f.a, closed)
+        .wrong_status(
+            instructions::submit_and_match(
+                payer,
+                f.m.bob.pubkey(),
+                f.m.alice.pubkey(),
+                cluster,
+                off(),
+                &f.m.id,
+                app,
+                contacts,
+                32,
+                false,
+            ),
+            ErrorCode::InvalidSessionState,
+        )
+        .blocked(
+            vec![instructions::submit_and_match(
+                payer,
+                blocked,
+                f.b[0].alice.pubkey(),
+                cluster,
+                off(),
+                &f.b[0].id,
+                app,
+                contacts,
+                32,
+                false,
+            )],
+            block,
+        )
+        // Alice's block is checked on the join as well
+        .case(
+            Attack::BlockedWallet,
+            remove(
+                &[instructions::submit_and_match(
+                    payer,
+                    f.b[0].bob.pubkey(),
+                    f.b[0].alice.pubkey(),
+                    cluster,
+                    off(),
+                    &f.b[0].id,
+                    app,
+                    contacts,
+                    32,
+                    false,
+                )],
+                blocked_wallet_pda(&f.b[0].alice.pubkey()),
+            ),
+            vec![program_error(ErrorCode::BlockCheckMissing)],
+        ),
+        Row::new(
+            "submit_and_match_16",
+            instructions::submit_and_match(
+                payer,
+                f.a.bob.pubkey(),
+                f.a.alice.pubkey(),
+                cluster,
+                off(),
+                &f.a.id,
+                app,
+                contacts,
+                16,
+                false,
+            ),
+        )
+        .signer(f.a.bob.pubkey())
+        .circuit(circuits::SUBMIT_AND_MATCH_16)
+        .session(&f.a, closed)
+        .wrong_status(
+            instructions::submit_and_match(
+                payer,
+                f.m.bob.pubkey(),
+                f.m.alice.pubkey(),
+                cluster,
+                off(),
+                &f.m.id,
+                app,
+                contacts,
+                16,
+                false,
+            ),
+            ErrorCode::InvalidSessionState,
+        )
+        .blocked(
+            vec![instructions::submit_and_match(
+                payer,
+                blocked,
+                f.b[1].alice.pubkey(),
+                cluster,
+                off(),
+                &f.b[1].id,
+                app,
+                contacts,
+                16,
+                false,
+            )],
+            block,
+        ),
+        Row::new(
+            "submit_and_match_8",
+            instructions::submit_and_match(
+                payer,
+                f.a.bob.pubkey(),
+                f.a.alice.pubkey(),
+                cluster,
+                off(),
+                &f.a.id,
+                app,
+                contacts,
+                8,
+                false,
+            ),
+        )
+        .signer(f.a.bob.pubkey())
+        .circuit(circuits::SUBMIT_AND_MATCH_8)
+        .session(&f.a, closed)
+        .wrong_status(
+            instructions::submit_and_match(
+                payer,
+                f.m.bob.pubkey(),
+                f.m.alice.pubkey(),
+                cluster,
+                off(),
+                &f.m.id,
+                app,
+                contacts,
+                8,
+                false,
+            ),
+            ErrorCode::InvalidSessionState,
+        )
+        .blocked(
+            vec![instructions::submit_and_match(
+                payer,
+                blocked,
+                f.b[2].alice.pubkey(),
+                cluster,
+                off(),
+                &f.b[2].id,
+                app,
+                contacts,
+                8,
+                false,
+            )],
+            block,
+        ),
+        Row::new(
+            "commit_set",
+            instructions::commit_set(f.a.bob.pubkey(), f.a.alice.pubkey(), &f.a.id, &f.set_chunks),
+        )
+        .signer(f.a.bob.pubkey())
+        .session(&f.a, closed)
+        .wrong_status(
+            instructions::commit_set(f.m.bob.pubkey(), f.m.alice.pubkey(), &f.m.id, &f.set_chunks),
+            ErrorCode::InvalidSessionState,
+        )
+        .blocked(vec![instructions::commit_set(blocked, f.set_b.alice.pubkey(), &f.set_b.id, &f.set_chunks)], block),
+        Row::new(
+            "submit_set_chunk",
+            instructions::submit_set_chunk(
+                payer,
+                f.set.bob.pubkey(),
+                cluster,
+                off(),
+                &f.set.id,
+                app,
+                &f.set_chunks,
+                0,
+            ),
+        )
+        .party(f.set.bob.pubkey())
+        .circuit(circuits::MATCH_SET_CHUNK)
+        .session(&f.set, closed)
+        .wrong_status(
+            instructions::submit_set_chunk(payer, f.a.bob.pubkey(), cluster, off(), &f.a.id, app, &f.set_chunks, 0),
+            ErrorCode::InvalidSessionState,
+        ),
+        Row::new(
+            "reveal_alice_matches",
+            instructions::reveal_alice_matches(payer, f.m.alice.pubkey(), cluster, off(), &f.m.id, app, key, nonce, 0),
+        )
+        .party(f.m.alice.pubkey())
+        .circuit(circuits::REVEAL_ALICE)
+        .session(&f.m, closed)
+        .wrong_status(
+            instructions::reveal_alice_matches(payer, f.a.alice.pubkey(), cluster, off(), &f.a.id, app, key, nonce, 0),
+            ErrorCode::InvalidSessionState,
+        ),
+        Row::new(
+            "reveal_bob_matches",
+            instructions::reveal_bob_matches(payer, f.m.bob.pubkey(), cluster, off(), &f.m.id, app, key, nonce, 0),
+        )
+        .party(f.m.bob.pubkey())
+        .circuit(circuits::REVEAL_BOB)
+        .session(&f.m, closed)
+        .wrong_status(
+            instructions::reveal_bob_matches(payer, f.a.bob.pubkey(), cluster, off(), &f.a.id, app, key, nonce, 0),
+            ErrorCode::InvalidSessionState,
+        ),
+        Row::new(
+            "reveal_both",
+            instructions::reveal_both(
+                payer,
+                f.m.alice.pubkey(),
+                f.m.bob.pubkey(),
+                cluster,
+                off(),
+                &f.m.id,
+                app,
+                key,
+                nonce,
+                key,
+                nonce,
+                0,
+            ),
+        )
+        .party(f.m.alice.pubkey())
+        .party(f.m.bob.pubkey())
+        .circuit(circuits::REVEAL_BOTH)
+        .session(&f.m, closed)
+        .wrong_status(
+            instructions::reveal_both(
+                payer,
+                f.a.alice.pubkey(),
+                f.a.bob.pubkey(),
+                cluster,
+                off(),
+                &f.a.id,
+                app,
+                key,
+                nonce,
+                key,
+                nonce,
+                0,
+            ),
+            ErrorCode::InvalidSessionState,
+        ),
+        Row::new(
+            "push_delta",
+            instructions::push_delta(payer, f.sub.alice.pubkey(), cluster, off(), &f.sub.id, app, &delta),
+        )
+        .party(f.sub.alice.pubkey())
+        .circuit(circuits::PUSH_DELTA)
+        .session(&f.sub, closed)
+        .wrong_status(
+            instructions::push_delta(
+                payer,
+                f.purged.alice.pubkey(),
+                cluster,
+                off(),
+                &f.purged.id,
+                app,
+                &delta,
+            ),
+            ErrorCode::InvalidSessionState,
+        ),
+        Row::new(
+            "submit_and_score",
+            instructions::submit_and_score(
+                payer,
+                f.a.bob.pubkey(),
+                f.a.alice.pubkey(),
+                cluster,
+                off(),
+                &f.a.id,
+                app,
+                contacts,
+            ),
+        )
+        .signer(f.a.bob.pubkey())
+        .circuit(circuits::SUBMIT_AND_SCORE)
+        .session(&f.a, closed)
+        .wrong_status(
+            instructions::submit_and_score(
+                payer,
+                f.m.bob.pubkey(),
+                f.m.alice.pubkey(),
+                cluster,
+                off(),
+                &f.m.id,
+                app,
+                contacts,
+            ),
+            ErrorCode::InvalidSessionState,
+        )
+        .blocked(
+            vec![instructions::submit_and_score(
+                payer,
+                blocked,
+                f.score_b.alice.pubkey(),
+                cluster,
+                off(),
+                &f.score_b.id,
+                app,
+                contacts,
+            )],
+            block,
+        ),
+        // The party is checked before the mode, so the one-shot fixture
+        // stands in for a matched score session
+        Row::new(
+            "reveal_alice_score",
+            instructions::reveal_alice_score(payer, f.m.alice.pubkey(), cluster, off(), &f.m.id, app, key, nonce),
+        )
+        .party(f.m.alice.pubkey())
+        .circuit(circuits::REVEAL_ALICE_SCORE)
+        .session(&f.m, closed)
+        .wrong_status(
+            instructions::reveal_alice_score(payer, f.a.alice.pubkey(), cluster, off(), &f.a.id, app, key, nonce),
+            ErrorCode::InvalidSessionState,
+        ),
+        Row::new(
+            "submit_contacts_bob",
+            instructions::submit_contacts_bob(
+                payer,
+                f.a.bob.pubkey(),
+                f.a.alice.pubkey(),
+                cluster,
+                off(),
+                &f.a.id,
+                app,
+                contacts,
+            ),
+        )
+        .signer(f.a.bob.pubkey())
+        .circuit(circuits::SUBMIT_BOB)
+        .session(&f.a, closed)
+        .wrong_status(
+            instructions::submit_contacts_bob(
+                payer,
+                f.m.bob.pubkey(),
+                f.m.alice.pubkey(),
+                cluster,
+                off(),
+                &f.m.id,
+                app,
+                contacts,
+            ),
+            ErrorCode::InvalidSessionState,
+        )
+        .blocked(
+            vec![instructions::submit_contacts_bob(
+                payer,
+                blocked,
+                f.conn_b.alice.pubkey(),
+                cluster,
+                off(),
+                &f.conn_b.id,
+                app,
+                contacts,
+            )],
+            block,
+        ),
+        // Anyone but Alice and Bob may connect: Alice is the wrong signer
+        Row::new(
+            "submit_connector",
+            instructions::submit_connector(payer, op, cluster, off(), &f.conn.id, app, contacts),
+        )
+        .signer(op)
+        .circuit(circuits::SUBMIT_CONNECTOR)
+        .session(&f.conn, closed)
+        .case(
+            Attack::WrongSigner,
+            vec![instructions::submit_connector(
+                payer,
+                f.conn.alice.pubkey(),
+                cluster,
+                off(),
+                &f.conn.id,
+                app,
+                contacts,
+            )],
+            vec![program_error(ErrorCode::Unauthorized)],
+        )
+        .wrong_status(
+            instructions::submit_connector(payer, op, cluster, off(), &f.a.id, app, contacts),
+            ErrorCode::InvalidSessionState,
+        ),
+        Row::new(
+            "reveal_second_degree",
+            instructions::reveal_second_degree(
+                payer,
+                f.follow_m.alice.pubkey(),
+                cluster,
+                off(),
+                &f.follow_m.id,
+                app,
+                key,
+                nonce,
+            ),
+        )
+        .party(f.follow_m.alice.pubkey())
+        .circuit(circuits::REVEAL_SECOND_DEGREE)
+        .session(&f.follow_m, closed)
+        .wrong_status(
+            instructions::reveal_second_degree(payer, f.a.alice.pubkey(), cluster, off(), &f.a.id, app, key, nonce),
+            ErrorCode::InvalidSessionState,
+        ),
+        // Anyone but Alice may follow-check: Alice is the wrong signer
+        Row::new(
+            "submit_and_check_follow",
+            instructions::submit_and_check_follow(
+                payer,
+                f.follow_b.bob.pubkey(),
+                f.follow_b.alice.pubkey(),
+                cluster,
+                off(),
+                &f.follow_b.id,
+                app,
+                &f.follow_list,
+            ),
+        )
+        .signer(f.follow_b.bob.pubkey())
+        .circuit(circuits::CHECK_FOLLOW)
+        .session(&f.follow_b, closed)
+        .case(
+            Attack::WrongSigner,
+            vec![instructions::submit_and_check_follow(
+                payer,
+                f.follow_b.alice.pubkey(),
+                f.follow_b.alice.pubkey(),
+                cluster,
+                off(),
+                &f.follow_b.id,
+                app,
+                &f.follow_list,
+            )],
+            vec![program_error(ErrorCode::Unauthorized)],
+        )
+        .wrong_status(
+            instructions::submit_and_check_follow(
+                payer,
+                f.follow_m.bob.pubkey(),
+                f.follow_m.alice.pubkey(),
+                cluster,
+                off(),
+                &f.follow_m.id,
+                app,
+                &f.follow_list,
+            ),
+            ErrorCode::InvalidSessionState,
+        )
+        .blocked(
+            vec![instructions::submit_and_check_follow(
+                payer,
+                blocked,
+                f.follow_b.alice.pubkey(),
+                cluster,
+                off(),
+                &f.follow_b.id,
+                app,
+                &f.follow_list,
+            )],
+            block,
+        ),
+        Row::new(
+            "purge_session_state",
+            instructions::purge_session_state(payer, f.m.alice.pubkey(), cluster, off(), &f.m.id),
+        )
+        .party(f.m.alice.pubkey())
+        .circuit(circuits::PURGE_STATE)
+        .session(&f.m, closed)
+        .wrong_status(
+            instructions::purge_session_state(payer, f.conn.alice.pubkey(), cluster, off(), &f.conn.id),
+            ErrorCode::InvalidSessionState,
+        ),
+        Row::new(
+            "migrate_session_state",
+            instructions::migrate_session_state(payer, f.m.alice.pubkey(), cluster, off(), &f.m.id),
+        )
+        .party(f.m.alice.pubkey())
+        .circuit(circuits::MIGRATE_STATE)
+        .session(&f.m, closed)
+        .wrong_status(
+            instructions::migrate_session_state(payer, f.purged.alice.pubkey(), cluster, off(), &f.purged.id),
+            ErrorCode::InvalidSessionState,
+        ),
+        Row::new(
+            "close_session",
+            instructions::close_session(f.purged.alice.pubkey(), payer, &f.purged.id),
+        )
+        .party(f.purged.alice.pubkey())
+        .session(&f.purged, closed)
+        .wrong_status(
+            instructions::close_session(f.m.alice.pubkey(), payer, &f.m.id),
+            ErrorCode::InvalidSessionState,
+        ),
+        Row::new(
+            "sweep_sessions",
+            instructions::sweep_sessions(payer, &[(f.purged.id, payer)]),
+        )
+        .signer(payer)
+        .session(&f.purged, closed)
+        .wrong_status(
+            instructions::sweep_sessions(payer, &[(f.m.id, payer)]),
+            ErrorCode::NotSweepable,
+        ),
+        Row::new(
+            "realloc_session",
+            instructions::realloc_session(payer, f.m.alice.pubkey(), &f.m.id),
+        )
+        .party(f.m.alice.pubkey())
+        .session(&f.m, closed)
+        .wrong_status(
+            instructions::realloc_session(payer, f.purged.alice.pubkey(), &f.purged.id),
+            ErrorCode::InvalidSessionState,
+        ),
+        Row::new(
+            "register_notification",
+            instructions::register_notification(f.m.alice.pubkey(), &f.m.id, [1; 32]),
+        )
+        .party(f.m.alice.pubkey())
+        .session(&f.m, closed)
+        .wrong_status(
+            instructions::register_notification(f.purged.alice.pubkey(), &f.purged.id, [1; 32]),
+            ErrorCode::InvalidSessionState,
+        ),
+        Row::new(
+            "register_app_hook",
+            instructions::register_app_hook(payer, f.a.alice.pubkey(), &f.a.id, Pubkey::new_unique(), vec![]),
+        )
+        .party(f.a.alice.pubkey())
+        .session(&f.a, closed)
+        .wrong_status(
+            instructions::register_app_hook(payer, f.m.alice.pubkey(), &f.m.id, Pubkey::new_unique(), vec![]),
+            ErrorCode::InvalidSessionState,
+        ),
+        Row::new(
+            "consent_connection",
+            instructions::consent_connection(f.m.alice.pubkey(), &f.m.id),
+        )
+        .party(f.m.alice.pubkey())
+        .session(&f.m, closed)
+        .wrong_status(
+            instructions::consent_connection(f.a.alice.pubkey(), &f.a.id),
+            ErrorCode::InvalidSessionState,
+        ),
+        Row::new(
+            "attest_connection",
+            instructions::attest_connection(payer, &f.m.id, f.m.alice.pubkey(), f.m.bob.pubkey()),
+        )
+        .session(&f.m, closed)
+        .wrong_status(
+            instructions::attest_connection(payer, &f.a.id, f.a.alice.pubkey(), f.a.bob.pubkey()),
+            ErrorCode::InvalidSessionState,
+        ),
+        Row::new(
+            "open_activity_log",
+            instructions::open_activity_log(payer, Pubkey::new_from_array(never)),
+        )
+        .signer(Pubkey::new_from_array(never)),
+        Row::new(
+            "open_pair_quota",
+            instructions::open_pair_quota(payer, op, Pubkey::new_from_array(never)),
+        ),
+        Row::new("renew_query_budget", instructions::renew_query_budget(op, payer))
+            .party(op)
+            .target(pair_quota_pda(&op, &payer), pair_quota_pda(&op, &Pubkey::new_from_array(never))),
+        Row::new("close_activity_log", instructions::close_activity_log(op))
+            .signer(op)
+            .target(activity_log_pda(&op), activity_log_pda(&Pubkey::new_from_array(never))),
+        Row::new("ping_session", instructions::ping_session(&f.m.id)).session(&f.m, closed),
+        Row::new("claim_invite", instructions::claim_invite(intruder, &f.invite.id, INVITE_CODE))
+            .signer(intruder)
+            .session(&f.invite, closed)
+            .wrong_status(
+                instructions::claim_invite(intruder, &f.a.id, INVITE_CODE),
+                ErrorCode::InvalidSessionState,
+            ),
+        Row::new("redeem_invite", instructions::redeem_invite(op, &f.invite.id, INVITE_CODE))
+            .signer(op)
+            .session(&f.invite, closed)
+            .case(
+                Attack::WrongCode,
+                vec![instructions::redeem_invite(op, &f.invite.id, "fedcba9876543210fedcba9876543210")],
+                vec![program_error(ErrorCode::InvalidInviteCode)],
+            )
+            .case(
+                Attack::WrongCode,
+                vec![instructions::redeem_invite(op, &f.invite.id, "0123456789abcdef")],
+                vec![program_error(ErrorCode::InviteCodeTooShort)],
+            )
+            // The code copied from the operator's redemption, without a
+            // claim of one's own
+            .case(
+                Attack::WrongSigner,
+                vec![instructions::redeem_invite(intruder, &f.invite.id, INVITE_CODE)],
+                vec![AnchorError::AccountNotInitialized as u32],
+            ),
+        Row::new("set_consent_record", instructions::set_consent_record(op, vec![payer], vec![])).signer(op),
+        Row::new("close_consent_record", instructions::close_consent_record(op))
+            .signer(op)
+            .target(consent_record_pda(&op), consent_record_pda(&Pubkey::new_from_array(never))),
+        Row::new("view_session", instructions::view_session(&f.m.id)).session(&f.m, closed),
+        Row::new("sync_deployment", instructions::sync_deployment(payer)).signer(payer),
+        Row::new("queue_batch", batched_reveal(&f.m))
+            .party(f.m.alice.pubkey())
+            .circuit(circuits::REVEAL_ALICE)
+            .session(&f.m, closed)
+            .wrong_status(batched_reveal(&f.a), ErrorCode::InvalidSessionState),
+        // ---- pools, blocklists, card registries ----
+        Row::new(
+            "create_pool",
+            instructions::create_pool(payer, op, cluster, off(), never, Namespace::Phone as u8, contacts),
+        )
+        .signer(op)
+        .circuit(circuits::CREATE_POOL),
+        Row::new(
+            "match_against_pool",
+            instructions::match_against_pool(payer, op, cluster, off(), &f.pool_id, contacts),
+        )
+        .signer(op)
+        .circuit(circuits::MATCH_POOL)
+        .target(pool_pda(&f.pool_id), pool_pda(&never)),
+        Row::new(
+            "create_blocklist",
+            instructions::create_blocklist(payer, op, cluster, off(), never, Namespace::Phone as u8, contacts),
+        )
+        .signer(op)
+        .circuit(circuits::CREATE_BLOCKLIST),
+        Row::new(
+            "check_against_blocklist",
+            instructions::check_against_blocklist(payer, op, cluster, off(), &f.blocklist_id, contacts),
+        )
+        .signer(op)
+        .circuit(circuits::CHECK_BLOCKLIST)
+        .target(blocklist_pda(&f.blocklist_id), blocklist_pda(&never)),
+        Row::new(
+            "create_card_registry",
+            instructions::create_card_registry(
+                payer,
+                op,
+                cluster,
+                off(),
+                never,
+                Namespace::Phone as u8,
+                HashScheme::Sha256 as u8,
+                key,
+                nonce,
+            ),
+        )
+        .signer(op)
+        .circuit(circuits::CREATE_CARD_REGISTRY),
+        Row::new(
+            "publish_contact_card",
+            instructions::publish_contact_card(payer, op, cluster, off(), &f.registry_id, &card),
+        )
+        .signer(op)
+        .circuit(circuits::PUBLISH_CARD)
+        .target(card_registry_pda(&f.registry_id), card_registry_pda(&never)),
+        Row::new(
+            "match_contact_cards",
+            instructions::match_contact_cards(payer, op, cluster, off(), &f.registry_id, contacts),
+        )
+        .signer(op)
+        .circuit(circuits::MATCH_CARDS)
+        .target(card_registry_pda(&f.registry_id), card_registry_pda(&never)),
+        // ---- instant matches: the operator and `a`'s Alice have staged
+        //      lists for each other ----
+        Row::new(
+            "stage_list",
+            instructions::stage_list(payer, op, Pubkey::new_from_array(never), contacts),
+        )
+        .signer(op),
+        Row::new(
+            "instant_match",
+            instructions::instant_match(payer, cluster, off(), op, f.a.alice.pubkey()),
+        )
+        .circuit(circuits::INSTANT_MATCH)
+        .target(
+            staged_list_pda(&op, &f.a.alice.pubkey()),
+            staged_list_pda(&op, &Pubkey::new_from_array(never)),
+        )
+        .target(
+            staged_list_pda(&f.a.alice.pubkey(), &op),
+            staged_list_pda(&Pubkey::new_from_array(never), &op),
+        ),
+        Row::new(
+            "close_staged_list",
+            instructions::close_staged_list(op, f.a.alice.pubkey(), payer),
+        )
+        .party(op)
+        .target(
+            staged_list_pda(&op, &f.a.alice.pubkey()),
+            staged_list_pda(&op, &Pubkey::new_from_array(never)),
+        ),
+        // ---- databases: `open_db` has epoch 0 open and epoch 1 staged,
+        //      `loading_db` is still loading epoch 0. Staging and rotating
+        //      check the provider first, so their rows have no valid
+        //      fixture and send the wrong-status instruction throughout ----
+        Row::new(
+            "create_database",
+            instructions::create_database(payer, op, never, Namespace::Phone as u8, HashScheme::Sha256 as u8),
+        )
+        .signer(op),
+        Row::new(
+            "stage_database_epoch",
+            instructions::stage_database_epoch(payer, op, f.loading_db, 1),
+        )
+        .party(op)
+        .target(database_pda(&f.loading_db, 0), database_pda(&never, 0))
+        .wrong_status(
+            instructions::stage_database_epoch(payer, op, f.loading_db, 1),
+            ErrorCode::DatabaseNotOpen,
+        ),
+        Row::new(
+            "rotate_database_epoch",
+            instructions::rotate_database_epoch(op, &f.open_db, 1),
+        )
+        .party(op)
+        .target(database_pda(&f.open_db, 0), database_pda(&never, 0))
+        .wrong_status(
+            instructions::rotate_database_epoch(op, &f.open_db, 1),
+            ErrorCode::DatabaseNotSealed,
+        ),
+        Row::new(
+            "load_database_chunk",
+            instructions::load_database_chunk(payer, op, cluster, off(), &f.loading_db, 0, contacts),
+        )
+        .party(op)
+        .circuit(circuits::LOAD_DATABASE_CHUNK)
+        .target(database_pda(&f.loading_db, 0), database_pda(&never, 0))
+        .wrong_status(
+            instructions::load_database_chunk(payer, op, cluster, off(), &f.open_db, 0, contacts),
+            ErrorCode::DatabaseNotLoading,
+        ),
+        Row::new("seal_database", instructions::seal_database(op, &f.loading_db, 0))
+            .party(op)
+            .target(database_pda(&f.loading_db, 0), database_pda(&never, 0))
+            .wrong_status(
+                instructions::seal_database(op, &f.open_db, 0),
+                ErrorCode::DatabaseNotLoading,
+            ),
+        Row::new(
+            "query_psi",
+            instructions::query_psi(payer, op, cluster, off(), &f.open_db, 0, contacts),
+        )
+        .signer(op)
+        .circuit(circuits::QUERY_PSI)
+        .target(database_pda(&f.open_db, 0), database_pda(&never, 0))
+        .wrong_status(
+            instructions::query_psi(payer, op, cluster, off(), &f.loading_db, 0, contacts),
+            ErrorCode::DatabaseNotOpen,
+        ),
+        Row::new(
+            "check_membership",
+            instructions::check_membership(payer, op, cluster, off(), &f.open_db, 0, app, &query),
+        )
+        .signer(op)
+        .circuit(circuits::CHECK_MEMBERSHIP)
+        .target(database_pda(&f.open_db, 0), database_pda(&never, 0))
+        .case(
+            Attack::WrongSigner,
+            vec![instructions::check_membership(payer, intruder, cluster, off(), &f.open_db, 0, app, &query)],
+            unauthorized(),
+        )
+        .wrong_status(
+            instructions::check_membership(payer, op, cluster, off(), &f.loading_db, 0, app, &query),
+            ErrorCode::DatabaseNotOpen,
+        ),
+        Row::new(
+            "check_membership_batch",
+            instructions::check_membership_batch(payer, op, cluster, off(), &f.open_db, 0, app, &batch),
+        )
+        .signer(op)
+        .circuit(circuits::CHECK_MEMBERSHIP_BATCH)
+        .target(database_pda(&f.open_db, 0), database_pda(&never, 0))
+        .case(
+            Attack::WrongSigner,
+            vec![instructions::check_membership_batch(payer, intruder, cluster, off(), &f.open_db, 0, app, &batch)],
+            unauthorized(),
+        )
+        .wrong_status(
+            instructions::check_membership_batch(payer, op, cluster, off(), &f.loading_db, 0, app, &batch),
+            ErrorCode::DatabaseNotOpen,
+        ),
+    ]
+}
+
+// ============================================================
+// WORLD AND FIXTURES
+// ============================================================
+
+struct World {
+    rpc: RpcClient,
+    /// Upgrade authority; pays for the fixtures
+    payer: Keypair,
+    /// Pays for simulations, so no party is signer by being fee payer
+    relayer: Keypair,
+    /// Credit authority of `app_id`, database provider, and stand-in
+    /// for roles anyone may take
+    operator: Keypair,
+    intruder: Keypair,
+    /// Blocked by the fixtures, so `unblock_wallet` has a block to lift
+    blocked: Pubkey,
+    cluster: u32,
+    mxe_key: [u8; 32],
+    app_id: [u8; 32],
+}
+
+impl World {
+    fn connect() -> Self {
+        let url = std::env::var("ARCIUM_LOCALNET_URL").unwrap_or_else(|_| "http://127.0.0.1:8899".into());
+        let rpc = RpcClient::new_with_commitment(url, CommitmentConfig::confirmed());
+        let path = std::env::var("ANCHOR_WALLET")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| dirs::home_dir().unwrap().join(".config/solana/id.json"));
+        let payer = read_keypair_file(path).expect("payer keypair");
+        let cluster = std::env::var("ARCIUM_CLUSTER_OFFSET")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+
+        let world = World {
+            relayer: funded_keypair(&rpc),
+            operator: funded_keypair(&rpc),
+            intruder: Keypair::new(),
+            blocked: Keypair::new().pubkey(),
+            mxe_key: rpc::fetch_mxe_public_key(&rpc).expect("MXE key: is `arcium localnet` running?"),
+            app_id: random_session_id(),
+            rpc,
+            payer,
+            cluster,
+        };
+        world.init_program();
+
+        let price = rpc::fetch_config(&world.rpc).expect("config").computation_price;
+        let (payer, operator) = (world.payer.pubkey(), world.operator.pubkey());
+        world.send(
+            &[&world.operator],
+            instructions::open_credit_account(payer, operator, world.app_id),
+        );
+        world.send(
+            &[],
+            instructions::deposit_credit(payer, &world.app_id, FIXTURE_COMPUTATIONS * price),
+        );
+        world.send(&[&world.operator], instructions::open_activity_log(payer, operator));
+        world.send(&[], instructions::open_pair_quota(payer, operator, payer));
+        world.send(
+            &[&world.operator],
+            instructions::set_consent_record(operator, vec![payer], vec![world.app_id]),
+        );
+        world.send(&[], instructions::block_wallet(payer, world.blocked));
+        world
+    }
+
+    /// Initialize every comp def, the global stats and the config, as
+    /// far as an earlier run has not.
+    fn init_program(&self) {
+        let mxe = rpc::fetch_mxe(&self.rpc).expect("MXE account");
+        for circuit in circuits::ALL {
+            if !rpc::is_comp_def_initialized(&self.rpc, circuit).unwrap() {
+                self.send(
+                    &[],
+                    instructions::init_comp_def(self.payer.pubkey(), circuit, mxe.lut_offset_slot),
+                );
+            }
+        }
+        if rpc::fetch_global_stats(&self.rpc).is_err() {
+            self.send(&[], instructions::init_global_stats(self.payer.pubkey()));
+        }
+        if rpc::fetch_config(&self.rpc).is_err() {
+            self.send(&[], instructions::init_config(self.payer.pubkey(), 1_000));
+        }
+    }
+
+    fn send(&self, signers: &[&Keypair], ix: Instruction) {
+        rpc::send_with_requeue(&self.rpc, &self.payer, signers, &[ix], RequeuePolicy::default())
+            .unwrap_or_else(|e| panic!("fixture transaction failed: {e}"));
+    }
+
+    /// Send a queue instruction at `offset` and wait for its callback.
+    fn compute<E: Event + AnchorDeserialize>(
+        &self,
+        signers: &[&Keypair],
+        circuit: &str,
+        offset: u64,
+        ix: Instruction,
+    ) -> E {
+        self.send(signers, ix);
+        let computation = arcium_accounts(self.cluster, circuit, offset).computation_account;
+        rpc::await_event(&self.rpc, &computation, CALLBACK_TIMEOUT)
+            .unwrap_or_else(|e| panic!("{circuit} callback: {e}"))
+    }
+
+    /// The error a transaction of `ixs` fails with, if any.
+    fn simulate(&self, ixs: &[Instruction]) -> Option<TransactionError> {
+        let tx = Transaction::new_unsigned(Message::new(ixs, Some(&self.relayer.pubkey())));
+        self.rpc
+            .simulate_transaction_with_config(
+                &tx,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    commitment: Some(self.rpc.commitment()),
+                    ..Default::default()
+                },
+            )
+            .expect("simulate")
+            .value
+            .err
+    }
+
+    /// An initialized account this program does not own.
+    fn foreign_account(&self) -> Pubkey {
+        arcium_client::pda::mxe_acc(&PROGRAM_ID)
+    }
+
+    fn now(&self) -> i64 {
+        let slot = self.rpc.get_slot().unwrap();
+        self.rpc.get_block_time(slot).unwrap()
+    }
+
+    fn cipher(&self) -> PartyCipher {
+        PartyCipher::new(self.mxe_key)
+    }
+
+    fn contacts(&self, namespace: Namespace) -> EncryptedContacts {
+        self.cipher()
+            .encrypt_contacts(&hash_contact_list(HashScheme::Sha256, namespace, &CONTACTS).unwrap())
+    }
+
+    // ---- session fixtures ----
+
+    /// A session awaiting Alice.
+    fn session(&self, mode: SessionMode, namespace: Namespace, join_deadline: i64) -> Fixture {
+        self.session_at(mode, namespace, join_deadline, 32, None)
+    }
+
+    /// A session of `capacity` contacts awaiting Alice, locked to
+    /// whoever redeems `invite` if one is given.
+    fn session_at(
+        &self,
+        mode: SessionMode,
+        namespace: Namespace,
+        join_deadline: i64,
+        capacity: u8,
+        invite: Option<&str>,
+    ) -> Fixture {
+        let fixture = Fixture {
+            id: random_session_id(),
+            alice: Keypair::new(),
+            bob: Keypair::new(),
+        };
+        let invite = invite.map_or(NO_INVITE, |code| invite_hash(&fixture.id, code.as_bytes()));
+        let offset = random_computation_offset();
+        let _: SessionInitialized = self.compute(
+            &[&fixture.alice, &self.operator],
+            circuits::INIT_SESSION,
+            offset,
+            instructions::with_app_authority(
+                instructions::create_session(
+                    self.payer.pubkey(),
+                    fixture.alice.pubkey(),
+                    self.cluster,
+                    offset,
+                    fixture.id,
+                    self.app_id,
+                    self.cipher().public_key(),
+                    u128::from_le_bytes(random_nonce()),
+                    mode as u8,
+                    namespace as u8,
+                    HashScheme::Sha256 as u8,
+                    false,
+                    false,
+                    join_deadline,
+                    0,
+                    capacity,
+                    NO_MEMO,
+                    Pubkey::default(),
+                    invite,
+                ),
+                self.operator.pubkey(),
+            ),
+        );
+        fixture
+    }
+
+    /// Alice submits `contacts`; the session awaits Bob.
+    fn alice_submits(&self, fixture: &Fixture, contacts: &EncryptedContacts) {
+        self.alice_submits_at(fixture, contacts, 32);
+    }
+
+    /// Alice submits `contacts` to a session of `capacity` contacts.
+    fn alice_submits_at(&self, fixture: &Fixture, contacts: &EncryptedContacts, capacity: u8) {
+        let offset = random_computation_offset();
+        let _: AliceSubmitted = self.compute(
+            &[&fixture.alice],
+            instructions::alice_circuit(capacity),
+            offset,
+            instructions::submit_contacts_alice(
+                self.payer.pubkey(),
+                fixture.alice.pubkey(),
+                self.cluster,
+                offset,
+                &fixture.id,
+                &self.app_id,
+                contacts,
+                capacity,
+            ),
+        );
+    }
+
+    /// Alice purges once the join deadline has passed.
+    fn purge(&self, fixture: &Fixture, join_deadline: i64) {
+        while self.now() <= join_deadline {
+            thread::sleep(Duration::from_secs(1));
+        }
+        let offset = random_computation_offset();
+        let _: StatePurged = self.compute(
+            &[&fixture.alice],
+            circuits::PURGE_STATE,
+            offset,
+            instructions::purge_session_state(
+                self.payer.pubkey(),
+                fixture.alice.pubkey(),
+                self.cluster,
+                offset,
+                &fixture.id,
+            ),
+        );
+    }
+}
+
+fn funded_keypair(rpc: &RpcClient) -> Keypair {
+    let keypair = Keypair::new();
+    let sig = rpc
+        .request_airdrop(&keypair.pubkey(), 10 * LAMPORTS_PER_SOL)
+        .expect("airdrop");
+    rpc.poll_for_signature(&sig).expect("airdrop confirmation");
+    keypair
+}
+
+struct Fixture {
+    id: [u8; 32],
+    alice: Keypair,
+    /// Bob's key whether or not he has joined
+    bob: Keypair,
+}
+
+impl Fixture {
+    fn session(&self) -> Pubkey {
+        session_pda(&self.id)
+    }
+
+    fn handle(&self) -> Pubkey {
+        state_handle_pda(&self.session())
+    }
+}
+
+struct Fixtures {
+    /// One-shot, awaiting Alice
+    a: Fixture,
+    /// One-shot, awaiting Bob, at capacities 32, 16 and 8
+    b: [Fixture; 3],
+    /// Score, awaiting Bob
+    score_b: Fixture,
+    /// Friends-of-friends, awaiting Bob
+    conn_b: Fixture,
+    /// Committed set, awaiting Bob's commitment
+    set_b: Fixture,
+    /// One-shot, awaiting Bob, its invite claimed by the operator
+    invite: Fixture,
+    /// One-shot, matched
+    m: Fixture,
+    /// Subscription, matched
+    sub: Fixture,
+    /// Friends-of-friends, awaiting the connector
+    conn: Fixture,
+    /// Mutual follow, awaiting Bob
+    follow_b: Fixture,
+    /// Mutual follow, matched
+    follow_m: Fixture,
+    /// Committed set, awaiting Bob's chunks with the set committed
+    set: Fixture,
+    /// Subscription, purged before Bob joined
+    purged: Fixture,
+    /// One-shot, purged and closed
+    closed: Fixture,
+    /// One-shot, stateless, awaiting Alice's first submission
+    stateless: Fixture,
+    set_chunks: Vec<EncryptedContacts>,
+    follow_list: EncryptedContacts,
+    template_id: [u8; 32],
+    pool_id: [u8; 32],
+    blocklist_id: [u8; 32],
+    registry_id: [u8; 32],
+    open_db: [u8; 32],
+    loading_db: [u8; 32],
+}
+
+impl Fixtures {
+    fn build(w: &World) -> Self {
+        let payer = w.payer.pubkey();
+        let contacts = w.contacts(Namespace::Phone);
+        let join = |fixture: &Fixture| {
+            let offset = random_computation_offset();
+            let _: MatchComplete = w.compute(
+                &[&fixture.bob],
+                circuits::SUBMIT_AND_MATCH,
+                offset,
+                instructions::submit_and_match(
+                    payer,
+                    fixture.bob.pubkey(),
+                    fixture.alice.pubkey(),
+                    w.cluster,
+                    offset,
+                    &fixture.id,
+                    &w.app_id,
+                    &contacts,
+                    32,
+                    false,
+                ),
+            );
+        };
+
+        let a = w.session(SessionMode::OneShot, Namespace::Phone, 0);
+
+        // Awaiting Bob, so a blocked Bob gets as far as being recorded
+        let b = [32, 16, 8].map(|capacity| {
+            let fixture = w.session_at(SessionMode::OneShot, Namespace::Phone, 0, capacity, None);
+            w.alice_submits_at(&fixture, &contacts, capacity);
+            fixture
+        });
+        let [score_b, conn_b, set_b] =
+            [SessionMode::Score, SessionMode::FriendsOfFriends, SessionMode::CommittedSet].map(|mode| {
+                let fixture = w.session(mode, Namespace::Phone, 0);
+                w.alice_submits(&fixture, &contacts);
+                fixture
+            });
+
+        let invite = w.session_at(SessionMode::OneShot, Namespace::Phone, 0, 32, Some(INVITE_CODE));
+        w.alice_submits(&invite, &contacts);
+        w.send(
+            &[&w.operator],
+            instructions::claim_invite(w.operator.pubkey(), &invite.id, INVITE_CODE),
+        );
+
+        let m = w.session(SessionMode::OneShot, Namespace::Phone, 0);
+        w.alice_submits(&m, &contacts);
+        join(&m);
+
+        let sub = w.session(SessionMode::Subscription, Namespace::Phone, 0);
+        w.alice_submits(&sub, &contacts);
+        join(&sub);
+
+        let conn = w.session(SessionMode::FriendsOfFriends, Namespace::Phone, 0);
+        w.alice_submits(&conn, &contacts);
+        let offset = random_computation_offset();
+        let _: BobSubmitted = w.compute(
+            &[&conn.bob],
+            circuits::SUBMIT_BOB,
+            offset,
+            instructions::submit_contacts_bob(
+                payer,
+                conn.bob.pubkey(),
+                conn.alice.pubkey(),
+                w.cluster,
+                offset,
+                &conn.id,
+                &w.app_id,
+                &contacts,
+            ),
+        );
+
+        // Each follows the other
+        let follow_b = w.session(SessionMode::MutualFollow, Namespace::Wallet, 0);
+        let follow_m = w.session(SessionMode::MutualFollow, Namespace::Wallet, 0);
+        for fixture in [&follow_b, &follow_m] {
+            let follows = fold_follow_list(HashScheme::Sha256, &[fixture.bob.pubkey().to_bytes()]).unwrap();
+            w.alice_submits(fixture, &w.cipher().encrypt_contacts(&follows));
+        }
+        let follow_list = w
+            .cipher()
+            .encrypt_contacts(&fold_follow_list(HashScheme::Sha256, &[follow_m.alice.pubkey().to_bytes()]).unwrap());
+        let offset = random_computation_offset();
+        let _: FollowChecked = w.compute(
+            &[&follow_m.bob],
+            circuits::CHECK_FOLLOW,
+            offset,
+            instructions::submit_and_check_follow(
+                payer,
+                follow_m.bob.pubkey(),
+                follow_m.alice.pubkey(),
+                w.cluster,
+                offset,
+                &follow_m.id,
+                &w.app_id,
+                &follow_list,
+            ),
+        );
+
+        let set = w.session(SessionMode::CommittedSet, Namespace::Phone, 0);
+        w.alice_submits(&set, &contacts);
+        let set_chunks: Vec<EncryptedContacts> = hash_contact_set(HashScheme::Sha256, Namespace::Phone, &CONTACTS)
+            .unwrap()
+            .iter()
+            .map(|chunk| w.cipher().encrypt_contacts(chunk))
+            .collect();
+        w.send(
+            &[&set.bob],
+            instructions::commit_set(set.bob.pubkey(), set.alice.pubkey(), &set.id, &set_chunks),
+        );
+
+        let purged_deadline = w.now() + PURGE_DEADLINE;
+        let purged = w.session(SessionMode::Subscription, Namespace::Phone, purged_deadline);
+        let closed_deadline = w.now() + PURGE_DEADLINE;
+        let closed = w.session(SessionMode::OneShot, Namespace::Phone, closed_deadline);
+        w.purge(&purged, purged_deadline);
+        w.purge(&closed, closed_deadline);
+        w.send(
+            &[&closed.alice],
+            instructions::close_session(closed.alice.pubkey(), payer, &closed.id),
+        );
+
+        let stateless = Fixture {
+            id: random_session_id(),
+            alice: Keypair::new(),
+            bob: Keypair::new(),
+        };
+        w.send(
+            &[&stateless.alice, &w.operator],
+            instructions::with_app_authority(
+                instructions::create_stateless_session(
+                    payer,
+                    stateless.alice.pubkey(),
+                    stateless.id,
+                    w.app_id,
+                    SessionMode::OneShot as u8,
+                    Namespace::Phone as u8,
+                    HashScheme::Sha256 as u8,
+                    false,
+                    false,
+                    0,
+                    0,
+                    32,
+                    NO_MEMO,
+                ),
+                w.operator.pubkey(),
+            ),
+        );
+
+        let template_id = random_session_id();
+        w.send(
+            &[&w.operator],
+            instructions::create_session_template(
+                payer,
+                w.operator.pubkey(),
+                w.app_id,
+                template_id,
+                SessionMode::OneShot as u8,
+                Namespace::Phone as u8,
+                HashScheme::Sha256 as u8,
+                false,
+                false,
+                0,
+                0,
+                Pubkey::default(),
+            ),
+        );
+
+        let operator = w.operator.pubkey();
+        let pool_id = random_session_id();
+        let offset = random_computation_offset();
+        let _: PoolOpened = w.compute(
+            &[&w.operator],
+            circuits::CREATE_POOL,
+            offset,
+            instructions::create_pool(
+                payer,
+                operator,
+                w.cluster,
+                offset,
+                pool_id,
+                Namespace::Phone as u8,
+                &contacts,
+            ),
+        );
+        let blocklist_id = random_session_id();
+        let offset = random_computation_offset();
+        let _: BlocklistOpened = w.compute(
+            &[&w.operator],
+            circuits::CREATE_BLOCKLIST,
+            offset,
+            instructions::create_blocklist(
+                payer,
+                operator,
+                w.cluster,
+                offset,
+                blocklist_id,
+                Namespace::Phone as u8,
+                &contacts,
+            ),
+        );
+        let registry_id = random_session_id();
+        let offset = random_computation_offset();
+        let _: CardRegistryOpened = w.compute(
+            &[&w.operator],
+            circuits::CREATE_CARD_REGISTRY,
+            offset,
+            instructions::create_card_registry(
+                payer,
+                operator,
+                w.cluster,
+                offset,
+                registry_id,
+                Namespace::Phone as u8,
+                HashScheme::Sha256 as u8,
+                w.cipher().public_key(),
+                u128::from_le_bytes(random_nonce()),
+            ),
+        );
+
+        // Staged for each other and never matched, so both stay `Staged`
+        w.send(
+            &[&w.operator],
+            instructions::stage_list(payer, operator, a.alice.pubkey(), &contacts),
+        );
+        w.send(
+            &[&a.alice],
+            instructions::stage_list(payer, a.alice.pubkey(), operator, &contacts),
+        );
+
+        let open_db = random_session_id();
+        let loading_db = random_session_id();
+        for id in [open_db, loading_db] {
+            w.send(
+                &[&w.operator],
+                instructions::create_database(payer, operator, id, Namespace::Phone as u8, HashScheme::Sha256 as u8),
+            );
+        }
+        let offset = random_computation_offset();
+        let _: DatabaseChunkLoaded = w.compute(
+            &[&w.operator],
+            circuits::LOAD_DATABASE_CHUNK,
+            offset,
+            instructions::load_database_chunk(payer, operator, w.cluster, offset, &open_db, 0, &contacts),
+        );
+        w.send(&[&w.operator], instructions::seal_database(operator, &open_db, 0));
+        w.send(
+            &[&w.operator],
+            instructions::stage_database_epoch(payer, operator, open_db, 1),
+        );
+
+        Fixtures {
+            a,
+            b,
+            score_b,
+            conn_b,
+            set_b,
+            invite,
+            m,
+            sub,
+            conn,
+            follow_b,
+            follow_m,
+            set,
+            purged,
+            closed,
+            stateless,
+            set_chunks,
+            follow_list,
+            template_id,
+            pool_id,
+            blocklist_id,
+            registry_id,
+            open_db,
+            loading_db,
+        }
+    }
+}
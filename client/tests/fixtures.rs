@@ -0,0 +1,72 @@
+// Seeded encryption fixtures.
+//
+// A `Fixtures` cipher must produce the same bytes for the same seed,
+// label, MXE key, and call order, and different bytes otherwise, and
+// its payloads must decrypt under the MXE side of the exchange.
+//
+//     cargo test --test fixtures
+
+use arcium_client::encryption::RescueCipher;
+use pcd_client::{hash_contact_list, Fixtures, HashScheme, HashedContacts, Namespace, MAX_CONTACTS};
+use x25519_dalek::PublicKey;
+
+fn contacts() -> HashedContacts {
+    hash_contact_list(
+        HashScheme::Sha256,
+        Namespace::Email,
+        &["alice@example.com", "bob@example.com", "carol@example.com"],
+    )
+    .unwrap()
+}
+
+#[test]
+fn same_seed_reproduces_every_byte() {
+    let fixtures = Fixtures::new(b"fixtures test");
+    let mxe = fixtures.public_key("mxe");
+    let first = fixtures.cipher("alice", mxe);
+    let second = Fixtures::new(b"fixtures test").cipher("alice", mxe);
+
+    for _ in 0..3 {
+        let a = first.encrypt_contacts(&contacts());
+        let b = second.encrypt_contacts(&contacts());
+        assert_eq!(a.to_bytes(), b.to_bytes());
+    }
+    assert_eq!(first.public_key(), fixtures.public_key("alice"));
+}
+
+#[test]
+fn seed_label_and_call_order_all_change_the_bytes() {
+    let fixtures = Fixtures::new(b"fixtures test");
+    let mxe = fixtures.public_key("mxe");
+    let alice = fixtures.cipher("alice", mxe);
+    let baseline = alice.encrypt_contacts(&contacts()).to_bytes();
+
+    // The next payload takes the next nonce
+    assert_ne!(alice.encrypt_contacts(&contacts()).to_bytes(), baseline);
+    let bob = fixtures.cipher("bob", mxe);
+    assert_ne!(bob.encrypt_contacts(&contacts()).to_bytes(), baseline);
+    let other = Fixtures::new(b"another test").cipher("alice", mxe);
+    assert_ne!(other.encrypt_contacts(&contacts()).to_bytes(), baseline);
+}
+
+#[test]
+fn payloads_decrypt_with_the_seeded_mxe_key() {
+    let fixtures = Fixtures::new(b"fixtures test");
+    let alice = fixtures.cipher("alice", fixtures.public_key("mxe"));
+    let shared = fixtures
+        .secret("mxe")
+        .diffie_hellman(&PublicKey::from(fixtures.public_key("alice")));
+    let mxe = RescueCipher::new(shared.as_bytes());
+
+    let hashed = contacts();
+    let encrypted = alice.encrypt_contacts(&hashed);
+    assert_eq!(encrypted.nonce.to_le_bytes(), fixtures.nonce("alice", 0));
+
+    let mut ciphertexts = encrypted.encrypted_hashes.to_vec();
+    ciphertexts.push(encrypted.encrypted_count);
+    ciphertexts.push(encrypted.encrypted_namespace);
+    let plaintext = mxe.decrypt(&ciphertexts, &encrypted.nonce.to_le_bytes());
+    assert_eq!(plaintext[..MAX_CONTACTS], hashed.hashes);
+    assert_eq!(plaintext[MAX_CONTACTS], hashed.count as u128);
+    assert_eq!(plaintext[MAX_CONTACTS + 1], hashed.namespace as u128);
+}
@@ -0,0 +1,93 @@
+// Golden vector decryption.
+//
+// Decrypts the match results of the circuit golden vectors
+// (encrypted-ixs/tests/golden/) through the SDK. Each result is
+// encrypted as the MXE would encrypt it for the recipient, under fixed
+// keys, and must decrypt to the slots and hashes the vector records.
+// The vectors come from the circuit reference model, so this ties the
+// SDK's unpacking of a `MatchResult` to what the circuits emit.
+//
+//     cargo test --test golden
+
+use std::{fs, path::PathBuf};
+
+use arcium_client::encryption::RescueCipher;
+use pcd_client::{crypto::DecryptedMatches, PartyCipher, MATCH_WORDS, MAX_CONTACTS};
+use serde::Deserialize;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+/// Only the fields this test reads; the rest of each vector is checked
+/// against the reference model by encrypted-ixs/tests/golden.rs.
+#[derive(Deserialize)]
+struct Vector {
+    description: String,
+    alice: Option<List>,
+    bob: List,
+    expected: Expected,
+}
+
+#[derive(Deserialize)]
+struct List {
+    hashes: [u128; MAX_CONTACTS],
+}
+
+#[derive(Deserialize)]
+struct Expected {
+    bob_result: Matches,
+    alice_result: Matches,
+}
+
+#[derive(Deserialize)]
+struct Matches {
+    indices: [u128; MATCH_WORDS],
+    match_count: u32,
+    slots: Vec<usize>,
+    matched_hashes: Vec<u128>,
+}
+
+#[test]
+fn golden_match_results_decrypt_to_their_slots() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../encrypted-ixs/tests/golden");
+    let party_secret = StaticSecret::from([7u8; 32]);
+    let mxe_secret = StaticSecret::from([9u8; 32]);
+    let party = PartyCipher::from_secret(party_secret.clone(), PublicKey::from(&mxe_secret).to_bytes());
+    let mxe = RescueCipher::new(mxe_secret.diffie_hellman(&PublicKey::from(&party_secret)).as_bytes());
+    let nonce = [3u8; 16];
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("reading {}: {e}", dir.display()))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no vectors in {}", dir.display());
+
+    for path in paths {
+        let vector: Vector = serde_json::from_str(&fs::read_to_string(&path).unwrap())
+            .unwrap_or_else(|e| panic!("parsing {}: {e}", path.display()));
+        let alice_hashes = vector.alice.map_or([0; MAX_CONTACTS], |alice| alice.hashes);
+
+        for (recipient, expected, list) in [
+            ("bob", &vector.expected.bob_result, &vector.bob.hashes),
+            ("alice", &vector.expected.alice_result, &alice_hashes),
+        ] {
+            let mut plaintext = expected.indices.to_vec();
+            plaintext.push(expected.match_count as u128);
+            let ciphertexts = mxe.encrypt(&plaintext, &nonce);
+            let mut encrypted_matches = [[0u8; 32]; MATCH_WORDS];
+            encrypted_matches.copy_from_slice(&ciphertexts[..MATCH_WORDS]);
+
+            let decrypted = party.decrypt_matches(&encrypted_matches, &ciphertexts[MATCH_WORDS], &nonce);
+            let context = format!("{} ({recipient}): {}", path.display(), vector.description);
+            assert_eq!(
+                decrypted,
+                DecryptedMatches {
+                    slots: expected.slots.clone(),
+                    match_count: expected.match_count,
+                },
+                "{context}"
+            );
+            assert_eq!(decrypted.hashes(list), expected.matched_hashes, "{context}");
+        }
+    }
+}
@@ -0,0 +1,65 @@
+// Callback lookup in confirmed transactions.
+//
+// Arcium invokes a computation's callback by CPI, so its signed output
+// is usually among the transaction's inner instructions rather than its
+// top-level ones. `verify::callback_args` must find it in either place,
+// and only in an instruction of this program.
+//
+//     cargo test --test verify
+
+use anchor_lang::Discriminator;
+use pcd_client::{verify::callback_args, PROGRAM_ID};
+use private_contact_discovery::instruction::{SubmitAndMatchCallback, SubmitContactsAliceCallback};
+use solana_sdk::{instruction::CompiledInstruction, pubkey::Pubkey};
+use solana_transaction_status::{UiCompiledInstruction, UiInnerInstructions, UiInstruction};
+
+const ARGS: [u8; 4] = [1, 2, 3, 4];
+
+/// Fee payer, the Arcium program, and this program.
+fn keys() -> Vec<Pubkey> {
+    vec![Pubkey::new_unique(), Pubkey::new_unique(), PROGRAM_ID]
+}
+
+fn instruction(program_id_index: u8, discriminator: &[u8]) -> CompiledInstruction {
+    CompiledInstruction {
+        program_id_index,
+        accounts: vec![0],
+        data: [discriminator, &ARGS].concat(),
+    }
+}
+
+/// `nested`, invoked from the first top-level instruction.
+fn inner(nested: &[CompiledInstruction]) -> Vec<UiInnerInstructions> {
+    vec![UiInnerInstructions {
+        index: 0,
+        instructions: nested
+            .iter()
+            .map(|ix| UiInstruction::Compiled(UiCompiledInstruction::from(ix, Some(2))))
+            .collect(),
+    }]
+}
+
+#[test]
+fn callback_invoked_by_cpi_is_found_among_inner_instructions() {
+    let top_level = [instruction(1, &[0; 8])];
+    let nested = [instruction(2, SubmitAndMatchCallback::DISCRIMINATOR)];
+    let args = callback_args::<SubmitAndMatchCallback>(&keys(), &top_level, &inner(&nested));
+    assert_eq!(args.as_deref(), Some(&ARGS[..]));
+}
+
+#[test]
+fn top_level_callback_is_still_found() {
+    let top_level = [instruction(2, SubmitAndMatchCallback::DISCRIMINATOR)];
+    let args = callback_args::<SubmitAndMatchCallback>(&keys(), &top_level, &[]);
+    assert_eq!(args.as_deref(), Some(&ARGS[..]));
+}
+
+#[test]
+fn other_callbacks_and_other_programs_are_skipped() {
+    let top_level = [instruction(1, &[0; 8])];
+    let nested = [
+        instruction(1, SubmitAndMatchCallback::DISCRIMINATOR),
+        instruction(2, SubmitContactsAliceCallback::DISCRIMINATOR),
+    ];
+    assert_eq!(callback_args::<SubmitAndMatchCallback>(&keys(), &top_level, &inner(&nested)), None);
+}
@@ -9,26 +9,74 @@
 
 use arcis::*;
 
+/// Plaintext reference implementation of every circuit.
+#[cfg(feature = "reference")]
+pub mod reference;
+
 #[encrypted]
 mod circuits {
     use arcis::*;
 
     /// Maximum contacts per user. Fixed at compile time (ARCIS requirement).
     /// 32 contacts = 1024 comparisons in PSI, well within MPC budget.
+    /// This module's sizes and struct layouts must match the `schema`
+    /// crate, which the program encodes its arguments from.
     const MAX_CONTACTS: usize = 32;
 
+    /// Smaller session capacities (`schema::CAPACITY_TIERS`), each with
+    /// its own submission and match circuits, so a session of short
+    /// lists pays for capacity squared comparisons rather than 1024.
+    const TIER_8: usize = 8;
+    const TIER_16: usize = 16;
+
+    /// Maximum additions (and, separately, removals) per subscription delta.
+    const MAX_DELTA: usize = 16;
+
+    /// Result slots returned per reveal page. Stored results hold
+    /// MAX_CONTACTS slots, so today there is exactly one page; larger
+    /// capacities are revealed a page at a time.
+    const PAGE_SIZE: usize = 32;
+
+    /// Words a `MatchResult` page packs its slots into, one byte each.
+    const MATCH_WORDS: usize = 2;
+
+    /// Contact cards a registry holds. Every publish rewrites the whole
+    /// registry, so it is kept smaller than a contact list.
+    const CARD_SLOTS: usize = 16;
+
+    /// Chunks (each a full `ContactList`) a server database holds. A
+    /// query compares against all of them: 32 x 256 comparisons.
+    const DATABASE_CHUNKS: usize = 8;
+
+    /// Identifiers one batched membership check asks about.
+    const MEMBERSHIP_BATCH_SLOTS: usize = 16;
+
+    /// Hashes at or above this are decoys: padding a client adds so a
+    /// short list fills every slot. Decoys never match and are not
+    /// counted. A real identifier lands here with probability 2^-64.
+    const DECOY_FLOOR: u128 = 0xFFFF_FFFF_FFFF_FFFF_0000_0000_0000_0000;
+
     // ================================================================
     // STRUCTS
     // ================================================================
 
     /// A user's contact list: fixed-size array of hashed contact identifiers.
-    /// Each contact is the upper 128 bits of SHA-256(normalize(contact)).
-    /// Unused slots MUST be 0 (padding).
+    /// Each contact is the upper 128 bits of
+    /// SHA-256(namespace_tag || ":" || normalize(contact)).
+    /// Used slots MUST be sorted ascending with no duplicates, and unused
+    /// slots MUST be 0 (padding); submissions that are not are rejected.
+    /// Decoys (`DECOY_FLOOR` and up) may fill otherwise unused slots and
+    /// count as used here; the circuits drop them when matching and
+    /// counting.
     pub struct ContactList {
         /// Hashed contact identifiers (u128 each, zero-padded)
         pub hashes: [u128; 32],
-        /// How many slots are actually used (1..=32), rest are 0
+        /// How many slots are actually used (1..=32), decoys included;
+        /// the rest are 0
         pub count: u32,
+        /// Identifier namespace the hashes were derived in
+        /// (0=phone, 1=email, 2=username, 3=wallet)
+        pub namespace: u8,
     }
 
     /// Session state held encrypted by the MXE cluster.
@@ -36,11 +84,11 @@ mod circuits {
     pub struct SessionState {
         /// Alice's contact hashes (first submitter)
         pub alice_hashes: [u128; 32],
-        /// Number of Alice's contacts
+        /// Number of Alice's contacts, decoys excluded
         pub alice_count: u32,
         /// Bob's contact hashes (second submitter)
         pub bob_hashes: [u128; 32],
-        /// Number of Bob's contacts
+        /// Number of Bob's contacts, decoys excluded
         pub bob_count: u32,
         /// Whether Alice has submitted (1=yes, 0=no)
         pub alice_submitted: u8,
@@ -54,21 +102,139 @@ mod circuits {
         pub result_bob: [u128; 32],
         /// Number of matches found
         pub result_count: u32,
+        /// Session namespace; lists from any other namespace are rejected
+        pub namespace: u8,
+        /// Folded session id, set at init. Every later call passes the
+        /// session it acts on and changes or reveals nothing on a
+        /// mismatch, so a misrouted state blob cannot be matched against
+        pub session_tag: u128,
+    }
+
+    /// `SessionState` as of the previous state layout, what
+    /// `migrate_session_state` reads. When `SessionState` changes (and
+    /// the program's `STATE_VERSION` with it), this keeps the old
+    /// definition; until the first change the two are the same.
+    pub struct LegacySessionState {
+        pub alice_hashes: [u128; 32],
+        pub alice_count: u32,
+        pub bob_hashes: [u128; 32],
+        pub bob_count: u32,
+        pub alice_submitted: u8,
+        pub bob_submitted: u8,
+        pub is_matched: u8,
+        pub result_alice: [u128; 32],
+        pub result_bob: [u128; 32],
+        pub result_count: u32,
+        pub namespace: u8,
+        pub session_tag: u128,
     }
 
-    /// The intersection result returned to a user.
+    /// The intersection result returned to a user. The recipient already
+    /// knows the hashes it submitted, so a result names only which of its
+    /// own list slots matched: byte `j` of `indices` (word `j / 16`, bits
+    /// `8 * (j % 16)` up) holds the slot plus one, or 0 for no match.
+    /// Reveals return one page of slots; `match_count` is always the
+    /// total, so the caller knows how many pages to fetch.
     pub struct MatchResult {
-        /// Matched contact hashes (0 = no match at that slot)
-        pub matches: [u128; 32],
+        /// Matched slots of the recipient's list, a byte each
+        pub indices: [u128; 2],
         /// Total number of mutual contacts found
         pub match_count: u32,
     }
 
+    /// A subscription round's changes to one party's list.
+    /// Unused slots MUST be 0 (padding).
+    pub struct ContactDelta {
+        /// Hashes to add (skipped if already present or the list is full)
+        pub added: [u128; 16],
+        /// Hashes to remove
+        pub removed: [u128; 16],
+        /// Identifier namespace the hashes were derived in
+        pub namespace: u8,
+    }
+
+    /// Overlap between two lists as fixed-point ratios, in basis points
+    /// (10000 = 100%). Returned instead of identities in score sessions.
+    /// With sizes hidden, both lists count as full (32 slots), so the
+    /// ratios depend on the match count alone.
+    pub struct OverlapScore {
+        /// Matches divided by the smaller list size
+        pub overlap_bp: u32,
+        /// Jaccard index: matches divided by the union size
+        pub jaccard_bp: u32,
+    }
+
+    /// Outcome of screening a list against a blocklist. Only whether and
+    /// how many of the user's contacts are listed, never which.
+    pub struct BlocklistResult {
+        /// 1 if at least one contact is on the blocklist, 0 otherwise
+        pub blocked: u8,
+        /// Number of the user's contacts on the blocklist
+        pub blocked_count: u32,
+    }
+
+    /// A messaging key a contact registers under its own hashed
+    /// identifier, so whoever discovers the contact can open an
+    /// encrypted channel to it. The key is an X25519 public key split
+    /// into two little-endian halves.
+    pub struct ContactCard {
+        /// The publisher's own identifier, hashed as others hash it
+        pub hash: u128,
+        /// Bytes 0..16 of the key
+        pub key_lo: u128,
+        /// Bytes 16..32 of the key
+        pub key_hi: u128,
+        /// Identifier namespace the hash was derived in
+        pub namespace: u8,
+    }
+
+    /// Published cards, held encrypted by the MXE on a registry account.
+    /// Empty slots have hash 0.
+    pub struct CardRegistry {
+        pub hashes: [u128; 16],
+        /// Folded pubkey of each entry's publisher, the only wallet that
+        /// may replace it
+        pub publishers: [u128; 16],
+        pub keys_lo: [u128; 16],
+        pub keys_hi: [u128; 16],
+        /// Number of occupied slots
+        pub count: u32,
+        /// Registry namespace; cards from any other namespace are dropped
+        pub namespace: u8,
+    }
+
+    /// Cards of a user's contacts found in a registry, by registry slot.
+    pub struct CardMatches {
+        /// Matched hashes (0 = slot not matched)
+        pub hashes: [u128; 16],
+        pub keys_lo: [u128; 16],
+        pub keys_hi: [u128; 16],
+        /// Number of cards returned
+        pub card_count: u32,
+    }
+
+    /// A single identifier a client asks a database about, e.g. "is this
+    /// number already registered?"
+    pub struct MembershipQuery {
+        pub hash: u128,
+        /// Identifier namespace the hash was derived in
+        pub namespace: u8,
+    }
+
+    /// Up to `MEMBERSHIP_BATCH_SLOTS` identifiers checked against a
+    /// database in one computation (e.g. an invite list at signup).
+    pub struct MembershipBatch {
+        /// Only the first `queries` slots (a plaintext input) are answered
+        pub hashes: [u128; 16],
+        /// Identifier namespace the hashes were derived in
+        pub namespace: u8,
+    }
+
     /// Lightweight confirmation returned after contact submission
     pub struct SubmitConfirmation {
         /// 1 if accepted, 0 if rejected
         pub accepted: u8,
-        /// Which party number (1=Alice, 2=Bob)
+        /// Which party number (1=Alice, 2=Bob, 3=connector)
         pub party: u8,
     }
 
@@ -77,10 +243,14 @@ mod circuits {
     // ================================================================
 
     /// Initialize a new PSI session.
-    /// Creates empty encrypted state for the MXE to hold.
+    /// Creates empty encrypted state for the MXE to hold, bound to the
+    /// session's identifier `namespace` and to the session itself by
+    /// `session_tag` (both set by the program).
     #[instruction]
     pub fn init_session(
         _input: Enc<Shared, u8>,
+        namespace: u8,
+        session_tag: u128,
     ) -> Enc<Mxe, SessionState> {
         let initial = SessionState {
             alice_hashes: [0u128; 32],
@@ -93,6 +263,8 @@ mod circuits {
             result_alice: [0u128; 32],
             result_bob: [0u128; 32],
             result_count: 0,
+            namespace,
+            session_tag,
         };
 
         Mxe::get().from_arcis(initial)
@@ -100,17 +272,132 @@ mod circuits {
 
     /// Submit contacts as the first party (Alice).
     /// Stores the encrypted contact list in session state.
-    /// Returns a confirmation to the submitter.
+    /// Returns a confirmation to the submitter, and the accepted flag in
+    /// plaintext so the program can roll back a rejected submission.
+    /// A list using more than the session's `capacity` slots is rejected.
     #[instruction]
     pub fn submit_contacts_alice(
         current_state: Enc<Mxe, SessionState>,
         contacts: Enc<Shared, ContactList>,
-    ) -> (Enc<Mxe, SessionState>, Enc<Shared, SubmitConfirmation>) {
-        let state = current_state.to_arcis();
-        let list = contacts.to_arcis();
+        capacity: u8,
+        session_tag: u128,
+    ) -> (Enc<Mxe, SessionState>, Enc<Shared, SubmitConfirmation>, bool) {
+        let (updated, confirmation, slot_available) = store_alice(
+            current_state.to_arcis(),
+            contacts.to_arcis(),
+            capacity as u32,
+            session_tag,
+            MAX_CONTACTS,
+        );
+        (
+            current_state.owner.from_arcis(updated),
+            contacts.owner.from_arcis(confirmation),
+            slot_available.reveal(),
+        )
+    }
+
+    /// `submit_contacts_alice` for a session of capacity 16: only the
+    /// first 16 slots are checked for order and counted.
+    #[instruction]
+    pub fn submit_contacts_alice_16(
+        current_state: Enc<Mxe, SessionState>,
+        contacts: Enc<Shared, ContactList>,
+        session_tag: u128,
+    ) -> (Enc<Mxe, SessionState>, Enc<Shared, SubmitConfirmation>, bool) {
+        let (updated, confirmation, slot_available) = store_alice(
+            current_state.to_arcis(),
+            contacts.to_arcis(),
+            TIER_16 as u32,
+            session_tag,
+            TIER_16,
+        );
+        (
+            current_state.owner.from_arcis(updated),
+            contacts.owner.from_arcis(confirmation),
+            slot_available.reveal(),
+        )
+    }
+
+    /// `submit_contacts_alice` for a session of capacity 8.
+    #[instruction]
+    pub fn submit_contacts_alice_8(
+        current_state: Enc<Mxe, SessionState>,
+        contacts: Enc<Shared, ContactList>,
+        session_tag: u128,
+    ) -> (Enc<Mxe, SessionState>, Enc<Shared, SubmitConfirmation>, bool) {
+        let (updated, confirmation, slot_available) = store_alice(
+            current_state.to_arcis(),
+            contacts.to_arcis(),
+            TIER_8 as u32,
+            session_tag,
+            TIER_8,
+        );
+        (
+            current_state.owner.from_arcis(updated),
+            contacts.owner.from_arcis(confirmation),
+            slot_available.reveal(),
+        )
+    }
+
+    /// `init_session` and `submit_contacts_alice` in one computation, for
+    /// sessions created with `create_stateless_session`: builds the empty
+    /// state bound to `namespace` and `session_tag` and stores Alice's
+    /// list in it. The state is returned even when the list is rejected,
+    /// so her resubmission goes through `submit_contacts_alice`.
+    #[instruction]
+    pub fn init_and_submit_alice(
+        contacts: Enc<Shared, ContactList>,
+        namespace: u8,
+        capacity: u8,
+        session_tag: u128,
+    ) -> (Enc<Mxe, SessionState>, Enc<Shared, SubmitConfirmation>, bool) {
+        let initial = SessionState {
+            alice_hashes: [0u128; 32],
+            alice_count: 0,
+            bob_hashes: [0u128; 32],
+            bob_count: 0,
+            alice_submitted: 0,
+            bob_submitted: 0,
+            is_matched: 0,
+            result_alice: [0u128; 32],
+            result_bob: [0u128; 32],
+            result_count: 0,
+            namespace,
+            session_tag,
+        };
+        let (updated, confirmation, slot_available) = store_alice(
+            initial,
+            contacts.to_arcis(),
+            capacity as u32,
+            session_tag,
+            MAX_CONTACTS,
+        );
+        (
+            Mxe::get().from_arcis(updated),
+            contacts.owner.from_arcis(confirmation),
+            slot_available.reveal(),
+        )
+    }
 
-        // Check if Alice slot is available
-        let slot_available = state.alice_submitted == 0;
+    /// The Alice submission circuits' shared body. `slots` is a constant
+    /// at every call and bounds the order checks and the count, which
+    /// cost the most here; a list whose `count` fits `slots` has only
+    /// zeros past it, so the result is the same as checking all 32.
+    fn store_alice(
+        state: SessionState,
+        list: ContactList,
+        capacity: u32,
+        session_tag: u128,
+        slots: usize,
+    ) -> (SessionState, SubmitConfirmation, bool) {
+        // Check if Alice slot is available, the state is this session's,
+        // the list is in the session's namespace, and the list is well
+        // formed and fits the session
+        let slot_available = state.alice_submitted == 0
+            && state.session_tag == session_tag
+            && list.namespace == state.namespace
+            && well_formed_within(list.hashes, list.count, slots)
+            && list.count <= capacity;
 
         // Copy hashes into state (both branches always evaluated in MPC)
         let mut new_hashes = [0u128; 32];
@@ -123,7 +410,7 @@ mod circuits {
         }
 
         let new_count = if slot_available {
-            list.count
+            contact_count_within(list.hashes, slots)
         } else {
             state.alice_count
         };
@@ -145,6 +432,8 @@ mod circuits {
             result_alice: state.result_alice,
             result_bob: state.result_bob,
             result_count: state.result_count,
+            namespace: state.namespace,
+            session_tag: state.session_tag,
         };
 
         let confirmation = SubmitConfirmation {
@@ -152,27 +441,114 @@ mod circuits {
             party: 1,
         };
 
-        (
-            current_state.owner.from_arcis(updated),
-            contacts.owner.from_arcis(confirmation),
-        )
+        (updated, confirmation, slot_available)
     }
 
     /// Submit contacts as the second party (Bob) AND compute intersection.
     /// This is the core PSI circuit: O(32*32) = 1024 comparisons.
     /// Returns Bob's match result; Alice's is stored in state for later retrieval.
+    /// The plaintext flag reports whether Bob's list was accepted. When
+    /// `publish` is 1 (both parties opted in) the match count is also
+    /// revealed in plaintext; otherwise the revealed count is always 0.
+    /// When `withhold` is 1 (a time-locked session before its reveal
+    /// slot) the result is stored but Bob receives an empty one, and
+    /// fetches his matches with `reveal_bob_matches` once unlocked.
     #[instruction]
     pub fn submit_and_match(
         current_state: Enc<Mxe, SessionState>,
         bob_contacts: Enc<Shared, ContactList>,
-    ) -> (Enc<Mxe, SessionState>, Enc<Shared, MatchResult>) {
-        let state = current_state.to_arcis();
-        let bob = bob_contacts.to_arcis();
+        publish: u8,
+        withhold: u8,
+        session_tag: u128,
+    ) -> (Enc<Mxe, SessionState>, Enc<Shared, MatchResult>, bool, u32) {
+        let (updated, result, accepted, public_count) = match_within(
+            current_state.to_arcis(),
+            bob_contacts.to_arcis(),
+            publish,
+            withhold,
+            session_tag,
+            MAX_CONTACTS,
+        );
+        (
+            current_state.owner.from_arcis(updated),
+            bob_contacts.owner.from_arcis(result),
+            accepted.reveal(),
+            public_count.reveal(),
+        )
+    }
 
+    /// `submit_and_match` for a session of capacity 16: 256 comparisons.
+    #[instruction]
+    pub fn submit_and_match_16(
+        current_state: Enc<Mxe, SessionState>,
+        bob_contacts: Enc<Shared, ContactList>,
+        publish: u8,
+        withhold: u8,
+        session_tag: u128,
+    ) -> (Enc<Mxe, SessionState>, Enc<Shared, MatchResult>, bool, u32) {
+        let (updated, result, accepted, public_count) = match_within(
+            current_state.to_arcis(),
+            bob_contacts.to_arcis(),
+            publish,
+            withhold,
+            session_tag,
+            TIER_16,
+        );
+        (
+            current_state.owner.from_arcis(updated),
+            bob_contacts.owner.from_arcis(result),
+            accepted.reveal(),
+            public_count.reveal(),
+        )
+    }
+
+    /// `submit_and_match` for a session of capacity 8: 64 comparisons.
+    #[instruction]
+    pub fn submit_and_match_8(
+        current_state: Enc<Mxe, SessionState>,
+        bob_contacts: Enc<Shared, ContactList>,
+        publish: u8,
+        withhold: u8,
+        session_tag: u128,
+    ) -> (Enc<Mxe, SessionState>, Enc<Shared, MatchResult>, bool, u32) {
+        let (updated, result, accepted, public_count) = match_within(
+            current_state.to_arcis(),
+            bob_contacts.to_arcis(),
+            publish,
+            withhold,
+            session_tag,
+            TIER_8,
+        );
+        (
+            current_state.owner.from_arcis(updated),
+            bob_contacts.owner.from_arcis(result),
+            accepted.reveal(),
+            public_count.reveal(),
+        )
+    }
+
+    /// The match circuits' shared body, comparing only the first
+    /// `capacity` slots of each list. `capacity` is a constant at every
+    /// call, so the loops unroll to capacity squared comparisons. Slots
+    /// past it are zero in any list the session accepted: Alice's
+    /// submission was checked against the session's capacity, and Bob's
+    /// list is refused here if its count exceeds it.
+    fn match_within(
+        state: SessionState,
+        bob: ContactList,
+        publish: u8,
+        withhold: u8,
+        session_tag: u128,
+        capacity: usize,
+    ) -> (SessionState, MatchResult, bool, u32) {
         // Both parties must have valid state
         let alice_ready = state.alice_submitted == 1;
         let not_already_matched = state.is_matched == 0;
-        let can_proceed = alice_ready && not_already_matched;
+        let same_session = state.session_tag == session_tag;
+        let same_namespace = bob.namespace == state.namespace;
+        let bob_well_formed = well_formed_within(bob.hashes, bob.count, capacity);
+        let can_proceed =
+            alice_ready && not_already_matched && same_session && same_namespace && bob_well_formed;
 
         // ============================================
         // CORE PSI: Nested loop with fixed bounds
@@ -185,15 +561,15 @@ mod circuits {
         let mut bob_matches = [0u128; 32];
         let mut match_count: u32 = 0;
 
-        for i in 0..32 {
+        for i in 0..capacity {
             let alice_hash = state.alice_hashes[i];
-            let alice_valid = alice_hash != 0;
+            let alice_valid = is_contact(alice_hash);
 
-            for j in 0..32 {
+            for j in 0..capacity {
                 let bob_hash = bob.hashes[j];
-                let bob_valid = bob_hash != 0;
+                let bob_valid = is_contact(bob_hash);
 
-                // A match: both valid, non-zero, equal, and session can proceed
+                // A match: both real contacts, equal, and session can proceed
                 let is_match = alice_valid && bob_valid && (alice_hash == bob_hash) && can_proceed;
 
                 // Mark matched positions (both branches always evaluated)
@@ -215,44 +591,50 @@ mod circuits {
             alice_hashes: state.alice_hashes,
             alice_count: state.alice_count,
             bob_hashes: if can_proceed { bob.hashes } else { state.bob_hashes },
-            bob_count: if can_proceed { bob.count } else { state.bob_count },
+            bob_count: if can_proceed { contact_count_within(bob.hashes, capacity) } else { state.bob_count },
             alice_submitted: state.alice_submitted,
             bob_submitted: if can_proceed { 1 } else { state.bob_submitted },
             is_matched: if can_proceed { 1 } else { state.is_matched },
             result_alice: if can_proceed { alice_matches } else { state.result_alice },
             result_bob: if can_proceed { bob_matches } else { state.result_bob },
             result_count: if can_proceed { match_count } else { state.result_count },
+            namespace: state.namespace,
+            session_tag: state.session_tag,
         };
 
-        // Return Bob's matches encrypted to his key
+        // Return Bob's matches encrypted to his key, unless escrowed
+        let released = withhold == 0;
         let result = MatchResult {
-            matches: bob_matches,
-            match_count,
+            indices: if released { pack_matches(bob_matches) } else { [0u128; MATCH_WORDS] },
+            match_count: if released { match_count } else { 0 },
         };
 
-        (
-            current_state.owner.from_arcis(updated),
-            bob_contacts.owner.from_arcis(result),
-        )
+        // Only the count both parties agreed to publish leaves the MPC
+        let public_count = if publish == 1 && can_proceed { match_count } else { 0 };
+
+        (updated, result, can_proceed, public_count)
     }
 
     /// Reveal Alice's matches.
     /// Called after submit_and_match so Alice can retrieve her intersection.
-    /// Reads stored results from MXE-encrypted state and encrypts to Alice's key.
+    /// Reads stored results from MXE-encrypted state and encrypts page
+    /// `page` (slots `page * PAGE_SIZE ..`) to Alice's key.
     #[instruction]
     pub fn reveal_alice_matches(
         current_state: Enc<Mxe, SessionState>,
         alice_key: Enc<Shared, u8>,
+        page: u8,
+        session_tag: u128,
     ) -> Enc<Shared, MatchResult> {
         let state = current_state.to_arcis();
 
-        // Only return results if matching is complete
-        let matched = state.is_matched == 1;
+        // Only return results if matching is complete in this session
+        let matched = state.is_matched == 1 && state.session_tag == session_tag;
 
-        let result_matches = if matched {
-            state.result_alice
+        let result_indices = if matched {
+            result_page(state.result_alice, page)
         } else {
-            [0u128; 32]
+            [0u128; MATCH_WORDS]
         };
 
         let result_count = if matched {
@@ -262,10 +644,1070 @@ mod circuits {
         };
 
         let result = MatchResult {
-            matches: result_matches,
+            indices: result_indices,
             match_count: result_count,
         };
 
         alice_key.owner.from_arcis(result)
     }
+
+    /// Reveal Bob's matches, a page at a time. Bob receives the first
+    /// page with `submit_and_match`; this re-reads his stored side of the
+    /// intersection so later pages (and lost results) can be fetched.
+    #[instruction]
+    pub fn reveal_bob_matches(
+        current_state: Enc<Mxe, SessionState>,
+        bob_key: Enc<Shared, u8>,
+        page: u8,
+        session_tag: u128,
+    ) -> Enc<Shared, MatchResult> {
+        let state = current_state.to_arcis();
+        let matched = state.is_matched == 1 && state.session_tag == session_tag;
+
+        let result = MatchResult {
+            indices: if matched { result_page(state.result_bob, page) } else { [0u128; MATCH_WORDS] },
+            match_count: if matched { state.result_count } else { 0 },
+        };
+
+        bob_key.owner.from_arcis(result)
+    }
+
+    /// Reveal page `page` of both sides of the intersection in one
+    /// computation: Alice's matches to Alice's key and Bob's to Bob's.
+    /// Equivalent to `reveal_alice_matches` plus `reveal_bob_matches`.
+    #[instruction]
+    pub fn reveal_both(
+        current_state: Enc<Mxe, SessionState>,
+        alice_key: Enc<Shared, u8>,
+        bob_key: Enc<Shared, u8>,
+        page: u8,
+        session_tag: u128,
+    ) -> (Enc<Shared, MatchResult>, Enc<Shared, MatchResult>) {
+        let state = current_state.to_arcis();
+        let matched = state.is_matched == 1 && state.session_tag == session_tag;
+
+        let alice = MatchResult {
+            indices: if matched { result_page(state.result_alice, page) } else { [0u128; MATCH_WORDS] },
+            match_count: if matched { state.result_count } else { 0 },
+        };
+        let bob = MatchResult {
+            indices: if matched { result_page(state.result_bob, page) } else { [0u128; MATCH_WORDS] },
+            match_count: if matched { state.result_count } else { 0 },
+        };
+
+        (alice_key.owner.from_arcis(alice), bob_key.owner.from_arcis(bob))
+    }
+
+    /// Match two lists in one computation, with no session state: each
+    /// party gets its side of the intersection encrypted to its own key.
+    /// Both lists must be well formed and in the same namespace, which
+    /// the plaintext flag reports; otherwise both results are empty.
+    #[instruction]
+    pub fn instant_match(
+        alice_contacts: Enc<Shared, ContactList>,
+        bob_contacts: Enc<Shared, ContactList>,
+    ) -> (Enc<Shared, MatchResult>, Enc<Shared, MatchResult>, bool) {
+        let alice = alice_contacts.to_arcis();
+        let bob = bob_contacts.to_arcis();
+
+        let can_proceed = alice.namespace == bob.namespace
+            && well_formed(alice.hashes, alice.count)
+            && well_formed(bob.hashes, bob.count);
+
+        let mut alice_matches = [0u128; 32];
+        let mut bob_matches = [0u128; 32];
+        let mut match_count: u32 = 0;
+
+        for i in 0..32 {
+            let alice_hash = alice.hashes[i];
+            let alice_valid = is_contact(alice_hash);
+
+            for j in 0..32 {
+                let bob_hash = bob.hashes[j];
+                let is_match = alice_valid && is_contact(bob_hash) && (alice_hash == bob_hash) && can_proceed;
+
+                alice_matches[i] = if is_match { alice_hash } else { alice_matches[i] };
+                bob_matches[j] = if is_match { bob_hash } else { bob_matches[j] };
+                match_count = if is_match { match_count + 1 } else { match_count };
+            }
+        }
+
+        let alice_result = MatchResult {
+            indices: pack_matches(alice_matches),
+            match_count,
+        };
+        let bob_result = MatchResult {
+            indices: pack_matches(bob_matches),
+            match_count,
+        };
+
+        (
+            alice_contacts.owner.from_arcis(alice_result),
+            bob_contacts.owner.from_arcis(bob_result),
+            can_proceed.reveal(),
+        )
+    }
+
+    /// Apply a party's delta in a subscription session and return only the
+    /// mutual contacts discovered since that party's last round.
+    /// `party` is 1 for Alice, 2 for Bob. Each party's stored result is its
+    /// view of the intersection as of its own last round, so the other party
+    /// learns about changes when it next pushes a delta (possibly empty).
+    #[instruction]
+    pub fn push_delta(
+        current_state: Enc<Mxe, SessionState>,
+        delta: Enc<Shared, ContactDelta>,
+        party: u8,
+        session_tag: u128,
+    ) -> (Enc<Mxe, SessionState>, Enc<Shared, MatchResult>) {
+        let state = current_state.to_arcis();
+        let changes = delta.to_arcis();
+
+        let is_alice = party == 1;
+        let can_proceed = state.is_matched == 1
+            && state.session_tag == session_tag
+            && changes.namespace == state.namespace;
+
+        // Pusher's list, the other party's list, and the pusher's last result
+        let mut mine = [0u128; 32];
+        let mut other = [0u128; 32];
+        let mut previous = [0u128; 32];
+        for i in 0..32 {
+            mine[i] = if is_alice { state.alice_hashes[i] } else { state.bob_hashes[i] };
+            other[i] = if is_alice { state.bob_hashes[i] } else { state.alice_hashes[i] };
+            previous[i] = if is_alice { state.result_alice[i] } else { state.result_bob[i] };
+        }
+
+        // Removals: clear every slot holding a removed hash
+        for i in 0..32 {
+            for k in 0..MAX_DELTA {
+                let removed = changes.removed[k];
+                let hit = removed != 0 && mine[i] == removed;
+                mine[i] = if hit { 0 } else { mine[i] };
+            }
+        }
+
+        // Additions: place each new hash in the first free or decoy slot,
+        // so a decoy-padded list still has room
+        for k in 0..MAX_DELTA {
+            let added = changes.added[k];
+            let mut placed = !is_contact(added);
+            for i in 0..32 {
+                placed = placed || mine[i] == added;
+            }
+            for i in 0..32 {
+                let take = !placed && !is_contact(mine[i]);
+                mine[i] = if take { added } else { mine[i] };
+                placed = placed || take;
+            }
+        }
+
+        let mine_count = contact_count(mine);
+
+        // Recompute the pusher's side of the intersection
+        let mut mine_matches = [0u128; 32];
+        let mut match_count: u32 = 0;
+        for i in 0..32 {
+            let mut found = false;
+            for j in 0..32 {
+                found = found || (is_contact(mine[i]) && mine[i] == other[j]);
+            }
+            mine_matches[i] = if found { mine[i] } else { 0 };
+            match_count = if found { match_count + 1 } else { match_count };
+        }
+
+        // Keep only matches missing from the pusher's previous result
+        let mut new_matches = [0u128; 32];
+        let mut new_count: u32 = 0;
+        for i in 0..32 {
+            let mut seen = false;
+            for j in 0..32 {
+                seen = seen || (mine_matches[i] != 0 && mine_matches[i] == previous[j]);
+            }
+            let is_new = mine_matches[i] != 0 && !seen && can_proceed;
+            new_matches[i] = if is_new { mine_matches[i] } else { 0 };
+            new_count = if is_new { new_count + 1 } else { new_count };
+        }
+
+        let alice_changes = can_proceed && is_alice;
+        let bob_changes = can_proceed && !is_alice;
+        let updated = SessionState {
+            alice_hashes: if alice_changes { mine } else { state.alice_hashes },
+            alice_count: if alice_changes { mine_count } else { state.alice_count },
+            bob_hashes: if bob_changes { mine } else { state.bob_hashes },
+            bob_count: if bob_changes { mine_count } else { state.bob_count },
+            alice_submitted: state.alice_submitted,
+            bob_submitted: state.bob_submitted,
+            is_matched: state.is_matched,
+            result_alice: if alice_changes { mine_matches } else { state.result_alice },
+            result_bob: if bob_changes { mine_matches } else { state.result_bob },
+            result_count: if can_proceed { match_count } else { state.result_count },
+            namespace: state.namespace,
+            session_tag: state.session_tag,
+        };
+
+        let result = MatchResult {
+            indices: pack_matches(new_matches),
+            match_count: new_count,
+        };
+
+        (
+            current_state.owner.from_arcis(updated),
+            delta.owner.from_arcis(result),
+        )
+    }
+
+    /// Store a host's list (e.g. event attendees) as a matchmaking pool.
+    /// The list is re-encrypted to the MXE and kept on the pool account.
+    #[instruction]
+    pub fn create_pool(
+        host_contacts: Enc<Shared, ContactList>,
+    ) -> Enc<Mxe, ContactList> {
+        let list = host_contacts.to_arcis();
+        Mxe::get().from_arcis(list)
+    }
+
+    /// Match a user's contacts against a pool.
+    /// Returns the user's contacts that are in the pool; the pool
+    /// itself is never revealed and is left unchanged.
+    #[instruction]
+    pub fn match_against_pool(
+        pool: Enc<Mxe, ContactList>,
+        contacts: Enc<Shared, ContactList>,
+    ) -> Enc<Shared, MatchResult> {
+        let members = pool.to_arcis();
+        let user = contacts.to_arcis();
+
+        // A list from another namespace matches nothing
+        let same_namespace = user.namespace == members.namespace;
+
+        let mut matches = [0u128; 32];
+        let mut match_count: u32 = 0;
+
+        for j in 0..32 {
+            let user_hash = user.hashes[j];
+            let mut found = false;
+            for i in 0..32 {
+                found = found || (is_contact(user_hash) && user_hash == members.hashes[i]);
+            }
+            found = found && same_namespace;
+            matches[j] = if found { user_hash } else { 0 };
+            match_count = if found { match_count + 1 } else { match_count };
+        }
+
+        let result = MatchResult {
+            indices: pack_matches(matches),
+            match_count,
+        };
+
+        contacts.owner.from_arcis(result)
+    }
+
+    /// Load one chunk of a provider's database. The chunk is re-encrypted
+    /// to the MXE and stored in the database account's next slot, but
+    /// only if it is well formed and in the database's `namespace`; the
+    /// plaintext flag reports which.
+    #[instruction]
+    pub fn load_database_chunk(
+        chunk: Enc<Shared, ContactList>,
+        namespace: u8,
+    ) -> (Enc<Mxe, ContactList>, bool) {
+        let list = chunk.to_arcis();
+        let accepted = list.namespace == namespace && well_formed(list.hashes, list.count);
+        (Mxe::get().from_arcis(list), accepted.reveal())
+    }
+
+    /// Query a client's small list against a server database of up to
+    /// `DATABASE_CHUNKS` stored chunks. Only the first `loaded` chunks
+    /// hold data; the rest are never compared. The client learns which
+    /// of its own contacts are in the database, and nothing else about
+    /// the database; the database is left unchanged.
+    #[instruction]
+    pub fn query_psi(
+        chunk_0: Enc<Mxe, ContactList>,
+        chunk_1: Enc<Mxe, ContactList>,
+        chunk_2: Enc<Mxe, ContactList>,
+        chunk_3: Enc<Mxe, ContactList>,
+        chunk_4: Enc<Mxe, ContactList>,
+        chunk_5: Enc<Mxe, ContactList>,
+        chunk_6: Enc<Mxe, ContactList>,
+        chunk_7: Enc<Mxe, ContactList>,
+        query: Enc<Shared, ContactList>,
+        loaded: u8,
+        namespace: u8,
+    ) -> Enc<Shared, MatchResult> {
+        let chunks = [
+            chunk_0.to_arcis(),
+            chunk_1.to_arcis(),
+            chunk_2.to_arcis(),
+            chunk_3.to_arcis(),
+            chunk_4.to_arcis(),
+            chunk_5.to_arcis(),
+            chunk_6.to_arcis(),
+            chunk_7.to_arcis(),
+        ];
+        let user = query.to_arcis();
+
+        // A list from another namespace matches nothing
+        let same_namespace = user.namespace == namespace;
+
+        let mut matches = [0u128; 32];
+        let mut match_count: u32 = 0;
+
+        for j in 0..32 {
+            let user_hash = user.hashes[j];
+            let mut found = false;
+            for k in 0..DATABASE_CHUNKS {
+                let active = (k as u8) < loaded;
+                for i in 0..32 {
+                    found = found || (active && is_contact(user_hash) && user_hash == chunks[k].hashes[i]);
+                }
+            }
+            found = found && same_namespace;
+            matches[j] = if found { user_hash } else { 0 };
+            match_count = if found { match_count + 1 } else { match_count };
+        }
+
+        let result = MatchResult {
+            indices: pack_matches(matches),
+            match_count,
+        };
+
+        query.owner.from_arcis(result)
+    }
+
+    /// Check one identifier against a server database: 1 if it is in
+    /// one of the first `loaded` chunks, else 0. A single comparison per
+    /// stored slot instead of `query_psi`'s 32, for clients that only
+    /// need a yes or no.
+    #[instruction]
+    pub fn check_membership(
+        chunk_0: Enc<Mxe, ContactList>,
+        chunk_1: Enc<Mxe, ContactList>,
+        chunk_2: Enc<Mxe, ContactList>,
+        chunk_3: Enc<Mxe, ContactList>,
+        chunk_4: Enc<Mxe, ContactList>,
+        chunk_5: Enc<Mxe, ContactList>,
+        chunk_6: Enc<Mxe, ContactList>,
+        chunk_7: Enc<Mxe, ContactList>,
+        query: Enc<Shared, MembershipQuery>,
+        loaded: u8,
+        namespace: u8,
+    ) -> Enc<Shared, u8> {
+        let chunks = [
+            chunk_0.to_arcis(),
+            chunk_1.to_arcis(),
+            chunk_2.to_arcis(),
+            chunk_3.to_arcis(),
+            chunk_4.to_arcis(),
+            chunk_5.to_arcis(),
+            chunk_6.to_arcis(),
+            chunk_7.to_arcis(),
+        ];
+        let asked = query.to_arcis();
+
+        let mut found = false;
+        for k in 0..DATABASE_CHUNKS {
+            let active = (k as u8) < loaded;
+            for i in 0..32 {
+                found = found || (active && is_contact(asked.hash) && asked.hash == chunks[k].hashes[i]);
+            }
+        }
+        // A hash from another namespace is never a member
+        found = found && asked.namespace == namespace;
+
+        let member: u8 = if found { 1 } else { 0 };
+        query.owner.from_arcis(member)
+    }
+
+    /// Check a batch of identifiers against a server database. Bit `j`
+    /// of the answer is set iff `j < queries` and `hashes[j]` is in one of
+    /// the first `loaded` chunks. Slots past `queries` are not billed and
+    /// are never answered.
+    #[instruction]
+    pub fn check_membership_batch(
+        chunk_0: Enc<Mxe, ContactList>,
+        chunk_1: Enc<Mxe, ContactList>,
+        chunk_2: Enc<Mxe, ContactList>,
+        chunk_3: Enc<Mxe, ContactList>,
+        chunk_4: Enc<Mxe, ContactList>,
+        chunk_5: Enc<Mxe, ContactList>,
+        chunk_6: Enc<Mxe, ContactList>,
+        chunk_7: Enc<Mxe, ContactList>,
+        batch: Enc<Shared, MembershipBatch>,
+        queries: u8,
+        loaded: u8,
+        namespace: u8,
+    ) -> Enc<Shared, u16> {
+        let chunks = [
+            chunk_0.to_arcis(),
+            chunk_1.to_arcis(),
+            chunk_2.to_arcis(),
+            chunk_3.to_arcis(),
+            chunk_4.to_arcis(),
+            chunk_5.to_arcis(),
+            chunk_6.to_arcis(),
+            chunk_7.to_arcis(),
+        ];
+        let asked = batch.to_arcis();
+        let same_namespace = asked.namespace == namespace;
+
+        let mut mask: u16 = 0;
+        for j in 0..MEMBERSHIP_BATCH_SLOTS {
+            let hash = asked.hashes[j];
+            let mut found = false;
+            for k in 0..DATABASE_CHUNKS {
+                let active = (k as u8) < loaded;
+                for i in 0..32 {
+                    found = found || (active && is_contact(hash) && hash == chunks[k].hashes[i]);
+                }
+            }
+            found = found && same_namespace && (j as u8) < queries;
+            mask = if found { mask | (1u16 << j) } else { mask };
+        }
+
+        batch.owner.from_arcis(mask)
+    }
+
+    /// Store a moderator's blocklist (e.g. known spam numbers).
+    /// Re-encrypted to the MXE and kept on the blocklist account.
+    #[instruction]
+    pub fn create_blocklist(
+        moderator_list: Enc<Shared, ContactList>,
+    ) -> Enc<Mxe, ContactList> {
+        let list = moderator_list.to_arcis();
+        Mxe::get().from_arcis(list)
+    }
+
+    /// Screen a user's contacts against a blocklist.
+    /// The user learns only whether any contacts are listed and how
+    /// many; the blocklist and the matching contacts stay hidden.
+    #[instruction]
+    pub fn check_against_blocklist(
+        blocklist: Enc<Mxe, ContactList>,
+        contacts: Enc<Shared, ContactList>,
+    ) -> Enc<Shared, BlocklistResult> {
+        let listed = blocklist.to_arcis();
+        let user = contacts.to_arcis();
+
+        let same_namespace = user.namespace == listed.namespace;
+
+        let mut blocked_count: u32 = 0;
+        for j in 0..32 {
+            let user_hash = user.hashes[j];
+            let mut found = false;
+            for i in 0..32 {
+                found = found || (is_contact(user_hash) && user_hash == listed.hashes[i]);
+            }
+            found = found && same_namespace;
+            blocked_count = if found { blocked_count + 1 } else { blocked_count };
+        }
+
+        let result = BlocklistResult {
+            blocked: if blocked_count > 0 { 1 } else { 0 },
+            blocked_count,
+        };
+
+        contacts.owner.from_arcis(result)
+    }
+
+    /// Submit Bob's contacts in a score session. Same intersection as
+    /// `submit_and_match`, but Bob only learns the overlap ratios.
+    /// `hide_counts` (plaintext, set by the program from the session)
+    /// scores against full lists so neither size can be solved for.
+    #[instruction]
+    pub fn submit_and_score(
+        current_state: Enc<Mxe, SessionState>,
+        bob_contacts: Enc<Shared, ContactList>,
+        hide_counts: u8,
+        session_tag: u128,
+    ) -> (Enc<Mxe, SessionState>, Enc<Shared, OverlapScore>, bool) {
+        let state = current_state.to_arcis();
+        let bob = bob_contacts.to_arcis();
+
+        let can_proceed = state.alice_submitted == 1
+            && state.is_matched == 0
+            && state.session_tag == session_tag
+            && bob.namespace == state.namespace
+            && well_formed(bob.hashes, bob.count);
+
+        let mut alice_matches = [0u128; 32];
+        let mut bob_matches = [0u128; 32];
+        let mut match_count: u32 = 0;
+
+        for i in 0..32 {
+            for j in 0..32 {
+                let is_match = is_contact(state.alice_hashes[i])
+                    && state.alice_hashes[i] == bob.hashes[j]
+                    && can_proceed;
+                alice_matches[i] = if is_match { state.alice_hashes[i] } else { alice_matches[i] };
+                bob_matches[j] = if is_match { bob.hashes[j] } else { bob_matches[j] };
+                match_count = if is_match { match_count + 1 } else { match_count };
+            }
+        }
+
+        let updated = SessionState {
+            alice_hashes: state.alice_hashes,
+            alice_count: state.alice_count,
+            bob_hashes: if can_proceed { bob.hashes } else { state.bob_hashes },
+            bob_count: if can_proceed { contact_count(bob.hashes) } else { state.bob_count },
+            alice_submitted: state.alice_submitted,
+            bob_submitted: if can_proceed { 1 } else { state.bob_submitted },
+            is_matched: if can_proceed { 1 } else { state.is_matched },
+            result_alice: if can_proceed { alice_matches } else { state.result_alice },
+            result_bob: if can_proceed { bob_matches } else { state.result_bob },
+            result_count: if can_proceed { match_count } else { state.result_count },
+            namespace: state.namespace,
+            session_tag: state.session_tag,
+        };
+
+        let score = overlap_score(state.alice_hashes, bob.hashes, match_count, hide_counts == 1);
+
+        (
+            current_state.owner.from_arcis(updated),
+            bob_contacts.owner.from_arcis(score),
+            can_proceed.reveal(),
+        )
+    }
+
+    /// Reveal the overlap ratios of a scored session to Alice.
+    #[instruction]
+    pub fn reveal_alice_score(
+        current_state: Enc<Mxe, SessionState>,
+        alice_key: Enc<Shared, u8>,
+        hide_counts: u8,
+        session_tag: u128,
+    ) -> Enc<Shared, OverlapScore> {
+        let state = current_state.to_arcis();
+        let matched = state.is_matched == 1 && state.session_tag == session_tag;
+
+        let score = overlap_score(
+            state.alice_hashes,
+            state.bob_hashes,
+            state.result_count,
+            hide_counts == 1,
+        );
+        let result = OverlapScore {
+            overlap_bp: if matched { score.overlap_bp } else { 0 },
+            jaccard_bp: if matched { score.jaccard_bp } else { 0 },
+        };
+
+        alice_key.owner.from_arcis(result)
+    }
+
+    /// Submit Bob's contacts in a friends-of-friends session.
+    /// Stores the list without matching; nothing is computed until
+    /// the connector submits.
+    #[instruction]
+    pub fn submit_contacts_bob(
+        current_state: Enc<Mxe, SessionState>,
+        contacts: Enc<Shared, ContactList>,
+        session_tag: u128,
+    ) -> (Enc<Mxe, SessionState>, Enc<Shared, SubmitConfirmation>, bool) {
+        let state = current_state.to_arcis();
+        let list = contacts.to_arcis();
+
+        let slot_available = state.alice_submitted == 1
+            && state.bob_submitted == 0
+            && state.session_tag == session_tag
+            && list.namespace == state.namespace
+            && well_formed(list.hashes, list.count);
+
+        let updated = SessionState {
+            alice_hashes: state.alice_hashes,
+            alice_count: state.alice_count,
+            bob_hashes: if slot_available { list.hashes } else { state.bob_hashes },
+            bob_count: if slot_available { contact_count(list.hashes) } else { state.bob_count },
+            alice_submitted: state.alice_submitted,
+            bob_submitted: if slot_available { 1 } else { state.bob_submitted },
+            is_matched: state.is_matched,
+            result_alice: state.result_alice,
+            result_bob: state.result_bob,
+            result_count: state.result_count,
+            namespace: state.namespace,
+            session_tag: state.session_tag,
+        };
+
+        let confirmation = SubmitConfirmation {
+            accepted: if slot_available { 1 } else { 0 },
+            party: 2,
+        };
+
+        (
+            current_state.owner.from_arcis(updated),
+            contacts.owner.from_arcis(confirmation),
+            slot_available.reveal(),
+        )
+    }
+
+    /// Submit the connector's contacts in a friends-of-friends session
+    /// and count the second-degree connections Alice and Bob share
+    /// through the connector: contacts of the connector that appear in
+    /// both Alice's and Bob's lists. Only the count is stored (in
+    /// `result_count`); no list is revealed to anyone.
+    /// O(2*32*32) = 2048 comparisons.
+    #[instruction]
+    pub fn submit_connector(
+        current_state: Enc<Mxe, SessionState>,
+        connector_contacts: Enc<Shared, ContactList>,
+        session_tag: u128,
+    ) -> (Enc<Mxe, SessionState>, Enc<Shared, SubmitConfirmation>, bool) {
+        let state = current_state.to_arcis();
+        let connector = connector_contacts.to_arcis();
+
+        let can_proceed = state.bob_submitted == 1
+            && state.is_matched == 0
+            && state.session_tag == session_tag
+            && connector.namespace == state.namespace
+            && well_formed(connector.hashes, connector.count);
+
+        let mut shared_count: u32 = 0;
+        for k in 0..32 {
+            let hash = connector.hashes[k];
+            let mut in_alice = false;
+            let mut in_bob = false;
+            for i in 0..32 {
+                in_alice = in_alice || hash == state.alice_hashes[i];
+                in_bob = in_bob || hash == state.bob_hashes[i];
+            }
+            let shared = is_contact(hash) && in_alice && in_bob;
+            shared_count = if shared { shared_count + 1 } else { shared_count };
+        }
+
+        let updated = SessionState {
+            alice_hashes: state.alice_hashes,
+            alice_count: state.alice_count,
+            bob_hashes: state.bob_hashes,
+            bob_count: state.bob_count,
+            alice_submitted: state.alice_submitted,
+            bob_submitted: state.bob_submitted,
+            is_matched: if can_proceed { 1 } else { state.is_matched },
+            result_alice: state.result_alice,
+            result_bob: state.result_bob,
+            result_count: if can_proceed { shared_count } else { state.result_count },
+            namespace: state.namespace,
+            session_tag: state.session_tag,
+        };
+
+        let confirmation = SubmitConfirmation {
+            accepted: if can_proceed { 1 } else { 0 },
+            party: 3,
+        };
+
+        (
+            current_state.owner.from_arcis(updated),
+            connector_contacts.owner.from_arcis(confirmation),
+            can_proceed.reveal(),
+        )
+    }
+
+    /// Reveal the friends-of-friends count to Alice or Bob.
+    #[instruction]
+    pub fn reveal_second_degree(
+        current_state: Enc<Mxe, SessionState>,
+        party_key: Enc<Shared, u8>,
+        session_tag: u128,
+    ) -> Enc<Shared, u32> {
+        let state = current_state.to_arcis();
+        let matched = state.is_matched == 1 && state.session_tag == session_tag;
+        let count = if matched { state.result_count } else { 0 };
+        party_key.owner.from_arcis(count)
+    }
+
+    /// Submit Bob's follow list in a mutual-follow session and check
+    /// whether Alice and Bob follow each other. Both lists hold folded
+    /// follow-target pubkeys; `alice_id` and `bob_id` are the parties'
+    /// own folded pubkeys, supplied by the program from the session and
+    /// the signer rather than by either client. The result (1 = mutual,
+    /// 0 = not) is stored in `result_count` so either party can reveal
+    /// it later with `reveal_second_degree`.
+    /// O(2*32) = 64 comparisons.
+    #[instruction]
+    pub fn submit_and_check_follow(
+        current_state: Enc<Mxe, SessionState>,
+        bob_follows: Enc<Shared, ContactList>,
+        alice_id: u128,
+        bob_id: u128,
+        session_tag: u128,
+    ) -> (Enc<Mxe, SessionState>, Enc<Shared, u8>, bool) {
+        let state = current_state.to_arcis();
+        let bob = bob_follows.to_arcis();
+
+        let can_proceed = state.alice_submitted == 1
+            && state.is_matched == 0
+            && state.session_tag == session_tag
+            && bob.namespace == state.namespace
+            && well_formed(bob.hashes, bob.count);
+
+        let mut alice_follows_bob = false;
+        let mut bob_follows_alice = false;
+        for i in 0..32 {
+            alice_follows_bob = alice_follows_bob || state.alice_hashes[i] == bob_id;
+            bob_follows_alice = bob_follows_alice || bob.hashes[i] == alice_id;
+        }
+        // Zero is padding, never a folded pubkey
+        let mutual = can_proceed
+            && alice_id != 0
+            && bob_id != 0
+            && alice_follows_bob
+            && bob_follows_alice;
+
+        let updated = SessionState {
+            alice_hashes: state.alice_hashes,
+            alice_count: state.alice_count,
+            bob_hashes: if can_proceed { bob.hashes } else { state.bob_hashes },
+            bob_count: if can_proceed { contact_count(bob.hashes) } else { state.bob_count },
+            alice_submitted: state.alice_submitted,
+            bob_submitted: if can_proceed { 1 } else { state.bob_submitted },
+            is_matched: if can_proceed { 1 } else { state.is_matched },
+            result_alice: state.result_alice,
+            result_bob: state.result_bob,
+            result_count: if can_proceed { if mutual { 1 } else { 0 } } else { state.result_count },
+            namespace: state.namespace,
+            session_tag: state.session_tag,
+        };
+
+        let result: u8 = if mutual { 1 } else { 0 };
+
+        (
+            current_state.owner.from_arcis(updated),
+            bob_follows.owner.from_arcis(result),
+            can_proceed.reveal(),
+        )
+    }
+
+    /// Create an empty contact card registry for identifiers in
+    /// `namespace` (set by the program).
+    #[instruction]
+    pub fn create_card_registry(
+        _input: Enc<Shared, u8>,
+        namespace: u8,
+    ) -> Enc<Mxe, CardRegistry> {
+        let empty = CardRegistry {
+            hashes: [0u128; 16],
+            publishers: [0u128; 16],
+            keys_lo: [0u128; 16],
+            keys_hi: [0u128; 16],
+            count: 0,
+            namespace,
+        };
+        Mxe::get().from_arcis(empty)
+    }
+
+    /// Publish a contact card to a registry. `publisher` is the signer's
+    /// folded pubkey (set by the program). A card whose hash is already
+    /// registered replaces that entry only if the same wallet published
+    /// it; otherwise it takes the first free slot. A card in the wrong
+    /// namespace, with a zero hash, claimed by another wallet, or sent
+    /// to a full registry is dropped and the registry is left unchanged.
+    #[instruction]
+    pub fn publish_contact_card(
+        registry: Enc<Mxe, CardRegistry>,
+        card: Enc<Shared, ContactCard>,
+        publisher: u128,
+    ) -> Enc<Mxe, CardRegistry> {
+        let current = registry.to_arcis();
+        let new_card = card.to_arcis();
+
+        let valid = is_contact(new_card.hash) && new_card.namespace == current.namespace;
+
+        let mut registered = false;
+        let mut owned = false;
+        for i in 0..CARD_SLOTS {
+            let same = current.hashes[i] == new_card.hash;
+            registered = registered || same;
+            owned = owned || (same && current.publishers[i] == publisher);
+        }
+
+        let mut hashes = [0u128; 16];
+        let mut publishers = [0u128; 16];
+        let mut keys_lo = [0u128; 16];
+        let mut keys_hi = [0u128; 16];
+        let mut inserted = false;
+        for i in 0..CARD_SLOTS {
+            let replace = valid && owned && current.hashes[i] == new_card.hash;
+            let insert = valid && !registered && !inserted && current.hashes[i] == 0;
+            let write = replace || insert;
+            hashes[i] = if write { new_card.hash } else { current.hashes[i] };
+            publishers[i] = if write { publisher } else { current.publishers[i] };
+            keys_lo[i] = if write { new_card.key_lo } else { current.keys_lo[i] };
+            keys_hi[i] = if write { new_card.key_hi } else { current.keys_hi[i] };
+            inserted = inserted || insert;
+        }
+
+        let updated = CardRegistry {
+            hashes,
+            publishers,
+            keys_lo,
+            keys_hi,
+            count: if inserted { current.count + 1 } else { current.count },
+            namespace: current.namespace,
+        };
+
+        registry.owner.from_arcis(updated)
+    }
+
+    /// Look a user's contacts up in a card registry: labeled PSI, where
+    /// each registry entry that is also in the user's list releases its
+    /// card. The user learns the keys of the contacts they already
+    /// have; every other entry, and every publisher, stays hidden.
+    #[instruction]
+    pub fn match_contact_cards(
+        registry: Enc<Mxe, CardRegistry>,
+        contacts: Enc<Shared, ContactList>,
+    ) -> Enc<Shared, CardMatches> {
+        let cards = registry.to_arcis();
+        let user = contacts.to_arcis();
+
+        let same_namespace = user.namespace == cards.namespace;
+
+        let mut hashes = [0u128; 16];
+        let mut keys_lo = [0u128; 16];
+        let mut keys_hi = [0u128; 16];
+        let mut card_count: u32 = 0;
+
+        for i in 0..CARD_SLOTS {
+            let entry = cards.hashes[i];
+            let mut found = false;
+            for j in 0..32 {
+                found = found || (is_contact(entry) && entry == user.hashes[j]);
+            }
+            found = found && same_namespace;
+            hashes[i] = if found { entry } else { 0 };
+            keys_lo[i] = if found { cards.keys_lo[i] } else { 0 };
+            keys_hi[i] = if found { cards.keys_hi[i] } else { 0 };
+            card_count = if found { card_count + 1 } else { card_count };
+        }
+
+        let result = CardMatches {
+            hashes,
+            keys_lo,
+            keys_hi,
+            card_count,
+        };
+
+        contacts.owner.from_arcis(result)
+    }
+
+    /// Match one chunk of Bob's committed set against Alice's stored list.
+    /// The program has already checked the chunk against the Merkle root
+    /// Bob committed to; here it is matched like a `submit_and_match`
+    /// list, except that Alice's matches accumulate across chunks and the
+    /// session only counts as matched once `last` (set by the program) is
+    /// 1. `result_count` is the number of Alice's matched slots, so an
+    /// identifier repeated in several chunks is counted once. Bob gets
+    /// this chunk's matches; the plaintext flag reports whether the chunk
+    /// was accepted.
+    #[instruction]
+    pub fn match_set_chunk(
+        current_state: Enc<Mxe, SessionState>,
+        chunk: Enc<Shared, ContactList>,
+        last: u8,
+        session_tag: u128,
+    ) -> (Enc<Mxe, SessionState>, Enc<Shared, MatchResult>, bool) {
+        let state = current_state.to_arcis();
+        let bob = chunk.to_arcis();
+
+        let can_proceed = state.alice_submitted == 1
+            && state.is_matched == 0
+            && state.session_tag == session_tag
+            && bob.namespace == state.namespace
+            && well_formed(bob.hashes, bob.count);
+
+        let mut alice_matches = state.result_alice;
+        let mut bob_matches = [0u128; 32];
+        let mut chunk_matches: u32 = 0;
+
+        for i in 0..32 {
+            let alice_hash = state.alice_hashes[i];
+            for j in 0..32 {
+                let is_match = is_contact(alice_hash) && alice_hash == bob.hashes[j] && can_proceed;
+                alice_matches[i] = if is_match { alice_hash } else { alice_matches[i] };
+                bob_matches[j] = if is_match { alice_hash } else { bob_matches[j] };
+                chunk_matches = if is_match { chunk_matches + 1 } else { chunk_matches };
+            }
+        }
+
+        let mut total: u32 = 0;
+        for i in 0..32 {
+            total = if alice_matches[i] != 0 { total + 1 } else { total };
+        }
+
+        let done = can_proceed && last == 1;
+        let updated = SessionState {
+            alice_hashes: state.alice_hashes,
+            alice_count: state.alice_count,
+            bob_hashes: state.bob_hashes,
+            bob_count: if can_proceed { state.bob_count + contact_count(bob.hashes) } else { state.bob_count },
+            alice_submitted: state.alice_submitted,
+            bob_submitted: if done { 1 } else { state.bob_submitted },
+            is_matched: if done { 1 } else { state.is_matched },
+            result_alice: alice_matches,
+            result_bob: state.result_bob,
+            result_count: total,
+            namespace: state.namespace,
+            session_tag: state.session_tag,
+        };
+
+        let result = MatchResult {
+            indices: pack_matches(bob_matches),
+            match_count: chunk_matches,
+        };
+
+        (
+            current_state.owner.from_arcis(updated),
+            chunk.owner.from_arcis(result),
+            can_proceed.reveal(),
+        )
+    }
+
+    /// Overwrite a finished session's stored state with zeros: both lists,
+    /// both result vectors and the count. Nothing about the old state
+    /// survives, so a purged session reveals nothing to anyone. The
+    /// session tag is kept: a purge discloses nothing even when run on a
+    /// misrouted blob, and the kept tag still fails every later check.
+    /// O(1), no comparisons.
+    #[instruction]
+    pub fn purge_session_state(current_state: Enc<Mxe, SessionState>) -> Enc<Mxe, SessionState> {
+        let state = current_state.to_arcis();
+        let purged = SessionState {
+            alice_hashes: [0u128; 32],
+            alice_count: 0,
+            bob_hashes: [0u128; 32],
+            bob_count: 0,
+            alice_submitted: 0,
+            bob_submitted: 0,
+            is_matched: 0,
+            result_alice: [0u128; 32],
+            result_bob: [0u128; 32],
+            result_count: 0,
+            namespace: 0,
+            session_tag: state.session_tag,
+        };
+        current_state.owner.from_arcis(purged)
+    }
+
+    /// Re-encode a session's stored state from the previous layout into
+    /// the current one, field by field; fields the new layout adds start
+    /// at their zero default. Like a purge it keeps the session tag, so a
+    /// misrouted blob migrates into one that still fails every later
+    /// check. O(1), no comparisons.
+    #[instruction]
+    pub fn migrate_session_state(current_state: Enc<Mxe, LegacySessionState>) -> Enc<Mxe, SessionState> {
+        let old = current_state.to_arcis();
+        let migrated = SessionState {
+            alice_hashes: old.alice_hashes,
+            alice_count: old.alice_count,
+            bob_hashes: old.bob_hashes,
+            bob_count: old.bob_count,
+            alice_submitted: old.alice_submitted,
+            bob_submitted: old.bob_submitted,
+            is_matched: old.is_matched,
+            result_alice: old.result_alice,
+            result_bob: old.result_bob,
+            result_count: old.result_count,
+            namespace: old.namespace,
+            session_tag: old.session_tag,
+        };
+        current_state.owner.from_arcis(migrated)
+    }
+
+    /// Page `page` of a stored result, packed: slots `page * PAGE_SIZE ..`
+    /// of `results`, empty past the end. Each byte names its slot in the
+    /// whole list, not in the page. `page` is plaintext, so the window is
+    /// selected without touching the encrypted values.
+    fn result_page(results: [u128; 32], page: u8) -> [u128; MATCH_WORDS] {
+        let start = (page as usize) * PAGE_SIZE;
+        let mut words = [0u128; MATCH_WORDS];
+        for j in 0..PAGE_SIZE {
+            for i in 0..MAX_CONTACTS {
+                if i == start + j {
+                    let index = ((i + 1) as u128) << (8 * (j % 16));
+                    words[j / 16] = if results[i] != 0 { words[j / 16] + index } else { words[j / 16] };
+                }
+            }
+        }
+        words
+    }
+
+    /// Pack a full result vector into a `MatchResult`'s `indices`: byte
+    /// `i` holds `i + 1` if slot `i` matched, else 0. A byte per slot
+    /// instead of a u128 hash per slot, so a result is 3 ciphertexts
+    /// rather than 33.
+    fn pack_matches(matches: [u128; 32]) -> [u128; MATCH_WORDS] {
+        let mut words = [0u128; MATCH_WORDS];
+        for i in 0..MAX_CONTACTS {
+            let index = ((i + 1) as u128) << (8 * (i % 16));
+            words[i / 16] = if matches[i] != 0 { words[i / 16] + index } else { words[i / 16] };
+        }
+        words
+    }
+
+    /// Whether a submitted list is in canonical form: at most 32 used
+    /// slots, used slots non-zero and strictly increasing (so sorted and
+    /// deduplicated), and every slot past `count` zero.
+    fn well_formed(hashes: [u128; 32], count: u32) -> bool {
+        well_formed_within(hashes, count, MAX_CONTACTS)
+    }
+
+    /// `well_formed` for a list of at most `slots` used slots. Every slot
+    /// is still checked for zero, but only the first `slots` for order,
+    /// the comparisons that dominate the check.
+    fn well_formed_within(hashes: [u128; 32], count: u32, slots: usize) -> bool {
+        let mut ok = count <= slots as u32;
+        for i in 0..32 {
+            let used = (i as u32) < count;
+            ok = ok && (used == (hashes[i] != 0));
+        }
+        for i in 1..slots {
+            let used = (i as u32) < count;
+            ok = ok && (!used || hashes[i - 1] < hashes[i]);
+        }
+        ok
+    }
+
+    /// Whether `hash` is a real identifier: neither padding nor a decoy.
+    fn is_contact(hash: u128) -> bool {
+        hash != 0 && hash < DECOY_FLOOR
+    }
+
+    /// Real identifiers in `hashes`, decoys and padding excluded.
+    fn contact_count(hashes: [u128; 32]) -> u32 {
+        contact_count_within(hashes, MAX_CONTACTS)
+    }
+
+    /// `contact_count` over the first `slots` slots, for a list whose
+    /// later slots are known to be zero.
+    fn contact_count_within(hashes: [u128; 32], slots: usize) -> u32 {
+        let mut count: u32 = 0;
+        for i in 0..slots {
+            count = if is_contact(hashes[i]) { count + 1 } else { count };
+        }
+        count
+    }
+
+    /// Overlap ratios from both lists and their match count.
+    /// List sizes are counted from real slots, not the client-supplied counts.
+    /// `hide_sizes` (plaintext) pads both to 32 instead, so a ratio never
+    /// lets one party work out how many contacts the other submitted.
+    fn overlap_score(alice: [u128; 32], bob: [u128; 32], match_count: u32, hide_sizes: bool) -> OverlapScore {
+        let mut alice_size: u32 = 0;
+        let mut bob_size: u32 = 0;
+        for i in 0..32 {
+            alice_size = if is_contact(alice[i]) || hide_sizes { alice_size + 1 } else { alice_size };
+            bob_size = if is_contact(bob[i]) || hide_sizes { bob_size + 1 } else { bob_size };
+        }
+
+        let smaller = if alice_size < bob_size { alice_size } else { bob_size };
+        let union = alice_size + bob_size - match_count;
+
+        // Guard the divisions; a zero-size side has no overlap
+        let smaller_div = if smaller == 0 { 1 } else { smaller };
+        let union_div = if union == 0 { 1 } else { union };
+
+        OverlapScore {
+            overlap_bp: if smaller == 0 { 0 } else { match_count * 10000 / smaller_div },
+            jaccard_bp: if union == 0 { 0 } else { match_count * 10000 / union_div },
+        }
+    }
 }
@@ -0,0 +1,864 @@
+// Private Contact Discovery - Plaintext Reference Model
+//
+// Plaintext re-implementation of every circuit in `circuits`.
+// Each function mirrors its encrypted counterpart step for step
+// (including the "both branches evaluated" selection logic), so
+// the output of an MPC run can be asserted equal to the output
+// of the same call here.
+//
+// Enabled with the `reference` feature.
+
+// Capacities come from the schema the program encodes arguments with,
+// so the model and the program cannot disagree on them; the circuits
+// spell the same numbers out for Arcis.
+pub use schema::{CAPACITY_TIERS, CARD_SLOTS, DATABASE_CHUNKS, DECOY_FLOOR, MATCH_WORDS, MAX_CONTACTS, MAX_DELTA, MEMBERSHIP_BATCH_SLOTS, PAGE_SIZE};
+
+/// Plaintext mirror of `circuits::ContactList`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ContactList {
+    /// Hashed contact identifiers (u128 each, zero-padded)
+    pub hashes: [u128; MAX_CONTACTS],
+    /// How many slots are actually used (1..=32), rest are 0
+    pub count: u32,
+    /// Identifier namespace the hashes were derived in
+    pub namespace: u8,
+}
+
+/// Plaintext mirror of `circuits::SessionState`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SessionState {
+    pub alice_hashes: [u128; MAX_CONTACTS],
+    pub alice_count: u32,
+    pub bob_hashes: [u128; MAX_CONTACTS],
+    pub bob_count: u32,
+    pub alice_submitted: u8,
+    pub bob_submitted: u8,
+    pub is_matched: u8,
+    pub result_alice: [u128; MAX_CONTACTS],
+    pub result_bob: [u128; MAX_CONTACTS],
+    pub result_count: u32,
+    pub namespace: u8,
+    pub session_tag: u128,
+}
+
+/// Plaintext mirror of `circuits::LegacySessionState`, the same layout
+/// as `SessionState` until the state layout first changes.
+pub type LegacySessionState = SessionState;
+
+/// Plaintext mirror of `circuits::MatchResult`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MatchResult {
+    pub indices: [u128; MATCH_WORDS],
+    pub match_count: u32,
+}
+
+impl MatchResult {
+    /// The recipient's list slots named by `indices`, in page order.
+    pub fn slots(&self) -> Vec<usize> {
+        (0..PAGE_SIZE).filter_map(|j| schema::matched_slot(&self.indices, j)).collect()
+    }
+
+    /// The matched hashes, read from the list the recipient submitted.
+    pub fn hashes(&self, list: &[u128; MAX_CONTACTS]) -> Vec<u128> {
+        self.slots().into_iter().map(|slot| list[slot]).collect()
+    }
+}
+
+/// Plaintext mirror of `circuits::ContactDelta`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ContactDelta {
+    pub added: [u128; MAX_DELTA],
+    pub removed: [u128; MAX_DELTA],
+    pub namespace: u8,
+}
+
+/// Plaintext mirror of `circuits::OverlapScore`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OverlapScore {
+    pub overlap_bp: u32,
+    pub jaccard_bp: u32,
+}
+
+/// Plaintext mirror of `circuits::BlocklistResult`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlocklistResult {
+    pub blocked: u8,
+    pub blocked_count: u32,
+}
+
+/// Plaintext mirror of `circuits::MembershipQuery`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MembershipQuery {
+    pub hash: u128,
+    pub namespace: u8,
+}
+
+/// Plaintext mirror of `circuits::MembershipBatch`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MembershipBatch {
+    pub hashes: [u128; MEMBERSHIP_BATCH_SLOTS],
+    pub namespace: u8,
+}
+
+/// Plaintext mirror of `circuits::SubmitConfirmation`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SubmitConfirmation {
+    pub accepted: u8,
+    pub party: u8,
+}
+
+/// Plaintext mirror of `circuits::ContactCard`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ContactCard {
+    pub hash: u128,
+    pub key_lo: u128,
+    pub key_hi: u128,
+    pub namespace: u8,
+}
+
+/// Plaintext mirror of `circuits::CardRegistry`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CardRegistry {
+    pub hashes: [u128; CARD_SLOTS],
+    pub publishers: [u128; CARD_SLOTS],
+    pub keys_lo: [u128; CARD_SLOTS],
+    pub keys_hi: [u128; CARD_SLOTS],
+    pub count: u32,
+    pub namespace: u8,
+}
+
+/// Plaintext mirror of `circuits::CardMatches`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CardMatches {
+    pub hashes: [u128; CARD_SLOTS],
+    pub keys_lo: [u128; CARD_SLOTS],
+    pub keys_hi: [u128; CARD_SLOTS],
+    pub card_count: u32,
+}
+
+impl ContactList {
+    /// Build a well-formed list from a slice of hashes: sorted,
+    /// deduplicated, and zero-padded, as the clients submit it.
+    /// Panics if more than `MAX_CONTACTS` hashes are given.
+    pub fn from_hashes(hashes: &[u128]) -> Self {
+        assert!(hashes.len() <= MAX_CONTACTS, "too many contacts");
+        let mut sorted: Vec<u128> = hashes.iter().copied().filter(|h| *h != 0).collect();
+        sorted.sort_unstable();
+        sorted.dedup();
+
+        let mut list = ContactList::default();
+        list.hashes[..sorted.len()].copy_from_slice(&sorted);
+        list.count = sorted.len() as u32;
+        list
+    }
+
+    /// The same list padded with `decoys` (each at or above
+    /// `DECOY_FLOOR`), re-sorted as a client submits it. Panics if the
+    /// list overflows.
+    pub fn with_decoys(&self, decoys: &[u128]) -> Self {
+        let used = &self.hashes[..self.count as usize];
+        let mut padded = Self::from_hashes(&[used, decoys].concat());
+        padded.namespace = self.namespace;
+        padded
+    }
+
+    /// The same list, tagged with another namespace.
+    pub fn in_namespace(mut self, namespace: u8) -> Self {
+        self.namespace = namespace;
+        self
+    }
+}
+
+impl ContactDelta {
+    /// Build a zero-padded delta from slices of added and removed hashes.
+    /// Panics if either slice holds more than `MAX_DELTA` hashes.
+    pub fn from_hashes(added: &[u128], removed: &[u128]) -> Self {
+        assert!(
+            added.len() <= MAX_DELTA && removed.len() <= MAX_DELTA,
+            "delta too large"
+        );
+        let mut delta = ContactDelta::default();
+        delta.added[..added.len()].copy_from_slice(added);
+        delta.removed[..removed.len()].copy_from_slice(removed);
+        delta
+    }
+}
+
+/// Reference for `circuits::init_session`.
+/// The encrypted dummy input carries no information and is omitted.
+pub fn init_session(namespace: u8, session_tag: u128) -> SessionState {
+    SessionState {
+        namespace,
+        session_tag,
+        ..SessionState::default()
+    }
+}
+
+/// Reference for `circuits::submit_contacts_alice`, and at capacity 16
+/// or 8 for `submit_contacts_alice_16` and `submit_contacts_alice_8`.
+/// The circuit's revealed flag is `confirmation.accepted == 1`.
+pub fn submit_contacts_alice(
+    state: &SessionState,
+    list: &ContactList,
+    capacity: u8,
+    session_tag: u128,
+) -> (SessionState, SubmitConfirmation) {
+    let slot_available = state.alice_submitted == 0
+        && state.session_tag == session_tag
+        && list.namespace == state.namespace
+        && well_formed(&list.hashes, list.count)
+        && list.count <= capacity as u32;
+
+    let mut updated = *state;
+    if slot_available {
+        updated.alice_hashes = list.hashes;
+        updated.alice_count = contact_count(&list.hashes);
+        updated.alice_submitted = 1;
+    }
+
+    let confirmation = SubmitConfirmation {
+        accepted: if slot_available { 1 } else { 0 },
+        party: 1,
+    };
+
+    (updated, confirmation)
+}
+
+/// Reference for `circuits::init_and_submit_alice`.
+pub fn init_and_submit_alice(
+    list: &ContactList,
+    namespace: u8,
+    capacity: u8,
+    session_tag: u128,
+) -> (SessionState, SubmitConfirmation) {
+    submit_contacts_alice(&init_session(namespace, session_tag), list, capacity, session_tag)
+}
+
+/// Reference for `circuits::submit_and_match`.
+/// Returns the updated state, Bob's match result, whether Bob's list
+/// was accepted, and the published match count (0 unless `publish` is 1).
+/// Bob's result is empty when `withhold` is 1.
+pub fn submit_and_match(
+    state: &SessionState,
+    bob: &ContactList,
+    publish: u8,
+    withhold: u8,
+    session_tag: u128,
+) -> (SessionState, MatchResult, bool, u32) {
+    submit_and_match_tier(state, bob, publish, withhold, MAX_CONTACTS as u8, session_tag)
+}
+
+/// Reference for `circuits::submit_and_match_16` and
+/// `circuits::submit_and_match_8` (and, at capacity 32,
+/// `submit_and_match`): only the first `capacity` slots are compared.
+pub fn submit_and_match_tier(
+    state: &SessionState,
+    bob: &ContactList,
+    publish: u8,
+    withhold: u8,
+    capacity: u8,
+    session_tag: u128,
+) -> (SessionState, MatchResult, bool, u32) {
+    let capacity = capacity as usize;
+    let can_proceed = state.alice_submitted == 1
+        && state.is_matched == 0
+        && state.session_tag == session_tag
+        && bob.namespace == state.namespace
+        && well_formed(&bob.hashes, bob.count)
+        && bob.count <= capacity as u32;
+
+    let mut alice_matches = [0u128; MAX_CONTACTS];
+    let mut bob_matches = [0u128; MAX_CONTACTS];
+    let mut match_count: u32 = 0;
+
+    for (i, &alice_hash) in state.alice_hashes[..capacity].iter().enumerate() {
+        for (j, &bob_hash) in bob.hashes[..capacity].iter().enumerate() {
+            let is_match =
+                is_contact(alice_hash) && is_contact(bob_hash) && alice_hash == bob_hash && can_proceed;
+            if is_match {
+                alice_matches[i] = alice_hash;
+                bob_matches[j] = bob_hash;
+                match_count += 1;
+            }
+        }
+    }
+
+    let mut updated = *state;
+    if can_proceed {
+        updated.bob_hashes = bob.hashes;
+        updated.bob_count = contact_count(&bob.hashes);
+        updated.bob_submitted = 1;
+        updated.is_matched = 1;
+        updated.result_alice = alice_matches;
+        updated.result_bob = bob_matches;
+        updated.result_count = match_count;
+    }
+
+    let result = if withhold == 0 {
+        MatchResult {
+            indices: pack_matches(&bob_matches),
+            match_count,
+        }
+    } else {
+        MatchResult::default()
+    };
+    let public_count = if publish == 1 && can_proceed { match_count } else { 0 };
+
+    (updated, result, can_proceed, public_count)
+}
+
+/// Reference for `circuits::reveal_alice_matches`.
+pub fn reveal_alice_matches(state: &SessionState, page: u8, session_tag: u128) -> MatchResult {
+    if state.is_matched == 1 && state.session_tag == session_tag {
+        MatchResult {
+            indices: result_page(&state.result_alice, page),
+            match_count: state.result_count,
+        }
+    } else {
+        MatchResult::default()
+    }
+}
+
+/// Reference for `circuits::reveal_bob_matches`.
+pub fn reveal_bob_matches(state: &SessionState, page: u8, session_tag: u128) -> MatchResult {
+    if state.is_matched == 1 && state.session_tag == session_tag {
+        MatchResult {
+            indices: result_page(&state.result_bob, page),
+            match_count: state.result_count,
+        }
+    } else {
+        MatchResult::default()
+    }
+}
+
+/// Reference for `circuits::reveal_both`: Alice's page, then Bob's.
+pub fn reveal_both(state: &SessionState, page: u8, session_tag: u128) -> (MatchResult, MatchResult) {
+    (
+        reveal_alice_matches(state, page, session_tag),
+        reveal_bob_matches(state, page, session_tag),
+    )
+}
+
+/// Reference for `circuits::instant_match`: Alice's result, Bob's
+/// result, and whether both lists were accepted.
+pub fn instant_match(alice: &ContactList, bob: &ContactList) -> (MatchResult, MatchResult, bool) {
+    let accepted = alice.namespace == bob.namespace
+        && well_formed(&alice.hashes, alice.count)
+        && well_formed(&bob.hashes, bob.count);
+    let mut alice_matches = [0u128; MAX_CONTACTS];
+    let mut bob_matches = [0u128; MAX_CONTACTS];
+    let mut match_count: u32 = 0;
+    if accepted {
+        for (i, &a) in alice.hashes.iter().enumerate() {
+            for (j, &b) in bob.hashes.iter().enumerate() {
+                if is_contact(a) && a == b {
+                    alice_matches[i] = a;
+                    bob_matches[j] = b;
+                    match_count += 1;
+                }
+            }
+        }
+    }
+    (
+        MatchResult {
+            indices: pack_matches(&alice_matches),
+            match_count,
+        },
+        MatchResult {
+            indices: pack_matches(&bob_matches),
+            match_count,
+        },
+        accepted,
+    )
+}
+
+/// Reference for `circuits::push_delta`.
+/// `party` is 1 for Alice, 2 for Bob. Returns the updated state and the
+/// matches the pusher had not seen in its previous result.
+pub fn push_delta(
+    state: &SessionState,
+    delta: &ContactDelta,
+    party: u8,
+    session_tag: u128,
+) -> (SessionState, MatchResult) {
+    let is_alice = party == 1;
+    let can_proceed = state.is_matched == 1
+        && state.session_tag == session_tag
+        && delta.namespace == state.namespace;
+
+    let (mut mine, other, previous) = if is_alice {
+        (state.alice_hashes, state.bob_hashes, state.result_alice)
+    } else {
+        (state.bob_hashes, state.alice_hashes, state.result_bob)
+    };
+
+    for slot in mine.iter_mut() {
+        if *slot != 0 && delta.removed.contains(slot) {
+            *slot = 0;
+        }
+    }
+
+    // Decoy slots are free for additions
+    for &added in &delta.added {
+        if !is_contact(added) || mine.contains(&added) {
+            continue;
+        }
+        if let Some(free) = mine.iter_mut().find(|h| !is_contact(**h)) {
+            *free = added;
+        }
+    }
+
+    let mine_count = contact_count(&mine);
+
+    let mut mine_matches = [0u128; MAX_CONTACTS];
+    let mut match_count: u32 = 0;
+    for (slot, &h) in mine_matches.iter_mut().zip(&mine) {
+        if is_contact(h) && other.contains(&h) {
+            *slot = h;
+            match_count += 1;
+        }
+    }
+
+    let mut new_matches = [0u128; MAX_CONTACTS];
+    let mut new_count: u32 = 0;
+    for (slot, &h) in new_matches.iter_mut().zip(&mine_matches) {
+        if h != 0 && !previous.contains(&h) && can_proceed {
+            *slot = h;
+            new_count += 1;
+        }
+    }
+
+    let mut updated = *state;
+    if can_proceed {
+        if is_alice {
+            updated.alice_hashes = mine;
+            updated.alice_count = mine_count;
+            updated.result_alice = mine_matches;
+        } else {
+            updated.bob_hashes = mine;
+            updated.bob_count = mine_count;
+            updated.result_bob = mine_matches;
+        }
+        updated.result_count = match_count;
+    }
+
+    let result = MatchResult {
+        indices: pack_matches(&new_matches),
+        match_count: new_count,
+    };
+
+    (updated, result)
+}
+
+/// Reference for `circuits::create_pool`: the pool is the host's list.
+pub fn create_pool(host: &ContactList) -> ContactList {
+    *host
+}
+
+/// Reference for `circuits::match_against_pool`.
+pub fn match_against_pool(pool: &ContactList, user: &ContactList) -> MatchResult {
+    let mut matches = [0u128; MAX_CONTACTS];
+    let mut match_count: u32 = 0;
+    for (slot, &h) in matches.iter_mut().zip(&user.hashes) {
+        if is_contact(h) && pool.hashes.contains(&h) && user.namespace == pool.namespace {
+            *slot = h;
+            match_count += 1;
+        }
+    }
+    MatchResult {
+        indices: pack_matches(&matches),
+        match_count,
+    }
+}
+
+/// Reference for `circuits::load_database_chunk`: the stored chunk and
+/// whether it was accepted.
+pub fn load_database_chunk(chunk: &ContactList, namespace: u8) -> (ContactList, bool) {
+    let accepted = chunk.namespace == namespace && well_formed(&chunk.hashes, chunk.count);
+    (*chunk, accepted)
+}
+
+/// Reference for `circuits::query_psi`. `chunks` holds every slot of
+/// the database; only the first `loaded` are compared.
+pub fn query_psi(
+    chunks: &[ContactList; DATABASE_CHUNKS],
+    query: &ContactList,
+    loaded: u8,
+    namespace: u8,
+) -> MatchResult {
+    let mut matches = [0u128; MAX_CONTACTS];
+    let mut match_count: u32 = 0;
+    let active = &chunks[..(loaded as usize).min(DATABASE_CHUNKS)];
+    for (slot, &h) in matches.iter_mut().zip(&query.hashes) {
+        let found = is_contact(h) && active.iter().any(|chunk| chunk.hashes.contains(&h));
+        if found && query.namespace == namespace {
+            *slot = h;
+            match_count += 1;
+        }
+    }
+    MatchResult {
+        indices: pack_matches(&matches),
+        match_count,
+    }
+}
+
+/// Reference for `circuits::check_membership`: 1 if the hash is in one
+/// of the first `loaded` chunks, else 0.
+pub fn check_membership(
+    chunks: &[ContactList; DATABASE_CHUNKS],
+    query: &MembershipQuery,
+    loaded: u8,
+    namespace: u8,
+) -> u8 {
+    let active = &chunks[..(loaded as usize).min(DATABASE_CHUNKS)];
+    let found = is_contact(query.hash) && active.iter().any(|chunk| chunk.hashes.contains(&query.hash));
+    (found && query.namespace == namespace) as u8
+}
+
+/// Reference for `circuits::check_membership_batch`: bit `j` is set iff
+/// `j < queries` and `hashes[j]` is in one of the first `loaded` chunks.
+pub fn check_membership_batch(
+    chunks: &[ContactList; DATABASE_CHUNKS],
+    batch: &MembershipBatch,
+    queries: u8,
+    loaded: u8,
+    namespace: u8,
+) -> u16 {
+    let mut mask = 0u16;
+    for (j, &hash) in batch.hashes.iter().enumerate().take(queries as usize) {
+        let query = MembershipQuery {
+            hash,
+            namespace: batch.namespace,
+        };
+        if check_membership(chunks, &query, loaded, namespace) == 1 {
+            mask |= 1 << j;
+        }
+    }
+    mask
+}
+
+/// Reference for `circuits::create_blocklist`: the blocklist is the
+/// moderator's list.
+pub fn create_blocklist(moderator: &ContactList) -> ContactList {
+    *moderator
+}
+
+/// Reference for `circuits::check_against_blocklist`.
+pub fn check_against_blocklist(blocklist: &ContactList, user: &ContactList) -> BlocklistResult {
+    let blocked_count = user
+        .hashes
+        .iter()
+        .filter(|h| is_contact(**h) && blocklist.hashes.contains(h) && user.namespace == blocklist.namespace)
+        .count() as u32;
+
+    BlocklistResult {
+        blocked: if blocked_count > 0 { 1 } else { 0 },
+        blocked_count,
+    }
+}
+
+/// Reference for `circuits::create_card_registry`.
+/// The encrypted dummy input carries no information and is omitted.
+pub fn create_card_registry(namespace: u8) -> CardRegistry {
+    CardRegistry {
+        namespace,
+        ..CardRegistry::default()
+    }
+}
+
+/// Reference for `circuits::publish_contact_card`.
+pub fn publish_contact_card(registry: &CardRegistry, card: &ContactCard, publisher: u128) -> CardRegistry {
+    let mut updated = *registry;
+    if !is_contact(card.hash) || card.namespace != registry.namespace {
+        return updated;
+    }
+    let slot = match registry.hashes.iter().position(|h| *h == card.hash) {
+        Some(i) if registry.publishers[i] == publisher => i,
+        Some(_) => return updated,
+        None => match registry.hashes.iter().position(|h| *h == 0) {
+            Some(i) => {
+                updated.count += 1;
+                i
+            }
+            None => return updated,
+        },
+    };
+    updated.hashes[slot] = card.hash;
+    updated.publishers[slot] = publisher;
+    updated.keys_lo[slot] = card.key_lo;
+    updated.keys_hi[slot] = card.key_hi;
+    updated
+}
+
+/// Reference for `circuits::match_contact_cards`.
+pub fn match_contact_cards(registry: &CardRegistry, user: &ContactList) -> CardMatches {
+    let mut result = CardMatches::default();
+    for (i, &h) in registry.hashes.iter().enumerate() {
+        if is_contact(h) && user.hashes.contains(&h) && user.namespace == registry.namespace {
+            result.hashes[i] = h;
+            result.keys_lo[i] = registry.keys_lo[i];
+            result.keys_hi[i] = registry.keys_hi[i];
+            result.card_count += 1;
+        }
+    }
+    result
+}
+
+/// Reference for `circuits::submit_and_score`.
+/// The state update is identical to `submit_and_match`.
+pub fn submit_and_score(
+    state: &SessionState,
+    bob: &ContactList,
+    hide_counts: u8,
+    session_tag: u128,
+) -> (SessionState, OverlapScore, bool) {
+    let (updated, result, accepted, _) = submit_and_match(state, bob, 0, 0, session_tag);
+    let score = overlap_score(&state.alice_hashes, &bob.hashes, result.match_count, hide_counts == 1);
+    (updated, score, accepted)
+}
+
+/// Reference for `circuits::reveal_alice_score`.
+pub fn reveal_alice_score(state: &SessionState, hide_counts: u8, session_tag: u128) -> OverlapScore {
+    if state.is_matched == 1 && state.session_tag == session_tag {
+        overlap_score(&state.alice_hashes, &state.bob_hashes, state.result_count, hide_counts == 1)
+    } else {
+        OverlapScore::default()
+    }
+}
+
+/// Reference for `circuits::submit_contacts_bob`.
+pub fn submit_contacts_bob(
+    state: &SessionState,
+    list: &ContactList,
+    session_tag: u128,
+) -> (SessionState, SubmitConfirmation) {
+    let slot_available = state.alice_submitted == 1
+        && state.bob_submitted == 0
+        && state.session_tag == session_tag
+        && list.namespace == state.namespace
+        && well_formed(&list.hashes, list.count);
+
+    let mut updated = *state;
+    if slot_available {
+        updated.bob_hashes = list.hashes;
+        updated.bob_count = contact_count(&list.hashes);
+        updated.bob_submitted = 1;
+    }
+
+    let confirmation = SubmitConfirmation {
+        accepted: if slot_available { 1 } else { 0 },
+        party: 2,
+    };
+
+    (updated, confirmation)
+}
+
+/// Reference for `circuits::submit_connector`.
+pub fn submit_connector(
+    state: &SessionState,
+    connector: &ContactList,
+    session_tag: u128,
+) -> (SessionState, SubmitConfirmation) {
+    let can_proceed = state.bob_submitted == 1
+        && state.is_matched == 0
+        && state.session_tag == session_tag
+        && connector.namespace == state.namespace
+        && well_formed(&connector.hashes, connector.count);
+
+    let shared_count = connector
+        .hashes
+        .iter()
+        .filter(|h| is_contact(**h) && state.alice_hashes.contains(h) && state.bob_hashes.contains(h))
+        .count() as u32;
+
+    let mut updated = *state;
+    if can_proceed {
+        updated.is_matched = 1;
+        updated.result_count = shared_count;
+    }
+
+    let confirmation = SubmitConfirmation {
+        accepted: if can_proceed { 1 } else { 0 },
+        party: 3,
+    };
+
+    (updated, confirmation)
+}
+
+/// Reference for `circuits::reveal_second_degree`.
+pub fn reveal_second_degree(state: &SessionState, session_tag: u128) -> u32 {
+    if state.is_matched == 1 && state.session_tag == session_tag {
+        state.result_count
+    } else {
+        0
+    }
+}
+
+/// Reference for `circuits::submit_and_check_follow`.
+pub fn submit_and_check_follow(
+    state: &SessionState,
+    bob: &ContactList,
+    alice_id: u128,
+    bob_id: u128,
+    session_tag: u128,
+) -> (SessionState, u8, bool) {
+    let can_proceed = state.alice_submitted == 1
+        && state.is_matched == 0
+        && state.session_tag == session_tag
+        && bob.namespace == state.namespace
+        && well_formed(&bob.hashes, bob.count);
+
+    let mutual = can_proceed
+        && alice_id != 0
+        && bob_id != 0
+        && state.alice_hashes.contains(&bob_id)
+        && bob.hashes.contains(&alice_id);
+
+    let mut updated = *state;
+    if can_proceed {
+        updated.bob_hashes = bob.hashes;
+        updated.bob_count = contact_count(&bob.hashes);
+        updated.bob_submitted = 1;
+        updated.is_matched = 1;
+        updated.result_count = if mutual { 1 } else { 0 };
+    }
+
+    (updated, if mutual { 1 } else { 0 }, can_proceed)
+}
+
+/// Reference for `circuits::match_set_chunk`.
+/// Returns the updated state, Bob's matches in this chunk, and whether
+/// the chunk was accepted.
+pub fn match_set_chunk(
+    state: &SessionState,
+    chunk: &ContactList,
+    last: u8,
+    session_tag: u128,
+) -> (SessionState, MatchResult, bool) {
+    let can_proceed = state.alice_submitted == 1
+        && state.is_matched == 0
+        && state.session_tag == session_tag
+        && chunk.namespace == state.namespace
+        && well_formed(&chunk.hashes, chunk.count);
+
+    let mut updated = *state;
+    let mut bob_matches = [0u128; MAX_CONTACTS];
+    let mut chunk_matches: u32 = 0;
+    if can_proceed {
+        for (i, &alice_hash) in state.alice_hashes.iter().enumerate() {
+            for (j, &bob_hash) in chunk.hashes.iter().enumerate() {
+                if is_contact(alice_hash) && alice_hash == bob_hash {
+                    updated.result_alice[i] = alice_hash;
+                    bob_matches[j] = alice_hash;
+                    chunk_matches += 1;
+                }
+            }
+        }
+        updated.bob_count += contact_count(&chunk.hashes);
+        updated.result_count = updated.result_alice.iter().filter(|h| **h != 0).count() as u32;
+        if last == 1 {
+            updated.bob_submitted = 1;
+            updated.is_matched = 1;
+        }
+    }
+
+    let result = MatchResult {
+        indices: pack_matches(&bob_matches),
+        match_count: chunk_matches,
+    };
+    (updated, result, can_proceed)
+}
+
+/// Reference for `circuits::purge_session_state`. Only the session
+/// tag survives.
+pub fn purge_session_state(state: &SessionState) -> SessionState {
+    SessionState {
+        session_tag: state.session_tag,
+        ..SessionState::default()
+    }
+}
+
+/// Reference for `circuits::migrate_session_state`.
+pub fn migrate_session_state(old: &LegacySessionState) -> SessionState {
+    SessionState {
+        alice_hashes: old.alice_hashes,
+        alice_count: old.alice_count,
+        bob_hashes: old.bob_hashes,
+        bob_count: old.bob_count,
+        alice_submitted: old.alice_submitted,
+        bob_submitted: old.bob_submitted,
+        is_matched: old.is_matched,
+        result_alice: old.result_alice,
+        result_bob: old.result_bob,
+        result_count: old.result_count,
+        namespace: old.namespace,
+        session_tag: old.session_tag,
+    }
+}
+
+/// Reference for the circuits' `result_page` helper.
+pub fn result_page(results: &[u128; MAX_CONTACTS], page: u8) -> [u128; MATCH_WORDS] {
+    let mut words = [0u128; MATCH_WORDS];
+    let start = page as usize * PAGE_SIZE;
+    for j in 0..PAGE_SIZE {
+        if results.get(start + j).is_some_and(|h| *h != 0) {
+            words[j / 16] += ((start + j + 1) as u128) << (8 * (j % 16));
+        }
+    }
+    words
+}
+
+/// Reference for the circuits' `pack_matches` helper.
+pub fn pack_matches(matches: &[u128; MAX_CONTACTS]) -> [u128; MATCH_WORDS] {
+    let mut words = [0u128; MATCH_WORDS];
+    for (i, h) in matches.iter().enumerate() {
+        if *h != 0 {
+            words[i / 16] += ((i + 1) as u128) << (8 * (i % 16));
+        }
+    }
+    words
+}
+
+/// Reference for the circuits' `well_formed` helper.
+pub fn well_formed(hashes: &[u128; MAX_CONTACTS], count: u32) -> bool {
+    let count = count as usize;
+    count <= MAX_CONTACTS
+        && hashes[..count].iter().all(|h| *h != 0)
+        && hashes[..count].windows(2).all(|w| w[0] < w[1])
+        && hashes[count..].iter().all(|h| *h == 0)
+}
+
+/// Reference for the circuits' `is_contact` helper.
+pub fn is_contact(hash: u128) -> bool {
+    hash != 0 && hash < DECOY_FLOOR
+}
+
+/// Reference for the circuits' `contact_count` helper.
+pub fn contact_count(hashes: &[u128; MAX_CONTACTS]) -> u32 {
+    hashes.iter().filter(|h| is_contact(**h)).count() as u32
+}
+
+/// Reference for the circuits' `overlap_score` helper.
+pub fn overlap_score(
+    alice: &[u128; MAX_CONTACTS],
+    bob: &[u128; MAX_CONTACTS],
+    match_count: u32,
+    hide_sizes: bool,
+) -> OverlapScore {
+    let size = |list: &[u128; MAX_CONTACTS]| {
+        if hide_sizes {
+            MAX_CONTACTS as u32
+        } else {
+            contact_count(list)
+        }
+    };
+    let alice_size = size(alice);
+    let bob_size = size(bob);
+    let smaller = alice_size.min(bob_size);
+    let union = alice_size + bob_size - match_count;
+
+    OverlapScore {
+        overlap_bp: (match_count * 10000).checked_div(smaller).unwrap_or(0),
+        jaccard_bp: (match_count * 10000).checked_div(union).unwrap_or(0),
+    }
+}
@@ -0,0 +1,174 @@
+// Golden test vectors for the session circuits.
+//
+// Each file in `tests/golden/` is one scenario: the lists Alice and Bob
+// submit, and what the circuits produce for them. That is the
+// `SessionState` after Bob's `submit_and_match`, Bob's `MatchResult`,
+// and the page `reveal_alice_matches` then returns. Each result also
+// records the slots and hashes a client reads out of it, which the
+// SDK's decryption tests (client/tests/golden.rs) check against.
+//
+// The vectors are generated from the reference model and committed, so
+// a circuit change that alters any of them shows up as a diff. After an
+// intended change, regenerate them and review that diff:
+//
+//     cargo test --features reference --test golden
+//     BLESS_GOLDEN=1 cargo test --features reference --test golden
+
+#![cfg(feature = "reference")]
+
+use std::{fs, path::PathBuf};
+
+use encrypted_ixs::reference::{self, ContactList, MatchResult, SessionState, MATCH_WORDS, MAX_CONTACTS};
+use serde::{Deserialize, Serialize};
+
+/// The scenario matrix, one vector file each.
+const SCENARIOS: [&str; 6] = [
+    "no_overlap",
+    "full_overlap",
+    "partial_overlap",
+    "duplicates",
+    "zero_counts",
+    "alice_not_submitted",
+];
+
+/// Capacity of a session that uses every list slot.
+const FULL: u8 = MAX_CONTACTS as u8;
+
+#[derive(Serialize, Deserialize)]
+struct Vector {
+    description: String,
+    namespace: u8,
+    session_tag: u128,
+    /// `None` when Alice never submits
+    alice: Option<List>,
+    bob: List,
+    expected: Expected,
+}
+
+#[derive(Serialize, Deserialize)]
+struct List {
+    hashes: [u128; MAX_CONTACTS],
+    count: u32,
+}
+
+impl List {
+    fn contact_list(&self, namespace: u8) -> ContactList {
+        ContactList {
+            hashes: self.hashes,
+            count: self.count,
+            namespace,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Expected {
+    /// `None` when Alice never submits
+    alice_accepted: Option<bool>,
+    bob_accepted: bool,
+    state: State,
+    bob_result: Matches,
+    alice_result: Matches,
+}
+
+/// `SessionState`, field for field.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct State {
+    alice_hashes: [u128; MAX_CONTACTS],
+    alice_count: u32,
+    bob_hashes: [u128; MAX_CONTACTS],
+    bob_count: u32,
+    alice_submitted: u8,
+    bob_submitted: u8,
+    is_matched: u8,
+    result_alice: [u128; MAX_CONTACTS],
+    result_bob: [u128; MAX_CONTACTS],
+    result_count: u32,
+    namespace: u8,
+    session_tag: u128,
+}
+
+impl From<SessionState> for State {
+    fn from(state: SessionState) -> Self {
+        State {
+            alice_hashes: state.alice_hashes,
+            alice_count: state.alice_count,
+            bob_hashes: state.bob_hashes,
+            bob_count: state.bob_count,
+            alice_submitted: state.alice_submitted,
+            bob_submitted: state.bob_submitted,
+            is_matched: state.is_matched,
+            result_alice: state.result_alice,
+            result_bob: state.result_bob,
+            result_count: state.result_count,
+            namespace: state.namespace,
+            session_tag: state.session_tag,
+        }
+    }
+}
+
+/// A `MatchResult`, and what the recipient reads from it given its list.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Matches {
+    indices: [u128; MATCH_WORDS],
+    match_count: u32,
+    slots: Vec<usize>,
+    matched_hashes: Vec<u128>,
+}
+
+impl Matches {
+    fn new(result: &MatchResult, list: &[u128; MAX_CONTACTS]) -> Self {
+        let slots = result.slots();
+        Matches {
+            indices: result.indices,
+            match_count: result.match_count,
+            matched_hashes: slots.iter().map(|&slot| list[slot]).collect(),
+            slots,
+        }
+    }
+}
+
+/// Run the vector's session through the reference model.
+fn run(vector: &Vector) -> Expected {
+    let tag = vector.session_tag;
+    let mut state = reference::init_session(vector.namespace, tag);
+    let alice_accepted = vector.alice.as_ref().map(|alice| {
+        let (updated, confirmation) =
+            reference::submit_contacts_alice(&state, &alice.contact_list(vector.namespace), FULL, tag);
+        state = updated;
+        confirmation.accepted == 1
+    });
+
+    let (state, bob_result, bob_accepted, _) =
+        reference::submit_and_match(&state, &vector.bob.contact_list(vector.namespace), 0, 0, tag);
+    let alice_result = reference::reveal_alice_matches(&state, 0, tag);
+
+    let alice_hashes = vector.alice.as_ref().map_or([0; MAX_CONTACTS], |alice| alice.hashes);
+    Expected {
+        alice_accepted,
+        bob_accepted,
+        state: state.into(),
+        bob_result: Matches::new(&bob_result, &vector.bob.hashes),
+        alice_result: Matches::new(&alice_result, &alice_hashes),
+    }
+}
+
+#[test]
+fn golden_vectors_match_the_reference_model() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let bless = std::env::var_os("BLESS_GOLDEN").is_some();
+
+    for scenario in SCENARIOS {
+        let path = dir.join(format!("{scenario}.json"));
+        let raw = fs::read_to_string(&path).unwrap_or_else(|e| panic!("reading {}: {e}", path.display()));
+        let mut vector: Vector = serde_json::from_str(&raw).unwrap_or_else(|e| panic!("parsing {scenario}: {e}"));
+        let actual = run(&vector);
+
+        if bless {
+            vector.expected = actual;
+            fs::write(&path, serde_json::to_string_pretty(&vector).unwrap() + "\n").unwrap();
+        } else {
+            assert_eq!(actual, vector.expected, "{scenario}: {}", vector.description);
+        }
+    }
+}
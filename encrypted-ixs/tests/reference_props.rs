@@ -0,0 +1,875 @@
+// Property-based tests for the PSI circuit logic.
+//
+// The circuits are exercised through the plaintext reference model
+// (`encrypted_ixs::reference`) and checked against an independent,
+// set-based model of the intersection. Run with:
+//
+//     cargo test --features reference
+
+#![cfg(feature = "reference")]
+
+use std::collections::HashSet;
+
+use encrypted_ixs::reference::{
+    self, ContactCard, ContactDelta, ContactList, MembershipBatch, MembershipQuery, SessionState, CAPACITY_TIERS,
+    CARD_SLOTS, DATABASE_CHUNKS, DECOY_FLOOR, MATCH_WORDS, MAX_CONTACTS, MAX_DELTA, MEMBERSHIP_BATCH_SLOTS,
+};
+use proptest::prelude::*;
+
+/// Session tag the tests' sessions are created with.
+const TAG: u128 = 0x5e55_10b7;
+
+/// Capacity of a session that uses every list slot.
+const FULL: u8 = MAX_CONTACTS as u8;
+
+/// Small hash universe so generated lists overlap frequently.
+fn overlapping_hash() -> impl Strategy<Value = u128> {
+    prop_oneof![
+        4 => 1u128..48,
+        1 => any::<u128>().prop_filter("non-zero", |h| *h != 0),
+    ]
+}
+
+/// A contact list with 0..=32 used slots. Duplicate input hashes are
+/// collapsed by `from_hashes`, so the list may hold fewer.
+fn contact_list() -> impl Strategy<Value = ContactList> {
+    prop::collection::vec(overlapping_hash(), 0..=MAX_CONTACTS)
+        .prop_map(|hashes| ContactList::from_hashes(&hashes))
+}
+
+/// A contact list with every slot used, drawn from a small universe so
+/// it still overlaps other lists.
+fn full_contact_list() -> impl Strategy<Value = ContactList> {
+    prop::sample::subsequence((1u128..=64).collect::<Vec<_>>(), MAX_CONTACTS)
+        .prop_map(|hashes| ContactList::from_hashes(&hashes))
+}
+
+/// A deduplicated contact list, as produced by the clients.
+fn dedup_contact_list() -> impl Strategy<Value = ContactList> {
+    prop::collection::hash_set(overlapping_hash(), 0..=MAX_CONTACTS).prop_map(|set| {
+        let hashes: Vec<u128> = set.into_iter().collect();
+        ContactList::from_hashes(&hashes)
+    })
+}
+
+/// A list that breaks canonical form: out of order, duplicated,
+/// zero inside the used slots, non-zero padding, or an oversized count.
+fn malformed_contact_list() -> impl Strategy<Value = ContactList> {
+    (prop::collection::hash_set(overlapping_hash(), 2..MAX_CONTACTS), 0u8..5).prop_map(
+        |(set, defect)| {
+            let hashes: Vec<u128> = set.into_iter().collect();
+            let mut list = ContactList::from_hashes(&hashes);
+            let n = list.count as usize;
+            match defect {
+                0 => list.hashes.swap(0, n - 1),
+                1 => list.hashes[1] = list.hashes[0],
+                2 => list.hashes[0] = 0,
+                3 => list.hashes[n] = list.hashes[n - 1] + 1,
+                _ => list.count = MAX_CONTACTS as u32 + 1,
+            }
+            list
+        },
+    )
+}
+
+/// A delta with up to `MAX_DELTA` additions and removals.
+fn contact_delta() -> impl Strategy<Value = ContactDelta> {
+    (
+        prop::collection::vec(overlapping_hash(), 0..=MAX_DELTA),
+        prop::collection::vec(overlapping_hash(), 0..=MAX_DELTA),
+    )
+        .prop_map(|(added, removed)| ContactDelta::from_hashes(&added, &removed))
+}
+
+fn nonzero(hashes: &[u128]) -> HashSet<u128> {
+    hashes.iter().copied().filter(|h| *h != 0).collect()
+}
+
+/// Number of (i, j) slot pairs holding equal non-zero hashes.
+fn pair_count(a: &[u128], b: &[u128]) -> u32 {
+    let mut count = 0;
+    for x in a {
+        for y in b {
+            if *x != 0 && x == y {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+/// `list` filled to capacity with decoys offset by `seed`, as a client
+/// pads a short list.
+fn padded(list: &ContactList, seed: u64) -> ContactList {
+    let decoys: Vec<u128> = (list.count as u64..MAX_CONTACTS as u64)
+        .map(|i| DECOY_FLOOR + u128::from(seed.wrapping_add(i)))
+        .collect();
+    list.with_decoys(&decoys)
+}
+
+fn matched_session(alice: &ContactList, bob: &ContactList) -> (SessionState, reference::MatchResult) {
+    let state = reference::init_session(0, TAG);
+    let (state, _) = reference::submit_contacts_alice(&state, alice, FULL, TAG);
+    let (state, result, _, _) = reference::submit_and_match(&state, bob, 0, 0, TAG);
+    (state, result)
+}
+
+#[test]
+fn init_session_is_all_zero() {
+    assert_eq!(reference::init_session(0, 0), SessionState::default());
+    assert_eq!(reference::init_session(2, TAG).namespace, 2);
+    assert_eq!(reference::init_session(2, TAG).session_tag, TAG);
+}
+
+#[test]
+fn reveal_before_match_is_empty() {
+    let list = ContactList::from_hashes(&[1, 2, 3]);
+    let state = reference::init_session(0, TAG);
+    let (state, _) = reference::submit_contacts_alice(&state, &list, FULL, TAG);
+    assert_eq!(
+        reference::reveal_alice_matches(&state, 0, TAG),
+        reference::MatchResult::default()
+    );
+}
+
+proptest! {
+    #[test]
+    fn alice_submission_is_accepted_once(first in contact_list(), second in contact_list()) {
+        let state = reference::init_session(0, TAG);
+
+        let (state, conf) = reference::submit_contacts_alice(&state, &first, FULL, TAG);
+        prop_assert_eq!(conf.accepted, 1);
+        prop_assert_eq!(conf.party, 1);
+        prop_assert_eq!(state.alice_hashes, first.hashes);
+        prop_assert_eq!(state.alice_count, first.count);
+        prop_assert_eq!(state.alice_submitted, 1);
+
+        let (again, conf) = reference::submit_contacts_alice(&state, &second, FULL, TAG);
+        prop_assert_eq!(conf.accepted, 0);
+        prop_assert_eq!(again, state);
+    }
+
+    #[test]
+    fn stateless_submission_builds_the_state_it_fills(
+        list in contact_list(),
+        bad in malformed_contact_list(),
+        namespace in 0u8..3,
+    ) {
+        let list = list.in_namespace(namespace);
+        let (state, conf) = reference::init_and_submit_alice(&list, namespace, FULL, TAG);
+        prop_assert_eq!(conf.accepted, 1);
+        prop_assert_eq!(state.namespace, namespace);
+        prop_assert_eq!(state.session_tag, TAG);
+        prop_assert_eq!(state.alice_hashes, list.hashes);
+        prop_assert_eq!(state.alice_submitted, 1);
+
+        // A rejected list still yields the session's empty state
+        let (state, conf) = reference::init_and_submit_alice(&bad.in_namespace(namespace), namespace, FULL, TAG);
+        prop_assert_eq!(conf.accepted, 0);
+        prop_assert_eq!(state, reference::init_session(namespace, TAG));
+    }
+
+    #[test]
+    fn match_without_alice_is_a_no_op(bob in contact_list()) {
+        let state = reference::init_session(0, TAG);
+        let (after, result, accepted, _) = reference::submit_and_match(&state, &bob, 0, 0, TAG);
+        prop_assert_eq!(after, state);
+        prop_assert_eq!(result, reference::MatchResult::default());
+        prop_assert!(!accepted);
+    }
+
+    #[test]
+    fn match_count_equals_slot_pair_count(alice in contact_list(), bob in contact_list()) {
+        let (state, result) = matched_session(&alice, &bob);
+        let expected = pair_count(&alice.hashes, &bob.hashes);
+        prop_assert_eq!(result.match_count, expected);
+        prop_assert_eq!(state.result_count, expected);
+        prop_assert_eq!(state.is_matched, 1);
+        prop_assert_eq!(state.bob_submitted, 1);
+        prop_assert_eq!(state.bob_hashes, bob.hashes);
+        prop_assert_eq!(state.bob_count, bob.count);
+    }
+
+    #[test]
+    fn matched_slots_are_exactly_the_intersection(alice in contact_list(), bob in contact_list()) {
+        let (state, result) = matched_session(&alice, &bob);
+        let alice_set = nonzero(&alice.hashes);
+        let bob_set = nonzero(&bob.hashes);
+
+        for (j, &h) in bob.hashes.iter().enumerate() {
+            let expected = if h != 0 && alice_set.contains(&h) { h } else { 0 };
+            prop_assert_eq!(result.slots().contains(&j), expected != 0);
+            prop_assert_eq!(state.result_bob[j], expected);
+        }
+        for (i, &h) in alice.hashes.iter().enumerate() {
+            let expected = if h != 0 && bob_set.contains(&h) { h } else { 0 };
+            prop_assert_eq!(state.result_alice[i], expected);
+        }
+    }
+
+    #[test]
+    fn dedup_lists_count_the_set_intersection(alice in dedup_contact_list(), bob in dedup_contact_list()) {
+        let (_, result) = matched_session(&alice, &bob);
+        let expected = nonzero(&alice.hashes)
+            .intersection(&nonzero(&bob.hashes))
+            .count() as u32;
+        prop_assert_eq!(result.match_count, expected);
+        prop_assert_eq!(result.slots().len() as u32, expected);
+    }
+
+    #[test]
+    fn both_parties_see_the_same_intersection(alice in contact_list(), bob in full_contact_list()) {
+        let (state, bob_result) = matched_session(&alice, &bob);
+        let alice_result = reference::reveal_alice_matches(&state, 0, TAG);
+        prop_assert_eq!(alice_result.match_count, bob_result.match_count);
+        prop_assert_eq!(
+            nonzero(&alice_result.hashes(&alice.hashes)),
+            nonzero(&bob_result.hashes(&bob.hashes))
+        );
+        prop_assert_eq!(reference::reveal_bob_matches(&state, 0, TAG), bob_result);
+        prop_assert_eq!(reference::reveal_both(&state, 0, TAG), (alice_result, bob_result));
+    }
+
+    #[test]
+    fn instant_match_equals_the_session_flow(alice in contact_list(), bob in contact_list()) {
+        let (state, bob_result) = matched_session(&alice, &bob);
+        let (alice_result, instant_bob, accepted) = reference::instant_match(&alice, &bob);
+        prop_assert!(accepted);
+        prop_assert_eq!(alice_result, reference::reveal_alice_matches(&state, 0, TAG));
+        prop_assert_eq!(instant_bob, bob_result);
+    }
+
+    #[test]
+    fn instant_match_rejects_either_malformed_list(good in dedup_contact_list(), bad in malformed_contact_list()) {
+        let empty = reference::MatchResult::default();
+        prop_assert_eq!(reference::instant_match(&good, &bad), (empty, empty, false));
+        prop_assert_eq!(reference::instant_match(&bad, &good), (empty, empty, false));
+        prop_assert_eq!(reference::instant_match(&good, &good.in_namespace(1)), (empty, empty, false));
+    }
+
+    #[test]
+    fn match_count_is_public_only_when_published(alice in contact_list(), bob in contact_list()) {
+        let state = reference::init_session(0, TAG);
+        let (state, _) = reference::submit_contacts_alice(&state, &alice, FULL, TAG);
+
+        let (private, result, _, public_count) = reference::submit_and_match(&state, &bob, 0, 0, TAG);
+        prop_assert_eq!(public_count, 0);
+        let (published, _, _, public_count) = reference::submit_and_match(&state, &bob, 1, 0, TAG);
+        prop_assert_eq!(public_count, result.match_count);
+
+        // Publishing changes nothing else
+        prop_assert_eq!(private, published);
+    }
+
+    #[test]
+    fn withheld_results_stay_in_state(alice in contact_list(), bob in contact_list()) {
+        let state = reference::init_session(0, TAG);
+        let (state, _) = reference::submit_contacts_alice(&state, &alice, FULL, TAG);
+
+        let (released, result, _, _) = reference::submit_and_match(&state, &bob, 0, 0, TAG);
+        let (withheld, empty, accepted, _) = reference::submit_and_match(&state, &bob, 0, 1, TAG);
+        prop_assert!(accepted);
+        prop_assert_eq!(empty, reference::MatchResult::default());
+
+        // The stored result is the same, so the later reveal recovers it
+        prop_assert_eq!(withheld, released);
+        prop_assert_eq!(reference::reveal_bob_matches(&withheld, 0, TAG), result);
+    }
+
+    #[test]
+    fn pages_past_capacity_are_empty(alice in contact_list(), bob in contact_list(), page in 1u8..=u8::MAX) {
+        let (state, _) = matched_session(&alice, &bob);
+        for result in [reference::reveal_alice_matches(&state, page, TAG), reference::reveal_bob_matches(&state, page, TAG)] {
+            // Still carries the total, so callers know when to stop paging
+            prop_assert_eq!(result.indices, [0u128; MATCH_WORDS]);
+            prop_assert_eq!(result.match_count, state.result_count);
+        }
+    }
+
+    #[test]
+    fn zero_padding_never_matches(count in 0usize..=MAX_CONTACTS) {
+        let alice = ContactList::from_hashes(&vec![7u128; count]);
+        let bob = ContactList::default();
+        let (_, result) = matched_session(&alice, &bob);
+        prop_assert_eq!(result.match_count, 0);
+        prop_assert!(result.slots().is_empty());
+    }
+
+    #[test]
+    fn second_match_is_a_no_op(alice in contact_list(), bob in contact_list(), other in contact_list()) {
+        let (state, _) = matched_session(&alice, &bob);
+        let (after, result, _, _) = reference::submit_and_match(&state, &other, 0, 0, TAG);
+        prop_assert_eq!(after, state);
+        prop_assert_eq!(result.match_count, 0);
+    }
+
+    #[test]
+    fn decoys_never_match_and_are_not_counted(
+        alice in contact_list(),
+        bob in contact_list(),
+        seed in any::<u64>(),
+    ) {
+        // Same seed on both sides: even identical decoys must not match
+        let (alice_padded, bob_padded) = (padded(&alice, seed), padded(&bob, seed));
+        prop_assert_eq!(alice_padded.count as usize, MAX_CONTACTS);
+
+        let (plain_state, plain) = matched_session(&alice, &bob);
+        let (state, result) = matched_session(&alice_padded, &bob_padded);
+        prop_assert_eq!(state.is_matched, 1);
+        prop_assert_eq!(result.match_count, plain.match_count);
+        prop_assert_eq!(nonzero(&result.hashes(&bob_padded.hashes)), nonzero(&plain.hashes(&bob.hashes)));
+        prop_assert_eq!(state.alice_count, alice.count);
+        prop_assert_eq!(state.bob_count, bob.count);
+        prop_assert_eq!(
+            nonzero(&reference::reveal_alice_matches(&state, 0, TAG).hashes(&alice_padded.hashes)),
+            nonzero(&reference::reveal_alice_matches(&plain_state, 0, TAG).hashes(&alice.hashes))
+        );
+    }
+
+    #[test]
+    fn deltas_replace_decoys(alice in contact_list(), added in overlapping_hash(), seed in any::<u64>()) {
+        prop_assume!(added < DECOY_FLOOR && !alice.hashes.contains(&added));
+        let (state, _) = matched_session(&padded(&alice, seed), &alice);
+        let delta = ContactDelta::from_hashes(&[added], &[]);
+        let (state, _) = reference::push_delta(&state, &delta, 1, TAG);
+        let expected = (alice.count as usize + 1).min(MAX_CONTACTS) as u32;
+        prop_assert_eq!(state.alice_count, expected);
+        prop_assert_eq!(state.alice_hashes.contains(&added), alice.count < MAX_CONTACTS as u32);
+    }
+
+    #[test]
+    fn delta_before_match_is_a_no_op(alice in contact_list(), delta in contact_delta(), party in 1u8..=2) {
+        let state = reference::init_session(0, TAG);
+        let (state, _) = reference::submit_contacts_alice(&state, &alice, FULL, TAG);
+        let (after, result) = reference::push_delta(&state, &delta, party, TAG);
+        prop_assert_eq!(after, state);
+        prop_assert_eq!(result, reference::MatchResult::default());
+    }
+
+    #[test]
+    fn delta_reports_only_new_matches(
+        alice in dedup_contact_list(),
+        bob in dedup_contact_list(),
+        delta in contact_delta(),
+        party in 1u8..=2,
+    ) {
+        let (state, _) = matched_session(&alice, &bob);
+        let (before, other) = if party == 1 {
+            (state.result_alice, state.bob_hashes)
+        } else {
+            (state.result_bob, state.alice_hashes)
+        };
+
+        let (after, result) = reference::push_delta(&state, &delta, party, TAG);
+        let mine = if party == 1 { after.alice_hashes } else { after.bob_hashes };
+        let current: HashSet<u128> = nonzero(&mine).intersection(&nonzero(&other)).copied().collect();
+        let expected: HashSet<u128> = current.difference(&nonzero(&before)).copied().collect();
+
+        // Slots name the pusher's list as it stands after the delta
+        prop_assert_eq!(nonzero(&result.hashes(&mine)), expected.clone());
+        prop_assert_eq!(result.match_count, expected.len() as u32);
+        prop_assert_eq!(after.result_count, current.len() as u32);
+    }
+
+    #[test]
+    fn removed_contacts_leave_the_list(
+        alice in dedup_contact_list(),
+        bob in dedup_contact_list(),
+        delta in contact_delta(),
+    ) {
+        let (state, _) = matched_session(&alice, &bob);
+        let (after, _) = reference::push_delta(&state, &delta, 2, TAG);
+        let added = nonzero(&delta.added);
+        for removed in nonzero(&delta.removed) {
+            if !added.contains(&removed) {
+                prop_assert!(!after.bob_hashes.contains(&removed));
+                prop_assert!(!after.result_bob.contains(&removed));
+            }
+        }
+        prop_assert_eq!(after.alice_hashes, state.alice_hashes);
+        prop_assert_eq!(after.result_alice, state.result_alice);
+    }
+
+    #[test]
+    fn repeated_delta_reports_nothing_new(
+        alice in dedup_contact_list(),
+        bob in dedup_contact_list(),
+        delta in contact_delta(),
+        party in 1u8..=2,
+    ) {
+        let (state, _) = matched_session(&alice, &bob);
+        let (state, _) = reference::push_delta(&state, &delta, party, TAG);
+        let (_, result) = reference::push_delta(&state, &ContactDelta::default(), party, TAG);
+        prop_assert_eq!(result.match_count, 0);
+    }
+
+    #[test]
+    fn pool_match_is_the_users_side_of_the_intersection(host in dedup_contact_list(), user in dedup_contact_list()) {
+        let pool = reference::create_pool(&host);
+        let result = reference::match_against_pool(&pool, &user);
+
+        // Same answer Bob would get in a pairwise session with the host
+        let (_, pairwise) = matched_session(&host, &user);
+        prop_assert_eq!(result, pairwise);
+    }
+
+    #[test]
+    fn database_query_is_the_clients_side_of_the_intersection(
+        db in prop::collection::vec(overlapping_hash(), 0..=DATABASE_CHUNKS * MAX_CONTACTS),
+        user in dedup_contact_list(),
+        garbage in full_contact_list(),
+    ) {
+        let mut db = db;
+        db.sort_unstable();
+        db.dedup();
+        let mut chunks = [garbage; DATABASE_CHUNKS];
+        let mut loaded = 0u8;
+        for (slot, hashes) in chunks.iter_mut().zip(db.chunks(MAX_CONTACTS)) {
+            let (stored, accepted) = reference::load_database_chunk(&ContactList::from_hashes(hashes), 0);
+            prop_assert!(accepted);
+            *slot = stored;
+            loaded += 1;
+        }
+
+        // Slots past `loaded` hold whatever the account does and never match
+        let result = reference::query_psi(&chunks, &user, loaded, 0);
+        let whole: HashSet<u128> = db.iter().copied().collect();
+        let expected: HashSet<u128> = nonzero(&user.hashes).intersection(&whole).copied().collect();
+        prop_assert_eq!(nonzero(&result.hashes(&user.hashes)), expected.clone());
+        prop_assert_eq!(result.match_count, expected.len() as u32);
+
+        let other = reference::query_psi(&chunks, &user.in_namespace(1), loaded, 0);
+        prop_assert_eq!(other, reference::MatchResult::default());
+    }
+
+    #[test]
+    fn membership_agrees_with_a_one_contact_query(
+        db in prop::collection::vec(overlapping_hash(), 0..=DATABASE_CHUNKS * MAX_CONTACTS),
+        hash in overlapping_hash(),
+        garbage in full_contact_list(),
+    ) {
+        let mut db = db;
+        db.sort_unstable();
+        db.dedup();
+        let mut chunks = [garbage; DATABASE_CHUNKS];
+        let mut loaded = 0u8;
+        for (slot, hashes) in chunks.iter_mut().zip(db.chunks(MAX_CONTACTS)) {
+            *slot = ContactList::from_hashes(hashes);
+            loaded += 1;
+        }
+
+        let query = MembershipQuery { hash, namespace: 0 };
+        let member = reference::check_membership(&chunks, &query, loaded, 0);
+        prop_assert_eq!(member == 1, db.contains(&hash));
+        let psi = reference::query_psi(&chunks, &ContactList::from_hashes(&[hash]), loaded, 0);
+        prop_assert_eq!(member as u32, psi.match_count);
+
+        let other = MembershipQuery { hash, namespace: 1 };
+        prop_assert_eq!(reference::check_membership(&chunks, &other, loaded, 0), 0);
+    }
+
+    #[test]
+    fn batch_mask_answers_only_the_billed_queries(
+        db in prop::collection::vec(overlapping_hash(), 0..=DATABASE_CHUNKS * MAX_CONTACTS),
+        hashes in prop::array::uniform16(overlapping_hash()),
+        queries in 0..=MEMBERSHIP_BATCH_SLOTS as u8,
+    ) {
+        let mut db = db;
+        db.sort_unstable();
+        db.dedup();
+        let mut chunks = [ContactList::default(); DATABASE_CHUNKS];
+        let mut loaded = 0u8;
+        for (slot, chunk) in chunks.iter_mut().zip(db.chunks(MAX_CONTACTS)) {
+            *slot = ContactList::from_hashes(chunk);
+            loaded += 1;
+        }
+
+        let batch = MembershipBatch { hashes, namespace: 0 };
+        let mask = reference::check_membership_batch(&chunks, &batch, queries, loaded, 0);
+        for (j, hash) in hashes.iter().enumerate() {
+            let expected = j < queries as usize && db.contains(hash);
+            prop_assert_eq!(mask & (1 << j) != 0, expected, "slot {}", j);
+        }
+
+        let other = MembershipBatch { hashes, namespace: 1 };
+        prop_assert_eq!(reference::check_membership_batch(&chunks, &other, queries, loaded, 0), 0);
+    }
+
+    #[test]
+    fn blocklist_check_reveals_only_the_listed_count(
+        moderator in dedup_contact_list(),
+        user in dedup_contact_list(),
+    ) {
+        let blocklist = reference::create_blocklist(&moderator);
+        let result = reference::check_against_blocklist(&blocklist, &user);
+
+        // Same count a pool match would report, without the identities
+        let pool = reference::create_pool(&moderator);
+        let pool_result = reference::match_against_pool(&pool, &user);
+        prop_assert_eq!(result.blocked_count, pool_result.match_count);
+        prop_assert_eq!(result.blocked == 1, result.blocked_count > 0);
+
+        let other = reference::check_against_blocklist(&blocklist, &user.in_namespace(1));
+        prop_assert_eq!(other, reference::BlocklistResult::default());
+    }
+
+    #[test]
+    fn card_lookup_releases_only_cards_of_known_contacts(
+        published in prop::collection::vec(overlapping_hash(), 0..=CARD_SLOTS),
+        user in dedup_contact_list(),
+    ) {
+        let mut registry = reference::create_card_registry(0);
+        for (i, &hash) in published.iter().enumerate() {
+            let card = ContactCard { hash, key_lo: hash ^ 1, key_hi: i as u128, namespace: 0 };
+            registry = reference::publish_contact_card(&registry, &card, hash.wrapping_add(7));
+        }
+        let result = reference::match_contact_cards(&registry, &user);
+
+        // One card per registered contact the user already has
+        let known: HashSet<u128> = nonzero(&published).intersection(&nonzero(&user.hashes)).copied().collect();
+        prop_assert_eq!(result.card_count as usize, known.len());
+        prop_assert_eq!(nonzero(&result.hashes), known);
+        for i in 0..CARD_SLOTS {
+            if result.hashes[i] != 0 {
+                prop_assert_eq!(result.keys_lo[i], result.hashes[i] ^ 1);
+            } else {
+                prop_assert_eq!((result.keys_lo[i], result.keys_hi[i]), (0, 0));
+            }
+        }
+
+        let other = reference::match_contact_cards(&registry, &user.in_namespace(1));
+        prop_assert_eq!(other, reference::CardMatches::default());
+    }
+
+    #[test]
+    fn only_a_cards_publisher_replaces_it(hash in 1u128.., key in any::<u128>()) {
+        let card = ContactCard { hash, key_lo: key, key_hi: 0, namespace: 0 };
+        let registry = reference::publish_contact_card(&reference::create_card_registry(0), &card, 1);
+        prop_assert_eq!(registry.count, 1);
+
+        let rotated = ContactCard { key_lo: key.wrapping_add(1), ..card };
+        let squatted = reference::publish_contact_card(&registry, &rotated, 2);
+        prop_assert_eq!(squatted, registry);
+        let replaced = reference::publish_contact_card(&registry, &rotated, 1);
+        prop_assert_eq!(replaced.count, 1);
+        prop_assert_eq!(replaced.keys_lo[0], key.wrapping_add(1));
+    }
+
+    #[test]
+    fn scores_match_the_set_formulas(alice in dedup_contact_list(), bob in dedup_contact_list()) {
+        let state = reference::init_session(0, TAG);
+        let (state, _) = reference::submit_contacts_alice(&state, &alice, FULL, TAG);
+        let (state, bob_score, accepted) = reference::submit_and_score(&state, &bob, 0, TAG);
+        prop_assert!(accepted);
+
+        let (a, b) = (nonzero(&alice.hashes), nonzero(&bob.hashes));
+        let shared = a.intersection(&b).count() as u32;
+        let smaller = a.len().min(b.len()) as u32;
+        let union = a.union(&b).count() as u32;
+
+        let overlap = (shared * 10000).checked_div(smaller).unwrap_or(0);
+        let jaccard = (shared * 10000).checked_div(union).unwrap_or(0);
+        prop_assert_eq!(bob_score.overlap_bp, overlap);
+        prop_assert_eq!(bob_score.jaccard_bp, jaccard);
+        prop_assert!(bob_score.jaccard_bp <= bob_score.overlap_bp);
+        prop_assert!(bob_score.overlap_bp <= 10000);
+
+        // Alice sees the same score
+        prop_assert_eq!(reference::reveal_alice_score(&state, 0, TAG), bob_score);
+    }
+
+    #[test]
+    fn hidden_sizes_score_only_the_match_count(
+        alice in dedup_contact_list(),
+        bob in dedup_contact_list(),
+        padding in dedup_contact_list(),
+    ) {
+        let (submitted, _) = reference::submit_contacts_alice(&reference::init_session(0, TAG), &alice, FULL, TAG);
+        let (state, bob_score, accepted) = reference::submit_and_score(&submitted, &bob, 1, TAG);
+        prop_assert!(accepted);
+        prop_assert_eq!(reference::reveal_alice_score(&state, 1, TAG), bob_score);
+
+        // Bob adding contacts Alice lacks changes nothing either side sees
+        let (a, b) = (nonzero(&alice.hashes), nonzero(&bob.hashes));
+        let mut grown: Vec<u128> = b.iter().copied().collect();
+        grown.extend(nonzero(&padding.hashes).difference(&a).take(MAX_CONTACTS - b.len()));
+        let grown = ContactList::from_hashes(&grown);
+        let (_, grown_score, _) = reference::submit_and_score(&submitted, &grown, 1, TAG);
+        prop_assert_eq!(grown_score, bob_score);
+
+        let shared = a.intersection(&b).count() as u32;
+        prop_assert_eq!(bob_score.overlap_bp, shared * 10000 / MAX_CONTACTS as u32);
+        prop_assert_eq!(bob_score.jaccard_bp, shared * 10000 / (2 * MAX_CONTACTS as u32 - shared));
+    }
+
+    #[test]
+    fn lists_from_another_namespace_never_match(
+        alice in contact_list(),
+        bob in contact_list(),
+        namespace in 1u8..=3,
+    ) {
+        // Same hashes, different namespace: the circuit must not match them
+        let (state, _) = matched_session(&alice, &bob.in_namespace(namespace));
+        prop_assert_eq!(state.is_matched, 0);
+        prop_assert_eq!(reference::reveal_alice_matches(&state, 0, TAG), reference::MatchResult::default());
+
+        let state = reference::init_session(namespace, TAG);
+        let (after, conf) = reference::submit_contacts_alice(&state, &alice, FULL, TAG);
+        prop_assert_eq!(conf.accepted, 0);
+        prop_assert_eq!(after, state);
+
+        let pool = reference::create_pool(&alice);
+        let result = reference::match_against_pool(&pool, &alice.in_namespace(namespace));
+        prop_assert_eq!(result.match_count, 0);
+    }
+
+    #[test]
+    fn second_degree_count_is_the_three_way_intersection(
+        alice in dedup_contact_list(),
+        bob in dedup_contact_list(),
+        connector in dedup_contact_list(),
+    ) {
+        let state = reference::init_session(0, TAG);
+        let (state, _) = reference::submit_contacts_alice(&state, &alice, FULL, TAG);
+        let (state, conf) = reference::submit_contacts_bob(&state, &bob, TAG);
+        prop_assert_eq!(conf.accepted, 1);
+        prop_assert_eq!(reference::reveal_second_degree(&state, TAG), 0);
+
+        let (state, conf) = reference::submit_connector(&state, &connector, TAG);
+        prop_assert_eq!(conf.accepted, 1);
+        prop_assert_eq!(conf.party, 3);
+
+        let (a, b, c) = (nonzero(&alice.hashes), nonzero(&bob.hashes), nonzero(&connector.hashes));
+        let expected = c.iter().filter(|h| a.contains(h) && b.contains(h)).count() as u32;
+        prop_assert_eq!(reference::reveal_second_degree(&state, TAG), expected);
+
+        // The lists themselves never land in a result slot
+        prop_assert_eq!(state.result_alice, [0u128; MAX_CONTACTS]);
+        prop_assert_eq!(state.result_bob, [0u128; MAX_CONTACTS]);
+
+        // A second connector is rejected
+        let (after, conf) = reference::submit_connector(&state, &alice, TAG);
+        prop_assert_eq!(conf.accepted, 0);
+        prop_assert_eq!(after, state);
+    }
+
+    #[test]
+    fn mutual_follow_needs_both_directions(
+        alice_follows in dedup_contact_list(),
+        bob_follows in dedup_contact_list(),
+        alice_id in overlapping_hash(),
+        bob_id in overlapping_hash(),
+    ) {
+        let state = reference::init_session(3, TAG);
+        let (state, _) = reference::submit_contacts_alice(&state, &alice_follows.in_namespace(3), FULL, TAG);
+        let (state, mutual, accepted) =
+            reference::submit_and_check_follow(&state, &bob_follows.in_namespace(3), alice_id, bob_id, TAG);
+        prop_assert!(accepted);
+
+        let expected = alice_follows.hashes.contains(&bob_id) && bob_follows.hashes.contains(&alice_id);
+        prop_assert_eq!(mutual, expected as u8);
+        prop_assert_eq!(reference::reveal_second_degree(&state, TAG), expected as u32);
+
+        // Nothing about either list lands in a result slot
+        prop_assert_eq!(state.result_alice, [0u128; MAX_CONTACTS]);
+        prop_assert_eq!(state.result_bob, [0u128; MAX_CONTACTS]);
+
+        // A second follow list is rejected
+        let (after, mutual, accepted) =
+            reference::submit_and_check_follow(&state, &alice_follows.in_namespace(3), bob_id, alice_id, TAG);
+        prop_assert!(!accepted);
+        prop_assert_eq!(mutual, 0);
+        prop_assert_eq!(after, state);
+    }
+
+    #[test]
+    fn committed_set_chunks_match_like_one_large_list(
+        alice in dedup_contact_list(),
+        set in prop::collection::vec(overlapping_hash(), 1..=3 * MAX_CONTACTS),
+    ) {
+        let mut set = set;
+        set.sort_unstable();
+        set.dedup();
+        let chunks: Vec<ContactList> = set.chunks(MAX_CONTACTS).map(ContactList::from_hashes).collect();
+
+        let state = reference::init_session(0, TAG);
+        let (mut state, _) = reference::submit_contacts_alice(&state, &alice, FULL, TAG);
+        let mut bob_seen = HashSet::new();
+        for (k, chunk) in chunks.iter().enumerate() {
+            prop_assert_eq!(reference::reveal_alice_matches(&state, 0, TAG), reference::MatchResult::default());
+            let last = (k + 1 == chunks.len()) as u8;
+            let (next, result, accepted) = reference::match_set_chunk(&state, chunk, last, TAG);
+            prop_assert!(accepted);
+            prop_assert_eq!(result.match_count, pair_count(&alice.hashes, &chunk.hashes));
+            bob_seen.extend(result.hashes(&chunk.hashes));
+            state = next;
+        }
+
+        // Both sides end up with the intersection against the whole set
+        let whole: HashSet<u128> = set.iter().copied().collect();
+        let expected: HashSet<u128> = nonzero(&alice.hashes).intersection(&whole).copied().collect();
+        let revealed = reference::reveal_alice_matches(&state, 0, TAG);
+        prop_assert_eq!(nonzero(&revealed.hashes(&alice.hashes)), expected.clone());
+        prop_assert_eq!(revealed.match_count, expected.len() as u32);
+        prop_assert_eq!(bob_seen, expected);
+
+        // A finished set takes no further chunks
+        let (after, _, accepted) = reference::match_set_chunk(&state, &chunks[0], 1, TAG);
+        prop_assert!(!accepted);
+        prop_assert_eq!(after, state);
+    }
+
+    #[test]
+    fn purged_sessions_reveal_nothing(alice in contact_list(), bob in contact_list(), page in 0u8..4) {
+        let (state, _) = matched_session(&alice, &bob);
+        let purged = reference::purge_session_state(&state);
+        prop_assert_eq!(purged, SessionState { session_tag: TAG, ..SessionState::default() });
+        prop_assert_eq!(reference::reveal_alice_matches(&purged, page, TAG), reference::MatchResult::default());
+        prop_assert_eq!(reference::reveal_second_degree(&purged, TAG), 0);
+
+        // A purged session takes no new lists
+        let (after, _, accepted, _) = reference::submit_and_match(&purged, &bob, 0, 0, TAG);
+        prop_assert!(!accepted);
+        prop_assert_eq!(after, purged);
+    }
+
+    #[test]
+    fn migrated_sessions_reveal_the_same(alice in contact_list(), bob in contact_list(), page in 0u8..4) {
+        let (state, _) = matched_session(&alice, &bob);
+        let migrated = reference::migrate_session_state(&state);
+        prop_assert_eq!(migrated.session_tag, TAG);
+        prop_assert_eq!(
+            reference::reveal_alice_matches(&migrated, page, TAG),
+            reference::reveal_alice_matches(&state, page, TAG)
+        );
+        prop_assert_eq!(reference::reveal_second_degree(&migrated, TAG), reference::reveal_second_degree(&state, TAG));
+    }
+
+    #[test]
+    fn misrouted_state_is_inert(
+        alice in dedup_contact_list(),
+        bob in dedup_contact_list(),
+        other_tag in any::<u128>().prop_filter("another session", |t| *t != TAG),
+        page in 0u8..4,
+    ) {
+        // Before matching: no call taking a list accepts it
+        let state = reference::init_session(0, TAG);
+        let (after, conf) = reference::submit_contacts_alice(&state, &alice, FULL, other_tag);
+        prop_assert_eq!(conf.accepted, 0);
+        prop_assert_eq!(after, state);
+
+        let (state, _) = reference::submit_contacts_alice(&state, &alice, FULL, TAG);
+        let (after, result, accepted, public_count) =
+            reference::submit_and_match(&state, &bob, 1, 0, other_tag);
+        prop_assert!(!accepted);
+        prop_assert_eq!(after, state);
+        prop_assert_eq!(result.match_count, 0);
+        prop_assert_eq!(public_count, 0);
+        let (after, _, accepted) = reference::submit_and_score(&state, &bob, 0, other_tag);
+        prop_assert!(!accepted);
+        prop_assert_eq!(after, state);
+        let (after, _, accepted) = reference::match_set_chunk(&state, &bob, 1, other_tag);
+        prop_assert!(!accepted);
+        prop_assert_eq!(after, state);
+        let (after, conf) = reference::submit_contacts_bob(&state, &bob, other_tag);
+        prop_assert_eq!(conf.accepted, 0);
+        prop_assert_eq!(after, state);
+
+        // After matching: nothing is revealed and no delta applies
+        let (state, _) = matched_session(&alice, &bob);
+        let empty = reference::MatchResult::default();
+        prop_assert_eq!(reference::reveal_alice_matches(&state, page, other_tag), empty);
+        prop_assert_eq!(reference::reveal_bob_matches(&state, page, other_tag), empty);
+        prop_assert_eq!(reference::reveal_both(&state, page, other_tag), (empty, empty));
+        prop_assert_eq!(reference::reveal_alice_score(&state, 0, other_tag), reference::OverlapScore::default());
+        prop_assert_eq!(reference::reveal_second_degree(&state, other_tag), 0);
+        let delta = ContactDelta::from_hashes(&[bob.hashes[0]], &[]);
+        let (after, result) = reference::push_delta(&state, &delta, 1, other_tag);
+        prop_assert_eq!(after, state);
+        prop_assert_eq!(result, empty);
+    }
+
+    #[test]
+    fn client_lists_are_well_formed(list in contact_list()) {
+        prop_assert!(reference::well_formed(&list.hashes, list.count));
+    }
+
+    #[test]
+    fn malformed_lists_are_rejected(
+        alice in dedup_contact_list(),
+        bad in malformed_contact_list(),
+        other in dedup_contact_list(),
+    ) {
+        prop_assert!(!reference::well_formed(&bad.hashes, bad.count));
+
+        // Alice's slot stays open for a corrected list
+        let state = reference::init_session(0, TAG);
+        let (after, conf) = reference::submit_contacts_alice(&state, &bad, FULL, TAG);
+        prop_assert_eq!(conf.accepted, 0);
+        prop_assert_eq!(after, state);
+        let (_, conf) = reference::submit_contacts_alice(&after, &alice, FULL, TAG);
+        prop_assert_eq!(conf.accepted, 1);
+
+        // Bob's rejected list is neither matched nor stored
+        let (state, _) = reference::submit_contacts_alice(&state, &alice, FULL, TAG);
+        let (after, result, accepted, public_count) = reference::submit_and_match(&state, &bad, 1, 0, TAG);
+        prop_assert!(!accepted);
+        prop_assert_eq!(public_count, 0);
+        prop_assert_eq!(after, state);
+        prop_assert_eq!(result, reference::MatchResult::default());
+        let (_, _, accepted) = reference::submit_and_score(&state, &bad, 0, TAG);
+        prop_assert!(!accepted);
+        let (after, mutual, accepted) = reference::submit_and_check_follow(&state, &bad, 1, 2, TAG);
+        prop_assert!(!accepted);
+        prop_assert_eq!(mutual, 0);
+        prop_assert_eq!(after, state);
+
+        let (after, conf) = reference::submit_contacts_bob(&state, &bad, TAG);
+        prop_assert_eq!(conf.accepted, 0);
+        prop_assert_eq!(after, state);
+
+        let (state, _) = reference::submit_contacts_bob(&state, &other, TAG);
+        let (after, conf) = reference::submit_connector(&state, &bad, TAG);
+        prop_assert_eq!(conf.accepted, 0);
+        prop_assert_eq!(after, state);
+    }
+
+    #[test]
+    fn tiers_match_like_full_capacity(
+        alice in contact_list(),
+        bob in contact_list(),
+        tier in prop::sample::select(CAPACITY_TIERS.to_vec()),
+    ) {
+        prop_assume!(alice.count <= tier as u32 && bob.count <= tier as u32);
+        let state = reference::init_session(0, TAG);
+        let (state, conf) = reference::submit_contacts_alice(&state, &alice, tier, TAG);
+        prop_assert_eq!(conf.accepted, 1);
+
+        // Slots past the tier are empty, so skipping them loses nothing
+        prop_assert_eq!(
+            reference::submit_and_match_tier(&state, &bob, 1, 0, tier, TAG),
+            reference::submit_and_match(&state, &bob, 1, 0, TAG)
+        );
+    }
+
+    #[test]
+    fn lists_over_the_tier_are_rejected(
+        alice in full_contact_list(),
+        bob in full_contact_list(),
+        small in dedup_contact_list(),
+        tier in prop::sample::select(CAPACITY_TIERS[..CAPACITY_TIERS.len() - 1].to_vec()),
+    ) {
+        let state = reference::init_session(0, TAG);
+        let (after, conf) = reference::submit_contacts_alice(&state, &alice, tier, TAG);
+        prop_assert_eq!(conf.accepted, 0);
+        prop_assert_eq!(after, state);
+
+        let small = ContactList::from_hashes(&small.hashes[..small.count.min(tier as u32) as usize]);
+        let (state, _) = reference::submit_contacts_alice(&state, &small, tier, TAG);
+        let (after, result, accepted, public_count) = reference::submit_and_match_tier(&state, &bob, 1, 0, tier, TAG);
+        prop_assert!(!accepted);
+        prop_assert_eq!(public_count, 0);
+        prop_assert_eq!(after, state);
+        prop_assert_eq!(result, reference::MatchResult::default());
+    }
+}
@@ -4,7 +4,7 @@ import { CSSProperties, FC, useState, useCallback, useEffect } from "react";
 import { useWallet } from "@solana/wallet-adapter-react";
 import { useConnection } from "@solana/wallet-adapter-react";
 import { WalletMultiButton } from "@solana/wallet-adapter-react-ui";
-import { hashContactList, MAX_CONTACTS, resolveMatches } from "@/utils/hash";
+import { hashContactList, MAX_CONTACTS, Namespace, resolveMatches } from "@/utils/hash";
 import {
   fetchAllSessions,
   DisplaySession,
@@ -182,7 +182,7 @@ export default function Home() {
     setStatusMessage("Hashing contacts locally...");
 
     try {
-      const { hashes, count } = await hashContactList(contactLines);
+      const { hashes, count } = await hashContactList(contactLines, Namespace.Phone);
       setHashCount(count);
       setIsHashing(false);
       setStatusMessage("Setting up Arcium encryption...");
@@ -213,8 +213,8 @@ export default function Home() {
 
       setStatusMessage("Encrypting contact hashes with Rescue cipher...");
       const nonce = generateNonce();
-      const { encryptedHashes, encryptedCount } = encryptContactHashes(
-        cipher, hashes, count, nonce
+      const { encryptedHashes, encryptedCount, encryptedNamespace } = encryptContactHashes(
+        cipher, hashes, count, Namespace.Phone, nonce
       );
 
       setIsEncrypting(false);
@@ -262,7 +262,7 @@ export default function Home() {
     setStatusMessage("Hashing contacts locally...");
 
     try {
-      const { hashes, count } = await hashContactList(contactLines);
+      const { hashes, count } = await hashContactList(contactLines, Namespace.Phone);
       setHashCount(count);
       setIsHashing(false);
       setStatusMessage("Setting up Arcium encryption...");
@@ -298,8 +298,8 @@ export default function Home() {
 
       setStatusMessage("Encrypting contacts with Rescue cipher...");
       const nonce = generateNonce();
-      const { encryptedHashes, encryptedCount } = encryptContactHashes(
-        cipher, hashes, count, nonce
+      const { encryptedHashes, encryptedCount, encryptedNamespace } = encryptContactHashes(
+        cipher, hashes, count, Namespace.Phone, nonce
       );
 
       setIsEncrypting(false);
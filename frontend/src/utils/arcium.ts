@@ -78,16 +78,19 @@ export async function createCipher(
 
 /**
  * Encrypt contact hashes for submission to Arcium MPC.
- * Returns 32 encrypted u128 values (each as a 32-byte array) plus encrypted count.
+ * Returns 32 encrypted u128 values (each as a 32-byte array) plus the
+ * encrypted count and namespace.
  */
 export function encryptContactHashes(
   cipher: RescueCipher,
   hashes: bigint[],
   count: number,
+  namespace: number,
   nonce: Uint8Array,
 ): {
   encryptedHashes: number[][];
   encryptedCount: number[];
+  encryptedNamespace: number[];
 } {
   // Encrypt each u128 hash individually
   const encryptedHashes: number[][] = [];
@@ -100,7 +103,10 @@ export function encryptContactHashes(
   const encCountResult = cipher.encrypt([BigInt(count)], nonce);
   const encryptedCount = encCountResult[0];
 
-  return { encryptedHashes, encryptedCount };
+  // Encrypt namespace as u8
+  const encryptedNamespace = cipher.encrypt([BigInt(namespace)], nonce)[0];
+
+  return { encryptedHashes, encryptedCount, encryptedNamespace };
 }
 
 /**
@@ -1,9 +1,22 @@
 // Client-side contact hashing for Private Contact Discovery
 // Contacts are normalized then hashed to u128 (upper 128 bits of SHA-256)
+// within a namespace, so a phone number never hashes like an email.
 // This happens entirely on the client - no plaintext contacts leave the device.
 
 export const MAX_CONTACTS = 32;
 
+/** Identifier namespaces; values match `Namespace` in the program. */
+export enum Namespace {
+  Phone = 0,
+  Email = 1,
+  Username = 2,
+  Wallet = 3,
+  /** Solana name-service names (.sol and other AllDomains TLDs) */
+  SolName = 4,
+}
+
+const NAMESPACE_TAGS = ["phone", "email", "username", "wallet", "solname"] as const;
+
 /**
  * Normalize a contact identifier for consistent hashing:
  * - Trim whitespace
@@ -26,12 +39,35 @@ export function normalizeContact(contact: string): string {
 }
 
 /**
- * Hash a single contact to u128 (upper 128 bits of SHA-256).
+ * Normalize a Solana name: trim, lowercase, drop a leading "@", and
+ * default to the .sol TLD. Must match `normalize_name` in the client.
+ */
+export function normalizeName(name: string): string {
+  let normalized = name.trim().replace(/^@+/, "").trim().toLowerCase();
+  if (normalized.length > 0 && !normalized.includes(".")) {
+    normalized += ".sol";
+  }
+  return normalized;
+}
+
+/** Normalize an identifier the way `namespace` hashes it. */
+export function normalizeIdentifier(contact: string, namespace: Namespace): string {
+  return namespace === Namespace.SolName ? normalizeName(contact) : normalizeContact(contact);
+}
+
+/**
+ * Hash a single contact to u128: upper 128 bits of
+ * SHA-256(namespace_tag + ":" + normalizeIdentifier(contact)).
  * Returns as BigInt for consistency with Arcium u128.
  */
-export async function hashContact(contact: string): Promise<bigint> {
-  const normalized = normalizeContact(contact);
-  const encoded = new TextEncoder().encode(normalized);
+export async function hashContact(
+  contact: string,
+  namespace: Namespace
+): Promise<bigint> {
+  const normalized = normalizeIdentifier(contact, namespace);
+  const encoded = new TextEncoder().encode(
+    `${NAMESPACE_TAGS[namespace]}:${normalized}`
+  );
 
   // SHA-256 via WebCrypto
   const hashBuffer = await crypto.subtle.digest("SHA-256", encoded as unknown as ArrayBuffer);
@@ -51,7 +87,8 @@ export async function hashContact(contact: string): Promise<bigint> {
  * Returns fixed-size array of 32 u128 values.
  */
 export async function hashContactList(
-  contacts: string[]
+  contacts: string[],
+  namespace: Namespace
 ): Promise<{
   hashes: bigint[];
   count: number;
@@ -61,17 +98,22 @@ export async function hashContactList(
   }
 
   // Deduplicate after normalization
-  const unique = [...new Set(contacts.map(normalizeContact))].filter(
+  const unique = [...new Set(contacts.map((c) => normalizeIdentifier(c, namespace)))].filter(
     (c) => c.length > 0
   );
 
   const hashes: bigint[] = new Array(MAX_CONTACTS).fill(BigInt(0));
 
-  for (let i = 0; i < unique.length; i++) {
-    hashes[i] = await hashContact(unique[i]);
+  // The circuits only accept lists sorted ascending with no duplicates
+  const sorted = [
+    ...new Set(await Promise.all(unique.map((c) => hashContact(c, namespace)))),
+  ].sort((a, b) => (a < b ? -1 : a > b ? 1 : 0));
+
+  for (let i = 0; i < sorted.length; i++) {
+    hashes[i] = sorted[i];
   }
 
-  return { hashes, count: unique.length };
+  return { hashes, count: sorted.length };
 }
 
 /**
@@ -104,13 +146,14 @@ export function bytesToU128(bytes: Uint8Array): bigint {
  */
 export async function resolveMatches(
   originalContacts: string[],
-  matchedHashes: bigint[]
+  matchedHashes: bigint[],
+  namespace: Namespace
 ): Promise<string[]> {
   const matches: string[] = [];
   const nonZeroHashes = matchedHashes.filter((h) => h !== BigInt(0));
 
   for (const contact of originalContacts) {
-    const hash = await hashContact(contact);
+    const hash = await hashContact(contact, namespace);
     if (nonZeroHashes.some((h) => h === hash)) {
       matches.push(contact);
     }
@@ -24,6 +24,22 @@ export interface SessionAccount {
   bob: PublicKey;
   status: number;
   bump: number;
+  /** 0 = one-shot, 1 = subscription */
+  mode: number;
+  /** Delta rounds pushed so far (subscription sessions) */
+  round: number;
+  /** 0 = phone, 1 = email, 2 = username, 3 = wallet */
+  namespace: number;
+  /** 0 = SHA-256, 1 = BLAKE3 (this UI hashes with SHA-256 only) */
+  hashScheme: number;
+  /** Whether Alice committed to her submission in advance */
+  committed: boolean;
+  /** Party whose last list the MPC rejected as malformed (0 = none) */
+  rejectedParty: number;
+  /** 0 = private, 1 = Alice opted in to a public count, 2 = both opted in */
+  publicCount: number;
+  /** Match count, or null unless both parties opted in and matching finished */
+  matchCount: number | null;
 }
 
 export interface DisplaySession {
@@ -31,7 +47,14 @@ export interface DisplaySession {
   publicKey: string;
   alice: string;
   bob: string;
-  status: "awaiting_alice" | "awaiting_bob" | "computing" | "matched";
+  status:
+    | "awaiting_alice"
+    | "awaiting_bob"
+    | "computing"
+    | "matched"
+    | "awaiting_connector"
+    | "purged"
+    | "failed";
   statusLabel: string;
   isAlice: boolean;
   isBob: boolean;
@@ -46,11 +69,18 @@ const SESSION_DISCRIMINATOR = Buffer.from([
   0x4a, 0xa7, 0xb6, 0x21, 0xd3, 0xfd, 0xcf, 0x60,
 ]);
 
+// Sessions vary in size across layout versions (see realloc_session);
+// every version holds at least the fields parsed below
+const SESSION_MIN_SIZE = 152;
+
 const STATUS_MAP = [
   "awaiting_alice",
   "awaiting_bob",
   "computing",
   "matched",
+  "awaiting_connector",
+  "purged",
+  "failed",
 ] as const;
 
 const STATUS_LABELS = [
@@ -58,26 +88,58 @@ const STATUS_LABELS = [
   "Waiting for Partner",
   "Computing Matches",
   "Complete",
+  "Waiting for Connector",
+  "Purged",
+  "Failed",
 ];
 
 /**
  * Parse a DiscoverySession account from raw on-chain data.
- * Layout: discriminator(8) + session_id(32) + alice(32) + bob(32) + status(1) + bump(1) = 106 bytes
+ * Zero-copy layout: discriminator(8) + session_id(32) + alice(32) + bob(32)
+ *         + commitment(32) + round(4, LE) + match_count(4, LE) + status(1)
+ *         + bump(1) + mode(1) + namespace(1) + hash_scheme(1) + rejected_party(1)
+ *         + public_count(1) + version(1) + stage_offsets(4 x 8, LE)
+ *         + finalized_slots(4 x 8, LE) + join_deadline(8) + purged_slot(8)
+ *         + callback_consumed(1) + rent_payer(32) + notification_target(32)
+ *         + app_id(32) + set_chunk_count(1) + set_pending_chunk(1)
+ *         + hide_counts(1) + capacity(1) + global_stats_bump(1)
+ *         + app_stats_bump(1) + reveal_window(1) + alice_windows(1)
+ *         + bob_windows(1) + app_hook(1) + connection_consent(1)
+ *         + state_version(1) + reserved(3)
+ *         + stage_fees(4 x 8, LE)
+ *         + refundable_fee(8)
+ *         + memo(32) + set_root(32) + set_chunks_done(8, LE)
+ *         + reveal_after_slot(8, LE) + alice_receipt(32) + bob_receipt(32)
+ *         + last_ping_slot(8, LE) + intended_bob(32) + invite_hash(32)
+ *         = 600 bytes today; older sessions are shorter, but every version
+ *         holds the fields read here (SESSION_MIN_SIZE)
  */
 export function parseSessionAccount(
   data: Buffer
 ): SessionAccount | null {
   try {
-    if (data.length < 106) return null;
+    if (data.length < SESSION_MIN_SIZE) return null;
 
     const accountData = data.slice(8);
     const sessionId = new Uint8Array(accountData.slice(0, 32));
     const alice = new PublicKey(accountData.slice(32, 64));
     const bob = new PublicKey(accountData.slice(64, 96));
-    const status = accountData[96];
-    const bump = accountData[97];
-
-    return { sessionId, alice, bob, status, bump };
+    const committed = accountData.slice(96, 128).some((b) => b !== 0);
+    const round = accountData.readUInt32LE(128);
+    const status = accountData[136];
+    const bump = accountData[137];
+    const mode = accountData[138];
+    const namespace = accountData[139];
+    const hashScheme = accountData[140];
+    const rejectedParty = accountData[141];
+    const publicCount = accountData[142];
+    const matchCount =
+      publicCount === 2 && status === 3 ? accountData.readUInt32LE(132) : null;
+
+    return {
+      sessionId, alice, bob, status, bump, mode, round, namespace, hashScheme, committed,
+      rejectedParty, publicCount, matchCount,
+    };
   } catch {
     return null;
   }
@@ -128,7 +190,6 @@ export async function fetchAllSessions(
   const accounts = await connection.getProgramAccounts(PROGRAM_ID, {
     commitment: "confirmed",
     filters: [
-      { dataSize: 106 },
       { memcmp: { offset: 0, bytes: Buffer.from(SESSION_DISCRIMINATOR).toString("base64"), encoding: "base64" } },
     ],
   });
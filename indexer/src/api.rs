@@ -0,0 +1,65 @@
+// JSON API over the indexed sessions.
+//
+//   GET /sessions/:wallet      sessions where the wallet is Alice or Bob
+//   GET /session/:session_id   one session by hex id
+//   GET /memo/:memo            sessions created with a hex memo hash
+//   GET /health                liveness + last indexed slot
+
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use serde_json::{json, Value};
+
+use crate::db::{Db, SessionRow};
+
+pub type SharedDb = Arc<Mutex<Db>>;
+
+pub fn router(db: SharedDb) -> Router {
+    Router::new()
+        .route("/sessions/:wallet", get(sessions_for_wallet))
+        .route("/session/:session_id", get(session))
+        .route("/memo/:memo", get(sessions_for_memo))
+        .route("/health", get(health))
+        .with_state(db)
+}
+
+fn internal(e: rusqlite::Error) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+async fn sessions_for_wallet(
+    State(db): State<SharedDb>,
+    Path(wallet): Path<String>,
+) -> Result<Json<Vec<SessionRow>>, (StatusCode, String)> {
+    let db = db.lock().unwrap();
+    db.sessions_for_wallet(&wallet).map(Json).map_err(internal)
+}
+
+async fn sessions_for_memo(
+    State(db): State<SharedDb>,
+    Path(memo): Path<String>,
+) -> Result<Json<Vec<SessionRow>>, (StatusCode, String)> {
+    let db = db.lock().unwrap();
+    db.sessions_for_memo(&memo.to_ascii_lowercase()).map(Json).map_err(internal)
+}
+
+async fn session(
+    State(db): State<SharedDb>,
+    Path(session_id): Path<String>,
+) -> Result<Json<SessionRow>, (StatusCode, String)> {
+    let db = db.lock().unwrap();
+    match db.session(&session_id.to_ascii_lowercase()).map_err(internal)? {
+        Some(row) => Ok(Json(row)),
+        None => Err((StatusCode::NOT_FOUND, format!("session {session_id} not indexed"))),
+    }
+}
+
+async fn health(State(db): State<SharedDb>) -> Result<Json<Value>, (StatusCode, String)> {
+    let slot = db.lock().unwrap().cursor().map_err(internal)?;
+    Ok(Json(json!({ "ok": true, "slot": slot })))
+}
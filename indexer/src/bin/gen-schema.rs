@@ -0,0 +1,133 @@
+// Event schema export: reads the Anchor IDL that `anchor build` writes
+// and prints a JSON Schema of every program event, with the IDL's doc
+// strings as descriptions, for consumers of decoded events (the indexer,
+// analytics pipelines) that want a typed contract instead of the IDL.
+//
+//     gen-schema --idl target/idl/private_contact_discovery.json > events.schema.json
+//
+// Each event is a definition under `$defs`, keyed by name, carrying its
+// 8-byte discriminator as `x-discriminator`. Public keys are base58
+// strings; byte arrays stay arrays of integers, as Anchor decodes them.
+
+use std::{fs, path::PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Parser;
+use serde_json::{json, Map, Value};
+
+#[derive(Parser)]
+#[command(name = "gen-schema", about = "Export a JSON Schema of the program's events")]
+struct Args {
+    /// Anchor IDL of the program
+    #[arg(long, default_value = "target/idl/private_contact_discovery.json")]
+    idl: PathBuf,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let idl: Value = serde_json::from_slice(&fs::read(&args.idl).with_context(|| format!("reading {:?}", args.idl))?)?;
+    let schema = event_schema(&idl)?;
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
+
+/// JSON Schema over the IDL's events, one `$defs` entry per event.
+fn event_schema(idl: &Value) -> Result<Value> {
+    let types = idl["types"].as_array().ok_or_else(|| anyhow!("IDL has no types"))?;
+    let find_type = |name: &str| types.iter().find(|ty| ty["name"] == name);
+
+    let mut defs = Map::new();
+    for event in idl["events"].as_array().ok_or_else(|| anyhow!("IDL has no events"))? {
+        let name = event["name"].as_str().ok_or_else(|| anyhow!("event without a name"))?;
+        let ty = find_type(name).ok_or_else(|| anyhow!("event {name} has no type definition"))?;
+        let mut def = struct_schema(ty, &find_type)?;
+        def["x-discriminator"] = event["discriminator"].clone();
+        defs.insert(name.to_string(), def);
+    }
+    let refs: Vec<Value> = defs
+        .keys()
+        .map(|name| json!({ "$ref": format!("#/$defs/{name}") }))
+        .collect();
+
+    Ok(json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": format!("{} events", idl["metadata"]["name"].as_str().unwrap_or("program")),
+        "description": format!("Events emitted by program {}", idl["address"].as_str().unwrap_or("?")),
+        "oneOf": refs,
+        "$defs": defs,
+    }))
+}
+
+/// Schema of an IDL struct type definition.
+fn struct_schema<'a>(ty: &Value, find_type: &impl Fn(&str) -> Option<&'a Value>) -> Result<Value> {
+    let name = ty["name"].as_str().unwrap_or("?");
+    if ty["type"]["kind"] != "struct" {
+        bail!("{name} is not a struct");
+    }
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for field in ty["type"]["fields"].as_array().into_iter().flatten() {
+        let field_name = field["name"]
+            .as_str()
+            .ok_or_else(|| anyhow!("{name} has an unnamed field"))?;
+        let mut schema = type_schema(&field["type"], find_type)?;
+        if let Some(docs) = docs(field) {
+            schema["description"] = docs.into();
+        }
+        properties.insert(field_name.to_string(), schema);
+        required.push(field_name.to_string());
+    }
+    let mut schema = json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    });
+    if let Some(docs) = docs(ty) {
+        schema["description"] = docs.into();
+    }
+    Ok(schema)
+}
+
+/// Schema of an IDL field type.
+fn type_schema<'a>(ty: &Value, find_type: &impl Fn(&str) -> Option<&'a Value>) -> Result<Value> {
+    if let Some(name) = ty.as_str() {
+        return Ok(match name {
+            "bool" => json!({ "type": "boolean" }),
+            "u8" => json!({ "type": "integer", "minimum": 0, "maximum": u8::MAX }),
+            "u16" => json!({ "type": "integer", "minimum": 0, "maximum": u16::MAX }),
+            "u32" => json!({ "type": "integer", "minimum": 0, "maximum": u32::MAX }),
+            "u64" | "u128" => json!({ "type": "integer", "minimum": 0 }),
+            "i8" | "i16" | "i32" | "i64" | "i128" => json!({ "type": "integer" }),
+            "pubkey" => json!({ "type": "string", "contentEncoding": "base58" }),
+            "string" => json!({ "type": "string" }),
+            other => bail!("unsupported IDL type {other}"),
+        });
+    }
+    if let Some([element, len]) = ty["array"].as_array().map(Vec::as_slice) {
+        let len = len.as_u64().ok_or_else(|| anyhow!("array length is not a number"))?;
+        return Ok(json!({
+            "type": "array",
+            "items": type_schema(element, find_type)?,
+            "minItems": len,
+            "maxItems": len,
+        }));
+    }
+    if let Some(element) = ty.get("vec") {
+        return Ok(json!({ "type": "array", "items": type_schema(element, find_type)? }));
+    }
+    if let Some(inner) = ty.get("option") {
+        return Ok(json!({ "anyOf": [type_schema(inner, find_type)?, { "type": "null" }] }));
+    }
+    if let Some(name) = ty["defined"]["name"].as_str() {
+        let defined = find_type(name).ok_or_else(|| anyhow!("type {name} is not defined in the IDL"))?;
+        return struct_schema(defined, find_type);
+    }
+    bail!("unsupported IDL type {ty}")
+}
+
+/// An IDL item's doc lines joined into one description.
+fn docs(item: &Value) -> Option<String> {
+    let lines: Vec<&str> = item["docs"].as_array()?.iter().filter_map(Value::as_str).collect();
+    (!lines.is_empty()).then(|| lines.join(" "))
+}
@@ -0,0 +1,200 @@
+// SQLite persistence for indexed sessions.
+
+use std::path::Path;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+/// A session row as served by the API.
+#[derive(Clone, Debug, Serialize)]
+pub struct SessionRow {
+    pub session_id: String,
+    pub alice: String,
+    pub bob: Option<String>,
+    pub status: String,
+    pub alice_revealed: bool,
+    pub created_slot: u64,
+    pub updated_slot: u64,
+    pub last_signature: String,
+    /// Hex notification target registered for the session, if any
+    pub notification_target: Option<String>,
+    /// Hex memo (hashed business reference) the session was created with, if any
+    pub memo: Option<String>,
+}
+
+pub struct Db {
+    conn: Connection,
+}
+
+impl Db {
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS sessions (
+                session_id     TEXT PRIMARY KEY,
+                alice          TEXT NOT NULL,
+                bob            TEXT,
+                status         TEXT NOT NULL,
+                alice_revealed INTEGER NOT NULL DEFAULT 0,
+                created_slot   INTEGER NOT NULL,
+                updated_slot   INTEGER NOT NULL,
+                last_signature TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS sessions_alice ON sessions (alice);
+            CREATE INDEX IF NOT EXISTS sessions_bob ON sessions (bob);
+            CREATE TABLE IF NOT EXISTS notifications (
+                session_id TEXT PRIMARY KEY,
+                target     TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS memos (
+                session_id TEXT PRIMARY KEY,
+                memo       TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS memos_memo ON memos (memo);
+            CREATE TABLE IF NOT EXISTS cursor (
+                id   INTEGER PRIMARY KEY CHECK (id = 0),
+                slot INTEGER NOT NULL
+            );",
+        )?;
+        Ok(Self { conn })
+    }
+
+    pub fn insert_session(
+        &self,
+        session_id: &str,
+        alice: &str,
+        slot: u64,
+        signature: &str,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO sessions (session_id, alice, status, created_slot, updated_slot, last_signature)
+             VALUES (?1, ?2, 'awaiting_alice', ?3, ?3, ?4)
+             ON CONFLICT (session_id) DO NOTHING",
+            params![session_id, alice, slot, signature],
+        )?;
+        Ok(())
+    }
+
+    /// Move a session to `status`. Older slots never overwrite newer ones.
+    pub fn set_status(
+        &self,
+        session_id: &str,
+        status: &str,
+        slot: u64,
+        signature: &str,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE sessions SET status = ?2, updated_slot = ?3, last_signature = ?4
+             WHERE session_id = ?1 AND updated_slot <= ?3",
+            params![session_id, status, slot, signature],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_bob(&self, session_id: &str, bob: &str) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE sessions SET bob = ?2 WHERE session_id = ?1",
+            params![session_id, bob],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_alice_revealed(&self, session_id: &str) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE sessions SET alice_revealed = 1 WHERE session_id = ?1",
+            params![session_id],
+        )?;
+        Ok(())
+    }
+
+    /// Record `target` as the session's notification target; an empty
+    /// target clears it.
+    pub fn set_notification_target(&self, session_id: &str, target: Option<&str>) -> rusqlite::Result<()> {
+        match target {
+            Some(target) => self.conn.execute(
+                "INSERT INTO notifications (session_id, target) VALUES (?1, ?2)
+                 ON CONFLICT (session_id) DO UPDATE SET target = excluded.target",
+                params![session_id, target],
+            )?,
+            None => self
+                .conn
+                .execute("DELETE FROM notifications WHERE session_id = ?1", params![session_id])?,
+        };
+        Ok(())
+    }
+
+    /// Record the memo a session was created with.
+    pub fn set_memo(&self, session_id: &str, memo: &str) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO memos (session_id, memo) VALUES (?1, ?2) ON CONFLICT (session_id) DO NOTHING",
+            params![session_id, memo],
+        )?;
+        Ok(())
+    }
+
+    pub fn sessions_for_wallet(&self, wallet: &str) -> rusqlite::Result<Vec<SessionRow>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{SELECT_SESSIONS} WHERE alice = ?1 OR bob = ?1 ORDER BY updated_slot DESC"
+        ))?;
+        let rows = stmt.query_map(params![wallet], row_to_session)?;
+        rows.collect()
+    }
+
+    /// Sessions created with `memo`, newest activity first.
+    pub fn sessions_for_memo(&self, memo: &str) -> rusqlite::Result<Vec<SessionRow>> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("{SELECT_SESSIONS} WHERE m.memo = ?1 ORDER BY updated_slot DESC"))?;
+        let rows = stmt.query_map(params![memo], row_to_session)?;
+        rows.collect()
+    }
+
+    pub fn session(&self, session_id: &str) -> rusqlite::Result<Option<SessionRow>> {
+        self.conn
+            .query_row(
+                &format!("{SELECT_SESSIONS} WHERE s.session_id = ?1"),
+                params![session_id],
+                row_to_session,
+            )
+            .optional()
+    }
+
+    /// Highest slot fully processed, for backfill after restart.
+    pub fn cursor(&self) -> rusqlite::Result<Option<u64>> {
+        self.conn
+            .query_row("SELECT slot FROM cursor WHERE id = 0", [], |r| r.get(0))
+            .optional()
+    }
+
+    pub fn set_cursor(&self, slot: u64) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO cursor (id, slot) VALUES (0, ?1)
+             ON CONFLICT (id) DO UPDATE SET slot = MAX(slot, excluded.slot)",
+            params![slot],
+        )?;
+        Ok(())
+    }
+}
+
+/// Columns `row_to_session` reads, with each session's notification
+/// target and memo joined in.
+const SELECT_SESSIONS: &str =
+    "SELECT s.session_id, alice, bob, status, alice_revealed, created_slot, updated_slot, last_signature, n.target, m.memo
+     FROM sessions s
+     LEFT JOIN notifications n ON n.session_id = s.session_id
+     LEFT JOIN memos m ON m.session_id = s.session_id";
+
+fn row_to_session(row: &rusqlite::Row<'_>) -> rusqlite::Result<SessionRow> {
+    Ok(SessionRow {
+        session_id: row.get(0)?,
+        alice: row.get(1)?,
+        bob: row.get(2)?,
+        status: row.get(3)?,
+        alice_revealed: row.get(4)?,
+        created_slot: row.get(5)?,
+        updated_slot: row.get(6)?,
+        last_signature: row.get(7)?,
+        notification_target: row.get(8)?,
+        memo: row.get(9)?,
+    })
+}
@@ -0,0 +1,273 @@
+// Private Contact Discovery - Session Indexer
+//
+// Subscribes to the program's logs, decodes its events, and keeps a
+// SQLite table of sessions and their statuses, served as JSON so apps
+// can discover a wallet's sessions without `getProgramAccounts` scans.
+// Sessions that reach `matched` or `failed` can push a notification to
+// each party through the notifiers in `notify`.
+//
+//     pcd-indexer --rpc https://api.devnet.solana.com \
+//                 --ws wss://api.devnet.solana.com \
+//                 --db sessions.db --listen 0.0.0.0:8080 \
+//                 --webhook https://app.example/pcd-hook
+
+mod api;
+mod db;
+mod notify;
+
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use futures::StreamExt;
+use pcd_client::{accounts::session_pda, EventNotification, SessionEvent, SessionEventStream};
+use private_contact_discovery::{DiscoverySession, Status, NO_MEMO};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+
+use api::SharedDb;
+use notify::{Notification, Notifier, NOTIFY_STATUSES};
+
+#[derive(Parser)]
+#[command(name = "pcd-indexer", about = "Index Private Contact Discovery sessions")]
+struct Args {
+    #[arg(long, default_value = "https://api.devnet.solana.com")]
+    rpc: String,
+    #[arg(long, default_value = "wss://api.devnet.solana.com")]
+    ws: String,
+    #[arg(long, default_value = "sessions.db")]
+    db: PathBuf,
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen: String,
+    /// POST finished sessions as JSON to this URL (repeatable)
+    #[arg(long)]
+    webhook: Vec<String>,
+    /// Send Dialect alerts for this dapp; the API key is read from
+    /// PCD_DIALECT_API_KEY
+    #[arg(long)]
+    dialect_app: Option<String>,
+    #[arg(long, default_value = "https://alerts-api.dialect.to")]
+    dialect_api: String,
+    /// Send FCM messages in this Firebase project, with access tokens
+    /// from the metadata server's service account
+    #[arg(long)]
+    fcm_project: Option<String>,
+    /// Read FCM access tokens from this file instead, which must be
+    /// rewritten with a fresh token at least hourly
+    #[arg(long, requires = "fcm_project")]
+    fcm_token_file: Option<PathBuf>,
+}
+
+/// The notifiers the flags ask for.
+fn notifiers(args: &Args) -> Result<Vec<Arc<dyn Notifier>>> {
+    let http = reqwest::Client::new();
+    let mut notifiers: Vec<Arc<dyn Notifier>> = Vec::new();
+    for url in &args.webhook {
+        notifiers.push(Arc::new(notify::Webhook {
+            http: http.clone(),
+            url: url.clone(),
+        }));
+    }
+    if let Some(app_id) = &args.dialect_app {
+        notifiers.push(Arc::new(notify::Dialect {
+            http: http.clone(),
+            api_url: args.dialect_api.trim_end_matches('/').to_string(),
+            app_id: app_id.clone(),
+            api_key: std::env::var("PCD_DIALECT_API_KEY").context("--dialect-app needs PCD_DIALECT_API_KEY")?,
+        }));
+    }
+    if let Some(project_id) = &args.fcm_project {
+        let source = match &args.fcm_token_file {
+            Some(path) => notify::FcmTokenSource::File(path.clone()),
+            None => notify::FcmTokenSource::Metadata,
+        };
+        notifiers.push(Arc::new(notify::Fcm {
+            http: http.clone(),
+            project_id: project_id.clone(),
+            tokens: notify::FcmTokens::new(http, source),
+        }));
+    }
+    Ok(notifiers)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    let notifiers = notifiers(&args)?;
+    let db: SharedDb = Arc::new(Mutex::new(db::Db::open(&args.db)?));
+    let rpc = RpcClient::new_with_commitment(args.rpc.clone(), CommitmentConfig::confirmed());
+
+    // Resume from the last indexed slot (full replay on first start)
+    let cursor = db.lock().unwrap().cursor()?.unwrap_or(0);
+    let events = SessionEventStream::connect(&args.rpc, &args.ws, Some(cursor)).await?;
+
+    let ingest = tokio::spawn(ingest(events, rpc, db.clone(), notifiers));
+    let listener = tokio::net::TcpListener::bind(&args.listen).await?;
+    println!("serving on {}", args.listen);
+    let serve = axum::serve(listener, api::router(db));
+
+    tokio::select! {
+        r = ingest => r??,
+        r = serve => r?,
+    }
+    Ok(())
+}
+
+/// Fold the event stream into the session table, notifying the parties
+/// of each session the event moved to a final status.
+async fn ingest(
+    mut events: SessionEventStream,
+    rpc: RpcClient,
+    db: SharedDb,
+    notifiers: Vec<Arc<dyn Notifier>>,
+) -> Result<()> {
+    while let Some(notification) = events.next().await {
+        let notification = notification?;
+        let id = hex::encode(notification.event.session_id());
+        let before = status(&db, &id);
+        if let Err(e) = apply(&rpc, &db, &notification).await {
+            eprintln!("failed to index {}: {e}", notification.signature);
+            continue;
+        }
+        if notifiers.is_empty() {
+            continue;
+        }
+        // Only a transition notifies, so replaying the cursor's slot
+        // after a restart does not notify twice
+        let row = db.lock().unwrap().session(&id)?;
+        if let Some(row) = row {
+            if NOTIFY_STATUSES.contains(&row.status.as_str()) && before.as_deref() != Some(row.status.as_str()) {
+                notify::dispatch(&notifiers, Notification::for_parties(&row));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn status(db: &SharedDb, session_id: &str) -> Option<String> {
+    db.lock().unwrap().session(session_id).ok().flatten().map(|row| row.status)
+}
+
+async fn apply(rpc: &RpcClient, db: &SharedDb, notification: &EventNotification) -> Result<()> {
+    let EventNotification { signature, slot, event } = notification;
+    let (slot, id) = (*slot, hex::encode(event.session_id()));
+
+    match event {
+        SessionEvent::SessionCreated(e) => {
+            let db = db.lock().unwrap();
+            db.insert_session(&id, &e.alice.to_string(), slot, signature)?;
+            if e.memo != NO_MEMO {
+                db.set_memo(&id, &hex::encode(e.memo))?;
+            }
+        }
+        SessionEvent::ContactsSubmitted(e) => {
+            // The connector's submission is followed by MatchComputing
+            let status = match e.party {
+                1 => Some("awaiting_bob"),
+                2 => Some("awaiting_connector"),
+                _ => None,
+            };
+            if let Some(status) = status {
+                db.lock().unwrap().set_status(&id, status, slot, signature)?;
+            }
+        }
+        SessionEvent::MatchComputing(e) => {
+            // Bob is recorded on the account, not in the event
+            let data = rpc.get_account_data(&session_pda(&e.session_id)).await?;
+            let session = DiscoverySession::try_from_account_data(&data)?;
+            let db = db.lock().unwrap();
+            db.set_bob(&id, &session.bob.to_string())?;
+            db.set_status(&id, "computing", slot, signature)?;
+        }
+        SessionEvent::DeltaSubmitted(_) | SessionEvent::SetChunkComputing(_) => {
+            db.lock().unwrap().set_status(&id, "computing", slot, signature)?;
+        }
+        SessionEvent::BobJoined(e) => {
+            db.lock().unwrap().set_bob(&id, &e.bob.to_string())?;
+        }
+        SessionEvent::SetCommitted(e) => {
+            db.lock().unwrap().set_bob(&id, &e.bob.to_string())?;
+        }
+        SessionEvent::SetChunkMatched(e) => {
+            let status = if e.chunks_remaining == 0 { "matched" } else { "awaiting_bob" };
+            db.lock().unwrap().set_status(&id, status, slot, signature)?;
+        }
+        SessionEvent::MatchComplete(_)
+        | SessionEvent::DeltaMatched(_)
+        | SessionEvent::ScoreComplete(_)
+        | SessionEvent::SecondDegreeComputed(_)
+        | SessionEvent::FollowChecked(_) => {
+            db.lock().unwrap().set_status(&id, "matched", slot, signature)?;
+        }
+        SessionEvent::SubmissionRejected(e) => {
+            // The callback rolled the session back to the rejected party's stage
+            let status = match e.party {
+                1 => "awaiting_alice",
+                3 => "awaiting_connector",
+                _ => "awaiting_bob",
+            };
+            db.lock().unwrap().set_status(&id, status, slot, signature)?;
+        }
+        SessionEvent::NotificationRegistered(e) => {
+            let target = (e.notification_target != [0u8; 32]).then(|| hex::encode(e.notification_target));
+            db.lock().unwrap().set_notification_target(&id, target.as_deref())?;
+        }
+        SessionEvent::SessionHeartbeat(e) => {
+            // Catch up on a session whose earlier events were missed
+            let db = db.lock().unwrap();
+            db.insert_session(&id, &e.alice.to_string(), slot, signature)?;
+            if e.memo != NO_MEMO {
+                db.set_memo(&id, &hex::encode(e.memo))?;
+            }
+            if e.bob != Pubkey::default() {
+                db.set_bob(&id, &e.bob.to_string())?;
+            }
+            if let Ok(status) = Status::try_from(e.status) {
+                let status = if status == Status::Purged && !e.purged { "purging" } else { status.name() };
+                db.set_status(&id, status, slot, signature)?;
+            }
+        }
+        SessionEvent::ComputationFailed(e) => {
+            // Not necessarily a session: pools and the like report their own id
+            eprintln!(
+                "computation failed for {id} at {signature}: stage {}, offset {}, error {}",
+                e.stage, e.computation_offset, e.reason
+            );
+        }
+        SessionEvent::SessionFailed(_) => {
+            db.lock().unwrap().set_status(&id, "failed", slot, signature)?;
+        }
+        SessionEvent::StatePurging(_) => {
+            db.lock().unwrap().set_status(&id, "purging", slot, signature)?;
+        }
+        SessionEvent::StatePurged(_) => {
+            db.lock().unwrap().set_status(&id, "purged", slot, signature)?;
+        }
+        SessionEvent::SessionClosed(_) => {
+            db.lock().unwrap().set_status(&id, "closed", slot, signature)?;
+        }
+        SessionEvent::AliceRevealed(_) | SessionEvent::AliceScoreRevealed(_) => {
+            db.lock().unwrap().set_alice_revealed(&id)?;
+        }
+        SessionEvent::SessionInitialized(_)
+        | SessionEvent::AliceSubmitted(_)
+        | SessionEvent::AliceRevealing(_)
+        | SessionEvent::BobSubmitted(_)
+        | SessionEvent::SecondDegreeRevealing(_)
+        | SessionEvent::SecondDegreeRevealed(_)
+        | SessionEvent::ContactsCommitted(_)
+        | SessionEvent::BobRevealing(_)
+        | SessionEvent::BobRevealed(_)
+        | SessionEvent::MatchCountPublished(_)
+        | SessionEvent::SessionReallocated(_)
+        | SessionEvent::StateMigrating(_)
+        | SessionEvent::StateMigrated(_)
+        | SessionEvent::RefundClaimed(_) => {}
+    }
+
+    db.lock().unwrap().set_cursor(slot)?;
+    Ok(())
+}
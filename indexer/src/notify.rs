@@ -0,0 +1,412 @@
+// Push notifications for finished sessions.
+//
+// When a session reaches `matched` or `failed`, the indexer builds a
+// `Notification` from the session's row (parties, notification target,
+// memo) and hands it to every configured notifier, once per party
+// wallet. Mobile apps get a push instead of polling `/sessions`.
+//
+//   webhook  POSTs the notification as JSON to a URL
+//   dialect  sends a Dialect alert to the wallet's subscribers
+//   fcm      sends an FCM message to the topic `pcd-<wallet>`, which the
+//            app subscribes each device to after the user signs in
+//
+// FCM takes OAuth2 access tokens that expire after an hour, so the FCM
+// notifier never holds one token: `FcmTokens` caches the current one
+// and fetches the next before it expires, from the metadata server's
+// service account or from a file an external job keeps rewriting.
+//
+// Delivery is best-effort: failures are logged, never retried, and
+// never hold up indexing. Notifications carry no match data, which
+// stays encrypted to the parties.
+
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::{ensure, Context, Result};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::Mutex;
+
+use crate::db::SessionRow;
+
+/// Statuses that notify.
+pub const NOTIFY_STATUSES: [&str; 2] = ["matched", "failed"];
+
+/// One wallet's session reached a final status.
+#[derive(Clone, Debug, Serialize)]
+pub struct Notification {
+    /// The party being notified
+    pub wallet: String,
+    pub session_id: String,
+    /// `matched` or `failed`
+    pub status: String,
+    pub slot: u64,
+    pub signature: String,
+    /// Hex notification target registered for the session, if any
+    pub notification_target: Option<String>,
+    /// Hex memo the session was created with, if any
+    pub memo: Option<String>,
+}
+
+impl Notification {
+    /// One notification per party of `row`: Alice, then Bob once known.
+    pub fn for_parties(row: &SessionRow) -> Vec<Notification> {
+        [Some(&row.alice), row.bob.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(|wallet| Notification {
+                wallet: wallet.clone(),
+                session_id: row.session_id.clone(),
+                status: row.status.clone(),
+                slot: row.updated_slot,
+                signature: row.last_signature.clone(),
+                notification_target: row.notification_target.clone(),
+                memo: row.memo.clone(),
+            })
+            .collect()
+    }
+
+    fn title(&self) -> &'static str {
+        match self.status.as_str() {
+            "matched" => "Contact discovery finished",
+            _ => "Contact discovery failed",
+        }
+    }
+
+    fn body(&self) -> String {
+        let short = &self.session_id[..8.min(self.session_id.len())];
+        match self.status.as_str() {
+            "matched" => format!("Session {short} has matched. Open the app to reveal your results."),
+            _ => format!("Session {short} failed. You can start a new one."),
+        }
+    }
+}
+
+/// A delivery channel.
+pub trait Notifier: Send + Sync {
+    /// Name for log lines.
+    fn name(&self) -> &'static str;
+
+    fn notify<'a>(&'a self, notification: &'a Notification) -> BoxFuture<'a, Result<()>>;
+}
+
+/// POSTs each notification as JSON.
+pub struct Webhook {
+    pub http: reqwest::Client,
+    pub url: String,
+}
+
+impl Notifier for Webhook {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn notify<'a>(&'a self, notification: &'a Notification) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let response = self.http.post(&self.url).json(notification).send().await?;
+            ensure!(response.status().is_success(), "webhook returned {}", response.status());
+            Ok(())
+        })
+    }
+}
+
+/// Sends Dialect alerts to the wallet's subscribers of one dapp.
+pub struct Dialect {
+    pub http: reqwest::Client,
+    /// Alerts API base, e.g. `https://alerts-api.dialect.to`
+    pub api_url: String,
+    pub app_id: String,
+    pub api_key: String,
+}
+
+impl Notifier for Dialect {
+    fn name(&self) -> &'static str {
+        "dialect"
+    }
+
+    fn notify<'a>(&'a self, notification: &'a Notification) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let body = json!({
+                "recipient": { "type": "subscriber", "walletAddress": notification.wallet },
+                "channels": ["PUSH", "IN_APP"],
+                "message": { "title": notification.title(), "body": notification.body() },
+                "data": notification,
+            });
+            let response = self
+                .http
+                .post(format!("{}/v2/{}/send", self.api_url, self.app_id))
+                .header("x-dialect-api-key", &self.api_key)
+                .json(&body)
+                .send()
+                .await?;
+            ensure!(response.status().is_success(), "dialect returned {}", response.status());
+            Ok(())
+        })
+    }
+}
+
+/// Lifetime of a Google OAuth2 access token.
+const TOKEN_LIFETIME: Duration = Duration::from_secs(3600);
+/// How long before expiry a cached token is replaced.
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(300);
+/// How often a token file is re-read.
+const TOKEN_FILE_RECHECK: Duration = Duration::from_secs(60);
+
+/// Token endpoint of the metadata server (GCE, GKE, Cloud Run).
+const METADATA_TOKEN_URL: &str = concat!(
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token",
+    "?scopes=https://www.googleapis.com/auth/firebase.messaging",
+);
+
+/// Where FCM access tokens with the `firebase.messaging` scope come from.
+pub enum FcmTokenSource {
+    /// The default service account of the metadata server, which mints
+    /// a fresh token on request
+    Metadata,
+    /// A file holding the current token, which an external job (e.g.
+    /// `gcloud auth print-access-token` on a timer) must rewrite before
+    /// it expires. A file not rewritten within `TOKEN_LIFETIME` minus
+    /// `TOKEN_REFRESH_MARGIN` is refused rather than sent as a token
+    /// about to die.
+    File(PathBuf),
+}
+
+#[derive(Deserialize)]
+struct MetadataToken {
+    access_token: String,
+    expires_in: u64,
+}
+
+struct CachedToken {
+    token: String,
+    refresh_at: Instant,
+}
+
+/// The current FCM access token, fetched from `source` on first use and
+/// again shortly before it expires.
+pub struct FcmTokens {
+    http: reqwest::Client,
+    source: FcmTokenSource,
+    cached: Mutex<Option<CachedToken>>,
+}
+
+impl FcmTokens {
+    pub fn new(http: reqwest::Client, source: FcmTokenSource) -> Self {
+        FcmTokens {
+            http,
+            source,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// A token valid for at least `TOKEN_REFRESH_MARGIN`.
+    pub async fn get(&self) -> Result<String> {
+        let mut cached = self.cached.lock().await;
+        if let Some(cached) = cached.as_ref().filter(|cached| Instant::now() < cached.refresh_at) {
+            return Ok(cached.token.clone());
+        }
+        let (token, valid_for) = self.fetch().await?;
+        *cached = Some(CachedToken {
+            token: token.clone(),
+            refresh_at: Instant::now() + valid_for.saturating_sub(TOKEN_REFRESH_MARGIN),
+        });
+        Ok(token)
+    }
+
+    /// Drop the cached token, after FCM rejected it.
+    pub async fn invalidate(&self) {
+        *self.cached.lock().await = None;
+    }
+
+    /// A token and how long it stays valid.
+    async fn fetch(&self) -> Result<(String, Duration)> {
+        match &self.source {
+            FcmTokenSource::Metadata => {
+                let response = self
+                    .http
+                    .get(METADATA_TOKEN_URL)
+                    .header("Metadata-Flavor", "Google")
+                    .send()
+                    .await?;
+                ensure!(response.status().is_success(), "metadata server returned {}", response.status());
+                let token: MetadataToken = response.json().await?;
+                Ok((token.access_token, Duration::from_secs(token.expires_in)))
+            }
+            FcmTokenSource::File(path) => {
+                let file = tokio::fs::metadata(path)
+                    .await
+                    .with_context(|| format!("FCM token file {}", path.display()))?;
+                let age = SystemTime::now().duration_since(file.modified()?).unwrap_or_default();
+                ensure!(
+                    age + TOKEN_REFRESH_MARGIN < TOKEN_LIFETIME,
+                    "FCM token file {} was last rewritten {}s ago, so its token has expired or is about to",
+                    path.display(),
+                    age.as_secs()
+                );
+                let token = tokio::fs::read_to_string(path).await?.trim().to_string();
+                ensure!(!token.is_empty(), "FCM token file {} is empty", path.display());
+                // Cached for at most `TOKEN_FILE_RECHECK`, to pick up the
+                // next rewrite
+                let valid_for = (TOKEN_LIFETIME - age).min(TOKEN_FILE_RECHECK + TOKEN_REFRESH_MARGIN);
+                Ok((token, valid_for))
+            }
+        }
+    }
+}
+
+/// Sends FCM messages through the HTTP v1 API to `pcd-<wallet>` topics.
+pub struct Fcm {
+    pub http: reqwest::Client,
+    pub project_id: String,
+    pub tokens: FcmTokens,
+}
+
+impl Notifier for Fcm {
+    fn name(&self) -> &'static str {
+        "fcm"
+    }
+
+    fn notify<'a>(&'a self, notification: &'a Notification) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            // FCM data values must be strings
+            let body = json!({
+                "message": {
+                    "topic": format!("pcd-{}", notification.wallet),
+                    "notification": { "title": notification.title(), "body": notification.body() },
+                    "data": {
+                        "session_id": notification.session_id,
+                        "status": notification.status,
+                        "signature": notification.signature,
+                    },
+                }
+            });
+            let response = self
+                .http
+                .post(format!(
+                    "https://fcm.googleapis.com/v1/projects/{}/messages:send",
+                    self.project_id
+                ))
+                .bearer_auth(self.tokens.get().await?)
+                .json(&body)
+                .send()
+                .await?;
+            if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+                self.tokens.invalidate().await;
+            }
+            ensure!(response.status().is_success(), "fcm returned {}", response.status());
+            Ok(())
+        })
+    }
+}
+
+/// Deliver `notifications` through every notifier in the background,
+/// logging failures.
+pub fn dispatch(notifiers: &[std::sync::Arc<dyn Notifier>], notifications: Vec<Notification>) {
+    for notifier in notifiers {
+        for notification in &notifications {
+            let (notifier, notification) = (notifier.clone(), notification.clone());
+            tokio::spawn(async move {
+                if let Err(e) = notifier.notify(&notification).await {
+                    eprintln!(
+                        "{} notification for {} to {} failed: {e}",
+                        notifier.name(),
+                        notification.session_id,
+                        notification.wallet
+                    );
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A token file under the temp dir, removed on drop.
+    struct TokenFile(PathBuf);
+
+    impl TokenFile {
+        fn new(name: &str, token: &str) -> Self {
+            let file = TokenFile(std::env::temp_dir().join(format!("pcd-fcm-{}-{name}", std::process::id())));
+            file.write(token);
+            file
+        }
+
+        fn write(&self, token: &str) {
+            std::fs::write(&self.0, token).unwrap();
+        }
+
+        fn age_by(&self, age: Duration) {
+            let file = std::fs::File::options().write(true).open(&self.0).unwrap();
+            file.set_modified(SystemTime::now() - age).unwrap();
+        }
+
+        fn tokens(&self) -> FcmTokens {
+            FcmTokens::new(reqwest::Client::new(), FcmTokenSource::File(self.0.clone()))
+        }
+    }
+
+    impl Drop for TokenFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn token_is_cached_until_refresh_at() {
+        let file = TokenFile::new("cached", "first\n");
+        let tokens = file.tokens();
+        assert_eq!(tokens.get().await.unwrap(), "first");
+
+        file.write("second");
+        assert_eq!(tokens.get().await.unwrap(), "first");
+
+        tokens.cached.lock().await.as_mut().unwrap().refresh_at = Instant::now();
+        assert_eq!(tokens.get().await.unwrap(), "second");
+    }
+
+    #[tokio::test]
+    async fn file_token_is_rechecked_well_before_it_expires() {
+        let file = TokenFile::new("recheck", "token");
+        let tokens = file.tokens();
+        let before = Instant::now();
+        tokens.get().await.unwrap();
+        let refresh_at = tokens.cached.lock().await.as_ref().unwrap().refresh_at;
+        assert!(refresh_at <= before + TOKEN_FILE_RECHECK + Duration::from_secs(1));
+        assert!(refresh_at >= before + TOKEN_FILE_RECHECK);
+    }
+
+    #[tokio::test]
+    async fn invalidate_fetches_the_next_token() {
+        let file = TokenFile::new("invalidate", "rejected");
+        let tokens = file.tokens();
+        assert_eq!(tokens.get().await.unwrap(), "rejected");
+
+        file.write("fresh");
+        tokens.invalidate().await;
+        assert_eq!(tokens.get().await.unwrap(), "fresh");
+    }
+
+    #[tokio::test]
+    async fn stale_token_file_is_refused() {
+        let file = TokenFile::new("stale", "token");
+        file.age_by(TOKEN_LIFETIME - TOKEN_REFRESH_MARGIN / 2);
+        assert!(file.tokens().get().await.is_err());
+
+        file.age_by(TOKEN_LIFETIME - TOKEN_REFRESH_MARGIN * 2);
+        assert_eq!(file.tokens().get().await.unwrap(), "token");
+    }
+
+    #[tokio::test]
+    async fn empty_or_missing_token_file_is_refused() {
+        let file = TokenFile::new("empty", " \n");
+        assert!(file.tokens().get().await.is_err());
+
+        let missing = FcmTokens::new(reqwest::Client::new(), FcmTokenSource::File(file.0.with_extension("missing")));
+        assert!(missing.get().await.is_err());
+    }
+}
@@ -0,0 +1,16 @@
+// Writes `account_layouts.json` to OUT_DIR: the byte layout of every
+// account type from `src/layout.rs`, which the crate exports as
+// `ACCOUNT_LAYOUTS_JSON` for readers that parse accounts without Anchor.
+
+use std::{env, fs, path::PathBuf};
+
+#[path = "src/layout.rs"]
+#[allow(dead_code)]
+mod layout;
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/layout.rs");
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").expect("OUT_DIR is set by cargo"));
+    fs::write(out_dir.join("account_layouts.json"), layout::to_json(layout::ACCOUNTS))
+        .expect("writing account_layouts.json");
+}
@@ -0,0 +1,555 @@
+// Fuzz target: the program entrypoint under adversarial input.
+//
+// Each run calls one instruction (any of them, callbacks included) with
+// mutated argument bytes, a mutated list of accounts in any order, and a
+// session account in an arbitrary state, then checks what a successful
+// call did:
+//
+//   - lamports are moved, never created or destroyed
+//   - only accounts this program owned are written to or debited
+//   - a session only moves along a legal status transition, signed for
+//     by a party (or, for the connector stage, by an outsider)
+//   - Alice never changes and Bob is only ever set by Bob himself
+//   - a session is only closed by Alice once purged (or, if it never
+//     stored state, once nothing is queued or owed), refunding its payer
+//   - a session owned by another program is never acted on
+//   - no callback succeeds when invoked directly
+//
+// Panics anywhere in the program are findings too. CPIs fail in this
+// harness, so each handler is covered up to its first CPI; the queueing
+// paths past it are exercised by the localnet tests. Run with
+//
+//     cargo fuzz run instruction_data
+
+#![no_main]
+
+use std::sync::{Mutex, OnceLock};
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        bpf_loader_upgradeable, compute_budget,
+        entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+        hash::hash,
+        instruction::Instruction,
+        native_loader,
+        program_stubs::{self, SyscallStubs},
+        system_program, sysvar,
+        sysvar::instructions::{
+            construct_instructions_data, store_current_index, BorrowedAccountMeta, BorrowedInstruction,
+        },
+    },
+    Discriminator,
+};
+use arbitrary::Arbitrary;
+use arcium_anchor::prelude::ARCIUM_PROG_ID;
+use libfuzzer_sys::fuzz_target;
+use private_contact_discovery::{DiscoverySession, SessionStateHandle, Status, ID};
+
+/// Every instruction in `private_contact_discovery`, by name. An
+/// instruction missing here is never called, so keep it in step with
+/// the program.
+const INSTRUCTIONS: &[&str] = &[
+    "init_session_comp_def", "init_submit_alice_comp_def", "init_submit_and_match_comp_def",
+    "init_reveal_alice_comp_def", "init_push_delta_comp_def", "init_create_pool_comp_def",
+    "init_match_pool_comp_def", "init_submit_and_score_comp_def",
+    "init_reveal_alice_score_comp_def", "init_submit_bob_comp_def",
+    "init_submit_connector_comp_def", "init_reveal_second_degree_comp_def",
+    "init_create_blocklist_comp_def", "init_check_blocklist_comp_def",
+    "init_submit_and_check_follow_comp_def", "init_reveal_bob_comp_def",
+    "init_purge_session_state_comp_def", "init_migrate_session_state_comp_def",
+    "init_create_card_registry_comp_def",
+    "init_publish_contact_card_comp_def", "init_match_contact_cards_comp_def",
+    "init_match_set_chunk_comp_def", "init_load_database_chunk_comp_def", "init_query_psi_comp_def",
+    "init_check_membership_comp_def", "init_check_membership_batch_comp_def",
+    "init_reveal_both_comp_def", "init_submit_and_match_16_comp_def", "init_submit_and_match_8_comp_def",
+    "init_submit_alice_16_comp_def", "init_submit_alice_8_comp_def", "init_instant_match_comp_def",
+    "init_and_submit_alice_comp_def", "init_global_stats", "create_session", "init_session_callback",
+    "create_stateless_session",
+    "create_session_template", "close_session_template", "create_session_from_template",
+    "commit_contacts", "submit_contacts_alice", "submit_contacts_with_permit",
+    "submit_contacts_alice_callback", "submit_contacts_alice_16", "submit_contacts_alice_16_callback",
+    "submit_contacts_alice_8", "submit_contacts_alice_8_callback", "init_and_submit_alice",
+    "init_and_submit_alice_callback", "submit_and_match",
+    "submit_and_match_callback", "submit_and_match_16", "submit_and_match_16_callback",
+    "submit_and_match_8", "submit_and_match_8_callback", "commit_set",
+    "submit_set_chunk", "match_set_chunk_callback", "reveal_alice_matches",
+    "reveal_alice_matches_callback", "reveal_bob_matches", "reveal_bob_matches_callback",
+    "reveal_both", "reveal_both_callback", "push_delta", "push_delta_callback", "create_pool",
+    "create_pool_callback", "match_against_pool", "match_against_pool_callback", "stage_list",
+    "instant_match", "instant_match_callback", "close_staged_list", "create_database",
+    "stage_database_epoch", "load_database_chunk", "load_database_chunk_callback", "seal_database",
+    "rotate_database_epoch", "query_psi", "query_psi_callback", "check_membership",
+    "check_membership_callback", "check_membership_batch", "check_membership_batch_callback",
+    "submit_and_score", "submit_and_score_callback", "reveal_alice_score",
+    "reveal_alice_score_callback", "submit_contacts_bob", "submit_contacts_bob_callback",
+    "submit_connector", "submit_connector_callback", "reveal_second_degree",
+    "reveal_second_degree_callback", "create_blocklist", "create_blocklist_callback",
+    "check_against_blocklist", "check_against_blocklist_callback", "create_card_registry",
+    "create_card_registry_callback", "publish_contact_card", "publish_contact_card_callback",
+    "match_contact_cards", "match_contact_cards_callback", "submit_and_check_follow",
+    "submit_and_check_follow_callback", "purge_session_state", "purge_session_state_callback",
+    "migrate_session_state", "migrate_session_state_callback",
+    "close_session", "sweep_sessions", "realloc_session", "register_notification", "register_app_hook",
+    "consent_connection", "attest_connection", "open_activity_log", "close_activity_log", "open_pair_quota",
+    "renew_query_budget", "set_consent_record", "close_consent_record", "sync_deployment", "ping_session",
+    "claim_invite", "redeem_invite", "view_session", "queue_batch", "init_config",
+    "set_computation_price", "set_fee_pool_share", "set_refund_share", "set_min_client_version",
+    "set_max_pair_sessions", "set_query_budget", "set_gatekeeper", "block_wallet", "unblock_wallet", "claim_refund",
+    "open_credit_account", "deposit_credit", "withdraw_credit", "create_app_lut", "extend_app_lut",
+];
+
+/// Most accounts passed to one call.
+const MAX_ACCOUNTS: usize = 24;
+/// Id of the one session the harness builds.
+const SESSION_ID: [u8; 32] = [7u8; 32];
+/// Wallets a call can pick from; the parties are among them.
+const WALLETS: u8 = 4;
+/// Error every CPI fails with.
+const CPI_UNAVAILABLE: u32 = 0xc91;
+/// Where an account's key and data start in its frame.
+const KEY_AT: usize = 4;
+const DATA_AT: usize = KEY_AT + 32 + 8;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    /// Instruction to call, as an index into `INSTRUCTIONS`
+    instruction: u8,
+    /// Argument bytes following the discriminator
+    args: Vec<u8>,
+    /// The session account before the call
+    session: SessionSeed,
+    /// Accounts in the order they are passed
+    accounts: Vec<Pick>,
+    /// Programs the transaction's other top-level instructions invoke
+    neighbours: Vec<Neighbour>,
+    /// Where among them this instruction sits
+    position: u8,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+#[derive(Arbitrary, Debug)]
+struct SessionSeed {
+    /// Raw session contents, zero-extended to the layout
+    bytes: Vec<u8>,
+    /// Status byte; values past the last `Status` are corrupt sessions
+    status: u8,
+    /// Wallet indices of the parties and of the rent payer; an index of
+    /// `WALLETS` or more leaves that key unset
+    alice: u8,
+    bob: u8,
+    rent_payer: u8,
+    /// Whether the session's state handle has recorded the MXE state
+    handle_generation: u32,
+}
+
+#[derive(Arbitrary, Debug)]
+enum Pick {
+    /// The session, owned by this program or by an impostor
+    Session { impostor: bool, writable: bool },
+    /// The session's state handle
+    StateHandle { writable: bool },
+    /// A wallet, possibly signing
+    Wallet { index: u8, signer: bool, writable: bool },
+    /// Any other address with any owner and contents
+    Blob { address: [u8; 32], owner: Owner, data: Vec<u8>, writable: bool },
+    /// A program or sysvar the program names by address
+    Known(Known),
+}
+
+#[derive(Arbitrary, Debug, Clone, Copy)]
+enum Owner {
+    Program,
+    System,
+    Arcium,
+    Impostor,
+}
+
+#[derive(Arbitrary, Debug, Clone, Copy)]
+enum Known {
+    SystemProgram,
+    ArciumProgram,
+    ComputeBudget,
+    Instructions,
+}
+
+/// A neighbouring instruction's program. Ed25519 and secp256k1 are left
+/// out: the runtime verifies their signatures before the transaction
+/// runs, which a synthesized instructions sysvar cannot stand in for.
+#[derive(Arbitrary, Debug, Clone, Copy)]
+enum Neighbour {
+    ComputeBudget,
+    Arcium,
+    System,
+    This,
+    Impostor,
+}
+
+/// One distinct account of a call, before or after it.
+#[derive(Clone, Debug, PartialEq)]
+struct Slot {
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    signer: bool,
+    writable: bool,
+    executable: bool,
+}
+
+/// Address standing in for a program that is not this one.
+fn impostor() -> Pubkey {
+    Pubkey::new_from_array([0xee; 32])
+}
+
+fn wallet(index: u8) -> Option<Pubkey> {
+    (index < WALLETS).then(|| Pubkey::new_from_array([index + 1; 32]))
+}
+
+fn session_key() -> Pubkey {
+    static KEY: OnceLock<Pubkey> = OnceLock::new();
+    *KEY.get_or_init(|| Pubkey::find_program_address(&[b"session", &SESSION_ID], &ID).0)
+}
+
+fn state_handle_key() -> Pubkey {
+    static KEY: OnceLock<Pubkey> = OnceLock::new();
+    *KEY.get_or_init(|| Pubkey::find_program_address(&[b"state_handle", session_key().as_ref()], &ID).0)
+}
+
+fn discriminator(name: &str) -> [u8; 8] {
+    let mut out = [0u8; 8];
+    out.copy_from_slice(&hash(format!("global:{name}").as_bytes()).to_bytes()[..8]);
+    out
+}
+
+impl SessionSeed {
+    fn session(&self) -> DiscoverySession {
+        let mut body = self.bytes.clone();
+        body.resize(DiscoverySession::SIZE - 8, 0);
+        let mut session: DiscoverySession = bytemuck::pod_read_unaligned(&body);
+        session.session_id = SESSION_ID;
+        session.status = self.status % (Status::ALL.len() as u8 + 1);
+        session.alice = wallet(self.alice).unwrap_or_default();
+        session.bob = wallet(self.bob).unwrap_or_default();
+        session.rent_payer = wallet(self.rent_payer).unwrap_or_default();
+        session
+    }
+
+    fn account_data(&self) -> Vec<u8> {
+        let mut data = DiscoverySession::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(&self.session()));
+        data
+    }
+
+    fn handle_data(&self) -> Vec<u8> {
+        let handle = SessionStateHandle {
+            session: session_key(),
+            state_nonce: 0,
+            state_hash: [0u8; 32],
+            generation: self.handle_generation,
+            bump: Pubkey::find_program_address(&[b"state_handle", session_key().as_ref()], &ID).1,
+        };
+        let mut data = SessionStateHandle::DISCRIMINATOR.to_vec();
+        handle.serialize(&mut data).unwrap();
+        data
+    }
+}
+
+impl Owner {
+    fn key(self) -> Pubkey {
+        match self {
+            Owner::Program => ID,
+            Owner::System => system_program::ID,
+            Owner::Arcium => ARCIUM_PROG_ID,
+            Owner::Impostor => impostor(),
+        }
+    }
+}
+
+impl Neighbour {
+    fn program_id(self) -> Pubkey {
+        match self {
+            Neighbour::ComputeBudget => compute_budget::ID,
+            Neighbour::Arcium => ARCIUM_PROG_ID,
+            Neighbour::System => system_program::ID,
+            Neighbour::This => ID,
+            Neighbour::Impostor => impostor(),
+        }
+    }
+}
+
+fn program(key: Pubkey, loader: Pubkey) -> Slot {
+    Slot { key, owner: loader, lamports: 1, data: Vec::new(), signer: false, writable: false, executable: true }
+}
+
+fn slot(pick: &Pick, seed: &SessionSeed) -> Slot {
+    match *pick {
+        Pick::Session { impostor: fake, writable } => Slot {
+            key: session_key(),
+            owner: if fake { impostor() } else { ID },
+            lamports: 10_000_000,
+            data: seed.account_data(),
+            signer: false,
+            writable,
+            executable: false,
+        },
+        Pick::StateHandle { writable } => Slot {
+            key: state_handle_key(),
+            owner: ID,
+            lamports: 2_000_000,
+            data: seed.handle_data(),
+            signer: false,
+            writable,
+            executable: false,
+        },
+        Pick::Wallet { index, signer, writable } => Slot {
+            key: wallet(index % WALLETS).unwrap(),
+            owner: system_program::ID,
+            lamports: 1_000_000_000,
+            data: Vec::new(),
+            signer,
+            writable,
+            executable: false,
+        },
+        Pick::Blob { address, owner, ref data, writable } => Slot {
+            key: Pubkey::new_from_array(address),
+            owner: owner.key(),
+            lamports: 5_000_000,
+            data: data.clone(),
+            signer: false,
+            writable,
+            executable: false,
+        },
+        Pick::Known(Known::SystemProgram) => program(system_program::ID, native_loader::ID),
+        Pick::Known(Known::ComputeBudget) => program(compute_budget::ID, native_loader::ID),
+        Pick::Known(Known::ArciumProgram) => program(ARCIUM_PROG_ID, bpf_loader_upgradeable::ID),
+        Pick::Known(Known::Instructions) => Slot {
+            key: sysvar::instructions::ID,
+            owner: sysvar::ID,
+            lamports: 1,
+            data: Vec::new(),
+            signer: false,
+            writable: false,
+            executable: false,
+        },
+    }
+}
+
+/// `slot` laid out as the entrypoint deserializes an account: its
+/// original data length before the key, its current length before the
+/// data, and room to grow after. `AccountInfo::realloc`, which `close`
+/// and `realloc` constraints use, reads and writes those lengths.
+fn frame(slot: &Slot) -> Vec<u8> {
+    let len = slot.data.len();
+    let mut frame = Vec::with_capacity(DATA_AT + len + MAX_PERMITTED_DATA_INCREASE);
+    frame.extend_from_slice(&(len as u32).to_le_bytes());
+    frame.extend_from_slice(slot.key.as_ref());
+    frame.extend_from_slice(&(len as u64).to_le_bytes());
+    frame.extend_from_slice(&slot.data);
+    frame.resize(DATA_AT + len + MAX_PERMITTED_DATA_INCREASE, 0);
+    frame
+}
+
+/// The instructions sysvar of a transaction running this instruction at
+/// `position` among `neighbours`, as the runtime would lay it out.
+fn instructions_sysvar(input: &Input, metas: &[(Pubkey, bool, bool)], data: &[u8]) -> Vec<u8> {
+    let neighbours: Vec<Pubkey> = input.neighbours.iter().take(4).map(|n| n.program_id()).collect();
+    let position = input.position as usize % (neighbours.len() + 1);
+    let mut program_ids = neighbours;
+    program_ids.insert(position, ID);
+    let instructions: Vec<BorrowedInstruction> = program_ids
+        .iter()
+        .enumerate()
+        .map(|(i, program_id)| BorrowedInstruction {
+            program_id,
+            accounts: if i == position {
+                metas
+                    .iter()
+                    .map(|(pubkey, is_signer, is_writable)| BorrowedAccountMeta {
+                        pubkey,
+                        is_signer: *is_signer,
+                        is_writable: *is_writable,
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            },
+            data: if i == position { data } else { &[] },
+        })
+        .collect();
+    let mut sysvar = construct_instructions_data(&instructions);
+    store_current_index(&mut sysvar, position as u16);
+    sysvar
+}
+
+/// Clock and return data of the call being run. The stubs are process
+/// wide, so the call's values are handed to them here.
+static CLOCK: Mutex<(u64, i64)> = Mutex::new((0, 0));
+static RETURN_DATA: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_log_data(&self, _fields: &[&[u8]]) {}
+
+    fn sol_invoke_signed(
+        &self,
+        _instruction: &Instruction,
+        _account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        Err(ProgramError::Custom(CPI_UNAVAILABLE))
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let (slot, unix_timestamp) = *CLOCK.lock().unwrap();
+        let clock = Clock { slot, unix_timestamp, ..Clock::default() };
+        unsafe { std::ptr::write_unaligned(var_addr as *mut Clock, clock) };
+        0
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { std::ptr::write_unaligned(var_addr as *mut Rent, Rent::default()) };
+        0
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        *RETURN_DATA.lock().unwrap() = data.to_vec();
+    }
+}
+
+fn status(byte: u8, name: &str) -> Status {
+    Status::try_from(byte).unwrap_or_else(|_| panic!("{name} left a corrupt status {byte}"))
+}
+
+/// Whether `signers` may move a session from `from` to `to` outside a
+/// callback. Alice and Bob are as of after the call, so Bob joining is
+/// signed by the Bob he became.
+fn authorized(from: Status, to: Status, after: &DiscoverySession, signers: &[Pubkey]) -> bool {
+    let party = signers.contains(&after.alice) || signers.contains(&after.bob);
+    match (from, to) {
+        (Status::AwaitingAlice, Status::AwaitingBob) => signers.contains(&after.alice),
+        (Status::AwaitingConnector, Status::Computing) => {
+            signers.iter().any(|s| *s != after.alice && *s != after.bob)
+        }
+        _ => party,
+    }
+}
+
+fn check(name: &str, before: &[Slot], after: &[Slot]) {
+    let total = |slots: &[Slot]| slots.iter().map(|s| u128::from(s.lamports)).sum::<u128>();
+    assert_eq!(total(before), total(after), "{name} created or destroyed lamports");
+
+    for (b, a) in before.iter().zip(after) {
+        let touched = a.data != b.data || a.lamports < b.lamports;
+        assert!(!touched || b.owner == ID, "{name} wrote to or debited {} owned by {}", b.key, b.owner);
+    }
+
+    assert!(!name.ends_with("_callback"), "{name} ran outside an Arcium callback");
+
+    let signers: Vec<Pubkey> = before.iter().filter(|s| s.signer).map(|s| s.key).collect();
+    let Some(i) = before.iter().position(|s| s.key == session_key()) else {
+        return;
+    };
+    let (b, a) = (&before[i], &after[i]);
+    if b.owner != ID {
+        assert!(RETURN_DATA.lock().unwrap().is_empty(), "{name} reported on an impostor session");
+        return;
+    }
+    let old = DiscoverySession::try_from_account_data(&b.data).unwrap();
+    if a.lamports == 0 {
+        assert!(signers.contains(&old.alice), "{name} closed a session without Alice");
+        let stateless = before
+            .iter()
+            .find(|s| s.key == state_handle_key())
+            .and_then(|s| SessionStateHandle::try_deserialize(&mut s.data.as_slice()).ok())
+            .is_some_and(|handle| handle.generation == 0);
+        if stateless {
+            assert!(old.pending_stage().is_none(), "{name} closed a session mid-computation");
+            assert_eq!(old.refundable_fee, 0, "{name} closed a session owed a refund");
+        } else {
+            assert_eq!(old.status, Status::Purged as u8, "{name} closed an unpurged session");
+            assert_ne!(old.purged_slot, 0, "{name} closed a session mid-purge");
+        }
+        let recipient = before.iter().position(|s| s.key == old.rent_recipient());
+        let refunded = recipient.is_some_and(|r| after[r].lamports >= before[r].lamports + b.lamports);
+        assert!(refunded, "{name} refunded a session to someone else");
+        return;
+    }
+    let new = DiscoverySession::try_from_account_data(&a.data).unwrap();
+    if new.status != old.status {
+        let (from, to) = (status(old.status, name), status(new.status, name));
+        assert!(from.can_transition(to), "{name} moved a session {from:?} -> {to:?}");
+        assert!(authorized(from, to, &new, &signers), "{name} moved a session {from:?} -> {to:?} unsigned");
+    }
+    assert_eq!(new.alice, old.alice, "{name} replaced Alice");
+    let joined = old.bob == Pubkey::default() && signers.contains(&new.bob);
+    assert!(new.bob == old.bob || joined, "{name} replaced Bob");
+}
+
+fuzz_target!(|input: Input| {
+    static STUBS: OnceLock<()> = OnceLock::new();
+    STUBS.get_or_init(|| {
+        program_stubs::set_syscall_stubs(Box::new(Stubs));
+    });
+    *CLOCK.lock().unwrap() = (input.slot, input.unix_timestamp);
+    RETURN_DATA.lock().unwrap().clear();
+
+    let name = INSTRUCTIONS[input.instruction as usize % INSTRUCTIONS.len()];
+    let mut data = discriminator(name).to_vec();
+    data.extend_from_slice(&input.args);
+
+    // A key passed twice is one account, with the flags of both uses
+    let mut slots: Vec<Slot> = Vec::new();
+    let mut order = Vec::new();
+    for pick in input.accounts.iter().take(MAX_ACCOUNTS) {
+        let new = slot(pick, &input.session);
+        match slots.iter().position(|s| s.key == new.key) {
+            Some(i) => {
+                slots[i].signer |= new.signer;
+                slots[i].writable |= new.writable;
+                order.push(i);
+            }
+            None => {
+                order.push(slots.len());
+                slots.push(new);
+            }
+        }
+    }
+    let metas: Vec<(Pubkey, bool, bool)> =
+        order.iter().map(|&i| (slots[i].key, slots[i].signer, slots[i].writable)).collect();
+    if let Some(sysvar) = slots.iter_mut().find(|s| s.key == sysvar::instructions::ID) {
+        sysvar.data = instructions_sysvar(&input, &metas, &data);
+    }
+
+    let before = slots.clone();
+    let mut frames: Vec<Vec<u8>> = slots.iter().map(frame).collect();
+    let infos: Vec<AccountInfo> = slots
+        .iter_mut()
+        .zip(frames.iter_mut())
+        .map(|(s, frame)| {
+            let (head, tail) = frame.split_at_mut(KEY_AT + 32);
+            let key: &Pubkey = bytemuck::from_bytes(&head[KEY_AT..]);
+            let data = &mut tail[8..8 + s.data.len()];
+            AccountInfo::new(key, s.signer, s.writable, &mut s.lamports, data, &s.owner, s.executable, 0)
+        })
+        .collect();
+    let accounts: Vec<AccountInfo> = order.iter().map(|&i| infos[i].clone()).collect();
+
+    if private_contact_discovery::entry(&ID, &accounts, &data).is_ok() {
+        let after: Vec<Slot> = before
+            .iter()
+            .zip(&infos)
+            .map(|(b, info)| Slot {
+                owner: *info.owner,
+                lamports: info.lamports(),
+                data: info.data.borrow().to_vec(),
+                ..b.clone()
+            })
+            .collect();
+        check(name, &before, &after);
+    }
+});
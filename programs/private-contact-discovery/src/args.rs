@@ -0,0 +1,239 @@
+// Argument layouts for `queue_computation`. Arcium flattens a circuit's
+// encrypted input into one 32-byte ciphertext per scalar, in field order,
+// right after the owner's x25519 key and nonce. The field order and
+// scalar types come from the `schema` crate, which the reference model
+// shares, so every instruction feeding the same circuit struct encodes it
+// the same way and a slot count that drifts from the schema fails to
+// compile instead of queueing garbage.
+
+use anchor_lang::prelude::*;
+use arcium_anchor::prelude::*;
+use schema::{Layout, Scalar};
+
+use crate::{DiscoveryBlocklist, DiscoveryCardRegistry, DiscoveryDatabase, DiscoveryPool, StagedList};
+
+/// Hash slots in a `ContactList`.
+pub const LIST_SLOTS: usize = schema::MAX_CONTACTS;
+/// Slots in each half of a `ContactDelta` (`added` and `removed`).
+pub const DELTA_SLOTS: usize = schema::MAX_DELTA;
+/// Ciphertexts in an encrypted `ContactList`: the hashes, the count, and
+/// the namespace.
+pub const CONTACT_LIST_CIPHERTEXTS: usize = schema::ciphertexts(schema::CONTACT_LIST);
+/// Ciphertexts in a `MatchResult` page: the packed matched slots and the
+/// count.
+pub const MATCH_RESULT_CIPHERTEXTS: usize = schema::ciphertexts(schema::MATCH_RESULT);
+/// Card slots in a `CardRegistry`.
+pub const CARD_SLOTS: usize = schema::CARD_SLOTS;
+/// Ciphertexts in an encrypted `ContactCard`: hash, both key halves,
+/// and namespace.
+pub const CONTACT_CARD_CIPHERTEXTS: usize = schema::ciphertexts(schema::CONTACT_CARD);
+/// Ciphertexts in an encrypted `CardRegistry`: four slot arrays, the
+/// count, and the namespace.
+pub const CARD_REGISTRY_CIPHERTEXTS: usize = schema::ciphertexts(schema::CARD_REGISTRY);
+/// Ciphertexts in `CardMatches`: three slot arrays and the count.
+pub const CARD_MATCHES_CIPHERTEXTS: usize = schema::ciphertexts(schema::CARD_MATCHES);
+/// Chunk slots in a server database.
+pub const DATABASE_CHUNKS: usize = schema::DATABASE_CHUNKS;
+/// Ciphertexts in an encrypted `MembershipQuery`: the hash and the
+/// namespace.
+pub const MEMBERSHIP_QUERY_CIPHERTEXTS: usize = schema::ciphertexts(schema::MEMBERSHIP_QUERY);
+/// Identifier slots in a `MembershipBatch`.
+pub const MEMBERSHIP_BATCH_SLOTS: usize = schema::MEMBERSHIP_BATCH_SLOTS;
+/// Ciphertexts in an encrypted `MembershipBatch`: the hashes and the
+/// namespace.
+pub const MEMBERSHIP_BATCH_CIPHERTEXTS: usize = schema::ciphertexts(schema::MEMBERSHIP_BATCH);
+
+// The instruction arguments are fixed-size arrays of ciphertexts; these
+// tie their sizes to the layouts they are encoded by
+const _: () = assert!(CONTACT_LIST_CIPHERTEXTS == LIST_SLOTS + 2);
+const _: () = assert!(schema::ciphertexts(schema::CONTACT_DELTA) == 2 * DELTA_SLOTS + 1);
+const _: () = assert!(MATCH_RESULT_CIPHERTEXTS == schema::MATCH_WORDS + 1);
+const _: () = assert!(CONTACT_CARD_CIPHERTEXTS == 4);
+const _: () = assert!(CARD_REGISTRY_CIPHERTEXTS == 4 * CARD_SLOTS + 2);
+const _: () = assert!(CARD_MATCHES_CIPHERTEXTS == 3 * CARD_SLOTS + 1);
+const _: () = assert!(MEMBERSHIP_QUERY_CIPHERTEXTS == 2);
+const _: () = assert!(MEMBERSHIP_BATCH_CIPHERTEXTS == MEMBERSHIP_BATCH_SLOTS + 1);
+// The answer is a u16 mask, one bit per slot
+const _: () = assert!(MEMBERSHIP_BATCH_SLOTS == 16);
+// Stored lists are handed to the MPC by byte range, so the accounts'
+// ranges must hold exactly one encrypted `ContactList`
+const _: () = assert!(DiscoveryPool::ENCRYPTED_LIST_LEN as usize == CONTACT_LIST_CIPHERTEXTS * 32);
+const _: () = assert!(DiscoveryBlocklist::ENCRYPTED_LIST_LEN as usize == CONTACT_LIST_CIPHERTEXTS * 32);
+const _: () = assert!(StagedList::ENCRYPTED_LIST_LEN as usize == CONTACT_LIST_CIPHERTEXTS * 32);
+const _: () = assert!(DiscoveryCardRegistry::ENCRYPTED_REGISTRY_LEN as usize == CARD_REGISTRY_CIPHERTEXTS * 32);
+const _: () = assert!(DiscoveryDatabase::ENCRYPTED_CHUNK_LEN as usize == CONTACT_LIST_CIPHERTEXTS * 32);
+const _: () = assert!(
+    DiscoveryDatabase::SIZE
+        >= DiscoveryDatabase::ENCRYPTED_CHUNKS_OFFSET as usize
+            + DATABASE_CHUNKS * DiscoveryDatabase::ENCRYPTED_CHUNK_LEN as usize
+);
+
+/// Append `ciphertexts` to `builder` as an encrypted `layout`, each with
+/// the scalar type its field has. The caller passes exactly
+/// `schema::ciphertexts(layout)` of them; the sizes asserted above make
+/// that hold for every layout used here. Walks the fields once instead
+/// of looking each ciphertext's field up, since submissions run this
+/// over 34 ciphertexts inside their compute budget.
+fn push_layout<'a>(
+    mut builder: ArgBuilder,
+    layout: Layout,
+    ciphertexts: impl IntoIterator<Item = &'a [u8; 32]>,
+) -> ArgBuilder {
+    let mut ciphertexts = ciphertexts.into_iter();
+    for field in layout {
+        for ciphertext in ciphertexts.by_ref().take(field.len) {
+            builder = match field.scalar {
+                Scalar::U8 => builder.encrypted_u8(*ciphertext),
+                Scalar::U32 => builder.encrypted_u32(*ciphertext),
+                Scalar::U128 => builder.encrypted_u128(*ciphertext),
+            };
+        }
+    }
+    builder
+}
+
+/// A party's key and nonce followed by its encrypted `ContactList`.
+pub fn contact_list_args(
+    pubkey: [u8; 32],
+    nonce: u128,
+    hashes: &[[u8; 32]; LIST_SLOTS],
+    count: [u8; 32],
+    namespace: [u8; 32],
+) -> ArgBuilder {
+    push_contact_list(ArgBuilder::new(), pubkey, nonce, hashes, count, namespace)
+}
+
+/// Append a party's key, nonce, and encrypted `ContactList` to `builder`.
+pub fn push_contact_list(
+    builder: ArgBuilder,
+    pubkey: [u8; 32],
+    nonce: u128,
+    hashes: &[[u8; 32]; LIST_SLOTS],
+    count: [u8; 32],
+    namespace: [u8; 32],
+) -> ArgBuilder {
+    push_layout(
+        builder.x25519_pubkey(pubkey).plaintext_u128(nonce),
+        schema::CONTACT_LIST,
+        hashes.iter().chain([&count, &namespace]),
+    )
+}
+
+/// A party's key and nonce followed by its encrypted `ContactDelta`.
+pub fn contact_delta_args(
+    pubkey: [u8; 32],
+    nonce: u128,
+    added: &[[u8; 32]; DELTA_SLOTS],
+    removed: &[[u8; 32]; DELTA_SLOTS],
+    namespace: [u8; 32],
+) -> ArgBuilder {
+    push_layout(
+        ArgBuilder::new().x25519_pubkey(pubkey).plaintext_u128(nonce),
+        schema::CONTACT_DELTA,
+        added.iter().chain(removed).chain([&namespace]),
+    )
+}
+
+/// An `Enc<Mxe, _>` (a `ContactList` or `CardRegistry`) the MPC reads
+/// straight from `account`'s `offset..offset + len`, encrypted under
+/// `nonce`.
+pub fn stored_list_args(nonce: u128, account: Pubkey, offset: u32, len: u32) -> ArgBuilder {
+    push_stored_list(ArgBuilder::new(), nonce, account, offset, len)
+}
+
+/// Append a stored `Enc<Mxe, _>` to `builder`, as `stored_list_args`.
+pub fn push_stored_list(builder: ArgBuilder, nonce: u128, account: Pubkey, offset: u32, len: u32) -> ArgBuilder {
+    builder.plaintext_u128(nonce).account(account, offset, len)
+}
+
+/// Append an `Enc<Shared, _>` the MPC reads straight from `account`'s
+/// `offset..offset + len`, with its owner's key and `nonce`.
+pub fn push_shared_stored_list(
+    builder: ArgBuilder,
+    pubkey: [u8; 32],
+    nonce: u128,
+    account: Pubkey,
+    offset: u32,
+    len: u32,
+) -> ArgBuilder {
+    builder.x25519_pubkey(pubkey).plaintext_u128(nonce).account(account, offset, len)
+}
+
+/// Every chunk slot of database `account`, each an `Enc<Mxe,
+/// ContactList>` read in place under its own nonce. Unloaded slots are
+/// passed too; the circuits mask them.
+pub fn database_chunks_args(account: Pubkey, nonces: &[[u8; 16]; DATABASE_CHUNKS]) -> ArgBuilder {
+    nonces
+        .iter()
+        .enumerate()
+        .fold(ArgBuilder::new(), |builder, (slot, nonce)| {
+            push_stored_list(
+                builder,
+                u128::from_le_bytes(*nonce),
+                account,
+                DiscoveryDatabase::ENCRYPTED_CHUNKS_OFFSET + slot as u32 * DiscoveryDatabase::ENCRYPTED_CHUNK_LEN,
+                DiscoveryDatabase::ENCRYPTED_CHUNK_LEN,
+            )
+        })
+}
+
+/// Append a client's key, nonce, and encrypted `MembershipQuery` to
+/// `builder`.
+pub fn push_membership_query(
+    builder: ArgBuilder,
+    pubkey: [u8; 32],
+    nonce: u128,
+    query: &[[u8; 32]; MEMBERSHIP_QUERY_CIPHERTEXTS],
+) -> ArgBuilder {
+    push_layout(
+        builder.x25519_pubkey(pubkey).plaintext_u128(nonce),
+        schema::MEMBERSHIP_QUERY,
+        query,
+    )
+}
+
+/// Append a client's key, nonce, and encrypted `MembershipBatch` to
+/// `builder`.
+pub fn push_membership_batch(
+    builder: ArgBuilder,
+    pubkey: [u8; 32],
+    nonce: u128,
+    hashes: &[[u8; 32]; MEMBERSHIP_BATCH_SLOTS],
+    namespace: [u8; 32],
+) -> ArgBuilder {
+    push_layout(
+        builder.x25519_pubkey(pubkey).plaintext_u128(nonce),
+        schema::MEMBERSHIP_BATCH,
+        hashes.iter().chain([&namespace]),
+    )
+}
+
+/// Append a publisher's key, nonce, and encrypted `ContactCard` to
+/// `builder`.
+pub fn push_contact_card(
+    builder: ArgBuilder,
+    pubkey: [u8; 32],
+    nonce: u128,
+    card: &[[u8; 32]; CONTACT_CARD_CIPHERTEXTS],
+) -> ArgBuilder {
+    push_layout(
+        builder.x25519_pubkey(pubkey).plaintext_u128(nonce),
+        schema::CONTACT_CARD,
+        card,
+    )
+}
+
+/// The key a result is encrypted to, carried by a dummy encrypted byte:
+/// circuits that take no party input still need one to learn the key.
+pub fn party_key_args(pubkey: [u8; 32], nonce: u128) -> ArgBuilder {
+    push_party_key(ArgBuilder::new(), pubkey, nonce)
+}
+
+/// Append another party's key to `builder`, as `party_key_args`, for
+/// circuits that encrypt results to more than one party.
+pub fn push_party_key(builder: ArgBuilder, pubkey: [u8; 32], nonce: u128) -> ArgBuilder {
+    builder
+        .x25519_pubkey(pubkey)
+        .plaintext_u128(nonce)
+        .encrypted_u8([0u8; 32])
+}
@@ -0,0 +1,181 @@
+// Sybil resistance through third-party attestations. While the config
+// names a gatekeeper, both parties of a session must hold a valid
+// attestation from it, such as a proof of personhood, before Bob is
+// recorded, so one operator cannot run enumeration from a farm of fresh
+// wallets. The attestations are passed as remaining accounts of the
+// instruction recording Bob and found by address.
+//
+// Each provider is an `AttestationProvider`: the program owning its
+// attestation accounts, where a wallet's attestation lives, and how to
+// tell whether one is valid. Adding a provider means a `Gatekeeper`
+// variant and its implementation here; the rest of the program only
+// calls `require_attested`.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey;
+
+use crate::{ErrorCode, ProtocolConfig};
+
+/// Attestation provider stored as `ProtocolConfig::gatekeeper`.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Gatekeeper {
+    /// No attestation required
+    None = 0,
+    /// A Civic Pass: an active gateway token of the config's gatekeeper
+    /// network
+    CivicPass = 1,
+}
+
+impl Gatekeeper {
+    pub fn from_u8(gatekeeper: u8) -> Option<Self> {
+        match gatekeeper {
+            0 => Some(Gatekeeper::None),
+            1 => Some(Gatekeeper::CivicPass),
+            _ => None,
+        }
+    }
+
+    fn provider(self) -> Option<&'static dyn AttestationProvider> {
+        match self {
+            Gatekeeper::None => None,
+            Gatekeeper::CivicPass => Some(&CivicPass),
+        }
+    }
+}
+
+/// One source of attestations.
+pub trait AttestationProvider {
+    /// Program that owns, and alone writes, the attestation accounts
+    fn program_id(&self) -> Pubkey;
+
+    /// Address of `wallet`'s attestation within `network`, the issuer
+    /// or policy the config accepts
+    fn address(&self, wallet: &Pubkey, network: &Pubkey) -> Pubkey;
+
+    /// Whether `data`, an account the provider's program owns, attests
+    /// `wallet` within `network` at unix time `now`
+    fn attests(&self, data: &[u8], wallet: &Pubkey, network: &Pubkey, now: i64) -> bool;
+}
+
+/// Address of `wallet`'s attestation under the config's gatekeeper, for
+/// clients to pass; `None` while no gatekeeper is set.
+pub fn attestation_address(config: &ProtocolConfig, wallet: &Pubkey) -> Option<Pubkey> {
+    let provider = Gatekeeper::from_u8(config.gatekeeper)?.provider()?;
+    Some(provider.address(wallet, &config.gatekeeper_network))
+}
+
+/// Fail unless each of `wallets` has a valid attestation from the
+/// config's gatekeeper among `accounts`: `AttestationMissing` if it is
+/// not passed, `AttestationInvalid` if it does not attest the wallet
+/// now. Passes when no gatekeeper is set.
+pub fn require_attested(config: &ProtocolConfig, accounts: &[AccountInfo], wallets: &[Pubkey]) -> Result<()> {
+    let gatekeeper = Gatekeeper::from_u8(config.gatekeeper).ok_or(ErrorCode::InvalidGatekeeper)?;
+    let Some(provider) = gatekeeper.provider() else {
+        return Ok(());
+    };
+    let network = &config.gatekeeper_network;
+    let now = Clock::get()?.unix_timestamp;
+    for wallet in wallets {
+        let key = provider.address(wallet, network);
+        let info = accounts
+            .iter()
+            .find(|info| info.key() == key && *info.owner == provider.program_id())
+            .ok_or(ErrorCode::AttestationMissing)?;
+        require!(
+            provider.attests(&info.try_borrow_data()?, wallet, network, now),
+            ErrorCode::AttestationInvalid
+        );
+    }
+    Ok(())
+}
+
+/// Civic's gateway program, which issues Civic Pass gateway tokens.
+pub const CIVIC_GATEWAY_PROGRAM: Pubkey = pubkey!("gatem74V238djXdzWnJf94Wo1DcnuGkfijbf3AuBhfs");
+
+/// `GatewayTokenState::Active`
+const GATEWAY_TOKEN_ACTIVE: u8 = 0;
+
+/// The leading fields of a gateway token account, as the gateway program
+/// serializes them (Borsh).
+#[derive(AnchorSerialize, AnchorDeserialize)]
+struct GatewayToken {
+    _features: u8,
+    _parent_gateway_token: Option<Pubkey>,
+    owner_wallet: Pubkey,
+    _owner_identity: Option<Pubkey>,
+    gatekeeper_network: Pubkey,
+    _issuing_gatekeeper: Pubkey,
+    state: u8,
+    expire_time: Option<i64>,
+}
+
+struct CivicPass;
+
+impl AttestationProvider for CivicPass {
+    fn program_id(&self) -> Pubkey {
+        CIVIC_GATEWAY_PROGRAM
+    }
+
+    /// The wallet's token at the default seed: ["<wallet>", "gateway",
+    /// 8 zero bytes, "<network>"]
+    fn address(&self, wallet: &Pubkey, network: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[wallet.as_ref(), b"gateway", &[0; 8], network.as_ref()],
+            &CIVIC_GATEWAY_PROGRAM,
+        )
+        .0
+    }
+
+    fn attests(&self, data: &[u8], wallet: &Pubkey, network: &Pubkey, now: i64) -> bool {
+        let Ok(token) = GatewayToken::deserialize(&mut &data[..]) else {
+            return false;
+        };
+        token.owner_wallet == *wallet
+            && token.gatekeeper_network == *network
+            && token.state == GATEWAY_TOKEN_ACTIVE
+            && !token.expire_time.is_some_and(|expiry| now >= expiry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(wallet: Pubkey, network: Pubkey, state: u8, expire_time: Option<i64>) -> Vec<u8> {
+        let mut data = Vec::new();
+        GatewayToken {
+            _features: 0,
+            _parent_gateway_token: None,
+            owner_wallet: wallet,
+            _owner_identity: None,
+            gatekeeper_network: network,
+            _issuing_gatekeeper: Pubkey::new_unique(),
+            state,
+            expire_time,
+        }
+        .serialize(&mut data)
+        .unwrap();
+        data
+    }
+
+    #[test]
+    fn active_token_of_the_network_attests_its_wallet() {
+        let (wallet, network) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let data = token(wallet, network, GATEWAY_TOKEN_ACTIVE, Some(100));
+        assert!(CivicPass.attests(&data, &wallet, &network, 99));
+        assert!(!CivicPass.attests(&data, &Pubkey::new_unique(), &network, 99));
+        assert!(!CivicPass.attests(&data, &wallet, &Pubkey::new_unique(), 99));
+    }
+
+    #[test]
+    fn frozen_revoked_and_expired_tokens_do_not_attest() {
+        let (wallet, network) = (Pubkey::new_unique(), Pubkey::new_unique());
+        for state in [1, 2] {
+            assert!(!CivicPass.attests(&token(wallet, network, state, None), &wallet, &network, 0));
+        }
+        let expiring = token(wallet, network, GATEWAY_TOKEN_ACTIVE, Some(100));
+        assert!(!CivicPass.attests(&expiring, &wallet, &network, 100));
+        assert!(!CivicPass.attests(&expiring[..40], &wallet, &network, 0));
+    }
+}
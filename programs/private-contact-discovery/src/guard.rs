@@ -0,0 +1,87 @@
+// Callback transaction guard: a callback may only run as part of the
+// Arcium program's callback flow. It reads the instructions sysvar and
+// checks that the top-level instruction being executed is Arcium's (so
+// the callback was reached by CPI, not called directly), and that every
+// other instruction in the transaction is Arcium's or a compute budget
+// setting. An attacker therefore cannot wrap a callback in their own
+// instructions to read or act on session state immediately before or
+// after it changes within one atomic transaction.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    compute_budget,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+use arcium_anchor::prelude::ARCIUM_PROG_ID;
+
+use crate::ErrorCode;
+
+/// Whether a transaction whose top-level instructions invoke
+/// `program_ids`, executing the one at `current`, is an Arcium callback
+/// transaction.
+fn is_callback_transaction(program_ids: &[Pubkey], current: usize) -> bool {
+    program_ids.get(current) == Some(&ARCIUM_PROG_ID)
+        && program_ids
+            .iter()
+            .all(|id| *id == ARCIUM_PROG_ID || *id == compute_budget::ID)
+}
+
+/// Fail with `UnexpectedCallbackTransaction` unless the running callback
+/// was invoked by the Arcium program in a transaction holding nothing
+/// but Arcium and compute budget instructions.
+pub fn require_callback_transaction(instructions_sysvar: &AccountInfo) -> Result<()> {
+    // Tests call callbacks directly (see testing.rs)
+    if cfg!(feature = "testing") {
+        return Ok(());
+    }
+    let current = load_current_index_checked(instructions_sysvar)? as usize;
+    let mut program_ids = Vec::new();
+    while let Ok(ix) = load_instruction_at_checked(program_ids.len(), instructions_sysvar) {
+        program_ids.push(ix.program_id);
+    }
+    require!(
+        is_callback_transaction(&program_ids, current),
+        ErrorCode::UnexpectedCallbackTransaction
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arcium_callback_transactions_pass() {
+        assert!(is_callback_transaction(&[ARCIUM_PROG_ID], 0));
+        assert!(is_callback_transaction(&[compute_budget::ID, ARCIUM_PROG_ID], 1));
+        assert!(is_callback_transaction(
+            &[compute_budget::ID, compute_budget::ID, ARCIUM_PROG_ID, ARCIUM_PROG_ID],
+            3
+        ));
+    }
+
+    #[test]
+    fn direct_invocation_is_refused() {
+        assert!(!is_callback_transaction(&[crate::ID], 0));
+        assert!(!is_callback_transaction(&[ARCIUM_PROG_ID, crate::ID], 1));
+        // Pointing at a compute budget instruction is not a CPI from Arcium
+        assert!(!is_callback_transaction(&[compute_budget::ID, ARCIUM_PROG_ID], 0));
+    }
+
+    #[test]
+    fn sandwiching_instructions_are_refused() {
+        let attacker = Pubkey::new_unique();
+        assert!(!is_callback_transaction(&[attacker, ARCIUM_PROG_ID], 1));
+        assert!(!is_callback_transaction(&[ARCIUM_PROG_ID, attacker], 0));
+        assert!(!is_callback_transaction(&[attacker, ARCIUM_PROG_ID, attacker], 1));
+        // Nor may this program's own instructions ride along
+        assert!(!is_callback_transaction(&[crate::ID, ARCIUM_PROG_ID], 1));
+        assert!(!is_callback_transaction(&[ARCIUM_PROG_ID, crate::ID], 0));
+    }
+
+    #[test]
+    fn out_of_range_index_is_refused() {
+        assert!(!is_callback_transaction(&[], 0));
+        assert!(!is_callback_transaction(&[ARCIUM_PROG_ID], 1));
+    }
+}
@@ -0,0 +1,560 @@
+// Account byte layouts, for readers that parse this program's accounts
+// without Anchor: indexers in other languages, light clients, and
+// explorers. Every account type is listed with its size and the offset,
+// size, and type of each field, offsets counted from the start of the
+// account data, so including the 8-byte discriminator (the first 8
+// bytes of SHA-256 over `account:<Name>`). Integers are little-endian
+// and fields are packed as listed; zero-copy accounts carry their
+// alignment padding as an explicit field.
+//
+// The build script includes this file and writes the same tables as
+// JSON, exported as `ACCOUNT_LAYOUTS_JSON`. It has no dependencies on the
+// rest of the crate for that reason; the crate asserts at compile time
+// that the zero-copy offsets and every account's size agree with the
+// account structs.
+
+/// Type of one element of a field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldType {
+    U8,
+    U16,
+    U32,
+    U64,
+    I64,
+    U128,
+    /// One byte, 0 or 1
+    Bool,
+    /// 32-byte Ed25519 public key
+    Pubkey,
+    /// Opaque bytes of the given width
+    Bytes(usize),
+}
+
+impl FieldType {
+    pub const fn size(self) -> usize {
+        match self {
+            FieldType::U8 | FieldType::Bool => 1,
+            FieldType::U16 => 2,
+            FieldType::U32 => 4,
+            FieldType::U64 | FieldType::I64 => 8,
+            FieldType::U128 => 16,
+            FieldType::Pubkey => 32,
+            FieldType::Bytes(width) => width,
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            FieldType::U8 => "u8",
+            FieldType::U16 => "u16",
+            FieldType::U32 => "u32",
+            FieldType::U64 => "u64",
+            FieldType::I64 => "i64",
+            FieldType::U128 => "u128",
+            FieldType::Bool => "bool",
+            FieldType::Pubkey => "pubkey",
+            FieldType::Bytes(_) => "bytes",
+        }
+    }
+}
+
+/// One field: `count` consecutive elements of `ty` at `offset`, 1 for a
+/// plain field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldLayout {
+    pub name: &'static str,
+    pub offset: usize,
+    pub ty: FieldType,
+    pub count: usize,
+}
+
+impl FieldLayout {
+    pub const fn one(name: &'static str, offset: usize, ty: FieldType) -> Self {
+        FieldLayout { name, offset, ty, count: 1 }
+    }
+
+    pub const fn array(name: &'static str, offset: usize, ty: FieldType, count: usize) -> Self {
+        FieldLayout { name, offset, ty, count }
+    }
+
+    pub const fn size(&self) -> usize {
+        self.ty.size() * self.count
+    }
+}
+
+/// An account type's fields, in order, after the discriminator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AccountLayout {
+    pub name: &'static str,
+    /// Account data length, discriminator included
+    pub size: usize,
+    pub fields: &'static [FieldLayout],
+}
+
+impl AccountLayout {
+    /// Whether the fields start after the discriminator, follow each
+    /// other without gaps, and end at `size`.
+    pub const fn is_packed(&self) -> bool {
+        let mut end = DISCRIMINATOR_LEN;
+        let mut i = 0;
+        while i < self.fields.len() {
+            if self.fields[i].offset != end {
+                return false;
+            }
+            end += self.fields[i].size();
+            i += 1;
+        }
+        end == self.size
+    }
+}
+
+/// Bytes of Anchor discriminator before the first field.
+pub const DISCRIMINATOR_LEN: usize = 8;
+
+use FieldType::*;
+
+const B32: FieldType = Bytes(32);
+
+/// `DiscoverySession`, zero-copy
+pub const DISCOVERY_SESSION: AccountLayout = AccountLayout {
+    name: "DiscoverySession",
+    size: 600,
+    fields: &[
+        FieldLayout::one("session_id", 8, B32),
+        FieldLayout::one("alice", 40, Pubkey),
+        FieldLayout::one("bob", 72, Pubkey),
+        FieldLayout::one("commitment", 104, B32),
+        FieldLayout::one("round", 136, U32),
+        FieldLayout::one("match_count", 140, U32),
+        FieldLayout::one("status", 144, U8),
+        FieldLayout::one("bump", 145, U8),
+        FieldLayout::one("mode", 146, U8),
+        FieldLayout::one("namespace", 147, U8),
+        FieldLayout::one("hash_scheme", 148, U8),
+        FieldLayout::one("rejected_party", 149, U8),
+        FieldLayout::one("public_count", 150, U8),
+        FieldLayout::one("version", 151, U8),
+        FieldLayout::array("stage_offsets", 152, U64, 4),
+        FieldLayout::array("finalized_slots", 184, U64, 4),
+        FieldLayout::one("join_deadline", 216, I64),
+        FieldLayout::one("purged_slot", 224, U64),
+        FieldLayout::one("callback_consumed", 232, U8),
+        FieldLayout::one("rent_payer", 233, Pubkey),
+        FieldLayout::one("notification_target", 265, B32),
+        FieldLayout::one("app_id", 297, B32),
+        FieldLayout::one("set_chunk_count", 329, U8),
+        FieldLayout::one("set_pending_chunk", 330, U8),
+        FieldLayout::one("hide_counts", 331, U8),
+        FieldLayout::one("capacity", 332, U8),
+        FieldLayout::one("global_stats_bump", 333, U8),
+        FieldLayout::one("app_stats_bump", 334, U8),
+        FieldLayout::one("reveal_window", 335, U8),
+        FieldLayout::one("alice_windows", 336, U8),
+        FieldLayout::one("bob_windows", 337, U8),
+        FieldLayout::one("app_hook", 338, U8),
+        FieldLayout::one("connection_consent", 339, U8),
+        FieldLayout::one("state_version", 340, U8),
+        FieldLayout::one("_reserved", 341, Bytes(3)),
+        FieldLayout::array("stage_fees", 344, U64, 4),
+        FieldLayout::one("refundable_fee", 376, U64),
+        FieldLayout::one("memo", 384, B32),
+        FieldLayout::one("set_root", 416, B32),
+        FieldLayout::one("set_chunks_done", 448, U64),
+        FieldLayout::one("reveal_after_slot", 456, U64),
+        FieldLayout::one("alice_receipt", 464, B32),
+        FieldLayout::one("bob_receipt", 496, B32),
+        FieldLayout::one("last_ping_slot", 528, U64),
+        FieldLayout::one("intended_bob", 536, Pubkey),
+        FieldLayout::one("invite_hash", 568, B32),
+    ],
+};
+
+/// `SessionStateHandle`
+pub const SESSION_STATE_HANDLE: AccountLayout = AccountLayout {
+    name: "SessionStateHandle",
+    size: 93,
+    fields: &[
+        FieldLayout::one("session", 8, Pubkey),
+        FieldLayout::one("state_nonce", 40, U128),
+        FieldLayout::one("state_hash", 56, B32),
+        FieldLayout::one("generation", 88, U32),
+        FieldLayout::one("bump", 92, U8),
+    ],
+};
+
+/// `SessionTemplate`
+pub const SESSION_TEMPLATE: AccountLayout = AccountLayout {
+    name: "SessionTemplate",
+    size: 154,
+    fields: &[
+        FieldLayout::one("app_id", 8, B32),
+        FieldLayout::one("template_id", 40, B32),
+        FieldLayout::one("authority", 72, Pubkey),
+        FieldLayout::one("mode", 104, U8),
+        FieldLayout::one("namespace", 105, U8),
+        FieldLayout::one("hash_scheme", 106, U8),
+        FieldLayout::one("public_count", 107, Bool),
+        FieldLayout::one("hide_counts", 108, Bool),
+        FieldLayout::one("join_ttl", 109, U32),
+        FieldLayout::one("reveal_delay", 113, U64),
+        FieldLayout::one("sponsor", 121, Pubkey),
+        FieldLayout::one("bump", 153, U8),
+    ],
+};
+
+/// `DiscoveryPool`
+pub const DISCOVERY_POOL: AccountLayout = AccountLayout {
+    name: "DiscoveryPool",
+    size: 1188,
+    fields: &[
+        FieldLayout::one("pool_id", 8, B32),
+        FieldLayout::one("host", 40, Pubkey),
+        FieldLayout::one("status", 72, U8),
+        FieldLayout::one("namespace", 73, U8),
+        FieldLayout::one("hash_scheme", 74, U8),
+        FieldLayout::array("encrypted_hashes", 75, B32, 32),
+        FieldLayout::one("encrypted_count", 1099, B32),
+        FieldLayout::one("encrypted_namespace", 1131, B32),
+        FieldLayout::one("nonce", 1163, U128),
+        FieldLayout::one("match_runs", 1179, U64),
+        FieldLayout::one("bump", 1187, U8),
+    ],
+};
+
+/// `DiscoveryBlocklist`
+pub const DISCOVERY_BLOCKLIST: AccountLayout = AccountLayout {
+    name: "DiscoveryBlocklist",
+    size: 1188,
+    fields: &[
+        FieldLayout::one("blocklist_id", 8, B32),
+        FieldLayout::one("moderator", 40, Pubkey),
+        FieldLayout::one("status", 72, U8),
+        FieldLayout::one("namespace", 73, U8),
+        FieldLayout::one("hash_scheme", 74, U8),
+        FieldLayout::array("encrypted_hashes", 75, B32, 32),
+        FieldLayout::one("encrypted_count", 1099, B32),
+        FieldLayout::one("encrypted_namespace", 1131, B32),
+        FieldLayout::one("nonce", 1163, U128),
+        FieldLayout::one("checks", 1179, U64),
+        FieldLayout::one("bump", 1187, U8),
+    ],
+};
+
+/// `StagedList`
+pub const STAGED_LIST: AccountLayout = AccountLayout {
+    name: "StagedList",
+    size: 1243,
+    fields: &[
+        FieldLayout::one("owner", 8, Pubkey),
+        FieldLayout::one("peer", 40, Pubkey),
+        FieldLayout::one("rent_payer", 72, Pubkey),
+        FieldLayout::one("status", 104, U8),
+        FieldLayout::one("hash_scheme", 105, U8),
+        FieldLayout::one("bump", 106, U8),
+        FieldLayout::one("pubkey", 107, B32),
+        FieldLayout::array("encrypted_hashes", 139, B32, 32),
+        FieldLayout::one("encrypted_count", 1163, B32),
+        FieldLayout::one("encrypted_namespace", 1195, B32),
+        FieldLayout::one("nonce", 1227, U128),
+    ],
+};
+
+/// `DiscoveryDatabase`, zero-copy
+pub const DISCOVERY_DATABASE: AccountLayout = AccountLayout {
+    name: "DiscoveryDatabase",
+    size: 8928,
+    fields: &[
+        FieldLayout::one("database_id", 8, B32),
+        FieldLayout::one("provider", 40, Pubkey),
+        FieldLayout::array("encrypted_chunks", 72, B32, 272),
+        FieldLayout::array("nonces", 8776, Bytes(16), 8),
+        FieldLayout::one("queries", 8904, U64),
+        FieldLayout::one("epoch", 8912, U32),
+        FieldLayout::one("status", 8916, U8),
+        FieldLayout::one("namespace", 8917, U8),
+        FieldLayout::one("hash_scheme", 8918, U8),
+        FieldLayout::one("chunk_count", 8919, U8),
+        FieldLayout::one("bump", 8920, U8),
+        FieldLayout::one("_padding", 8921, Bytes(7)),
+    ],
+};
+
+/// `DiscoveryCardRegistry`
+pub const DISCOVERY_CARD_REGISTRY: AccountLayout = AccountLayout {
+    name: "DiscoveryCardRegistry",
+    size: 2220,
+    fields: &[
+        FieldLayout::one("registry_id", 8, B32),
+        FieldLayout::one("authority", 40, Pubkey),
+        FieldLayout::one("status", 72, U8),
+        FieldLayout::one("namespace", 73, U8),
+        FieldLayout::one("hash_scheme", 74, U8),
+        FieldLayout::array("encrypted_registry", 75, B32, 66),
+        FieldLayout::one("nonce", 2187, U128),
+        FieldLayout::one("publishes", 2203, U64),
+        FieldLayout::one("lookups", 2211, U64),
+        FieldLayout::one("bump", 2219, U8),
+    ],
+};
+
+/// `GlobalStats`; `usage.*` are the `UsageCounters` fields
+pub const GLOBAL_STATS: AccountLayout = AccountLayout {
+    name: "GlobalStats",
+    size: 41,
+    fields: &[
+        FieldLayout::one("usage.sessions_created", 8, U64),
+        FieldLayout::one("usage.matches_completed", 16, U64),
+        FieldLayout::one("usage.submissions_rejected", 24, U64),
+        FieldLayout::one("usage.computations", 32, U64),
+        FieldLayout::one("bump", 40, U8),
+    ],
+};
+
+/// `AppStats`
+pub const APP_STATS: AccountLayout = AccountLayout {
+    name: "AppStats",
+    size: 73,
+    fields: &[
+        FieldLayout::one("app_id", 8, B32),
+        FieldLayout::one("usage.sessions_created", 40, U64),
+        FieldLayout::one("usage.matches_completed", 48, U64),
+        FieldLayout::one("usage.submissions_rejected", 56, U64),
+        FieldLayout::one("usage.computations", 64, U64),
+        FieldLayout::one("bump", 72, U8),
+    ],
+};
+
+/// `ProtocolConfig`
+pub const PROTOCOL_CONFIG: AccountLayout = AccountLayout {
+    name: "ProtocolConfig",
+    size: 94,
+    fields: &[
+        FieldLayout::one("admin", 8, Pubkey),
+        FieldLayout::one("computation_price", 40, U64),
+        FieldLayout::one("fee_pool_share_bps", 48, U16),
+        FieldLayout::one("refund_bps", 50, U16),
+        FieldLayout::one("min_client_version", 52, U16),
+        FieldLayout::one("max_pair_sessions", 54, U16),
+        FieldLayout::one("query_budget", 56, U32),
+        FieldLayout::one("gatekeeper", 60, U8),
+        FieldLayout::one("gatekeeper_network", 61, Pubkey),
+        FieldLayout::one("bump", 93, U8),
+    ],
+};
+
+/// `Treasury`
+pub const TREASURY: AccountLayout = AccountLayout {
+    name: "Treasury",
+    size: 33,
+    fields: &[
+        FieldLayout::one("collected", 8, U64),
+        FieldLayout::one("fee_pool_topped_up", 16, U64),
+        FieldLayout::one("refunded", 24, U64),
+        FieldLayout::one("bump", 32, U8),
+    ],
+};
+
+/// `CreditAccount`
+pub const CREDIT_ACCOUNT: AccountLayout = AccountLayout {
+    name: "CreditAccount",
+    size: 81,
+    fields: &[
+        FieldLayout::one("app_id", 8, B32),
+        FieldLayout::one("authority", 40, Pubkey),
+        FieldLayout::one("balance", 72, U64),
+        FieldLayout::one("bump", 80, U8),
+    ],
+};
+
+/// `ActivityLog`
+pub const ACTIVITY_LOG: AccountLayout = AccountLayout {
+    name: "ActivityLog",
+    size: 699,
+    fields: &[
+        FieldLayout::one("wallet", 8, Pubkey),
+        FieldLayout::array("session_ids", 40, B32, 16),
+        FieldLayout::array("slots", 552, U64, 16),
+        FieldLayout::array("kinds", 680, U8, 16),
+        FieldLayout::one("head", 696, U8),
+        FieldLayout::one("len", 697, U8),
+        FieldLayout::one("bump", 698, U8),
+    ],
+};
+
+pub const DEPLOYMENT_REGISTRY: AccountLayout = AccountLayout {
+    name: "DeploymentRegistry",
+    size: 162,
+    fields: &[
+        FieldLayout::one("program_version", 8, U16),
+        FieldLayout::one("session_version", 10, U8),
+        FieldLayout::array("circuit_versions", 11, U16, 33),
+        FieldLayout::one("initialized_comp_defs", 77, U64),
+        FieldLayout::one("mxe", 85, Pubkey),
+        FieldLayout::one("cluster_offset", 117, U32),
+        FieldLayout::one("mxe_x25519_pubkey", 121, B32),
+        FieldLayout::one("updated_slot", 153, U64),
+        FieldLayout::one("bump", 161, U8),
+    ],
+};
+
+/// `InviteClaim`
+pub const INVITE_CLAIM: AccountLayout = AccountLayout {
+    name: "InviteClaim",
+    size: 113,
+    fields: &[
+        FieldLayout::one("session", 8, Pubkey),
+        FieldLayout::one("bob", 40, Pubkey),
+        FieldLayout::one("commitment", 72, B32),
+        FieldLayout::one("slot", 104, U64),
+        FieldLayout::one("bump", 112, U8),
+    ],
+};
+
+/// `AppLookupTable`
+pub const APP_LOOKUP_TABLE: AccountLayout = AccountLayout {
+    name: "AppLookupTable",
+    size: 73,
+    fields: &[
+        FieldLayout::one("app_id", 8, B32),
+        FieldLayout::one("table", 40, Pubkey),
+        FieldLayout::one("bump", 72, U8),
+    ],
+};
+
+/// `AppHook`
+pub const APP_HOOK: AccountLayout = AccountLayout {
+    name: "AppHook",
+    size: 203,
+    fields: &[
+        FieldLayout::one("session", 8, Pubkey),
+        FieldLayout::one("program", 40, Pubkey),
+        FieldLayout::array("accounts", 72, Pubkey, 4),
+        FieldLayout::one("writable", 200, U8),
+        FieldLayout::one("account_count", 201, U8),
+        FieldLayout::one("bump", 202, U8),
+    ],
+};
+
+/// `ConnectionAttestation`
+pub const CONNECTION_ATTESTATION: AccountLayout = AccountLayout {
+    name: "ConnectionAttestation",
+    size: 145,
+    fields: &[
+        FieldLayout::one("party_a", 8, Pubkey),
+        FieldLayout::one("party_b", 40, Pubkey),
+        FieldLayout::one("session_id", 72, B32),
+        FieldLayout::one("app_id", 104, B32),
+        FieldLayout::one("attested_at", 136, I64),
+        FieldLayout::one("bump", 144, U8),
+    ],
+};
+
+/// `PairQuota`
+pub const PAIR_QUOTA: AccountLayout = AccountLayout {
+    name: "PairQuota",
+    size: 91,
+    fields: &[
+        FieldLayout::one("party_a", 8, Pubkey),
+        FieldLayout::one("party_b", 40, Pubkey),
+        FieldLayout::one("window_start", 72, I64),
+        FieldLayout::one("sessions", 80, U16),
+        FieldLayout::array("queried_slots", 82, U32, 2),
+        FieldLayout::one("bump", 90, U8),
+    ],
+};
+
+/// `ConsentRecord`
+pub const CONSENT_RECORD: AccountLayout = AccountLayout {
+    name: "ConsentRecord",
+    size: 427,
+    fields: &[
+        FieldLayout::one("wallet", 8, Pubkey),
+        FieldLayout::array("counterparties", 40, Pubkey, 8),
+        FieldLayout::one("counterparty_count", 296, U8),
+        FieldLayout::array("apps", 297, B32, 4),
+        FieldLayout::one("app_count", 425, U8),
+        FieldLayout::one("bump", 426, U8),
+    ],
+};
+
+/// `BlockedWallet`
+pub const BLOCKED_WALLET: AccountLayout = AccountLayout {
+    name: "BlockedWallet",
+    size: 49,
+    fields: &[
+        FieldLayout::one("wallet", 8, Pubkey),
+        FieldLayout::one("blocked_at", 40, I64),
+        FieldLayout::one("bump", 48, U8),
+    ],
+};
+
+/// Every account type the program owns.
+pub const ACCOUNTS: &[AccountLayout] = &[
+    DISCOVERY_SESSION,
+    SESSION_STATE_HANDLE,
+    SESSION_TEMPLATE,
+    DISCOVERY_POOL,
+    DISCOVERY_BLOCKLIST,
+    STAGED_LIST,
+    DISCOVERY_DATABASE,
+    DISCOVERY_CARD_REGISTRY,
+    GLOBAL_STATS,
+    APP_STATS,
+    PROTOCOL_CONFIG,
+    TREASURY,
+    CREDIT_ACCOUNT,
+    ACTIVITY_LOG,
+    DEPLOYMENT_REGISTRY,
+    INVITE_CLAIM,
+    APP_LOOKUP_TABLE,
+    APP_HOOK,
+    CONNECTION_ATTESTATION,
+    PAIR_QUOTA,
+    CONSENT_RECORD,
+    BLOCKED_WALLET,
+];
+
+const _: () = {
+    let mut i = 0;
+    while i < ACCOUNTS.len() {
+        assert!(ACCOUNTS[i].is_packed());
+        i += 1;
+    }
+};
+
+/// `accounts` as JSON: `{"discriminator_len": 8, "accounts": [{"name",
+/// "size", "fields": [{"name", "offset", "size", "type", "count"}]}]}`,
+/// where `type` is a `FieldType` name and a `bytes` element is
+/// `size / count` bytes wide.
+pub fn to_json(accounts: &[AccountLayout]) -> String {
+    let accounts: Vec<String> = accounts
+        .iter()
+        .map(|account| {
+            let fields: Vec<String> = account
+                .fields
+                .iter()
+                .map(|field| {
+                    format!(
+                        "{{\"name\": \"{}\", \"offset\": {}, \"size\": {}, \"type\": \"{}\", \"count\": {}}}",
+                        field.name,
+                        field.offset,
+                        field.size(),
+                        field.ty.name(),
+                        field.count
+                    )
+                })
+                .collect();
+            format!(
+                "    {{\"name\": \"{}\", \"size\": {}, \"fields\": [\n      {}\n    ]}}",
+                account.name,
+                account.size,
+                fields.join(",\n      ")
+            )
+        })
+        .collect();
+    format!(
+        "{{\n  \"discriminator_len\": {},\n  \"accounts\": [\n{}\n  ]\n}}\n",
+        DISCRIMINATOR_LEN,
+        accounts.join(",\n")
+    )
+}
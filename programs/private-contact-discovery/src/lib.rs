@@ -7,14 +7,212 @@
 // Users submit encrypted contact hashes; the MPC nodes compute
 // the intersection without anyone seeing the full lists.
 
+use std::collections::BTreeSet;
+
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::address_lookup_table;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::{invoke_signed, set_return_data};
 use arcium_anchor::prelude::*;
 
+/// A `key=value` log line for following a session in an explorer, with
+/// `verbose-logs`. Compiled out otherwise, so deployed builds pay no
+/// compute for it.
+#[cfg(feature = "verbose-logs")]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        msg!($($arg)*)
+    };
+}
+
+#[cfg(not(feature = "verbose-logs"))]
+macro_rules! trace {
+    ($($arg:tt)*) => {};
+}
+
+mod args;
+pub mod gatekeeper;
+mod guard;
+pub mod layout;
+mod merkle;
+mod permit;
+pub mod soulbound;
+mod state;
+#[cfg(feature = "testing")]
+pub mod testing;
+
+// With `testing`, computations are recorded in-process instead of queued
+// with Arcium (see testing.rs)
+#[cfg(feature = "testing")]
+use testing::queue_computation;
+
+/// A callback's output, verified against the cluster's signature. With
+/// `testing`, the output is the next one a test queued instead.
+#[cfg(not(feature = "testing"))]
+macro_rules! verify_output {
+    ($output:expr, $cluster:expr, $computation:expr $(,)?) => {
+        $output.verify_output($cluster, $computation)
+    };
+}
+
+#[cfg(feature = "testing")]
+macro_rules! verify_output {
+    ($output:expr, $cluster:expr, $computation:expr $(,)?) => {
+        $crate::testing::verify_output(&$output, $cluster, $computation)
+    };
+}
+
+/// `queue_computation`, logging the computation's offset with
+/// `verbose-logs`.
+macro_rules! queue {
+    ($accounts:expr, $computation_offset:expr, $($rest:expr),+ $(,)?) => {{
+        let computation_offset: u64 = $computation_offset;
+        trace!("queue computation_offset={}", computation_offset);
+        queue_computation($accounts, computation_offset, $($rest),+)
+    }};
+}
+
+pub use merkle::{set_leaf, set_proof, set_root, verify_set_proof, MAX_SET_CHUNKS};
+pub use permit::{ed25519_instruction_data, permit_message, PERMIT_DOMAIN};
+pub use state::{ComputationStage, Status};
+
+/// `layout::ACCOUNTS` as JSON, generated by the build script, for
+/// indexers that parse accounts by byte offset.
+pub const ACCOUNT_LAYOUTS_JSON: &str = include_str!(concat!(env!("OUT_DIR"), "/account_layouts.json"));
+
 // Computation definition offsets for each encrypted instruction
 const COMP_DEF_OFFSET_INIT_SESSION: u32 = comp_def_offset("init_session");
 const COMP_DEF_OFFSET_SUBMIT_ALICE: u32 = comp_def_offset("submit_contacts_alice");
 const COMP_DEF_OFFSET_SUBMIT_AND_MATCH: u32 = comp_def_offset("submit_and_match");
+const COMP_DEF_OFFSET_SUBMIT_AND_MATCH_16: u32 = comp_def_offset("submit_and_match_16");
+const COMP_DEF_OFFSET_SUBMIT_AND_MATCH_8: u32 = comp_def_offset("submit_and_match_8");
+const COMP_DEF_OFFSET_SUBMIT_ALICE_16: u32 = comp_def_offset("submit_contacts_alice_16");
+const COMP_DEF_OFFSET_SUBMIT_ALICE_8: u32 = comp_def_offset("submit_contacts_alice_8");
 const COMP_DEF_OFFSET_REVEAL_ALICE: u32 = comp_def_offset("reveal_alice_matches");
+const COMP_DEF_OFFSET_PUSH_DELTA: u32 = comp_def_offset("push_delta");
+const COMP_DEF_OFFSET_CREATE_POOL: u32 = comp_def_offset("create_pool");
+const COMP_DEF_OFFSET_MATCH_POOL: u32 = comp_def_offset("match_against_pool");
+const COMP_DEF_OFFSET_SUBMIT_AND_SCORE: u32 = comp_def_offset("submit_and_score");
+const COMP_DEF_OFFSET_REVEAL_ALICE_SCORE: u32 = comp_def_offset("reveal_alice_score");
+const COMP_DEF_OFFSET_SUBMIT_BOB: u32 = comp_def_offset("submit_contacts_bob");
+const COMP_DEF_OFFSET_SUBMIT_CONNECTOR: u32 = comp_def_offset("submit_connector");
+const COMP_DEF_OFFSET_REVEAL_SECOND_DEGREE: u32 = comp_def_offset("reveal_second_degree");
+const COMP_DEF_OFFSET_CREATE_BLOCKLIST: u32 = comp_def_offset("create_blocklist");
+const COMP_DEF_OFFSET_CHECK_BLOCKLIST: u32 = comp_def_offset("check_against_blocklist");
+const COMP_DEF_OFFSET_SUBMIT_AND_CHECK_FOLLOW: u32 = comp_def_offset("submit_and_check_follow");
+const COMP_DEF_OFFSET_REVEAL_BOB: u32 = comp_def_offset("reveal_bob_matches");
+const COMP_DEF_OFFSET_PURGE_SESSION_STATE: u32 = comp_def_offset("purge_session_state");
+const COMP_DEF_OFFSET_CREATE_CARD_REGISTRY: u32 = comp_def_offset("create_card_registry");
+const COMP_DEF_OFFSET_PUBLISH_CONTACT_CARD: u32 = comp_def_offset("publish_contact_card");
+const COMP_DEF_OFFSET_MATCH_CONTACT_CARDS: u32 = comp_def_offset("match_contact_cards");
+const COMP_DEF_OFFSET_MATCH_SET_CHUNK: u32 = comp_def_offset("match_set_chunk");
+const COMP_DEF_OFFSET_LOAD_DATABASE_CHUNK: u32 = comp_def_offset("load_database_chunk");
+const COMP_DEF_OFFSET_QUERY_PSI: u32 = comp_def_offset("query_psi");
+const COMP_DEF_OFFSET_CHECK_MEMBERSHIP: u32 = comp_def_offset("check_membership");
+const COMP_DEF_OFFSET_CHECK_MEMBERSHIP_BATCH: u32 = comp_def_offset("check_membership_batch");
+const COMP_DEF_OFFSET_REVEAL_BOTH: u32 = comp_def_offset("reveal_both");
+const COMP_DEF_OFFSET_INSTANT_MATCH: u32 = comp_def_offset("instant_match");
+const COMP_DEF_OFFSET_INIT_AND_SUBMIT_ALICE: u32 = comp_def_offset("init_and_submit_alice");
+const COMP_DEF_OFFSET_MIGRATE_SESSION_STATE: u32 = comp_def_offset("migrate_session_state");
+
+/// Every circuit and its computation definition offset, in the order
+/// `view_session` expects their accounts.
+const CIRCUITS: [(&str, u32); 33] = [
+    ("init_session", COMP_DEF_OFFSET_INIT_SESSION),
+    ("submit_contacts_alice", COMP_DEF_OFFSET_SUBMIT_ALICE),
+    ("submit_and_match", COMP_DEF_OFFSET_SUBMIT_AND_MATCH),
+    ("reveal_alice_matches", COMP_DEF_OFFSET_REVEAL_ALICE),
+    ("push_delta", COMP_DEF_OFFSET_PUSH_DELTA),
+    ("create_pool", COMP_DEF_OFFSET_CREATE_POOL),
+    ("match_against_pool", COMP_DEF_OFFSET_MATCH_POOL),
+    ("submit_and_score", COMP_DEF_OFFSET_SUBMIT_AND_SCORE),
+    ("reveal_alice_score", COMP_DEF_OFFSET_REVEAL_ALICE_SCORE),
+    ("submit_contacts_bob", COMP_DEF_OFFSET_SUBMIT_BOB),
+    ("submit_connector", COMP_DEF_OFFSET_SUBMIT_CONNECTOR),
+    ("reveal_second_degree", COMP_DEF_OFFSET_REVEAL_SECOND_DEGREE),
+    ("create_blocklist", COMP_DEF_OFFSET_CREATE_BLOCKLIST),
+    ("check_against_blocklist", COMP_DEF_OFFSET_CHECK_BLOCKLIST),
+    ("submit_and_check_follow", COMP_DEF_OFFSET_SUBMIT_AND_CHECK_FOLLOW),
+    ("reveal_bob_matches", COMP_DEF_OFFSET_REVEAL_BOB),
+    ("purge_session_state", COMP_DEF_OFFSET_PURGE_SESSION_STATE),
+    ("create_card_registry", COMP_DEF_OFFSET_CREATE_CARD_REGISTRY),
+    ("publish_contact_card", COMP_DEF_OFFSET_PUBLISH_CONTACT_CARD),
+    ("match_contact_cards", COMP_DEF_OFFSET_MATCH_CONTACT_CARDS),
+    ("match_set_chunk", COMP_DEF_OFFSET_MATCH_SET_CHUNK),
+    ("load_database_chunk", COMP_DEF_OFFSET_LOAD_DATABASE_CHUNK),
+    ("query_psi", COMP_DEF_OFFSET_QUERY_PSI),
+    ("check_membership", COMP_DEF_OFFSET_CHECK_MEMBERSHIP),
+    ("check_membership_batch", COMP_DEF_OFFSET_CHECK_MEMBERSHIP_BATCH),
+    ("reveal_both", COMP_DEF_OFFSET_REVEAL_BOTH),
+    ("submit_and_match_16", COMP_DEF_OFFSET_SUBMIT_AND_MATCH_16),
+    ("submit_and_match_8", COMP_DEF_OFFSET_SUBMIT_AND_MATCH_8),
+    ("submit_contacts_alice_16", COMP_DEF_OFFSET_SUBMIT_ALICE_16),
+    ("submit_contacts_alice_8", COMP_DEF_OFFSET_SUBMIT_ALICE_8),
+    ("instant_match", COMP_DEF_OFFSET_INSTANT_MATCH),
+    ("init_and_submit_alice", COMP_DEF_OFFSET_INIT_AND_SUBMIT_ALICE),
+    ("migrate_session_state", COMP_DEF_OFFSET_MIGRATE_SESSION_STATE),
+];
+
+/// Version of the program's interface: instruction arguments, account
+/// layouts and events. Bump on any change a client must be rebuilt for;
+/// `sync_deployment` records it in the `DeploymentRegistry`, and clients
+/// send the version they were built for with `create_session`.
+pub const PROGRAM_VERSION: u16 = 17;
+
+/// Version of each circuit's interface, in `CIRCUITS` order. Bump a
+/// circuit's entry when its inputs or outputs change, so clients built
+/// against the old circuit refuse to encrypt for the new one.
+pub const CIRCUIT_VERSIONS: [u16; 33] = [1; 33];
+
+/// `DeploymentRegistry::cluster_offset` when the MXE has no cluster.
+pub const NO_CLUSTER: u32 = u32::MAX;
+
+/// Result pages a reveal can return: stored results hold 32 slots and
+/// each page carries 32 (`PAGE_SIZE` in the circuits). Grows with the
+/// circuits' capacity; pages at or past it are rejected before queueing.
+pub const RESULT_PAGES: u8 = 1;
+
+/// Slots `ping_session` waits between heartbeats of one session, about
+/// a minute.
+pub const PING_INTERVAL_SLOTS: u64 = 150;
+
+/// `DiscoverySession::alice_windows` (or `bob_windows`) once every
+/// result window has been delivered, one bit per page.
+pub const ALL_WINDOWS: u8 = ((1u16 << RESULT_PAGES) - 1) as u8;
+
+/// `ComputationFailedEvent::stage` of a computation outside the session
+/// stages: pools, databases, blocklists, card registries, instant
+/// matches, and state purges and migrations. Also
+/// `SessionHeartbeat::pending_stage` when no callback is outstanding.
+pub const NO_STAGE: u8 = u8::MAX;
+
+// The window bitmaps are one byte
+const _: () = assert!(RESULT_PAGES <= 8);
+
+/// `DiscoverySession::connection_consent` bit of Alice's consent.
+pub const CONSENT_ALICE: u8 = 1;
+/// `DiscoverySession::connection_consent` bit of Bob's consent.
+pub const CONSENT_BOB: u8 = 2;
+
+/// Items one `queue_batch` call queues at most. Each brings its own
+/// dozen-odd accounts, so four about fill a transaction.
+pub const MAX_BATCH: usize = 4;
+
+/// Sessions one `sweep_sessions` call closes at most. Each brings three
+/// accounts, so eight about fill a transaction.
+pub const MAX_SWEEP: usize = 8;
+
+/// Slots a purged or failed session stays Alice's to close before
+/// anyone may sweep it, about a day.
+pub const SWEEP_DELAY_SLOTS: u64 = 216_000;
+
+/// Share of a swept session's rent paid to the sweeper, in basis points.
+/// The rest, and the state handle's rent, go to the rent recipient.
+pub const SWEEP_REWARD_BPS: u64 = 500;
+
+/// Seconds in the window `ProtocolConfig::max_pair_sessions` counts
+/// sessions over: a day.
+pub const PAIR_WINDOW_SECS: i64 = 86_400;
 
 declare_id!("7RFXacB7U6bs3MnJYmue1EgPgbiUC9JsjbzWVDDPM64t");
 
@@ -44,49 +242,336 @@ pub mod private_contact_discovery {
         Ok(())
     }
 
+    /// Initialize the computation definition for submit_and_match_16
+    pub fn init_submit_and_match_16_comp_def(ctx: Context<InitSubmitAndMatch16CompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for submit_and_match_8
+    pub fn init_submit_and_match_8_comp_def(ctx: Context<InitSubmitAndMatch8CompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for submit_contacts_alice_16
+    pub fn init_submit_alice_16_comp_def(ctx: Context<InitSubmitAlice16CompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for submit_contacts_alice_8
+    pub fn init_submit_alice_8_comp_def(ctx: Context<InitSubmitAlice8CompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
     /// Initialize the computation definition for reveal_alice_matches
     pub fn init_reveal_alice_comp_def(ctx: Context<InitRevealAliceCompDef>) -> Result<()> {
         init_comp_def(ctx.accounts, None, None)?;
         Ok(())
     }
 
+    /// Initialize the computation definition for push_delta
+    pub fn init_push_delta_comp_def(ctx: Context<InitPushDeltaCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for create_pool
+    pub fn init_create_pool_comp_def(ctx: Context<InitCreatePoolCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for match_against_pool
+    pub fn init_match_pool_comp_def(ctx: Context<InitMatchPoolCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for submit_and_score
+    pub fn init_submit_and_score_comp_def(ctx: Context<InitSubmitAndScoreCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for reveal_alice_score
+    pub fn init_reveal_alice_score_comp_def(ctx: Context<InitRevealAliceScoreCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for submit_contacts_bob
+    pub fn init_submit_bob_comp_def(ctx: Context<InitSubmitBobCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for submit_connector
+    pub fn init_submit_connector_comp_def(ctx: Context<InitSubmitConnectorCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for reveal_second_degree
+    pub fn init_reveal_second_degree_comp_def(
+        ctx: Context<InitRevealSecondDegreeCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for create_blocklist
+    pub fn init_create_blocklist_comp_def(ctx: Context<InitCreateBlocklistCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for check_against_blocklist
+    pub fn init_check_blocklist_comp_def(ctx: Context<InitCheckBlocklistCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for submit_and_check_follow
+    pub fn init_submit_and_check_follow_comp_def(
+        ctx: Context<InitSubmitAndCheckFollowCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for reveal_bob_matches
+    pub fn init_reveal_bob_comp_def(ctx: Context<InitRevealBobCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for purge_session_state
+    pub fn init_purge_session_state_comp_def(
+        ctx: Context<InitPurgeSessionStateCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for migrate_session_state
+    pub fn init_migrate_session_state_comp_def(
+        ctx: Context<InitMigrateSessionStateCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for create_card_registry
+    pub fn init_create_card_registry_comp_def(
+        ctx: Context<InitCreateCardRegistryCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for publish_contact_card
+    pub fn init_publish_contact_card_comp_def(
+        ctx: Context<InitPublishContactCardCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for match_contact_cards
+    pub fn init_match_contact_cards_comp_def(
+        ctx: Context<InitMatchContactCardsCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for match_set_chunk
+    pub fn init_match_set_chunk_comp_def(
+        ctx: Context<InitMatchSetChunkCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for load_database_chunk
+    pub fn init_load_database_chunk_comp_def(
+        ctx: Context<InitLoadDatabaseChunkCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for query_psi
+    pub fn init_query_psi_comp_def(ctx: Context<InitQueryPsiCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for check_membership
+    pub fn init_check_membership_comp_def(
+        ctx: Context<InitCheckMembershipCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for check_membership_batch
+    pub fn init_check_membership_batch_comp_def(
+        ctx: Context<InitCheckMembershipBatchCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for reveal_both
+    pub fn init_reveal_both_comp_def(ctx: Context<InitRevealBothCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for instant_match
+    pub fn init_instant_match_comp_def(ctx: Context<InitInstantMatchCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for init_and_submit_alice
+    pub fn init_and_submit_alice_comp_def(ctx: Context<InitAndSubmitAliceCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Create the program-wide `GlobalStats` counters and the `AppStats`
+    /// bucket shared by sessions without an app. Once per deployment,
+    /// before the first session: `create_session` and every callback
+    /// update them.
+    pub fn init_global_stats(ctx: Context<InitGlobalStats>) -> Result<()> {
+        ctx.accounts.global_stats.bump = ctx.bumps.global_stats;
+        ctx.accounts.unattributed_app_stats.app_id = NO_APP_ID;
+        ctx.accounts.unattributed_app_stats.bump = ctx.bumps.unattributed_app_stats;
+        Ok(())
+    }
+
     // ============================================================
     // SESSION MANAGEMENT
     // ============================================================
 
-    /// Create a new PSI session between two parties.
-    /// Alice creates the session and initializes encrypted state.
+    /// Alice creates a PSI session and queues `init_session` for its
+    /// encrypted state. The options go to `SessionConfig`, whose fields
+    /// say what each does; the README covers the rest (app billing,
+    /// consent, invites, the version gate). Returns the session's
+    /// address and the queued offset, for programs that create sessions
+    /// by CPI.
     pub fn create_session(
         ctx: Context<CreateSession>,
+        client_version: u16,
         computation_offset: u64,
         session_id: [u8; 32],
+        app_id: [u8; 32],
         pubkey: [u8; 32],
         nonce: u128,
-    ) -> Result<()> {
-        let session = &mut ctx.accounts.session;
+        mode: u8,
+        namespace: u8,
+        hash_scheme: u8,
+        public_count: bool,
+        hide_counts: bool,
+        join_deadline: i64,
+        reveal_after_slot: u64,
+        capacity: u8,
+        memo: [u8; 32],
+        intended_bob: Pubkey,
+        invite_hash: [u8; 32],
+    ) -> Result<SessionQueued> {
+        require_client_version(&ctx.accounts.billing.config, client_version)?;
+        require!(
+            intended_bob == Pubkey::default() || invite_hash == NO_INVITE,
+            ErrorCode::InvalidInvite
+        );
+        let signers = [&*ctx.accounts.alice, &*ctx.accounts.payer];
+        ctx.accounts.billing.credit.require_authority(signers.into_iter().chain(ctx.remaining_accounts))?;
+        if intended_bob != Pubkey::default() {
+            let app = &ctx.accounts.billing.credit;
+            require_discovery_consent(ctx.remaining_accounts, &intended_bob, &ctx.accounts.alice.key(), app)?;
+        }
+        let config = SessionConfig {
+            mode,
+            namespace,
+            hash_scheme,
+            public_count,
+            hide_counts,
+            join_deadline,
+            reveal_after_slot,
+            capacity,
+        };
+        require_unblocked(ctx.remaining_accounts, &ctx.accounts.alice.key())?;
+        require_new_session(&session_id, &ctx.accounts.session)?;
+        config.validate()?;
+        require!(
+            join_deadline == 0 || join_deadline > Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidDeadline
+        );
+
+        let mut session = ctx.accounts.session.load_init()?;
         session.session_id = session_id;
-        session.alice = ctx.accounts.payer.key();
-        session.bob = Pubkey::default();
-        session.status = SessionStatus::AwaitingAlice as u8;
+        session.alice = ctx.accounts.alice.key();
+        session.rent_payer = ctx.accounts.payer.key();
         session.bump = ctx.bumps.session;
+        config.apply(&mut session);
+        session.app_id = app_id;
+        session.memo = memo;
+        session.intended_bob = intended_bob;
+        session.invite_hash = invite_hash;
+        session.global_stats_bump = ctx.accounts.global_stats.bump;
+        session.app_stats_bump = ctx.bumps.app_stats;
+
+        ctx.accounts.state_handle.session = ctx.accounts.session.key();
+        ctx.accounts.state_handle.bump = ctx.bumps.state_handle;
+
+        ctx.accounts.app_stats.app_id = app_id;
+        ctx.accounts.app_stats.bump = ctx.bumps.app_stats;
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Session);
 
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
         // Build args for encrypted init (dummy input to establish encryption)
-        let args = ArgBuilder::new()
-            .x25519_pubkey(pubkey)
-            .plaintext_u128(nonce)
-            .encrypted_u8([0u8; 32])
+        // and the namespace and session the MXE state is bound to
+        let args = args::party_key_args(pubkey, nonce)
+            .plaintext_u8(namespace)
+            .plaintext_u128(session.state_tag())
             .build();
 
-        queue_computation(
+        session.record_queued(ComputationStage::Init, computation_offset)?;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Init, &ctx.accounts.pool_account)?;
+        let app_stats = ctx.accounts.app_stats.key();
+        drop(session);
+        queue!(
             ctx.accounts,
             computation_offset,
             args,
             vec![InitSessionCallback::callback_ix(
                 computation_offset,
                 &ctx.accounts.mxe_account,
-                &[],
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.state_handle.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats_pda(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
             )?],
             1,
             0,
@@ -94,10 +579,15 @@ pub mod private_contact_discovery {
 
         emit!(SessionCreated {
             session_id,
-            alice: ctx.accounts.payer.key(),
+            memo,
+            alice: ctx.accounts.alice.key(),
         });
+        record_activity(ctx.remaining_accounts, &ctx.accounts.alice.key(), session_id, ActivityKind::Created)?;
 
-        Ok(())
+        Ok(SessionQueued {
+            session: ctx.accounts.session.key(),
+            computation_offset,
+        })
     }
 
     /// Callback for session initialization
@@ -106,646 +596,12217 @@ pub mod private_contact_discovery {
         ctx: Context<InitSessionCallback>,
         output: SignedComputationOutputs<InitSessionOutput>,
     ) -> Result<()> {
-        let _o = match output.verify_output(
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let state = match verify_output!(
+            output,
             &ctx.accounts.cluster_account,
             &ctx.accounts.computation_account,
         ) {
-            Ok(out) => out,
-            Err(_) => return Err(ErrorCode::ComputationFailed.into()),
+            Ok(InitSessionOutput { field_0 }) => field_0,
+            Err(error) => return fail_computation(&ctx.accounts.session, ComputationStage::Init, &error),
         };
+        return_result_account(&ctx.accounts.session.key());
 
-        emit!(SessionInitialized {});
+        let mut session = ctx.accounts.session.load_mut()?;
+        if !session.consume_callback(ComputationStage::Init, Clock::get()?.slot) {
+            return Ok(());
+        }
+        ctx.accounts.state_handle.record(state.nonce, &state.ciphertexts);
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Computation);
+        emit!(SessionInitialized {
+            session_id: session.session_id,
+            memo: session.memo,
+        });
 
         Ok(())
     }
 
-    // ============================================================
-    // ALICE SUBMITS CONTACTS
-    // ============================================================
-
-    /// Alice submits her encrypted contact hashes.
-    /// Contacts are hashed client-side (SHA-256 -> u128) before encryption.
-    pub fn submit_contacts_alice(
-        ctx: Context<SubmitContactsAlice>,
-        computation_offset: u64,
-        encrypted_hashes: [[u8; 32]; 32],
-        encrypted_count: [u8; 32],
-        pubkey: [u8; 32],
-        nonce: u128,
-    ) -> Result<()> {
-        require!(
-            ctx.accounts.session.status == SessionStatus::AwaitingAlice as u8,
-            ErrorCode::InvalidSessionState
-        );
+    /// Create a session without queueing `init_session`: its MXE state is
+    /// built by Alice's first submission, `init_and_submit_alice`, which
+    /// saves pairwise sessions a computation and its fee. Takes
+    /// `create_session`'s options and checks them the same way. Until
+    /// that submission lands the session has no state, so instructions
+    /// that read it fail with `StateNotInitialized`, and Alice can close
+    /// it without a purge. Returns the session's address.
+    pub fn create_stateless_session(
+        ctx: Context<CreateStatelessSession>,
+        client_version: u16,
+        session_id: [u8; 32],
+        app_id: [u8; 32],
+        mode: u8,
+        namespace: u8,
+        hash_scheme: u8,
+        public_count: bool,
+        hide_counts: bool,
+        join_deadline: i64,
+        reveal_after_slot: u64,
+        capacity: u8,
+        memo: [u8; 32],
+    ) -> Result<Pubkey> {
+        require_client_version(&ctx.accounts.config, client_version)?;
+        let signers = [&*ctx.accounts.alice, &*ctx.accounts.payer];
+        ctx.accounts.credit.require_authority(signers.into_iter().chain(ctx.remaining_accounts))?;
+        let config = SessionConfig {
+            mode,
+            namespace,
+            hash_scheme,
+            public_count,
+            hide_counts,
+            join_deadline,
+            reveal_after_slot,
+            capacity,
+        };
+        require_unblocked(ctx.remaining_accounts, &ctx.accounts.alice.key())?;
+        require_new_session(&session_id, &ctx.accounts.session)?;
+        config.validate()?;
         require!(
-            ctx.accounts.alice.key() == ctx.accounts.session.alice,
-            ErrorCode::Unauthorized
+            join_deadline == 0 || join_deadline > Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidDeadline
         );
 
-        ctx.accounts.session.status = SessionStatus::AwaitingBob as u8;
-        let session_id = ctx.accounts.session.session_id;
-
-        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
-
-        // Build args: ContactList struct = 32 x u128 hashes + u32 count
-        let mut builder = ArgBuilder::new()
-            .x25519_pubkey(pubkey)
-            .plaintext_u128(nonce);
-
-        // Each hash is an encrypted u128 (in 32-byte ciphertext)
-        for i in 0..32 {
-            builder = builder.encrypted_u128(encrypted_hashes[i]);
-        }
-        // Contact count
-        builder = builder.encrypted_u32(encrypted_count);
+        let mut session = ctx.accounts.session.load_init()?;
+        session.session_id = session_id;
+        session.alice = ctx.accounts.alice.key();
+        session.rent_payer = ctx.accounts.payer.key();
+        session.bump = ctx.bumps.session;
+        config.apply(&mut session);
+        session.app_id = app_id;
+        session.memo = memo;
+        session.global_stats_bump = ctx.accounts.global_stats.bump;
+        session.app_stats_bump = ctx.bumps.app_stats;
 
-        let args = builder.build();
+        ctx.accounts.state_handle.session = ctx.accounts.session.key();
+        ctx.accounts.state_handle.bump = ctx.bumps.state_handle;
 
-        queue_computation(
-            ctx.accounts,
-            computation_offset,
-            args,
-            vec![SubmitContactsAliceCallback::callback_ix(
-                computation_offset,
-                &ctx.accounts.mxe_account,
-                &[],
-            )?],
-            1,
-            0,
-        )?;
+        ctx.accounts.app_stats.app_id = app_id;
+        ctx.accounts.app_stats.bump = ctx.bumps.app_stats;
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Session);
 
-        emit!(ContactsSubmitted {
+        emit!(SessionCreated {
             session_id,
-            party: 1,
+            memo,
+            alice: ctx.accounts.alice.key(),
         });
+        record_activity(ctx.remaining_accounts, &ctx.accounts.alice.key(), session_id, ActivityKind::Created)?;
 
-        Ok(())
+        Ok(ctx.accounts.session.key())
     }
 
-    /// Callback for Alice's contact submission
-    #[arcium_callback(encrypted_ix = "submit_contacts_alice")]
-    pub fn submit_contacts_alice_callback(
-        ctx: Context<SubmitContactsAliceCallback>,
-        output: SignedComputationOutputs<SubmitContactsAliceOutput>,
+    // ============================================================
+    // SESSION TEMPLATES
+    // ============================================================
+
+    /// The authority of `app_id`'s credit account stores a preset session
+    /// configuration under `template_id`. `join_ttl` is the seconds Bob
+    /// gets to join a session created from it (0 for no deadline) and
+    /// `reveal_delay` the slots its result stays escrowed (0 for none).
+    /// `sponsor`, if not the default key, is the only payer sessions
+    /// from the template may be funded and billed through, so an app can
+    /// keep every session on its own backend. The options are validated
+    /// as `create_session` would, once, here.
+    pub fn create_session_template(
+        ctx: Context<CreateSessionTemplate>,
+        app_id: [u8; 32],
+        template_id: [u8; 32],
+        mode: u8,
+        namespace: u8,
+        hash_scheme: u8,
+        public_count: bool,
+        hide_counts: bool,
+        join_ttl: u32,
+        reveal_delay: u64,
+        sponsor: Pubkey,
     ) -> Result<()> {
-        let _o = match output.verify_output(
-            &ctx.accounts.cluster_account,
-            &ctx.accounts.computation_account,
-        ) {
-            Ok(out) => out,
-            Err(_) => return Err(ErrorCode::ComputationFailed.into()),
-        };
+        SessionConfig {
+            mode,
+            namespace,
+            hash_scheme,
+            public_count,
+            hide_counts,
+            join_deadline: 0,
+            reveal_after_slot: reveal_delay,
+            capacity: schema::MAX_CONTACTS as u8,
+        }
+        .validate()?;
+
+        let template = &mut ctx.accounts.template;
+        template.app_id = app_id;
+        template.template_id = template_id;
+        template.authority = ctx.accounts.authority.key();
+        template.mode = mode;
+        template.namespace = namespace;
+        template.hash_scheme = hash_scheme;
+        template.public_count = public_count;
+        template.hide_counts = hide_counts;
+        template.join_ttl = join_ttl;
+        template.reveal_delay = reveal_delay;
+        template.sponsor = sponsor;
+        template.bump = ctx.bumps.template;
 
-        emit!(AliceSubmitted {});
+        emit!(SessionTemplateCreated {
+            app_id,
+            template_id,
+            authority: template.authority,
+        });
 
         Ok(())
     }
 
-    // ============================================================
-    // BOB SUBMITS AND TRIGGERS MATCH
-    // ============================================================
+    /// The template's authority retires it and takes back its rent.
+    /// Sessions already created from it are unaffected.
+    pub fn close_session_template(ctx: Context<CloseSessionTemplate>) -> Result<()> {
+        emit!(SessionTemplateClosed {
+            app_id: ctx.accounts.template.app_id,
+            template_id: ctx.accounts.template.template_id,
+        });
+        Ok(())
+    }
 
-    /// Bob submits contacts AND the MPC computes the intersection.
-    /// This is the core PSI operation: 32x32 = 1024 encrypted comparisons.
-    /// Bob receives his matches immediately via the callback.
-    pub fn submit_and_match(
-        ctx: Context<SubmitAndMatch>,
+    /// `create_session` with the configuration taken from `template`:
+    /// the mode, namespace, hash scheme, count options, and app come from
+    /// the template, the join deadline is `join_ttl` seconds from now and
+    /// the reveal slot `reveal_delay` slots from now. Alice supplies only
+    /// her key and the session's id and memo. `client_version` is gated
+    /// as in `create_session`.
+    pub fn create_session_from_template(
+        ctx: Context<CreateSessionFromTemplate>,
+        client_version: u16,
         computation_offset: u64,
-        encrypted_hashes: [[u8; 32]; 32],
-        encrypted_count: [u8; 32],
+        session_id: [u8; 32],
         pubkey: [u8; 32],
         nonce: u128,
-    ) -> Result<()> {
+        memo: [u8; 32],
+    ) -> Result<SessionQueued> {
+        require_client_version(&ctx.accounts.billing.config, client_version)?;
+        require_unblocked(ctx.remaining_accounts, &ctx.accounts.alice.key())?;
+        require_new_session(&session_id, &ctx.accounts.session)?;
+        let template = &ctx.accounts.template;
         require!(
-            ctx.accounts.session.status == SessionStatus::AwaitingBob as u8,
-            ErrorCode::InvalidSessionState
+            template.sponsor == Pubkey::default() || template.sponsor == ctx.accounts.payer.key(),
+            ErrorCode::SponsorMismatch
         );
+        let clock = Clock::get()?;
+        let config = template.config(clock.unix_timestamp, clock.slot);
+        let app_id = template.app_id;
 
-        // Record Bob's identity and update status
-        ctx.accounts.session.bob = ctx.accounts.bob.key();
-        ctx.accounts.session.status = SessionStatus::Computing as u8;
-        let session_id = ctx.accounts.session.session_id;
+        let mut session = ctx.accounts.session.load_init()?;
+        session.session_id = session_id;
+        session.alice = ctx.accounts.alice.key();
+        session.rent_payer = ctx.accounts.payer.key();
+        session.bump = ctx.bumps.session;
+        config.apply(&mut session);
+        session.app_id = app_id;
+        session.memo = memo;
+        session.global_stats_bump = ctx.accounts.global_stats.bump;
+        session.app_stats_bump = ctx.bumps.app_stats;
 
-        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.state_handle.session = ctx.accounts.session.key();
+        ctx.accounts.state_handle.bump = ctx.bumps.state_handle;
 
-        // Build args: ContactList struct = 32 x u128 hashes + u32 count
-        let mut builder = ArgBuilder::new()
-            .x25519_pubkey(pubkey)
-            .plaintext_u128(nonce);
+        ctx.accounts.app_stats.app_id = app_id;
+        ctx.accounts.app_stats.bump = ctx.bumps.app_stats;
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Session);
 
-        for i in 0..32 {
-            builder = builder.encrypted_u128(encrypted_hashes[i]);
-        }
-        builder = builder.encrypted_u32(encrypted_count);
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
-        let args = builder.build();
+        let args = args::party_key_args(pubkey, nonce)
+            .plaintext_u8(config.namespace)
+            .plaintext_u128(session.state_tag())
+            .build();
 
-        queue_computation(
+        session.record_queued(ComputationStage::Init, computation_offset)?;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Init, &ctx.accounts.pool_account)?;
+        let app_stats = ctx.accounts.app_stats.key();
+        drop(session);
+        queue!(
             ctx.accounts,
             computation_offset,
             args,
-            vec![SubmitAndMatchCallback::callback_ix(
+            vec![InitSessionCallback::callback_ix(
                 computation_offset,
                 &ctx.accounts.mxe_account,
-                &[],
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.state_handle.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats_pda(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
             )?],
             1,
             0,
         )?;
 
-        emit!(MatchComputing {
+        emit!(SessionCreated {
             session_id,
+            memo,
+            alice: ctx.accounts.alice.key(),
         });
+        record_activity(ctx.remaining_accounts, &ctx.accounts.alice.key(), session_id, ActivityKind::Created)?;
 
-        Ok(())
+        Ok(SessionQueued {
+            session: ctx.accounts.session.key(),
+            computation_offset,
+        })
     }
 
-    /// Callback for PSI computation
-    #[arcium_callback(encrypted_ix = "submit_and_match")]
-    pub fn submit_and_match_callback(
-        ctx: Context<SubmitAndMatchCallback>,
-        output: SignedComputationOutputs<SubmitAndMatchOutput>,
-    ) -> Result<()> {
-        let _o = match output.verify_output(
-            &ctx.accounts.cluster_account,
-            &ctx.accounts.computation_account,
-        ) {
-            Ok(out) => out,
-            Err(_) => return Err(ErrorCode::ComputationFailed.into()),
-        };
+    // ============================================================
+    // ALICE COMMITS (OPTIONAL)
+    // ============================================================
+
+    /// Alice commits to the ciphertexts she will submit, before sharing
+    /// the session id. `submit_contacts_alice` then only accepts the
+    /// committed submission (see `contacts_commitment`), so Alice cannot
+    /// pick her list after learning who Bob is.
+    pub fn commit_contacts(ctx: Context<CommitContacts>, commitment: [u8; 32]) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        session.require_status(Status::AwaitingAlice)?;
+        require!(
+            ctx.accounts.alice.key() == session.alice,
+            ErrorCode::Unauthorized
+        );
+        require!(
+            session.commitment == [0u8; 32],
+            ErrorCode::AlreadyCommitted
+        );
+        require!(commitment != [0u8; 32], ErrorCode::InvalidCommitment);
 
-        emit!(MatchComplete {});
+        session.commitment = commitment;
+
+        emit!(ContactsCommitted {
+            session_id: session.session_id,
+            memo: session.memo,
+            commitment,
+        });
 
         Ok(())
     }
 
     // ============================================================
-    // ALICE REVEALS HER MATCHES
+    // ALICE SUBMITS CONTACTS
     // ============================================================
 
-    /// Alice retrieves her side of the intersection result.
-    /// The MPC reads stored results from encrypted state and
-    /// encrypts them to Alice's key.
-    pub fn reveal_alice_matches(
-        ctx: Context<RevealAliceMatches>,
+    /// Alice submits her encrypted contact hashes.
+    /// Contacts are hashed client-side (see `HashScheme`) before encryption.
+    /// If she committed first, the submission must match the commitment.
+    /// Any capacity is accepted here; sessions created with a smaller one
+    /// can use the cheaper `submit_contacts_alice_16` or `_8` instead.
+    pub fn submit_contacts_alice(
+        ctx: Context<SubmitContactsAlice>,
+        computation_offset: u64,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        begin_alice(&mut session, ctx.accounts.alice.key(), hash_scheme, None, || {
+            contacts_commitment(&encrypted_hashes, &encrypted_count, &encrypted_namespace, &pubkey, nonce)
+        })?;
+        let session_id = session.session_id;
+        let memo = session.memo;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let args = args::contact_list_args(
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .plaintext_u8(session.capacity())
+        .plaintext_u128(session.state_tag())
+        .build();
+
+        session.record_queued(ComputationStage::Alice, computation_offset)?;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Alice, &ctx.accounts.pool_account)?;
+        let (global_stats, app_stats) = session.stats_pdas();
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![SubmitContactsAliceCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats,
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(ContactsSubmitted {
+            session_id,
+            memo,
+            party: 1,
+        });
+        record_activity(ctx.remaining_accounts, &ctx.accounts.alice.key(), session_id, ActivityKind::Submitted)?;
+
+        Ok(())
+    }
+
+    /// A relayer submits Alice's encrypted contact hashes on her behalf.
+    /// Instead of signing the transaction, Alice signs `permit_message`
+    /// over the session, the ciphertexts' `contacts_commitment`, and an
+    /// `expiry` off-chain; the transaction must verify that signature
+    /// with an Ed25519 program instruction placed right before this one.
+    /// Otherwise identical to `submit_contacts_alice`, callback included.
+    pub fn submit_contacts_with_permit(
+        ctx: Context<SubmitContactsWithPermit>,
         computation_offset: u64,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
         pubkey: [u8; 32],
         nonce: u128,
+        expiry: i64,
     ) -> Result<()> {
         require!(
-            ctx.accounts.session.status == SessionStatus::Matched as u8,
-            ErrorCode::InvalidSessionState
+            Clock::get()?.unix_timestamp <= expiry,
+            ErrorCode::PermitExpired
         );
-        require!(
-            ctx.accounts.alice.key() == ctx.accounts.session.alice,
-            ErrorCode::Unauthorized
+        let mut session = ctx.accounts.session.load_mut()?;
+        let ciphertext_hash = contacts_commitment(
+            &encrypted_hashes,
+            &encrypted_count,
+            &encrypted_namespace,
+            &pubkey,
+            nonce,
         );
-        let session_id = ctx.accounts.session.session_id;
+        begin_alice(&mut session, ctx.accounts.alice.key(), hash_scheme, None, || ciphertext_hash)?;
+        permit::require_permit(
+            &ctx.accounts.instructions_sysvar,
+            &session.alice,
+            &permit_message(&session.session_id, &ciphertext_hash, expiry),
+        )?;
+        let session_id = session.session_id;
+        let memo = session.memo;
 
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
-        // Dummy encrypted input to establish Alice's encryption key
-        let args = ArgBuilder::new()
-            .x25519_pubkey(pubkey)
-            .plaintext_u128(nonce)
-            .encrypted_u8([0u8; 32])
-            .build();
+        let args = args::contact_list_args(
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .plaintext_u8(session.capacity())
+        .plaintext_u128(session.state_tag())
+        .build();
 
-        queue_computation(
+        session.record_queued(ComputationStage::Alice, computation_offset)?;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Alice, &ctx.accounts.pool_account)?;
+        let (global_stats, app_stats) = session.stats_pdas();
+        drop(session);
+        queue!(
             ctx.accounts,
             computation_offset,
             args,
-            vec![RevealAliceMatchesCallback::callback_ix(
+            vec![SubmitContactsAliceCallback::callback_ix(
                 computation_offset,
                 &ctx.accounts.mxe_account,
-                &[],
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats,
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
             )?],
             1,
             0,
         )?;
 
-        emit!(AliceRevealing {
+        emit!(ContactsSubmitted {
             session_id,
+            memo,
+            party: 1,
         });
+        record_activity(ctx.remaining_accounts, &ctx.accounts.alice.key(), session_id, ActivityKind::Submitted)?;
 
         Ok(())
     }
 
-    /// Callback for Alice's match reveal
-    #[arcium_callback(encrypted_ix = "reveal_alice_matches")]
-    pub fn reveal_alice_matches_callback(
-        ctx: Context<RevealAliceMatchesCallback>,
-        output: SignedComputationOutputs<RevealAliceMatchesOutput>,
+    /// Callback for Alice's contact submission.
+    /// A rejected list reopens her slot so she can resubmit.
+    #[arcium_callback(encrypted_ix = "submit_contacts_alice")]
+    pub fn submit_contacts_alice_callback(
+        ctx: Context<SubmitContactsAliceCallback>,
+        output: SignedComputationOutputs<SubmitContactsAliceOutput>,
     ) -> Result<()> {
-        let _o = match output.verify_output(
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let (confirmation, accepted) = match verify_output!(
+            output,
             &ctx.accounts.cluster_account,
             &ctx.accounts.computation_account,
         ) {
-            Ok(out) => out,
-            Err(_) => return Err(ErrorCode::ComputationFailed.into()),
+            Ok(SubmitContactsAliceOutput {
+                field_0: SubmitContactsAliceOutputStruct0 { field_1, field_2, .. },
+            }) => (field_1, field_2),
+            Err(error) => return fail_computation(&ctx.accounts.session, ComputationStage::Alice, &error),
         };
+        return_result_account(&ctx.accounts.session.key());
+
+        finish_alice(
+            &mut *ctx.accounts.session.load_mut()?,
+            &mut ctx.accounts.global_stats,
+            &mut ctx.accounts.app_stats,
+            confirmation.ciphertexts,
+            confirmation.nonce,
+            accepted,
+        )
+    }
+
+    /// `submit_contacts_alice` for a session created with capacity 16:
+    /// the MPC checks and counts only the first 16 slots of her list.
+    pub fn submit_contacts_alice_16(
+        ctx: Context<SubmitContactsAlice16>,
+        computation_offset: u64,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        begin_alice(&mut session, ctx.accounts.alice.key(), hash_scheme, Some(16), || {
+            contacts_commitment(&encrypted_hashes, &encrypted_count, &encrypted_namespace, &pubkey, nonce)
+        })?;
+        let session_id = session.session_id;
+        let memo = session.memo;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let args = args::contact_list_args(
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .plaintext_u128(session.state_tag())
+        .build();
+
+        session.record_queued(ComputationStage::Alice, computation_offset)?;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Alice, &ctx.accounts.pool_account)?;
+        let (global_stats, app_stats) = session.stats_pdas();
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![SubmitContactsAlice16Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats,
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
 
-        emit!(AliceRevealed {});
+        emit!(ContactsSubmitted {
+            session_id,
+            memo,
+            party: 1,
+        });
+        record_activity(ctx.remaining_accounts, &ctx.accounts.alice.key(), session_id, ActivityKind::Submitted)?;
 
         Ok(())
     }
-}
 
-// ============================================================
-// ACCOUNT STRUCTURES
-// ============================================================
+    /// Callback for Alice's capacity 16 submission
+    #[arcium_callback(encrypted_ix = "submit_contacts_alice_16")]
+    pub fn submit_contacts_alice_16_callback(
+        ctx: Context<SubmitContactsAlice16Callback>,
+        output: SignedComputationOutputs<SubmitContactsAlice16Output>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let (confirmation, accepted) = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(SubmitContactsAlice16Output {
+                field_0: SubmitContactsAlice16OutputStruct0 { field_1, field_2, .. },
+            }) => (field_1, field_2),
+            Err(error) => return fail_computation(&ctx.accounts.session, ComputationStage::Alice, &error),
+        };
+        return_result_account(&ctx.accounts.session.key());
 
-#[repr(u8)]
-pub enum SessionStatus {
-    AwaitingAlice = 0,
-    AwaitingBob = 1,
-    Computing = 2,
-    Matched = 3,
-}
+        finish_alice(
+            &mut *ctx.accounts.session.load_mut()?,
+            &mut ctx.accounts.global_stats,
+            &mut ctx.accounts.app_stats,
+            confirmation.ciphertexts,
+            confirmation.nonce,
+            accepted,
+        )
+    }
 
-#[account]
-#[derive(Default)]
-pub struct DiscoverySession {
-    /// Unique session identifier
-    pub session_id: [u8; 32],
-    /// First party (creates the session)
-    pub alice: Pubkey,
-    /// Second party (joins the session)
-    pub bob: Pubkey,
-    /// Current session status
-    pub status: u8,
-    /// PDA bump seed
-    pub bump: u8,
-}
+    /// `submit_contacts_alice` for a session created with capacity 8.
+    pub fn submit_contacts_alice_8(
+        ctx: Context<SubmitContactsAlice8>,
+        computation_offset: u64,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        begin_alice(&mut session, ctx.accounts.alice.key(), hash_scheme, Some(8), || {
+            contacts_commitment(&encrypted_hashes, &encrypted_count, &encrypted_namespace, &pubkey, nonce)
+        })?;
+        let session_id = session.session_id;
+        let memo = session.memo;
 
-impl DiscoverySession {
-    // 8 (discriminator) + 32 + 32 + 32 + 1 + 1 = 106 bytes
-    pub const SIZE: usize = 8 + 32 + 32 + 32 + 1 + 1;
-}
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
-// ============================================================
-// CONTEXT STRUCTURES - Queue Computation
-// ============================================================
+        let args = args::contact_list_args(
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .plaintext_u128(session.state_tag())
+        .build();
 
-#[queue_computation_accounts("init_session", payer)]
-#[derive(Accounts)]
-#[instruction(computation_offset: u64, session_id: [u8; 32])]
-pub struct CreateSession<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(
-        init,
-        payer = payer,
-        space = DiscoverySession::SIZE,
-        seeds = [b"session", session_id.as_ref()],
-        bump
-    )]
-    pub session: Account<'info, DiscoverySession>,
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = payer,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: mempool_account
-    pub mempool_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: executing_pool
-    pub executing_pool: UncheckedAccount<'info>,
-    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: computation_account
-    pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_SESSION))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
-    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
-    pub pool_account: Account<'info, FeePool>,
-    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
-    pub clock_account: Account<'info, ClockAccount>,
-    pub system_program: Program<'info, System>,
-    pub arcium_program: Program<'info, Arcium>,
-}
+        session.record_queued(ComputationStage::Alice, computation_offset)?;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Alice, &ctx.accounts.pool_account)?;
+        let (global_stats, app_stats) = session.stats_pdas();
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![SubmitContactsAlice8Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats,
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
 
-#[queue_computation_accounts("submit_contacts_alice", alice)]
-#[derive(Accounts)]
-#[instruction(computation_offset: u64)]
-pub struct SubmitContactsAlice<'info> {
-    #[account(mut)]
-    pub alice: Signer<'info>,
-    #[account(mut)]
-    pub session: Account<'info, DiscoverySession>,
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = alice,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: mempool_account
-    pub mempool_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: executing_pool
-    pub executing_pool: UncheckedAccount<'info>,
-    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: computation_account
-    pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_ALICE))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
-    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
-    pub pool_account: Account<'info, FeePool>,
-    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
-    pub clock_account: Account<'info, ClockAccount>,
-    pub system_program: Program<'info, System>,
-    pub arcium_program: Program<'info, Arcium>,
-}
+        emit!(ContactsSubmitted {
+            session_id,
+            memo,
+            party: 1,
+        });
+        record_activity(ctx.remaining_accounts, &ctx.accounts.alice.key(), session_id, ActivityKind::Submitted)?;
 
-#[queue_computation_accounts("submit_and_match", bob)]
-#[derive(Accounts)]
-#[instruction(computation_offset: u64)]
-pub struct SubmitAndMatch<'info> {
-    #[account(mut)]
-    pub bob: Signer<'info>,
-    #[account(mut)]
-    pub session: Account<'info, DiscoverySession>,
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = bob,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: mempool_account
-    pub mempool_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: executing_pool
-    pub executing_pool: UncheckedAccount<'info>,
-    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: computation_account
-    pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_AND_MATCH))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
-    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
-    pub pool_account: Account<'info, FeePool>,
-    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+        Ok(())
+    }
+
+    /// Callback for Alice's capacity 8 submission
+    #[arcium_callback(encrypted_ix = "submit_contacts_alice_8")]
+    pub fn submit_contacts_alice_8_callback(
+        ctx: Context<SubmitContactsAlice8Callback>,
+        output: SignedComputationOutputs<SubmitContactsAlice8Output>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let (confirmation, accepted) = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(SubmitContactsAlice8Output {
+                field_0: SubmitContactsAlice8OutputStruct0 { field_1, field_2, .. },
+            }) => (field_1, field_2),
+            Err(error) => return fail_computation(&ctx.accounts.session, ComputationStage::Alice, &error),
+        };
+        return_result_account(&ctx.accounts.session.key());
+
+        finish_alice(
+            &mut *ctx.accounts.session.load_mut()?,
+            &mut ctx.accounts.global_stats,
+            &mut ctx.accounts.app_stats,
+            confirmation.ciphertexts,
+            confirmation.nonce,
+            accepted,
+        )
+    }
+
+    /// Alice's first submission to a session from
+    /// `create_stateless_session`: one computation creates the session's
+    /// MXE state and stores her list in it. Checked as
+    /// `submit_contacts_alice` is, at any capacity, and fails with
+    /// `StateAlreadyInitialized` once the state exists, after which she
+    /// resubmits with `submit_contacts_alice`.
+    pub fn init_and_submit_alice(
+        ctx: Context<InitAndSubmitAlice>,
+        computation_offset: u64,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        require!(session.pending_stage().is_none(), ErrorCode::ComputationPending);
+        begin_alice(&mut session, ctx.accounts.alice.key(), hash_scheme, None, || {
+            contacts_commitment(&encrypted_hashes, &encrypted_count, &encrypted_namespace, &pubkey, nonce)
+        })?;
+        let session_id = session.session_id;
+        let memo = session.memo;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let args = args::contact_list_args(
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .plaintext_u8(session.namespace)
+        .plaintext_u8(capacity)
+        .plaintext_u128(session.state_tag())
+        .build();
+
+        session.record_queued(ComputationStage::Alice, computation_offset)?;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Alice, &ctx.accounts.pool_account)?;
+        let (global_stats, app_stats) = session.stats_pdas();
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![InitAndSubmitAliceCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.state_handle.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats,
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(ContactsSubmitted {
+            session_id,
+            memo,
+            party: 1,
+        });
+        record_activity(ctx.remaining_accounts, &ctx.accounts.alice.key(), session_id, ActivityKind::Submitted)?;
+
+        Ok(())
+    }
+
+    /// Callback for Alice's stateless submission. The returned state is
+    /// recorded whether or not her list was accepted, so a rejected list
+    /// is resubmitted against it like any other.
+    #[arcium_callback(encrypted_ix = "init_and_submit_alice")]
+    pub fn init_and_submit_alice_callback(
+        ctx: Context<InitAndSubmitAliceCallback>,
+        output: SignedComputationOutputs<InitAndSubmitAliceOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let (state, confirmation, accepted) = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(InitAndSubmitAliceOutput {
+                field_0: InitAndSubmitAliceOutputStruct0 { field_0, field_1, field_2 },
+            }) => (field_0, field_1, field_2),
+            Err(error) => return fail_computation(&ctx.accounts.session, ComputationStage::Alice, &error),
+        };
+        return_result_account(&ctx.accounts.session.key());
+
+        let mut session = ctx.accounts.session.load_mut()?;
+        // A re-sent callback finds the stage no longer pending
+        if session.pending_stage() == Some(ComputationStage::Alice) {
+            ctx.accounts.state_handle.record(state.nonce, &state.ciphertexts);
+            emit!(SessionInitialized {
+                session_id: session.session_id,
+                memo: session.memo,
+            });
+        }
+        finish_alice(
+            &mut session,
+            &mut ctx.accounts.global_stats,
+            &mut ctx.accounts.app_stats,
+            confirmation.ciphertexts,
+            confirmation.nonce,
+            accepted,
+        )
+    }
+
+    // ============================================================
+    // BOB SUBMITS AND TRIGGERS MATCH
+    // ============================================================
+
+    /// Bob submits contacts AND the MPC computes the intersection.
+    /// This is the core PSI operation: 32x32 = 1024 encrypted comparisons.
+    /// Bob receives his matches immediately via the callback, or an
+    /// empty result while a time-locked session is still escrowed.
+    /// `public_count` is Bob's opt-in to publishing the match count; the
+    /// count is written to the session only if Alice opted in as well.
+    /// Sessions created with a smaller capacity use `submit_and_match_16`
+    /// or `submit_and_match_8` instead. A session with an app hook takes
+    /// its `AppHook` as the first remaining account, so the callback can
+    /// notify the hook's program.
+    pub fn submit_and_match(
+        ctx: Context<SubmitAndMatch>,
+        computation_offset: u64,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+        public_count: bool,
+    ) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        let (publish, withhold) = begin_match(
+            &mut session,
+            ctx.accounts.bob.key(),
+            hash_scheme,
+            public_count,
+            schema::MAX_CONTACTS as u8,
+            &ctx.accounts.billing.config,
+            ctx.remaining_accounts,
+        )?;
+        let session_id = session.session_id;
+        let memo = session.memo;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // The list, the publish flag, then whether Bob's copy is withheld
+        let args = args::contact_list_args(
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .plaintext_u8(publish as u8)
+        .plaintext_u8(withhold as u8)
+        .plaintext_u128(session.state_tag())
+        .build();
+
+        session.record_queued(ComputationStage::Match, computation_offset)?;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Match, &ctx.accounts.pool_account)?;
+        let (global_stats, app_stats) = session.stats_pdas();
+        let callback_accounts = [
+            CallbackAccount {
+                pubkey: ctx.accounts.session.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: global_stats,
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: app_stats,
+                is_writable: true,
+            },
+        ]
+        .into_iter()
+        .chain(activity_callback_accounts(ctx.remaining_accounts, &[session.alice, session.bob]))
+        .chain(app_hook_callback_accounts(&session, &ctx.accounts.session.key(), ctx.remaining_accounts)?)
+        .collect::<Vec<_>>();
+        record_activity(ctx.remaining_accounts, &session.bob, session_id, ActivityKind::Submitted)?;
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![SubmitAndMatchCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &callback_accounts,
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(MatchComputing {
+            session_id,
+            memo,
+        });
+
+        Ok(())
+    }
+
+    /// Callback for PSI computation
+    #[arcium_callback(encrypted_ix = "submit_and_match")]
+    pub fn submit_and_match_callback(
+        ctx: Context<SubmitAndMatchCallback>,
+        output: SignedComputationOutputs<SubmitAndMatchOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let (result, accepted, public_count) = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(SubmitAndMatchOutput {
+                field_0: SubmitAndMatchOutputStruct0 { field_1, field_2, field_3, .. },
+            }) => (field_1, field_2, field_3),
+            Err(error) => {
+                fail_computation(&ctx.accounts.session, ComputationStage::Match, &error)?;
+                record_match_activity(&ctx.accounts.session, ctx.remaining_accounts, false)?;
+                return Ok(());
+            }
+        };
+        return_result_account(&ctx.accounts.session.key());
+
+        let matched = finish_match(
+            &mut *ctx.accounts.session.load_mut()?,
+            &mut ctx.accounts.global_stats,
+            &mut ctx.accounts.app_stats,
+            &result.ciphertexts,
+            result.nonce,
+            accepted,
+            public_count,
+        )?;
+        let logs = record_match_activity(&ctx.accounts.session, ctx.remaining_accounts, matched)?;
+        if matched {
+            notify_app_hook(&ctx.accounts.session, &ctx.remaining_accounts[logs..]);
+        }
+
+        Ok(())
+    }
+
+    /// `submit_and_match` for a session created with capacity 16: the
+    /// MPC compares 16x16 = 256 slot pairs.
+    pub fn submit_and_match_16(
+        ctx: Context<SubmitAndMatch16>,
+        computation_offset: u64,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+        public_count: bool,
+    ) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        let (publish, withhold) = begin_match(
+            &mut session,
+            ctx.accounts.bob.key(),
+            hash_scheme,
+            public_count,
+            16,
+            &ctx.accounts.billing.config,
+            ctx.remaining_accounts,
+        )?;
+        let session_id = session.session_id;
+        let memo = session.memo;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let args = args::contact_list_args(
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .plaintext_u8(publish as u8)
+        .plaintext_u8(withhold as u8)
+        .plaintext_u128(session.state_tag())
+        .build();
+
+        session.record_queued(ComputationStage::Match, computation_offset)?;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Match, &ctx.accounts.pool_account)?;
+        let (global_stats, app_stats) = session.stats_pdas();
+        let callback_accounts = [
+            CallbackAccount {
+                pubkey: ctx.accounts.session.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: global_stats,
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: app_stats,
+                is_writable: true,
+            },
+        ]
+        .into_iter()
+        .chain(activity_callback_accounts(ctx.remaining_accounts, &[session.alice, session.bob]))
+        .chain(app_hook_callback_accounts(&session, &ctx.accounts.session.key(), ctx.remaining_accounts)?)
+        .collect::<Vec<_>>();
+        record_activity(ctx.remaining_accounts, &session.bob, session_id, ActivityKind::Submitted)?;
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![SubmitAndMatch16Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &callback_accounts,
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(MatchComputing {
+            session_id,
+            memo,
+        });
+
+        Ok(())
+    }
+
+    /// Callback for the capacity 16 PSI computation
+    #[arcium_callback(encrypted_ix = "submit_and_match_16")]
+    pub fn submit_and_match_16_callback(
+        ctx: Context<SubmitAndMatch16Callback>,
+        output: SignedComputationOutputs<SubmitAndMatch16Output>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let (result, accepted, public_count) = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(SubmitAndMatch16Output {
+                field_0: SubmitAndMatch16OutputStruct0 { field_1, field_2, field_3, .. },
+            }) => (field_1, field_2, field_3),
+            Err(error) => {
+                fail_computation(&ctx.accounts.session, ComputationStage::Match, &error)?;
+                record_match_activity(&ctx.accounts.session, ctx.remaining_accounts, false)?;
+                return Ok(());
+            }
+        };
+        return_result_account(&ctx.accounts.session.key());
+
+        let matched = finish_match(
+            &mut *ctx.accounts.session.load_mut()?,
+            &mut ctx.accounts.global_stats,
+            &mut ctx.accounts.app_stats,
+            &result.ciphertexts,
+            result.nonce,
+            accepted,
+            public_count,
+        )?;
+        let logs = record_match_activity(&ctx.accounts.session, ctx.remaining_accounts, matched)?;
+        if matched {
+            notify_app_hook(&ctx.accounts.session, &ctx.remaining_accounts[logs..]);
+        }
+
+        Ok(())
+    }
+
+    /// `submit_and_match` for a session created with capacity 8: the
+    /// MPC compares 8x8 = 64 slot pairs.
+    pub fn submit_and_match_8(
+        ctx: Context<SubmitAndMatch8>,
+        computation_offset: u64,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+        public_count: bool,
+    ) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        let (publish, withhold) = begin_match(
+            &mut session,
+            ctx.accounts.bob.key(),
+            hash_scheme,
+            public_count,
+            8,
+            &ctx.accounts.billing.config,
+            ctx.remaining_accounts,
+        )?;
+        let session_id = session.session_id;
+        let memo = session.memo;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let args = args::contact_list_args(
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .plaintext_u8(publish as u8)
+        .plaintext_u8(withhold as u8)
+        .plaintext_u128(session.state_tag())
+        .build();
+
+        session.record_queued(ComputationStage::Match, computation_offset)?;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Match, &ctx.accounts.pool_account)?;
+        let (global_stats, app_stats) = session.stats_pdas();
+        let callback_accounts = [
+            CallbackAccount {
+                pubkey: ctx.accounts.session.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: global_stats,
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: app_stats,
+                is_writable: true,
+            },
+        ]
+        .into_iter()
+        .chain(activity_callback_accounts(ctx.remaining_accounts, &[session.alice, session.bob]))
+        .chain(app_hook_callback_accounts(&session, &ctx.accounts.session.key(), ctx.remaining_accounts)?)
+        .collect::<Vec<_>>();
+        record_activity(ctx.remaining_accounts, &session.bob, session_id, ActivityKind::Submitted)?;
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![SubmitAndMatch8Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &callback_accounts,
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(MatchComputing {
+            session_id,
+            memo,
+        });
+
+        Ok(())
+    }
+
+    /// Callback for the capacity 8 PSI computation
+    #[arcium_callback(encrypted_ix = "submit_and_match_8")]
+    pub fn submit_and_match_8_callback(
+        ctx: Context<SubmitAndMatch8Callback>,
+        output: SignedComputationOutputs<SubmitAndMatch8Output>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let (result, accepted, public_count) = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(SubmitAndMatch8Output {
+                field_0: SubmitAndMatch8OutputStruct0 { field_1, field_2, field_3, .. },
+            }) => (field_1, field_2, field_3),
+            Err(error) => {
+                fail_computation(&ctx.accounts.session, ComputationStage::Match, &error)?;
+                record_match_activity(&ctx.accounts.session, ctx.remaining_accounts, false)?;
+                return Ok(());
+            }
+        };
+        return_result_account(&ctx.accounts.session.key());
+
+        let matched = finish_match(
+            &mut *ctx.accounts.session.load_mut()?,
+            &mut ctx.accounts.global_stats,
+            &mut ctx.accounts.app_stats,
+            &result.ciphertexts,
+            result.nonce,
+            accepted,
+            public_count,
+        )?;
+        let logs = record_match_activity(&ctx.accounts.session, ctx.remaining_accounts, matched)?;
+        if matched {
+            notify_app_hook(&ctx.accounts.session, &ctx.remaining_accounts[logs..]);
+        }
+
+        Ok(())
+    }
+
+    // ============================================================
+    // COMMITTED SETS
+    // ============================================================
+
+    /// Bob commits, in a `CommittedSet` session, to a set split into
+    /// `chunk_count` encrypted chunks: `set_root` is the Merkle root over
+    /// their `set_leaf`s. Only chunks with a witness against the root can
+    /// be matched afterwards, so Bob cannot choose later chunks after
+    /// seeing earlier results.
+    pub fn commit_set(ctx: Context<CommitSet>, set_root: [u8; 32], chunk_count: u8) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        session.require_status(Status::AwaitingBob)?;
+        session.require_joinable(Clock::get()?.unix_timestamp)?;
+        require!(
+            session.mode == SessionMode::CommittedSet as u8,
+            ErrorCode::ModeMismatch
+        );
+        require!(
+            session.bob == Pubkey::default(),
+            ErrorCode::SetAlreadyCommitted
+        );
+        require!(
+            set_root != [0u8; 32] && (1..=MAX_SET_CHUNKS).contains(&chunk_count),
+            ErrorCode::InvalidSetCommitment
+        );
+
+        let slots = chunk_count as u32 * session.capacity() as u32;
+        record_bob(&mut session, ctx.accounts.bob.key(), slots, &ctx.accounts.config, ctx.remaining_accounts)?;
+        session.set_root = set_root;
+        session.set_chunk_count = chunk_count;
+        session.set_chunks_done = 0;
+
+        emit!(SetCommitted {
+            session_id: session.session_id,
+            memo: session.memo,
+            bob: session.bob,
+            set_root,
+            chunk_count,
+        });
+
+        Ok(())
+    }
+
+    /// Bob matches chunk `chunk_index` of his committed set against
+    /// Alice's list. `proof` is the chunk's Merkle witness (sibling
+    /// hashes, bottom first) over the `contacts_commitment` of exactly
+    /// these ciphertexts. Chunks go one at a time; the session is
+    /// `Matched` once every chunk has been accepted.
+    pub fn submit_set_chunk(
+        ctx: Context<SubmitSetChunk>,
+        computation_offset: u64,
+        chunk_index: u8,
+        proof: Vec<[u8; 32]>,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        require!(
+            hash_scheme == session.hash_scheme,
+            ErrorCode::HashSchemeMismatch
+        );
+        session.require_status(Status::AwaitingBob)?;
+        require!(
+            session.mode == SessionMode::CommittedSet as u8,
+            ErrorCode::ModeMismatch
+        );
+        require!(session.set_chunk_count > 0, ErrorCode::SetNotCommitted);
+        require!(
+            ctx.accounts.bob.key() == session.bob,
+            ErrorCode::Unauthorized
+        );
+        require!(
+            chunk_index < session.set_chunk_count
+                && session.set_chunks_done & (1u64 << chunk_index) == 0,
+            ErrorCode::ChunkAlreadyMatched
+        );
+
+        let commitment = contacts_commitment(
+            &encrypted_hashes,
+            &encrypted_count,
+            &encrypted_namespace,
+            &pubkey,
+            nonce,
+        );
+        require!(
+            verify_set_proof(
+                &session.set_root,
+                session.set_chunk_count,
+                chunk_index,
+                &set_leaf(chunk_index, &commitment),
+                &proof,
+            ),
+            ErrorCode::InvalidSetProof
+        );
+
+        session.set_pending_chunk = chunk_index;
+        let last = session.set_chunks_done.count_ones() + 1 == session.set_chunk_count as u32;
+        session.transition(Status::Computing)?;
+        let session_id = session.session_id;
+        let memo = session.memo;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // The chunk, whether it completes the set, and the session tag
+        let args = args::contact_list_args(
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .plaintext_u8(last as u8)
+        .plaintext_u128(session.state_tag())
+        .build();
+
+        session.record_queued(ComputationStage::Match, computation_offset)?;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Match, &ctx.accounts.pool_account)?;
+        let (global_stats, app_stats) = session.stats_pdas();
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![MatchSetChunkCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats,
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(SetChunkComputing {
+            session_id,
+            memo,
+            chunk_index,
+        });
+
+        Ok(())
+    }
+
+    /// Callback for one committed-set chunk
+    #[arcium_callback(encrypted_ix = "match_set_chunk")]
+    pub fn match_set_chunk_callback(
+        ctx: Context<MatchSetChunkCallback>,
+        output: SignedComputationOutputs<MatchSetChunkOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let (result, accepted) = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(MatchSetChunkOutput {
+                field_0: MatchSetChunkOutputStruct0 { field_1, field_2, .. },
+            }) => (field_1, field_2),
+            Err(error) => return fail_computation(&ctx.accounts.session, ComputationStage::Match, &error),
+        };
+        return_result_account(&ctx.accounts.session.key());
+
+        let mut session = ctx.accounts.session.load_mut()?;
+        if !session.consume_callback(ComputationStage::Match, Clock::get()?.slot) {
+            return Ok(());
+        }
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Computation);
+        if !accepted {
+            // The root still binds the chunk, so Bob keeps his slot; a
+            // chunk that cannot be fixed stalls the set until Alice purges
+            // it past the deadline
+            meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Rejection);
+            session.transition(Status::AwaitingBob)?;
+            return reject_submission(&mut session, 2);
+        }
+
+        let chunk_index = session.set_pending_chunk;
+        session.set_chunks_done |= 1u64 << chunk_index;
+        session.rejected_party = 0;
+        let chunks_remaining = session.set_chunk_count - session.set_chunks_done.count_ones() as u8;
+        if chunks_remaining == 0 {
+            // Every chunk is in: unlock Alice's reveal
+            session.transition(Status::Matched)?;
+            meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Match);
+        } else {
+            session.transition(Status::AwaitingBob)?;
+        }
+
+        let (encrypted_matches, encrypted_count) = split_match_result(&result.ciphertexts);
+        emit!(SetChunkMatched {
+            session_id: session.session_id,
+            memo: session.memo,
+            chunk_index,
+            chunks_remaining,
+            encrypted_matches,
+            encrypted_count,
+            nonce: result.nonce.to_le_bytes(),
+        });
+
+        Ok(())
+    }
+
+    // ============================================================
+    // ALICE REVEALS HER MATCHES
+    // ============================================================
+
+    /// Alice retrieves her side of the intersection result.
+    /// The MPC reads stored results from encrypted state and
+    /// encrypts page `page` (32 slots) of them to Alice's key.
+    pub fn reveal_alice_matches(
+        ctx: Context<RevealAliceMatches>,
+        computation_offset: u64,
+        pubkey: [u8; 32],
+        nonce: u128,
+        page: u8,
+    ) -> Result<()> {
+        require!(page < RESULT_PAGES, ErrorCode::InvalidPage);
+        let mut session = ctx.accounts.session.load_mut()?;
+        session.require_status(Status::Matched)?;
+        require!(
+            ctx.accounts.alice.key() == session.alice,
+            ErrorCode::Unauthorized
+        );
+        require!(
+            session.mode == SessionMode::OneShot as u8
+                || session.mode == SessionMode::Subscription as u8
+                || session.mode == SessionMode::CommittedSet as u8,
+            ErrorCode::ModeMismatch
+        );
+        session.require_revealable(Clock::get()?.slot)?;
+        let session_id = session.session_id;
+        let memo = session.memo;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // Dummy encrypted input to establish Alice's encryption key
+        let args = args::party_key_args(pubkey, nonce)
+            .plaintext_u8(page)
+            .plaintext_u128(session.state_tag())
+            .build();
+
+        session.record_queued(ComputationStage::Reveal, computation_offset)?;
+        session.reveal_window = page;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Reveal, &ctx.accounts.pool_account)?;
+        let (global_stats, app_stats) = session.stats_pdas();
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![RevealAliceMatchesCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats,
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(AliceRevealing {
+            session_id,
+            memo,
+        });
+
+        Ok(())
+    }
+
+    /// Callback for Alice's match reveal
+    #[arcium_callback(encrypted_ix = "reveal_alice_matches")]
+    pub fn reveal_alice_matches_callback(
+        ctx: Context<RevealAliceMatchesCallback>,
+        output: SignedComputationOutputs<RevealAliceMatchesOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let result = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RevealAliceMatchesOutput { field_0 }) => field_0,
+            Err(error) => return fail_computation(&ctx.accounts.session, ComputationStage::Reveal, &error),
+        };
+        return_result_account(&ctx.accounts.session.key());
+
+        let (encrypted_matches, encrypted_count) = split_match_result(&result.ciphertexts);
+        let mut session = ctx.accounts.session.load_mut()?;
+        if !session.consume_callback(ComputationStage::Reveal, Clock::get()?.slot) {
+            return Ok(());
+        }
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Computation);
+        let window = session.reveal_window;
+        let complete = deliver_window(&mut session.alice_windows, window);
+        let nonce = result.nonce.to_le_bytes();
+        session.alice_receipt =
+            result_receipt(&session.session_id, 1, window, &encrypted_matches, &encrypted_count, &nonce);
+        emit!(AliceRevealed {
+            session_id: session.session_id,
+            memo: session.memo,
+            encrypted_matches,
+            encrypted_count,
+            nonce,
+            window,
+            complete,
+        });
+
+        Ok(())
+    }
+
+    // ============================================================
+    // BOB REVEALS HIS MATCHES
+    // ============================================================
+
+    /// Bob re-reads page `page` of his side of the intersection. His
+    /// first page already arrives with `submit_and_match`; this fetches
+    /// the rest once sessions hold more than one page.
+    pub fn reveal_bob_matches(
+        ctx: Context<RevealBobMatches>,
+        computation_offset: u64,
+        pubkey: [u8; 32],
+        nonce: u128,
+        page: u8,
+    ) -> Result<()> {
+        require!(page < RESULT_PAGES, ErrorCode::InvalidPage);
+        let mut session = ctx.accounts.session.load_mut()?;
+        session.require_status(Status::Matched)?;
+        require!(
+            ctx.accounts.bob.key() == session.bob,
+            ErrorCode::Unauthorized
+        );
+        require!(
+            session.mode == SessionMode::OneShot as u8
+                || session.mode == SessionMode::Subscription as u8,
+            ErrorCode::ModeMismatch
+        );
+        session.require_revealable(Clock::get()?.slot)?;
+        let session_id = session.session_id;
+        let memo = session.memo;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // Dummy encrypted input to establish Bob's encryption key
+        let args = args::party_key_args(pubkey, nonce)
+            .plaintext_u8(page)
+            .plaintext_u128(session.state_tag())
+            .build();
+
+        session.record_queued(ComputationStage::Reveal, computation_offset)?;
+        session.reveal_window = page;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Reveal, &ctx.accounts.pool_account)?;
+        let (global_stats, app_stats) = session.stats_pdas();
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![RevealBobMatchesCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats,
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(BobRevealing {
+            session_id,
+            memo,
+            page,
+        });
+
+        Ok(())
+    }
+
+    /// Callback for Bob's match reveal
+    #[arcium_callback(encrypted_ix = "reveal_bob_matches")]
+    pub fn reveal_bob_matches_callback(
+        ctx: Context<RevealBobMatchesCallback>,
+        output: SignedComputationOutputs<RevealBobMatchesOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let result = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RevealBobMatchesOutput { field_0 }) => field_0,
+            Err(error) => return fail_computation(&ctx.accounts.session, ComputationStage::Reveal, &error),
+        };
+        return_result_account(&ctx.accounts.session.key());
+
+        let (encrypted_matches, encrypted_count) = split_match_result(&result.ciphertexts);
+        let mut session = ctx.accounts.session.load_mut()?;
+        if !session.consume_callback(ComputationStage::Reveal, Clock::get()?.slot) {
+            return Ok(());
+        }
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Computation);
+        let window = session.reveal_window;
+        let complete = deliver_window(&mut session.bob_windows, window);
+        let nonce = result.nonce.to_le_bytes();
+        session.bob_receipt =
+            result_receipt(&session.session_id, 2, window, &encrypted_matches, &encrypted_count, &nonce);
+        emit!(BobRevealed {
+            session_id: session.session_id,
+            memo: session.memo,
+            encrypted_matches,
+            encrypted_count,
+            nonce,
+            window,
+            complete,
+        });
+
+        Ok(())
+    }
+
+    // ============================================================
+    // JOINT REVEAL
+    // ============================================================
+
+    /// Alice and Bob, both signing, fetch page `page` of their sides of
+    /// the intersection with one computation instead of two: the MPC
+    /// encrypts Alice's page to `alice_pubkey` and Bob's to `bob_pubkey`.
+    /// Billed as a single reveal; the callback emits the same
+    /// `AliceRevealed` and `BobRevealed` events as the separate reveals.
+    pub fn reveal_both(
+        ctx: Context<RevealBoth>,
+        computation_offset: u64,
+        alice_pubkey: [u8; 32],
+        alice_nonce: u128,
+        bob_pubkey: [u8; 32],
+        bob_nonce: u128,
+        page: u8,
+    ) -> Result<()> {
+        require!(page < RESULT_PAGES, ErrorCode::InvalidPage);
+        let mut session = ctx.accounts.session.load_mut()?;
+        session.require_status(Status::Matched)?;
+        require!(
+            ctx.accounts.alice.key() == session.alice && ctx.accounts.bob.key() == session.bob,
+            ErrorCode::Unauthorized
+        );
+        require!(
+            session.mode == SessionMode::OneShot as u8
+                || session.mode == SessionMode::Subscription as u8,
+            ErrorCode::ModeMismatch
+        );
+        session.require_revealable(Clock::get()?.slot)?;
+        let session_id = session.session_id;
+        let memo = session.memo;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // A dummy encrypted input per party establishes each key
+        let args = args::push_party_key(args::party_key_args(alice_pubkey, alice_nonce), bob_pubkey, bob_nonce)
+            .plaintext_u8(page)
+            .plaintext_u128(session.state_tag())
+            .build();
+
+        session.record_queued(ComputationStage::Reveal, computation_offset)?;
+        session.reveal_window = page;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Reveal, &ctx.accounts.pool_account)?;
+        let (global_stats, app_stats) = session.stats_pdas();
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![RevealBothCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats,
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(BothRevealing {
+            session_id,
+            memo,
+            page,
+        });
+
+        Ok(())
+    }
+
+    /// Callback for a joint reveal
+    #[arcium_callback(encrypted_ix = "reveal_both")]
+    pub fn reveal_both_callback(
+        ctx: Context<RevealBothCallback>,
+        output: SignedComputationOutputs<RevealBothOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let (alice, bob) = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RevealBothOutput {
+                field_0: RevealBothOutputStruct0 { field_0, field_1 },
+            }) => (field_0, field_1),
+            Err(error) => return fail_computation(&ctx.accounts.session, ComputationStage::Reveal, &error),
+        };
+        return_result_account(&ctx.accounts.session.key());
+
+        let mut session = ctx.accounts.session.load_mut()?;
+        if !session.consume_callback(ComputationStage::Reveal, Clock::get()?.slot) {
+            return Ok(());
+        }
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Computation);
+        let window = session.reveal_window;
+
+        let (encrypted_matches, encrypted_count) = split_match_result(&alice.ciphertexts);
+        let complete = deliver_window(&mut session.alice_windows, window);
+        let nonce = alice.nonce.to_le_bytes();
+        session.alice_receipt =
+            result_receipt(&session.session_id, 1, window, &encrypted_matches, &encrypted_count, &nonce);
+        emit!(AliceRevealed {
+            session_id: session.session_id,
+            memo: session.memo,
+            encrypted_matches,
+            encrypted_count,
+            nonce,
+            window,
+            complete,
+        });
+        let (encrypted_matches, encrypted_count) = split_match_result(&bob.ciphertexts);
+        let complete = deliver_window(&mut session.bob_windows, window);
+        let nonce = bob.nonce.to_le_bytes();
+        session.bob_receipt =
+            result_receipt(&session.session_id, 2, window, &encrypted_matches, &encrypted_count, &nonce);
+        emit!(BobRevealed {
+            session_id: session.session_id,
+            memo: session.memo,
+            encrypted_matches,
+            encrypted_count,
+            nonce,
+            window,
+            complete,
+        });
+
+        Ok(())
+    }
+
+    // ============================================================
+    // SUBSCRIPTION DELTA ROUNDS
+    // ============================================================
+
+    /// Either party pushes added/removed contacts to a matched
+    /// subscription session. The MPC updates that party's stored list
+    /// and returns only mutual contacts it has not been told about yet.
+    pub fn push_delta(
+        ctx: Context<PushDelta>,
+        computation_offset: u64,
+        hash_scheme: u8,
+        encrypted_added: [[u8; 32]; 16],
+        encrypted_removed: [[u8; 32]; 16],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        require!(
+            hash_scheme == session.hash_scheme,
+            ErrorCode::HashSchemeMismatch
+        );
+        require!(
+            session.mode == SessionMode::Subscription as u8,
+            ErrorCode::NotSubscription
+        );
+        session.require_status(Status::Matched)?;
+        session.require_revealable(Clock::get()?.slot)?;
+
+        let signer = ctx.accounts.party.key();
+        let party: u8 = if signer == session.alice {
+            1
+        } else if signer == session.bob {
+            2
+        } else {
+            return Err(ErrorCode::Unauthorized.into());
+        };
+
+        // One round at a time: the next delta waits for this callback
+        session.transition(Status::Computing)?;
+        session.round += 1;
+        let session_id = session.session_id;
+        let memo = session.memo;
+        let round = session.round;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // The delta, the submitting party, and the session tag
+        let args = args::contact_delta_args(
+            pubkey,
+            nonce,
+            &encrypted_added,
+            &encrypted_removed,
+            encrypted_namespace,
+        )
+        .plaintext_u8(party)
+        .plaintext_u128(session.state_tag())
+        .build();
+
+        session.record_queued(ComputationStage::Match, computation_offset)?;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Match, &ctx.accounts.pool_account)?;
+        let (global_stats, app_stats) = session.stats_pdas();
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![PushDeltaCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats,
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(DeltaSubmitted {
+            session_id,
+            memo,
+            party,
+            round,
+        });
+
+        Ok(())
+    }
+
+    /// Callback for a subscription delta round
+    #[arcium_callback(encrypted_ix = "push_delta")]
+    pub fn push_delta_callback(
+        ctx: Context<PushDeltaCallback>,
+        output: SignedComputationOutputs<PushDeltaOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let result = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(PushDeltaOutput {
+                field_0: PushDeltaOutputStruct0 { field_1, .. },
+            }) => field_1,
+            Err(error) => return fail_computation(&ctx.accounts.session, ComputationStage::Match, &error),
+        };
+        return_result_account(&ctx.accounts.session.key());
+
+        // Round done: accept the next delta
+        let mut session = ctx.accounts.session.load_mut()?;
+        if !session.consume_callback(ComputationStage::Match, Clock::get()?.slot) {
+            return Ok(());
+        }
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Computation);
+        session.transition(Status::Matched)?;
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Match);
+
+        let (encrypted_matches, encrypted_count) = split_match_result(&result.ciphertexts);
+        emit!(DeltaMatched {
+            session_id: session.session_id,
+            memo: session.memo,
+            round: session.round,
+            encrypted_matches,
+            encrypted_count,
+            nonce: result.nonce.to_le_bytes(),
+        });
+
+        Ok(())
+    }
+
+    // ============================================================
+    // MATCHMAKING POOLS
+    // ============================================================
+
+    /// A host publishes an encrypted list (e.g. event attendees) as a pool.
+    /// The MPC re-encrypts it to the MXE and the callback stores it on
+    /// the pool account, so any number of users can match against it.
+    /// Users must hash their contacts in the pool's `namespace`.
+    pub fn create_pool(
+        ctx: Context<CreatePool>,
+        computation_offset: u64,
+        pool_id: [u8; 32],
+        namespace: u8,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(
+            namespace <= Namespace::SolName as u8,
+            ErrorCode::InvalidNamespace
+        );
+        require!(
+            hash_scheme <= HashScheme::Blake3 as u8,
+            ErrorCode::InvalidHashScheme
+        );
+        pool.pool_id = pool_id;
+        pool.host = ctx.accounts.host.key();
+        pool.status = PoolStatus::Initializing as u8;
+        pool.namespace = namespace;
+        pool.hash_scheme = hash_scheme;
+        pool.match_runs = 0;
+        pool.bump = ctx.bumps.pool;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let args = args::contact_list_args(
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .build();
+
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![CreatePoolCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.pool.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats_pda(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(PoolCreated {
+            pool_id,
+            host: ctx.accounts.host.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Callback for pool creation: store the MXE-encrypted list
+    #[arcium_callback(encrypted_ix = "create_pool")]
+    pub fn create_pool_callback(
+        ctx: Context<CreatePoolCallback>,
+        output: SignedComputationOutputs<CreatePoolOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let list = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(CreatePoolOutput { field_0 }) => field_0,
+            Err(error) => return computation_failed(ctx.accounts.pool.pool_id, NO_STAGE, 0, &error),
+        };
+        return_result_account(&ctx.accounts.pool.key());
+
+        ctx.accounts.global_stats.usage.record(Usage::Computation);
+
+        let pool = &mut ctx.accounts.pool;
+        pool.encrypted_hashes.copy_from_slice(&list.ciphertexts[..32]);
+        pool.encrypted_count = list.ciphertexts[32];
+        pool.encrypted_namespace = list.ciphertexts[33];
+        pool.nonce = list.nonce;
+        pool.status = PoolStatus::Open as u8;
+
+        emit!(PoolOpened {
+            pool_id: pool.pool_id,
+        });
+
+        Ok(())
+    }
+
+    /// Any user matches their contacts against an open pool and
+    /// learns which of them are in it. The pool stays unchanged.
+    pub fn match_against_pool(
+        ctx: Context<MatchAgainstPool>,
+        computation_offset: u64,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(
+            hash_scheme == pool.hash_scheme,
+            ErrorCode::HashSchemeMismatch
+        );
+        require!(
+            pool.status == PoolStatus::Open as u8,
+            ErrorCode::PoolNotOpen
+        );
+        pool.match_runs += 1;
+        let pool_id = pool.pool_id;
+        let pool_nonce = pool.nonce;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // Pool list (Enc<Mxe, ContactList>) is read straight from the pool account
+        let stored = args::stored_list_args(
+            pool_nonce,
+            ctx.accounts.pool.key(),
+            DiscoveryPool::ENCRYPTED_LIST_OFFSET,
+            DiscoveryPool::ENCRYPTED_LIST_LEN,
+        );
+        let args = args::push_contact_list(
+            stored,
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .build();
+
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![MatchAgainstPoolCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.pool.key(),
+                        is_writable: false,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats_pda(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(PoolMatching {
+            pool_id,
+            user: ctx.accounts.user.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Callback for a pool match
+    #[arcium_callback(encrypted_ix = "match_against_pool")]
+    pub fn match_against_pool_callback(
+        ctx: Context<MatchAgainstPoolCallback>,
+        output: SignedComputationOutputs<MatchAgainstPoolOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let result = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(MatchAgainstPoolOutput { field_0 }) => field_0,
+            Err(error) => return computation_failed(ctx.accounts.pool.pool_id, NO_STAGE, 0, &error),
+        };
+        return_result_account(&ctx.accounts.pool.key());
+
+        ctx.accounts.global_stats.usage.record(Usage::Computation);
+        ctx.accounts.global_stats.usage.record(Usage::Match);
+
+        let (encrypted_matches, encrypted_count) = split_match_result(&result.ciphertexts);
+        emit!(PoolMatched {
+            pool_id: ctx.accounts.pool.pool_id,
+            encrypted_matches,
+            encrypted_count,
+            nonce: result.nonce.to_le_bytes(),
+        });
+
+        Ok(())
+    }
+
+    // ============================================================
+    // INSTANT MATCHES
+    // ============================================================
+
+    /// A party stages its list, encrypted to its own key, for an instant
+    /// match with `peer`. The list waits on a
+    /// `StagedList` PDA until a relayer runs `instant_match` on it and
+    /// the peer's list, or the owner closes it.
+    pub fn stage_list(
+        ctx: Context<StageList>,
+        peer: Pubkey,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        require!(peer != owner, ErrorCode::PeerMismatch);
+        require!(
+            hash_scheme <= HashScheme::Blake3 as u8,
+            ErrorCode::InvalidHashScheme
+        );
+        let staged = &mut ctx.accounts.staged_list;
+        staged.owner = owner;
+        staged.peer = peer;
+        staged.rent_payer = ctx.accounts.payer.key();
+        staged.status = StagedListStatus::Staged as u8;
+        staged.hash_scheme = hash_scheme;
+        staged.bump = ctx.bumps.staged_list;
+        staged.pubkey = pubkey;
+        staged.encrypted_hashes = encrypted_hashes;
+        staged.encrypted_count = encrypted_count;
+        staged.encrypted_namespace = encrypted_namespace;
+        staged.nonce = nonce;
+
+        emit!(ListStaged { owner, peer });
+
+        Ok(())
+    }
+
+    /// Anyone (typically a relayer) matches two lists staged for each
+    /// other in a single computation: no session, no state, no reveal.
+    /// Each party's side of the intersection is emitted encrypted to the
+    /// key its list was staged with, and the callback closes both lists.
+    pub fn instant_match(ctx: Context<InstantMatch>, computation_offset: u64) -> Result<()> {
+        let alice_list = &ctx.accounts.alice_list;
+        let bob_list = &ctx.accounts.bob_list;
+        require!(
+            alice_list.status == StagedListStatus::Staged as u8
+                && bob_list.status == StagedListStatus::Staged as u8,
+            ErrorCode::ListNotStaged
+        );
+        require!(
+            alice_list.peer == bob_list.owner && bob_list.peer == alice_list.owner,
+            ErrorCode::PeerMismatch
+        );
+        require!(
+            alice_list.hash_scheme == bob_list.hash_scheme,
+            ErrorCode::HashSchemeMismatch
+        );
+        let alice = alice_list.owner;
+        let bob = bob_list.owner;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // Both lists (Enc<Shared, ContactList>) are read straight from their accounts
+        let args = [alice_list, bob_list]
+            .into_iter()
+            .fold(ArgBuilder::new(), |builder, list| {
+                args::push_shared_stored_list(
+                    builder,
+                    list.pubkey,
+                    list.nonce,
+                    list.key(),
+                    StagedList::ENCRYPTED_LIST_OFFSET,
+                    StagedList::ENCRYPTED_LIST_LEN,
+                )
+            })
+            .build();
+        let callback_accounts = [alice_list, bob_list]
+            .into_iter()
+            .flat_map(|list| {
+                [
+                    CallbackAccount {
+                        pubkey: list.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: list.rent_payer,
+                        is_writable: true,
+                    },
+                ]
+            })
+            .chain([CallbackAccount {
+                pubkey: global_stats_pda(),
+                is_writable: true,
+            }])
+            .collect::<Vec<_>>();
+        ctx.accounts.alice_list.status = StagedListStatus::Matching as u8;
+        ctx.accounts.bob_list.status = StagedListStatus::Matching as u8;
+
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![InstantMatchCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &callback_accounts,
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(InstantMatching { alice, bob });
+
+        Ok(())
+    }
+
+    /// Callback for an instant match: emit both results and close both
+    /// staged lists, refunding their rent. A failed match closes them too,
+    /// and the parties stage again.
+    #[arcium_callback(encrypted_ix = "instant_match")]
+    pub fn instant_match_callback(
+        ctx: Context<InstantMatchCallback>,
+        output: SignedComputationOutputs<InstantMatchOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let (alice, bob, accepted) = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(InstantMatchOutput {
+                field_0: InstantMatchOutputStruct0 { field_0, field_1, field_2 },
+            }) => (field_0, field_1, field_2),
+            Err(error) => return computation_failed(ctx.accounts.alice_list.key().to_bytes(), NO_STAGE, 0, &error),
+        };
+        return_result_account(&ctx.accounts.alice_list.key());
+
+        ctx.accounts.global_stats.usage.record(Usage::Computation);
+        ctx.accounts.global_stats.usage.record(if accepted { Usage::Match } else { Usage::Rejection });
+
+        let (alice_matches, alice_count) = split_match_result(&alice.ciphertexts);
+        let (bob_matches, bob_count) = split_match_result(&bob.ciphertexts);
+        emit!(InstantMatched {
+            alice: ctx.accounts.alice_list.owner,
+            bob: ctx.accounts.bob_list.owner,
+            accepted,
+            encrypted_alice_matches: alice_matches,
+            encrypted_alice_count: alice_count,
+            alice_nonce: alice.nonce.to_le_bytes(),
+            encrypted_bob_matches: bob_matches,
+            encrypted_bob_count: bob_count,
+            bob_nonce: bob.nonce.to_le_bytes(),
+        });
+
+        Ok(())
+    }
+
+    /// The owner withdraws a staged list and reclaims its rent. Also
+    /// frees a list whose match was queued but never called back; a
+    /// callback that arrives afterwards fails on the missing account.
+    pub fn close_staged_list(ctx: Context<CloseStagedList>) -> Result<()> {
+        emit!(StagedListClosed {
+            owner: ctx.accounts.staged_list.owner,
+            peer: ctx.accounts.staged_list.peer,
+        });
+        Ok(())
+    }
+
+    // ============================================================
+    // SERVER DATABASES (UNBALANCED PSI)
+    // ============================================================
+
+    /// A provider (e.g. an app server) opens an empty database for
+    /// unbalanced PSI: a large list it loads chunk by chunk with
+    /// `load_database_chunk`, seals, and then lets any number of clients
+    /// query with their small address books through `query_psi`.
+    /// Clients must hash their contacts in the database's `namespace`.
+    /// This is epoch 0; `stage_database_epoch` starts the next version.
+    pub fn create_database(
+        ctx: Context<CreateDatabase>,
+        database_id: [u8; 32],
+        namespace: u8,
+        hash_scheme: u8,
+    ) -> Result<()> {
+        require!(
+            namespace <= Namespace::SolName as u8,
+            ErrorCode::InvalidNamespace
+        );
+        require!(
+            hash_scheme <= HashScheme::Blake3 as u8,
+            ErrorCode::InvalidHashScheme
+        );
+        let mut database = ctx.accounts.database.load_init()?;
+        database.database_id = database_id;
+        database.provider = ctx.accounts.provider.key();
+        database.status = DatabaseStatus::Loading as u8;
+        database.namespace = namespace;
+        database.hash_scheme = hash_scheme;
+        database.chunk_count = 0;
+        database.queries = 0;
+        database.epoch = 0;
+        database.bump = ctx.bumps.database;
+
+        emit!(DatabaseCreated {
+            database_id,
+            provider: database.provider,
+            epoch: 0,
+        });
+
+        Ok(())
+    }
+
+    /// The provider starts the next version of a live database: an empty
+    /// database at epoch `epoch` (the live epoch + 1) with the same
+    /// namespace and hash scheme, loaded and sealed as usual and then
+    /// swapped in by `rotate_database_epoch`. The live version keeps
+    /// answering queries meanwhile.
+    pub fn stage_database_epoch(
+        ctx: Context<StageDatabaseEpoch>,
+        database_id: [u8; 32],
+        epoch: u32,
+    ) -> Result<()> {
+        let live = ctx.accounts.live_database.load()?;
+        require!(
+            ctx.accounts.provider.key() == live.provider,
+            ErrorCode::Unauthorized
+        );
+        require!(
+            live.database_id == database_id,
+            ErrorCode::DatabaseEpochMismatch
+        );
+        require!(
+            live.status == DatabaseStatus::Open as u8,
+            ErrorCode::DatabaseNotOpen
+        );
+        require!(
+            live.epoch.checked_add(1) == Some(epoch),
+            ErrorCode::DatabaseEpochMismatch
+        );
+
+        let mut database = ctx.accounts.database.load_init()?;
+        database.database_id = database_id;
+        database.provider = live.provider;
+        database.status = DatabaseStatus::Loading as u8;
+        database.namespace = live.namespace;
+        database.hash_scheme = live.hash_scheme;
+        database.chunk_count = 0;
+        database.queries = 0;
+        database.epoch = epoch;
+        database.bump = ctx.bumps.database;
+
+        emit!(DatabaseCreated {
+            database_id,
+            provider: database.provider,
+            epoch,
+        });
+
+        Ok(())
+    }
+
+    /// The provider loads the next chunk (one sorted, deduplicated list
+    /// of up to 32 hashes) of its database. The MPC re-encrypts it to the
+    /// MXE and the callback stores it in the next free slot, under its
+    /// own nonce. One load is in flight at a time.
+    pub fn load_database_chunk(
+        ctx: Context<LoadDatabaseChunk>,
+        computation_offset: u64,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let mut database = ctx.accounts.database.load_mut()?;
+        require!(
+            ctx.accounts.provider.key() == database.provider,
+            ErrorCode::Unauthorized
+        );
+        require!(
+            hash_scheme == database.hash_scheme,
+            ErrorCode::HashSchemeMismatch
+        );
+        require!(
+            database.status == DatabaseStatus::Loading as u8,
+            ErrorCode::DatabaseNotLoading
+        );
+        require!(
+            (database.chunk_count as usize) < args::DATABASE_CHUNKS,
+            ErrorCode::DatabaseFull
+        );
+        database.status = DatabaseStatus::ChunkPending as u8;
+        let database_id = database.database_id;
+        let epoch = database.epoch;
+        let chunk_index = database.chunk_count;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // The chunk, then the namespace it must be in
+        let args = args::contact_list_args(
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .plaintext_u8(database.namespace)
+        .build();
+
+        drop(database);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![LoadDatabaseChunkCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.database.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats_pda(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(DatabaseChunkLoading {
+            database_id,
+            epoch,
+            chunk_index,
+        });
+
+        Ok(())
+    }
+
+    /// Callback for a chunk load: store the MXE-encrypted chunk if the
+    /// MPC accepted it. Either way, and also when the output fails
+    /// verification, the database goes back to accepting chunks, so one
+    /// bad load cannot lock it.
+    #[arcium_callback(encrypted_ix = "load_database_chunk")]
+    pub fn load_database_chunk_callback(
+        ctx: Context<LoadDatabaseChunkCallback>,
+        output: SignedComputationOutputs<LoadDatabaseChunkOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let verified = verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        );
+        let mut database = ctx.accounts.database.load_mut()?;
+        database.status = DatabaseStatus::Loading as u8;
+        let (list, accepted) = match verified {
+            Ok(LoadDatabaseChunkOutput {
+                field_0: LoadDatabaseChunkOutputStruct0 { field_0, field_1 },
+            }) => (field_0, field_1),
+            Err(error) => return computation_failed(database.database_id, NO_STAGE, 0, &error),
+        };
+        return_result_account(&ctx.accounts.database.key());
+
+        ctx.accounts.global_stats.usage.record(Usage::Computation);
+
+        let chunk_index = database.chunk_count;
+        if accepted {
+            let start = chunk_index as usize * args::CONTACT_LIST_CIPHERTEXTS;
+            database.encrypted_chunks[start..start + args::CONTACT_LIST_CIPHERTEXTS]
+                .copy_from_slice(&list.ciphertexts);
+            database.nonces[chunk_index as usize] = list.nonce.to_le_bytes();
+            database.chunk_count += 1;
+        } else {
+            ctx.accounts.global_stats.usage.record(Usage::Rejection);
+        }
+
+        emit!(DatabaseChunkLoaded {
+            database_id: database.database_id,
+            epoch: database.epoch,
+            chunk_index,
+            accepted,
+        });
+
+        Ok(())
+    }
+
+    /// The provider seals its database once every chunk is loaded. From
+    /// then on it takes no more chunks. Epoch 0 opens for queries at
+    /// once; a later epoch waits for `rotate_database_epoch`.
+    pub fn seal_database(ctx: Context<SealDatabase>) -> Result<()> {
+        let mut database = ctx.accounts.database.load_mut()?;
+        require!(
+            ctx.accounts.provider.key() == database.provider,
+            ErrorCode::Unauthorized
+        );
+        require!(
+            database.status == DatabaseStatus::Loading as u8,
+            ErrorCode::DatabaseNotLoading
+        );
+        require!(database.chunk_count > 0, ErrorCode::DatabaseEmpty);
+
+        if database.epoch == 0 {
+            database.status = DatabaseStatus::Open as u8;
+            emit!(DatabaseOpened {
+                database_id: database.database_id,
+                epoch: 0,
+                chunk_count: database.chunk_count,
+            });
+        } else {
+            database.status = DatabaseStatus::Sealed as u8;
+            emit!(DatabaseEpochSealed {
+                database_id: database.database_id,
+                epoch: database.epoch,
+                chunk_count: database.chunk_count,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The provider swaps a sealed next epoch in for the live one, in one
+    /// step: the next epoch opens and the live one is retired, refusing
+    /// new queries. Only its status changes, so queries already queued
+    /// against it read the same chunks and finish as usual, their results
+    /// carrying the old epoch.
+    pub fn rotate_database_epoch(ctx: Context<RotateDatabaseEpoch>) -> Result<()> {
+        let mut live = ctx.accounts.live_database.load_mut()?;
+        let mut next = ctx.accounts.next_database.load_mut()?;
+        require!(
+            ctx.accounts.provider.key() == live.provider,
+            ErrorCode::Unauthorized
+        );
+        require!(
+            live.status == DatabaseStatus::Open as u8,
+            ErrorCode::DatabaseNotOpen
+        );
+        require!(
+            next.database_id == live.database_id && live.epoch.checked_add(1) == Some(next.epoch),
+            ErrorCode::DatabaseEpochMismatch
+        );
+        require!(
+            next.status == DatabaseStatus::Sealed as u8,
+            ErrorCode::DatabaseNotSealed
+        );
+        live.status = DatabaseStatus::Retired as u8;
+        next.status = DatabaseStatus::Open as u8;
+
+        emit!(DatabaseEpochRotated {
+            database_id: next.database_id,
+            retired_epoch: live.epoch,
+            epoch: next.epoch,
+            chunk_count: next.chunk_count,
+        });
+
+        Ok(())
+    }
+
+    /// Any client queries its contacts against a sealed database and
+    /// learns which of them are in it. Every chunk slot is passed to the
+    /// MPC; only the loaded ones are compared. The database stays
+    /// unchanged.
+    pub fn query_psi(
+        ctx: Context<QueryPsi>,
+        computation_offset: u64,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let mut database = ctx.accounts.database.load_mut()?;
+        require!(
+            hash_scheme == database.hash_scheme,
+            ErrorCode::HashSchemeMismatch
+        );
+        require!(
+            database.status == DatabaseStatus::Open as u8,
+            ErrorCode::DatabaseNotOpen
+        );
+        database.queries += 1;
+        let database_id = database.database_id;
+        let epoch = database.epoch;
+        let database_key = ctx.accounts.database.key();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // Each chunk is read straight from its slot, then the query, the
+        // loaded count, and the namespace
+        let args = args::push_contact_list(
+            args::database_chunks_args(database_key, &database.nonces),
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .plaintext_u8(database.chunk_count)
+        .plaintext_u8(database.namespace)
+        .build();
+
+        drop(database);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![QueryPsiCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: database_key,
+                        is_writable: false,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats_pda(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(DatabaseQuerying {
+            database_id,
+            epoch,
+            user: ctx.accounts.user.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Callback for a database query
+    #[arcium_callback(encrypted_ix = "query_psi")]
+    pub fn query_psi_callback(
+        ctx: Context<QueryPsiCallback>,
+        output: SignedComputationOutputs<QueryPsiOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let result = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(QueryPsiOutput { field_0 }) => field_0,
+            Err(error) => return computation_failed(ctx.accounts.database.load()?.database_id, NO_STAGE, 0, &error),
+        };
+        return_result_account(&ctx.accounts.database.key());
+
+        ctx.accounts.global_stats.usage.record(Usage::Computation);
+        ctx.accounts.global_stats.usage.record(Usage::Match);
+
+        let database = ctx.accounts.database.load()?;
+        let (encrypted_matches, encrypted_count) = split_match_result(&result.ciphertexts);
+        emit!(DatabaseQueried {
+            database_id: database.database_id,
+            epoch: database.epoch,
+            encrypted_matches,
+            encrypted_count,
+            nonce: result.nonce.to_le_bytes(),
+        });
+
+        Ok(())
+    }
+
+    /// Any client asks whether one identifier is in a live database
+    /// ("is this number already registered?") and learns only yes or
+    /// no, encrypted to its key. Cheaper than `query_psi` with a
+    /// one-contact list: one comparison per stored slot. Billed as one
+    /// query to the app whose credit account is passed; its authority
+    /// signs, as `user`, `payer` or a remaining account.
+    pub fn check_membership(
+        ctx: Context<CheckMembership>,
+        computation_offset: u64,
+        hash_scheme: u8,
+        encrypted_query: [[u8; 32]; 2],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let mut database = ctx.accounts.database.load_mut()?;
+        require!(
+            hash_scheme == database.hash_scheme,
+            ErrorCode::HashSchemeMismatch
+        );
+        require!(
+            database.status == DatabaseStatus::Open as u8,
+            ErrorCode::DatabaseNotOpen
+        );
+        database.queries += 1;
+        let database_id = database.database_id;
+        let epoch = database.epoch;
+        let database_key = ctx.accounts.database.key();
+
+        let signers = [&*ctx.accounts.user, &*ctx.accounts.payer];
+        ctx.accounts.billing.credit.require_authority(signers.into_iter().chain(ctx.remaining_accounts))?;
+        let fee = ctx.accounts.billing.charge_queries(1, &ctx.accounts.pool_account)?;
+        emit!(QueriesCharged {
+            app_id: ctx.accounts.billing.credit.app_id,
+            database_id,
+            queries: 1,
+            fee,
+        });
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let args = args::push_membership_query(
+            args::database_chunks_args(database_key, &database.nonces),
+            pubkey,
+            nonce,
+            &encrypted_query,
+        )
+        .plaintext_u8(database.chunk_count)
+        .plaintext_u8(database.namespace)
+        .build();
+
+        drop(database);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![CheckMembershipCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: database_key,
+                        is_writable: false,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats_pda(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(DatabaseQuerying {
+            database_id,
+            epoch,
+            user: ctx.accounts.user.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Callback for a membership check
+    #[arcium_callback(encrypted_ix = "check_membership")]
+    pub fn check_membership_callback(
+        ctx: Context<CheckMembershipCallback>,
+        output: SignedComputationOutputs<CheckMembershipOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let member = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(CheckMembershipOutput { field_0 }) => field_0,
+            Err(error) => return computation_failed(ctx.accounts.database.load()?.database_id, NO_STAGE, 0, &error),
+        };
+        return_result_account(&ctx.accounts.database.key());
+
+        ctx.accounts.global_stats.usage.record(Usage::Computation);
+
+        let database = ctx.accounts.database.load()?;
+        emit!(MembershipChecked {
+            database_id: database.database_id,
+            epoch: database.epoch,
+            encrypted_member: member.ciphertexts[0],
+            nonce: member.nonce.to_le_bytes(),
+        });
+
+        Ok(())
+    }
+
+    /// Check the first `queries` (1 to 16) of a batch of identifiers
+    /// against a live database in one computation, e.g. an invite list
+    /// at signup. The client learns a 16-bit mask, bit `j` set iff
+    /// identifier `j` is in the database. Each of the `queries` is
+    /// billed like a single check, with the same signature from the
+    /// app's authority; slots past them are never answered.
+    pub fn check_membership_batch(
+        ctx: Context<CheckMembershipBatch>,
+        computation_offset: u64,
+        hash_scheme: u8,
+        queries: u8,
+        encrypted_hashes: [[u8; 32]; 16],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        require!(
+            queries >= 1 && queries as usize <= args::MEMBERSHIP_BATCH_SLOTS,
+            ErrorCode::InvalidQueryCount
+        );
+        let mut database = ctx.accounts.database.load_mut()?;
+        require!(
+            hash_scheme == database.hash_scheme,
+            ErrorCode::HashSchemeMismatch
+        );
+        require!(
+            database.status == DatabaseStatus::Open as u8,
+            ErrorCode::DatabaseNotOpen
+        );
+        database.queries += queries as u64;
+        let database_id = database.database_id;
+        let epoch = database.epoch;
+        let database_key = ctx.accounts.database.key();
+
+        let signers = [&*ctx.accounts.user, &*ctx.accounts.payer];
+        ctx.accounts.billing.credit.require_authority(signers.into_iter().chain(ctx.remaining_accounts))?;
+        let fee = ctx.accounts.billing.charge_queries(queries, &ctx.accounts.pool_account)?;
+        emit!(QueriesCharged {
+            app_id: ctx.accounts.billing.credit.app_id,
+            database_id,
+            queries,
+            fee,
+        });
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let args = args::push_membership_batch(
+            args::database_chunks_args(database_key, &database.nonces),
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_namespace,
+        )
+        .plaintext_u8(queries)
+        .plaintext_u8(database.chunk_count)
+        .plaintext_u8(database.namespace)
+        .build();
+
+        drop(database);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![CheckMembershipBatchCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: database_key,
+                        is_writable: false,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats_pda(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(DatabaseQuerying {
+            database_id,
+            epoch,
+            user: ctx.accounts.user.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Callback for a batched membership check
+    #[arcium_callback(encrypted_ix = "check_membership_batch")]
+    pub fn check_membership_batch_callback(
+        ctx: Context<CheckMembershipBatchCallback>,
+        output: SignedComputationOutputs<CheckMembershipBatchOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let mask = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(CheckMembershipBatchOutput { field_0 }) => field_0,
+            Err(error) => return computation_failed(ctx.accounts.database.load()?.database_id, NO_STAGE, 0, &error),
+        };
+        return_result_account(&ctx.accounts.database.key());
+
+        ctx.accounts.global_stats.usage.record(Usage::Computation);
+
+        let database = ctx.accounts.database.load()?;
+        emit!(MembershipBatchChecked {
+            database_id: database.database_id,
+            epoch: database.epoch,
+            encrypted_mask: mask.ciphertexts[0],
+            nonce: mask.nonce.to_le_bytes(),
+        });
+
+        Ok(())
+    }
+
+    // ============================================================
+    // OVERLAP SCORES
+    // ============================================================
+
+    /// Bob submits contacts to a score session. The MPC computes the
+    /// same intersection as `submit_and_match` but returns only the
+    /// overlap ratios, never the matched identities.
+    pub fn submit_and_score(
+        ctx: Context<SubmitAndScore>,
+        computation_offset: u64,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        require!(
+            hash_scheme == session.hash_scheme,
+            ErrorCode::HashSchemeMismatch
+        );
+        session.require_status(Status::AwaitingBob)?;
+        session.require_joinable(Clock::get()?.unix_timestamp)?;
+        require!(
+            session.mode == SessionMode::Score as u8,
+            ErrorCode::ModeMismatch
+        );
+
+        let (slots, config) = (session.capacity() as u32, &ctx.accounts.billing.config);
+        record_bob(&mut session, ctx.accounts.bob.key(), slots, config, ctx.remaining_accounts)?;
+        session.transition(Status::Computing)?;
+        let session_id = session.session_id;
+        let memo = session.memo;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let args = args::contact_list_args(
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .plaintext_u8(session.hide_counts)
+        .plaintext_u128(session.state_tag())
+        .build();
+
+        session.record_queued(ComputationStage::Match, computation_offset)?;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Match, &ctx.accounts.pool_account)?;
+        let (global_stats, app_stats) = session.stats_pdas();
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![SubmitAndScoreCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats,
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(MatchComputing {
+            session_id,
+            memo,
+        });
+
+        Ok(())
+    }
+
+    /// Callback for a scored match
+    #[arcium_callback(encrypted_ix = "submit_and_score")]
+    pub fn submit_and_score_callback(
+        ctx: Context<SubmitAndScoreCallback>,
+        output: SignedComputationOutputs<SubmitAndScoreOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let (score, accepted) = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(SubmitAndScoreOutput {
+                field_0: SubmitAndScoreOutputStruct0 { field_1, field_2, .. },
+            }) => (field_1, field_2),
+            Err(error) => return fail_computation(&ctx.accounts.session, ComputationStage::Match, &error),
+        };
+        return_result_account(&ctx.accounts.session.key());
+
+        let mut session = ctx.accounts.session.load_mut()?;
+        if !session.consume_callback(ComputationStage::Match, Clock::get()?.slot) {
+            return Ok(());
+        }
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Computation);
+        if !accepted {
+            meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Rejection);
+            return reopen_bob_slot(&mut session);
+        }
+
+        // Scoring is done: unlock Alice's score reveal
+        session.transition(Status::Matched)?;
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Match);
+        session.rejected_party = 0;
+
+        emit!(ScoreComplete {
+            session_id: session.session_id,
+            memo: session.memo,
+            encrypted_score: score.ciphertexts,
+            nonce: score.nonce.to_le_bytes(),
+        });
+
+        Ok(())
+    }
+
+    /// Alice retrieves the overlap ratios of a scored session.
+    pub fn reveal_alice_score(
+        ctx: Context<RevealAliceScore>,
+        computation_offset: u64,
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        session.require_status(Status::Matched)?;
+        require!(
+            ctx.accounts.alice.key() == session.alice,
+            ErrorCode::Unauthorized
+        );
+        require!(
+            session.mode == SessionMode::Score as u8,
+            ErrorCode::ModeMismatch
+        );
+        let session_id = session.session_id;
+        let memo = session.memo;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // Dummy encrypted input to establish Alice's encryption key
+        let args = args::party_key_args(pubkey, nonce)
+            .plaintext_u8(session.hide_counts)
+            .plaintext_u128(session.state_tag())
+            .build();
+
+        session.record_queued(ComputationStage::Reveal, computation_offset)?;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Reveal, &ctx.accounts.pool_account)?;
+        let (global_stats, app_stats) = session.stats_pdas();
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![RevealAliceScoreCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats,
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(AliceRevealing {
+            session_id,
+            memo,
+        });
+
+        Ok(())
+    }
+
+    /// Callback for Alice's score reveal
+    #[arcium_callback(encrypted_ix = "reveal_alice_score")]
+    pub fn reveal_alice_score_callback(
+        ctx: Context<RevealAliceScoreCallback>,
+        output: SignedComputationOutputs<RevealAliceScoreOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let score = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RevealAliceScoreOutput { field_0 }) => field_0,
+            Err(error) => return fail_computation(&ctx.accounts.session, ComputationStage::Reveal, &error),
+        };
+        return_result_account(&ctx.accounts.session.key());
+
+        let mut session = ctx.accounts.session.load_mut()?;
+        if !session.consume_callback(ComputationStage::Reveal, Clock::get()?.slot) {
+            return Ok(());
+        }
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Computation);
+        emit!(AliceScoreRevealed {
+            session_id: session.session_id,
+            memo: session.memo,
+            encrypted_score: score.ciphertexts,
+            nonce: score.nonce.to_le_bytes(),
+        });
+
+        Ok(())
+    }
+
+    // ============================================================
+    // FRIENDS OF FRIENDS
+    // ============================================================
+
+    /// Bob submits his contacts to a friends-of-friends session.
+    /// Nothing is matched yet: the list waits in MXE state for the
+    /// connector, a contact both Alice and Bob have.
+    pub fn submit_contacts_bob(
+        ctx: Context<SubmitContactsBob>,
+        computation_offset: u64,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        require!(
+            hash_scheme == session.hash_scheme,
+            ErrorCode::HashSchemeMismatch
+        );
+        session.require_status(Status::AwaitingBob)?;
+        session.require_joinable(Clock::get()?.unix_timestamp)?;
+        require!(
+            session.mode == SessionMode::FriendsOfFriends as u8,
+            ErrorCode::ModeMismatch
+        );
+
+        let (slots, config) = (session.capacity() as u32, &ctx.accounts.billing.config);
+        record_bob(&mut session, ctx.accounts.bob.key(), slots, config, ctx.remaining_accounts)?;
+        session.transition(Status::AwaitingConnector)?;
+        let session_id = session.session_id;
+        let memo = session.memo;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let args = args::contact_list_args(
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .plaintext_u128(session.state_tag())
+        .build();
+
+        session.record_queued(ComputationStage::Match, computation_offset)?;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Match, &ctx.accounts.pool_account)?;
+        let (global_stats, app_stats) = session.stats_pdas();
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![SubmitContactsBobCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats,
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(ContactsSubmitted {
+            session_id,
+            memo,
+            party: 2,
+        });
+
+        Ok(())
+    }
+
+    /// Callback for Bob's friends-of-friends submission
+    #[arcium_callback(encrypted_ix = "submit_contacts_bob")]
+    pub fn submit_contacts_bob_callback(
+        ctx: Context<SubmitContactsBobCallback>,
+        output: SignedComputationOutputs<SubmitContactsBobOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let (confirmation, accepted) = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(SubmitContactsBobOutput {
+                field_0: SubmitContactsBobOutputStruct0 { field_1, field_2, .. },
+            }) => (field_1, field_2),
+            Err(error) => return fail_computation(&ctx.accounts.session, ComputationStage::Match, &error),
+        };
+        return_result_account(&ctx.accounts.session.key());
+
+        let mut session = ctx.accounts.session.load_mut()?;
+        if !session.consume_callback(ComputationStage::Match, Clock::get()?.slot) {
+            return Ok(());
+        }
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Computation);
+        if !accepted {
+            meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Rejection);
+            return reopen_bob_slot(&mut session);
+        }
+        session.rejected_party = 0;
+
+        emit!(BobSubmitted {
+            session_id: session.session_id,
+            memo: session.memo,
+            encrypted_confirmation: confirmation.ciphertexts,
+            nonce: confirmation.nonce.to_le_bytes(),
+        });
+
+        Ok(())
+    }
+
+    /// The connector submits their contacts and the MPC counts the
+    /// connector's contacts that both Alice and Bob also have.
+    /// The connector must be neither Alice nor Bob.
+    pub fn submit_connector(
+        ctx: Context<SubmitConnector>,
+        computation_offset: u64,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        require!(
+            hash_scheme == session.hash_scheme,
+            ErrorCode::HashSchemeMismatch
+        );
+        session.require_status(Status::AwaitingConnector)?;
+        require!(
+            session.mode == SessionMode::FriendsOfFriends as u8,
+            ErrorCode::ModeMismatch
+        );
+        let connector = ctx.accounts.connector.key();
+        require!(
+            connector != session.alice && connector != session.bob,
+            ErrorCode::Unauthorized
+        );
+
+        session.transition(Status::Computing)?;
+        let session_id = session.session_id;
+        let memo = session.memo;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let args = args::contact_list_args(
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .plaintext_u128(session.state_tag())
+        .build();
+
+        session.record_queued(ComputationStage::Match, computation_offset)?;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Match, &ctx.accounts.pool_account)?;
+        let (global_stats, app_stats) = session.stats_pdas();
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![SubmitConnectorCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats,
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(ContactsSubmitted {
+            session_id,
+            memo,
+            party: 3,
+        });
+        emit!(MatchComputing {
+            session_id,
+            memo,
+        });
+
+        Ok(())
+    }
+
+    /// Callback for the connector's submission
+    #[arcium_callback(encrypted_ix = "submit_connector")]
+    pub fn submit_connector_callback(
+        ctx: Context<SubmitConnectorCallback>,
+        output: SignedComputationOutputs<SubmitConnectorOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let (confirmation, accepted) = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(SubmitConnectorOutput {
+                field_0: SubmitConnectorOutputStruct0 { field_1, field_2, .. },
+            }) => (field_1, field_2),
+            Err(error) => return fail_computation(&ctx.accounts.session, ComputationStage::Match, &error),
+        };
+        return_result_account(&ctx.accounts.session.key());
+
+        let mut session = ctx.accounts.session.load_mut()?;
+        if !session.consume_callback(ComputationStage::Match, Clock::get()?.slot) {
+            return Ok(());
+        }
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Computation);
+        if !accepted {
+            // Wait for a connector with a well-formed list
+            session.transition(Status::AwaitingConnector)?;
+            meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Rejection);
+            return reject_submission(&mut session, 3);
+        }
+
+        // Counting is done: unlock both reveals
+        session.transition(Status::Matched)?;
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Match);
+        session.rejected_party = 0;
+
+        emit!(SecondDegreeComputed {
+            session_id: session.session_id,
+            memo: session.memo,
+            encrypted_confirmation: confirmation.ciphertexts,
+            nonce: confirmation.nonce.to_le_bytes(),
+        });
+
+        Ok(())
+    }
+
+    /// Alice or Bob retrieves the friends-of-friends count, or in a
+    /// mutual-follow session the mutual-follow flag (1 or 0).
+    pub fn reveal_second_degree(
+        ctx: Context<RevealSecondDegree>,
+        computation_offset: u64,
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        session.require_status(Status::Matched)?;
+        require!(
+            session.mode == SessionMode::FriendsOfFriends as u8
+                || session.mode == SessionMode::MutualFollow as u8,
+            ErrorCode::ModeMismatch
+        );
+
+        let signer = ctx.accounts.party.key();
+        let party: u8 = if signer == session.alice {
+            1
+        } else if signer == session.bob {
+            2
+        } else {
+            return Err(ErrorCode::Unauthorized.into());
+        };
+        let session_id = session.session_id;
+        let memo = session.memo;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // Dummy encrypted input to establish the party's encryption key
+        let args = args::party_key_args(pubkey, nonce)
+            .plaintext_u128(session.state_tag())
+            .build();
+
+        session.record_queued(ComputationStage::Reveal, computation_offset)?;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Reveal, &ctx.accounts.pool_account)?;
+        let (global_stats, app_stats) = session.stats_pdas();
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![RevealSecondDegreeCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats,
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(SecondDegreeRevealing {
+            session_id,
+            memo,
+            party,
+        });
+
+        Ok(())
+    }
+
+    /// Callback for a friends-of-friends reveal
+    #[arcium_callback(encrypted_ix = "reveal_second_degree")]
+    pub fn reveal_second_degree_callback(
+        ctx: Context<RevealSecondDegreeCallback>,
+        output: SignedComputationOutputs<RevealSecondDegreeOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let count = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RevealSecondDegreeOutput { field_0 }) => field_0,
+            Err(error) => return fail_computation(&ctx.accounts.session, ComputationStage::Reveal, &error),
+        };
+        return_result_account(&ctx.accounts.session.key());
+
+        let mut session = ctx.accounts.session.load_mut()?;
+        if !session.consume_callback(ComputationStage::Reveal, Clock::get()?.slot) {
+            return Ok(());
+        }
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Computation);
+        emit!(SecondDegreeRevealed {
+            session_id: session.session_id,
+            memo: session.memo,
+            encrypted_count: count.ciphertexts[0],
+            nonce: count.nonce.to_le_bytes(),
+        });
+
+        Ok(())
+    }
+
+    // ============================================================
+    // BLOCKLIST SCREENING
+    // ============================================================
+
+    /// A moderator publishes an encrypted blocklist (e.g. known spam or
+    /// abuse numbers). The MPC re-encrypts it to the MXE and the callback
+    /// stores it on the blocklist account.
+    /// Users must hash their contacts in the blocklist's `namespace`.
+    pub fn create_blocklist(
+        ctx: Context<CreateBlocklist>,
+        computation_offset: u64,
+        blocklist_id: [u8; 32],
+        namespace: u8,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let blocklist = &mut ctx.accounts.blocklist;
+        require!(
+            namespace <= Namespace::SolName as u8,
+            ErrorCode::InvalidNamespace
+        );
+        require!(
+            hash_scheme <= HashScheme::Blake3 as u8,
+            ErrorCode::InvalidHashScheme
+        );
+        blocklist.blocklist_id = blocklist_id;
+        blocklist.moderator = ctx.accounts.moderator.key();
+        blocklist.status = PoolStatus::Initializing as u8;
+        blocklist.namespace = namespace;
+        blocklist.hash_scheme = hash_scheme;
+        blocklist.checks = 0;
+        blocklist.bump = ctx.bumps.blocklist;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let args = args::contact_list_args(
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .build();
+
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![CreateBlocklistCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.blocklist.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats_pda(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(BlocklistCreated {
+            blocklist_id,
+            moderator: ctx.accounts.moderator.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Callback for blocklist creation: store the MXE-encrypted list
+    #[arcium_callback(encrypted_ix = "create_blocklist")]
+    pub fn create_blocklist_callback(
+        ctx: Context<CreateBlocklistCallback>,
+        output: SignedComputationOutputs<CreateBlocklistOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let list = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(CreateBlocklistOutput { field_0 }) => field_0,
+            Err(error) => return computation_failed(ctx.accounts.blocklist.blocklist_id, NO_STAGE, 0, &error),
+        };
+        return_result_account(&ctx.accounts.blocklist.key());
+
+        ctx.accounts.global_stats.usage.record(Usage::Computation);
+
+        let blocklist = &mut ctx.accounts.blocklist;
+        blocklist.encrypted_hashes.copy_from_slice(&list.ciphertexts[..32]);
+        blocklist.encrypted_count = list.ciphertexts[32];
+        blocklist.encrypted_namespace = list.ciphertexts[33];
+        blocklist.nonce = list.nonce;
+        blocklist.status = PoolStatus::Open as u8;
+
+        emit!(BlocklistOpened {
+            blocklist_id: blocklist.blocklist_id,
+        });
+
+        Ok(())
+    }
+
+    /// Any user screens their contacts against an open blocklist and
+    /// learns only whether any are listed and how many, encrypted to
+    /// their key. The blocklist stays unchanged.
+    pub fn check_against_blocklist(
+        ctx: Context<CheckAgainstBlocklist>,
+        computation_offset: u64,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let blocklist = &mut ctx.accounts.blocklist;
+        require!(
+            hash_scheme == blocklist.hash_scheme,
+            ErrorCode::HashSchemeMismatch
+        );
+        require!(
+            blocklist.status == PoolStatus::Open as u8,
+            ErrorCode::BlocklistNotOpen
+        );
+        blocklist.checks += 1;
+        let blocklist_id = blocklist.blocklist_id;
+        let blocklist_nonce = blocklist.nonce;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // Blocklist (Enc<Mxe, ContactList>) is read straight from its account
+        let stored = args::stored_list_args(
+            blocklist_nonce,
+            ctx.accounts.blocklist.key(),
+            DiscoveryBlocklist::ENCRYPTED_LIST_OFFSET,
+            DiscoveryBlocklist::ENCRYPTED_LIST_LEN,
+        );
+        let args = args::push_contact_list(
+            stored,
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .build();
+
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![CheckAgainstBlocklistCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.blocklist.key(),
+                        is_writable: false,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats_pda(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(BlocklistChecking {
+            blocklist_id,
+            user: ctx.accounts.user.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Callback for a blocklist check
+    #[arcium_callback(encrypted_ix = "check_against_blocklist")]
+    pub fn check_against_blocklist_callback(
+        ctx: Context<CheckAgainstBlocklistCallback>,
+        output: SignedComputationOutputs<CheckAgainstBlocklistOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let result = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(CheckAgainstBlocklistOutput { field_0 }) => field_0,
+            Err(error) => return computation_failed(ctx.accounts.blocklist.blocklist_id, NO_STAGE, 0, &error),
+        };
+        return_result_account(&ctx.accounts.blocklist.key());
+
+        ctx.accounts.global_stats.usage.record(Usage::Computation);
+
+        emit!(BlocklistChecked {
+            blocklist_id: ctx.accounts.blocklist.blocklist_id,
+            encrypted_result: result.ciphertexts,
+            nonce: result.nonce.to_le_bytes(),
+        });
+
+        Ok(())
+    }
+
+    // ============================================================
+    // CONTACT CARDS
+    // ============================================================
+
+    /// Open an empty contact card registry. Users publish messaging keys
+    /// to it under their own hashed identifier with
+    /// `publish_contact_card`, and anyone who already has them as a
+    /// contact can fetch those keys with `match_contact_cards`.
+    /// Cards must be hashed in the registry's `namespace`.
+    pub fn create_card_registry(
+        ctx: Context<CreateCardRegistry>,
+        computation_offset: u64,
+        registry_id: [u8; 32],
+        namespace: u8,
+        hash_scheme: u8,
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        require!(
+            namespace <= Namespace::SolName as u8,
+            ErrorCode::InvalidNamespace
+        );
+        require!(
+            hash_scheme <= HashScheme::Blake3 as u8,
+            ErrorCode::InvalidHashScheme
+        );
+        registry.registry_id = registry_id;
+        registry.authority = ctx.accounts.authority.key();
+        registry.status = PoolStatus::Initializing as u8;
+        registry.namespace = namespace;
+        registry.hash_scheme = hash_scheme;
+        registry.publishes = 0;
+        registry.lookups = 0;
+        registry.bump = ctx.bumps.registry;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let args = args::party_key_args(pubkey, nonce)
+            .plaintext_u8(namespace)
+            .build();
+
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![CreateCardRegistryCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.registry.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats_pda(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(CardRegistryCreated {
+            registry_id,
+            authority: ctx.accounts.authority.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Callback for registry creation: store the empty MXE-encrypted
+    /// registry
+    #[arcium_callback(encrypted_ix = "create_card_registry")]
+    pub fn create_card_registry_callback(
+        ctx: Context<CreateCardRegistryCallback>,
+        output: SignedComputationOutputs<CreateCardRegistryOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let cards = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(CreateCardRegistryOutput { field_0 }) => field_0,
+            Err(error) => return computation_failed(ctx.accounts.registry.registry_id, NO_STAGE, 0, &error),
+        };
+        return_result_account(&ctx.accounts.registry.key());
+
+        ctx.accounts.global_stats.usage.record(Usage::Computation);
+
+        let registry = &mut ctx.accounts.registry;
+        registry.encrypted_registry = cards.ciphertexts;
+        registry.nonce = cards.nonce;
+        registry.status = PoolStatus::Open as u8;
+
+        emit!(CardRegistryOpened {
+            registry_id: registry.registry_id,
+        });
+
+        Ok(())
+    }
+
+    /// Publish the signer's contact card: their own hashed identifier
+    /// and an X25519 messaging key, encrypted to their key. The MPC binds
+    /// the entry to the signer's folded pubkey, so only the wallet that
+    /// first published a hash can replace its key. Nothing checks that
+    /// the hash is really the publisher's: the first wallet to claim an
+    /// identifier owns its entry.
+    ///
+    /// Every publish rewrites the whole registry, so the registry stays
+    /// `Initializing` until the callback stores the result; publishes and
+    /// lookups queued meanwhile are refused.
+    pub fn publish_contact_card(
+        ctx: Context<PublishContactCard>,
+        computation_offset: u64,
+        encrypted_card: [[u8; 32]; 4],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        require!(
+            registry.status == PoolStatus::Open as u8,
+            ErrorCode::RegistryNotOpen
+        );
+        registry.status = PoolStatus::Initializing as u8;
+        registry.publishes += 1;
+        let registry_id = registry.registry_id;
+        let registry_nonce = registry.nonce;
+        let publisher = ctx.accounts.publisher.key();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // Registry (Enc<Mxe, CardRegistry>) is read straight from its account
+        let stored = args::stored_list_args(
+            registry_nonce,
+            ctx.accounts.registry.key(),
+            DiscoveryCardRegistry::ENCRYPTED_REGISTRY_OFFSET,
+            DiscoveryCardRegistry::ENCRYPTED_REGISTRY_LEN,
+        );
+        let args = args::push_contact_card(stored, pubkey, nonce, &encrypted_card)
+            .plaintext_u128(fold_pubkey(&publisher))
+            .build();
+
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![PublishContactCardCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.registry.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats_pda(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(ContactCardPublishing {
+            registry_id,
+            publisher,
+        });
+
+        Ok(())
+    }
+
+    /// Callback for a publish: store the updated registry and reopen it.
+    /// A computation that fails verification still reopens the registry
+    /// with its previous contents, so one bad publish cannot lock it.
+    #[arcium_callback(encrypted_ix = "publish_contact_card")]
+    pub fn publish_contact_card_callback(
+        ctx: Context<PublishContactCardCallback>,
+        output: SignedComputationOutputs<PublishContactCardOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let cards = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(PublishContactCardOutput { field_0 }) => field_0,
+            Err(error) => {
+                ctx.accounts.registry.status = PoolStatus::Open as u8;
+                return computation_failed(ctx.accounts.registry.registry_id, NO_STAGE, 0, &error);
+            }
+        };
+        return_result_account(&ctx.accounts.registry.key());
+
+        ctx.accounts.global_stats.usage.record(Usage::Computation);
+
+        let registry = &mut ctx.accounts.registry;
+        registry.encrypted_registry = cards.ciphertexts;
+        registry.nonce = cards.nonce;
+        registry.status = PoolStatus::Open as u8;
+
+        emit!(ContactCardPublished {
+            registry_id: registry.registry_id,
+        });
+
+        Ok(())
+    }
+
+    /// Any user looks their contacts up in an open registry and gets back,
+    /// encrypted to their key, the card of each registered contact that
+    /// is also in their list: enough to open an encrypted channel to the
+    /// people they already know. Entries for anyone else stay hidden, and
+    /// the registry stays unchanged.
+    pub fn match_contact_cards(
+        ctx: Context<MatchContactCards>,
+        computation_offset: u64,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        require!(
+            hash_scheme == registry.hash_scheme,
+            ErrorCode::HashSchemeMismatch
+        );
+        require!(
+            registry.status == PoolStatus::Open as u8,
+            ErrorCode::RegistryNotOpen
+        );
+        registry.lookups += 1;
+        let registry_id = registry.registry_id;
+        let registry_nonce = registry.nonce;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let stored = args::stored_list_args(
+            registry_nonce,
+            ctx.accounts.registry.key(),
+            DiscoveryCardRegistry::ENCRYPTED_REGISTRY_OFFSET,
+            DiscoveryCardRegistry::ENCRYPTED_REGISTRY_LEN,
+        );
+        let args = args::push_contact_list(
+            stored,
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .build();
+
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![MatchContactCardsCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.registry.key(),
+                        is_writable: false,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats_pda(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(CardsMatching {
+            registry_id,
+            user: ctx.accounts.user.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Callback for a card lookup
+    #[arcium_callback(encrypted_ix = "match_contact_cards")]
+    pub fn match_contact_cards_callback(
+        ctx: Context<MatchContactCardsCallback>,
+        output: SignedComputationOutputs<MatchContactCardsOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let result = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(MatchContactCardsOutput { field_0 }) => field_0,
+            Err(error) => return computation_failed(ctx.accounts.registry.registry_id, NO_STAGE, 0, &error),
+        };
+        return_result_account(&ctx.accounts.registry.key());
+
+        ctx.accounts.global_stats.usage.record(Usage::Computation);
+
+        let mut encrypted_hashes = [[0u8; 32]; args::CARD_SLOTS];
+        let mut encrypted_keys_lo = [[0u8; 32]; args::CARD_SLOTS];
+        let mut encrypted_keys_hi = [[0u8; 32]; args::CARD_SLOTS];
+        encrypted_hashes.copy_from_slice(&result.ciphertexts[..args::CARD_SLOTS]);
+        encrypted_keys_lo.copy_from_slice(&result.ciphertexts[args::CARD_SLOTS..2 * args::CARD_SLOTS]);
+        encrypted_keys_hi.copy_from_slice(&result.ciphertexts[2 * args::CARD_SLOTS..3 * args::CARD_SLOTS]);
+
+        emit!(CardsMatched {
+            registry_id: ctx.accounts.registry.registry_id,
+            encrypted_hashes,
+            encrypted_keys_lo,
+            encrypted_keys_hi,
+            encrypted_count: result.ciphertexts[3 * args::CARD_SLOTS],
+            nonce: result.nonce.to_le_bytes(),
+        });
+
+        Ok(())
+    }
+
+    // ============================================================
+    // MUTUAL FOLLOWS
+    // ============================================================
+
+    /// Bob submits his follow list (folded follow-target pubkeys) to a
+    /// mutual-follow session; Alice's list is the one she submitted with
+    /// `submit_contacts_alice`. The program folds both parties' own
+    /// pubkeys (Alice from the session, Bob from the signer) and passes
+    /// them to the MPC in plaintext, so neither side can claim to be
+    /// someone else. The MPC learns whether each follows the other and
+    /// returns only that bit.
+    pub fn submit_and_check_follow(
+        ctx: Context<SubmitAndCheckFollow>,
+        computation_offset: u64,
+        hash_scheme: u8,
+        encrypted_hashes: [[u8; 32]; 32],
+        encrypted_count: [u8; 32],
+        encrypted_namespace: [u8; 32],
+        pubkey: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        require!(
+            hash_scheme == session.hash_scheme,
+            ErrorCode::HashSchemeMismatch
+        );
+        session.require_status(Status::AwaitingBob)?;
+        session.require_joinable(Clock::get()?.unix_timestamp)?;
+        require!(
+            session.mode == SessionMode::MutualFollow as u8,
+            ErrorCode::ModeMismatch
+        );
+        let bob = ctx.accounts.bob.key();
+        require!(bob != session.alice, ErrorCode::Unauthorized);
+
+        let alice_id = fold_pubkey(&session.alice);
+        let bob_id = fold_pubkey(&bob);
+        let slots = session.capacity() as u32;
+        record_bob(&mut session, bob, slots, &ctx.accounts.billing.config, ctx.remaining_accounts)?;
+        session.transition(Status::Computing)?;
+        let session_id = session.session_id;
+        let memo = session.memo;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // The list, both parties' folded pubkeys, and the session tag
+        let args = args::contact_list_args(
+            pubkey,
+            nonce,
+            &encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+        )
+        .plaintext_u128(alice_id)
+        .plaintext_u128(bob_id)
+        .plaintext_u128(session.state_tag())
+        .build();
+
+        session.record_queued(ComputationStage::Match, computation_offset)?;
+        ctx.accounts.billing.charge(&mut session, ComputationStage::Match, &ctx.accounts.pool_account)?;
+        let (global_stats, app_stats) = session.stats_pdas();
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![SubmitAndCheckFollowCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats,
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(ContactsSubmitted {
+            session_id,
+            memo,
+            party: 2,
+        });
+        emit!(MatchComputing {
+            session_id,
+            memo,
+        });
+
+        Ok(())
+    }
+
+    /// Callback for a mutual-follow check
+    #[arcium_callback(encrypted_ix = "submit_and_check_follow")]
+    pub fn submit_and_check_follow_callback(
+        ctx: Context<SubmitAndCheckFollowCallback>,
+        output: SignedComputationOutputs<SubmitAndCheckFollowOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let (mutual, accepted) = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(SubmitAndCheckFollowOutput {
+                field_0: SubmitAndCheckFollowOutputStruct0 { field_1, field_2, .. },
+            }) => (field_1, field_2),
+            Err(error) => return fail_computation(&ctx.accounts.session, ComputationStage::Match, &error),
+        };
+        return_result_account(&ctx.accounts.session.key());
+
+        let mut session = ctx.accounts.session.load_mut()?;
+        if !session.consume_callback(ComputationStage::Match, Clock::get()?.slot) {
+            return Ok(());
+        }
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Computation);
+        if !accepted {
+            meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Rejection);
+            return reopen_bob_slot(&mut session);
+        }
+
+        // Either party can now reveal the flag with reveal_second_degree
+        session.transition(Status::Matched)?;
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Match);
+        session.rejected_party = 0;
+
+        emit!(FollowChecked {
+            session_id: session.session_id,
+            memo: session.memo,
+            encrypted_mutual: mutual.ciphertexts[0],
+            nonce: mutual.nonce.to_le_bytes(),
+        });
+
+        Ok(())
+    }
+
+    // ============================================================
+    // SESSION MAINTENANCE
+    // ============================================================
+
+    /// Zero the session's stored MXE state: both lists and all results.
+    /// Either party may purge a matched session, which ends reveals for
+    /// both; Alice may purge a session Bob never joined once its
+    /// `join_deadline` has passed. The session is `Purged` from here on.
+    pub fn purge_session_state(
+        ctx: Context<PurgeSessionState>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        let signer = ctx.accounts.party.key();
+        match session.status()? {
+            Status::Matched | Status::Failed => {
+                require!(
+                    signer == session.alice || signer == session.bob,
+                    ErrorCode::Unauthorized
+                );
+            }
+            Status::AwaitingAlice | Status::AwaitingBob => {
+                require!(signer == session.alice, ErrorCode::Unauthorized);
+                require!(
+                    session.join_expired(Clock::get()?.unix_timestamp),
+                    ErrorCode::DeadlineNotReached
+                );
+            }
+            _ => return Err(ErrorCode::InvalidSessionState.into()),
+        }
+        session.require_current_state()?;
+        session.transition(Status::Purged)?;
+        session.purged_slot = 0;
+        let session_id = session.session_id;
+        let memo = session.memo;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // The circuit takes only the stored state
+        let args = ArgBuilder::new().build();
+
+        let (global_stats, app_stats) = session.stats_pdas();
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![PurgeSessionStateCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats,
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(StatePurging { session_id, memo });
+
+        Ok(())
+    }
+
+    /// Callback for a state purge
+    #[arcium_callback(encrypted_ix = "purge_session_state")]
+    pub fn purge_session_state_callback(
+        ctx: Context<PurgeSessionStateCallback>,
+        output: SignedComputationOutputs<PurgeSessionStateOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        if let Err(error) = verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            return computation_failed(ctx.accounts.session.load()?.session_id, NO_STAGE, 0, &error);
+        }
+        return_result_account(&ctx.accounts.session.key());
+
+        let mut session = ctx.accounts.session.load_mut()?;
+        if session.purged_slot != 0 {
+            return Ok(());
+        }
+        session.purged_slot = Clock::get()?.slot;
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Computation);
+        emit!(StatePurged {
+            session_id: session.session_id,
+            memo: session.memo,
+        });
+
+        Ok(())
+    }
+
+    /// Re-encode the session's stored MXE state from the previous
+    /// `SessionState` layout into the current one, after an upgrade that
+    /// bumped `STATE_VERSION`. Until it lands, every computation on the
+    /// session fails with `StateMigrationRequired`, so state in the old
+    /// layout is never fed to circuits reading the new one. Either party
+    /// may migrate; nothing may be pending, and a session already on
+    /// the current layout fails with `StateCurrent`.
+    pub fn migrate_session_state(
+        ctx: Context<MigrateSessionState>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        let session = ctx.accounts.session.load()?;
+        let signer = ctx.accounts.party.key();
+        require!(
+            signer == session.alice || (signer == session.bob && session.bob != Pubkey::default()),
+            ErrorCode::Unauthorized
+        );
+        require!(session.status()? != Status::Purged, ErrorCode::InvalidSessionState);
+        require!(session.pending_stage().is_none(), ErrorCode::ComputationPending);
+        require!(session.state_version() < STATE_VERSION, ErrorCode::StateCurrent);
+        let session_id = session.session_id;
+        let memo = session.memo;
+        let from_version = session.state_version();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // The circuit takes only the stored state
+        let args = ArgBuilder::new().build();
+
+        let (global_stats, app_stats) = session.stats_pdas();
+        drop(session);
+        queue!(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![MigrateSessionStateCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.session.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.state_handle.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: global_stats,
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: app_stats,
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        emit!(StateMigrating {
+            session_id,
+            memo,
+            from_version,
+        });
+
+        Ok(())
+    }
+
+    /// Callback for a state migration: point the handle at the migrated
+    /// state and tag the session with `STATE_VERSION`.
+    #[arcium_callback(encrypted_ix = "migrate_session_state")]
+    pub fn migrate_session_state_callback(
+        ctx: Context<MigrateSessionStateCallback>,
+        output: SignedComputationOutputs<MigrateSessionStateOutput>,
+    ) -> Result<()> {
+        guard::require_callback_transaction(&ctx.accounts.instructions_sysvar)?;
+        let state = match verify_output!(
+            output,
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(MigrateSessionStateOutput { field_0 }) => field_0,
+            Err(error) => return computation_failed(ctx.accounts.session.load()?.session_id, NO_STAGE, 0, &error),
+        };
+        return_result_account(&ctx.accounts.session.key());
+
+        let mut session = ctx.accounts.session.load_mut()?;
+        // A second migration queued before the first landed
+        if session.state_version() >= STATE_VERSION {
+            return Ok(());
+        }
+        let from_version = session.state_version();
+        session.state_version = STATE_VERSION;
+        ctx.accounts.state_handle.record(state.nonce, &state.ciphertexts);
+        meter(&mut ctx.accounts.global_stats, &mut ctx.accounts.app_stats, Usage::Computation);
+        emit!(StateMigrated {
+            session_id: session.session_id,
+            memo: session.memo,
+            from_version,
+            state_version: STATE_VERSION,
+        });
+
+        Ok(())
+    }
+
+    /// Alice closes a session whose state has been purged, along with its
+    /// state handle. A session that never stored state (a stateless one
+    /// Alice has not submitted to, or one whose first computation failed
+    /// and has been refunded) has nothing to purge and closes as is. The
+    /// rent goes back to whoever funded the session's creation.
+    pub fn close_session(ctx: Context<CloseSession>) -> Result<()> {
+        let session = ctx.accounts.session.load()?;
+        require!(
+            ctx.accounts.alice.key() == session.alice,
+            ErrorCode::Unauthorized
+        );
+        if ctx.accounts.state_handle.generation == 0 {
+            require!(
+                matches!(session.status()?, Status::AwaitingAlice | Status::Failed),
+                ErrorCode::InvalidSessionState
+            );
+            require!(session.pending_stage().is_none(), ErrorCode::ComputationPending);
+            require!(session.refundable_fee == 0, ErrorCode::RefundUnclaimed);
+        } else {
+            session.require_status(Status::Purged)?;
+            require!(session.purged_slot != 0, ErrorCode::PurgePending);
+        }
+        require!(
+            ctx.accounts.rent_payer.key() == session.rent_recipient(),
+            ErrorCode::RentPayerMismatch
+        );
+
+        emit!(SessionClosed {
+            session_id: session.session_id,
+            memo: session.memo,
+        });
+        record_activity(ctx.remaining_accounts, &session.alice, session.session_id, ActivityKind::Closed)?;
+
+        Ok(())
+    }
+
+    /// Close up to `MAX_SWEEP` abandoned sessions in one transaction,
+    /// without their owners: each passes `DiscoverySession::sweepable`.
+    /// Remaining accounts come in threes, as `SweptSession`: the
+    /// session's rent recipient, the session, and its state handle. The
+    /// recipient gets the rent less `SWEEP_REWARD_BPS` of the session's,
+    /// which goes to the sweeper. Anyone may crank it.
+    pub fn sweep_sessions<'info>(ctx: Context<'_, '_, 'info, 'info, SweepSessions<'info>>) -> Result<()> {
+        let count = ctx.remaining_accounts.len() / 3;
+        require!(
+            ctx.remaining_accounts.len() % 3 == 0 && (1..=MAX_SWEEP).contains(&count),
+            ErrorCode::InvalidSweep
+        );
+        let clock = Clock::get()?;
+
+        let mut accounts = ctx.remaining_accounts;
+        while !accounts.is_empty() {
+            let mut bumps = SweptSessionBumps::default();
+            let mut reallocs = BTreeSet::new();
+            let mut swept = SweptSession::try_accounts(&crate::ID, &mut accounts, &[], &mut bumps, &mut reallocs)?;
+            {
+                let session = swept.session.load()?;
+                let stores_state = swept.state_handle.generation != 0;
+                require!(
+                    session.sweepable(stores_state, clock.slot, clock.unix_timestamp)?,
+                    ErrorCode::NotSweepable
+                );
+                require!(
+                    swept.rent_payer.key() == session.rent_recipient(),
+                    ErrorCode::RentPayerMismatch
+                );
+                emit!(SessionClosed {
+                    session_id: session.session_id,
+                    memo: session.memo,
+                });
+            }
+
+            let reward = swept.session.get_lamports() * SWEEP_REWARD_BPS / 10_000;
+            swept.session.sub_lamports(reward)?;
+            ctx.accounts.sweeper.add_lamports(reward)?;
+            // Closes both accounts to the rent recipient
+            swept.exit(&crate::ID)?;
+        }
+
+        Ok(())
+    }
+
+    /// Alice grows a session created under an older layout to the current
+    /// `DiscoverySession::SIZE` (the payer funds the extra rent) and tags it with
+    /// `SESSION_VERSION`. New bytes are zeroed, which every added field
+    /// must treat as its default. A session already on the current layout
+    /// is left as is. Sessions that predate `SessionStateHandle` get one
+    /// here, so they can keep queueing computations.
+    pub fn realloc_session(ctx: Context<ReallocSession>) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        require!(
+            ctx.accounts.alice.key() == session.alice,
+            ErrorCode::Unauthorized
+        );
+        let from_version = session.version;
+        session.version = SESSION_VERSION;
+
+        let handle = &mut ctx.accounts.state_handle;
+        if handle.session == Pubkey::default() {
+            handle.session = ctx.accounts.session.key();
+            handle.bump = ctx.bumps.state_handle;
+            handle.generation = 1;
+        }
+
+        emit!(SessionReallocated {
+            session_id: session.session_id,
+            memo: session.memo,
+            from_version,
+            version: SESSION_VERSION,
+        });
+
+        Ok(())
+    }
+
+    /// Alice registers where the integrating application wants completion
+    /// notices routed: an opaque hash (of a webhook URL, an app id, ...)
+    /// that the program never interprets. It is echoed in `MatchComplete`
+    /// so an indexer or relayer can route per application. All zeros
+    /// clears it.
+    pub fn register_notification(
+        ctx: Context<RegisterNotification>,
+        notification_target: [u8; 32],
+    ) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        require!(
+            ctx.accounts.alice.key() == session.alice,
+            ErrorCode::Unauthorized
+        );
+        require!(
+            session.status()? != Status::Purged,
+            ErrorCode::InvalidSessionState
+        );
+        session.notification_target = notification_target;
+
+        emit!(NotificationRegistered {
+            session_id: session.session_id,
+            memo: session.memo,
+            notification_target,
+        });
+
+        Ok(())
+    }
+
+    /// Alice registers a program for the match callback to notify: once
+    /// the match lands, the callback calls `program` with an
+    /// `AppHookNotice`, signed by the session's `hook_authority` PDA for
+    /// `program` and followed by `accounts`, so an integrating app can
+    /// act on the match in the same transaction (mint a connection
+    /// record, ...). Only before the match is queued; registering again
+    /// replaces the hook. The notice is best-effort: a hook that cannot
+    /// be called is reported with `AppHookFailed` and the match stands.
+    pub fn register_app_hook(
+        ctx: Context<RegisterAppHook>,
+        program: Pubkey,
+        accounts: Vec<HookAccount>,
+    ) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        require!(
+            ctx.accounts.alice.key() == session.alice,
+            ErrorCode::Unauthorized
+        );
+        require!(
+            matches!(session.status()?, Status::AwaitingAlice | Status::AwaitingBob),
+            ErrorCode::InvalidSessionState
+        );
+        require!(
+            accounts.len() <= MAX_HOOK_ACCOUNTS && program != ID && program != anchor_lang::system_program::ID,
+            ErrorCode::InvalidAppHook
+        );
+        session.app_hook = 1;
+
+        let hook = &mut ctx.accounts.app_hook;
+        hook.session = ctx.accounts.session.key();
+        hook.program = program;
+        hook.accounts = [Pubkey::default(); MAX_HOOK_ACCOUNTS];
+        hook.writable = 0;
+        for (i, account) in accounts.iter().enumerate() {
+            hook.accounts[i] = account.pubkey;
+            hook.writable |= (account.is_writable as u8) << i;
+        }
+        hook.account_count = accounts.len() as u8;
+        hook.bump = ctx.bumps.app_hook;
+
+        emit!(AppHookRegistered {
+            session_id: session.session_id,
+            memo: session.memo,
+            program,
+            account_count: hook.account_count,
+        });
+
+        Ok(())
+    }
+
+    // ============================================================
+    // CONNECTION ATTESTATIONS
+    // ============================================================
+
+    /// Alice or Bob consents to an on-chain record of their connection.
+    /// Only on a matched session; once both have consented, anyone can
+    /// call `attest_connection`.
+    pub fn consent_connection(ctx: Context<ConsentConnection>) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        require!(
+            session.status()? == Status::Matched,
+            ErrorCode::InvalidSessionState
+        );
+        let party = ctx.accounts.party.key();
+        let bit = if party == session.alice {
+            CONSENT_ALICE
+        } else if party == session.bob {
+            CONSENT_BOB
+        } else {
+            return err!(ErrorCode::Unauthorized);
+        };
+        session.connection_consent |= bit;
+
+        emit!(ConnectionConsented {
+            session_id: session.session_id,
+            memo: session.memo,
+            party: bit,
+        });
+
+        Ok(())
+    }
+
+    /// Record the connection between a matched session's parties as a
+    /// `ConnectionAttestation`, once both consented, and give each of
+    /// them a soulbound Token-2022 token of the attestation's mint.
+    /// `party_a` and `party_b` are the two wallets in ascending order,
+    /// so each pair has one attestation however many sessions they
+    /// share. The attestation belongs to this program and has no
+    /// instruction that moves it; the tokens are non-transferable.
+    pub fn attest_connection(ctx: Context<AttestConnection>, party_a: Pubkey, party_b: Pubkey) -> Result<()> {
+        let session = ctx.accounts.session.load()?;
+        require!(
+            session.status()? == Status::Matched,
+            ErrorCode::InvalidSessionState
+        );
+        require!(
+            session.connection_consent == CONSENT_ALICE | CONSENT_BOB,
+            ErrorCode::ConsentMissing
+        );
+        let (first, second) = if session.alice < session.bob {
+            (session.alice, session.bob)
+        } else {
+            (session.bob, session.alice)
+        };
+        require!(
+            party_a == first && party_b == second,
+            ErrorCode::Unauthorized
+        );
+
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.party_a = party_a;
+        attestation.party_b = party_b;
+        attestation.session_id = session.session_id;
+        attestation.app_id = session.app_id;
+        attestation.attested_at = Clock::get()?.unix_timestamp;
+        attestation.bump = ctx.bumps.attestation;
+
+        let attestation_info = ctx.accounts.attestation.to_account_info();
+        soulbound::mint_soulbound(
+            &soulbound::SoulboundAccounts {
+                payer: &ctx.accounts.payer.to_account_info(),
+                mint: &ctx.accounts.mint.to_account_info(),
+                authority: &attestation_info,
+                system_program: &ctx.accounts.system_program.to_account_info(),
+                token_program: &ctx.accounts.token_program.to_account_info(),
+                associated_token_program: &ctx.accounts.associated_token_program.to_account_info(),
+            },
+            &[b"connection", party_a.as_ref(), party_b.as_ref(), &[ctx.bumps.attestation]],
+            &[
+                (&ctx.accounts.party_a_wallet.to_account_info(), &ctx.accounts.party_a_token.to_account_info()),
+                (&ctx.accounts.party_b_wallet.to_account_info(), &ctx.accounts.party_b_token.to_account_info()),
+            ],
+        )?;
+
+        emit!(ConnectionAttested {
+            session_id: session.session_id,
+            memo: session.memo,
+            attestation: ctx.accounts.attestation.key(),
+            mint: ctx.accounts.mint.key(),
+            party_a,
+            party_b,
+        });
+
+        Ok(())
+    }
+
+    // ============================================================
+    // ACTIVITY LOGS
+    // ============================================================
+
+    /// A wallet opens its `ActivityLog`, a ring buffer of the last
+    /// `ACTIVITY_LOG_LEN` session events that concerned it, so a wallet
+    /// can show recent discovery activity without an indexer. Optional:
+    /// instructions only write a log passed to them as a remaining
+    /// account.
+    pub fn open_activity_log(ctx: Context<OpenActivityLog>) -> Result<()> {
+        let log = &mut ctx.accounts.activity_log;
+        log.wallet = ctx.accounts.wallet.key();
+        log.bump = ctx.bumps.activity_log;
+        Ok(())
+    }
+
+    /// The wallet closes its `ActivityLog`, reclaiming the rent.
+    pub fn close_activity_log(_ctx: Context<CloseActivityLog>) -> Result<()> {
+        Ok(())
+    }
+
+    /// A wallet sets who may create sessions naming it as their
+    /// `intended_bob`: up to `MAX_CONSENT_COUNTERPARTIES` wallets as
+    /// Alice, and up to `MAX_CONSENT_APPS` apps whose sessions may. The
+    /// lists replace the previous ones; the record is created, funded by
+    /// the wallet, on first use. Without a record nobody may name the
+    /// wallet, though anyone may still join an open session.
+    pub fn set_consent_record(
+        ctx: Context<SetConsentRecord>,
+        counterparties: Vec<Pubkey>,
+        apps: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require!(
+            counterparties.len() <= MAX_CONSENT_COUNTERPARTIES
+                && apps.len() <= MAX_CONSENT_APPS
+                && !apps.contains(&NO_APP_ID),
+            ErrorCode::InvalidConsentRecord
+        );
+        let record = &mut ctx.accounts.consent_record;
+        record.wallet = ctx.accounts.wallet.key();
+        record.counterparties = [Pubkey::default(); MAX_CONSENT_COUNTERPARTIES];
+        record.counterparties[..counterparties.len()].copy_from_slice(&counterparties);
+        record.counterparty_count = counterparties.len() as u8;
+        record.apps = [[0; 32]; MAX_CONSENT_APPS];
+        record.apps[..apps.len()].copy_from_slice(&apps);
+        record.app_count = apps.len() as u8;
+        record.bump = ctx.bumps.consent_record;
+
+        emit!(ConsentRecordSet {
+            wallet: record.wallet,
+            counterparties,
+            apps,
+        });
+
+        Ok(())
+    }
+
+    /// The wallet closes its `ConsentRecord`, reclaiming the rent; no one
+    /// may name it as Bob afterwards.
+    pub fn close_consent_record(_ctx: Context<CloseConsentRecord>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Open the `PairQuota` that counts the sessions between `party_a`
+    /// and `party_b`, the two wallets in ascending order. Anyone may
+    /// open it and fund it; it is needed only while the config caps
+    /// sessions per pair or sets a query budget.
+    pub fn open_pair_quota(ctx: Context<OpenPairQuota>, party_a: Pubkey, party_b: Pubkey) -> Result<()> {
+        require!(party_a < party_b, ErrorCode::InvalidPair);
+        let quota = &mut ctx.accounts.pair_quota;
+        quota.party_a = party_a;
+        quota.party_b = party_b;
+        quota.bump = ctx.bumps.pair_quota;
+        Ok(())
+    }
+
+    /// One wallet of a `PairQuota` re-consents to the other querying
+    /// it, resetting the slots the other has submitted to 0.
+    pub fn renew_query_budget(ctx: Context<RenewQueryBudget>) -> Result<()> {
+        let target = ctx.accounts.target.key();
+        let quota = &mut ctx.accounts.pair_quota;
+        let querier = if target == quota.party_a {
+            quota.party_b
+        } else if target == quota.party_b {
+            quota.party_a
+        } else {
+            return err!(ErrorCode::Unauthorized);
+        };
+        quota.queried_slots[usize::from(querier == quota.party_b)] = 0;
+
+        emit!(QueryBudgetRenewed {
+            pair_quota: quota.key(),
+            target,
+            querier,
+        });
+
+        Ok(())
+    }
+
+    // ============================================================
+    // DEPLOYMENT REGISTRY
+    // ============================================================
+
+    /// Record this deployment in the `DeploymentRegistry`: the program,
+    /// session layout and circuit versions this binary was built with,
+    /// which computation definitions are ready, and the MXE and cluster
+    /// it computes on. Send after deploying or upgrading the program and
+    /// after initializing or finalizing computation definitions. Anyone
+    /// may send it, since it only copies what is on-chain; every
+    /// computation definition must be passed, in `CIRCUITS` order, so
+    /// none can be left out to misreport it.
+    pub fn sync_deployment(ctx: Context<SyncDeployment>) -> Result<()> {
+        require!(
+            ctx.remaining_accounts.len() == CIRCUITS.len(),
+            ErrorCode::CompDefListMismatch
+        );
+        let mut initialized_comp_defs = 0u64;
+        for (i, ((_, offset), info)) in CIRCUITS.iter().zip(ctx.remaining_accounts).enumerate() {
+            require_keys_eq!(info.key(), derive_comp_def_pda!(*offset), ErrorCode::CompDefListMismatch);
+            let ready = Account::<ComputationDefinitionAccount>::try_from(info)
+                .map(|comp_def| comp_def_ready(&comp_def))
+                .unwrap_or(false);
+            initialized_comp_defs |= (ready as u64) << i;
+        }
+
+        let mxe = &ctx.accounts.mxe_account;
+        let registry = &mut ctx.accounts.registry;
+        registry.program_version = PROGRAM_VERSION;
+        registry.session_version = SESSION_VERSION;
+        registry.circuit_versions = CIRCUIT_VERSIONS;
+        registry.initialized_comp_defs = initialized_comp_defs;
+        registry.mxe = mxe.key();
+        registry.cluster_offset = mxe.cluster.unwrap_or(NO_CLUSTER);
+        registry.mxe_x25519_pubkey = mxe.utility_pubkeys.x25519_pubkey().unwrap_or_default();
+        registry.updated_slot = Clock::get()?.slot;
+        registry.bump = ctx.bumps.registry;
+
+        emit!(DeploymentSynced {
+            program_version: PROGRAM_VERSION,
+            initialized_comp_defs,
+            cluster_offset: registry.cluster_offset,
+        });
+        Ok(())
+    }
+
+    /// Bob commits to the invite code of a session created with an
+    /// `invite_hash` without revealing it: `commitment` is `invite_claim`
+    /// over the session id, the code and his wallet, kept in his
+    /// `InviteClaim` PDA of the session until he redeems it. Only while
+    /// the session awaits Bob with an invite pending.
+    pub fn claim_invite(ctx: Context<ClaimInvite>, commitment: [u8; 32]) -> Result<()> {
+        let session = ctx.accounts.session.load()?;
+        session.require_status(Status::AwaitingBob)?;
+        require!(session.invite_hash != NO_INVITE, ErrorCode::InvalidInviteCode);
+
+        let claim = &mut ctx.accounts.invite_claim;
+        claim.session = ctx.accounts.session.key();
+        claim.bob = ctx.accounts.bob.key();
+        claim.commitment = commitment;
+        claim.slot = Clock::get()?.slot;
+        claim.bump = ctx.bumps.invite_claim;
+        Ok(())
+    }
+
+    /// Bob reveals the invite code he claimed with `claim_invite` in an
+    /// earlier slot, and becomes the session's `intended_bob`: he alone
+    /// may join it from then on. The claim binds the code to his wallet
+    /// before it is public, so a copy of this transaction cannot take
+    /// the invite: the copier has no earlier claim. Closes the claim.
+    /// Fails with `InvalidInviteCode` if the code does not hash to the
+    /// session's invite or to the claim, or the session has none
+    /// pending, with `InviteClaimTooRecent` in the claim's own slot, and
+    /// with `InviteCodeTooShort` for a code under `MIN_INVITE_CODE_LEN`.
+    pub fn redeem_invite(ctx: Context<RedeemInvite>, code: String) -> Result<()> {
+        require!(code.len() >= MIN_INVITE_CODE_LEN, ErrorCode::InviteCodeTooShort);
+        let mut session = ctx.accounts.session.load_mut()?;
+        session.require_status(Status::AwaitingBob)?;
+        let bob = ctx.accounts.bob.key();
+        require!(bob != session.alice, ErrorCode::Unauthorized);
+        require!(
+            session.invite_hash != NO_INVITE
+                && invite_hash(&session.session_id, code.as_bytes()) == session.invite_hash
+                && invite_claim(&session.session_id, code.as_bytes(), &bob) == ctx.accounts.invite_claim.commitment,
+            ErrorCode::InvalidInviteCode
+        );
+        require!(
+            Clock::get()?.slot > ctx.accounts.invite_claim.slot,
+            ErrorCode::InviteClaimTooRecent
+        );
+        session.invite_hash = NO_INVITE;
+        session.intended_bob = bob;
+
+        emit!(InviteRedeemed {
+            session_id: session.session_id,
+            memo: session.memo,
+            bob,
+        });
+
+        Ok(())
+    }
+
+    /// Re-emit the session's current status as `SessionHeartbeat`, so an
+    /// indexer that missed events can resynchronize a live session
+    /// without scanning accounts. Anyone may crank it, once per
+    /// `PING_INTERVAL_SLOTS` per session.
+    pub fn ping_session(ctx: Context<PingSession>) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        let slot = Clock::get()?.slot;
+        require!(
+            session.last_ping_slot == 0 || slot >= session.last_ping_slot + PING_INTERVAL_SLOTS,
+            ErrorCode::PingTooSoon
+        );
+        session.last_ping_slot = slot;
+
+        emit!(SessionHeartbeat {
+            session_id: session.session_id,
+            memo: session.memo,
+            alice: session.alice,
+            bob: session.bob,
+            status: session.status,
+            purged: session.purged_slot != 0,
+            pending_stage: session.pending_stage().map_or(NO_STAGE, |stage| stage as u8),
+        });
+
+        Ok(())
+    }
+
+    /// Read-only report on a session, meant to be simulated rather than
+    /// sent: the report comes back as the transaction's return data.
+    /// Pass the computation definition accounts as remaining accounts in
+    /// `CIRCUITS` order to have their readiness reported; any left out,
+    /// or at the wrong address, count as not initialized.
+    pub fn view_session(ctx: Context<ViewSession>) -> Result<SessionReport> {
+        let session = ctx.accounts.session.load()?;
+        let now = Clock::get()?.unix_timestamp;
+
+        let initialized_comp_defs = CIRCUITS
+            .iter()
+            .zip(ctx.remaining_accounts)
+            .filter(|((_, offset), info)| {
+                info.key() == derive_comp_def_pda!(*offset)
+                    && Account::<ComputationDefinitionAccount>::try_from(info)
+                        .map(|comp_def| comp_def_ready(&comp_def))
+                        .unwrap_or(false)
+            })
+            .map(|((circuit, _), _)| circuit.to_string())
+            .collect();
+        let pending = session.pending_stage();
+
+        Ok(SessionReport {
+            session_id: session.session_id,
+            status: session.status()?.name().to_string(),
+            mode: SessionMode::name(session.mode).to_string(),
+            capacity: session.capacity(),
+            alice: session.alice,
+            bob: session.bob,
+            app_id: session.app_id,
+            join_deadline: session.join_deadline,
+            join_expired: session.join_expired(now),
+            reveal_after_slot: session.reveal_after_slot,
+            reveal_locked: session.reveal_locked(Clock::get()?.slot),
+            pending_stage: pending.map(|stage| stage.name().to_string()),
+            pending_offset: pending.map(|stage| session.stage_offsets[stage as usize]),
+            version: session.version,
+            refundable_fee: session.refundable_fee,
+            memo: session.memo,
+            initialized_comp_defs,
+        })
+    }
+
+    // ============================================================
+    // BATCHED QUEUEING
+    // ============================================================
+
+    /// Queue up to `MAX_BATCH` computations, each on its own session, in
+    /// one transaction. Each item's accounts follow as remaining accounts,
+    /// in item order, exactly as its own instruction takes them, and the
+    /// item is handed to that instruction: it is authorized, billed and
+    /// called back as if sent alone, and any item failing fails the batch.
+    /// Only stages that take no contact list are batchable; four lists'
+    /// ciphertexts would not fit in one transaction.
+    pub fn queue_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, QueueBatch<'info>>,
+        items: Vec<BatchItem>,
+    ) -> Result<()> {
+        require!(
+            !items.is_empty() && items.len() <= MAX_BATCH,
+            ErrorCode::InvalidBatch
+        );
+
+        let mut accounts = ctx.remaining_accounts;
+        for item in items {
+            let offset = item.computation_offset;
+            match item.stage {
+                BatchStage::RevealAlice { pubkey, nonce, page } => {
+                    run_batched::<RevealAliceMatches>(&mut accounts, offset, |ctx| {
+                        reveal_alice_matches(ctx, offset, pubkey, nonce, page)
+                    })?
+                }
+                BatchStage::RevealBob { pubkey, nonce, page } => {
+                    run_batched::<RevealBobMatches>(&mut accounts, offset, |ctx| {
+                        reveal_bob_matches(ctx, offset, pubkey, nonce, page)
+                    })?
+                }
+                BatchStage::RevealAliceScore { pubkey, nonce } => {
+                    run_batched::<RevealAliceScore>(&mut accounts, offset, |ctx| {
+                        reveal_alice_score(ctx, offset, pubkey, nonce)
+                    })?
+                }
+                BatchStage::RevealSecondDegree { pubkey, nonce } => {
+                    run_batched::<RevealSecondDegree>(&mut accounts, offset, |ctx| {
+                        reveal_second_degree(ctx, offset, pubkey, nonce)
+                    })?
+                }
+                BatchStage::Purge => {
+                    run_batched::<PurgeSessionState>(&mut accounts, offset, |ctx| {
+                        purge_session_state(ctx, offset)
+                    })?
+                }
+            }
+        }
+        // Every account passed must belong to an item
+        require!(accounts.is_empty(), ErrorCode::InvalidBatch);
+
+        Ok(())
+    }
+
+    // ============================================================
+    // PREPAID CREDITS
+    // ============================================================
+
+    /// Create the deployment's `ProtocolConfig`, its `Treasury`, and the
+    /// credit account of sessions without an app. Only the program's
+    /// upgrade authority may call it, and it becomes the config admin.
+    /// `computation_price` is the lamports each queued session
+    /// computation debits from its app's credit; 0 makes them free.
+    pub fn init_config(ctx: Context<InitConfig>, computation_price: u64) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.computation_price = computation_price;
+        config.bump = ctx.bumps.config;
+
+        ctx.accounts.treasury.bump = ctx.bumps.treasury;
+
+        let credit = &mut ctx.accounts.unattributed_credit;
+        credit.app_id = NO_APP_ID;
+        credit.authority = ctx.accounts.admin.key();
+        credit.bump = ctx.bumps.unattributed_credit;
+
+        Ok(())
+    }
+
+    /// The config admin changes the price of a queued computation.
+    pub fn set_computation_price(ctx: Context<UpdateConfig>, computation_price: u64) -> Result<()> {
+        ctx.accounts.config.computation_price = computation_price;
+        emit!(ComputationPriceSet { computation_price });
+        Ok(())
+    }
+
+    /// The config admin sets the share of every computation fee, in
+    /// basis points, that tops up the Arcium fee pool instead of staying
+    /// in the treasury.
+    pub fn set_fee_pool_share(ctx: Context<UpdateConfig>, fee_pool_share_bps: u16) -> Result<()> {
+        require!(fee_pool_share_bps <= MAX_BPS, ErrorCode::InvalidFeeShare);
+        ctx.accounts.config.fee_pool_share_bps = fee_pool_share_bps;
+        emit!(FeePoolShareSet { fee_pool_share_bps });
+        Ok(())
+    }
+
+    /// The config admin sets the share, in basis points, of a failed
+    /// computation's fee that `claim_refund` returns.
+    pub fn set_refund_share(ctx: Context<UpdateConfig>, refund_bps: u16) -> Result<()> {
+        require!(refund_bps <= MAX_BPS, ErrorCode::InvalidFeeShare);
+        ctx.accounts.config.refund_bps = refund_bps;
+        emit!(RefundShareSet { refund_bps });
+        Ok(())
+    }
+
+    /// The config admin sets the oldest `PROGRAM_VERSION` a client may
+    /// have been built for to create a session. Raise it past a release
+    /// whose argument encoding is known to be broken, so its sessions
+    /// fail with `ClientVersionUnsupported` instead of matching garbage.
+    pub fn set_min_client_version(ctx: Context<UpdateConfig>, min_client_version: u16) -> Result<()> {
+        require!(min_client_version <= PROGRAM_VERSION, ErrorCode::InvalidClientVersion);
+        ctx.accounts.config.min_client_version = min_client_version;
+        emit!(MinClientVersionSet { min_client_version });
+        Ok(())
+    }
+
+    /// The config admin caps the sessions the same two wallets may join
+    /// within `PAIR_WINDOW_SECS`, 0 for no cap. Repeated adaptive
+    /// queries against one target can reconstruct its list one guess at
+    /// a time; the cap bounds how fast, and `set_query_budget` how far.
+    /// Once set, every instruction that records Bob must be passed the
+    /// pair's `PairQuota`.
+    pub fn set_max_pair_sessions(ctx: Context<UpdateConfig>, max_pair_sessions: u16) -> Result<()> {
+        ctx.accounts.config.max_pair_sessions = max_pair_sessions;
+        emit!(MaxPairSessionsSet { max_pair_sessions });
+        Ok(())
+    }
+
+    /// The config admin caps the contact slots one wallet may submit, as
+    /// Bob, against the same Alice over all their sessions, 0 for no
+    /// cap. This bounds what he could learn rather than what matched,
+    /// which the program never sees: each session charges his list's
+    /// capacity (every chunk's, for a committed set).
+    /// Past the cap his sessions with her fail with
+    /// `QueryBudgetExhausted` until she calls `renew_query_budget`.
+    /// Once set, every instruction that records Bob must be passed the
+    /// pair's `PairQuota`.
+    pub fn set_query_budget(ctx: Context<UpdateConfig>, query_budget: u32) -> Result<()> {
+        ctx.accounts.config.query_budget = query_budget;
+        emit!(QueryBudgetSet { query_budget });
+        Ok(())
+    }
+
+    /// The config admin requires both parties of a session to hold an
+    /// attestation from `gatekeeper` (a `gatekeeper::Gatekeeper`) within
+    /// `network` before Bob is recorded, or lifts the requirement with
+    /// `Gatekeeper::None`. Once set, every instruction that records Bob
+    /// must be passed both attestations.
+    pub fn set_gatekeeper(ctx: Context<UpdateConfig>, gatekeeper: u8, network: Pubkey) -> Result<()> {
+        require!(
+            gatekeeper::Gatekeeper::from_u8(gatekeeper).is_some(),
+            ErrorCode::InvalidGatekeeper
+        );
+        let config = &mut ctx.accounts.config;
+        config.gatekeeper = gatekeeper;
+        config.gatekeeper_network = network;
+        emit!(GatekeeperSet { gatekeeper, network });
+        Ok(())
+    }
+
+    /// The config admin blocks `wallet` from creating sessions and from
+    /// joining them as Bob, by opening its `BlockedWallet` PDA; those
+    /// instructions fail with `WalletBlocked` while it exists. Sessions already created or joined are left as
+    /// they are.
+    pub fn block_wallet(ctx: Context<BlockWallet>, wallet: Pubkey) -> Result<()> {
+        let blocked = &mut ctx.accounts.blocked_wallet;
+        blocked.wallet = wallet;
+        blocked.blocked_at = Clock::get()?.unix_timestamp;
+        blocked.bump = ctx.bumps.blocked_wallet;
+        emit!(WalletBlocked { wallet });
+        Ok(())
+    }
+
+    /// The config admin lifts a block, closing the `BlockedWallet` PDA
+    /// and taking back its rent.
+    pub fn unblock_wallet(ctx: Context<UnblockWallet>) -> Result<()> {
+        emit!(WalletUnblocked {
+            wallet: ctx.accounts.blocked_wallet.wallet,
+        });
+        Ok(())
+    }
+
+    /// Refund the configured share of the fee of the computation that
+    /// failed a session, from the treasury back to the credit of the
+    /// session's app, which paid it. Anyone may crank it; the refund
+    /// policy in force at the claim applies.
+    pub fn claim_refund(ctx: Context<ClaimRefund>) -> Result<()> {
+        let mut session = ctx.accounts.session.load_mut()?;
+        require!(session.refundable_fee > 0, ErrorCode::NothingToRefund);
+        require!(
+            ctx.accounts.credit.app_id == session.app_id,
+            ErrorCode::CreditAppMismatch
+        );
+        let fee = std::mem::take(&mut session.refundable_fee);
+        let refund = ctx.accounts.config.refund_share(fee);
+
+        let treasury = &mut ctx.accounts.treasury;
+        let spendable = treasury
+            .get_lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(Treasury::SIZE));
+        require!(refund <= spendable, ErrorCode::TreasuryShortfall);
+        treasury.refunded += refund;
+        treasury.sub_lamports(refund)?;
+
+        let credit = &mut ctx.accounts.credit;
+        credit.balance += refund;
+        credit.add_lamports(refund)?;
+
+        emit!(RefundClaimed {
+            session_id: session.session_id,
+            memo: session.memo,
+            app_id: session.app_id,
+            fee,
+            refund,
+        });
+
+        Ok(())
+    }
+
+    /// Open the credit account sessions of `app_id` are billed to. The
+    /// first to open it becomes its `authority`, the only key that can
+    /// withdraw from it.
+    pub fn open_credit_account(ctx: Context<OpenCreditAccount>, app_id: [u8; 32]) -> Result<()> {
+        let credit = &mut ctx.accounts.credit;
+        credit.app_id = app_id;
+        credit.authority = ctx.accounts.authority.key();
+        credit.balance = 0;
+        credit.bump = ctx.bumps.credit;
+        Ok(())
+    }
+
+    /// Add `amount` lamports to an app's credit. Anyone may top it up.
+    pub fn deposit_credit(ctx: Context<DepositCredit>, amount: u64) -> Result<()> {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.depositor.to_account_info(),
+                    to: ctx.accounts.credit.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        let credit = &mut ctx.accounts.credit;
+        credit.balance += amount;
+
+        emit!(CreditDeposited {
+            app_id: credit.app_id,
+            amount,
+            balance: credit.balance,
+        });
+
+        Ok(())
+    }
+
+    /// The credit account's authority takes back unspent credit.
+    pub fn withdraw_credit(ctx: Context<WithdrawCredit>, amount: u64) -> Result<()> {
+        let credit = &mut ctx.accounts.credit;
+        require!(credit.balance >= amount, ErrorCode::InsufficientCredit);
+        credit.balance -= amount;
+        credit.sub_lamports(amount)?;
+        ctx.accounts.authority.add_lamports(amount)?;
+
+        emit!(CreditWithdrawn {
+            app_id: credit.app_id,
+            amount,
+            balance: credit.balance,
+        });
+
+        Ok(())
+    }
+
+    // ============================================================
+    // APP LOOKUP TABLES
+    // ============================================================
+
+    /// Create an address lookup table for an app and fill it with the
+    /// Arcium accounts every queue instruction passes: the MXE, its
+    /// mempool, execution pool and cluster, the fee pool and the clock.
+    /// The table's authority is the app's `AppLookupTable` PDA, so only
+    /// this program can extend it, and only for the credit authority.
+    /// `recent_slot` must be a recent slot; the table's address is
+    /// derived from it and the PDA.
+    pub fn create_app_lut(ctx: Context<CreateAppLut>, app_id: [u8; 32], recent_slot: u64) -> Result<()> {
+        let app_lut_key = ctx.accounts.app_lut.key();
+        let payer_key = ctx.accounts.payer.key();
+        let (create_ix, table) = address_lookup_table::instruction::create_lookup_table(
+            app_lut_key,
+            payer_key,
+            recent_slot,
+        );
+        require_keys_eq!(
+            table,
+            ctx.accounts.lookup_table.key(),
+            ErrorCode::LookupTableMismatch
+        );
+
+        let bump = ctx.bumps.app_lut;
+        let signer_seeds: &[&[u8]] = &[b"app_lut", app_id.as_ref(), &[bump]];
+        invoke_signed(
+            &create_ix,
+            &[
+                ctx.accounts.lookup_table.to_account_info(),
+                ctx.accounts.app_lut.to_account_info(),
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            &[signer_seeds],
+        )?;
+
+        let addresses = vec![
+            ctx.accounts.mxe_account.key(),
+            ctx.accounts.mempool_account.key(),
+            ctx.accounts.executing_pool.key(),
+            ctx.accounts.cluster_account.key(),
+            ctx.accounts.pool_account.key(),
+            ctx.accounts.clock_account.key(),
+        ];
+        invoke_signed(
+            &address_lookup_table::instruction::extend_lookup_table(table, app_lut_key, Some(payer_key), addresses),
+            &[
+                ctx.accounts.lookup_table.to_account_info(),
+                ctx.accounts.app_lut.to_account_info(),
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            &[signer_seeds],
+        )?;
+
+        let app_lut = &mut ctx.accounts.app_lut;
+        app_lut.app_id = app_id;
+        app_lut.table = table;
+        app_lut.bump = bump;
+
+        emit!(AppLutCreated { app_id, table });
+
+        Ok(())
+    }
+
+    /// Add further addresses to an app's lookup table, e.g. the
+    /// computation definitions, sign PDA and billing accounts its
+    /// sessions use. Only the app's credit authority may extend it.
+    pub fn extend_app_lut(ctx: Context<ExtendAppLut>, addresses: Vec<Pubkey>) -> Result<()> {
+        let app_lut = &ctx.accounts.app_lut;
+        let count = addresses.len() as u32;
+        let signer_seeds: &[&[u8]] = &[b"app_lut", app_lut.app_id.as_ref(), &[app_lut.bump]];
+        invoke_signed(
+            &address_lookup_table::instruction::extend_lookup_table(
+                app_lut.table,
+                app_lut.key(),
+                Some(ctx.accounts.payer.key()),
+                addresses,
+            ),
+            &[
+                ctx.accounts.lookup_table.to_account_info(),
+                ctx.accounts.app_lut.to_account_info(),
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            &[signer_seeds],
+        )?;
+
+        emit!(AppLutExtended {
+            app_id: app_lut.app_id,
+            table: app_lut.table,
+            count,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================
+// ACCOUNT STRUCTURES
+// ============================================================
+
+/// What a session computes, stored as `DiscoverySession::mode`.
+#[repr(u8)]
+pub enum SessionMode {
+    /// One match, then Alice's reveal
+    OneShot = 0,
+    /// After the initial match, either party may push delta rounds
+    Subscription = 1,
+    /// Both parties learn only overlap ratios, not identities
+    Score = 2,
+    /// A third party (the connector) submits after Bob; Alice and Bob
+    /// learn only how many of the connector's contacts they both have
+    FriendsOfFriends = 3,
+    /// Both lists are follow targets (folded pubkeys); the parties learn
+    /// only whether each follows the other
+    MutualFollow = 4,
+    /// Bob commits to a large set by Merkle root and matches it against
+    /// Alice's list one chunk at a time; only Alice learns the matches
+    CommittedSet = 5,
+}
+
+impl SessionMode {
+    /// Name of the mode stored as `mode`, used in `SessionReport`.
+    pub fn name(mode: u8) -> &'static str {
+        match mode {
+            0 => "one_shot",
+            1 => "subscription",
+            2 => "score",
+            3 => "friends_of_friends",
+            4 => "mutual_follow",
+            5 => "committed_set",
+            _ => "unknown",
+        }
+    }
+}
+
+/// Identifier type a session's hashes are derived from. Clients mix it
+/// into the hash domain, so the same string in two namespaces never
+/// hashes alike, and the circuits only compare lists tagged with the
+/// session's namespace.
+#[repr(u8)]
+pub enum Namespace {
+    Phone = 0,
+    Email = 1,
+    Username = 2,
+    Wallet = 3,
+    /// Solana name-service names, hashed after `.sol` normalization
+    SolName = 4,
+}
+
+/// Whether a session's match count is published in plaintext. Both
+/// parties must opt in: Alice at creation, Bob when he submits.
+#[repr(u8)]
+pub enum PublicCount {
+    /// The count stays encrypted
+    Private = 0,
+    /// Alice opted in; Bob has not (yet)
+    AliceOptedIn = 1,
+    /// Both opted in; `match_count` holds the count once matched
+    BothOptedIn = 2,
+}
+
+/// How clients derive identifier hashes. Ciphertexts do not reveal the
+/// scheme, so the program checks the one each submission declares.
+#[repr(u8)]
+pub enum HashScheme {
+    /// Upper 128 bits of SHA-256
+    Sha256 = 0,
+    /// Upper 128 bits of BLAKE3
+    Blake3 = 1,
+}
+
+/// Zero-copy: instructions borrow the account data in place instead of
+/// deserializing it. Fields are laid out so the `repr(C)` struct has no
+/// implicit padding; offsets below exclude the 8-byte discriminator.
+/// Layout history: v0 ended at `public_count` (152 bytes), v1 added
+/// `version` and the reserve (216), v2 the stage accounting (280), v3
+/// `join_deadline`, v4 `purged_slot`, v5 `callback_consumed` and v6
+/// `rent_payer` out of the reserve (280), v7 `notification_target` by
+/// growing the account (312), v8 `app_id` likewise (344), v9
+/// `stage_fees` and `refundable_fee` after the reserve, where a `u64`
+/// needs no padding (384), v10 `memo` (416), v11 the committed-set
+/// chunk counters out of the reserve and `set_root` and
+/// `set_chunks_done` after `memo` (456), v12 `hide_counts` out of the
+/// reserve (456), v13 `reveal_after_slot` after `set_chunks_done` (464),
+/// v14 `capacity`, v15 the stats bumps and v16 the reveal windows out of
+/// the reserve (464), v17 the result receipts after `reveal_after_slot`
+/// (528), v18 `app_hook`, v19 `connection_consent` and v20
+/// `state_version` out of the reserve (528), v21 `last_ping_slot` after
+/// the receipts (536), v22 `intended_bob` after it (568), v23
+/// `invite_hash` after that (600).
+#[account(zero_copy)]
+#[derive(Default)]
+pub struct DiscoverySession {
+    /// Unique session identifier (0)
+    pub session_id: [u8; 32],
+    /// First party, creates the session (32)
+    pub alice: Pubkey,
+    /// Second party, joins the session (64)
+    pub bob: Pubkey,
+    /// Alice's `contacts_commitment`, all zeros if she did not commit (96)
+    pub commitment: [u8; 32],
+    /// Number of delta rounds pushed so far (128)
+    pub round: u32,
+    /// Plaintext match count, set only when both parties opted in.
+    /// Reflects the initial match; subscription deltas do not update it. (132)
+    pub match_count: u32,
+    /// Current session status, see `Status` (136)
+    pub status: u8,
+    /// PDA bump seed (137)
+    pub bump: u8,
+    /// Session mode, see `SessionMode` (138)
+    pub mode: u8,
+    /// Identifier namespace, see `Namespace` (139)
+    pub namespace: u8,
+    /// Identifier hash scheme, see `HashScheme` (140)
+    pub hash_scheme: u8,
+    /// Party whose last submission the MPC rejected as malformed
+    /// (1=Alice, 2=Bob, 3=connector), 0 once a submission is accepted (141)
+    pub rejected_party: u8,
+    /// Match count publication opt-ins, see `PublicCount` (142)
+    pub public_count: u8,
+    /// Layout version, see `SESSION_VERSION`; 0 on sessions created
+    /// before versioning (143)
+    pub version: u8,
+    /// Computation offset last queued for each `ComputationStage`,
+    /// 0 if never queued (144)
+    pub stage_offsets: [u64; 4],
+    /// Slot at which each stage's last callback ran, 0 while its
+    /// computation is outstanding or was never queued (176)
+    pub finalized_slots: [u64; 4],
+    /// Unix timestamp after which Bob can no longer join, 0 for none (208)
+    pub join_deadline: i64,
+    /// Slot at which the purge callback zeroed the MXE state, 0 while
+    /// the purge is outstanding or was never queued (216)
+    pub purged_slot: u64,
+    /// Bit per `ComputationStage`, set once the stage's callback has been
+    /// handled and cleared when the stage is queued again (224)
+    pub callback_consumed: u8,
+    /// Account that funded the session's creation, refunded on close;
+    /// the default key on sessions created before sponsorship (225)
+    pub rent_payer: Pubkey,
+    /// Opaque routing hash set by `register_notification`, all zeros if
+    /// none (257)
+    pub notification_target: [u8; 32],
+    /// Integrating application metered by the session's `AppStats`, all
+    /// zeros for none or on sessions created before version 8 (289)
+    pub app_id: [u8; 32],
+    /// Chunks Bob committed to in a `CommittedSet` session, 0 until
+    /// `commit_set` (321)
+    pub set_chunk_count: u8,
+    /// Chunk whose match is queued or was last queued (322)
+    pub set_pending_chunk: u8,
+    /// 1 if neither party's list size may reach the other, set at
+    /// creation; 0 on sessions created before version 12 (323)
+    pub hide_counts: u8,
+    /// Contacts per list the session accepts, one of `CAPACITY_TIERS`;
+    /// 0 on sessions created before version 14, read as 32 (324)
+    pub capacity: u8,
+    /// Bump of the `GlobalStats` PDA, so queues address the callback's
+    /// stats accounts without a bump search; 0 on sessions created
+    /// before version 15 (325)
+    pub global_stats_bump: u8,
+    /// Bump of the session app's `AppStats` PDA, likewise (326)
+    pub app_stats_bump: u8,
+    /// Result window (page) the outstanding reveal was queued for (327)
+    pub reveal_window: u8,
+    /// Bit per result window a reveal has delivered to Alice since the
+    /// match; all of `ALL_WINDOWS` means her result is complete (328)
+    pub alice_windows: u8,
+    /// Likewise for Bob's reveals; the page `submit_and_match` returns
+    /// him is not counted (329)
+    pub bob_windows: u8,
+    /// 1 once Alice registered an `AppHook` for the match callback to
+    /// notify, else 0 (330)
+    pub app_hook: u8,
+    /// `CONSENT_ALICE` and `CONSENT_BOB` bits of the parties who
+    /// consented to attesting their connection (331)
+    pub connection_consent: u8,
+    /// `SessionState` layout the session's MXE state is in, see
+    /// `STATE_VERSION`; 0 on sessions created before version 20, whose
+    /// state is in layout 1 (332)
+    pub state_version: u8,
+    /// Zeroed space for future fields, so adding one does not strand
+    /// existing sessions (333)
+    pub _reserved: [u8; 3],
+    /// Lamports billed for each `ComputationStage`'s last queue, 0 when
+    /// free or queued before version 9 (336)
+    pub stage_fees: [u64; 4],
+    /// Fee of the computation that failed the session, until
+    /// `claim_refund` pays it out (368)
+    pub refundable_fee: u64,
+    /// Hashed business reference set at creation and echoed in every
+    /// session event, all zeros for none or on sessions created before
+    /// version 10 (376)
+    pub memo: [u8; 32],
+    /// Merkle root over Bob's chunk commitments in a `CommittedSet`
+    /// session, see `set_root`; all zeros until `commit_set` (408)
+    pub set_root: [u8; 32],
+    /// Bit per chunk whose match callback accepted it (440)
+    pub set_chunks_done: u64,
+    /// Slot before which results stay escrowed, 0 for none or on
+    /// sessions created before version 13 (448)
+    pub reveal_after_slot: u64,
+    /// `result_receipt` of the last result delivered to Alice, all zeros
+    /// until her first reveal or on sessions created before version 17 (456)
+    pub alice_receipt: [u8; 32],
+    /// Likewise for Bob, set by the match and by his reveals (488)
+    pub bob_receipt: [u8; 32],
+    /// Slot of the last `ping_session`, 0 if never pinged (520)
+    pub last_ping_slot: u64,
+    /// The only wallet that may join, all zeros for anyone (528)
+    pub intended_bob: Pubkey,
+    /// `invite_hash` of the code that makes its redeemer
+    /// `intended_bob`, `NO_INVITE` once redeemed or if none (560)
+    pub invite_hash: [u8; 32],
+}
+
+/// Current `DiscoverySession` layout. Bump it whenever a field is added,
+/// by carving it from `_reserved` or by growing `SIZE`; `realloc_session`
+/// brings older sessions up to it.
+pub const SESSION_VERSION: u8 = 23;
+
+/// Current layout of the circuits' `SessionState`. Bump it whenever that
+/// struct changes, and give `migrate_session_state` the old layout as
+/// `LegacySessionState`; sessions whose state is older must migrate it
+/// before anything else is computed on it.
+pub const STATE_VERSION: u8 = 1;
+
+impl DiscoverySession {
+    // 8 (discriminator) + 333 + 3 (reserved) + 256 = 600 bytes
+    pub const SIZE: usize = 8 + std::mem::size_of::<DiscoverySession>();
+
+    /// Account `close_session` refunds: the creation's payer, or Alice on
+    /// sessions created before sponsorship.
+    pub fn rent_recipient(&self) -> Pubkey {
+        if self.rent_payer == Pubkey::default() {
+            self.alice
+        } else {
+            self.rent_payer
+        }
+    }
+
+    /// Addresses of the `GlobalStats` and `AppStats` PDAs the session's
+    /// callbacks meter against. `find_program_address` costs a hash and a
+    /// curve check per bump tried, so the bumps stored at creation are
+    /// used when set; older sessions search for them.
+    pub fn stats_pdas(&self) -> (Pubkey, Pubkey) {
+        let global = match self.global_stats_bump {
+            0 => None,
+            bump => Pubkey::create_program_address(&[b"global_stats", &[bump]], &ID).ok(),
+        };
+        let app = match self.app_stats_bump {
+            0 => None,
+            bump => Pubkey::create_program_address(&[b"app_stats", &self.app_id, &[bump]], &ID).ok(),
+        };
+        (
+            global.unwrap_or_else(global_stats_pda),
+            app.unwrap_or_else(|| app_stats_pda(&self.app_id)),
+        )
+    }
+
+    /// Decode a session from raw account data, discriminator included.
+    /// Zero-copy accounts have no `AccountDeserialize`; this is the
+    /// off-chain counterpart of `AccountLoader::load`.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self> {
+        let body = data
+            .strip_prefix(Self::DISCRIMINATOR)
+            .ok_or(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch)?;
+        let body = body
+            .get(..Self::SIZE - 8)
+            .ok_or(anchor_lang::error::ErrorCode::AccountDidNotDeserialize)?;
+        Ok(bytemuck::pod_read_unaligned(body))
+    }
+}
+
+const _: () = assert!(DiscoverySession::SIZE == 600);
+
+/// What `create_session` returns.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SessionQueued {
+    /// The session PDA
+    pub session: Pubkey,
+    /// Offset the `init_session` computation was queued at
+    pub computation_offset: u64,
+}
+
+/// Most accounts an app hook can be passed, besides its `hook_authority`.
+pub const MAX_HOOK_ACCOUNTS: usize = 4;
+
+/// Preimage of the instruction discriminator an app hook is called
+/// with, so an Anchor program receives the call as its
+/// `on_discovery_match` instruction.
+pub const APP_HOOK_INSTRUCTION: &[u8] = b"global:on_discovery_match";
+
+/// An account `register_app_hook` passes to the hook program.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HookAccount {
+    pub pubkey: Pubkey,
+    pub is_writable: bool,
+}
+
+/// Arguments of the call an app hook receives when its session's match
+/// lands. The first account signs it: the `hook_authority` PDA with
+/// seeds `["hook_authority", session, hook program]`. A hook checks that
+/// account is a signer and equals the PDA derived from `session` and
+/// its own program id; a notice signed for another session or another
+/// hook program, which a hook could pass along, then fails the check.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AppHookNotice {
+    pub session: Pubkey,
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub alice: Pubkey,
+    pub bob: Pubkey,
+}
+
+/// Alices a `ConsentRecord` lets name its wallet as `intended_bob`.
+pub const MAX_CONSENT_COUNTERPARTIES: usize = 8;
+
+/// Apps whose sessions a `ConsentRecord` lets name its wallet.
+pub const MAX_CONSENT_APPS: usize = 4;
+
+/// Entries an `ActivityLog` keeps; each new one past that overwrites
+/// the oldest.
+pub const ACTIVITY_LOG_LEN: usize = 16;
+
+/// What an `ActivityLog` entry records.
+#[repr(u8)]
+pub enum ActivityKind {
+    /// The wallet created the session
+    Created = 0,
+    /// The wallet submitted its list
+    Submitted = 1,
+    /// The session matched
+    Matched = 2,
+    /// The MPC rejected the wallet's list
+    Rejected = 3,
+    /// The session's computation failed
+    Failed = 4,
+    /// The wallet closed the session
+    Closed = 5,
+}
+
+/// One computation `queue_batch` queues: the offset to queue it at and
+/// the stage, with the arguments its own instruction takes.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatchItem {
+    pub computation_offset: u64,
+    pub stage: BatchStage,
+}
+
+/// The computations `queue_batch` can queue, each named after the
+/// instruction that queues it alone.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchStage {
+    /// `reveal_alice_matches`
+    RevealAlice { pubkey: [u8; 32], nonce: u128, page: u8 },
+    /// `reveal_bob_matches`
+    RevealBob { pubkey: [u8; 32], nonce: u128, page: u8 },
+    /// `reveal_alice_score`
+    RevealAliceScore { pubkey: [u8; 32], nonce: u128 },
+    /// `reveal_second_degree`
+    RevealSecondDegree { pubkey: [u8; 32], nonce: u128 },
+    /// `purge_session_state`
+    Purge,
+}
+
+/// What `view_session` returns. Solana caps return data at 1024 bytes;
+/// with every circuit listed as initialized this takes about 700.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SessionReport {
+    pub session_id: [u8; 32],
+    /// `Status::name`
+    pub status: String,
+    /// `SessionMode::name`
+    pub mode: String,
+    /// Contacts per list, see `DiscoverySession::capacity`
+    pub capacity: u8,
+    pub alice: Pubkey,
+    /// The default key until Bob joins
+    pub bob: Pubkey,
+    pub app_id: [u8; 32],
+    /// Unix timestamp, 0 for none
+    pub join_deadline: i64,
+    /// Whether Bob can no longer join
+    pub join_expired: bool,
+    /// Slot before which results stay escrowed, 0 for none
+    pub reveal_after_slot: u64,
+    /// Whether that slot is still ahead
+    pub reveal_locked: bool,
+    /// `ComputationStage::name` of the computation queued but not yet
+    /// finalized, if any
+    pub pending_stage: Option<String>,
+    /// Offset that computation was queued at
+    pub pending_offset: Option<u64>,
+    /// Layout version, see `SESSION_VERSION`
+    pub version: u8,
+    /// Lamports `claim_refund` would pay out of a failed computation
+    pub refundable_fee: u64,
+    /// Hashed business reference, all zeros for none
+    pub memo: [u8; 32],
+    /// Circuits whose computation definitions are finalized, among those
+    /// passed
+    pub initialized_comp_defs: Vec<String>,
+}
+
+/// Lifecycle of a `DiscoveryPool`, also used by blocklists and card
+/// registries.
+#[repr(u8)]
+pub enum PoolStatus {
+    /// Waiting for the MPC to store the host's list
+    Initializing = 0,
+    /// Accepting matches
+    Open = 1,
+}
+
+/// A host's list, stored once by the MPC and matched against any
+/// user's list with `match_against_pool`. Uses `PoolStatus` for its
+/// lifecycle.
+#[account]
+pub struct DiscoveryPool {
+    /// Unique pool identifier
+    pub pool_id: [u8; 32],
+    /// Host who published the list
+    pub host: Pubkey,
+    /// Current pool status
+    pub status: u8,
+    /// Identifier namespace (see `Namespace`)
+    pub namespace: u8,
+    /// Identifier hash scheme (see `HashScheme`)
+    pub hash_scheme: u8,
+    /// Host's ContactList encrypted to the MXE (32 hashes)
+    pub encrypted_hashes: [[u8; 32]; 32],
+    /// Host's contact count encrypted to the MXE
+    pub encrypted_count: [u8; 32],
+    /// Host's list namespace encrypted to the MXE
+    pub encrypted_namespace: [u8; 32],
+    /// Nonce of the MXE encryption
+    pub nonce: u128,
+    /// Number of match_against_pool runs queued
+    pub match_runs: u64,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl DiscoveryPool {
+    // 8 (discriminator) + 32 + 32 + 1 + 1 + 1 + 32*32 + 32 + 32 + 16 + 8 + 1 = 1188 bytes
+    pub const SIZE: usize = 8 + 32 + 32 + 1 + 1 + 1 + 32 * 32 + 32 + 32 + 16 + 8 + 1;
+
+    /// Byte offset of `encrypted_hashes` (followed by `encrypted_count`
+    /// and `encrypted_namespace`)
+    pub const ENCRYPTED_LIST_OFFSET: u32 = 8 + 32 + 32 + 1 + 1 + 1;
+    /// 34 ciphertexts: 32 hashes + count + namespace
+    pub const ENCRYPTED_LIST_LEN: u32 = 34 * 32;
+}
+
+/// A moderator's blocklist, screened with `check_against_blocklist`.
+/// Uses `PoolStatus` for its lifecycle.
+#[account]
+pub struct DiscoveryBlocklist {
+    /// Unique blocklist identifier
+    pub blocklist_id: [u8; 32],
+    /// Moderator who published the list
+    pub moderator: Pubkey,
+    /// Current status (see `PoolStatus`)
+    pub status: u8,
+    /// Identifier namespace (see `Namespace`)
+    pub namespace: u8,
+    /// Identifier hash scheme (see `HashScheme`)
+    pub hash_scheme: u8,
+    /// Moderator's ContactList encrypted to the MXE (32 hashes)
+    pub encrypted_hashes: [[u8; 32]; 32],
+    /// Moderator's list size encrypted to the MXE
+    pub encrypted_count: [u8; 32],
+    /// Moderator's list namespace encrypted to the MXE
+    pub encrypted_namespace: [u8; 32],
+    /// Nonce of the MXE encryption
+    pub nonce: u128,
+    /// Number of check_against_blocklist runs queued
+    pub checks: u64,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl DiscoveryBlocklist {
+    // Same layout as DiscoveryPool: 1188 bytes
+    pub const SIZE: usize = 8 + 32 + 32 + 1 + 1 + 1 + 32 * 32 + 32 + 32 + 16 + 8 + 1;
+
+    /// Byte offset of `encrypted_hashes` (followed by `encrypted_count`
+    /// and `encrypted_namespace`)
+    pub const ENCRYPTED_LIST_OFFSET: u32 = 8 + 32 + 32 + 1 + 1 + 1;
+    /// 34 ciphertexts: 32 hashes + count + namespace
+    pub const ENCRYPTED_LIST_LEN: u32 = 34 * 32;
+}
+
+/// Lifecycle of a `StagedList`.
+#[repr(u8)]
+pub enum StagedListStatus {
+    /// Waiting for `instant_match`
+    Staged = 0,
+    /// Queued in an instant match; the callback closes the list
+    Matching = 1,
+}
+
+/// One party's list, encrypted to its own key, waiting to be matched
+/// against `peer`'s with `instant_match`. Nothing is stored after the
+/// match: the callback closes both lists.
+#[account]
+pub struct StagedList {
+    /// Party whose list this is
+    pub owner: Pubkey,
+    /// Party the list may be matched against
+    pub peer: Pubkey,
+    /// Refunded the rent when the list is closed
+    pub rent_payer: Pubkey,
+    /// Current status (see `StagedListStatus`)
+    pub status: u8,
+    /// Identifier hash scheme (see `HashScheme`)
+    pub hash_scheme: u8,
+    /// PDA bump seed
+    pub bump: u8,
+    /// Owner's x25519 key the list is encrypted to
+    pub pubkey: [u8; 32],
+    /// Owner's ContactList encrypted to the owner's key (32 hashes)
+    pub encrypted_hashes: [[u8; 32]; 32],
+    /// Owner's contact count encrypted to the owner's key
+    pub encrypted_count: [u8; 32],
+    /// Owner's list namespace encrypted to the owner's key
+    pub encrypted_namespace: [u8; 32],
+    /// Nonce of the encryption
+    pub nonce: u128,
+}
+
+impl StagedList {
+    // 8 (discriminator) + 32 + 32 + 32 + 1 + 1 + 1 + 32 + 32*32 + 32 + 32 + 16 = 1243 bytes
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + 1 + 1 + 1 + 32 + 32 * 32 + 32 + 32 + 16;
+
+    /// Byte offset of `encrypted_hashes` (followed by `encrypted_count`
+    /// and `encrypted_namespace`)
+    pub const ENCRYPTED_LIST_OFFSET: u32 = 8 + 32 + 32 + 32 + 1 + 1 + 1 + 32;
+    /// 34 ciphertexts: 32 hashes + count + namespace
+    pub const ENCRYPTED_LIST_LEN: u32 = 34 * 32;
+}
+
+/// Lifecycle of a `DiscoveryDatabase`.
+#[repr(u8)]
+pub enum DatabaseStatus {
+    /// The provider is loading chunks
+    Loading = 0,
+    /// A chunk load is queued; nothing else is accepted until it lands
+    ChunkPending = 1,
+    /// Live: accepting queries, no more chunks
+    Open = 2,
+    /// A sealed later epoch, waiting for `rotate_database_epoch`
+    Sealed = 3,
+    /// Replaced by the next epoch; refuses new queries
+    Retired = 4,
+}
+
+/// A provider's database for unbalanced PSI: up to `DATABASE_CHUNKS`
+/// MXE-encrypted `ContactList`s, each loaded by its own
+/// `load_database_chunk` computation under its own nonce and read in
+/// place by every `query_psi`. Each epoch of a database is its own
+/// account, so rotating to a new version never rewrites chunks a queued
+/// query still reads. Zero-copy: at almost 9 KB it is too large to
+/// deserialize on the stack. Offsets exclude the discriminator.
+#[account(zero_copy)]
+pub struct DiscoveryDatabase {
+    /// Unique database identifier (0)
+    pub database_id: [u8; 32],
+    /// Wallet that created the database and loads its chunks (32)
+    pub provider: Pubkey,
+    /// One `ContactList` encrypted to the MXE per slot (32 hashes, count,
+    /// namespace), loaded slots first (64)
+    pub encrypted_chunks: [[u8; 32]; 272],
+    /// Little-endian nonce of each slot's MXE encryption (8768)
+    pub nonces: [[u8; 16]; 8],
+    /// Number of query_psi runs queued (8896)
+    pub queries: u64,
+    /// Version of the database, 0 for the first (8904)
+    pub epoch: u32,
+    /// Current status, see `DatabaseStatus` (8908)
+    pub status: u8,
+    /// Identifier namespace, see `Namespace` (8909)
+    pub namespace: u8,
+    /// Identifier hash scheme, see `HashScheme` (8910)
+    pub hash_scheme: u8,
+    /// Slots loaded so far (8911)
+    pub chunk_count: u8,
+    /// PDA bump seed (8912)
+    pub bump: u8,
+    /// Pads the struct to a multiple of 8 bytes (8913)
+    pub _padding: [u8; 7],
+}
+
+impl DiscoveryDatabase {
+    // 8 (discriminator) + 64 + 272*32 + 8*16 + 8 + 4 + 5 + 7 = 8928 bytes
+    pub const SIZE: usize = 8 + std::mem::size_of::<DiscoveryDatabase>();
+
+    /// Byte offset of the first chunk slot
+    pub const ENCRYPTED_CHUNKS_OFFSET: u32 = 8 + 32 + 32;
+    /// 34 ciphertexts per slot: 32 hashes + count + namespace
+    pub const ENCRYPTED_CHUNK_LEN: u32 = 34 * 32;
+
+    /// Decode a database from raw account data, discriminator included,
+    /// as `DiscoverySession::try_from_account_data`.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self> {
+        let body = data
+            .strip_prefix(Self::DISCRIMINATOR)
+            .ok_or(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch)?;
+        let body = body
+            .get(..Self::SIZE - 8)
+            .ok_or(anchor_lang::error::ErrorCode::AccountDidNotDeserialize)?;
+        Ok(bytemuck::pod_read_unaligned(body))
+    }
+}
+
+const _: () = assert!(DiscoveryDatabase::SIZE == 8928);
+
+/// A registry of contact cards, published with `publish_contact_card`
+/// and looked up with `match_contact_cards`. Uses `PoolStatus` for its
+/// lifecycle, and drops back to `Initializing` while a publish is in
+/// flight.
+#[account]
+pub struct DiscoveryCardRegistry {
+    /// Unique registry identifier
+    pub registry_id: [u8; 32],
+    /// Wallet that created the registry
+    pub authority: Pubkey,
+    /// Current status (see `PoolStatus`)
+    pub status: u8,
+    /// Identifier namespace (see `Namespace`)
+    pub namespace: u8,
+    /// Identifier hash scheme (see `HashScheme`)
+    pub hash_scheme: u8,
+    /// CardRegistry encrypted to the MXE (16 hashes, publishers and key
+    /// halves each, then count and namespace)
+    pub encrypted_registry: [[u8; 32]; 66],
+    /// Nonce of the MXE encryption
+    pub nonce: u128,
+    /// Number of publish_contact_card runs queued
+    pub publishes: u64,
+    /// Number of match_contact_cards runs queued
+    pub lookups: u64,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl DiscoveryCardRegistry {
+    // 8 (discriminator) + 32 + 32 + 1 + 1 + 1 + 66*32 + 16 + 8 + 8 + 1 = 2220 bytes
+    pub const SIZE: usize = 8 + 32 + 32 + 1 + 1 + 1 + 66 * 32 + 16 + 8 + 8 + 1;
+
+    /// Byte offset of `encrypted_registry`
+    pub const ENCRYPTED_REGISTRY_OFFSET: u32 = 8 + 32 + 32 + 1 + 1 + 1;
+    /// 66 ciphertexts: 4 x 16 slots + count + namespace
+    pub const ENCRYPTED_REGISTRY_LEN: u32 = 66 * 32;
+}
+
+/// Usage counters kept by `GlobalStats` and each `AppStats`. Callbacks
+/// count only what they handle: a computation whose output fails
+/// verification reverts its callback and is not counted.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UsageCounters {
+    /// Sessions created
+    pub sessions_created: u64,
+    /// Match computations (initial, delta round, or pool) that finished
+    pub matches_completed: u64,
+    /// Submissions the MPC rejected as malformed
+    pub submissions_rejected: u64,
+    /// Computation callbacks handled, duplicates excluded
+    pub computations: u64,
+}
+
+impl UsageCounters {
+    pub const SIZE: usize = 4 * 8;
+
+    fn record(&mut self, usage: Usage) {
+        let counter = match usage {
+            Usage::Session => &mut self.sessions_created,
+            Usage::Match => &mut self.matches_completed,
+            Usage::Rejection => &mut self.submissions_rejected,
+            Usage::Computation => &mut self.computations,
+        };
+        *counter += 1;
+    }
+}
+
+/// What an instruction or callback adds to `UsageCounters`.
+#[derive(Clone, Copy)]
+enum Usage {
+    Session,
+    Match,
+    Rejection,
+    Computation,
+}
+
+/// Program-wide counters, one PDA per deployment, so dashboards can be
+/// sourced from on-chain data alone. Pools, blocklists, and instant
+/// matches count here only; sessions also count against their `AppStats`.
+#[account]
+pub struct GlobalStats {
+    /// Counters since creation
+    pub usage: UsageCounters,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl GlobalStats {
+    // 8 (discriminator) + 32 (usage) + 1 = 41 bytes
+    pub const SIZE: usize = 8 + UsageCounters::SIZE + 1;
+}
+
+/// `app_id` of sessions created without an integrating application.
+pub const NO_APP_ID: [u8; 32] = [0u8; 32];
+
+/// `memo` of sessions created without a business reference.
+pub const NO_MEMO: [u8; 32] = [0u8; 32];
+
+/// `invite_hash` of sessions without a pending invite.
+pub const NO_INVITE: [u8; 32] = [0u8; 32];
+
+/// Prefix of every invite hash, so an invite code's hash can never be
+/// mistaken for any other hash the program checks.
+pub const INVITE_DOMAIN: &[u8] = b"pcd-invite-v1";
+
+/// Shortest invite code `redeem_invite` accepts, in bytes: 128 bits as
+/// hex. The hash is public and can be guessed against offline, so a
+/// short code would give the invite to whoever searches fastest.
+pub const MIN_INVITE_CODE_LEN: usize = 32;
+
+/// Prefix of every invite claim, keeping claims apart from invite hashes.
+pub const INVITE_CLAIM_DOMAIN: &[u8] = b"pcd-invite-claim-v1";
+
+/// Bob's commitment to a session's invite code, opened with
+/// `claim_invite` and closed when `redeem_invite` reveals the code.
+/// PDA of the session and Bob.
+#[account]
+pub struct InviteClaim {
+    /// The `DiscoverySession` whose invite is claimed
+    pub session: Pubkey,
+    pub bob: Pubkey,
+    /// `invite_claim` over the session id, the code and `bob`
+    pub commitment: [u8; 32],
+    /// Slot of the claim; the code is only accepted in a later one
+    pub slot: u64,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl InviteClaim {
+    // 8 (discriminator) + 32 + 32 + 32 + 8 + 1 = 113 bytes
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + 8 + 1;
+}
+
+const _: () = assert!(InviteClaim::SIZE == 113);
+
+/// One integrating application's session usage, keyed by the `app_id`
+/// its sessions were created with, so the app can meter itself for
+/// billing and quotas. Sessions without an app (all-zero id, and every
+/// session created before app ids) share the all-zero bucket.
+#[account]
+pub struct AppStats {
+    /// Application identifier, as passed to `create_session`
+    pub app_id: [u8; 32],
+    /// Counters since creation
+    pub usage: UsageCounters,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl AppStats {
+    // 8 (discriminator) + 32 + 32 (usage) + 1 = 73 bytes
+    pub const SIZE: usize = 8 + 32 + UsageCounters::SIZE + 1;
+}
+
+/// Deployment-wide settings, created by `init_config`.
+#[account]
+pub struct ProtocolConfig {
+    /// The program's upgrade authority at `init_config`
+    pub admin: Pubkey,
+    /// Lamports each queued session computation debits from its app's
+    /// `CreditAccount`; 0 for free
+    pub computation_price: u64,
+    /// Share of each computation fee, in basis points, sent on to the
+    /// Arcium fee pool; the rest stays in the `Treasury`
+    pub fee_pool_share_bps: u16,
+    /// Share of a failed computation's fee, in basis points, that
+    /// `claim_refund` returns to the app's credit
+    pub refund_bps: u16,
+    /// Oldest `PROGRAM_VERSION` `create_session` accepts from a client;
+    /// 0 accepts any
+    pub min_client_version: u16,
+    /// Most sessions the same two wallets may join within
+    /// `PAIR_WINDOW_SECS`, counted in their `PairQuota`; 0 for no limit
+    pub max_pair_sessions: u16,
+    /// Most contact slots a wallet may submit, as Bob, against one Alice
+    /// until she renews it, counted in their `PairQuota`; 0 for no limit
+    pub query_budget: u32,
+    /// Attestation provider both parties need before Bob is recorded (a
+    /// `gatekeeper::Gatekeeper`); 0 for none
+    pub gatekeeper: u8,
+    /// Network, within the gatekeeper, whose attestations are accepted
+    pub gatekeeper_network: Pubkey,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl ProtocolConfig {
+    // 8 (discriminator) + 32 + 8 + 2 + 2 + 2 + 2 + 4 + 1 + 32 + 1 = 94 bytes
+    pub const SIZE: usize = 8 + 32 + 8 + 2 + 2 + 2 + 2 + 4 + 1 + 32 + 1;
+
+    /// The part of a `fee` that tops up the Arcium fee pool.
+    pub fn fee_pool_share(&self, fee: u64) -> u64 {
+        bps_of(fee, self.fee_pool_share_bps)
+    }
+
+    /// The part of a failed computation's `fee` that is refunded.
+    pub fn refund_share(&self, fee: u64) -> u64 {
+        bps_of(fee, self.refund_bps)
+    }
+}
+
+fn bps_of(amount: u64, bps: u16) -> u64 {
+    (amount as u128 * bps as u128 / MAX_BPS as u128) as u64
+}
+
+/// Basis points in a whole.
+pub const MAX_BPS: u16 = 10_000;
+
+/// Holds the protocol's share of the computation fees debited from app
+/// credit.
+#[account]
+pub struct Treasury {
+    /// Lamports kept for the protocol since creation
+    pub collected: u64,
+    /// Lamports forwarded to the Arcium fee pool since creation
+    pub fee_pool_topped_up: u64,
+    /// Lamports refunded for failed computations since creation
+    pub refunded: u64,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl Treasury {
+    // 8 (discriminator) + 8 + 8 + 8 + 1 = 33 bytes
+    pub const SIZE: usize = 8 + 8 + 8 + 8 + 1;
+}
+
+/// An app's prepaid credit. The lamports sit on the account itself;
+/// `balance` is the part of them (above rent) that computations may
+/// spend.
+#[account]
+pub struct CreditAccount {
+    /// Application whose sessions are billed here
+    pub app_id: [u8; 32],
+    /// Key that opened the account and may withdraw from it
+    pub authority: Pubkey,
+    /// Spendable lamports
+    pub balance: u64,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl CreditAccount {
+    // 8 (discriminator) + 32 + 32 + 8 + 1 = 81 bytes
+    pub const SIZE: usize = 8 + 32 + 32 + 8 + 1;
+
+    /// Fail with `Unauthorized` unless the authority is among `signers`
+    /// and signed. An app is only billed for sessions and queries its
+    /// authority co-signed; the unattributed credit is open to anyone.
+    pub fn require_authority<'a, 'info: 'a>(
+        &self,
+        signers: impl IntoIterator<Item = &'a AccountInfo<'info>>,
+    ) -> Result<()> {
+        if self.app_id == NO_APP_ID {
+            return Ok(());
+        }
+        require!(
+            signers.into_iter().any(|info| info.is_signer && info.key() == self.authority),
+            ErrorCode::Unauthorized
+        );
+        Ok(())
+    }
+}
+
+/// An app's address lookup table. The PDA is the table's authority, so
+/// the table can only be extended through `extend_app_lut`.
+#[account]
+pub struct AppLookupTable {
+    /// Application whose transactions use the table
+    pub app_id: [u8; 32],
+    /// Address of the lookup table
+    pub table: Pubkey,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl AppLookupTable {
+    // 8 (discriminator) + 32 + 32 + 1 = 73 bytes
+    pub const SIZE: usize = 8 + 32 + 32 + 1;
+}
+
+const _: () = assert!(AppLookupTable::SIZE == 73);
+
+/// Program a session's match callback notifies, registered by Alice
+/// with `register_app_hook`. PDA of the session.
+#[account]
+pub struct AppHook {
+    /// The `DiscoverySession` whose match triggers the hook
+    pub session: Pubkey,
+    /// Program the callback calls
+    pub program: Pubkey,
+    /// Accounts passed after the `hook_authority`, the first
+    /// `account_count` of them
+    pub accounts: [Pubkey; MAX_HOOK_ACCOUNTS],
+    /// Bit per account passed writable
+    pub writable: u8,
+    pub account_count: u8,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl AppHook {
+    // 8 (discriminator) + 32 + 32 + 4 * 32 + 1 + 1 + 1 = 203 bytes
+    pub const SIZE: usize = 8 + 32 + 32 + MAX_HOOK_ACCOUNTS * 32 + 1 + 1 + 1;
+}
+
+const _: () = assert!(AppHook::SIZE == 203);
+
+/// Soulbound record that two wallets matched and both consented to it,
+/// created by `attest_connection`. PDA of the pair, in ascending order.
+#[account]
+pub struct ConnectionAttestation {
+    /// The lower of the two wallets
+    pub party_a: Pubkey,
+    /// The higher of the two wallets
+    pub party_b: Pubkey,
+    /// Session the connection was attested from
+    pub session_id: [u8; 32],
+    /// Application that session was billed to, all zeros for none
+    pub app_id: [u8; 32],
+    /// Unix timestamp of the attestation
+    pub attested_at: i64,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl ConnectionAttestation {
+    // 8 (discriminator) + 32 + 32 + 32 + 32 + 8 + 1 = 145 bytes
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + 32 + 8 + 1;
+}
+
+const _: () = assert!(ConnectionAttestation::SIZE == 145);
+
+/// A wallet the config admin blocked with `block_wallet`: while this
+/// PDA of the wallet exists, every instruction creating a session or
+/// recording Bob refuses it (see `require_unblocked`).
+#[account]
+pub struct BlockedWallet {
+    pub wallet: Pubkey,
+    /// Unix timestamp of the block
+    pub blocked_at: i64,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl BlockedWallet {
+    // 8 (discriminator) + 32 + 8 + 1 = 49 bytes
+    pub const SIZE: usize = 8 + 32 + 8 + 1;
+}
+
+const _: () = assert!(BlockedWallet::SIZE == 49);
+
+/// Sessions two wallets joined in the current window, counted against
+/// `ProtocolConfig::max_pair_sessions`, and the slots each submitted
+/// against the other, counted against `ProtocolConfig::query_budget`.
+/// Opened with `open_pair_quota`; PDA of the pair, in ascending order.
+#[account]
+pub struct PairQuota {
+    /// The lower of the two wallets
+    pub party_a: Pubkey,
+    /// The higher of the two wallets
+    pub party_b: Pubkey,
+    /// Unix timestamp the current window started at
+    pub window_start: i64,
+    /// Sessions joined since `window_start`
+    pub sessions: u16,
+    /// Contact slots each wallet has submitted, as Bob, against the other
+    /// since the other last renewed its budget: `party_a`'s, then
+    /// `party_b`'s
+    pub queried_slots: [u32; 2],
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl PairQuota {
+    // 8 (discriminator) + 32 + 32 + 8 + 2 + 2 * 4 + 1 = 91 bytes
+    pub const SIZE: usize = 8 + 32 + 32 + 8 + 2 + 2 * 4 + 1;
+}
+
+const _: () = assert!(PairQuota::SIZE == 91);
+
+/// Who may create sessions naming a wallet as their `intended_bob`, set
+/// with `set_consent_record`: the first `counterparty_count` wallets of
+/// `counterparties`, as Alice, and sessions of the first `app_count`
+/// apps of `apps`. PDA of the wallet.
+#[account]
+pub struct ConsentRecord {
+    pub wallet: Pubkey,
+    pub counterparties: [Pubkey; MAX_CONSENT_COUNTERPARTIES],
+    pub counterparty_count: u8,
+    pub apps: [[u8; 32]; MAX_CONSENT_APPS],
+    pub app_count: u8,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl ConsentRecord {
+    // 8 (discriminator) + 32 + 8 * 32 + 1 + 4 * 32 + 1 + 1 = 427 bytes
+    pub const SIZE: usize = 8 + 32 + MAX_CONSENT_COUNTERPARTIES * 32 + 1 + MAX_CONSENT_APPS * 32 + 1 + 1;
+
+    /// Whether `alice`, or a session of the app `app_id`, may name the
+    /// wallet as its Bob. Callers vouch for `app_id`: it must be an app
+    /// whose credit authority signed for the session.
+    pub fn allows(&self, alice: &Pubkey, app_id: &[u8; 32]) -> bool {
+        self.counterparties[..self.counterparty_count as usize].contains(alice)
+            || (*app_id != NO_APP_ID && self.apps[..self.app_count as usize].contains(app_id))
+    }
+}
+
+const _: () = assert!(ConsentRecord::SIZE == 427);
+
+/// A wallet's recent session activity, opened with `open_activity_log`.
+/// Entry `i` is `session_ids[i]`, `kinds[i]` (an `ActivityKind`), and
+/// `slots[i]`; the newest is just before `head`, and only the last
+/// `len` are set. PDA of the wallet.
+#[account]
+pub struct ActivityLog {
+    pub wallet: Pubkey,
+    pub session_ids: [[u8; 32]; ACTIVITY_LOG_LEN],
+    pub slots: [u64; ACTIVITY_LOG_LEN],
+    pub kinds: [u8; ACTIVITY_LOG_LEN],
+    /// Index the next entry is written at
+    pub head: u8,
+    /// Entries set, at most `ACTIVITY_LOG_LEN`
+    pub len: u8,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl ActivityLog {
+    // 8 (discriminator) + 32 + 16 * 32 + 16 * 8 + 16 + 1 + 1 + 1 = 699 bytes
+    pub const SIZE: usize = 8 + 32 + ACTIVITY_LOG_LEN * (32 + 8 + 1) + 1 + 1 + 1;
+
+    /// Append an entry, overwriting the oldest once the log is full.
+    pub fn push(&mut self, session_id: [u8; 32], kind: ActivityKind, slot: u64) {
+        let i = self.head as usize;
+        self.session_ids[i] = session_id;
+        self.slots[i] = slot;
+        self.kinds[i] = kind as u8;
+        self.head = ((i + 1) % ACTIVITY_LOG_LEN) as u8;
+        self.len = (self.len + 1).min(ACTIVITY_LOG_LEN as u8);
+    }
+
+    /// The entries newest first, as `(session_id, kind, slot)`.
+    pub fn entries(&self) -> impl Iterator<Item = ([u8; 32], u8, u64)> + '_ {
+        (1..=self.len as usize).map(move |back| {
+            let i = (self.head as usize + ACTIVITY_LOG_LEN - back) % ACTIVITY_LOG_LEN;
+            (self.session_ids[i], self.kinds[i], self.slots[i])
+        })
+    }
+}
+
+/// What a client checks before encrypting for this deployment, written
+/// by `sync_deployment`. Versions are those of the binary that last
+/// synced it, so a stale registry after an upgrade shows as a mismatch
+/// rather than passing. Singleton PDA.
+#[account]
+pub struct DeploymentRegistry {
+    /// `PROGRAM_VERSION`
+    pub program_version: u16,
+    /// `SESSION_VERSION`
+    pub session_version: u8,
+    /// `CIRCUIT_VERSIONS`, in `CIRCUITS` order
+    pub circuit_versions: [u16; 33],
+    /// Bit `i` set if `CIRCUITS[i]`'s computation definition is ready
+    pub initialized_comp_defs: u64,
+    /// The MXE account
+    pub mxe: Pubkey,
+    /// The MXE's cluster, `NO_CLUSTER` if none
+    pub cluster_offset: u32,
+    /// The MXE's x25519 key parties encrypt to, zero until it has one
+    pub mxe_x25519_pubkey: [u8; 32],
+    /// Slot of the last sync
+    pub updated_slot: u64,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl DeploymentRegistry {
+    // 8 (discriminator) + 2 + 1 + 33 * 2 + 8 + 32 + 4 + 32 + 8 + 1 = 162 bytes
+    pub const SIZE: usize = 8 + 2 + 1 + 33 * 2 + 8 + 32 + 4 + 32 + 8 + 1;
+
+    /// Whether `CIRCUITS[index]`'s computation definition was ready.
+    pub fn comp_def_ready(&self, index: usize) -> bool {
+        index < self.circuit_versions.len() && self.initialized_comp_defs & (1 << index) != 0
+    }
+}
+
+/// On-chain reference to a session's `Enc<Mxe, SessionState>`: the
+/// nonce and ciphertext hash of the state blob `init_session` returned.
+/// PDA of the session account, created with it; every instruction that
+/// queues a computation on the session's state must pass it, so a
+/// session can only be computed on once its state exists and only with
+/// its own. Sessions that predate handles get one from
+/// `realloc_session`, with an all-zero reference.
+#[account]
+pub struct SessionStateHandle {
+    /// The `DiscoverySession` whose state this is
+    pub session: Pubkey,
+    /// Nonce the MXE encrypted the state under
+    pub state_nonce: u128,
+    /// SHA-256 over the state's ciphertexts, in order
+    pub state_hash: [u8; 32],
+    /// State blobs recorded so far; 0 until the init callback (or, for a
+    /// stateless session, Alice's first submission callback) lands
+    pub generation: u32,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl SessionStateHandle {
+    // 8 (discriminator) + 32 + 16 + 32 + 4 + 1 = 93 bytes
+    pub const SIZE: usize = 8 + 32 + 16 + 32 + 4 + 1;
+
+    /// Point the handle at a state blob the MPC returned.
+    pub fn record(&mut self, nonce: u128, ciphertexts: &[[u8; 32]]) {
+        let parts: Vec<&[u8]> = ciphertexts.iter().map(|c| c.as_slice()).collect();
+        self.state_nonce = nonce;
+        self.state_hash = anchor_lang::solana_program::hash::hashv(&parts).to_bytes();
+        self.generation += 1;
+    }
+}
+
+/// An app's preset session configuration, created by its credit
+/// account's authority; `create_session_from_template` creates sessions
+/// from it. Sessions hold 32 contacts each, so there is no capacity to
+/// choose.
+#[account]
+pub struct SessionTemplate {
+    /// Application the template's sessions are metered and billed to
+    pub app_id: [u8; 32],
+    /// Template identifier, unique per app
+    pub template_id: [u8; 32],
+    /// Credit account authority that created it, the only key that can
+    /// close it
+    pub authority: Pubkey,
+    /// `SessionMode`
+    pub mode: u8,
+    /// `Namespace`
+    pub namespace: u8,
+    /// `HashScheme`
+    pub hash_scheme: u8,
+    /// Alice's opt-in to publishing the match count
+    pub public_count: bool,
+    /// Whether list sizes are hidden from the counterparty
+    pub hide_counts: bool,
+    /// Seconds Bob has to join after creation, 0 for no deadline
+    pub join_ttl: u32,
+    /// Slots after creation that the result stays escrowed, 0 for none
+    pub reveal_delay: u64,
+    /// Only payer allowed to fund sessions from the template, the
+    /// default key for any
+    pub sponsor: Pubkey,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl SessionTemplate {
+    // 8 (discriminator) + 32 + 32 + 32 + 5 + 4 + 8 + 32 + 1 = 154 bytes
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + 5 + 4 + 8 + 32 + 1;
+
+    /// Configuration of a session created from the template at unix
+    /// time `now` and slot `slot`.
+    pub fn config(&self, now: i64, slot: u64) -> SessionConfig {
+        SessionConfig {
+            mode: self.mode,
+            namespace: self.namespace,
+            hash_scheme: self.hash_scheme,
+            public_count: self.public_count,
+            hide_counts: self.hide_counts,
+            join_deadline: if self.join_ttl == 0 { 0 } else { now + self.join_ttl as i64 },
+            reveal_after_slot: if self.reveal_delay == 0 { 0 } else { slot + self.reveal_delay },
+            capacity: schema::MAX_CONTACTS as u8,
+        }
+    }
+}
+
+/// Fail to build unless `$layout` lists exactly the fields of the
+/// zero-copy `$account`, in order, at the offsets the compiler gives them.
+macro_rules! assert_zero_copy_layout {
+    ($layout:expr, $account:ty, [$($field:ident),* $(,)?]) => {
+        const _: () = {
+            let mut i = 0;
+            $(
+                assert!(
+                    $layout.fields[i].offset == layout::DISCRIMINATOR_LEN + core::mem::offset_of!($account, $field)
+                );
+                i += 1;
+            )*
+            assert!(i == $layout.fields.len());
+        };
+    };
+}
+
+assert_zero_copy_layout!(
+    layout::DISCOVERY_SESSION,
+    DiscoverySession,
+    [
+        session_id, alice, bob, commitment, round, match_count, status, bump, mode, namespace, hash_scheme,
+        rejected_party, public_count, version, stage_offsets, finalized_slots, join_deadline, purged_slot,
+        callback_consumed, rent_payer, notification_target, app_id, set_chunk_count, set_pending_chunk,
+        hide_counts, capacity, global_stats_bump, app_stats_bump, reveal_window, alice_windows, bob_windows,
+        app_hook, connection_consent, state_version, _reserved, stage_fees, refundable_fee, memo, set_root,
+        set_chunks_done, reveal_after_slot, alice_receipt, bob_receipt, last_ping_slot, intended_bob, invite_hash,
+    ]
+);
+
+assert_zero_copy_layout!(
+    layout::DISCOVERY_DATABASE,
+    DiscoveryDatabase,
+    [
+        database_id, provider, encrypted_chunks, nonces, queries, epoch, status, namespace, hash_scheme,
+        chunk_count, bump, _padding,
+    ]
+);
+
+// Borsh accounts are packed in field order, so matching sizes (with the
+// layout's own contiguity check) pin down their offsets.
+const _: () = {
+    assert!(layout::DISCOVERY_SESSION.size == DiscoverySession::SIZE);
+    assert!(layout::DISCOVERY_DATABASE.size == DiscoveryDatabase::SIZE);
+    assert!(layout::SESSION_STATE_HANDLE.size == SessionStateHandle::SIZE);
+    assert!(layout::SESSION_TEMPLATE.size == SessionTemplate::SIZE);
+    assert!(layout::DISCOVERY_POOL.size == DiscoveryPool::SIZE);
+    assert!(layout::DISCOVERY_BLOCKLIST.size == DiscoveryBlocklist::SIZE);
+    assert!(layout::STAGED_LIST.size == StagedList::SIZE);
+    assert!(layout::DISCOVERY_CARD_REGISTRY.size == DiscoveryCardRegistry::SIZE);
+    assert!(layout::GLOBAL_STATS.size == GlobalStats::SIZE);
+    assert!(layout::APP_STATS.size == AppStats::SIZE);
+    assert!(layout::PROTOCOL_CONFIG.size == ProtocolConfig::SIZE);
+    assert!(layout::TREASURY.size == Treasury::SIZE);
+    assert!(layout::CREDIT_ACCOUNT.size == CreditAccount::SIZE);
+    assert!(layout::ACTIVITY_LOG.size == ActivityLog::SIZE);
+    assert!(layout::DEPLOYMENT_REGISTRY.size == DeploymentRegistry::SIZE);
+    assert!(layout::INVITE_CLAIM.size == InviteClaim::SIZE);
+    assert!(layout::APP_LOOKUP_TABLE.size == AppLookupTable::SIZE);
+    assert!(layout::APP_HOOK.size == AppHook::SIZE);
+    assert!(layout::CONNECTION_ATTESTATION.size == ConnectionAttestation::SIZE);
+    assert!(layout::PAIR_QUOTA.size == PairQuota::SIZE);
+    assert!(layout::CONSENT_RECORD.size == ConsentRecord::SIZE);
+    assert!(layout::BLOCKED_WALLET.size == BlockedWallet::SIZE);
+};
+
+/// Options a session is created with, from `create_session`'s arguments
+/// or a `SessionTemplate`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SessionConfig {
+    /// A `SessionMode`
+    pub mode: u8,
+    /// A `Namespace`; the circuits reject lists hashed in any other
+    pub namespace: u8,
+    /// A `HashScheme` every submission must declare
+    pub hash_scheme: u8,
+    /// Alice's opt-in to publishing the match count, taking effect only
+    /// if Bob opts in too
+    pub public_count: bool,
+    /// Keep either list's size from the counterparty
+    pub hide_counts: bool,
+    /// Unix timestamp after which Bob can no longer join, 0 for none
+    pub join_deadline: i64,
+    /// Slot before which results stay escrowed, 0 for none
+    pub reveal_after_slot: u64,
+    /// One of `schema::CAPACITY_TIERS`: the most contacts either list
+    /// may hold
+    pub capacity: u8,
+}
+
+impl SessionConfig {
+    /// Fail unless the options are known values that combine: mutual
+    /// follow needs the wallet namespace, a public count needs a mode
+    /// with a match result, committed-set sessions cannot hide counts,
+    /// a time lock needs a one-shot or subscription session without
+    /// a public count, and a capacity below 32 needs a one-shot session,
+    /// the only mode with a match circuit per tier. The join deadline is
+    /// checked by the caller.
+    pub fn validate(&self) -> Result<()> {
+        let mode = self.mode;
+        let matching = mode == SessionMode::OneShot as u8 || mode == SessionMode::Subscription as u8;
+        require!(
+            mode <= SessionMode::CommittedSet as u8,
+            ErrorCode::InvalidSessionMode
+        );
+        require!(
+            self.namespace <= Namespace::SolName as u8,
+            ErrorCode::InvalidNamespace
+        );
+        require!(
+            mode != SessionMode::MutualFollow as u8 || self.namespace == Namespace::Wallet as u8,
+            ErrorCode::InvalidNamespace
+        );
+        require!(
+            self.hash_scheme <= HashScheme::Blake3 as u8,
+            ErrorCode::InvalidHashScheme
+        );
+        require!(!self.public_count || matching, ErrorCode::ModeMismatch);
+        require!(
+            !self.hide_counts || mode != SessionMode::CommittedSet as u8,
+            ErrorCode::ModeMismatch
+        );
+        require!(
+            self.reveal_after_slot == 0 || (matching && !self.public_count),
+            ErrorCode::ModeMismatch
+        );
+        require!(
+            schema::CAPACITY_TIERS.contains(&self.capacity),
+            ErrorCode::InvalidCapacity
+        );
+        require!(
+            self.capacity == schema::MAX_CONTACTS as u8 || mode == SessionMode::OneShot as u8,
+            ErrorCode::ModeMismatch
+        );
+        Ok(())
+    }
+
+    /// Write the options to a freshly created `session` and reset the
+    /// rest of its lifecycle fields.
+    pub fn apply(&self, session: &mut DiscoverySession) {
+        session.bob = Pubkey::default();
+        session.status = Status::AwaitingAlice.into();
+        session.mode = self.mode;
+        session.round = 0;
+        session.namespace = self.namespace;
+        session.hash_scheme = self.hash_scheme;
+        session.commitment = [0u8; 32];
+        session.rejected_party = 0;
+        session.public_count = if self.public_count {
+            PublicCount::AliceOptedIn as u8
+        } else {
+            PublicCount::Private as u8
+        };
+        session.match_count = 0;
+        session.hide_counts = self.hide_counts as u8;
+        session.version = SESSION_VERSION;
+        session.state_version = STATE_VERSION;
+        session.join_deadline = self.join_deadline;
+        session.reveal_after_slot = self.reveal_after_slot;
+        session.capacity = self.capacity;
+    }
+}
+
+/// Commitment to an encrypted ContactList: SHA-256 over the 32 hash
+/// ciphertexts, the count and namespace ciphertexts, the pubkey, and the
+/// nonce (little-endian), in that order.
+pub fn contacts_commitment(
+    encrypted_hashes: &[[u8; 32]; 32],
+    encrypted_count: &[u8; 32],
+    encrypted_namespace: &[u8; 32],
+    pubkey: &[u8; 32],
+    nonce: u128,
+) -> [u8; 32] {
+    let nonce = nonce.to_le_bytes();
+    let mut parts: Vec<&[u8]> = encrypted_hashes.iter().map(|h| h.as_slice()).collect();
+    parts.extend([
+        encrypted_count.as_slice(),
+        encrypted_namespace.as_slice(),
+        pubkey.as_slice(),
+        nonce.as_slice(),
+    ]);
+    anchor_lang::solana_program::hash::hashv(&parts).to_bytes()
+}
+
+/// Hash locking a session to its invite `code`: SHA-256 over
+/// `INVITE_DOMAIN`, the session id, and the code. The session id salts
+/// it, so the same code hashes differently in every session.
+pub fn invite_hash(session_id: &[u8; 32], code: &[u8]) -> [u8; 32] {
+    anchor_lang::solana_program::hash::hashv(&[INVITE_DOMAIN, session_id, code]).to_bytes()
+}
+
+/// Commitment of `bob` to a session's invite `code`: SHA-256 over
+/// `INVITE_CLAIM_DOMAIN`, the session id, the code, and his wallet.
+pub fn invite_claim(session_id: &[u8; 32], code: &[u8], bob: &Pubkey) -> [u8; 32] {
+    anchor_lang::solana_program::hash::hashv(&[INVITE_CLAIM_DOMAIN, session_id, code, bob.as_ref()]).to_bytes()
+}
+
+/// Receipt for a match result delivered to `party` (1=Alice, 2=Bob):
+/// SHA-256 over the session id, the party, the result window, the two
+/// packed slot ciphertexts, the count ciphertext, and the nonce, in that
+/// order. The session keeps each party's latest, so a result relayed
+/// off-chain can be checked against it before it is decrypted. Must
+/// match `result_receipt` in the client SDK.
+pub fn result_receipt(
+    session_id: &[u8; 32],
+    party: u8,
+    window: u8,
+    encrypted_matches: &[[u8; 32]; 2],
+    encrypted_count: &[u8; 32],
+    nonce: &[u8; 16],
+) -> [u8; 32] {
+    anchor_lang::solana_program::hash::hashv(&[
+        session_id,
+        &[party, window],
+        &encrypted_matches[0],
+        &encrypted_matches[1],
+        encrypted_count,
+        nonce,
+    ])
+    .to_bytes()
+}
+
+/// Fold a 32-byte pubkey into the u128 a follow list holds: the XOR of
+/// its two 16-byte halves, each read big-endian. Must match
+/// `fold_pubkey` in the client SDK.
+pub fn fold_pubkey(pubkey: &Pubkey) -> u128 {
+    let bytes = pubkey.to_bytes();
+    let mut high = [0u8; 16];
+    let mut low = [0u8; 16];
+    high.copy_from_slice(&bytes[..16]);
+    low.copy_from_slice(&bytes[16..]);
+    u128::from_be_bytes(high) ^ u128::from_be_bytes(low)
+}
+
+/// Record a rejected submission and tell both parties. The caller has
+/// already rolled the status back to the submitting party's stage.
+fn reject_submission(session: &mut DiscoverySession, party: u8) -> Result<()> {
+    session.rejected_party = party;
+    emit!(SubmissionRejected {
+        session_id: session.session_id,
+        memo: session.memo,
+        party,
+    });
+    Ok(())
+}
+
+/// Reopen Bob's slot after the MPC rejected his list, so he (or anyone
+/// else) can submit again.
+fn reopen_bob_slot(session: &mut DiscoverySession) -> Result<()> {
+    session.transition(Status::AwaitingBob)?;
+    session.bob = Pubkey::default();
+    reject_submission(session, 2)
+}
+
+/// Checks shared by the `submit_contacts_alice` instructions: fail
+/// unless the session awaits Alice's list from `alice`, was created with
+/// `capacity` if the instruction serves one tier only (`None` accepts
+/// any), and any earlier commitment matches `commitment`, which is only
+/// computed when there is one. Then move the session to `AwaitingBob`.
+fn begin_alice(
+    session: &mut DiscoverySession,
+    alice: Pubkey,
+    hash_scheme: u8,
+    capacity: Option<u8>,
+    commitment: impl FnOnce() -> [u8; 32],
+) -> Result<()> {
+    require!(hash_scheme == session.hash_scheme, ErrorCode::HashSchemeMismatch);
+    session.require_status(Status::AwaitingAlice)?;
+    require!(alice == session.alice, ErrorCode::Unauthorized);
+    require!(
+        !capacity.is_some_and(|capacity| session.capacity() != capacity),
+        ErrorCode::CapacityMismatch
+    );
+    require!(
+        session.commitment == [0u8; 32] || session.commitment == commitment(),
+        ErrorCode::CommitmentMismatch
+    );
+    session.transition(Status::AwaitingBob)
+}
+
+/// Apply a verified Alice submission output, of any capacity: reopen her
+/// slot if the list was rejected, otherwise emit her confirmation.
+fn finish_alice(
+    session: &mut DiscoverySession,
+    global_stats: &mut GlobalStats,
+    app_stats: &mut AppStats,
+    confirmation: [[u8; 32]; 2],
+    nonce: u128,
+    accepted: bool,
+) -> Result<()> {
+    if !session.consume_callback(ComputationStage::Alice, Clock::get()?.slot) {
+        return Ok(());
+    }
+    meter(global_stats, app_stats, Usage::Computation);
+    if !accepted {
+        // The commitment bound the rejected ciphertexts, so it goes too
+        session.transition(Status::AwaitingAlice)?;
+        session.commitment = [0u8; 32];
+        meter(global_stats, app_stats, Usage::Rejection);
+        return reject_submission(session, 1);
+    }
+    session.rejected_party = 0;
+
+    emit!(AliceSubmitted {
+        session_id: session.session_id,
+        memo: session.memo,
+        encrypted_confirmation: confirmation,
+        nonce: nonce.to_le_bytes(),
+    });
+
+    Ok(())
+}
+
+/// Record `bob` as the session's Bob and emit `BobJoined`, the signal
+/// for Alice's client to start watching for the result. Charges the
+/// session, and the `slots` Bob matches against Alice's list, to the
+/// pair's `PairQuota` among `accounts` (the instruction's remaining
+/// accounts) when the config limits either, and requires both parties'
+/// attestations among them when the config sets a gatekeeper. Fails
+/// while Bob or Alice is blocked, see `require_unblocked`, so a block
+/// also stops sessions created before it from being joined.
+fn record_bob(
+    session: &mut DiscoverySession,
+    bob: Pubkey,
+    slots: u32,
+    config: &ProtocolConfig,
+    accounts: &[AccountInfo],
+) -> Result<()> {
+    require_unblocked(accounts, &bob)?;
+    require_unblocked(accounts, &session.alice)?;
+    require!(session.invite_hash == NO_INVITE, ErrorCode::InviteNotRedeemed);
+    require!(
+        session.intended_bob == Pubkey::default() || session.intended_bob == bob,
+        ErrorCode::Unauthorized
+    );
+    gatekeeper::require_attested(config, accounts, &[session.alice, bob])?;
+    charge_pair_quota(accounts, session.alice, bob, slots, config)?;
+    session.bob = bob;
+    emit!(BobJoined {
+        session_id: session.session_id,
+        memo: session.memo,
+        bob,
+    });
+    Ok(())
+}
+
+/// Fail with `WalletBlocked` while the admin blocks `wallet`. Its
+/// `BlockedWallet` address must be among `accounts` (the instruction's
+/// remaining accounts) whether or not a block exists there, so leaving
+/// it out cannot skip the check (`BlockCheckMissing`).
+fn require_unblocked(accounts: &[AccountInfo], wallet: &Pubkey) -> Result<()> {
+    let key = Pubkey::find_program_address(&[b"blocked", wallet.as_ref()], &ID).0;
+    let info = accounts
+        .iter()
+        .find(|info| info.key() == key)
+        .ok_or(ErrorCode::BlockCheckMissing)?;
+    require!(*info.owner != ID || info.data_is_empty(), ErrorCode::WalletBlocked);
+    Ok(())
+}
+
+/// Fail with `DiscoveryNotConsented` unless `bob`'s `ConsentRecord`,
+/// found by address among `accounts`, lets `alice` or the app of `app`
+/// create sessions naming him. `app` is the credit account the session
+/// bills, whose authority has already signed
+/// (`CreditAccount::require_authority`), so only the app itself can
+/// use an app-scoped consent.
+fn require_discovery_consent(
+    accounts: &[AccountInfo],
+    bob: &Pubkey,
+    alice: &Pubkey,
+    app: &CreditAccount,
+) -> Result<()> {
+    require!(bob != alice, ErrorCode::DiscoveryNotConsented);
+    let key = Pubkey::find_program_address(&[b"consent", bob.as_ref()], &ID).0;
+    let info = accounts
+        .iter()
+        .find(|info| info.key() == key && *info.owner == ID)
+        .ok_or(ErrorCode::DiscoveryNotConsented)?;
+    let record = ConsentRecord::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    require!(record.allows(alice, &app.app_id), ErrorCode::DiscoveryNotConsented);
+    Ok(())
+}
+
+/// The two wallets of a pair in the order their PDAs' seeds take them:
+/// ascending.
+pub fn pair_parties(a: Pubkey, b: Pubkey) -> (Pubkey, Pubkey) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Charge a session in which `bob` submits `slots` contact slots
+/// against `alice` to their `PairQuota`, found by address among
+/// `accounts`. Fails with `PairRateLimited` once `max_pair_sessions`
+/// sessions fall in the current window, a new one starting
+/// `PAIR_WINDOW_SECS` after the last did, and with
+/// `QueryBudgetExhausted` once Bob's slots against Alice would pass
+/// `query_budget`. With neither limit set nothing is charged;
+/// otherwise a missing quota fails with `PairQuotaMissing`.
+fn charge_pair_quota(
+    accounts: &[AccountInfo],
+    alice: Pubkey,
+    bob: Pubkey,
+    slots: u32,
+    config: &ProtocolConfig,
+) -> Result<()> {
+    let (limit, budget) = (config.max_pair_sessions, config.query_budget);
+    if limit == 0 && budget == 0 {
+        return Ok(());
+    }
+    let (party_a, party_b) = pair_parties(alice, bob);
+    let key = Pubkey::find_program_address(&[b"pair", party_a.as_ref(), party_b.as_ref()], &ID).0;
+    let info = accounts
+        .iter()
+        .find(|info| info.key() == key && *info.owner == ID)
+        .ok_or(ErrorCode::PairQuotaMissing)?;
+    let mut quota = PairQuota::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+
+    let now = Clock::get()?.unix_timestamp;
+    if now >= quota.window_start.saturating_add(PAIR_WINDOW_SECS) {
+        quota.window_start = now;
+        quota.sessions = 0;
+    }
+    require!(limit == 0 || quota.sessions < limit, ErrorCode::PairRateLimited);
+    quota.sessions = quota.sessions.saturating_add(1);
+
+    // Bob is the querier, Alice the target
+    let querier = usize::from(bob == party_b);
+    let queried = quota.queried_slots[querier].saturating_add(slots);
+    require!(budget == 0 || queried <= budget, ErrorCode::QueryBudgetExhausted);
+    quota.queried_slots[querier] = queried;
+    trace!(
+        "pair_session pair={} sessions={} limit={} queried_slots={} budget={}",
+        key,
+        quota.sessions,
+        limit,
+        queried,
+        budget
+    );
+    quota.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+/// Checks and bookkeeping shared by the `submit_and_match` instructions,
+/// which differ only in the circuit they queue: fail unless the session
+/// awaits Bob, was created with `capacity`, and matches lists; then record
+/// Bob, counting the pair's session against `config`'s limit, and move
+/// the session to `Computing`. Returns the circuit's
+/// `publish` and `withhold` flags.
+fn begin_match(
+    session: &mut DiscoverySession,
+    bob: Pubkey,
+    hash_scheme: u8,
+    public_count: bool,
+    capacity: u8,
+    config: &ProtocolConfig,
+    accounts: &[AccountInfo],
+) -> Result<(bool, bool)> {
+    require!(hash_scheme == session.hash_scheme, ErrorCode::HashSchemeMismatch);
+    session.require_status(Status::AwaitingBob)?;
+    session.require_joinable(Clock::get()?.unix_timestamp)?;
+    require!(
+        session.mode == SessionMode::OneShot as u8 || session.mode == SessionMode::Subscription as u8,
+        ErrorCode::ModeMismatch
+    );
+    require!(session.capacity() == capacity, ErrorCode::CapacityMismatch);
+
+    // Record Bob's identity and update status
+    record_bob(session, bob, capacity as u32, config, accounts)?;
+    session.transition(Status::Computing)?;
+
+    // Both opted in: the circuit reveals the count for the callback
+    let publish = public_count && session.public_count == PublicCount::AliceOptedIn as u8;
+    if publish {
+        session.public_count = PublicCount::BothOptedIn as u8;
+    }
+    let withhold = session.reveal_locked(Clock::get()?.slot);
+    Ok((publish, withhold))
+}
+
+/// Apply a verified `submit_and_match` output, of any capacity, to the
+/// session: reopen Bob's slot if his list was rejected, otherwise mark
+/// the session matched, publish the count if both opted in, and emit
+/// Bob's encrypted result. Returns whether the match landed, so the
+/// callback knows to notify the app hook.
+fn finish_match(
+    session: &mut DiscoverySession,
+    global_stats: &mut GlobalStats,
+    app_stats: &mut AppStats,
+    result: &[[u8; 32]; args::MATCH_RESULT_CIPHERTEXTS],
+    nonce: u128,
+    accepted: bool,
+    public_count: u32,
+) -> Result<bool> {
+    if !session.consume_callback(ComputationStage::Match, Clock::get()?.slot) {
+        return Ok(false);
+    }
+    meter(global_stats, app_stats, Usage::Computation);
+    if !accepted {
+        // Bob's opt-in leaves with his list; Alice's stands
+        if session.public_count == PublicCount::BothOptedIn as u8 {
+            session.public_count = PublicCount::AliceOptedIn as u8;
+        }
+        meter(global_stats, app_stats, Usage::Rejection);
+        reopen_bob_slot(session)?;
+        return Ok(false);
+    }
+
+    // Matching is done: unlock Alice's reveal
+    session.transition(Status::Matched)?;
+    meter(global_stats, app_stats, Usage::Match);
+    session.rejected_party = 0;
+    session.alice_windows = 0;
+    session.bob_windows = 0;
+
+    let (encrypted_matches, encrypted_count) = split_match_result(result);
+    let nonce = nonce.to_le_bytes();
+    session.alice_receipt = [0; 32];
+    session.bob_receipt = result_receipt(&session.session_id, 2, 0, &encrypted_matches, &encrypted_count, &nonce);
+
+    if session.public_count == PublicCount::BothOptedIn as u8 {
+        session.match_count = public_count;
+        emit!(MatchCountPublished {
+            session_id: session.session_id,
+            memo: session.memo,
+            match_count: public_count,
+        });
+    }
+
+    emit!(MatchComplete {
+        session_id: session.session_id,
+        memo: session.memo,
+        encrypted_matches,
+        encrypted_count,
+        nonce,
+        notification_target: session.notification_target,
+    });
+
+    Ok(true)
+}
+
+/// Callback accounts that let the match callback notify the session's
+/// app hook: its `hook_authority`, the hook program, and the hook's
+/// accounts. None when the session has no hook; otherwise its `AppHook`
+/// must be the first remaining account.
+fn app_hook_callback_accounts(
+    session: &DiscoverySession,
+    session_key: &Pubkey,
+    remaining: &[AccountInfo],
+) -> Result<Vec<CallbackAccount>> {
+    if session.app_hook == 0 {
+        return Ok(Vec::new());
+    }
+    let info = remaining.first().ok_or(ErrorCode::AppHookMissing)?;
+    let expected = Pubkey::find_program_address(&[b"app_hook", session_key.as_ref()], &ID).0;
+    require!(
+        info.key() == expected && *info.owner == ID,
+        ErrorCode::AppHookMissing
+    );
+    let hook = AppHook::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+
+    let mut accounts = vec![
+        CallbackAccount {
+            pubkey: hook_authority(session_key, &hook.program).0,
+            is_writable: false,
+        },
+        CallbackAccount {
+            pubkey: hook.program,
+            is_writable: false,
+        },
+    ];
+    accounts.extend((0..hook.account_count as usize).map(|i| CallbackAccount {
+        pubkey: hook.accounts[i],
+        is_writable: hook.writable & (1 << i) != 0,
+    }));
+    Ok(accounts)
+}
+
+/// The PDA that signs `program`'s app hook calls for `session`, and
+/// its bump.
+fn hook_authority(session: &Pubkey, program: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"hook_authority", session.as_ref(), program.as_ref()], &ID)
+}
+
+/// Notify the session's app hook, if any, without failing the callback:
+/// a hook that cannot be called is reported with `AppHookFailed`. The
+/// runtime does not hand a failing CPI's error back to its caller, so
+/// a hook program that itself fails still aborts the transaction.
+fn notify_app_hook(session: &AccountLoader<DiscoverySession>, accounts: &[AccountInfo]) {
+    if let Err(error) = call_app_hook(session, accounts) {
+        if let Ok(session_data) = session.load() {
+            emit!(AppHookFailed {
+                session_id: session_data.session_id,
+                memo: session_data.memo,
+                program: accounts.get(1).map(|program| program.key()).unwrap_or_default(),
+                reason: error_code(&error),
+            });
+        }
+    }
+}
+
+/// Call the session's app hook with an `AppHookNotice`, signed by its
+/// `hook_authority` PDA. `accounts` are the callback's remaining
+/// accounts, as `app_hook_callback_accounts` queued them.
+fn call_app_hook(session: &AccountLoader<DiscoverySession>, accounts: &[AccountInfo]) -> Result<()> {
+    let notice = {
+        let session_data = session.load()?;
+        if session_data.app_hook == 0 {
+            return Ok(());
+        }
+        AppHookNotice {
+            session: session.key(),
+            session_id: session_data.session_id,
+            memo: session_data.memo,
+            alice: session_data.alice,
+            bob: session_data.bob,
+        }
+    };
+    let [authority, program, hook_accounts @ ..] = accounts else {
+        return err!(ErrorCode::AppHookMissing);
+    };
+    let (expected, bump) = hook_authority(&notice.session, &program.key());
+    require!(authority.key() == expected, ErrorCode::AppHookMissing);
+
+    let mut data = anchor_lang::solana_program::hash::hash(APP_HOOK_INSTRUCTION).to_bytes()[..8].to_vec();
+    notice.serialize(&mut data)?;
+    let metas = std::iter::once(AccountMeta::new_readonly(expected, true))
+        .chain(hook_accounts.iter().map(|info| AccountMeta {
+            pubkey: info.key(),
+            is_signer: false,
+            is_writable: info.is_writable,
+        }))
+        .collect();
+    let ix = Instruction {
+        program_id: program.key(),
+        accounts: metas,
+        data,
+    };
+    let program_key = program.key();
+    invoke_signed(
+        &ix,
+        accounts,
+        &[&[b"hook_authority", notice.session.as_ref(), program_key.as_ref(), &[bump]]],
+    )?;
+    Ok(())
+}
+
+/// `wallet`'s `ActivityLog` PDA.
+fn activity_log_pda(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"activity", wallet.as_ref()], &ID).0
+}
+
+/// Append an entry to `wallet`'s activity log if it is among `accounts`
+/// (the instruction's remaining accounts) and has been opened. Returns
+/// the log's address if it was written.
+fn record_activity(
+    accounts: &[AccountInfo],
+    wallet: &Pubkey,
+    session_id: [u8; 32],
+    kind: ActivityKind,
+) -> Result<Option<Pubkey>> {
+    let key = activity_log_pda(wallet);
+    let Some(info) = accounts.iter().find(|info| info.key() == key && *info.owner == ID) else {
+        return Ok(None);
+    };
+    write_activity(info, session_id, kind)?;
+    Ok(Some(key))
+}
+
+fn write_activity(info: &AccountInfo, session_id: [u8; 32], kind: ActivityKind) -> Result<()> {
+    let mut log = ActivityLog::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    log.push(session_id, kind, Clock::get()?.slot);
+    log.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+/// Callback accounts for the opened activity logs of `wallets` among
+/// `accounts`, so the callback can record the outcome in them. They
+/// come first among the callback's remaining accounts.
+fn activity_callback_accounts(accounts: &[AccountInfo], wallets: &[Pubkey]) -> Vec<CallbackAccount> {
+    wallets
+        .iter()
+        .map(activity_log_pda)
+        .filter(|key| accounts.iter().any(|info| info.key() == *key && *info.owner == ID))
+        .map(|pubkey| CallbackAccount {
+            pubkey,
+            is_writable: true,
+        })
+        .collect()
+}
+
+/// Record a match callback's outcome in the activity logs queued with
+/// it, the leading remaining accounts this program owns: matched for
+/// both parties if the match `landed`, rejected for Bob if his list was,
+/// failed for both if the computation was. Returns how many logs there
+/// were, so the app hook's accounts can be found after them.
+fn record_match_activity(
+    session: &AccountLoader<DiscoverySession>,
+    accounts: &[AccountInfo],
+    landed: bool,
+) -> Result<usize> {
+    let logs = accounts.iter().take_while(|info| *info.owner == ID).count();
+    let session = session.load()?;
+    let status = session.status()?;
+    for info in &accounts[..logs] {
+        let wallet = ActivityLog::try_deserialize(&mut &info.try_borrow_data()?[..])?.wallet;
+        let is_alice = wallet == session.alice;
+        let kind = match status {
+            Status::Matched if landed => ActivityKind::Matched,
+            Status::Failed => ActivityKind::Failed,
+            Status::AwaitingBob if !is_alice && session.rejected_party == 2 => ActivityKind::Rejected,
+            _ => continue,
+        };
+        write_activity(info, session.session_id, kind)?;
+    }
+    Ok(logs)
+}
+
+/// Handle a session computation the MPC did not complete: the session
+/// ends `Failed` and the stage's fee becomes refundable. The callback
+/// then succeeds, so the failure is recorded instead of reverted.
+fn fail_computation(session: &AccountLoader<DiscoverySession>, stage: ComputationStage, error: &Error) -> Result<()> {
+    let mut session = session.load_mut()?;
+    computation_failed(session.session_id, stage as u8, session.stage_offsets[stage as usize], error)?;
+    if session.fail_stage(stage, Clock::get()?.slot)? {
+        emit!(SessionFailed {
+            session_id: session.session_id,
+            memo: session.memo,
+            stage: stage as u8,
+            refundable_fee: session.refundable_fee,
+        });
+    }
+    Ok(())
+}
+
+/// Emit `ComputationFailedEvent` for a callback whose output did not
+/// verify. Returns `Ok` for the callback to return: an error would
+/// revert the event with the rest of the transaction.
+fn computation_failed(session_id: [u8; 32], stage: u8, computation_offset: u64, error: &Error) -> Result<()> {
+    emit!(ComputationFailedEvent {
+        session_id,
+        stage,
+        computation_offset,
+        reason: error_code(error),
+    });
+    Ok(())
+}
+
+/// `error`'s numeric code, for events that report a failure.
+fn error_code(error: &Error) -> u32 {
+    match error {
+        Error::AnchorError(e) => e.error_code_number,
+        Error::ProgramError(e) => u64::from(e.program_error.clone()) as u32,
+    }
+}
+
+/// Fail with `ClientVersionUnsupported` if `client_version`, the
+/// `PROGRAM_VERSION` a session-creating client was built for, is below
+/// the config's `min_client_version`.
+fn require_client_version(config: &ProtocolConfig, client_version: u16) -> Result<()> {
+    require!(
+        client_version >= config.min_client_version,
+        ErrorCode::ClientVersionUnsupported
+    );
+    Ok(())
+}
+
+/// Fail unless `session_id` can name a new session: it is not all zeros,
+/// and `session`, its PDA, does not already hold one. The creating
+/// contexts take the PDA `init_if_needed`, so a taken id arrives here as
+/// an initialized account.
+fn require_new_session(session_id: &[u8; 32], session: &AccountLoader<DiscoverySession>) -> Result<()> {
+    require!(*session_id != [0u8; 32], ErrorCode::InvalidSessionId);
+    let data = session.to_account_info().try_borrow_data()?;
+    require!(
+        !data.starts_with(DiscoverySession::DISCRIMINATOR),
+        ErrorCode::SessionIdTaken
+    );
+    Ok(())
+}
+
+/// Address of the `GlobalStats` PDA, passed to every callback.
+fn global_stats_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"global_stats"], &ID).0
+}
+
+/// Address of `app_id`'s `AppStats` PDA, passed to session callbacks.
+fn app_stats_pda(app_id: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[b"app_stats", app_id], &ID).0
+}
+
+/// Count `usage` program-wide and against the session's app.
+fn meter(global: &mut GlobalStats, app: &mut AppStats, usage: Usage) {
+    global.usage.record(usage);
+    app.usage.record(usage);
+}
+
+/// Parse one `queue_batch` item's accounts off the front of `accounts`
+/// as the context `T` of its own instruction, run `handler` on it, and
+/// persist the accounts as the instruction would on exit. Every batchable
+/// context takes `computation_offset` as its only instruction argument.
+fn run_batched<'info, T>(
+    accounts: &mut &'info [AccountInfo<'info>],
+    computation_offset: u64,
+    handler: impl FnOnce(Context<'_, '_, '_, 'info, T>) -> Result<()>,
+) -> Result<()>
+where
+    T: Accounts<'info, T::Bumps> + AccountsExit<'info> + Bumps,
+    T::Bumps: Default,
+{
+    let mut bumps = T::Bumps::default();
+    let mut reallocs = BTreeSet::new();
+    let mut item = T::try_accounts(
+        &crate::ID,
+        accounts,
+        &computation_offset.to_le_bytes(),
+        &mut bumps,
+        &mut reallocs,
+    )?;
+    handler(Context::new(&crate::ID, &mut item, &[], bumps))?;
+    item.exit(&crate::ID)
+}
+
+/// Split an encrypted MatchResult (2 packed slot ciphertexts + 1 count
+/// ciphertext) into its two fields for event emission.
+fn split_match_result(ciphertexts: &[[u8; 32]; args::MATCH_RESULT_CIPHERTEXTS]) -> ([[u8; 32]; 2], [u8; 32]) {
+    let mut matches = [[0u8; 32]; 2];
+    matches.copy_from_slice(&ciphertexts[..2]);
+    (matches, ciphertexts[2])
+}
+
+/// Mark result window `window` delivered in a party's `windows` bitmap
+/// and report whether every one of the `RESULT_PAGES` windows now has.
+fn deliver_window(windows: &mut u8, window: u8) -> bool {
+    *windows |= 1 << window;
+    *windows == ALL_WINDOWS
+}
+
+/// Set the return data of a callback to the account holding its result
+/// (the session, pool, or blocklist), so the program whose computation
+/// it was can read where the output landed without parsing events.
+/// Failed session computations return nothing.
+fn return_result_account(account: &Pubkey) {
+    set_return_data(account.as_ref());
+}
+
+/// Whether a computation definition can be queued against. The comp defs
+/// are created with no circuit source, so the circuit is uploaded
+/// on-chain afterwards and only usable once that upload is finalized.
+fn comp_def_ready(comp_def: &ComputationDefinitionAccount) -> bool {
+    match &comp_def.circuit_source {
+        CircuitSource::OnChain(source) => source.is_completed,
+        _ => true,
+    }
+}
+
+// ============================================================
+// CONTEXT STRUCTURES - Queue Computation
+// ============================================================
+
+#[queue_computation_accounts("init_session", payer)]
+#[derive(Accounts)]
+#[instruction(client_version: u16, computation_offset: u64, session_id: [u8; 32], app_id: [u8; 32])]
+pub struct CreateSession<'info> {
+    pub alice: Signer<'info>,
+    /// Funds rent and the computation fee: Alice herself, or a sponsor
+    /// such as the app's backend. Every paying context takes one.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// `init_if_needed` so that a taken id reaches the handler, which
+    /// refuses it with `SessionIdTaken`, rather than failing in account
+    /// creation
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = DiscoverySession::SIZE,
+        seeds = [b"session", session_id.as_ref()],
+        bump
+    )]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = SessionStateHandle::SIZE,
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = AppStats::SIZE,
+        seeds = [b"app_stats", app_id.as_ref()],
+        bump
+    )]
+    pub app_stats: Account<'info, AppStats>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_SESSION),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[derive(Accounts)]
+#[instruction(client_version: u16, session_id: [u8; 32], app_id: [u8; 32])]
+pub struct CreateStatelessSession<'info> {
+    pub alice: Signer<'info>,
+    /// Funds rent; no computation is queued, so nothing is billed
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// `init_if_needed` as in `CreateSession`
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = DiscoverySession::SIZE,
+        seeds = [b"session", session_id.as_ref()],
+        bump
+    )]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = SessionStateHandle::SIZE,
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    /// The app's credit, later billed for the session, whose authority
+    /// must sign
+    #[account(seeds = [b"credit", app_id.as_ref()], bump = credit.bump)]
+    pub credit: Account<'info, CreditAccount>,
+    /// For its `min_client_version`
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = AppStats::SIZE,
+        seeds = [b"app_stats", app_id.as_ref()],
+        bump
+    )]
+    pub app_stats: Account<'info, AppStats>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("init_session", payer)]
+#[derive(Accounts)]
+#[instruction(client_version: u16, computation_offset: u64, session_id: [u8; 32])]
+pub struct CreateSessionFromTemplate<'info> {
+    pub alice: Signer<'info>,
+    /// Funds rent and the computation fee; must be the template's
+    /// sponsor if it names one
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"template", template.app_id.as_ref(), template.template_id.as_ref()],
+        bump = template.bump
+    )]
+    pub template: Account<'info, SessionTemplate>,
+    /// `init_if_needed` so that a taken id reaches the handler, which
+    /// refuses it with `SessionIdTaken`, rather than failing in account
+    /// creation
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = DiscoverySession::SIZE,
+        seeds = [b"session", session_id.as_ref()],
+        bump
+    )]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = SessionStateHandle::SIZE,
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = AppStats::SIZE,
+        seeds = [b"app_stats", template.app_id.as_ref()],
+        bump
+    )]
+    pub app_stats: Account<'info, AppStats>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_SESSION),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("submit_contacts_alice", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SubmitContactsAlice<'info> {
+    pub alice: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_ALICE),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("submit_contacts_alice", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SubmitContactsWithPermit<'info> {
+    /// CHECK: authorized by the Ed25519 permit, not by signing
+    pub alice: UncheckedAccount<'info>,
+    /// The relayer landing the transaction
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_ALICE),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("submit_contacts_alice_16", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SubmitContactsAlice16<'info> {
+    pub alice: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_ALICE_16),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("submit_contacts_alice_8", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SubmitContactsAlice8<'info> {
+    pub alice: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_ALICE_8),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("init_and_submit_alice", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct InitAndSubmitAlice<'info> {
+    pub alice: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation == 0 @ ErrorCode::StateAlreadyInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_AND_SUBMIT_ALICE),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("submit_and_match", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SubmitAndMatch<'info> {
+    pub bob: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_AND_MATCH),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("submit_and_match_16", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SubmitAndMatch16<'info> {
+    pub bob: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_AND_MATCH_16),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("submit_and_match_8", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SubmitAndMatch8<'info> {
+    pub bob: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_AND_MATCH_8),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("match_set_chunk", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SubmitSetChunk<'info> {
+    pub bob: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_MATCH_SET_CHUNK),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("reveal_alice_matches", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RevealAliceMatches<'info> {
+    pub alice: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_ALICE),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("reveal_bob_matches", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RevealBobMatches<'info> {
+    pub bob: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_BOB),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("reveal_both", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RevealBoth<'info> {
+    pub alice: Signer<'info>,
+    pub bob: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_BOTH),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("push_delta", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PushDelta<'info> {
+    pub party: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_PUSH_DELTA),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("create_pool", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, pool_id: [u8; 32])]
+pub struct CreatePool<'info> {
+    pub host: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = DiscoveryPool::SIZE,
+        seeds = [b"pool", pool_id.as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, DiscoveryPool>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CREATE_POOL),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("match_against_pool", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct MatchAgainstPool<'info> {
+    pub user: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub pool: Account<'info, DiscoveryPool>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_MATCH_POOL),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[derive(Accounts)]
+#[instruction(peer: Pubkey)]
+pub struct StageList<'info> {
+    pub owner: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = StagedList::SIZE,
+        seeds = [b"staged_list", owner.key().as_ref(), peer.as_ref()],
+        bump
+    )]
+    pub staged_list: Account<'info, StagedList>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("instant_match", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct InstantMatch<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"staged_list", alice_list.owner.as_ref(), alice_list.peer.as_ref()],
+        bump = alice_list.bump
+    )]
+    pub alice_list: Account<'info, StagedList>,
+    #[account(
+        mut,
+        seeds = [b"staged_list", bob_list.owner.as_ref(), bob_list.peer.as_ref()],
+        bump = bob_list.bump
+    )]
+    pub bob_list: Account<'info, StagedList>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_INSTANT_MATCH),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[derive(Accounts)]
+pub struct CloseStagedList<'info> {
+    pub owner: Signer<'info>,
+    /// Refunded the rent; must be `StagedList::rent_payer`
+    #[account(mut, address = staged_list.rent_payer @ ErrorCode::Unauthorized)]
+    pub rent_payer: SystemAccount<'info>,
+    #[account(
+        mut,
+        close = rent_payer,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub staged_list: Account<'info, StagedList>,
+}
+
+#[derive(Accounts)]
+#[instruction(database_id: [u8; 32])]
+pub struct CreateDatabase<'info> {
+    pub provider: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = DiscoveryDatabase::SIZE,
+        seeds = [b"database", database_id.as_ref(), &0u32.to_le_bytes()],
+        bump
+    )]
+    pub database: AccountLoader<'info, DiscoveryDatabase>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(database_id: [u8; 32], epoch: u32)]
+pub struct StageDatabaseEpoch<'info> {
+    pub provider: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub live_database: AccountLoader<'info, DiscoveryDatabase>,
+    #[account(
+        init,
+        payer = payer,
+        space = DiscoveryDatabase::SIZE,
+        seeds = [b"database", database_id.as_ref(), &epoch.to_le_bytes()],
+        bump
+    )]
+    pub database: AccountLoader<'info, DiscoveryDatabase>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RotateDatabaseEpoch<'info> {
+    pub provider: Signer<'info>,
+    #[account(mut)]
+    pub live_database: AccountLoader<'info, DiscoveryDatabase>,
+    #[account(mut)]
+    pub next_database: AccountLoader<'info, DiscoveryDatabase>,
+}
+
+#[queue_computation_accounts("load_database_chunk", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct LoadDatabaseChunk<'info> {
+    pub provider: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub database: AccountLoader<'info, DiscoveryDatabase>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_LOAD_DATABASE_CHUNK),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[derive(Accounts)]
+pub struct SealDatabase<'info> {
+    pub provider: Signer<'info>,
+    #[account(mut)]
+    pub database: AccountLoader<'info, DiscoveryDatabase>,
+}
+
+#[queue_computation_accounts("query_psi", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct QueryPsi<'info> {
+    pub user: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub database: AccountLoader<'info, DiscoveryDatabase>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_QUERY_PSI),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("check_membership", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct CheckMembership<'info> {
+    pub user: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub database: AccountLoader<'info, DiscoveryDatabase>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_MEMBERSHIP),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("check_membership_batch", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct CheckMembershipBatch<'info> {
+    pub user: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub database: AccountLoader<'info, DiscoveryDatabase>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_MEMBERSHIP_BATCH),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("submit_and_score", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SubmitAndScore<'info> {
+    pub bob: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_AND_SCORE),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("reveal_alice_score", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RevealAliceScore<'info> {
+    pub alice: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_ALICE_SCORE),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("submit_contacts_bob", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SubmitContactsBob<'info> {
+    pub bob: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_BOB),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("submit_connector", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SubmitConnector<'info> {
+    pub connector: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_CONNECTOR),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("reveal_second_degree", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RevealSecondDegree<'info> {
+    pub party: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_SECOND_DEGREE),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[derive(Accounts)]
+pub struct CommitContacts<'info> {
+    pub alice: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+}
+
+#[derive(Accounts)]
+pub struct CommitSet<'info> {
+    pub bob: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSession<'info> {
+    pub alice: Signer<'info>,
+    /// Refunded the rent; must be `DiscoverySession::rent_recipient`
+    #[account(mut)]
+    pub rent_payer: SystemAccount<'info>,
+    #[account(mut, close = rent_payer)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+}
+
+#[derive(Accounts)]
+pub struct SweepSessions<'info> {
+    /// Paid `SWEEP_REWARD_BPS` of each swept session's rent
+    #[account(mut)]
+    pub sweeper: Signer<'info>,
+}
+
+/// One session of a `sweep_sessions` call, parsed from its remaining
+/// accounts.
+#[derive(Accounts)]
+pub struct SweptSession<'info> {
+    /// Refunded the rent; must be `DiscoverySession::rent_recipient`
+    #[account(mut)]
+    pub rent_payer: SystemAccount<'info>,
+    #[account(mut, close = rent_payer)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+}
+
+#[derive(Accounts)]
+pub struct ViewSession<'info> {
+    pub session: AccountLoader<'info, DiscoverySession>,
+}
+
+#[derive(Accounts)]
+pub struct PingSession<'info> {
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimInvite<'info> {
+    /// Funds the claim, refunded when he redeems it
+    #[account(mut)]
+    pub bob: Signer<'info>,
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        init,
+        payer = bob,
+        space = InviteClaim::SIZE,
+        seeds = [b"invite_claim", session.key().as_ref(), bob.key().as_ref()],
+        bump
+    )]
+    pub invite_claim: Account<'info, InviteClaim>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemInvite<'info> {
+    /// Refunded the claim's rent
+    #[account(mut)]
+    pub bob: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        mut,
+        close = bob,
+        seeds = [b"invite_claim", session.key().as_ref(), bob.key().as_ref()],
+        bump = invite_claim.bump
+    )]
+    pub invite_claim: Account<'info, InviteClaim>,
+}
+
+/// Each item's accounts, its own signers and payer among them, follow
+/// as remaining accounts.
+#[derive(Accounts)]
+pub struct QueueBatch<'info> {
+    /// Whoever drives the batch; the items authorize themselves
+    pub relayer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReallocSession<'info> {
+    pub alice: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// Grown before the handler loads it, so sessions smaller than the
+    /// current layout can still be read
+    #[account(
+        mut,
+        realloc = DiscoverySession::SIZE,
+        realloc::payer = payer,
+        realloc::zero = true,
+    )]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    /// Created for sessions that predate state handles
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = SessionStateHandle::SIZE,
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterNotification<'info> {
+    pub alice: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterAppHook<'info> {
+    pub alice: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    /// `init_if_needed` so that registering again replaces the hook
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = AppHook::SIZE,
+        seeds = [b"app_hook", session.key().as_ref()],
+        bump
+    )]
+    pub app_hook: Account<'info, AppHook>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConsentConnection<'info> {
+    /// Alice or Bob
+    pub party: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+}
+
+#[derive(Accounts)]
+#[instruction(party_a: Pubkey, party_b: Pubkey)]
+pub struct AttestConnection<'info> {
+    /// Funds the attestation; anyone
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        init,
+        payer = payer,
+        space = ConnectionAttestation::SIZE,
+        seeds = [b"connection", party_a.as_ref(), party_b.as_ref()],
+        bump
+    )]
+    pub attestation: Account<'info, ConnectionAttestation>,
+    /// CHECK: the connection's Token-2022 mint, allocated here and
+    /// initialized by `soulbound::mint_soulbound`
+    #[account(
+        init,
+        payer = payer,
+        space = soulbound::MINT_SIZE,
+        owner = soulbound::TOKEN_2022_PROGRAM_ID,
+        seeds = [b"connection_mint", attestation.key().as_ref()],
+        bump
+    )]
+    pub mint: UncheckedAccount<'info>,
+    /// CHECK: receives `party_a`'s token
+    #[account(address = party_a)]
+    pub party_a_wallet: UncheckedAccount<'info>,
+    /// CHECK: receives `party_b`'s token
+    #[account(address = party_b)]
+    pub party_b_wallet: UncheckedAccount<'info>,
+    /// CHECK: `party_a`'s associated token account for `mint`; the
+    /// associated token program checks the address and creates it
+    #[account(mut)]
+    pub party_a_token: UncheckedAccount<'info>,
+    /// CHECK: `party_b`'s, likewise
+    #[account(mut)]
+    pub party_b_token: UncheckedAccount<'info>,
+    /// CHECK: checked against `soulbound::TOKEN_2022_PROGRAM_ID`
+    #[account(address = soulbound::TOKEN_2022_PROGRAM_ID)]
+    pub token_program: UncheckedAccount<'info>,
+    /// CHECK: checked against `soulbound::ASSOCIATED_TOKEN_PROGRAM_ID`
+    #[account(address = soulbound::ASSOCIATED_TOKEN_PROGRAM_ID)]
+    pub associated_token_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(party_a: Pubkey, party_b: Pubkey)]
+pub struct OpenPairQuota<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = PairQuota::SIZE,
+        seeds = [b"pair", party_a.as_ref(), party_b.as_ref()],
+        bump
+    )]
+    pub pair_quota: Account<'info, PairQuota>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RenewQueryBudget<'info> {
+    /// Either wallet of the pair
+    pub target: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pair", pair_quota.party_a.as_ref(), pair_quota.party_b.as_ref()],
+        bump = pair_quota.bump
+    )]
+    pub pair_quota: Account<'info, PairQuota>,
+}
+
+#[derive(Accounts)]
+pub struct SetConsentRecord<'info> {
+    /// Funds the record on first use
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = wallet,
+        space = ConsentRecord::SIZE,
+        seeds = [b"consent", wallet.key().as_ref()],
+        bump
+    )]
+    pub consent_record: Account<'info, ConsentRecord>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseConsentRecord<'info> {
+    /// Refunded the rent
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    #[account(
+        mut,
+        close = wallet,
+        seeds = [b"consent", wallet.key().as_ref()],
+        bump = consent_record.bump
+    )]
+    pub consent_record: Account<'info, ConsentRecord>,
+}
+
+#[derive(Accounts)]
+pub struct OpenActivityLog<'info> {
+    pub wallet: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = ActivityLog::SIZE,
+        seeds = [b"activity", wallet.key().as_ref()],
+        bump
+    )]
+    pub activity_log: Account<'info, ActivityLog>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseActivityLog<'info> {
+    /// Refunded the rent
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    #[account(
+        mut,
+        close = wallet,
+        seeds = [b"activity", wallet.key().as_ref()],
+        bump = activity_log.bump
+    )]
+    pub activity_log: Account<'info, ActivityLog>,
+}
+
+#[derive(Accounts)]
+pub struct SyncDeployment<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = DeploymentRegistry::SIZE,
+        seeds = [b"deployment"],
+        bump
+    )]
+    pub registry: Account<'info, DeploymentRegistry>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts every billed queue instruction goes through: the price, the
+/// credit of the session's (or querying) app, and the treasury
+/// collecting the protocol's share of it.
+#[derive(Accounts)]
+pub struct Billing<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(mut, seeds = [b"credit", credit.app_id.as_ref()], bump = credit.bump)]
+    pub credit: Account<'info, CreditAccount>,
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+}
+
+impl<'info> Billing<'info> {
+    /// Move the price of `session`'s `stage` computation out of its app's
+    /// credit: the configured fee pool share to `fee_pool` (the Arcium
+    /// fee pool the computation is queued against), the rest to the
+    /// treasury. The fee is recorded on the session in case the
+    /// computation fails. Fails with `CreditAppMismatch` if the credit
+    /// account belongs to another app and `InsufficientCredit` if it
+    /// cannot cover the price. The app's authority co-signed the
+    /// session's creation (`CreditAccount::require_authority`), or its
+    /// template, so it consented to the session's charges.
+    pub fn charge(
+        &mut self,
+        session: &mut DiscoverySession,
+        stage: ComputationStage,
+        fee_pool: &impl ToAccountInfo<'info>,
+    ) -> Result<()> {
+        require!(self.credit.app_id == session.app_id, ErrorCode::CreditAppMismatch);
+        let price = self.config.computation_price;
+        session.stage_fees[stage as usize] = price;
+        self.collect(price, fee_pool)
+    }
+
+    /// Bill `queries` lookups, the computation price each, to the credit
+    /// account's app, split as `charge` does. Returns the fee.
+    pub fn charge_queries(&mut self, queries: u8, fee_pool: &impl ToAccountInfo<'info>) -> Result<u64> {
+        let fee = self
+            .config
+            .computation_price
+            .checked_mul(queries as u64)
+            .ok_or(ErrorCode::InsufficientCredit)?;
+        self.collect(fee, fee_pool)?;
+        Ok(fee)
+    }
+
+    fn collect(&mut self, price: u64, fee_pool: &impl ToAccountInfo<'info>) -> Result<()> {
+        if price == 0 {
+            return Ok(());
+        }
+        require!(self.credit.balance >= price, ErrorCode::InsufficientCredit);
+        self.credit.balance -= price;
+        self.credit.sub_lamports(price)?;
+
+        let top_up = self.config.fee_pool_share(price);
+        if top_up > 0 {
+            self.treasury.fee_pool_topped_up += top_up;
+            fee_pool.to_account_info().add_lamports(top_up)?;
+        }
+        self.treasury.collected += price - top_up;
+        self.treasury.add_lamports(price - top_up)?;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(init, payer = admin, space = ProtocolConfig::SIZE, seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(init, payer = admin, space = Treasury::SIZE, seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        init,
+        payer = admin,
+        space = CreditAccount::SIZE,
+        seeds = [b"credit", NO_APP_ID.as_ref()],
+        bump
+    )]
+    pub unattributed_credit: Account<'info, CreditAccount>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()) @ ErrorCode::Unauthorized)]
+    pub program: Program<'info, crate::program::PrivateContactDiscovery>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ ErrorCode::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct BlockWallet<'info> {
+    /// Funds the block
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        init,
+        payer = admin,
+        space = BlockedWallet::SIZE,
+        seeds = [b"blocked", wallet.as_ref()],
+        bump
+    )]
+    pub blocked_wallet: Account<'info, BlockedWallet>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnblockWallet<'info> {
+    /// Refunded the rent
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"blocked", blocked_wallet.wallet.as_ref()],
+        bump = blocked_wallet.bump
+    )]
+    pub blocked_wallet: Account<'info, BlockedWallet>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRefund<'info> {
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(mut, seeds = [b"credit", credit.app_id.as_ref()], bump = credit.bump)]
+    pub credit: Account<'info, CreditAccount>,
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+}
+
+#[derive(Accounts)]
+#[instruction(app_id: [u8; 32])]
+pub struct OpenCreditAccount<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = CreditAccount::SIZE,
+        seeds = [b"credit", app_id.as_ref()],
+        bump
+    )]
+    pub credit: Account<'info, CreditAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(app_id: [u8; 32], template_id: [u8; 32])]
+pub struct CreateSessionTemplate<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"credit", app_id.as_ref()],
+        bump = credit.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub credit: Account<'info, CreditAccount>,
+    #[account(
+        init,
+        payer = payer,
+        space = SessionTemplate::SIZE,
+        seeds = [b"template", app_id.as_ref(), template_id.as_ref()],
+        bump
+    )]
+    pub template: Account<'info, SessionTemplate>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSessionTemplate<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"template", template.app_id.as_ref(), template.template_id.as_ref()],
+        bump = template.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub template: Account<'info, SessionTemplate>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCredit<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    #[account(mut, seeds = [b"credit", credit.app_id.as_ref()], bump = credit.bump)]
+    pub credit: Account<'info, CreditAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawCredit<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"credit", credit.app_id.as_ref()],
+        bump = credit.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub credit: Account<'info, CreditAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(app_id: [u8; 32])]
+pub struct CreateAppLut<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"credit", app_id.as_ref()],
+        bump = credit.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub credit: Account<'info, CreditAccount>,
+    #[account(
+        init,
+        payer = payer,
+        space = AppLookupTable::SIZE,
+        seeds = [b"app_lut", app_id.as_ref()],
+        bump
+    )]
+    pub app_lut: Account<'info, AppLookupTable>,
+    #[account(mut)]
+    /// CHECK: lookup_table, checked against the address derived from app_lut and recent_slot
+    pub lookup_table: UncheckedAccount<'info>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExtendAppLut<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"credit", app_lut.app_id.as_ref()],
+        bump = credit.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub credit: Account<'info, CreditAccount>,
+    #[account(seeds = [b"app_lut", app_lut.app_id.as_ref()], bump = app_lut.bump)]
+    pub app_lut: Account<'info, AppLookupTable>,
+    #[account(mut, address = app_lut.table @ ErrorCode::LookupTableMismatch)]
+    /// CHECK: lookup_table
+    pub lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("create_blocklist", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, blocklist_id: [u8; 32])]
+pub struct CreateBlocklist<'info> {
+    pub moderator: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = DiscoveryBlocklist::SIZE,
+        seeds = [b"blocklist", blocklist_id.as_ref()],
+        bump
+    )]
+    pub blocklist: Account<'info, DiscoveryBlocklist>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CREATE_BLOCKLIST),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("check_against_blocklist", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct CheckAgainstBlocklist<'info> {
+    pub user: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub blocklist: Account<'info, DiscoveryBlocklist>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_BLOCKLIST),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("create_card_registry", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, registry_id: [u8; 32])]
+pub struct CreateCardRegistry<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = DiscoveryCardRegistry::SIZE,
+        seeds = [b"card_registry", registry_id.as_ref()],
+        bump
+    )]
+    pub registry: Account<'info, DiscoveryCardRegistry>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CREATE_CARD_REGISTRY),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("publish_contact_card", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PublishContactCard<'info> {
+    pub publisher: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub registry: Account<'info, DiscoveryCardRegistry>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_PUBLISH_CONTACT_CARD),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("match_contact_cards", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct MatchContactCards<'info> {
+    pub user: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub registry: Account<'info, DiscoveryCardRegistry>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_MATCH_CONTACT_CARDS),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("submit_and_check_follow", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SubmitAndCheckFollow<'info> {
+    pub bob: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    pub billing: Billing<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_AND_CHECK_FOLLOW),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("purge_session_state", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PurgeSessionState<'info> {
+    pub party: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_PURGE_SESSION_STATE),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("migrate_session_state", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct MigrateSessionState<'info> {
+    pub party: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(
+        seeds = [b"state_handle", session.key().as_ref()],
+        bump = state_handle.bump,
+        constraint = state_handle.generation != 0 @ ErrorCode::StateNotInitialized,
+    )]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::MempoolMismatch))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ExecPoolMismatch))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::CompAccountMismatch))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_MIGRATE_SESSION_STATE),
+        constraint = comp_def_ready(&comp_def_account) @ ErrorCode::CompDefNotInitialized,
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
     pub clock_account: Account<'info, ClockAccount>,
     pub system_program: Program<'info, System>,
     pub arcium_program: Program<'info, Arcium>,
 }
 
-#[queue_computation_accounts("reveal_alice_matches", alice)]
+// ============================================================
+// CONTEXT STRUCTURES - Callbacks
+// ============================================================
+
+#[callback_accounts("init_session")]
+#[derive(Accounts)]
+pub struct InitSessionCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_SESSION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"state_handle", session.key().as_ref()], bump = state_handle.bump)]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("submit_contacts_alice")]
+#[derive(Accounts)]
+pub struct SubmitContactsAliceCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_ALICE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("submit_contacts_alice_16")]
+#[derive(Accounts)]
+pub struct SubmitContactsAlice16Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_ALICE_16))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("submit_contacts_alice_8")]
+#[derive(Accounts)]
+pub struct SubmitContactsAlice8Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_ALICE_8))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("init_and_submit_alice")]
+#[derive(Accounts)]
+pub struct InitAndSubmitAliceCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_AND_SUBMIT_ALICE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"state_handle", session.key().as_ref()], bump = state_handle.bump)]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("submit_and_match")]
+#[derive(Accounts)]
+pub struct SubmitAndMatchCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_AND_MATCH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("submit_and_match_16")]
+#[derive(Accounts)]
+pub struct SubmitAndMatch16Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_AND_MATCH_16))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("submit_and_match_8")]
+#[derive(Accounts)]
+pub struct SubmitAndMatch8Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_AND_MATCH_8))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("match_set_chunk")]
+#[derive(Accounts)]
+pub struct MatchSetChunkCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MATCH_SET_CHUNK))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("reveal_alice_matches")]
+#[derive(Accounts)]
+pub struct RevealAliceMatchesCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_ALICE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("reveal_bob_matches")]
+#[derive(Accounts)]
+pub struct RevealBobMatchesCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_BOB))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("reveal_both")]
+#[derive(Accounts)]
+pub struct RevealBothCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_BOTH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("push_delta")]
+#[derive(Accounts)]
+pub struct PushDeltaCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PUSH_DELTA))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("create_pool")]
+#[derive(Accounts)]
+pub struct CreatePoolCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CREATE_POOL))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub pool: Account<'info, DiscoveryPool>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+}
+
+#[callback_accounts("match_against_pool")]
+#[derive(Accounts)]
+pub struct MatchAgainstPoolCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MATCH_POOL))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    pub pool: Account<'info, DiscoveryPool>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+}
+
+#[callback_accounts("instant_match")]
+#[derive(Accounts)]
+pub struct InstantMatchCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INSTANT_MATCH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut, close = alice_rent_payer)]
+    pub alice_list: Account<'info, StagedList>,
+    #[account(mut, address = alice_list.rent_payer)]
+    pub alice_rent_payer: SystemAccount<'info>,
+    #[account(mut, close = bob_rent_payer)]
+    pub bob_list: Account<'info, StagedList>,
+    #[account(mut, address = bob_list.rent_payer)]
+    pub bob_rent_payer: SystemAccount<'info>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+}
+
+#[callback_accounts("load_database_chunk")]
+#[derive(Accounts)]
+pub struct LoadDatabaseChunkCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_LOAD_DATABASE_CHUNK))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub database: AccountLoader<'info, DiscoveryDatabase>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+}
+
+#[callback_accounts("query_psi")]
+#[derive(Accounts)]
+pub struct QueryPsiCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_QUERY_PSI))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    pub database: AccountLoader<'info, DiscoveryDatabase>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+}
+
+#[callback_accounts("check_membership")]
+#[derive(Accounts)]
+pub struct CheckMembershipCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_MEMBERSHIP))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    pub database: AccountLoader<'info, DiscoveryDatabase>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+}
+
+#[callback_accounts("check_membership_batch")]
+#[derive(Accounts)]
+pub struct CheckMembershipBatchCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_MEMBERSHIP_BATCH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    pub database: AccountLoader<'info, DiscoveryDatabase>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+}
+
+#[callback_accounts("submit_and_score")]
+#[derive(Accounts)]
+pub struct SubmitAndScoreCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_AND_SCORE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("reveal_alice_score")]
+#[derive(Accounts)]
+pub struct RevealAliceScoreCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_ALICE_SCORE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("submit_contacts_bob")]
+#[derive(Accounts)]
+pub struct SubmitContactsBobCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_BOB))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("submit_connector")]
+#[derive(Accounts)]
+pub struct SubmitConnectorCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_CONNECTOR))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("reveal_second_degree")]
+#[derive(Accounts)]
+pub struct RevealSecondDegreeCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_SECOND_DEGREE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("create_blocklist")]
+#[derive(Accounts)]
+pub struct CreateBlocklistCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CREATE_BLOCKLIST))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub blocklist: Account<'info, DiscoveryBlocklist>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+}
+
+#[callback_accounts("check_against_blocklist")]
+#[derive(Accounts)]
+pub struct CheckAgainstBlocklistCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_BLOCKLIST))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    pub blocklist: Account<'info, DiscoveryBlocklist>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+}
+
+#[callback_accounts("create_card_registry")]
+#[derive(Accounts)]
+pub struct CreateCardRegistryCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CREATE_CARD_REGISTRY))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub registry: Account<'info, DiscoveryCardRegistry>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+}
+
+#[callback_accounts("publish_contact_card")]
+#[derive(Accounts)]
+pub struct PublishContactCardCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PUBLISH_CONTACT_CARD))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub registry: Account<'info, DiscoveryCardRegistry>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+}
+
+#[callback_accounts("match_contact_cards")]
+#[derive(Accounts)]
+pub struct MatchContactCardsCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MATCH_CONTACT_CARDS))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    pub registry: Account<'info, DiscoveryCardRegistry>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+}
+
+#[callback_accounts("submit_and_check_follow")]
+#[derive(Accounts)]
+pub struct SubmitAndCheckFollowCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_AND_CHECK_FOLLOW))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("purge_session_state")]
+#[derive(Accounts)]
+pub struct PurgeSessionStateCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PURGE_SESSION_STATE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+#[callback_accounts("migrate_session_state")]
+#[derive(Accounts)]
+pub struct MigrateSessionStateCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MIGRATE_SESSION_STATE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub session: AccountLoader<'info, DiscoverySession>,
+    #[account(mut, seeds = [b"state_handle", session.key().as_ref()], bump = state_handle.bump)]
+    pub state_handle: Account<'info, SessionStateHandle>,
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(mut, seeds = [b"app_stats", app_stats.app_id.as_ref()], bump = app_stats.bump)]
+    pub app_stats: Account<'info, AppStats>,
+}
+
+// ============================================================
+// COMPUTATION DEFINITION INIT CONTEXTS
+// ============================================================
+
+#[init_computation_definition_accounts("init_session", payer)]
+#[derive(Accounts)]
+pub struct InitSessionCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("submit_contacts_alice", payer)]
+#[derive(Accounts)]
+pub struct InitSubmitAliceCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("submit_contacts_alice_16", payer)]
+#[derive(Accounts)]
+pub struct InitSubmitAlice16CompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("submit_contacts_alice_8", payer)]
+#[derive(Accounts)]
+pub struct InitSubmitAlice8CompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("init_and_submit_alice", payer)]
+#[derive(Accounts)]
+pub struct InitAndSubmitAliceCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("submit_and_match", payer)]
+#[derive(Accounts)]
+pub struct InitSubmitAndMatchCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("submit_and_match_16", payer)]
+#[derive(Accounts)]
+pub struct InitSubmitAndMatch16CompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("submit_and_match_8", payer)]
+#[derive(Accounts)]
+pub struct InitSubmitAndMatch8CompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("reveal_alice_matches", payer)]
+#[derive(Accounts)]
+pub struct InitRevealAliceCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("reveal_bob_matches", payer)]
+#[derive(Accounts)]
+pub struct InitRevealBobCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("reveal_both", payer)]
+#[derive(Accounts)]
+pub struct InitRevealBothCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("instant_match", payer)]
+#[derive(Accounts)]
+pub struct InitInstantMatchCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("push_delta", payer)]
+#[derive(Accounts)]
+pub struct InitPushDeltaCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("create_pool", payer)]
+#[derive(Accounts)]
+pub struct InitCreatePoolCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("match_against_pool", payer)]
+#[derive(Accounts)]
+pub struct InitMatchPoolCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("submit_and_score", payer)]
+#[derive(Accounts)]
+pub struct InitSubmitAndScoreCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("reveal_alice_score", payer)]
+#[derive(Accounts)]
+pub struct InitRevealAliceScoreCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("submit_contacts_bob", payer)]
+#[derive(Accounts)]
+pub struct InitSubmitBobCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("submit_connector", payer)]
+#[derive(Accounts)]
+pub struct InitSubmitConnectorCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("reveal_second_degree", payer)]
+#[derive(Accounts)]
+pub struct InitRevealSecondDegreeCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("create_blocklist", payer)]
+#[derive(Accounts)]
+pub struct InitCreateBlocklistCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("check_against_blocklist", payer)]
+#[derive(Accounts)]
+pub struct InitCheckBlocklistCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("submit_and_check_follow", payer)]
+#[derive(Accounts)]
+pub struct InitSubmitAndCheckFollowCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("create_card_registry", payer)]
+#[derive(Accounts)]
+pub struct InitCreateCardRegistryCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("publish_contact_card", payer)]
+#[derive(Accounts)]
+pub struct InitPublishContactCardCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("match_contact_cards", payer)]
+#[derive(Accounts)]
+pub struct InitMatchContactCardsCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("match_set_chunk", payer)]
+#[derive(Accounts)]
+pub struct InitMatchSetChunkCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("load_database_chunk", payer)]
+#[derive(Accounts)]
+pub struct InitLoadDatabaseChunkCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("query_psi", payer)]
+#[derive(Accounts)]
+pub struct InitQueryPsiCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("check_membership", payer)]
+#[derive(Accounts)]
+pub struct InitCheckMembershipCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("check_membership_batch", payer)]
+#[derive(Accounts)]
+pub struct InitCheckMembershipBatchCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("purge_session_state", payer)]
+#[derive(Accounts)]
+pub struct InitPurgeSessionStateCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("migrate_session_state", payer)]
 #[derive(Accounts)]
-#[instruction(computation_offset: u64)]
-pub struct RevealAliceMatches<'info> {
+pub struct InitMigrateSessionStateCompDef<'info> {
     #[account(mut)]
-    pub alice: Signer<'info>,
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitGlobalStats<'info> {
     #[account(mut)]
-    pub session: Account<'info, DiscoverySession>,
+    pub payer: Signer<'info>,
     #[account(
-        init_if_needed,
-        space = 9,
-        payer = alice,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
+        init,
+        payer = payer,
+        space = GlobalStats::SIZE,
+        seeds = [b"global_stats"],
+        bump
     )]
-    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: mempool_account
-    pub mempool_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: executing_pool
-    pub executing_pool: UncheckedAccount<'info>,
-    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: computation_account
-    pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_ALICE))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
-    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
-    pub pool_account: Account<'info, FeePool>,
-    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
-    pub clock_account: Account<'info, ClockAccount>,
+    pub global_stats: Account<'info, GlobalStats>,
+    #[account(
+        init,
+        payer = payer,
+        space = AppStats::SIZE,
+        seeds = [b"app_stats", NO_APP_ID.as_ref()],
+        bump
+    )]
+    pub unattributed_app_stats: Account<'info, AppStats>,
     pub system_program: Program<'info, System>,
-    pub arcium_program: Program<'info, Arcium>,
 }
 
-// ============================================================
-// CONTEXT STRUCTURES - Callbacks
-// ============================================================
+// ============================================================
+// EVENTS
+// ============================================================
+
+/// Alice created a session; its `init_session` computation is queued.
+#[event]
+pub struct SessionCreated {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub alice: Pubkey,
+}
+
+/// The session's MXE state exists and it accepts submissions.
+#[event]
+pub struct SessionInitialized {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+}
+
+/// Alice committed to her submission with `commit_contacts`.
+#[event]
+pub struct ContactsCommitted {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub commitment: [u8; 32],
+}
+
+/// A party's encrypted list was queued for the MPC.
+#[event]
+pub struct ContactsSubmitted {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub party: u8,
+}
+
+/// The MPC rejected a list that was not sorted, deduplicated, and
+/// zero-padded (or was in the wrong namespace). The session is back at
+/// the stage where `party` submits.
+#[event]
+pub struct SubmissionRejected {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    /// Rejected party (1=Alice, 2=Bob, 3=connector)
+    pub party: u8,
+}
+
+/// The MPC accepted Alice's list; Bob can join.
+#[event]
+pub struct AliceSubmitted {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    /// SubmitConfirmation encrypted to Alice's key (accepted, party)
+    pub encrypted_confirmation: [[u8; 32]; 2],
+    pub nonce: [u8; 16],
+}
+
+/// Bob was recorded on the session: he submitted his list, or committed
+/// his set. Emitted again if a rejected Bob's slot is taken anew.
+#[event]
+pub struct BobJoined {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub bob: Pubkey,
+}
+
+/// `bob` redeemed the session's invite code and is now the only wallet
+/// that may join it.
+#[event]
+pub struct InviteRedeemed {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub bob: Pubkey,
+}
+
+/// `ping_session`: the session's status as it stands, for indexers
+/// resynchronizing after downtime.
+#[event]
+pub struct SessionHeartbeat {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub alice: Pubkey,
+    /// The default key until Bob joins
+    pub bob: Pubkey,
+    /// `Status`
+    pub status: u8,
+    /// Whether a `Purged` session's purge has landed
+    pub purged: bool,
+    /// `ComputationStage` whose callback is outstanding, or `NO_STAGE`
+    pub pending_stage: u8,
+}
+
+/// Bob's list was queued and the intersection is being computed.
+#[event]
+pub struct MatchComputing {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+}
+
+/// The intersection is computed. Carries Bob's result; Alice reads hers
+/// with `reveal_alice_matches`.
+#[event]
+pub struct MatchComplete {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    /// Bob's matched list slots, packed a byte each, encrypted to Bob's key
+    pub encrypted_matches: [[u8; 32]; 2],
+    /// Bob's match count, encrypted to Bob's key
+    pub encrypted_count: [u8; 32],
+    pub nonce: [u8; 16],
+    /// The session's `notification_target`, all zeros if none
+    pub notification_target: [u8; 32],
+}
+
+/// Bob committed a `CommittedSet` session to a set of chunks.
+#[event]
+pub struct SetCommitted {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub bob: Pubkey,
+    pub set_root: [u8; 32],
+    pub chunk_count: u8,
+}
+
+/// One chunk of a `CommittedSet` session was queued for matching.
+#[event]
+pub struct SetChunkComputing {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub chunk_index: u8,
+}
+
+/// One chunk of a `CommittedSet` session was matched.
+#[event]
+pub struct SetChunkMatched {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub chunk_index: u8,
+    /// Chunks still to match; the session is `Matched` at 0
+    pub chunks_remaining: u8,
+    /// This chunk's matched slots, packed a byte each, encrypted to Bob's key
+    pub encrypted_matches: [[u8; 32]; 2],
+    /// This chunk's match count, encrypted to Bob's key
+    pub encrypted_count: [u8; 32],
+    pub nonce: [u8; 16],
+}
+
+/// Both parties opted in, so the match count is public.
+#[event]
+pub struct MatchCountPublished {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub match_count: u32,
+}
+
+/// Alice's result was queued for re-encryption to her key.
+#[event]
+pub struct AliceRevealing {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+}
+
+/// Alice's result, encrypted to her key.
+#[event]
+pub struct AliceRevealed {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    /// Alice's matched list slots, packed a byte each, encrypted to Alice's key
+    pub encrypted_matches: [[u8; 32]; 2],
+    /// Alice's match count, encrypted to Alice's key
+    pub encrypted_count: [u8; 32],
+    pub nonce: [u8; 16],
+    /// Result window (page) this event carries
+    pub window: u8,
+    /// Whether every result window has now been delivered to Alice
+    pub complete: bool,
+}
+
+/// A page of Bob's result was queued for re-encryption to his key.
+#[event]
+pub struct BobRevealing {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub page: u8,
+}
+
+/// A reveal of both parties' results, at `page` of Bob's, was queued.
+#[event]
+pub struct BothRevealing {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub page: u8,
+}
+
+/// One page of Bob's result, encrypted to his key.
+#[event]
+pub struct BobRevealed {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    /// One page of Bob's matched list slots, encrypted to Bob's key
+    pub encrypted_matches: [[u8; 32]; 2],
+    /// Bob's total match count, encrypted to Bob's key
+    pub encrypted_count: [u8; 32],
+    pub nonce: [u8; 16],
+    /// Result window (page) this event carries
+    pub window: u8,
+    /// Whether every result window has now been delivered to Bob
+    pub complete: bool,
+}
+
+/// A subscription party pushed a delta round.
+#[event]
+pub struct DeltaSubmitted {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    /// Pushing party (1=Alice, 2=Bob)
+    pub party: u8,
+    pub round: u32,
+}
+
+/// A delta round was matched against the other party's list.
+#[event]
+pub struct DeltaMatched {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub round: u32,
+    /// Slots of newly discovered matches in the pusher's stored list,
+    /// encrypted to the pushing party's key
+    pub encrypted_matches: [[u8; 32]; 2],
+    /// Number of new matches, encrypted to the pushing party's key
+    pub encrypted_count: [u8; 32],
+    pub nonce: [u8; 16],
+}
+
+/// A host created a pool; its list is being stored by the MPC.
+#[event]
+pub struct PoolCreated {
+    pub pool_id: [u8; 32],
+    pub host: Pubkey,
+}
+
+/// The pool's list is stored and it accepts matches.
+#[event]
+pub struct PoolOpened {
+    pub pool_id: [u8; 32],
+}
+
+/// A user's list was queued against the pool.
+#[event]
+pub struct PoolMatching {
+    pub pool_id: [u8; 32],
+    pub user: Pubkey,
+}
+
+/// A user's match against the pool.
+#[event]
+pub struct PoolMatched {
+    pub pool_id: [u8; 32],
+    /// Slots of the user's list found in the pool, encrypted to the user's key
+    pub encrypted_matches: [[u8; 32]; 2],
+    /// Number of user's contacts in the pool, encrypted to the user's key
+    pub encrypted_count: [u8; 32],
+    pub nonce: [u8; 16],
+}
+
+/// A party staged its list for an instant match with `peer`.
+#[event]
+pub struct ListStaged {
+    pub owner: Pubkey,
+    pub peer: Pubkey,
+}
+
+/// Two staged lists were queued for an instant match.
+#[event]
+pub struct InstantMatching {
+    pub alice: Pubkey,
+    pub bob: Pubkey,
+}
+
+/// Both sides of an instant match. When `accepted` is false (a list
+/// was malformed or the namespaces differ) both results are empty.
+#[event]
+pub struct InstantMatched {
+    pub alice: Pubkey,
+    pub bob: Pubkey,
+    pub accepted: bool,
+    /// Alice's matched list slots, encrypted to the key her list was staged with
+    pub encrypted_alice_matches: [[u8; 32]; 2],
+    /// Match count, encrypted to Alice's key
+    pub encrypted_alice_count: [u8; 32],
+    pub alice_nonce: [u8; 16],
+    /// Bob's matched list slots, encrypted to the key his list was staged with
+    pub encrypted_bob_matches: [[u8; 32]; 2],
+    /// Match count, encrypted to Bob's key
+    pub encrypted_bob_count: [u8; 32],
+    pub bob_nonce: [u8; 16],
+}
+
+/// A staged list was withdrawn by its owner.
+#[event]
+pub struct StagedListClosed {
+    pub owner: Pubkey,
+    pub peer: Pubkey,
+}
+
+/// A provider created a database epoch.
+#[event]
+pub struct DatabaseCreated {
+    pub database_id: [u8; 32],
+    pub provider: Pubkey,
+    pub epoch: u32,
+}
+
+/// A chunk load into the database was queued.
+#[event]
+pub struct DatabaseChunkLoading {
+    pub database_id: [u8; 32],
+    pub epoch: u32,
+    pub chunk_index: u8,
+}
+
+/// A chunk load landed. A rejected chunk (malformed, or in the wrong
+/// namespace) was not stored and its slot is still free.
+#[event]
+pub struct DatabaseChunkLoaded {
+    pub database_id: [u8; 32],
+    pub epoch: u32,
+    pub chunk_index: u8,
+    pub accepted: bool,
+}
+
+/// The database's first epoch is sealed and accepts queries.
+#[event]
+pub struct DatabaseOpened {
+    pub database_id: [u8; 32],
+    pub epoch: u32,
+    pub chunk_count: u8,
+}
+
+/// A later epoch is loaded and waits for `rotate_database_epoch`.
+#[event]
+pub struct DatabaseEpochSealed {
+    pub database_id: [u8; 32],
+    pub epoch: u32,
+    pub chunk_count: u8,
+}
+
+/// Queries moved from `retired_epoch` to `epoch`.
+#[event]
+pub struct DatabaseEpochRotated {
+    pub database_id: [u8; 32],
+    pub retired_epoch: u32,
+    pub epoch: u32,
+    pub chunk_count: u8,
+}
+
+/// A user's query was queued against the database.
+#[event]
+pub struct DatabaseQuerying {
+    pub database_id: [u8; 32],
+    pub epoch: u32,
+    pub user: Pubkey,
+}
+
+/// A membership check's answer. `epoch` is the version it was checked
+/// against.
+#[event]
+pub struct MembershipChecked {
+    pub database_id: [u8; 32],
+    pub epoch: u32,
+    /// 1 if the identifier is in the database, else 0, encrypted to the client's key
+    pub encrypted_member: [u8; 32],
+    pub nonce: [u8; 16],
+}
+
+/// A batched membership check's answer.
+#[event]
+pub struct MembershipBatchChecked {
+    pub database_id: [u8; 32],
+    pub epoch: u32,
+    /// Bit `j` set iff identifier `j` is in the database, encrypted to the client's key
+    pub encrypted_mask: [u8; 32],
+    pub nonce: [u8; 16],
+}
+
+/// Membership lookups billed to `app_id`'s credit, `fee` lamports in all.
+#[event]
+pub struct QueriesCharged {
+    pub app_id: [u8; 32],
+    pub database_id: [u8; 32],
+    pub queries: u8,
+    pub fee: u64,
+}
+
+/// A query's result. `epoch` is the version it was matched against,
+/// which may already be retired if the database rotated meanwhile.
+#[event]
+pub struct DatabaseQueried {
+    pub database_id: [u8; 32],
+    pub epoch: u32,
+    /// Slots of the client's list found in the database, encrypted to the client's key
+    pub encrypted_matches: [[u8; 32]; 2],
+    /// Number of client's contacts in the database, encrypted to the client's key
+    pub encrypted_count: [u8; 32],
+    pub nonce: [u8; 16],
+}
+
+/// A score session's overlap, encrypted to Bob's key.
+#[event]
+pub struct ScoreComplete {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    /// OverlapScore (overlap_bp, jaccard_bp) encrypted to Bob's key
+    pub encrypted_score: [[u8; 32]; 2],
+    pub nonce: [u8; 16],
+}
+
+/// A score session's overlap, encrypted to Alice's key.
+#[event]
+pub struct AliceScoreRevealed {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    /// OverlapScore (overlap_bp, jaccard_bp) encrypted to Alice's key
+    pub encrypted_score: [[u8; 32]; 2],
+    pub nonce: [u8; 16],
+}
+
+/// The MPC accepted Bob's list in a friends-of-friends session; it
+/// waits for the connector.
+#[event]
+pub struct BobSubmitted {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    /// SubmitConfirmation encrypted to Bob's key (accepted, party)
+    pub encrypted_confirmation: [[u8; 32]; 2],
+    pub nonce: [u8; 16],
+}
+
+/// The connector's list was accepted and the friends-of-friends
+/// count computed.
+#[event]
+pub struct SecondDegreeComputed {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    /// SubmitConfirmation encrypted to the connector's key (accepted, party)
+    pub encrypted_confirmation: [[u8; 32]; 2],
+    pub nonce: [u8; 16],
+}
+
+/// A party's second-degree result was queued for re-encryption.
+#[event]
+pub struct SecondDegreeRevealing {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    /// Revealing party (1=Alice, 2=Bob)
+    pub party: u8,
+}
 
-#[callback_accounts("init_session")]
-#[derive(Accounts)]
-pub struct InitSessionCallback<'info> {
-    pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_SESSION))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    /// CHECK: computation_account
-    pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
-    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
-    /// CHECK: instructions_sysvar
-    pub instructions_sysvar: AccountInfo<'info>,
+/// A party's second-degree result, encrypted to its key.
+#[event]
+pub struct SecondDegreeRevealed {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    /// Friends-of-friends count (mutual-follow flag in mutual-follow
+    /// sessions), encrypted to the revealing party's key
+    pub encrypted_count: [u8; 32],
+    pub nonce: [u8; 16],
 }
 
-#[callback_accounts("submit_contacts_alice")]
-#[derive(Accounts)]
-pub struct SubmitContactsAliceCallback<'info> {
-    pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_ALICE))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    /// CHECK: computation_account
-    pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
-    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
-    /// CHECK: instructions_sysvar
-    pub instructions_sysvar: AccountInfo<'info>,
+/// A moderator created a blocklist; its list is being stored by the MPC.
+#[event]
+pub struct BlocklistCreated {
+    pub blocklist_id: [u8; 32],
+    pub moderator: Pubkey,
 }
 
-#[callback_accounts("submit_and_match")]
-#[derive(Accounts)]
-pub struct SubmitAndMatchCallback<'info> {
-    pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_AND_MATCH))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    /// CHECK: computation_account
-    pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
-    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
-    /// CHECK: instructions_sysvar
-    pub instructions_sysvar: AccountInfo<'info>,
+/// The blocklist's list is stored and it accepts checks.
+#[event]
+pub struct BlocklistOpened {
+    pub blocklist_id: [u8; 32],
 }
 
-#[callback_accounts("reveal_alice_matches")]
-#[derive(Accounts)]
-pub struct RevealAliceMatchesCallback<'info> {
-    pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_ALICE))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    /// CHECK: computation_account
-    pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
-    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
-    /// CHECK: instructions_sysvar
-    pub instructions_sysvar: AccountInfo<'info>,
+/// A user's list was queued against the blocklist.
+#[event]
+pub struct BlocklistChecking {
+    pub blocklist_id: [u8; 32],
+    pub user: Pubkey,
 }
 
-// ============================================================
-// COMPUTATION DEFINITION INIT CONTEXTS
-// ============================================================
+/// A user's blocklist check.
+#[event]
+pub struct BlocklistChecked {
+    pub blocklist_id: [u8; 32],
+    /// BlocklistResult (blocked, blocked_count) encrypted to the user's key
+    pub encrypted_result: [[u8; 32]; 2],
+    pub nonce: [u8; 16],
+}
 
-#[init_computation_definition_accounts("init_session", payer)]
-#[derive(Accounts)]
-pub struct InitSessionCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut)]
-    /// CHECK: comp_def_account
-    pub comp_def_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
-    /// CHECK: address_lookup_table
-    pub address_lookup_table: UncheckedAccount<'info>,
-    #[account(address = LUT_PROGRAM_ID)]
-    /// CHECK: lut_program
-    pub lut_program: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+/// A card registry was created; the MPC is initializing it.
+#[event]
+pub struct CardRegistryCreated {
+    pub registry_id: [u8; 32],
+    pub authority: Pubkey,
 }
 
-#[init_computation_definition_accounts("submit_contacts_alice", payer)]
-#[derive(Accounts)]
-pub struct InitSubmitAliceCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut)]
-    /// CHECK: comp_def_account
-    pub comp_def_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
-    /// CHECK: address_lookup_table
-    pub address_lookup_table: UncheckedAccount<'info>,
-    #[account(address = LUT_PROGRAM_ID)]
-    /// CHECK: lut_program
-    pub lut_program: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+/// The card registry's state is stored and it accepts cards.
+#[event]
+pub struct CardRegistryOpened {
+    pub registry_id: [u8; 32],
 }
 
-#[init_computation_definition_accounts("submit_and_match", payer)]
-#[derive(Accounts)]
-pub struct InitSubmitAndMatchCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut)]
-    /// CHECK: comp_def_account
-    pub comp_def_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
-    /// CHECK: address_lookup_table
-    pub address_lookup_table: UncheckedAccount<'info>,
-    #[account(address = LUT_PROGRAM_ID)]
-    /// CHECK: lut_program
-    pub lut_program: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+/// A contact card was queued for the registry.
+#[event]
+pub struct ContactCardPublishing {
+    pub registry_id: [u8; 32],
+    pub publisher: Pubkey,
 }
 
-#[init_computation_definition_accounts("reveal_alice_matches", payer)]
-#[derive(Accounts)]
-pub struct InitRevealAliceCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut)]
-    /// CHECK: comp_def_account
-    pub comp_def_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
-    /// CHECK: address_lookup_table
-    pub address_lookup_table: UncheckedAccount<'info>,
-    #[account(address = LUT_PROGRAM_ID)]
-    /// CHECK: lut_program
-    pub lut_program: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+/// A contact card was stored and the registry is open again.
+#[event]
+pub struct ContactCardPublished {
+    pub registry_id: [u8; 32],
 }
 
-// ============================================================
-// EVENTS
-// ============================================================
+/// A user's list was queued against the registry's cards.
+#[event]
+pub struct CardsMatching {
+    pub registry_id: [u8; 32],
+    pub user: Pubkey,
+}
 
+/// A user's lookup in the card registry.
 #[event]
-pub struct SessionCreated {
+pub struct CardsMatched {
+    pub registry_id: [u8; 32],
+    /// CardMatches encrypted to the user's key, by registry slot: the
+    /// matched hashes (0 = no match), both key halves, and the count
+    pub encrypted_hashes: [[u8; 32]; 16],
+    pub encrypted_keys_lo: [[u8; 32]; 16],
+    pub encrypted_keys_hi: [[u8; 32]; 16],
+    pub encrypted_count: [u8; 32],
+    pub nonce: [u8; 16],
+}
+
+/// A mutual-follow session's answer, encrypted to Bob's key.
+#[event]
+pub struct FollowChecked {
     pub session_id: [u8; 32],
-    pub alice: Pubkey,
+    pub memo: [u8; 32],
+    /// 1 if Alice and Bob follow each other, else 0, encrypted to Bob's key
+    pub encrypted_mutual: [u8; 32],
+    pub nonce: [u8; 16],
 }
 
+/// The session account was closed and its rent refunded.
 #[event]
-pub struct SessionInitialized {}
+pub struct SessionClosed {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+}
 
+/// Zeroing the session's MXE state was queued.
 #[event]
-pub struct ContactsSubmitted {
+pub struct StatePurging {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+}
+
+/// The session's MXE state is zeroed.
+#[event]
+pub struct StatePurged {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+}
+
+/// A migration of the session's MXE state to the current layout was
+/// queued.
+#[event]
+pub struct StateMigrating {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub from_version: u8,
+}
+
+/// The session's MXE state is in the current layout.
+#[event]
+pub struct StateMigrated {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub from_version: u8,
+    pub state_version: u8,
+}
+
+/// Alice set the session's notification target.
+#[event]
+pub struct NotificationRegistered {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub notification_target: [u8; 32],
+}
+
+/// Alice registered the session's app hook.
+#[event]
+pub struct AppHookRegistered {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub program: Pubkey,
+    pub account_count: u8,
+}
+
+/// The match landed but the session's app hook could not be notified,
+/// for instance because the callback was not passed its accounts.
+#[event]
+pub struct AppHookFailed {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub program: Pubkey,
+    /// Error code that kept the hook from being called
+    pub reason: u32,
+}
+
+/// A party consented to attesting the session's connection. `party` is
+/// `CONSENT_ALICE` or `CONSENT_BOB`.
+#[event]
+pub struct ConnectionConsented {
     pub session_id: [u8; 32],
+    pub memo: [u8; 32],
     pub party: u8,
 }
 
+/// The session's parties' connection was attested.
 #[event]
-pub struct AliceSubmitted {}
+pub struct ConnectionAttested {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub attestation: Pubkey,
+    /// Mint of the pair's soulbound tokens
+    pub mint: Pubkey,
+    pub party_a: Pubkey,
+    pub party_b: Pubkey,
+}
 
+/// An older session was brought up to the current layout.
 #[event]
-pub struct MatchComputing {
+pub struct SessionReallocated {
     pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub from_version: u8,
+    pub version: u8,
 }
 
+/// The config admin changed the computation price.
 #[event]
-pub struct MatchComplete {}
+pub struct ComputationPriceSet {
+    pub computation_price: u64,
+}
 
+/// The config admin changed the fee pool share.
 #[event]
-pub struct AliceRevealing {
+pub struct FeePoolShareSet {
+    pub fee_pool_share_bps: u16,
+}
+
+/// The config admin changed the refund share.
+#[event]
+pub struct RefundShareSet {
+    pub refund_bps: u16,
+}
+
+/// The config admin changed the oldest client version sessions accept.
+#[event]
+pub struct MinClientVersionSet {
+    pub min_client_version: u16,
+}
+
+/// The config admin changed how many sessions a pair may join per day.
+#[event]
+pub struct MaxPairSessionsSet {
+    pub max_pair_sessions: u16,
+}
+
+/// The config admin changed how many slots a wallet may submit against
+/// another before it must renew.
+#[event]
+pub struct QueryBudgetSet {
+    pub query_budget: u32,
+}
+
+/// `wallet` replaced who may create sessions naming it as Bob.
+#[event]
+pub struct ConsentRecordSet {
+    pub wallet: Pubkey,
+    pub counterparties: Vec<Pubkey>,
+    pub apps: Vec<[u8; 32]>,
+}
+
+/// The config admin changed the attestation provider both parties need.
+#[event]
+pub struct GatekeeperSet {
+    pub gatekeeper: u8,
+    pub network: Pubkey,
+}
+
+/// The config admin blocked `wallet`.
+#[event]
+pub struct WalletBlocked {
+    pub wallet: Pubkey,
+}
+
+/// The config admin lifted the block on `wallet`.
+#[event]
+pub struct WalletUnblocked {
+    pub wallet: Pubkey,
+}
+
+/// `target` renewed the slots `querier` may submit against it.
+#[event]
+pub struct QueryBudgetRenewed {
+    pub pair_quota: Pubkey,
+    pub target: Pubkey,
+    pub querier: Pubkey,
+}
+
+/// A session computation failed; the session is `Failed` and its
+/// fee refundable.
+#[event]
+pub struct SessionFailed {
+    pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    /// `ComputationStage` whose computation failed
+    pub stage: u8,
+    pub refundable_fee: u64,
+}
+
+/// A callback's output failed verification: the MPC aborted or could
+/// not produce a signed result. Emitted on every failed callback,
+/// session or not, so monitoring sees failures that leave no failed
+/// transaction behind.
+#[event]
+pub struct ComputationFailedEvent {
+    /// The session, or for other computations the pool, database,
+    /// blocklist or card registry id, or Alice's staged list address for
+    /// an instant match
+    pub session_id: [u8; 32],
+    /// `ComputationStage`, or `NO_STAGE`
+    pub stage: u8,
+    /// Offset the stage last queued; 0 outside the session stages, where
+    /// the callback transaction's computation account identifies it
+    pub computation_offset: u64,
+    /// Error number the verification failed with
+    pub reason: u32,
+}
+
+/// A failed session's fee share was refunded to its app's credit.
+#[event]
+pub struct RefundClaimed {
     pub session_id: [u8; 32],
+    pub memo: [u8; 32],
+    pub app_id: [u8; 32],
+    pub fee: u64,
+    pub refund: u64,
+}
+
+/// An app's credit authority stored a session template.
+#[event]
+pub struct SessionTemplateCreated {
+    pub app_id: [u8; 32],
+    pub template_id: [u8; 32],
+    pub authority: Pubkey,
 }
 
+/// A session template was closed.
 #[event]
-pub struct AliceRevealed {}
+pub struct SessionTemplateClosed {
+    pub app_id: [u8; 32],
+    pub template_id: [u8; 32],
+}
+
+/// Lamports were added to an app's credit.
+#[event]
+pub struct CreditDeposited {
+    pub app_id: [u8; 32],
+    pub amount: u64,
+    pub balance: u64,
+}
+
+/// The credit authority withdrew lamports from an app's credit.
+#[event]
+pub struct CreditWithdrawn {
+    pub app_id: [u8; 32],
+    pub amount: u64,
+    pub balance: u64,
+}
+
+/// An app's lookup table was created with the static Arcium accounts.
+#[event]
+pub struct AppLutCreated {
+    pub app_id: [u8; 32],
+    pub table: Pubkey,
+}
+
+/// The credit authority added `count` addresses to an app's lookup table.
+#[event]
+pub struct AppLutExtended {
+    pub app_id: [u8; 32],
+    pub table: Pubkey,
+    pub count: u32,
+}
+
+/// `sync_deployment` recorded the deployment.
+#[event]
+pub struct DeploymentSynced {
+    pub program_version: u16,
+    /// Bit `i` set if `CIRCUITS[i]`'s computation definition is ready
+    pub initialized_comp_defs: u64,
+    pub cluster_offset: u32,
+}
 
 // ============================================================
 // ERRORS
@@ -763,4 +12824,172 @@ pub enum ErrorCode {
     Unauthorized,
     #[msg("Session already matched")]
     AlreadyMatched,
+    #[msg("Unknown session mode")]
+    InvalidSessionMode,
+    #[msg("Unknown identifier namespace")]
+    InvalidNamespace,
+    #[msg("Unknown identifier hash scheme")]
+    InvalidHashScheme,
+    #[msg("Submission uses a different hash scheme than the session")]
+    HashSchemeMismatch,
+    #[msg("Alice already committed to her contacts")]
+    AlreadyCommitted,
+    #[msg("Commitment must not be all zeros")]
+    InvalidCommitment,
+    #[msg("Submission does not match Alice's commitment")]
+    CommitmentMismatch,
+    #[msg("Session is not a subscription session")]
+    NotSubscription,
+    #[msg("Pool is not open for matching")]
+    PoolNotOpen,
+    #[msg("Blocklist is not open for checks")]
+    BlocklistNotOpen,
+    #[msg("Instruction not available in this session mode")]
+    ModeMismatch,
+    #[msg("Result page is past the session's capacity")]
+    InvalidPage,
+    #[msg("Mempool account does not belong to the MXE's cluster")]
+    MempoolMismatch,
+    #[msg("Executing pool account does not belong to the MXE's cluster")]
+    ExecPoolMismatch,
+    #[msg("Computation account does not match the computation offset")]
+    CompAccountMismatch,
+    #[msg("Computation definition circuit has not been uploaded and finalized")]
+    CompDefNotInitialized,
+    #[msg("Session cannot move to that status from its current one")]
+    InvalidTransition,
+    #[msg("Join deadline must be in the future")]
+    InvalidDeadline,
+    #[msg("The session's join deadline has passed")]
+    DeadlineExpired,
+    #[msg("The session's join deadline has not passed")]
+    DeadlineNotReached,
+    #[msg("The session's results are escrowed until its reveal slot")]
+    RevealLocked,
+    #[msg("The session's state purge has not completed")]
+    PurgePending,
+    #[msg("Rent must be refunded to the account that funded the session")]
+    RentPayerMismatch,
+    #[msg("No valid Ed25519 permit from the party precedes this instruction")]
+    InvalidPermit,
+    #[msg("The permit has expired")]
+    PermitExpired,
+    #[msg("The app's credit does not cover the computation price")]
+    InsufficientCredit,
+    #[msg("Credit account belongs to a different app")]
+    CreditAppMismatch,
+    #[msg("Fee share exceeds 10000 basis points")]
+    InvalidFeeShare,
+    #[msg("The session has no failed computation fee to refund")]
+    NothingToRefund,
+    #[msg("The treasury cannot cover the refund")]
+    TreasuryShortfall,
+    #[msg("Card registry is not open")]
+    RegistryNotOpen,
+    #[msg("A set is already committed to this session")]
+    SetAlreadyCommitted,
+    #[msg("Set root must be nonzero with 1 to 64 chunks")]
+    InvalidSetCommitment,
+    #[msg("No set is committed to this session")]
+    SetNotCommitted,
+    #[msg("Chunk is not in the committed set")]
+    InvalidSetProof,
+    #[msg("Chunk is out of range or already matched")]
+    ChunkAlreadyMatched,
+    #[msg("Database is not accepting chunks")]
+    DatabaseNotLoading,
+    #[msg("Every database chunk slot is loaded")]
+    DatabaseFull,
+    #[msg("Database has no chunks loaded")]
+    DatabaseEmpty,
+    #[msg("Database is not open for queries")]
+    DatabaseNotOpen,
+    #[msg("Database is not the next epoch of this database")]
+    DatabaseEpochMismatch,
+    #[msg("Next database epoch is not sealed")]
+    DatabaseNotSealed,
+    #[msg("A membership batch holds 1 to 16 queries")]
+    InvalidQueryCount,
+    #[msg("Sessions from this template must be funded by its sponsor")]
+    SponsorMismatch,
+    #[msg("Callbacks run only by Arcium CPI, alone in their transaction")]
+    UnexpectedCallbackTransaction,
+    #[msg("The session's MXE state has not been initialized")]
+    StateNotInitialized,
+    #[msg("Session id must not be all zeros")]
+    InvalidSessionId,
+    #[msg("A session with this id already exists")]
+    SessionIdTaken,
+    #[msg("Capacity must be one of 8, 16, or 32 contacts")]
+    InvalidCapacity,
+    #[msg("Session was created with a different capacity")]
+    CapacityMismatch,
+    #[msg("Staged lists were not staged for each other")]
+    PeerMismatch,
+    #[msg("List is not staged for a match")]
+    ListNotStaged,
+    #[msg("The session's MXE state already exists")]
+    StateAlreadyInitialized,
+    #[msg("A computation for this session is still queued")]
+    ComputationPending,
+    #[msg("The session's failed computation fee must be refunded first")]
+    RefundUnclaimed,
+    #[msg("A batch takes one to four items and exactly their accounts")]
+    InvalidBatch,
+    #[msg("Lookup table does not match the app's derived table")]
+    LookupTableMismatch,
+    #[msg("The session's app hook account was not passed")]
+    AppHookMissing,
+    #[msg("An app hook takes at most four accounts and cannot target this or the system program")]
+    InvalidAppHook,
+    #[msg("Both parties must consent before their connection is attested")]
+    ConsentMissing,
+    #[msg("Every computation definition must be passed, in circuit order")]
+    CompDefListMismatch,
+    #[msg("This client version is no longer supported; upgrade the SDK")]
+    ClientVersionUnsupported,
+    #[msg("Minimum client version is newer than the program")]
+    InvalidClientVersion,
+    #[msg("Session state is in an older layout; migrate it first")]
+    StateMigrationRequired,
+    #[msg("Session state is already in the current layout")]
+    StateCurrent,
+    #[msg("Session was pinged too recently")]
+    PingTooSoon,
+    #[msg("Sweep takes one to MAX_SWEEP sessions of three accounts each")]
+    InvalidSweep,
+    #[msg("Session is not abandoned, or is still in Alice's close window")]
+    NotSweepable,
+    #[msg("Sessions per pair are limited; pass the pair's opened PairQuota")]
+    PairQuotaMissing,
+    #[msg("This pair has joined its daily limit of sessions")]
+    PairRateLimited,
+    #[msg("Pair wallets must be distinct and in ascending order")]
+    InvalidPair,
+    #[msg("Bob has submitted his query budget against this Alice; she must renew it")]
+    QueryBudgetExhausted,
+    #[msg("The intended Bob's consent record does not allow this Alice or app")]
+    DiscoveryNotConsented,
+    #[msg("A consent record takes at most eight wallets and four apps, none app-less")]
+    InvalidConsentRecord,
+    #[msg("The config admin has blocked this wallet")]
+    WalletBlocked,
+    #[msg("Unknown gatekeeper")]
+    InvalidGatekeeper,
+    #[msg("A party's attestation from the config's gatekeeper was not passed")]
+    AttestationMissing,
+    #[msg("A party's attestation is not valid: wrong wallet or network, inactive, or expired")]
+    AttestationInvalid,
+    #[msg("A session takes an intended Bob or an invite, not both")]
+    InvalidInvite,
+    #[msg("Wrong invite code, or the session has no invite pending")]
+    InvalidInviteCode,
+    #[msg("The session's invite must be redeemed before Bob can join")]
+    InviteNotRedeemed,
+    #[msg("The wallet's BlockedWallet address must be passed")]
+    BlockCheckMissing,
+    #[msg("An invite claim can only be redeemed after the slot it was made in")]
+    InviteClaimTooRecent,
+    #[msg("Invite codes must be at least MIN_INVITE_CODE_LEN bytes")]
+    InviteCodeTooShort,
 }
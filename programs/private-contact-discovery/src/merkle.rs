@@ -0,0 +1,128 @@
+// Merkle commitments over a committed set: a set too large for one
+// `ContactList` is split into chunks, each encrypted on its own, and the
+// owner publishes the root of a tree over the chunks' ciphertext
+// commitments. The circuits cannot hash, so the program checks each
+// chunk's membership witness before queueing it; the MPC only ever sees
+// chunks the root already committed to.
+
+use anchor_lang::solana_program::hash::hashv;
+
+/// Most chunks a committed set may have (32 entries each).
+pub const MAX_SET_CHUNKS: u8 = 64;
+
+/// Domain of a leaf hash, so a leaf can never be read as an inner node.
+pub const SET_LEAF_DOMAIN: &[u8] = b"pcd-set-leaf-v1";
+/// Domain of an inner node hash.
+pub const SET_NODE_DOMAIN: &[u8] = b"pcd-set-node-v1";
+
+/// The leaf for chunk `index`, whose ciphertexts have `contacts_commitment`
+/// `commitment`. Binding the index stops a chunk being replayed in
+/// another chunk's place.
+pub fn set_leaf(index: u8, commitment: &[u8; 32]) -> [u8; 32] {
+    hashv(&[SET_LEAF_DOMAIN, &[index], commitment]).to_bytes()
+}
+
+fn node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    hashv(&[SET_NODE_DOMAIN, left, right]).to_bytes()
+}
+
+/// Depth of the tree over `chunk_count` leaves, padded with zero leaves
+/// to the next power of two.
+pub fn set_depth(chunk_count: u8) -> usize {
+    (chunk_count.max(1) as usize).next_power_of_two().trailing_zeros() as usize
+}
+
+fn padded(leaves: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    let mut level = leaves.to_vec();
+    level.resize(leaves.len().max(1).next_power_of_two(), [0u8; 32]);
+    level
+}
+
+fn parent_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level.chunks(2).map(|pair| node(&pair[0], &pair[1])).collect()
+}
+
+/// Root of the tree over `leaves`.
+pub fn set_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut level = padded(leaves);
+    while level.len() > 1 {
+        level = parent_level(&level);
+    }
+    level[0]
+}
+
+/// Sibling hashes from leaf `index` up to the root, bottom first.
+pub fn set_proof(leaves: &[[u8; 32]], index: usize) -> Vec<[u8; 32]> {
+    let mut level = padded(leaves);
+    let mut index = index;
+    let mut proof = Vec::new();
+    while level.len() > 1 {
+        proof.push(level[index ^ 1]);
+        level = parent_level(&level);
+        index /= 2;
+    }
+    proof
+}
+
+/// Whether `proof` shows `leaf` at `index` in a set of `chunk_count`
+/// chunks with root `root`.
+pub fn verify_set_proof(
+    root: &[u8; 32],
+    chunk_count: u8,
+    index: u8,
+    leaf: &[u8; 32],
+    proof: &[[u8; 32]],
+) -> bool {
+    if index >= chunk_count || proof.len() != set_depth(chunk_count) {
+        return false;
+    }
+    let mut hash = *leaf;
+    let mut position = index as usize;
+    for sibling in proof {
+        hash = if position % 2 == 0 {
+            node(&hash, sibling)
+        } else {
+            node(sibling, &hash)
+        };
+        position /= 2;
+    }
+    hash == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(n: u8) -> Vec<[u8; 32]> {
+        (0..n).map(|i| set_leaf(i, &[i.wrapping_mul(31); 32])).collect()
+    }
+
+    #[test]
+    fn proofs_verify_for_every_chunk() {
+        for n in (1..=9).chain([MAX_SET_CHUNKS]) {
+            let leaves = leaves(n);
+            let root = set_root(&leaves);
+            for i in 0..n {
+                let proof = set_proof(&leaves, i as usize);
+                assert!(verify_set_proof(&root, n, i, &leaves[i as usize], &proof), "{i} of {n}");
+            }
+        }
+    }
+
+    #[test]
+    fn tampered_witnesses_fail() {
+        let leaves = leaves(5);
+        let root = set_root(&leaves);
+        let proof = set_proof(&leaves, 2);
+        assert!(!verify_set_proof(&root, 5, 2, &leaves[3], &proof));
+        assert!(!verify_set_proof(&root, 5, 3, &leaves[2], &proof));
+        assert!(!verify_set_proof(&root, 5, 2, &leaves[2], &proof[1..]));
+        // Padding leaves are never chunks
+        assert!(!verify_set_proof(&root, 5, 6, &[0u8; 32], &set_proof(&leaves, 6)));
+    }
+
+    #[test]
+    fn leaves_bind_their_index() {
+        assert_ne!(set_leaf(0, &[1u8; 32]), set_leaf(1, &[1u8; 32]));
+    }
+}
@@ -0,0 +1,148 @@
+// Permit-style signed intents: a party signs, off-chain, the submission
+// a relayer will land for them. The transaction carries an Ed25519
+// program instruction verifying that signature right before the program
+// instruction; the program reads it back through the instructions sysvar
+// and checks it covers the expected signer and message. The party's
+// wallet never has to be online when the transaction is sent.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    ed25519_program,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+
+use crate::ErrorCode;
+
+/// Prefix of every permit message, so a permit signature can never be
+/// mistaken for a signature over anything else.
+pub const PERMIT_DOMAIN: &[u8] = b"pcd-permit-v1";
+
+// Ed25519 program instruction layout: a u8 signature count, a padding
+// byte, then one 14-byte offsets record per signature.
+const HEADER_LEN: usize = 2;
+const OFFSETS_LEN: usize = 14;
+const PUBKEY_LEN: usize = 32;
+const SIGNATURE_LEN: usize = 64;
+/// `*_instruction_index` value meaning "this instruction's own data"
+const THIS_INSTRUCTION: u16 = u16::MAX;
+
+/// The bytes a party signs to authorize submitting the ciphertexts whose
+/// `contacts_commitment` is `ciphertext_hash` to `session_id`, valid
+/// until unix time `expiry`.
+pub fn permit_message(session_id: &[u8; 32], ciphertext_hash: &[u8; 32], expiry: i64) -> Vec<u8> {
+    [PERMIT_DOMAIN, session_id, ciphertext_hash, &expiry.to_le_bytes()].concat()
+}
+
+/// Data for an Ed25519 program instruction verifying one signature, with
+/// the public key, signature, and message all held inline.
+pub fn ed25519_instruction_data(
+    signer: &Pubkey,
+    signature: &[u8; SIGNATURE_LEN],
+    message: &[u8],
+) -> Vec<u8> {
+    let pubkey_offset = HEADER_LEN + OFFSETS_LEN;
+    let signature_offset = pubkey_offset + PUBKEY_LEN;
+    let message_offset = signature_offset + SIGNATURE_LEN;
+
+    let mut data = Vec::with_capacity(message_offset + message.len());
+    data.extend_from_slice(&[1, 0]);
+    for field in [
+        signature_offset as u16,
+        THIS_INSTRUCTION,
+        pubkey_offset as u16,
+        THIS_INSTRUCTION,
+        message_offset as u16,
+        message.len() as u16,
+        THIS_INSTRUCTION,
+    ] {
+        data.extend_from_slice(&field.to_le_bytes());
+    }
+    data.extend_from_slice(signer.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+    data
+}
+
+/// The signer and message an Ed25519 instruction verifies, if it
+/// verifies exactly one signature and holds everything inline. The
+/// Ed25519 program has already checked the signature by the time the
+/// program runs; this only reads back what it checked.
+fn verified_signature(data: &[u8]) -> Option<(Pubkey, &[u8])> {
+    if data.len() < HEADER_LEN + OFFSETS_LEN || data[0] != 1 {
+        return None;
+    }
+    let field = |i: usize| {
+        let at = HEADER_LEN + 2 * i;
+        u16::from_le_bytes([data[at], data[at + 1]])
+    };
+    // Offsets pointing into other instructions could verify bytes this
+    // program never sees
+    if field(1) != THIS_INSTRUCTION || field(3) != THIS_INSTRUCTION || field(6) != THIS_INSTRUCTION {
+        return None;
+    }
+    let pubkey_offset = field(2) as usize;
+    let message_offset = field(4) as usize;
+    let message_len = field(5) as usize;
+
+    let pubkey = data.get(pubkey_offset..pubkey_offset + PUBKEY_LEN)?;
+    let message = data.get(message_offset..message_offset + message_len)?;
+    Some((Pubkey::try_from(pubkey).ok()?, message))
+}
+
+/// Fail with `InvalidPermit` unless the instruction right before the
+/// current one is an Ed25519 program instruction verifying `signer`'s
+/// signature over exactly `message`.
+pub fn require_permit(instructions_sysvar: &AccountInfo, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    let current = load_current_index_checked(instructions_sysvar)?;
+    require!(current > 0, ErrorCode::InvalidPermit);
+    let ix = load_instruction_at_checked(current as usize - 1, instructions_sysvar)?;
+    require_keys_eq!(ix.program_id, ed25519_program::ID, ErrorCode::InvalidPermit);
+
+    let (verified_signer, verified_message) =
+        verified_signature(&ix.data).ok_or(ErrorCode::InvalidPermit)?;
+    require!(
+        verified_signer == *signer && verified_message == message,
+        ErrorCode::InvalidPermit
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instruction_data_round_trips() {
+        let signer = Pubkey::new_unique();
+        let message = permit_message(&[7u8; 32], &[9u8; 32], 1_700_000_000);
+        let data = ed25519_instruction_data(&signer, &[3u8; 64], &message);
+        assert_eq!(verified_signature(&data), Some((signer, message.as_slice())));
+    }
+
+    #[test]
+    fn permit_message_binds_every_field() {
+        let base = permit_message(&[1u8; 32], &[2u8; 32], 100);
+        assert!(base.starts_with(PERMIT_DOMAIN));
+        assert_ne!(base, permit_message(&[0u8; 32], &[2u8; 32], 100));
+        assert_ne!(base, permit_message(&[1u8; 32], &[0u8; 32], 100));
+        assert_ne!(base, permit_message(&[1u8; 32], &[2u8; 32], 101));
+    }
+
+    #[test]
+    fn offsets_into_other_instructions_are_refused() {
+        let signer = Pubkey::new_unique();
+        let mut data = ed25519_instruction_data(&signer, &[3u8; 64], b"hello");
+        // message_instruction_index -> instruction 0
+        data[HEADER_LEN + 12..HEADER_LEN + 14].copy_from_slice(&0u16.to_le_bytes());
+        assert_eq!(verified_signature(&data), None);
+    }
+
+    #[test]
+    fn only_single_signature_instructions_are_read() {
+        let signer = Pubkey::new_unique();
+        let mut data = ed25519_instruction_data(&signer, &[3u8; 64], b"hello");
+        data[0] = 2;
+        assert_eq!(verified_signature(&data), None);
+        assert_eq!(verified_signature(&data[..HEADER_LEN]), None);
+    }
+}
@@ -0,0 +1,150 @@
+// Soulbound connection tokens on Token-2022.
+//
+// `attest_connection` gives each wallet of an attested pair one token of
+// a mint of its own. The mint carries Token-2022's NonTransferable
+// extension, so the token cannot leave the wallet's associated token
+// account (its holder can still burn it), and its mint authority is
+// revoked once both tokens exist, so the supply stays at two. Wallets
+// and explorers show it like any other token.
+//
+// The instructions are encoded here by hand, as the gatekeeper module
+// reads Civic's accounts, rather than through the SPL crates: only four
+// of them are needed, each a tag and a few bytes.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::solana_program::pubkey;
+
+/// The Token-2022 program.
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// The associated token account program.
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey = pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// A Token-2022 mint with the NonTransferable extension: the mint padded
+/// to an account's 165 bytes, the account type byte, and the
+/// extension's 4-byte header (it has no data).
+pub const MINT_SIZE: usize = 165 + 1 + 4;
+
+/// Token-2022 instruction tags.
+const SET_AUTHORITY: u8 = 6;
+const MINT_TO: u8 = 7;
+const INITIALIZE_MINT_2: u8 = 20;
+const INITIALIZE_NON_TRANSFERABLE_MINT: u8 = 32;
+
+/// `AuthorityType::MintTokens`
+const AUTHORITY_MINT_TOKENS: u8 = 0;
+
+/// `AssociatedTokenAccountInstruction::CreateIdempotent`
+const CREATE_IDEMPOTENT: u8 = 1;
+
+/// `wallet`'s associated token account for the Token-2022 `mint`.
+pub fn associated_token_address(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), TOKEN_2022_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Accounts `mint_soulbound` works with.
+pub struct SoulboundAccounts<'a, 'info> {
+    /// Funds the holders' token accounts; signs
+    pub payer: &'a AccountInfo<'info>,
+    /// Allocated with `MINT_SIZE` bytes and owned by Token-2022, not yet
+    /// initialized
+    pub mint: &'a AccountInfo<'info>,
+    /// This program's PDA that mints, signing with `authority_seeds`
+    pub authority: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+    pub token_program: &'a AccountInfo<'info>,
+    pub associated_token_program: &'a AccountInfo<'info>,
+}
+
+/// Initialize `mint` as a non-transferable mint with no decimals, give
+/// each holder, a wallet and its associated token account, one token,
+/// and revoke the mint authority.
+pub fn mint_soulbound<'info>(
+    accounts: &SoulboundAccounts<'_, 'info>,
+    authority_seeds: &[&[u8]],
+    holders: &[(&AccountInfo<'info>, &AccountInfo<'info>)],
+) -> Result<()> {
+    let mint = accounts.mint.key();
+    let authority = accounts.authority.key();
+    require_keys_eq!(accounts.token_program.key(), TOKEN_2022_PROGRAM_ID);
+    require_keys_eq!(accounts.associated_token_program.key(), ASSOCIATED_TOKEN_PROGRAM_ID);
+
+    invoke(
+        &token_instruction(vec![AccountMeta::new(mint, false)], vec![INITIALIZE_NON_TRANSFERABLE_MINT]),
+        &[accounts.mint.clone()],
+    )?;
+    // Decimals 0, the mint authority, and no freeze authority
+    let mut data = vec![INITIALIZE_MINT_2, 0];
+    data.extend_from_slice(authority.as_ref());
+    data.push(0);
+    invoke(
+        &token_instruction(vec![AccountMeta::new(mint, false)], data),
+        &[accounts.mint.clone()],
+    )?;
+
+    for (wallet, token_account) in holders {
+        let create = Instruction {
+            program_id: ASSOCIATED_TOKEN_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(accounts.payer.key(), true),
+                AccountMeta::new(token_account.key(), false),
+                AccountMeta::new_readonly(wallet.key(), false),
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new_readonly(accounts.system_program.key(), false),
+                AccountMeta::new_readonly(TOKEN_2022_PROGRAM_ID, false),
+            ],
+            data: vec![CREATE_IDEMPOTENT],
+        };
+        invoke(
+            &create,
+            &[
+                accounts.payer.clone(),
+                (*token_account).clone(),
+                (*wallet).clone(),
+                accounts.mint.clone(),
+                accounts.system_program.clone(),
+                accounts.token_program.clone(),
+            ],
+        )?;
+
+        let mut data = vec![MINT_TO];
+        data.extend_from_slice(&1u64.to_le_bytes());
+        invoke_signed(
+            &token_instruction(
+                vec![
+                    AccountMeta::new(mint, false),
+                    AccountMeta::new(token_account.key(), false),
+                    AccountMeta::new_readonly(authority, true),
+                ],
+                data,
+            ),
+            &[accounts.mint.clone(), (*token_account).clone(), accounts.authority.clone()],
+            &[authority_seeds],
+        )?;
+    }
+
+    // No new authority
+    invoke_signed(
+        &token_instruction(
+            vec![AccountMeta::new(mint, false), AccountMeta::new_readonly(authority, true)],
+            vec![SET_AUTHORITY, AUTHORITY_MINT_TOKENS, 0],
+        ),
+        &[accounts.mint.clone(), accounts.authority.clone()],
+        &[authority_seeds],
+    )?;
+    Ok(())
+}
+
+fn token_instruction(accounts: Vec<AccountMeta>, data: Vec<u8>) -> Instruction {
+    Instruction {
+        program_id: TOKEN_2022_PROGRAM_ID,
+        accounts,
+        data,
+    }
+}
@@ -0,0 +1,608 @@
+// Session lifecycle: the statuses a DiscoverySession moves through and
+// the transitions allowed between them. Every status change goes through
+// `DiscoverySession::transition`, so an instruction or callback that would
+// move a session somewhere it cannot go fails instead of corrupting it.
+//
+// Each computation is also recorded per stage (offset when queued, slot
+// when its callback lands), so a stuck session shows which computation
+// it is waiting on. A stage's callback is handled at most once per
+// queue: a duplicated or re-sent callback transaction finds the stage
+// consumed and returns without touching the session or emitting events.
+// A computation the MPC fails to complete consumes its stage too, and
+// leaves the session `Failed` with the stage's fee open for a refund.
+//
+// Queueing also checks the session's MXE state is in the layout the
+// circuits read (`STATE_VERSION`); state left in an older layout by an
+// upgrade must go through `migrate_session_state` first.
+
+use anchor_lang::prelude::*;
+
+use crate::{DiscoverySession, ErrorCode, STATE_VERSION, SWEEP_DELAY_SLOTS};
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    AwaitingAlice = 0,
+    AwaitingBob = 1,
+    Computing = 2,
+    Matched = 3,
+    /// Friends-of-friends only: both lists are in, waiting for the connector
+    AwaitingConnector = 4,
+    /// The stored MXE state is zeroed (or being zeroed); terminal
+    Purged = 5,
+    /// A computation the MPC could not complete ended the session; it can
+    /// only be purged
+    Failed = 6,
+}
+
+impl Status {
+    pub const ALL: [Status; 7] = [
+        Status::AwaitingAlice,
+        Status::AwaitingBob,
+        Status::Computing,
+        Status::Matched,
+        Status::AwaitingConnector,
+        Status::Purged,
+        Status::Failed,
+    ];
+
+    /// Whether a session may move from `self` to `next`. Staying put is
+    /// never a transition.
+    pub fn can_transition(self, next: Status) -> bool {
+        use Status::*;
+        matches!(
+            (self, next),
+            // Alice's list is in
+            (AwaitingAlice, AwaitingBob)
+                // Bob's list is in and the match (or score, or follow check) is queued
+                | (AwaitingBob, Computing)
+                // Friends-of-friends: Bob's list is in, the connector is next
+                | (AwaitingBob, AwaitingConnector)
+                | (AwaitingConnector, Computing)
+                // The MPC finished
+                | (Computing, Matched)
+                // The MPC rejected Alice's, Bob's, or the connector's list
+                | (AwaitingBob, AwaitingAlice)
+                | (Computing, AwaitingBob)
+                | (AwaitingConnector, AwaitingBob)
+                | (Computing, AwaitingConnector)
+                // Subscription delta round
+                | (Matched, Computing)
+                // A finished session, or one Bob never joined, is wiped
+                | (Matched, Purged)
+                | (AwaitingAlice, Purged)
+                | (AwaitingBob, Purged)
+                // Any outstanding computation can fail
+                | (AwaitingAlice, Failed)
+                | (AwaitingBob, Failed)
+                | (AwaitingConnector, Failed)
+                | (Computing, Failed)
+                | (Matched, Failed)
+                // A failed session is wiped like any other
+                | (Failed, Purged)
+        )
+    }
+
+    /// `next` if the move is legal, `InvalidTransition` otherwise.
+    pub fn try_transition(self, next: Status) -> Result<Status> {
+        require!(self.can_transition(next), ErrorCode::InvalidTransition);
+        Ok(next)
+    }
+
+    /// Name used in `SessionReport` and by the CLI.
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::AwaitingAlice => "awaiting_alice",
+            Status::AwaitingBob => "awaiting_bob",
+            Status::Computing => "computing",
+            Status::Matched => "matched",
+            Status::AwaitingConnector => "awaiting_connector",
+            Status::Purged => "purged",
+            Status::Failed => "failed",
+        }
+    }
+}
+
+/// Computation stages whose offsets and finalize slots a session keeps.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComputationStage {
+    /// `create_session`
+    Init = 0,
+    /// `submit_contacts_alice`
+    Alice = 1,
+    /// Bob's, the connector's, or a delta submission: whatever computes
+    /// the session's result
+    Match = 2,
+    /// Any reveal of a stored result
+    Reveal = 3,
+}
+
+impl ComputationStage {
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+
+    /// Name used in `SessionReport`.
+    pub fn name(self) -> &'static str {
+        match self {
+            ComputationStage::Init => "init",
+            ComputationStage::Alice => "alice",
+            ComputationStage::Match => "match",
+            ComputationStage::Reveal => "reveal",
+        }
+    }
+}
+
+impl TryFrom<u8> for Status {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        Status::ALL
+            .into_iter()
+            .find(|status| *status as u8 == value)
+            .ok_or_else(|| ErrorCode::InvalidSessionState.into())
+    }
+}
+
+impl From<Status> for u8 {
+    fn from(status: Status) -> u8 {
+        status as u8
+    }
+}
+
+impl DiscoverySession {
+    /// The session's status, `InvalidSessionState` if the byte is unknown.
+    pub fn status(&self) -> Result<Status> {
+        Status::try_from(self.status)
+    }
+
+    /// Fail with `InvalidSessionState` unless the session is in `expected`.
+    pub fn require_status(&self, expected: Status) -> Result<()> {
+        let status = self.status()?;
+        trace!(
+            "require_status session={} status={} expected={}",
+            Pubkey::new_from_array(self.session_id),
+            status.name(),
+            expected.name()
+        );
+        require!(status == expected, ErrorCode::InvalidSessionState);
+        Ok(())
+    }
+
+    /// Move the session to `next` if that is a legal step from its
+    /// current status.
+    pub fn transition(&mut self, next: Status) -> Result<()> {
+        let status = self.status()?;
+        trace!(
+            "transition session={} from={} to={}",
+            Pubkey::new_from_array(self.session_id),
+            status.name(),
+            next.name()
+        );
+        self.status = status.try_transition(next)?.into();
+        Ok(())
+    }
+
+    /// Whether the join deadline is set and `now` is past it.
+    pub fn join_expired(&self, now: i64) -> bool {
+        self.join_deadline != 0 && now > self.join_deadline
+    }
+
+    /// Fail with `DeadlineExpired` if Bob can no longer join at `now`.
+    pub fn require_joinable(&self, now: i64) -> Result<()> {
+        require!(!self.join_expired(now), ErrorCode::DeadlineExpired);
+        Ok(())
+    }
+
+    /// The tag the session's MXE state is bound to: its id folded the
+    /// way `fold_pubkey` folds a pubkey. Every circuit reading the state
+    /// is passed it and refuses a state bound to another session.
+    pub fn state_tag(&self) -> u128 {
+        crate::fold_pubkey(&Pubkey::new_from_array(self.session_id))
+    }
+
+    /// Contacts per list the session accepts. Sessions created before
+    /// capacities existed hold 0 and take full lists.
+    pub fn capacity(&self) -> u8 {
+        if self.capacity == 0 {
+            schema::MAX_CONTACTS as u8
+        } else {
+            self.capacity
+        }
+    }
+
+    /// Whether the session's results are still escrowed at `slot`.
+    pub fn reveal_locked(&self, slot: u64) -> bool {
+        slot < self.reveal_after_slot
+    }
+
+    /// Fail with `RevealLocked` while results are escrowed at `slot`.
+    pub fn require_revealable(&self, slot: u64) -> Result<()> {
+        require!(!self.reveal_locked(slot), ErrorCode::RevealLocked);
+        Ok(())
+    }
+
+    /// `SessionState` layout the session's MXE state is in. Sessions
+    /// created before the tag hold 0, and their state is in layout 1.
+    pub fn state_version(&self) -> u8 {
+        self.state_version.max(1)
+    }
+
+    /// Fail with `StateMigrationRequired` unless the session's state is
+    /// in the layout the circuits read.
+    pub fn require_current_state(&self) -> Result<()> {
+        require!(self.state_version() == STATE_VERSION, ErrorCode::StateMigrationRequired);
+        Ok(())
+    }
+
+    /// Record that `stage` queued the computation at `computation_offset`.
+    /// Its finalize slot and consumed bit reset until the callback lands.
+    /// Fails with `StateMigrationRequired` if the session's state is in
+    /// an older layout, so it is never fed to the current circuits.
+    pub fn record_queued(
+        &mut self,
+        stage: ComputationStage,
+        computation_offset: u64,
+    ) -> Result<()> {
+        self.require_current_state()?;
+        trace!(
+            "record_queued session={} stage={} computation_offset={}",
+            Pubkey::new_from_array(self.session_id),
+            stage.name(),
+            computation_offset
+        );
+        self.stage_offsets[stage as usize] = computation_offset;
+        self.finalized_slots[stage as usize] = 0;
+        self.callback_consumed &= !stage.bit();
+        Ok(())
+    }
+
+    /// Record that `stage`'s callback ran at `slot`, accepted or not, and
+    /// mark it consumed. Returns `false`, changing nothing, if the stage's
+    /// callback was already handled since it was last queued; the caller
+    /// must then return without acting on the output.
+    pub fn consume_callback(&mut self, stage: ComputationStage, slot: u64) -> bool {
+        if self.callback_consumed & stage.bit() != 0 {
+            trace!(
+                "callback_ignored session={} stage={} reason=already_consumed",
+                Pubkey::new_from_array(self.session_id),
+                stage.name()
+            );
+            return false;
+        }
+        self.callback_consumed |= stage.bit();
+        self.finalized_slots[stage as usize] = slot;
+        true
+    }
+
+    /// Record that `stage`'s computation failed at `slot`: consume the
+    /// stage, move the session to `Failed`, and hold the fee charged for
+    /// it until `claim_refund`. Returns `false`, changing nothing, if the
+    /// stage's callback was already handled.
+    pub fn fail_stage(&mut self, stage: ComputationStage, slot: u64) -> Result<bool> {
+        if self.callback_consumed & stage.bit() != 0 {
+            return Ok(false);
+        }
+        self.transition(Status::Failed)?;
+        self.consume_callback(stage, slot);
+        self.refundable_fee = self.stage_fees[stage as usize];
+        Ok(true)
+    }
+
+    /// Whether anyone may close the session with `sweep_sessions` at
+    /// `slot` and unix time `now`. `stores_state` is whether its MXE
+    /// state was ever initialized; such a session is swept once its
+    /// purge has landed. One without state is swept once it failed with
+    /// nothing left to refund, or when Alice never submitted and the
+    /// join deadline passed. A purged or failed session waits
+    /// `SWEEP_DELAY_SLOTS`, so Alice can close it first for the full rent.
+    pub fn sweepable(&self, stores_state: bool, slot: u64, now: i64) -> Result<bool> {
+        let status = self.status()?;
+        if stores_state {
+            return Ok(status == Status::Purged
+                && self.purged_slot != 0
+                && slot >= self.purged_slot.saturating_add(SWEEP_DELAY_SLOTS));
+        }
+        if self.pending_stage().is_some() || self.refundable_fee != 0 {
+            return Ok(false);
+        }
+        Ok(match status {
+            Status::Failed => {
+                let failed_slot = self.finalized_slots.iter().copied().max().unwrap_or(0);
+                slot >= failed_slot.saturating_add(SWEEP_DELAY_SLOTS)
+            }
+            Status::AwaitingAlice => self.join_expired(now),
+            _ => false,
+        })
+    }
+
+    /// The stage whose computation is queued but not finalized, if any.
+    pub fn pending_stage(&self) -> Option<ComputationStage> {
+        use ComputationStage::*;
+        [Init, Alice, Match, Reveal].into_iter().find(|stage| {
+            self.stage_offsets[*stage as usize] != 0 && self.finalized_slots[*stage as usize] == 0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Status::*;
+
+    const LEGAL: [(Status, Status); 19] = [
+        (AwaitingAlice, AwaitingBob),
+        (AwaitingBob, AwaitingAlice),
+        (AwaitingBob, Computing),
+        (AwaitingBob, AwaitingConnector),
+        (AwaitingConnector, Computing),
+        (AwaitingConnector, AwaitingBob),
+        (Computing, Matched),
+        (Computing, AwaitingBob),
+        (Computing, AwaitingConnector),
+        (Matched, Computing),
+        (Matched, Purged),
+        (AwaitingAlice, Purged),
+        (AwaitingBob, Purged),
+        (AwaitingAlice, Failed),
+        (AwaitingBob, Failed),
+        (AwaitingConnector, Failed),
+        (Computing, Failed),
+        (Matched, Failed),
+        (Failed, Purged),
+    ];
+
+    fn session(status: Status) -> DiscoverySession {
+        DiscoverySession {
+            status: status.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn every_pair_is_legal_only_if_listed() {
+        for from in Status::ALL {
+            for to in Status::ALL {
+                let legal = LEGAL.contains(&(from, to));
+                assert_eq!(from.can_transition(to), legal, "{from:?} -> {to:?}");
+                assert_eq!(from.try_transition(to).is_ok(), legal, "{from:?} -> {to:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn illegal_transitions_report_invalid_transition() {
+        let err = Matched.try_transition(AwaitingAlice).unwrap_err();
+        assert_eq!(err, ErrorCode::InvalidTransition.into());
+    }
+
+    #[test]
+    fn no_status_transitions_to_itself() {
+        for status in Status::ALL {
+            assert!(!status.can_transition(status), "{status:?}");
+        }
+    }
+
+    #[test]
+    fn only_a_rejected_alice_returns_to_awaiting_alice() {
+        for status in Status::ALL {
+            assert_eq!(status.can_transition(AwaitingAlice), status == AwaitingBob, "{status:?}");
+        }
+    }
+
+    #[test]
+    fn status_bytes_round_trip() {
+        for status in Status::ALL {
+            assert_eq!(Status::try_from(u8::from(status)).unwrap(), status);
+        }
+        for byte in 7..=u8::MAX {
+            assert!(Status::try_from(byte).is_err(), "{byte}");
+        }
+    }
+
+    #[test]
+    fn status_names_are_distinct() {
+        for a in Status::ALL {
+            for b in Status::ALL {
+                assert_eq!(a.name() == b.name(), a == b, "{a:?} {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn session_transition_updates_the_status_byte() {
+        let mut s = session(AwaitingBob);
+        s.transition(Computing).unwrap();
+        assert_eq!(s.status, Computing as u8);
+        s.transition(Matched).unwrap();
+        assert_eq!(s.status().unwrap(), Matched);
+    }
+
+    #[test]
+    fn failed_session_transition_leaves_the_status_alone() {
+        let mut s = session(Matched);
+        assert!(s.transition(AwaitingBob).is_err());
+        assert_eq!(s.status, Matched as u8);
+    }
+
+    #[test]
+    fn purged_is_terminal() {
+        for status in Status::ALL {
+            assert!(!Purged.can_transition(status), "{status:?}");
+        }
+    }
+
+    #[test]
+    fn stage_accounting_tracks_the_outstanding_computation() {
+        let mut s = session(AwaitingAlice);
+        assert_eq!(s.pending_stage(), None);
+        s.record_queued(ComputationStage::Init, 7).unwrap();
+        assert_eq!(s.pending_stage(), Some(ComputationStage::Init));
+        s.consume_callback(ComputationStage::Init, 100);
+        s.record_queued(ComputationStage::Alice, 8).unwrap();
+        assert_eq!(s.pending_stage(), Some(ComputationStage::Alice));
+        s.consume_callback(ComputationStage::Alice, 120);
+        assert_eq!(s.pending_stage(), None);
+        assert_eq!(s.stage_offsets, [7, 8, 0, 0]);
+        assert_eq!(s.finalized_slots, [100, 120, 0, 0]);
+    }
+
+    #[test]
+    fn requeueing_a_stage_clears_its_finalize_slot() {
+        let mut s = session(Matched);
+        s.record_queued(ComputationStage::Reveal, 1).unwrap();
+        s.consume_callback(ComputationStage::Reveal, 50);
+        s.record_queued(ComputationStage::Reveal, 2).unwrap();
+        assert_eq!(s.stage_offsets[ComputationStage::Reveal as usize], 2);
+        assert_eq!(s.pending_stage(), Some(ComputationStage::Reveal));
+    }
+
+    #[test]
+    fn a_stage_callback_is_consumed_once_per_queue() {
+        let mut s = session(Computing);
+        s.record_queued(ComputationStage::Match, 3).unwrap();
+        assert!(s.consume_callback(ComputationStage::Match, 40));
+        assert!(!s.consume_callback(ComputationStage::Match, 41));
+        assert_eq!(s.finalized_slots[ComputationStage::Match as usize], 40);
+
+        // Other stages are independent
+        s.record_queued(ComputationStage::Reveal, 4).unwrap();
+        assert!(s.consume_callback(ComputationStage::Reveal, 42));
+
+        s.record_queued(ComputationStage::Match, 5).unwrap();
+        assert!(s.consume_callback(ComputationStage::Match, 43));
+        assert_eq!(s.callback_consumed, 0b1100);
+    }
+
+    #[test]
+    fn a_failed_stage_holds_its_fee_for_refund() {
+        let mut s = session(Computing);
+        s.record_queued(ComputationStage::Match, 6).unwrap();
+        s.stage_fees[ComputationStage::Match as usize] = 5_000;
+        assert!(s.fail_stage(ComputationStage::Match, 44).unwrap());
+        assert_eq!(s.status().unwrap(), Failed);
+        assert_eq!(s.refundable_fee, 5_000);
+        assert_eq!(s.pending_stage(), None);
+
+        // A re-sent failure, or a late success, finds the stage consumed
+        assert!(!s.fail_stage(ComputationStage::Match, 45).unwrap());
+        assert!(!s.consume_callback(ComputationStage::Match, 45));
+        assert_eq!(s.finalized_slots[ComputationStage::Match as usize], 44);
+    }
+
+    #[test]
+    fn a_failure_on_a_purged_session_changes_nothing() {
+        let mut s = session(Purged);
+        s.record_queued(ComputationStage::Reveal, 9).unwrap();
+        s.stage_fees[ComputationStage::Reveal as usize] = 5_000;
+        assert!(s.fail_stage(ComputationStage::Reveal, 50).is_err());
+        assert_eq!(s.refundable_fee, 0);
+        assert_eq!(s.pending_stage(), Some(ComputationStage::Reveal));
+    }
+
+    #[test]
+    fn join_deadline_is_inclusive_and_zero_means_none() {
+        let mut s = session(AwaitingBob);
+        assert!(s.require_joinable(i64::MAX).is_ok());
+        assert!(!s.join_expired(i64::MAX));
+
+        s.join_deadline = 1_000;
+        assert!(s.require_joinable(999).is_ok());
+        assert!(s.require_joinable(1_000).is_ok());
+        assert!(!s.join_expired(1_000));
+        assert!(s.join_expired(1_001));
+        assert_eq!(s.require_joinable(1_001).unwrap_err(), ErrorCode::DeadlineExpired.into());
+    }
+
+    #[test]
+    fn reveal_lock_opens_at_its_slot_and_zero_means_none() {
+        let mut s = session(Matched);
+        assert!(s.require_revealable(0).is_ok());
+
+        s.reveal_after_slot = 500;
+        assert!(s.reveal_locked(499));
+        assert_eq!(s.require_revealable(499).unwrap_err(), ErrorCode::RevealLocked.into());
+        assert!(!s.reveal_locked(500));
+        assert!(s.require_revealable(500).is_ok());
+    }
+
+    #[test]
+    fn capacity_zero_means_full_lists() {
+        let mut s = session(AwaitingAlice);
+        assert_eq!(s.capacity(), 32);
+        s.capacity = 8;
+        assert_eq!(s.capacity(), 8);
+    }
+
+    #[test]
+    fn state_tags_differ_between_sessions() {
+        let mut a = session(AwaitingAlice);
+        let mut b = session(AwaitingAlice);
+        a.session_id = [1u8; 32];
+        b.session_id = [1u8; 32];
+        b.session_id[31] = 2;
+        assert_ne!(a.state_tag(), b.state_tag());
+        assert_eq!(a.state_tag(), crate::fold_pubkey(&Pubkey::new_from_array(a.session_id)));
+    }
+
+    #[test]
+    fn require_status_checks_the_current_status() {
+        let s = session(AwaitingConnector);
+        assert!(s.require_status(AwaitingConnector).is_ok());
+        assert!(s.require_status(AwaitingBob).is_err());
+        let corrupt = DiscoverySession {
+            status: 9,
+            ..Default::default()
+        };
+        assert!(corrupt.require_status(AwaitingAlice).is_err());
+    }
+
+    #[test]
+    fn purged_sessions_sweep_after_the_delay() {
+        let mut s = session(Purged);
+        assert!(!s.sweepable(true, u64::MAX, 0).unwrap());
+
+        s.purged_slot = 100;
+        assert!(!s.sweepable(true, 100 + SWEEP_DELAY_SLOTS - 1, 0).unwrap());
+        assert!(s.sweepable(true, 100 + SWEEP_DELAY_SLOTS, 0).unwrap());
+        for status in [AwaitingBob, Matched, Failed] {
+            assert!(!session(status).sweepable(true, u64::MAX, i64::MAX).unwrap());
+        }
+    }
+
+    #[test]
+    fn stateless_sessions_sweep_once_failed_and_refunded_or_expired() {
+        let mut s = session(Computing);
+        s.record_queued(ComputationStage::Init, 3).unwrap();
+        s.stage_fees[ComputationStage::Init as usize] = 5_000;
+        s.fail_stage(ComputationStage::Init, 40).unwrap();
+        assert!(!s.sweepable(false, u64::MAX, 0).unwrap());
+        s.refundable_fee = 0;
+        assert!(!s.sweepable(false, 40 + SWEEP_DELAY_SLOTS - 1, 0).unwrap());
+        assert!(s.sweepable(false, 40 + SWEEP_DELAY_SLOTS, 0).unwrap());
+
+        let mut s = session(AwaitingAlice);
+        assert!(!s.sweepable(false, u64::MAX, i64::MAX).unwrap());
+        s.join_deadline = 1_000;
+        assert!(!s.sweepable(false, 0, 1_000).unwrap());
+        assert!(s.sweepable(false, 0, 1_001).unwrap());
+        s.record_queued(ComputationStage::Alice, 8).unwrap();
+        assert!(!s.sweepable(false, 0, 1_001).unwrap());
+    }
+
+    #[test]
+    fn queueing_requires_the_current_state_layout() {
+        let mut s = session(Matched);
+        assert_eq!(s.state_version(), 1);
+        assert!(s.record_queued(ComputationStage::Reveal, 1).is_ok());
+
+        s.state_version = STATE_VERSION + 1;
+        assert!(s.require_current_state().is_err());
+        assert!(s.record_queued(ComputationStage::Reveal, 2).is_err());
+        assert_eq!(s.stage_offsets[ComputationStage::Reveal as usize], 1);
+
+        s.state_version = STATE_VERSION;
+        assert!(s.record_queued(ComputationStage::Reveal, 2).is_ok());
+    }
+}
@@ -0,0 +1,104 @@
+// In-process Arcium stand-ins for unit tests, behind the `testing`
+// feature.
+//
+// The program's instructions queue MPC computations and its callbacks
+// verify cluster-signed outputs, so exercising either normally takes a
+// live cluster. With `testing`, the program runs under plain
+// `solana-program-test` (with `processor!`, so it shares the test's
+// process):
+//
+//   queue_computation   records the computation (offset, arguments,
+//                       callback instructions) instead of CPI-ing into
+//                       Arcium; `take_queued` returns what was recorded
+//   verify_output       skips the signature check and returns the next
+//                       output the test queued with `push_output`, or a
+//                       failure queued with `push_failure`
+//   callback guard      lets a test call a callback directly
+//
+// The session state machine and account constraints then run as
+// deployed: a test sends an instruction, checks the queued arguments,
+// pushes the output it wants the cluster to have produced, and sends
+// the callback.
+//
+// Never deploy a `testing` build: anyone could call a callback with any
+// output.
+
+use std::{collections::VecDeque, sync::Mutex};
+
+use anchor_lang::prelude::*;
+use arcium_anchor::prelude::{ArgumentList, CallbackInstruction, SignedComputationOutputs};
+
+/// A computation the program queued.
+pub struct QueuedComputation {
+    pub computation_offset: u64,
+    pub args: ArgumentList,
+    pub callback_instructions: Vec<CallbackInstruction>,
+    pub num_callback_txs: u8,
+    pub cu_price_micro: u64,
+}
+
+static QUEUED: Mutex<Vec<QueuedComputation>> = Mutex::new(Vec::new());
+
+/// Outputs for the next callbacks, in order: serialized, or `None` for
+/// a computation that failed.
+static OUTPUTS: Mutex<VecDeque<Option<Vec<u8>>>> = Mutex::new(VecDeque::new());
+
+/// Stand-in for `arcium_anchor::queue_computation`.
+pub fn queue_computation<T>(
+    _accounts: &mut T,
+    computation_offset: u64,
+    args: ArgumentList,
+    callback_instructions: Vec<CallbackInstruction>,
+    num_callback_txs: u8,
+    cu_price_micro: u64,
+) -> Result<()> {
+    QUEUED.lock().unwrap().push(QueuedComputation {
+        computation_offset,
+        args,
+        callback_instructions,
+        num_callback_txs,
+        cu_price_micro,
+    });
+    Ok(())
+}
+
+/// Stand-in for `SignedComputationOutputs::verify_output`. Panics if the
+/// test queued no output, or one that does not decode as `O`.
+pub fn verify_output<O: AnchorDeserialize, C, A>(
+    _output: &SignedComputationOutputs<O>,
+    _cluster_account: &C,
+    _computation_account: &A,
+) -> Result<O> {
+    let next = OUTPUTS
+        .lock()
+        .unwrap()
+        .pop_front()
+        .expect("callback ran with no output queued: call testing::push_output first");
+    match next {
+        Some(bytes) => Ok(O::deserialize(&mut bytes.as_slice()).expect("queued output does not match the callback")),
+        None => Err(crate::ErrorCode::ComputationFailed.into()),
+    }
+}
+
+/// Every computation queued since the last call, oldest first.
+pub fn take_queued() -> Vec<QueuedComputation> {
+    std::mem::take(&mut *QUEUED.lock().unwrap())
+}
+
+/// Queue `output` for the next callback to receive as verified.
+pub fn push_output<O: AnchorSerialize>(output: &O) {
+    let mut bytes = Vec::new();
+    output.serialize(&mut bytes).unwrap();
+    OUTPUTS.lock().unwrap().push_back(Some(bytes));
+}
+
+/// Make the next callback's computation fail verification.
+pub fn push_failure() {
+    OUTPUTS.lock().unwrap().push_back(None);
+}
+
+/// Forget everything recorded or queued, between tests.
+pub fn reset() {
+    QUEUED.lock().unwrap().clear();
+    OUTPUTS.lock().unwrap().clear();
+}
@@ -0,0 +1,279 @@
+// Shared helpers for the localnet end-to-end harness.
+//
+// Mirrors the frontend client path (frontend/src/utils/hash.ts and
+// arcium.ts): contacts are normalized, hashed to u128 in a namespace
+// domain, encrypted with
+// a Rescue cipher keyed by an x25519 exchange with the MXE, and sent
+// to the program with every Arcium account the macros expect.
+
+use std::{thread, time::Duration, time::Instant};
+
+use anchor_client::{
+    solana_client::{rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig},
+    solana_sdk::{
+        commitment_config::CommitmentConfig, instruction::Instruction, pubkey::Pubkey, signature::Signature,
+        transaction::Transaction,
+    },
+};
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator, Event};
+use arcium_anchor::prelude::{comp_def_offset, MXEAccount};
+use arcium_client::{encryption::RescueCipher, pda};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+pub const MAX_CONTACTS: usize = 32;
+
+/// Cluster offset used by `arcium localnet` unless overridden.
+pub fn cluster_offset() -> u32 {
+    std::env::var("ARCIUM_CLUSTER_OFFSET")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0)
+}
+
+pub fn rpc_url() -> String {
+    std::env::var("ARCIUM_LOCALNET_URL").unwrap_or_else(|_| "http://127.0.0.1:8899".into())
+}
+
+// ============================================================
+// HASHING (mirror of frontend/src/utils/hash.ts)
+// ============================================================
+
+pub fn normalize_contact(contact: &str) -> String {
+    let mut normalized = contact.trim().to_lowercase();
+    let phone_like = !normalized.is_empty()
+        && normalized
+            .chars()
+            .all(|c| c.is_ascii_digit() || " -+()".contains(c));
+    if phone_like {
+        normalized.retain(|c| c.is_ascii_digit() || c == '+');
+        if !normalized.starts_with('+') {
+            normalized.insert_str(0, "+1");
+        }
+    }
+    normalized
+}
+
+/// Domain tag for a `Namespace` value.
+pub fn namespace_tag(namespace: u8) -> &'static str {
+    match namespace {
+        0 => "phone",
+        1 => "email",
+        2 => "username",
+        3 => "wallet",
+        4 => "solname",
+        other => panic!("unknown namespace {other}"),
+    }
+}
+
+/// Upper 128 bits of SHA-256(namespace_tag || ":" || normalize(contact)), big-endian.
+pub fn hash_contact(namespace: u8, contact: &str) -> u128 {
+    let preimage = format!("{}:{}", namespace_tag(namespace), normalize_contact(contact));
+    let digest = Sha256::digest(preimage.as_bytes());
+    u128::from_be_bytes(digest[..16].try_into().unwrap())
+}
+
+/// Hash, sort, deduplicate, and zero-pad a contact list.
+pub fn hash_contact_list(namespace: u8, contacts: &[&str]) -> ([u128; MAX_CONTACTS], u32) {
+    let mut sorted: Vec<u128> = contacts.iter().map(|c| hash_contact(namespace, c)).collect();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    let mut hashes = [0u128; MAX_CONTACTS];
+    hashes[..sorted.len()].copy_from_slice(&sorted);
+    (hashes, sorted.len() as u32)
+}
+
+// ============================================================
+// ENCRYPTION
+// ============================================================
+
+/// One party's x25519 keypair plus the Rescue cipher shared with the MXE.
+pub struct PartyCipher {
+    pub public_key: [u8; 32],
+    cipher: RescueCipher,
+}
+
+impl PartyCipher {
+    pub fn new(mxe_public_key: [u8; 32]) -> Self {
+        let secret = StaticSecret::random_from_rng(rand::thread_rng());
+        let public_key = PublicKey::from(&secret).to_bytes();
+        let shared = secret.diffie_hellman(&PublicKey::from(mxe_public_key));
+        Self {
+            public_key,
+            cipher: RescueCipher::new(shared.as_bytes()),
+        }
+    }
+
+    /// Encrypt a ContactList (32 hashes + count + namespace) under one nonce.
+    pub fn encrypt_contacts(
+        &self,
+        hashes: &[u128; MAX_CONTACTS],
+        count: u32,
+        namespace: u8,
+        nonce: &[u8; 16],
+    ) -> ([[u8; 32]; MAX_CONTACTS], [u8; 32], [u8; 32]) {
+        let mut plaintext = hashes.to_vec();
+        plaintext.push(count as u128);
+        plaintext.push(namespace as u128);
+        let ciphertexts = self.cipher.encrypt(&plaintext, nonce);
+
+        let mut encrypted_hashes = [[0u8; 32]; MAX_CONTACTS];
+        encrypted_hashes.copy_from_slice(&ciphertexts[..MAX_CONTACTS]);
+        (encrypted_hashes, ciphertexts[MAX_CONTACTS], ciphertexts[MAX_CONTACTS + 1])
+    }
+
+    /// Decrypt a MatchResult emitted by a callback event. The result
+    /// names matched slots (a byte each, slot plus one) of the list the
+    /// recipient submitted; `list` maps them back to hashes.
+    pub fn decrypt_matches(
+        &self,
+        encrypted_matches: &[[u8; 32]; 2],
+        encrypted_count: &[u8; 32],
+        nonce: &[u8; 16],
+        list: &[u128; MAX_CONTACTS],
+    ) -> (Vec<u128>, u32) {
+        let mut ciphertexts = encrypted_matches.to_vec();
+        ciphertexts.push(*encrypted_count);
+        let plaintext = self.cipher.decrypt(&ciphertexts, nonce);
+        let count = plaintext[2] as u32;
+        let matches = plaintext[..2]
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .filter(|byte| *byte != 0)
+            .map(|byte| list[byte as usize - 1])
+            .collect();
+        (matches, count)
+    }
+}
+
+pub fn random_nonce() -> [u8; 16] {
+    let mut nonce = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    nonce
+}
+
+pub fn random_offset() -> u64 {
+    rand::thread_rng().next_u64()
+}
+
+pub fn fetch_mxe_public_key(rpc: &RpcClient, program_id: &Pubkey) -> [u8; 32] {
+    let data = rpc
+        .get_account_data(&pda::mxe_acc(program_id))
+        .expect("MXE account missing: is `arcium localnet` running?");
+    let mxe = MXEAccount::try_deserialize(&mut data.as_slice()).expect("MXE account layout");
+    mxe.utility_pubkeys
+        .x25519_pubkey()
+        .expect("MXE x25519 key not yet set")
+}
+
+// ============================================================
+// ACCOUNTS
+// ============================================================
+
+/// The Arcium accounts shared by every queue_computation context.
+pub struct ArciumAccounts {
+    pub mxe_account: Pubkey,
+    pub mempool_account: Pubkey,
+    pub executing_pool: Pubkey,
+    pub computation_account: Pubkey,
+    pub comp_def_account: Pubkey,
+    pub cluster_account: Pubkey,
+    pub pool_account: Pubkey,
+    pub clock_account: Pubkey,
+}
+
+pub fn arcium_accounts(program_id: &Pubkey, circuit: &str, computation_offset: u64) -> ArciumAccounts {
+    let cluster = cluster_offset();
+    ArciumAccounts {
+        mxe_account: pda::mxe_acc(program_id),
+        mempool_account: pda::mempool_acc(cluster),
+        executing_pool: pda::execpool_acc(cluster),
+        computation_account: pda::computation_acc(cluster, computation_offset),
+        comp_def_account: pda::comp_def_acc(program_id, comp_def_offset(circuit)),
+        cluster_account: pda::cluster_acc(cluster),
+        pool_account: pda::fee_pool_acc(),
+        clock_account: pda::clock_acc(),
+    }
+}
+
+pub fn session_pda(program_id: &Pubkey, session_id: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[b"session", session_id], program_id).0
+}
+
+pub fn state_handle_pda(program_id: &Pubkey, session: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"state_handle", session.as_ref()], program_id).0
+}
+
+// ============================================================
+// COMPUTE UNITS
+// ============================================================
+
+/// Compute units `ixs` use, simulated unsigned with `payer` as fee payer.
+/// Panics if the simulation fails.
+pub fn simulated_units(rpc: &RpcClient, payer: &Pubkey, ixs: &[Instruction]) -> u64 {
+    let tx = Transaction::new_with_payer(ixs, Some(payer));
+    let result = rpc
+        .simulate_transaction_with_config(
+            &tx,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(CommitmentConfig::confirmed()),
+                ..Default::default()
+            },
+        )
+        .expect("simulation")
+        .value;
+    assert!(result.err.is_none(), "simulation failed: {:?}\n{:?}", result.err, result.logs);
+    result.units_consumed.expect("units consumed")
+}
+
+// ============================================================
+// EVENTS
+// ============================================================
+
+/// Poll the transactions touching `account` until one emits event `E`.
+/// Callbacks land in a separate transaction after the MPC finalizes.
+pub fn await_event<E: Event + AnchorDeserialize>(
+    rpc: &RpcClient,
+    account: &Pubkey,
+    timeout: Duration,
+) -> E {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        let signatures = rpc
+            .get_signatures_for_address(account)
+            .unwrap_or_default();
+        for status in signatures {
+            let signature: Signature = status.signature.parse().unwrap();
+            if let Some(event) = find_event::<E>(rpc, &signature) {
+                return event;
+            }
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+    panic!("timed out waiting for event on {account}");
+}
+
+fn find_event<E: Event + AnchorDeserialize>(rpc: &RpcClient, signature: &Signature) -> Option<E> {
+    let tx = rpc
+        .get_transaction_with_config(
+            signature,
+            anchor_client::solana_client::rpc_config::RpcTransactionConfig {
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+                ..Default::default()
+            },
+        )
+        .ok()?;
+    let logs: Vec<String> = tx.transaction.meta?.log_messages.into()?;
+
+    logs.iter()
+        .filter_map(|line| line.strip_prefix("Program data: "))
+        .filter_map(|data| STANDARD.decode(data).ok())
+        .find(|bytes| bytes.starts_with(E::DISCRIMINATOR))
+        .and_then(|bytes| E::deserialize(&mut &bytes[E::DISCRIMINATOR.len()..]).ok())
+}
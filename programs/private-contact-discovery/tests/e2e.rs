@@ -0,0 +1,538 @@
+// Localnet end-to-end test for Private Contact Discovery.
+//
+// Deploys the program (if needed), initializes all four computation
+// definitions, runs a full Alice/Bob session through the MPC cluster,
+// and decrypts both parties' results. Both submissions are simulated
+// first and must stay within `SUBMISSION_CU_BUDGET`.
+//
+// Requires a running Arcium localnet:
+//
+//     arcium build
+//     arcium localnet            # in another terminal
+//     cargo test --features e2e -- --nocapture
+//
+// Environment:
+//     ARCIUM_LOCALNET_URL     RPC endpoint (default http://127.0.0.1:8899)
+//     ARCIUM_CLUSTER_OFFSET   MXE cluster offset (default 0)
+//     ANCHOR_WALLET           payer keypair (default ~/.config/solana/id.json)
+
+#![cfg(feature = "e2e")]
+
+mod common;
+
+use std::{collections::HashSet, path::PathBuf, process::Command, rc::Rc, time::Duration};
+
+use anchor_client::{
+    solana_client::rpc_client::RpcClient,
+    solana_sdk::{
+        commitment_config::CommitmentConfig,
+        instruction::AccountMeta,
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        signature::{read_keypair_file, Keypair},
+        bpf_loader_upgradeable,
+        signer::Signer,
+        system_program,
+    },
+    Client, Cluster, Program,
+};
+use anchor_lang::AccountDeserialize;
+use arcium_anchor::prelude::{MXEAccount, ARCIUM_PROG_ID, LUT_PROGRAM_ID, SIGN_PDA_SEED};
+use arcium_client::pda;
+use common::*;
+use private_contact_discovery::{
+    accounts, instruction, AliceRevealed, AppStats, CreditAccount, GlobalStats, HashScheme, MatchComplete,
+    Namespace, ProtocolConfig, SessionMode, SessionStateHandle, ID, NO_APP_ID, NO_INVITE, NO_MEMO,
+    PROGRAM_VERSION,
+};
+
+const CALLBACK_TIMEOUT: Duration = Duration::from_secs(120);
+/// Compute units a submission may use: half the default per-instruction
+/// limit, so chunking, bonds and fees can still be added to them
+const SUBMISSION_CU_BUDGET: u64 = 100_000;
+/// Computation price set when this test creates the config itself
+const E2E_COMPUTATION_PRICE: u64 = 1_000;
+
+fn payer() -> Keypair {
+    let path = std::env::var("ANCHOR_WALLET").map(PathBuf::from).unwrap_or_else(|_| {
+        dirs::home_dir().unwrap().join(".config/solana/id.json")
+    });
+    read_keypair_file(path).expect("payer keypair")
+}
+
+fn funded_keypair(rpc: &RpcClient) -> Keypair {
+    let keypair = Keypair::new();
+    let sig = rpc
+        .request_airdrop(&keypair.pubkey(), 10 * LAMPORTS_PER_SOL)
+        .expect("airdrop");
+    rpc.poll_for_signature(&sig).expect("airdrop confirmation");
+    keypair
+}
+
+/// Deploy target/deploy/private_contact_discovery.so unless already live.
+fn ensure_deployed(rpc: &RpcClient) {
+    if rpc.get_account(&ID).map(|a| a.executable).unwrap_or(false) {
+        return;
+    }
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../..");
+    let status = Command::new("solana")
+        .current_dir(&root)
+        .args([
+            "program",
+            "deploy",
+            "--url",
+            &rpc_url(),
+            "--program-id",
+            "target/deploy/private_contact_discovery-keypair.json",
+            "target/deploy/private_contact_discovery.so",
+        ])
+        .status()
+        .expect("solana CLI on PATH");
+    assert!(status.success(), "program deploy failed");
+}
+
+fn init_comp_defs(program: &Program<Rc<Keypair>>, rpc: &RpcClient) {
+    let mxe_account = pda::mxe_acc(&ID);
+    let mxe_data = rpc.get_account_data(&mxe_account).expect("MXE account");
+    let mxe = MXEAccount::try_deserialize(&mut mxe_data.as_slice()).unwrap();
+    let address_lookup_table = pda::mxe_lut_acc(&mxe_account, mxe.lut_offset_slot);
+
+    let circuits: [(&str, fn() -> Vec<u8>); 12] = [
+        ("init_session", || anchor_lang::InstructionData::data(&instruction::InitSessionCompDef {})),
+        ("submit_contacts_alice", || anchor_lang::InstructionData::data(&instruction::InitSubmitAliceCompDef {})),
+        ("submit_and_match", || anchor_lang::InstructionData::data(&instruction::InitSubmitAndMatchCompDef {})),
+        ("reveal_alice_matches", || anchor_lang::InstructionData::data(&instruction::InitRevealAliceCompDef {})),
+        ("push_delta", || anchor_lang::InstructionData::data(&instruction::InitPushDeltaCompDef {})),
+        ("create_pool", || anchor_lang::InstructionData::data(&instruction::InitCreatePoolCompDef {})),
+        ("match_against_pool", || anchor_lang::InstructionData::data(&instruction::InitMatchPoolCompDef {})),
+        ("submit_and_score", || anchor_lang::InstructionData::data(&instruction::InitSubmitAndScoreCompDef {})),
+        ("reveal_alice_score", || anchor_lang::InstructionData::data(&instruction::InitRevealAliceScoreCompDef {})),
+        ("submit_contacts_bob", || anchor_lang::InstructionData::data(&instruction::InitSubmitBobCompDef {})),
+        ("submit_connector", || anchor_lang::InstructionData::data(&instruction::InitSubmitConnectorCompDef {})),
+        ("reveal_second_degree", || anchor_lang::InstructionData::data(&instruction::InitRevealSecondDegreeCompDef {})),
+    ];
+
+    for (circuit, data) in circuits {
+        let comp_def_account = arcium_accounts(&ID, circuit, 0).comp_def_account;
+        if rpc.get_account(&comp_def_account).is_ok() {
+            continue;
+        }
+        // All init contexts share the same account layout
+        let metas = anchor_lang::ToAccountMetas::to_account_metas(
+            &accounts::InitSessionCompDef {
+                payer: program.payer(),
+                mxe_account,
+                comp_def_account,
+                address_lookup_table,
+                lut_program: LUT_PROGRAM_ID,
+                arcium_program: ARCIUM_PROG_ID,
+                system_program: system_program::ID,
+            },
+            None,
+        );
+        program
+            .request()
+            .instruction(anchor_client::solana_sdk::instruction::Instruction {
+                program_id: ID,
+                accounts: metas,
+                data: data(),
+            })
+            .send()
+            .unwrap_or_else(|e| panic!("init {circuit} comp def: {e}"));
+    }
+
+    if rpc.get_account(&global_stats_pda()).is_err() {
+        program
+            .request()
+            .accounts(accounts::InitGlobalStats {
+                payer: program.payer(),
+                global_stats: global_stats_pda(),
+                unattributed_app_stats: app_stats_pda(&NO_APP_ID),
+                system_program: system_program::ID,
+            })
+            .args(instruction::InitGlobalStats {})
+            .send()
+            .expect("init_global_stats");
+    }
+
+    // The payer deployed the program, so it is the upgrade authority
+    if rpc.get_account(&config_pda()).is_err() {
+        program
+            .request()
+            .accounts(accounts::InitConfig {
+                admin: program.payer(),
+                config: config_pda(),
+                treasury: treasury_pda(),
+                unattributed_credit: credit_pda(&NO_APP_ID),
+                program: ID,
+                program_data: Pubkey::find_program_address(&[ID.as_ref()], &bpf_loader_upgradeable::ID).0,
+                system_program: system_program::ID,
+            })
+            .args(instruction::InitConfig { computation_price: E2E_COMPUTATION_PRICE })
+            .send()
+            .expect("init_config");
+    }
+}
+
+fn global_stats_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"global_stats"], &ID).0
+}
+
+fn fetch_global_stats(rpc: &RpcClient) -> GlobalStats {
+    let data = rpc.get_account_data(&global_stats_pda()).unwrap();
+    GlobalStats::try_deserialize(&mut data.as_slice()).unwrap()
+}
+
+fn app_stats_pda(app_id: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[b"app_stats", app_id], &ID).0
+}
+
+fn fetch_app_stats(rpc: &RpcClient, app_id: &[u8; 32]) -> AppStats {
+    let data = rpc.get_account_data(&app_stats_pda(app_id)).unwrap();
+    AppStats::try_deserialize(&mut data.as_slice()).unwrap()
+}
+
+fn config_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &ID).0
+}
+
+fn blocked_wallet_pda(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"blocked", wallet.as_ref()], &ID).0
+}
+
+fn treasury_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"treasury"], &ID).0
+}
+
+fn credit_pda(app_id: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[b"credit", app_id], &ID).0
+}
+
+fn fetch_credit(rpc: &RpcClient, app_id: &[u8; 32]) -> CreditAccount {
+    let data = rpc.get_account_data(&credit_pda(app_id)).unwrap();
+    CreditAccount::try_deserialize(&mut data.as_slice()).unwrap()
+}
+
+fn billing(app_id: &[u8; 32]) -> accounts::Billing {
+    accounts::Billing {
+        config: config_pda(),
+        credit: credit_pda(app_id),
+        treasury: treasury_pda(),
+    }
+}
+
+#[test]
+fn full_alice_bob_session() {
+    let rpc = RpcClient::new_with_commitment(rpc_url(), CommitmentConfig::confirmed());
+    ensure_deployed(&rpc);
+
+    let payer = Rc::new(payer());
+    let client = Client::new_with_options(
+        Cluster::Custom(rpc_url(), rpc_url().replace("http", "ws").replace("8899", "8900")),
+        payer,
+        CommitmentConfig::confirmed(),
+    );
+    let program = client.program(ID).unwrap();
+    init_comp_defs(&program, &rpc);
+    let stats_before = fetch_global_stats(&rpc).usage;
+
+    let alice = funded_keypair(&rpc);
+    let bob = funded_keypair(&rpc);
+    let mxe_public_key = fetch_mxe_public_key(&rpc, &ID);
+
+    let namespace = Namespace::Phone as u8;
+    let alice_contacts = ["+1 555 0100", "+1 555 0142", "+1 555 0177", "+1 555 0199"];
+    let bob_contacts = ["555-0142", "(555) 0199", "+44 20 7946 0000"];
+
+    let mut session_id = [0u8; 32];
+    session_id[..16].copy_from_slice(&random_nonce());
+    let session = session_pda(&ID, &session_id);
+    let state_handle = state_handle_pda(&ID, &session);
+    let mut app_id = [0u8; 32];
+    app_id[..16].copy_from_slice(&random_nonce());
+    let sign_pda_account = Pubkey::find_program_address(&[&SIGN_PDA_SEED], &ID).0;
+
+    // 0. Alice prepays the app's credit for the session's four computations
+    let config_data = rpc.get_account_data(&config_pda()).unwrap();
+    let price = ProtocolConfig::try_deserialize(&mut config_data.as_slice()).unwrap().computation_price;
+    program
+        .request()
+        .signer(&alice)
+        .accounts(accounts::OpenCreditAccount {
+            authority: alice.pubkey(),
+            payer: alice.pubkey(),
+            credit: credit_pda(&app_id),
+            system_program: system_program::ID,
+        })
+        .args(instruction::OpenCreditAccount { app_id })
+        .accounts(accounts::DepositCredit {
+            depositor: alice.pubkey(),
+            credit: credit_pda(&app_id),
+            system_program: system_program::ID,
+        })
+        .args(instruction::DepositCredit { amount: 4 * price })
+        .send()
+        .expect("open_credit_account + deposit_credit");
+
+    // 1. Alice creates the session
+    let alice_cipher = PartyCipher::new(mxe_public_key);
+    let offset = random_offset();
+    let a = arcium_accounts(&ID, "init_session", offset);
+    program
+        .request()
+        .signer(&alice)
+        .accounts(accounts::CreateSession {
+            alice: alice.pubkey(),
+            payer: alice.pubkey(),
+            session,
+            state_handle,
+            billing: billing(&app_id),
+            global_stats: global_stats_pda(),
+            app_stats: app_stats_pda(&app_id),
+            sign_pda_account,
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        })
+        .accounts(vec![AccountMeta::new_readonly(blocked_wallet_pda(&alice.pubkey()), false)])
+        .args(instruction::CreateSession {
+            client_version: PROGRAM_VERSION,
+            computation_offset: offset,
+            session_id,
+            app_id,
+            pubkey: alice_cipher.public_key,
+            nonce: u128::from_le_bytes(random_nonce()),
+            mode: SessionMode::OneShot as u8,
+            namespace,
+            hash_scheme: HashScheme::Sha256 as u8,
+            public_count: false,
+            hide_counts: false,
+            join_deadline: 0,
+            reveal_after_slot: 0,
+            capacity: 32,
+            memo: NO_MEMO,
+            intended_bob: Pubkey::default(),
+            invite_hash: NO_INVITE,
+        })
+        .send()
+        .expect("create_session");
+    await_event::<private_contact_discovery::SessionInitialized>(
+        &rpc,
+        &a.computation_account,
+        CALLBACK_TIMEOUT,
+    );
+    let handle_data = rpc.get_account_data(&state_handle).expect("state handle");
+    let handle = SessionStateHandle::try_deserialize(&mut handle_data.as_slice()).unwrap();
+    assert_eq!(handle.session, session);
+    assert_eq!(handle.generation, 1, "init callback should record the state blob");
+
+    // 2. Alice submits her contacts
+    let (alice_hashes, alice_count) = hash_contact_list(namespace, &alice_contacts);
+    let nonce = random_nonce();
+    let (encrypted_hashes, encrypted_count, encrypted_namespace) =
+        alice_cipher.encrypt_contacts(&alice_hashes, alice_count, namespace, &nonce);
+    let offset = random_offset();
+    let a = arcium_accounts(&ID, "submit_contacts_alice", offset);
+    let submit_alice = program
+        .request()
+        .signer(&alice)
+        .accounts(accounts::SubmitContactsAlice {
+            alice: alice.pubkey(),
+            payer: alice.pubkey(),
+            session,
+            state_handle,
+            billing: billing(&app_id),
+            sign_pda_account,
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        })
+        .args(instruction::SubmitContactsAlice {
+            computation_offset: offset,
+            hash_scheme: HashScheme::Sha256 as u8,
+            encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+            pubkey: alice_cipher.public_key,
+            nonce: u128::from_le_bytes(nonce),
+        });
+    let units = simulated_units(&rpc, &program.payer(), &submit_alice.instructions().unwrap());
+    assert!(
+        units <= SUBMISSION_CU_BUDGET,
+        "submit_contacts_alice used {units} compute units, budget {SUBMISSION_CU_BUDGET}"
+    );
+    submit_alice.send().expect("submit_contacts_alice");
+    await_event::<private_contact_discovery::AliceSubmitted>(
+        &rpc,
+        &a.computation_account,
+        CALLBACK_TIMEOUT,
+    );
+
+    // 3. Bob submits and triggers the match; the client's payer sponsors
+    //    his fees, so Bob only signs
+    let bob_cipher = PartyCipher::new(mxe_public_key);
+    let (bob_hashes, bob_count) = hash_contact_list(namespace, &bob_contacts);
+    let nonce = random_nonce();
+    let (encrypted_hashes, encrypted_count, encrypted_namespace) =
+        bob_cipher.encrypt_contacts(&bob_hashes, bob_count, namespace, &nonce);
+    let offset = random_offset();
+    let a = arcium_accounts(&ID, "submit_and_match", offset);
+    let bob_balance = rpc.get_balance(&bob.pubkey()).unwrap();
+    let submit_bob = program
+        .request()
+        .signer(&bob)
+        .accounts(accounts::SubmitAndMatch {
+            bob: bob.pubkey(),
+            payer: program.payer(),
+            session,
+            state_handle,
+            billing: billing(&app_id),
+            sign_pda_account,
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        })
+        .accounts(vec![
+            AccountMeta::new_readonly(blocked_wallet_pda(&bob.pubkey()), false),
+            AccountMeta::new_readonly(blocked_wallet_pda(&alice.pubkey()), false),
+        ])
+        .args(instruction::SubmitAndMatch {
+            computation_offset: offset,
+            hash_scheme: HashScheme::Sha256 as u8,
+            encrypted_hashes,
+            encrypted_count,
+            encrypted_namespace,
+            pubkey: bob_cipher.public_key,
+            nonce: u128::from_le_bytes(nonce),
+            public_count: false,
+        });
+    let units = simulated_units(&rpc, &program.payer(), &submit_bob.instructions().unwrap());
+    assert!(
+        units <= SUBMISSION_CU_BUDGET,
+        "submit_and_match used {units} compute units, budget {SUBMISSION_CU_BUDGET}"
+    );
+    submit_bob.send().expect("submit_and_match");
+    let bob_event: MatchComplete = await_event(&rpc, &a.computation_account, CALLBACK_TIMEOUT);
+    assert_eq!(rpc.get_balance(&bob.pubkey()).unwrap(), bob_balance);
+
+    // 4. Alice reveals her side
+    let offset = random_offset();
+    let a = arcium_accounts(&ID, "reveal_alice_matches", offset);
+    program
+        .request()
+        .signer(&alice)
+        .accounts(accounts::RevealAliceMatches {
+            alice: alice.pubkey(),
+            payer: alice.pubkey(),
+            session,
+            state_handle,
+            billing: billing(&app_id),
+            sign_pda_account,
+            mxe_account: a.mxe_account,
+            mempool_account: a.mempool_account,
+            executing_pool: a.executing_pool,
+            computation_account: a.computation_account,
+            comp_def_account: a.comp_def_account,
+            cluster_account: a.cluster_account,
+            pool_account: a.pool_account,
+            clock_account: a.clock_account,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        })
+        .args(instruction::RevealAliceMatches {
+            computation_offset: offset,
+            pubkey: alice_cipher.public_key,
+            nonce: u128::from_le_bytes(random_nonce()),
+            page: 0,
+        })
+        .send()
+        .expect("reveal_alice_matches");
+    let alice_event: AliceRevealed = await_event(&rpc, &a.computation_account, CALLBACK_TIMEOUT);
+
+    // 5. Both parties decrypt the same intersection
+    let expected: HashSet<u128> = ["+1 555 0142", "+1 555 0199"]
+        .iter()
+        .map(|c| hash_contact(namespace, c))
+        .collect();
+
+    let (bob_matches, bob_match_count) = bob_cipher.decrypt_matches(
+        &bob_event.encrypted_matches,
+        &bob_event.encrypted_count,
+        &bob_event.nonce,
+        &bob_hashes,
+    );
+    assert_eq!(bob_match_count, 2);
+    assert_eq!(bob_matches.into_iter().collect::<HashSet<_>>(), expected);
+
+    let (alice_matches, alice_match_count) = alice_cipher.decrypt_matches(
+        &alice_event.encrypted_matches,
+        &alice_event.encrypted_count,
+        &alice_event.nonce,
+        &alice_hashes,
+    );
+    assert_eq!(alice_match_count, 2);
+    assert_eq!(alice_matches.into_iter().collect::<HashSet<_>>(), expected);
+
+    let data = rpc.get_account_data(&session).unwrap();
+    let session_account =
+        private_contact_discovery::DiscoverySession::try_from_account_data(&data).unwrap();
+    assert_eq!(session_account.status, private_contact_discovery::Status::Matched as u8);
+    assert_eq!(session_account.bob, bob.pubkey());
+    assert_eq!(session_account.rent_recipient(), alice.pubkey());
+
+    // Each party's receipt pins the result their event carried
+    let bob_receipt = private_contact_discovery::result_receipt(
+        &session_id,
+        2,
+        0,
+        &bob_event.encrypted_matches,
+        &bob_event.encrypted_count,
+        &bob_event.nonce,
+    );
+    assert_eq!(session_account.bob_receipt, bob_receipt);
+    let alice_receipt = private_contact_discovery::result_receipt(
+        &session_id,
+        1,
+        alice_event.window,
+        &alice_event.encrypted_matches,
+        &alice_event.encrypted_count,
+        &alice_event.nonce,
+    );
+    assert_eq!(session_account.alice_receipt, alice_receipt);
+
+    // init, Alice's submission, the match, and Alice's reveal
+    let stats = fetch_global_stats(&rpc).usage;
+    assert_eq!(stats.sessions_created, stats_before.sessions_created + 1);
+    assert_eq!(stats.matches_completed, stats_before.matches_completed + 1);
+    assert_eq!(stats.computations, stats_before.computations + 4);
+    // A fresh app id, so its counters hold this session alone
+    let app = fetch_app_stats(&rpc, &app_id);
+    assert_eq!(app.app_id, app_id);
+    assert_eq!(app.usage.sessions_created, 1);
+    assert_eq!(app.usage.matches_completed, 1);
+    assert_eq!(app.usage.computations, 4);
+    assert_eq!(session_account.app_id, app_id);
+    // The deposit covered exactly the four computations
+    assert_eq!(fetch_credit(&rpc, &app_id).balance, 0);
+}
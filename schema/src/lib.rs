@@ -0,0 +1,219 @@
+// Private Contact Discovery - Circuit Interface Schema
+//
+// The sizes and flattened ciphertext layouts of the structs the circuits
+// exchange with the program. Arcium encodes an encrypted struct as one
+// 32-byte ciphertext per scalar, in field order, so a layout here is the
+// ordered list of a struct's scalar fields. The program builds its
+// `queue_computation` arguments and splits callback outputs from these
+// layouts, and the plaintext reference model takes its capacities from
+// them.
+//
+// The `#[encrypted]` circuits module is compiled by Arcis from its own
+// source, so it still spells out its structs; each layout below names
+// the circuit struct it mirrors, and the program asserts at compile time
+// that every buffer it hands the cluster is sized from here.
+
+#![no_std]
+
+/// Maximum contacts per list (`hashes: [u128; 32]` in `ContactList`).
+pub const MAX_CONTACTS: usize = 32;
+
+/// Maximum additions, and separately removals, per `ContactDelta`.
+pub const MAX_DELTA: usize = 16;
+
+/// List capacities a session can be created with. A session's lists
+/// may use only its first `capacity` slots, and its match circuit
+/// compares only those: capacity squared comparisons instead of 1024.
+/// There is no 64 tier: 64 ciphertexts of 32 bytes alone exceed a
+/// transaction's 1232 bytes, so lists that long are matched as a
+/// committed set, `MAX_CONTACTS` per chunk.
+pub const CAPACITY_TIERS: [u8; 3] = [8, 16, MAX_CONTACTS as u8];
+
+/// Result slots returned per reveal page.
+pub const PAGE_SIZE: usize = 32;
+
+/// Words a `MatchResult` page packs its slots into, one byte per slot.
+pub const MATCH_WORDS: usize = PAGE_SIZE / 16;
+
+/// Contact cards a registry holds. Every publish rewrites the whole
+/// registry, so it is kept smaller than a contact list.
+pub const CARD_SLOTS: usize = 16;
+
+/// Chunks, each a full `ContactList`, a server database holds. Each is
+/// stored under its own MXE nonce and every query reads all of them.
+pub const DATABASE_CHUNKS: usize = 8;
+
+/// Identifiers one batched membership check asks about, each billed as
+/// one query. The answer is a bit mask, one bit per slot.
+pub const MEMBERSHIP_BATCH_SLOTS: usize = 16;
+
+/// Hashes at or above this are decoys, never matched or counted by the
+/// circuits. Clients pad short lists with them so every slot is used.
+pub const DECOY_FLOOR: u128 = 0xFFFF_FFFF_FFFF_FFFF_0000_0000_0000_0000;
+
+/// Scalar type of one encrypted field, and so of one ciphertext.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scalar {
+    U8,
+    U32,
+    U128,
+}
+
+/// One field of a circuit struct: `len` consecutive scalars, 1 for a
+/// plain field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Field {
+    pub name: &'static str,
+    pub scalar: Scalar,
+    pub len: usize,
+}
+
+impl Field {
+    pub const fn one(name: &'static str, scalar: Scalar) -> Self {
+        Field { name, scalar, len: 1 }
+    }
+
+    pub const fn array(name: &'static str, scalar: Scalar, len: usize) -> Self {
+        Field { name, scalar, len }
+    }
+}
+
+/// A circuit struct's fields, in declaration order.
+pub type Layout = &'static [Field];
+
+/// `circuits::ContactList`
+pub const CONTACT_LIST: Layout = &[
+    Field::array("hashes", Scalar::U128, MAX_CONTACTS),
+    Field::one("count", Scalar::U32),
+    Field::one("namespace", Scalar::U8),
+];
+
+/// `circuits::ContactDelta`
+pub const CONTACT_DELTA: Layout = &[
+    Field::array("added", Scalar::U128, MAX_DELTA),
+    Field::array("removed", Scalar::U128, MAX_DELTA),
+    Field::one("namespace", Scalar::U8),
+];
+
+/// `circuits::MatchResult`, one reveal page of it
+pub const MATCH_RESULT: Layout = &[
+    Field::array("indices", Scalar::U128, MATCH_WORDS),
+    Field::one("match_count", Scalar::U32),
+];
+
+/// `circuits::ContactCard`
+pub const CONTACT_CARD: Layout = &[
+    Field::one("hash", Scalar::U128),
+    Field::one("key_lo", Scalar::U128),
+    Field::one("key_hi", Scalar::U128),
+    Field::one("namespace", Scalar::U8),
+];
+
+/// `circuits::CardRegistry`
+pub const CARD_REGISTRY: Layout = &[
+    Field::array("hashes", Scalar::U128, CARD_SLOTS),
+    Field::array("publishers", Scalar::U128, CARD_SLOTS),
+    Field::array("keys_lo", Scalar::U128, CARD_SLOTS),
+    Field::array("keys_hi", Scalar::U128, CARD_SLOTS),
+    Field::one("count", Scalar::U32),
+    Field::one("namespace", Scalar::U8),
+];
+
+/// `circuits::CardMatches`
+pub const CARD_MATCHES: Layout = &[
+    Field::array("hashes", Scalar::U128, CARD_SLOTS),
+    Field::array("keys_lo", Scalar::U128, CARD_SLOTS),
+    Field::array("keys_hi", Scalar::U128, CARD_SLOTS),
+    Field::one("card_count", Scalar::U32),
+];
+
+/// `circuits::MembershipQuery`
+pub const MEMBERSHIP_QUERY: Layout = &[
+    Field::one("hash", Scalar::U128),
+    Field::one("namespace", Scalar::U8),
+];
+
+/// `circuits::MembershipBatch`
+pub const MEMBERSHIP_BATCH: Layout = &[
+    Field::array("hashes", Scalar::U128, MEMBERSHIP_BATCH_SLOTS),
+    Field::one("namespace", Scalar::U8),
+];
+
+/// Number of ciphertexts an encrypted `layout` takes.
+pub const fn ciphertexts(layout: Layout) -> usize {
+    let mut total = 0;
+    let mut i = 0;
+    while i < layout.len() {
+        total += layout[i].len;
+        i += 1;
+    }
+    total
+}
+
+/// The scalar type of the `index`th ciphertext of `layout`, `None` past
+/// its end.
+pub const fn scalar_at(layout: Layout, index: usize) -> Option<Scalar> {
+    let mut start = 0;
+    let mut i = 0;
+    while i < layout.len() {
+        if index < start + layout[i].len {
+            return Some(layout[i].scalar);
+        }
+        start += layout[i].len;
+        i += 1;
+    }
+    None
+}
+
+/// The list slot packed into byte `j` of a `MatchResult`'s `indices`:
+/// the byte holds the slot plus one when the recipient's list matched
+/// there, and 0 otherwise. `None` for an unmatched byte or `j` past the
+/// page.
+pub const fn matched_slot(indices: &[u128; MATCH_WORDS], j: usize) -> Option<usize> {
+    if j >= PAGE_SIZE {
+        return None;
+    }
+    let byte = (indices[j / 16] >> (8 * (j % 16))) as u8;
+    if byte == 0 {
+        None
+    } else {
+        Some(byte as usize - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ciphertext_counts_match_the_circuit_structs() {
+        assert_eq!(ciphertexts(CONTACT_LIST), 34);
+        assert_eq!(ciphertexts(CONTACT_DELTA), 33);
+        assert_eq!(ciphertexts(MATCH_RESULT), 3);
+        assert_eq!(ciphertexts(CONTACT_CARD), 4);
+        assert_eq!(ciphertexts(CARD_REGISTRY), 66);
+        assert_eq!(ciphertexts(CARD_MATCHES), 49);
+        assert_eq!(ciphertexts(MEMBERSHIP_QUERY), 2);
+        assert_eq!(ciphertexts(MEMBERSHIP_BATCH), 17);
+    }
+
+    #[test]
+    fn scalars_follow_field_order() {
+        assert_eq!(scalar_at(CONTACT_LIST, 0), Some(Scalar::U128));
+        assert_eq!(scalar_at(CONTACT_LIST, MAX_CONTACTS - 1), Some(Scalar::U128));
+        assert_eq!(scalar_at(CONTACT_LIST, MAX_CONTACTS), Some(Scalar::U32));
+        assert_eq!(scalar_at(CONTACT_LIST, MAX_CONTACTS + 1), Some(Scalar::U8));
+        assert_eq!(scalar_at(CONTACT_LIST, MAX_CONTACTS + 2), None);
+        assert_eq!(scalar_at(CONTACT_DELTA, 2 * MAX_DELTA), Some(Scalar::U8));
+    }
+
+    #[test]
+    fn matched_slots_are_read_one_byte_per_slot() {
+        let indices = [1 | (16u128 << 120), 32u128 << 8];
+        assert_eq!(matched_slot(&indices, 0), Some(0));
+        assert_eq!(matched_slot(&indices, 1), None);
+        assert_eq!(matched_slot(&indices, 15), Some(15));
+        assert_eq!(matched_slot(&indices, 17), Some(31));
+        assert_eq!(matched_slot(&indices, PAGE_SIZE), None);
+    }
+}
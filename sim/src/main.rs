@@ -0,0 +1,478 @@
+// Private Contact Discovery - Circuit Simulator
+//
+// Runs a session's circuits over plaintext inputs through the reference
+// model (`encrypted_ixs::reference`), which mirrors every circuit step
+// for step, and prints what each step returns and the state it leaves.
+// A circuit change can be tried against a fixture in seconds, without
+// deploying to a cluster; the reference property tests then pin it down.
+//
+//     cargo run -p arcis-sim -- sim/fixtures/session.json
+//     cargo run -p arcis-sim -- sim/fixtures/session.json --state
+//
+// A fixture is one session: its namespace and tag, then the circuits to
+// run against its state, in order. Hashes are plain integers, so a
+// fixture can use small numbers; 0 is an empty slot and hashes at or
+// above DECOY_FLOOR are decoys, as on chain.
+//
+//     {
+//       "namespace": 0,
+//       "session_tag": 7,
+//       "steps": [
+//         "init_session",
+//         { "submit_contacts_alice": { "list": { "hashes": [1, 2, 3] } } },
+//         { "submit_and_match": { "list": { "hashes": [2, 3, 4] }, "publish": 1 } },
+//         { "reveal_alice_matches": {} }
+//       ]
+//     }
+//
+// A list's namespace defaults to the session's and its count to its
+// length; a step's `session_tag` defaults to the session's, so a step
+// can set either to see the circuit refuse its input. `instant_match`
+// runs on its own lists and leaves the state alone.
+//
+// Only the circuits' logic is simulated. Encryption, the program's
+// status checks, and fees are not, so a step the program would refuse
+// still runs here.
+
+use std::path::PathBuf;
+
+use anyhow::{ensure, Context, Result};
+use clap::Parser;
+use encrypted_ixs::reference::{
+    self, ContactDelta, ContactList, MatchResult, SessionState, SubmitConfirmation, MAX_CONTACTS, MAX_DELTA,
+};
+use serde::Deserialize;
+
+#[derive(Parser)]
+#[command(name = "arcis-sim", about = "Run circuits over plaintext inputs from a JSON fixture")]
+struct Args {
+    /// Fixture to run
+    fixture: PathBuf,
+    /// Print the full state after every step, not just at the end
+    #[arg(long)]
+    state: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Fixture {
+    #[serde(default)]
+    namespace: u8,
+    session_tag: u128,
+    steps: Vec<Step>,
+}
+
+/// One circuit run, named as in `circuits`.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum Step {
+    InitSession,
+    SubmitContactsAlice {
+        list: List,
+        #[serde(default = "full")]
+        capacity: u8,
+        session_tag: Option<u128>,
+    },
+    InitAndSubmitAlice {
+        list: List,
+        #[serde(default = "full")]
+        capacity: u8,
+        session_tag: Option<u128>,
+    },
+    SubmitAndMatch {
+        list: List,
+        #[serde(default)]
+        publish: u8,
+        #[serde(default)]
+        withhold: u8,
+        #[serde(default = "full")]
+        capacity: u8,
+        session_tag: Option<u128>,
+    },
+    SubmitAndScore {
+        list: List,
+        #[serde(default)]
+        hide_counts: u8,
+        session_tag: Option<u128>,
+    },
+    SubmitContactsBob {
+        list: List,
+        session_tag: Option<u128>,
+    },
+    SubmitConnector {
+        list: List,
+        session_tag: Option<u128>,
+    },
+    SubmitAndCheckFollow {
+        list: List,
+        alice_id: u128,
+        bob_id: u128,
+        session_tag: Option<u128>,
+    },
+    MatchSetChunk {
+        list: List,
+        #[serde(default)]
+        last: bool,
+        session_tag: Option<u128>,
+    },
+    PushDelta {
+        party: Party,
+        #[serde(default)]
+        added: Vec<u128>,
+        #[serde(default)]
+        removed: Vec<u128>,
+        namespace: Option<u8>,
+        session_tag: Option<u128>,
+    },
+    RevealAliceMatches {
+        #[serde(default)]
+        page: u8,
+        session_tag: Option<u128>,
+    },
+    RevealBobMatches {
+        #[serde(default)]
+        page: u8,
+        session_tag: Option<u128>,
+    },
+    RevealBoth {
+        #[serde(default)]
+        page: u8,
+        session_tag: Option<u128>,
+    },
+    RevealAliceScore {
+        #[serde(default)]
+        hide_counts: u8,
+        session_tag: Option<u128>,
+    },
+    RevealSecondDegree {
+        session_tag: Option<u128>,
+    },
+    PurgeSessionState,
+    MigrateSessionState,
+    InstantMatch {
+        alice: List,
+        bob: List,
+    },
+}
+
+/// A contact list. Always an object: serde cannot buffer u128s, so an
+/// untagged bare-array form is not available.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct List {
+    hashes: Vec<u128>,
+    namespace: Option<u8>,
+    count: Option<u32>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Party {
+    Alice,
+    Bob,
+}
+
+fn full() -> u8 {
+    MAX_CONTACTS as u8
+}
+
+impl List {
+    fn build(&self, session_namespace: u8) -> Result<ContactList> {
+        ensure!(
+            self.hashes.len() <= MAX_CONTACTS,
+            "a list holds at most {MAX_CONTACTS} hashes, got {}",
+            self.hashes.len()
+        );
+        let mut list = ContactList {
+            count: self.count.unwrap_or(self.hashes.len() as u32),
+            namespace: self.namespace.unwrap_or(session_namespace),
+            ..ContactList::default()
+        };
+        list.hashes[..self.hashes.len()].copy_from_slice(&self.hashes);
+        Ok(list)
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let raw = std::fs::read_to_string(&args.fixture).with_context(|| format!("reading {}", args.fixture.display()))?;
+    let fixture: Fixture = serde_json::from_str(&raw).with_context(|| format!("parsing {}", args.fixture.display()))?;
+
+    let mut sim = Sim {
+        namespace: fixture.namespace,
+        session_tag: fixture.session_tag,
+        state: None,
+    };
+    for (i, step) in fixture.steps.iter().enumerate() {
+        println!("[{i}] {}", step.name());
+        sim.run(step).with_context(|| format!("step {i} ({})", step.name()))?;
+        if args.state {
+            sim.print_state();
+        }
+    }
+    if !args.state {
+        println!("final state");
+        sim.print_state();
+    }
+    Ok(())
+}
+
+impl Step {
+    fn name(&self) -> &'static str {
+        match self {
+            Step::InitSession => "init_session",
+            Step::SubmitContactsAlice { .. } => "submit_contacts_alice",
+            Step::InitAndSubmitAlice { .. } => "init_and_submit_alice",
+            Step::SubmitAndMatch { .. } => "submit_and_match",
+            Step::SubmitAndScore { .. } => "submit_and_score",
+            Step::SubmitContactsBob { .. } => "submit_contacts_bob",
+            Step::SubmitConnector { .. } => "submit_connector",
+            Step::SubmitAndCheckFollow { .. } => "submit_and_check_follow",
+            Step::MatchSetChunk { .. } => "match_set_chunk",
+            Step::PushDelta { .. } => "push_delta",
+            Step::RevealAliceMatches { .. } => "reveal_alice_matches",
+            Step::RevealBobMatches { .. } => "reveal_bob_matches",
+            Step::RevealBoth { .. } => "reveal_both",
+            Step::RevealAliceScore { .. } => "reveal_alice_score",
+            Step::RevealSecondDegree { .. } => "reveal_second_degree",
+            Step::PurgeSessionState => "purge_session_state",
+            Step::MigrateSessionState => "migrate_session_state",
+            Step::InstantMatch { .. } => "instant_match",
+        }
+    }
+}
+
+struct Sim {
+    namespace: u8,
+    session_tag: u128,
+    /// `None` until a step creates the state
+    state: Option<SessionState>,
+}
+
+impl Sim {
+    fn state(&self) -> Result<&SessionState> {
+        self.state
+            .as_ref()
+            .context("no session state yet: run init_session or init_and_submit_alice first")
+    }
+
+    fn list(&self, list: &List) -> Result<ContactList> {
+        list.build(self.namespace)
+    }
+
+    fn run(&mut self, step: &Step) -> Result<()> {
+        let default_tag = self.session_tag;
+        let tag = |session_tag: &Option<u128>| session_tag.unwrap_or(default_tag);
+        match step {
+            Step::InitSession => {
+                self.state = Some(reference::init_session(self.namespace, self.session_tag));
+            }
+            Step::SubmitContactsAlice {
+                list,
+                capacity,
+                session_tag,
+            } => {
+                let (state, confirmation) =
+                    reference::submit_contacts_alice(self.state()?, &self.list(list)?, *capacity, tag(session_tag));
+                print_confirmation(&confirmation);
+                self.state = Some(state);
+            }
+            Step::InitAndSubmitAlice {
+                list,
+                capacity,
+                session_tag,
+            } => {
+                let (state, confirmation) =
+                    reference::init_and_submit_alice(&self.list(list)?, self.namespace, *capacity, tag(session_tag));
+                print_confirmation(&confirmation);
+                self.state = Some(state);
+            }
+            Step::SubmitAndMatch {
+                list,
+                publish,
+                withhold,
+                capacity,
+                session_tag,
+            } => {
+                let (state, result, accepted, published) = reference::submit_and_match_tier(
+                    self.state()?,
+                    &self.list(list)?,
+                    *publish,
+                    *withhold,
+                    *capacity,
+                    tag(session_tag),
+                );
+                println!("    accepted: {accepted}");
+                print_matches("bob", &result);
+                println!("    published count: {published}");
+                self.state = Some(state);
+            }
+            Step::SubmitAndScore {
+                list,
+                hide_counts,
+                session_tag,
+            } => {
+                let (state, score, accepted) =
+                    reference::submit_and_score(self.state()?, &self.list(list)?, *hide_counts, tag(session_tag));
+                println!("    accepted: {accepted}");
+                println!("    overlap: {} bp, jaccard: {} bp", score.overlap_bp, score.jaccard_bp);
+                self.state = Some(state);
+            }
+            Step::SubmitContactsBob { list, session_tag } => {
+                let (state, confirmation) =
+                    reference::submit_contacts_bob(self.state()?, &self.list(list)?, tag(session_tag));
+                print_confirmation(&confirmation);
+                self.state = Some(state);
+            }
+            Step::SubmitConnector { list, session_tag } => {
+                let (state, confirmation) =
+                    reference::submit_connector(self.state()?, &self.list(list)?, tag(session_tag));
+                print_confirmation(&confirmation);
+                self.state = Some(state);
+            }
+            Step::SubmitAndCheckFollow {
+                list,
+                alice_id,
+                bob_id,
+                session_tag,
+            } => {
+                let (state, mutual, accepted) = reference::submit_and_check_follow(
+                    self.state()?,
+                    &self.list(list)?,
+                    *alice_id,
+                    *bob_id,
+                    tag(session_tag),
+                );
+                println!("    accepted: {accepted}");
+                println!("    mutual: {}", mutual == 1);
+                self.state = Some(state);
+            }
+            Step::MatchSetChunk {
+                list,
+                last,
+                session_tag,
+            } => {
+                let (state, result, accepted) =
+                    reference::match_set_chunk(self.state()?, &self.list(list)?, *last as u8, tag(session_tag));
+                println!("    accepted: {accepted}");
+                print_matches("bob (chunk)", &result);
+                self.state = Some(state);
+            }
+            Step::PushDelta {
+                party,
+                added,
+                removed,
+                namespace,
+                session_tag,
+            } => {
+                ensure!(
+                    added.len() <= MAX_DELTA && removed.len() <= MAX_DELTA,
+                    "a delta adds and removes at most {MAX_DELTA} hashes each"
+                );
+                let mut delta = ContactDelta {
+                    namespace: namespace.unwrap_or(self.namespace),
+                    ..ContactDelta::default()
+                };
+                delta.added[..added.len()].copy_from_slice(added);
+                delta.removed[..removed.len()].copy_from_slice(removed);
+                let party_id = match party {
+                    Party::Alice => 1,
+                    Party::Bob => 2,
+                };
+                let (state, result) = reference::push_delta(self.state()?, &delta, party_id, tag(session_tag));
+                print_matches("new", &result);
+                self.state = Some(state);
+            }
+            Step::RevealAliceMatches { page, session_tag } => {
+                print_matches(
+                    "alice",
+                    &reference::reveal_alice_matches(self.state()?, *page, tag(session_tag)),
+                );
+            }
+            Step::RevealBobMatches { page, session_tag } => {
+                print_matches(
+                    "bob",
+                    &reference::reveal_bob_matches(self.state()?, *page, tag(session_tag)),
+                );
+            }
+            Step::RevealBoth { page, session_tag } => {
+                let (alice, bob) = reference::reveal_both(self.state()?, *page, tag(session_tag));
+                print_matches("alice", &alice);
+                print_matches("bob", &bob);
+            }
+            Step::RevealAliceScore {
+                hide_counts,
+                session_tag,
+            } => {
+                let score = reference::reveal_alice_score(self.state()?, *hide_counts, tag(session_tag));
+                println!("    overlap: {} bp, jaccard: {} bp", score.overlap_bp, score.jaccard_bp);
+            }
+            Step::RevealSecondDegree { session_tag } => {
+                let count = reference::reveal_second_degree(self.state()?, tag(session_tag));
+                println!("    second-degree count: {count}");
+            }
+            Step::PurgeSessionState => {
+                self.state = Some(reference::purge_session_state(self.state()?));
+            }
+            Step::MigrateSessionState => {
+                self.state = Some(reference::migrate_session_state(self.state()?));
+            }
+            Step::InstantMatch { alice, bob } => {
+                let (alice_result, bob_result, accepted) =
+                    reference::instant_match(&self.list(alice)?, &self.list(bob)?);
+                println!("    accepted: {accepted}");
+                print_matches("alice", &alice_result);
+                print_matches("bob", &bob_result);
+            }
+        }
+        Ok(())
+    }
+
+    fn print_state(&self) {
+        let Some(state) = &self.state else {
+            println!("    state: none");
+            return;
+        };
+        println!(
+            "    state: namespace {}, tag {:#x}, alice submitted {}, bob submitted {}, matched {}",
+            state.namespace, state.session_tag, state.alice_submitted, state.bob_submitted, state.is_matched
+        );
+        println!("    alice ({}): {}", state.alice_count, hashes(&state.alice_hashes));
+        println!("    bob ({}): {}", state.bob_count, hashes(&state.bob_hashes));
+        println!("    result count: {}", state.result_count);
+        println!("    result alice: {}", hashes(&state.result_alice));
+        println!("    result bob: {}", hashes(&state.result_bob));
+    }
+}
+
+fn print_confirmation(confirmation: &SubmitConfirmation) {
+    println!(
+        "    accepted: {}, party: {}",
+        confirmation.accepted == 1,
+        confirmation.party
+    );
+}
+
+/// A result page as the recipient reads it: the count and its list slots.
+fn print_matches(recipient: &str, result: &MatchResult) {
+    println!(
+        "    {recipient} matches: {} at slots {:?}",
+        result.match_count,
+        result.slots()
+    );
+}
+
+/// The occupied slots of `hashes` as `slot:hash`.
+fn hashes(hashes: &[u128]) -> String {
+    let occupied: Vec<String> = hashes
+        .iter()
+        .enumerate()
+        .filter(|(_, h)| **h != 0)
+        .map(|(slot, h)| format!("{slot}:{h:#x}"))
+        .collect();
+    if occupied.is_empty() {
+        "-".into()
+    } else {
+        occupied.join(" ")
+    }
+}